@@ -1,5 +1,6 @@
 use core::arch::asm;
 
+use alloc::{string::String, vec::Vec};
 use x86::cpuid::{cpuid, CpuIdResult};
 
 /// @brief 获取当前cpu的apic id
@@ -23,3 +24,146 @@ pub fn cpu_reset() -> ! {
     unsafe { x86::io::outb(0x64, 0xfe) };
     loop {}
 }
+
+/// cpuid 0x1号功能中，edx寄存器里各个特性位的名称（与Linux的/proc/cpuinfo相同）
+const CPUID_1_EDX_FLAGS: [(u32, &str); 23] = [
+    (0, "fpu"),
+    (1, "vme"),
+    (2, "de"),
+    (3, "pse"),
+    (4, "tsc"),
+    (5, "msr"),
+    (6, "pae"),
+    (7, "mce"),
+    (8, "cx8"),
+    (9, "apic"),
+    (11, "sep"),
+    (12, "mtrr"),
+    (13, "pge"),
+    (14, "mca"),
+    (15, "cmov"),
+    (16, "pat"),
+    (17, "pse36"),
+    (19, "clflush"),
+    (23, "mmx"),
+    (24, "fxsr"),
+    (25, "sse"),
+    (26, "sse2"),
+    (28, "ht"),
+];
+
+/// cpuid 0x1号功能中，ecx寄存器里各个特性位的名称
+const CPUID_1_ECX_FLAGS: [(u32, &str); 17] = [
+    (0, "pni"),
+    (1, "pclmulqdq"),
+    (3, "monitor"),
+    (9, "ssse3"),
+    (12, "fma"),
+    (13, "cx16"),
+    (19, "sse4_1"),
+    (20, "sse4_2"),
+    (21, "x2apic"),
+    (22, "movbe"),
+    (23, "popcnt"),
+    (25, "aes"),
+    (26, "xsave"),
+    (28, "avx"),
+    (29, "f16c"),
+    (30, "rdrand"),
+    (31, "hypervisor"),
+];
+
+/// cpuid 0x80000001号功能中，edx寄存器里各个特性位的名称
+const CPUID_EXT_1_EDX_FLAGS: [(u32, &str); 5] = [
+    (11, "syscall"),
+    (20, "nx"),
+    (26, "pdpe1gb"),
+    (27, "rdtscp"),
+    (29, "lm"),
+];
+
+/// @brief 通过cpuid指令获取到的处理器信息
+#[derive(Debug, Clone)]
+pub struct CpuInfo {
+    /// 处理器制造商
+    pub vendor_id: String,
+    /// 处理器名称
+    pub model_name: String,
+    pub family: u32,
+    pub model: u32,
+    pub stepping: u32,
+    /// 处理器支持的特性
+    pub flags: Vec<&'static str>,
+    /// 物理地址的宽度
+    pub phys_addr_bits: u32,
+    /// 线性地址的宽度
+    pub virt_addr_bits: u32,
+}
+
+impl CpuInfo {
+    /// @brief 获取当前处理器的信息
+    pub fn current() -> Self {
+        let leaf0: CpuIdResult = cpuid!(0x0);
+        let mut vendor: Vec<u8> = Vec::with_capacity(12);
+        for reg in [leaf0.ebx, leaf0.edx, leaf0.ecx] {
+            vendor.extend_from_slice(&reg.to_le_bytes());
+        }
+
+        let leaf1: CpuIdResult = cpuid!(0x1);
+        let base_family: u32 = (leaf1.eax >> 8) & 0xf;
+        let mut family: u32 = base_family;
+        let mut model: u32 = (leaf1.eax >> 4) & 0xf;
+        if base_family == 0xf {
+            family += (leaf1.eax >> 20) & 0xff;
+        }
+        if base_family == 0x6 || base_family == 0xf {
+            model += ((leaf1.eax >> 16) & 0xf) << 4;
+        }
+
+        let mut flags: Vec<&'static str> = Vec::new();
+        let mut add_flags = |reg: u32, table: &[(u32, &'static str)]| {
+            for (bit, name) in table {
+                if reg & (1 << *bit) != 0 {
+                    flags.push(*name);
+                }
+            }
+        };
+        add_flags(leaf1.edx, &CPUID_1_EDX_FLAGS);
+        add_flags(leaf1.ecx, &CPUID_1_ECX_FLAGS);
+
+        let max_extended: u32 = cpuid!(0x8000_0000).eax;
+        if max_extended >= 0x8000_0001 {
+            add_flags(cpuid!(0x8000_0001).edx, &CPUID_EXT_1_EDX_FLAGS);
+        }
+
+        // 处理器名称（不支持时为空）
+        let mut brand: Vec<u8> = Vec::new();
+        if max_extended >= 0x8000_0004 {
+            for leaf in 0x8000_0002..=0x8000_0004u32 {
+                let r: CpuIdResult = cpuid!(leaf);
+                for reg in [r.eax, r.ebx, r.ecx, r.edx] {
+                    brand.extend_from_slice(&reg.to_le_bytes());
+                }
+            }
+        }
+        let brand: Vec<u8> = brand.into_iter().take_while(|c| *c != 0).collect();
+
+        let (phys_addr_bits, virt_addr_bits) = if max_extended >= 0x8000_0008 {
+            let r: CpuIdResult = cpuid!(0x8000_0008);
+            (r.eax & 0xff, (r.eax >> 8) & 0xff)
+        } else {
+            (36, 48)
+        };
+
+        return CpuInfo {
+            vendor_id: String::from_utf8_lossy(&vendor).into_owned(),
+            model_name: String::from(String::from_utf8_lossy(&brand).trim()),
+            family,
+            model,
+            stepping: leaf1.eax & 0xf,
+            flags,
+            phys_addr_bits,
+            virt_addr_bits,
+        };
+    }
+}
@@ -14,8 +14,9 @@ use crate::libs::lib_ui::screen_manager::scm_disable_put_to_window;
 use crate::libs::printk::PrintkWriter;
 use crate::libs::spinlock::SpinLock;
 
-use crate::mm::allocator::page_frame::{FrameAllocator, PageFrameCount};
+use crate::mm::allocator::page_frame::{FrameAllocator, PageFrameCount, PageFrameUsage};
 use crate::mm::mmio_buddy::mmio_init;
+use crate::mm::page_cache::page_cache_reclaim;
 use crate::{
     arch::MMArch,
     mm::allocator::{buddy::BuddyAllocator, bump::BumpAllocator},
@@ -517,11 +518,22 @@ impl FrameAllocator for LockedFrameAllocator {
         &mut self,
         count: crate::mm::allocator::page_frame::PageFrameCount,
     ) -> Option<(PhysAddr, PageFrameCount)> {
-        if let Some(ref mut allocator) = *INNER_ALLOCATOR.lock_irqsave() {
-            return allocator.allocate(count);
-        } else {
-            return None;
+        let try_allocate = || {
+            if let Some(ref mut allocator) = *INNER_ALLOCATOR.lock_irqsave() {
+                return allocator.allocate(count);
+            } else {
+                return None;
+            }
+        };
+        if let Some(r) = try_allocate() {
+            return Some(r);
         }
+
+        // 内存紧张，回收页缓存之后再试一次（回收时不能持有分配器的锁，因为回收会释放页）
+        if page_cache_reclaim(count) {
+            return try_allocate();
+        }
+        return None;
     }
 
     unsafe fn free(
@@ -535,8 +547,12 @@ impl FrameAllocator for LockedFrameAllocator {
         }
     }
 
-    unsafe fn usage(&self) -> crate::mm::allocator::page_frame::PageFrameUsage {
-        todo!()
+    unsafe fn usage(&self) -> PageFrameUsage {
+        if let Some(ref allocator) = *INNER_ALLOCATOR.lock_irqsave() {
+            return allocator.usage();
+        } else {
+            return PageFrameUsage::new(PageFrameCount::new(0), PageFrameCount::new(0));
+        }
     }
 }
 
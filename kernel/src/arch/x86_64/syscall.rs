@@ -7,11 +7,15 @@ use crate::{
     exception::InterruptArch,
     filesystem::vfs::MAX_PATHLEN,
     include::bindings::bindings::{
-        pt_regs, set_system_trap_gate, CLONE_FS, CLONE_SIGNAL, CLONE_VM, USER_CS, USER_DS,
+        process_do_exit, pt_regs, set_system_trap_gate, CLONE_FS, CLONE_SIGNAL, CLONE_VM, USER_CS,
+        USER_DS,
     },
-    ipc::signal::sys_rt_sigreturn,
+    ipc::{signal::sys_rt_sigreturn, signal_types::SignalNumber},
+    kerror,
     mm::{ucontext::AddressSpace, verify_area, VirtAddr},
-    process::exec::{load_binary_file, ExecParam, ExecParamFlags},
+    process::exec::{
+        check_exec_permission, load_binary_file, lookup_exec_file, ExecParam, ExecParamFlags,
+    },
     syscall::{
         user_access::{check_and_clone_cstr, check_and_clone_cstr_array},
         Syscall, SystemError, SYS_EXECVE, SYS_FORK, SYS_RT_SIGRETURN, SYS_VFORK,
@@ -146,15 +150,10 @@ pub unsafe extern "C" fn rs_do_execve(
     }
     let (path, argv, envp) = r.unwrap();
 
+    // tmp_rs_execve只会在销毁原有的地址空间之前返回错误，因此可以把错误码返回给原来的程序
     return tmp_rs_execve(path, argv, envp, regs)
         .map(|_| 0)
-        .unwrap_or_else(|e| {
-            panic!(
-                "Failed to execve, pid: {} error: {:?}",
-                current_pcb().pid,
-                e
-            )
-        });
+        .unwrap_or_else(|e| e.to_posix_errno() as usize);
 }
 
 /// 执行第一个用户进程的函数（只应该被调用一次）
@@ -186,6 +185,10 @@ fn tmp_rs_execve(
     //     argv,
     //     envp
     // );
+    // 在销毁原有的地址空间之前查找文件并检查权限，使得execve失败时，进程还能够返回到原来的程序中
+    let inode = lookup_exec_file(&path)?;
+    let cred = check_exec_permission(&inode)?;
+
     // 关中断，防止在设置地址空间的时候，发生中断，然后进调度器，出现错误。
     let irq_guard = unsafe { CurrentIrqArch::save_and_disable_irq() };
     // 暂存原本的用户地址空间的引用(因为如果在切换页表之前释放了它，可能会造成内存use after free)
@@ -212,9 +215,22 @@ fn tmp_rs_execve(
     drop(irq_guard);
     // kdebug!("to load binary file");
     let mut param = ExecParam::new(path.as_str(), address_space.clone(), ExecParamFlags::EXEC);
-    // 加载可执行文件
-    let load_result = load_binary_file(&mut param)
-        .unwrap_or_else(|e| panic!("Failed to load binary file: {:?}, path: {:?}", e, path));
+    // 加载可执行文件。此时原来的地址空间已经被销毁，进程无法再返回到原来的程序中，因此加载失败时只能退出
+    let load_result = match load_binary_file(&mut param, inode) {
+        Ok(r) => r,
+        Err(e) => {
+            kerror!(
+                "tmp_rs_execve: failed to load {}: {:?}, pid={}",
+                path,
+                e,
+                current_pcb().pid
+            );
+            unsafe { process_do_exit(SignalNumber::SIGSEGV as u64) };
+            unreachable!();
+        }
+    };
+    // 新程序以S_ISUID、S_ISGID位所指定的身份运行
+    current_pcb().set_cred(cred);
     // kdebug!("load binary file done");
 
     param.init_info_mut().args = argv;
@@ -236,6 +252,15 @@ fn tmp_rs_execve(
 
     // kdebug!("write proc_init_info to user stack done");
 
+    // 记录进程的参数和环境变量，以便通过/proc/<pid>/cmdline和/proc/<pid>/environ查看
+    let mut address_space_guard = address_space.write();
+    address_space_guard.args = param.init_info().args.clone();
+    address_space_guard.envs = param.init_info().envs.clone();
+    drop(address_space_guard);
+
+    // 把可执行文件的文件名设置为进程的名字
+    current_pcb().set_name(path.rsplit('/').next().unwrap_or(&path));
+
     // （兼容旧版libc）把argv的指针写到寄存器内
     // TODO: 改写旧版libc，不再需要这个兼容
     regs.rdi = param.init_info().args.len() as u64;
@@ -0,0 +1,165 @@
+use alloc::{
+    string::{String, ToString},
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+
+use crate::{
+    filesystem::{
+        sysfs::file::sysfs_create_file,
+        vfs::{seq_file::SeqOperations, IndexNode},
+    },
+    syscall::SystemError,
+};
+
+use super::{
+    uevent::{device_uevent, KObjectAction},
+    Device, DeviceNumber, DeviceState, DeviceType,
+};
+
+/// @brief: 设备在sysfs中的属性文件的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeviceAttrKind {
+    /// 设备号（major:minor）
+    Dev,
+    /// 设备的uevent环境变量（KEY=VALUE）。写入add、remove或change时，会重新广播对应的事件
+    Uevent,
+    /// 设备当前状态
+    State,
+    /// 设备占用的资源，每项资源一行
+    Resource,
+}
+
+/// @brief: 设备的属性文件
+///
+/// 属性文件只持有设备的弱引用，避免设备与其sysfs目录之间形成循环引用
+#[derive(Debug)]
+struct DeviceAttr {
+    device: Weak<dyn Device>,
+    kind: DeviceAttrKind,
+    /// 设备在sysfs中的路径（相对于/sys）
+    devpath: String,
+    /// 设备所属的子系统（总线或类）
+    subsystem: Option<String>,
+}
+
+impl SeqOperations for DeviceAttr {
+    fn show(&self, index: usize, buf: &mut String) -> Result<bool, SystemError> {
+        let device: Arc<dyn Device> = self.device.upgrade().ok_or(SystemError::ENODEV)?;
+
+        // 资源文件按照资源逐条输出，其余的属性文件只有一条记录
+        if self.kind == DeviceAttrKind::Resource {
+            let entries: Vec<String> = device.resource().map_or(Vec::new(), |r| r.entries());
+            match entries.get(index) {
+                Some(entry) => {
+                    buf.push_str(entry);
+                    return Ok(true);
+                }
+                None => return Ok(false),
+            }
+        }
+
+        if index != 0 {
+            return Ok(false);
+        }
+        match self.kind {
+            DeviceAttrKind::Dev => {
+                let devnum = device.id_table().device_number();
+                buf.push_str(&format!("{}:{}\n", devnum.major(), devnum.minor()));
+            }
+            DeviceAttrKind::Uevent => {
+                for env in device_uevent_env(device.as_ref(), self.subsystem.as_deref()) {
+                    buf.push_str(&env);
+                    buf.push('\n');
+                }
+            }
+            DeviceAttrKind::State => {
+                let state: &str = match device.state() {
+                    DeviceState::NotInitialized => "not initialized",
+                    DeviceState::Initialized => "initialized",
+                    DeviceState::UnDefined => "undefined",
+                };
+                buf.push_str(state);
+                buf.push('\n');
+            }
+            DeviceAttrKind::Resource => unreachable!(),
+        }
+        return Ok(true);
+    }
+
+    fn store(&self, buf: &[u8]) -> Result<usize, SystemError> {
+        if self.kind != DeviceAttrKind::Uevent {
+            return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+        }
+        let device: Arc<dyn Device> = self.device.upgrade().ok_or(SystemError::ENODEV)?;
+        let action: &str = core::str::from_utf8(buf).map_err(|_| SystemError::EINVAL)?;
+        let action = KObjectAction::try_from(action.trim())?;
+        device_uevent(
+            device.as_ref(),
+            action,
+            &self.devpath,
+            self.subsystem.as_deref(),
+        );
+        return Ok(buf.len());
+    }
+}
+
+/// @brief: 获取设备的uevent环境变量
+/// @parameter device: 设备
+///            subsystem: 设备所属的子系统（总线或类）
+/// @return: KEY=VALUE形式的环境变量列表
+pub fn device_uevent_env(device: &dyn Device, subsystem: Option<&str>) -> Vec<String> {
+    let mut env: Vec<String> = Vec::new();
+    let id_table = device.id_table();
+    let devnum = id_table.device_number();
+    // 只有拥有设备号的设备，才会在devfs中有对应的设备节点
+    if devnum != DeviceNumber::default() {
+        env.push(format!("MAJOR={}", devnum.major()));
+        env.push(format!("MINOR={}", devnum.minor()));
+        let dir: &str = match device.dev_type() {
+            DeviceType::Block => "block",
+            _ => "char",
+        };
+        env.push(format!("DEVNAME={}/{}", dir, id_table.name()));
+    }
+    let devtype: String = format!("{:?}", device.dev_type()).to_lowercase();
+    env.push(format!("DEVTYPE={}", devtype));
+    if let Some(subsystem) = subsystem {
+        env.push(format!("SUBSYSTEM={}", subsystem));
+    }
+    return env;
+}
+
+/// @brief: 在设备的sysfs目录下创建dev、uevent、state和resource属性文件
+/// @parameter device: 设备
+///            dir: 设备在sysfs中的目录
+///            devpath: 设备在sysfs中的路径（相对于/sys）
+///            subsystem: 设备所属的子系统（总线或类）
+/// @return: 操作成功，返回()，操作失败，返回错误码
+pub fn device_create_attrs(
+    device: Arc<dyn Device>,
+    dir: &Arc<dyn IndexNode>,
+    devpath: &str,
+    subsystem: Option<&str>,
+) -> Result<(), SystemError> {
+    let mut attrs: Vec<(&str, DeviceAttrKind, u32)> = Vec::new();
+    if device.id_table().device_number() != DeviceNumber::default() {
+        attrs.push(("dev", DeviceAttrKind::Dev, 0o444));
+    }
+    attrs.push(("uevent", DeviceAttrKind::Uevent, 0o644));
+    attrs.push(("state", DeviceAttrKind::State, 0o444));
+    if device.resource().is_some() {
+        attrs.push(("resource", DeviceAttrKind::Resource, 0o444));
+    }
+
+    for (name, kind, mode) in attrs {
+        let attr = DeviceAttr {
+            device: Arc::downgrade(&device),
+            kind,
+            devpath: devpath.to_string(),
+            subsystem: subsystem.map(|s| s.to_string()),
+        };
+        sysfs_create_file(dir, name, mode, Arc::new(attr))?;
+    }
+    return Ok(());
+}
@@ -2,6 +2,7 @@ use alloc::{
     collections::BTreeMap,
     string::{String, ToString},
     sync::Arc,
+    vec::Vec,
 };
 
 use crate::{
@@ -16,13 +17,20 @@ use crate::{
     libs::spinlock::SpinLock,
     syscall::SystemError,
 };
-use core::{any::Any, fmt::Debug};
+use core::{any::Any, fmt::Debug, ops::Range};
+
+use self::{
+    attrs::device_create_attrs,
+    uevent::{device_uevent, KObjectAction},
+};
 
 use super::platform::CompatibleTable;
 
+pub mod attrs;
 pub mod bus;
 pub mod driver;
 pub mod init;
+pub mod uevent;
 
 lazy_static! {
     pub static ref DEVICE_MANAGER: Arc<LockedDeviceManager> = Arc::new(LockedDeviceManager::new());
@@ -64,6 +72,20 @@ pub trait Device: KObject {
     /// @parameter id_table: 设备标识符，用于唯一标识该设备
     /// @return: 设备实例
     fn sys_info(&self) -> Option<Arc<dyn IndexNode>>;
+
+    /// @brief: 获取设备当前状态
+    /// @parameter: None
+    /// @return: 设备状态
+    fn state(&self) -> DeviceState {
+        return DeviceState::UnDefined;
+    }
+
+    /// @brief: 获取设备占用的资源
+    /// @parameter: None
+    /// @return: 设备资源，没有占用资源的设备返回None
+    fn resource(&self) -> Option<DeviceResource> {
+        return None;
+    }
 }
 
 // 暂定是不可修改的，在初始化的时候就要确定。以后可能会包括例如硬件中断包含的信息
@@ -116,11 +138,40 @@ impl DevicePrivateData {
 #[derive(Debug, Clone)]
 pub struct DeviceResource {
     //可能会用来保存例如 IRQ PWM 内存地址等需要申请的资源，将来由资源管理器+Framework框架进行管理。
+    /// 设备使用的中断号
+    pub irqs: Vec<u32>,
+    /// 设备使用的IO端口区间（左闭右开）
+    pub io_ports: Vec<Range<u16>>,
+    /// 设备使用的MMIO区间（左闭右开）
+    pub mmio: Vec<Range<usize>>,
 }
 
 impl Default for DeviceResource {
     fn default() -> Self {
-        return Self {};
+        return Self {
+            irqs: Vec::new(),
+            io_ports: Vec::new(),
+            mmio: Vec::new(),
+        };
+    }
+}
+
+impl DeviceResource {
+    /// @brief: 以“类型 范围”的形式列出设备的各项资源，每项资源一行
+    /// @parameter: None
+    /// @return: 资源列表
+    pub fn entries(&self) -> Vec<String> {
+        let mut entries: Vec<String> = Vec::new();
+        for irq in self.irqs.iter() {
+            entries.push(format!("irq {}\n", irq));
+        }
+        for io in self.io_ports.iter() {
+            entries.push(format!("io {:#06x}-{:#06x}\n", io.start, io.end - 1));
+        }
+        for mem in self.mmio.iter() {
+            entries.push(format!("mem {:#018x}-{:#018x}\n", mem.start, mem.end - 1));
+        }
+        return entries;
     }
 }
 
@@ -369,7 +420,11 @@ pub fn device_register<T: Device>(device: Arc<T>) -> Result<(), DeviceError> {
     DEVICE_MANAGER.add_device(device.id_table(), device.clone());
     match sys_device_register(&device.id_table().name()) {
         Ok(sys_info) => {
+            let devpath = format!("/devices/{}", device.id_table().name());
+            device_create_attrs(device.clone(), &sys_info, &devpath, None)
+                .map_err(|_| DeviceError::RegisterError)?;
             device.set_sys_info(Some(sys_info));
+            device_uevent(device.as_ref(), KObjectAction::Add, &devpath, None);
             return Ok(());
         }
         Err(_) => Err(DeviceError::RegisterError),
@@ -384,6 +439,8 @@ pub fn device_unregister<T: Device>(device: Arc<T>) -> Result<(), DeviceError> {
     match sys_device_unregister(&device.id_table().name()) {
         Ok(_) => {
             device.set_sys_info(None);
+            let devpath = format!("/devices/{}", device.id_table().name());
+            device_uevent(device.as_ref(), KObjectAction::Remove, &devpath, None);
             return Ok(());
         }
         Err(_) => Err(DeviceError::RegisterError),
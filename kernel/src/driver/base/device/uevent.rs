@@ -0,0 +1,98 @@
+//! 设备的热插拔事件（uevent）
+//!
+//! 设备被添加、移除或者状态发生变化时，内核通过NETLINK_KOBJECT_UEVENT协议的netlink socket，
+//! 向订阅了组播组1的用户程序广播事件。事件的格式与Linux保持一致：
+//!
+//! ```text
+//! add@/devices/xxx\0ACTION=add\0DEVPATH=/devices/xxx\0KEY=VALUE\0...SEQNUM=1\0
+//! ```
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    net::socket::{netlink_broadcast, NETLINK_KOBJECT_UEVENT},
+    syscall::SystemError,
+};
+
+use super::{attrs::device_uevent_env, Device};
+
+/// uevent广播的netlink组播组
+pub const UEVENT_GROUP: u32 = 1;
+
+/// 事件的序列号，每发送一个事件就加1
+static UEVENT_SEQNUM: AtomicU64 = AtomicU64::new(0);
+
+/// @brief: 热插拔事件的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KObjectAction {
+    /// 设备被添加
+    Add,
+    /// 设备被移除
+    Remove,
+    /// 设备的状态发生变化
+    Change,
+}
+
+impl KObjectAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KObjectAction::Add => "add",
+            KObjectAction::Remove => "remove",
+            KObjectAction::Change => "change",
+        }
+    }
+}
+
+impl TryFrom<&str> for KObjectAction {
+    type Error = SystemError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "add" => Ok(KObjectAction::Add),
+            "remove" => Ok(KObjectAction::Remove),
+            "change" => Ok(KObjectAction::Change),
+            _ => Err(SystemError::EINVAL),
+        }
+    }
+}
+
+/// @brief: 向用户空间广播一个热插拔事件
+/// @parameter action: 事件的种类
+///            devpath: 设备的路径（相对于/sys）
+///            env: 附加的KEY=VALUE形式的环境变量
+pub fn kobject_uevent_env(action: KObjectAction, devpath: &str, env: &[String]) {
+    let seqnum: u64 = UEVENT_SEQNUM.fetch_add(1, Ordering::SeqCst) + 1;
+
+    let mut fields: Vec<String> = Vec::new();
+    fields.push(format!("{}@{}", action.as_str(), devpath));
+    fields.push(format!("ACTION={}", action.as_str()));
+    fields.push(format!("DEVPATH={}", devpath));
+    fields.extend(env.iter().cloned());
+    fields.push(format!("SEQNUM={}", seqnum));
+
+    // 每个字段都以'\0'结尾
+    let mut msg: Vec<u8> = Vec::new();
+    for field in fields.iter() {
+        msg.extend_from_slice(field.as_bytes());
+        msg.push(0);
+    }
+
+    netlink_broadcast(NETLINK_KOBJECT_UEVENT, UEVENT_GROUP, &msg);
+}
+
+/// @brief: 广播设备的热插拔事件，事件中携带设备的uevent环境变量
+/// @parameter device: 设备
+///            action: 事件的种类
+///            devpath: 设备在sysfs中的路径（相对于/sys）
+///            subsystem: 设备所属的子系统（总线或类）
+pub fn device_uevent(
+    device: &dyn Device,
+    action: KObjectAction,
+    devpath: &str,
+    subsystem: Option<&str>,
+) {
+    let env: Vec<String> = device_uevent_env(device, subsystem);
+    kobject_uevent_env(action, devpath, &env);
+}
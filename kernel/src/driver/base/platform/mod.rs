@@ -1,8 +1,8 @@
 use super::device::{
     bus::{bus_driver_register, bus_register, Bus, BusDriver, BusState},
     driver::DriverError,
-    Device, DeviceError, DeviceNumber, DevicePrivateData, DeviceResource, DeviceType, IdTable,
-    KObject,
+    Device, DeviceError, DeviceNumber, DevicePrivateData, DeviceResource, DeviceState, DeviceType,
+    IdTable, KObject,
 };
 use crate::{
     driver::Driver, filesystem::vfs::IndexNode, libs::spinlock::SpinLock, syscall::SystemError,
@@ -340,6 +340,11 @@ impl Device for LockedPlatform {
         return self.0.lock().sys_info.clone();
     }
 
+    #[inline]
+    fn state(&self) -> DeviceState {
+        return self.get_state().into();
+    }
+
     fn as_any_ref(&self) -> &dyn core::any::Any {
         self
     }
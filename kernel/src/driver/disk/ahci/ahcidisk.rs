@@ -4,7 +4,8 @@ use crate::driver::base::block::disk_info::Partition;
 use crate::driver::base::block::SeekFrom;
 use crate::driver::base::device::{Device, DeviceType, KObject};
 use crate::driver::disk::ahci::HBA_PxIS_TFES;
-use crate::filesystem::mbr::MbrDiskPartionTable;
+use crate::filesystem::gpt::GptPartitionTable;
+use crate::filesystem::mbr::{MbrDiskPartionTable, MBR_PART_TYPE_GPT_PROTECTIVE};
 use crate::include::bindings::bindings::verify_area;
 
 use crate::kdebug;
@@ -26,7 +27,7 @@ use core::fmt::Debug;
 use core::sync::atomic::compiler_fence;
 use core::{mem::size_of, ptr::write_bytes};
 
-/// @brief: 只支持MBR分区格式的磁盘结构体
+/// @brief: 支持MBR和GPT分区格式的磁盘结构体
 pub struct AhciDisk {
     pub name: String,
     pub flags: u16,                      // 磁盘的状态flags
@@ -384,9 +385,42 @@ impl LockedAhciDisk {
         let table: MbrDiskPartionTable = result.read_mbr_table()?;
         let weak_this: Weak<LockedAhciDisk> = Arc::downgrade(&result); // 获取this的弱指针
 
+        // 保护性MBR表示磁盘使用GPT分区表
+        if table.is_protective() {
+            match result.read_gpt_table(&table) {
+                Ok(gpt) => {
+                    for (i, entry) in gpt.entries.iter().enumerate() {
+                        if entry.is_unused() {
+                            continue;
+                        }
+                        part_s.push(Partition::new(
+                            entry.starting_lba,
+                            entry.starting_lba,
+                            entry.sectors_num(),
+                            weak_this.clone(),
+                            i as u16,
+                        ));
+                    }
+                    result.0.lock().partitions = part_s;
+                    result.0.lock().self_ref = weak_this;
+                    return Ok(result);
+                }
+                Err(e) => {
+                    // 主、备份GPT都已损坏，退而使用MBR中的其他分区（如果是混合MBR的话）
+                    kerror!(
+                        "Failed to read GPT of disk {}: {:?}, falling back to MBR",
+                        result.0.lock().name,
+                        e
+                    );
+                }
+            }
+        }
+
         // 求出有多少可用分区
         for i in 0..4 {
-            if table.dpte[i].part_type != 0 {
+            if table.dpte[i].part_type != 0
+                && table.dpte[i].part_type != MBR_PART_TYPE_GPT_PROTECTIVE
+            {
                 part_s.push(Partition::new(
                     table.dpte[i].starting_sector() as u64,
                     table.dpte[i].starting_lba as u64,
@@ -435,6 +469,16 @@ impl LockedAhciDisk {
 
         return Ok(table);
     }
+
+    /// @brief: 从磁盘中读取 GPT 分区表
+    ///
+    /// @param mbr 磁盘的第0个扇区中的保护性MBR（主GPT头损坏时，用于定位备份GPT头）
+    pub fn read_gpt_table(
+        &self,
+        mbr: &MbrDiskPartionTable,
+    ) -> Result<GptPartitionTable, SystemError> {
+        return GptPartitionTable::read(self, mbr);
+    }
 }
 
 impl KObject for LockedAhciDisk {}
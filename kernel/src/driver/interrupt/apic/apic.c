@@ -15,6 +15,7 @@
 #pragma GCC optimize("O0")
 // 导出定义在irq.c中的中段门表
 extern void (*interrupt_table[24])(void);
+extern void rs_irq_stat_inc(uint64_t vector);
 
 static bool flag_support_apic = false;
 static bool flag_support_x2apic = false;
@@ -409,6 +410,8 @@ int apic_init()
  */
 void do_IRQ(struct pt_regs *rsp, ul number)
 {
+    // 统计各个cpu上每个中断向量发生的次数（用于/proc/interrupts）
+    rs_irq_stat_inc(number);
 
     if (number < 0x80 && number >= 32) // 以0x80为界限，低于0x80的是外部中断控制器，高于0x80的是Local APIC
     {
@@ -1,4 +1,7 @@
-use core::intrinsics::unlikely;
+use core::{
+    intrinsics::unlikely,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use alloc::string::String;
 
@@ -55,6 +58,8 @@ struct TtyCore {
     /// 输出的mpsc队列输入输出端
     output_rx: mpsc::Receiver<u8>,
     output_tx: mpsc::Sender<u8>,
+    /// stdin缓冲区中尚未被读取的字节数
+    stdin_len: AtomicUsize,
 
     /// tty核心的状态
     state: RwLock<TtyCoreState>,
@@ -90,6 +95,7 @@ impl TtyCore {
             stdin_tx,
             output_rx,
             output_tx,
+            stdin_len: AtomicUsize::new(0),
             state,
         };
     }
@@ -173,6 +179,7 @@ impl TtyCore {
                 let x = *val.unwrap();
                 buf[cnt] = x;
                 cnt += 1;
+                self.stdin_len.fetch_sub(1, Ordering::SeqCst);
 
                 if unlikely(self.stdin_should_return(x)) {
                     return Ok(cnt);
@@ -182,6 +189,12 @@ impl TtyCore {
         return Ok(cnt);
     }
 
+    /// @brief 判断stdin缓冲区中是否有尚未被读取的数据
+    #[inline]
+    pub fn stdin_readable(&self) -> bool {
+        return self.stdin_len.load(Ordering::SeqCst) > 0;
+    }
+
     fn stdin_should_return(&self, c: u8) -> bool {
         // 如果是换行符或者是ctrl+d，那么就应该返回
         return c == b'\n' || c == 4;
@@ -213,6 +226,8 @@ impl TtyCore {
                     _ => return Err(TtyError::Unknown(format!("{e:?}"))),
                 }
             } else {
+                // 先增加计数，再提交数据，保证读者看到数据时，计数不会小于0
+                self.stdin_len.fetch_add(1, Ordering::SeqCst);
                 *r.unwrap() = buf[cnt];
                 cnt += 1;
             }
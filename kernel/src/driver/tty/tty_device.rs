@@ -7,7 +7,11 @@ use alloc::{
 use crate::{
     filesystem::{
         devfs::{devfs_register, DevFS, DeviceINode},
-        vfs::{file::FileMode, FilePrivateData, FileType, IndexNode, Metadata, ROOT_INODE},
+        vfs::{
+            file::FileMode,
+            poll::{PollWaitQueue, PollWaiter},
+            FilePrivateData, FileType, IndexNode, Metadata, PollStatus, ROOT_INODE,
+        },
     },
     kerror,
     libs::{
@@ -34,6 +38,8 @@ pub struct TtyDevice {
     fs: RwLock<Weak<DevFS>>,
     /// TTY设备私有信息
     private_data: RwLock<TtyDevicePrivateData>,
+    /// 通过poll、select、epoll等待这个TTY设备的进程
+    poll_wait_queue: PollWaitQueue,
 }
 
 #[derive(Debug)]
@@ -51,6 +57,7 @@ impl TtyDevice {
             core: TtyCore::new(),
             fs: RwLock::new(Weak::default()),
             private_data: TtyDevicePrivateData::new(name),
+            poll_wait_queue: PollWaitQueue::INIT,
         });
         // 默认开启输入回显
         result.core.enable_echo();
@@ -88,12 +95,16 @@ impl TtyDevice {
     pub fn input(&self, buf: &[u8]) -> Result<usize, SystemError> {
         let r: Result<usize, TtyError> = self.core.input(buf, false);
         if r.is_ok() {
+            self.poll_wait_queue.wakeup(PollStatus::READ);
             return Ok(r.unwrap());
         }
 
         let r = r.unwrap_err();
         match r {
-            TtyError::BufferFull(x) => return Ok(x),
+            TtyError::BufferFull(x) => {
+                self.poll_wait_queue.wakeup(PollStatus::READ);
+                return Ok(x);
+            }
             TtyError::Closed => return Err(SystemError::ENODEV),
             e => {
                 kerror!("tty error occurred while writing data to its input port, msg={e:?}");
@@ -211,8 +222,18 @@ impl IndexNode for TtyDevice {
         return Err(SystemError::EIO);
     }
 
-    fn poll(&self) -> Result<crate::filesystem::vfs::PollStatus, SystemError> {
-        return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+    fn poll(&self) -> Result<PollStatus, SystemError> {
+        // 输出是同步进行的，因此TTY设备总是可写的
+        let mut status = PollStatus::WRITE;
+        if self.core.stdin_readable() {
+            status.insert(PollStatus::READ);
+        }
+        return Ok(status);
+    }
+
+    fn add_poll_waiter(&self, waiter: Weak<dyn PollWaiter>) -> Result<(), SystemError> {
+        self.poll_wait_queue.register(waiter);
+        return Ok(());
     }
 
     fn fs(&self) -> Arc<dyn crate::filesystem::vfs::FileSystem> {
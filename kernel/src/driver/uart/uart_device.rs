@@ -3,8 +3,11 @@ use crate::{
         base::{
             char::CharDevice,
             device::{
-                driver::DriverError, Device, DeviceError, DeviceNumber, DevicePrivateData,
-                DeviceResource, DeviceState, DeviceType, IdTable, KObject, DEVICE_MANAGER,
+                attrs::device_create_attrs,
+                driver::DriverError,
+                uevent::{device_uevent, KObjectAction},
+                Device, DeviceError, DeviceNumber, DevicePrivateData, DeviceResource, DeviceState,
+                DeviceType, IdTable, KObject, DEVICE_MANAGER,
             },
             platform::{
                 platform_device::PlatformDevice, platform_driver::PlatformDriver, CompatibleTable,
@@ -14,7 +17,7 @@ use crate::{
     },
     filesystem::{
         devfs::{devfs_register, DevFS, DeviceINode},
-        sysfs::bus::{bus_device_register, bus_driver_register},
+        sysfs::bus::{bus_device_bind_driver, bus_device_register, bus_driver_register},
         vfs::{FilePrivateData, FileSystem, FileType, IndexNode, Metadata, PollStatus},
     },
     include::bindings::bindings::{io_in8, io_out8},
@@ -31,6 +34,7 @@ use core::{
     any::Any,
     char,
     intrinsics::offset,
+    mem::size_of,
     str::{self, from_utf8},
 };
 
@@ -198,6 +202,19 @@ impl Device for LockedUart {
         DeviceType::Serial
     }
 
+    fn state(&self) -> DeviceState {
+        return self.0.lock().private_data.state();
+    }
+
+    fn resource(&self) -> Option<DeviceResource> {
+        // 串口的各个寄存器占用从端口基地址开始的连续IO端口
+        let port: u16 = self.0.lock().port.to_u16();
+        return Some(DeviceResource {
+            io_ports: vec![port..port + size_of::<UartRegister>() as u16],
+            ..Default::default()
+        });
+    }
+
     fn as_any_ref(&self) -> &dyn Any {
         self
     }
@@ -692,11 +709,29 @@ pub fn uart_init() -> Result<(), SystemError> {
     drop(dev);
     let device_inode = bus_device_register("platform:0", &UART_DEV.id_table().name())
         .expect("uart device register error");
+    let devpath = format!("/bus/platform:0/devices/{}", UART_DEV.id_table().name());
+    device_create_attrs(
+        UART_DEV.clone(),
+        &device_inode,
+        &devpath,
+        Some("platform:0"),
+    )?;
     UART_DEV.set_sys_info(Some(device_inode));
     let driver_inode = bus_driver_register("platform:0", &UART_DRV.id_table().name())
         .expect("uart driver register error");
     UART_DRV.set_sys_info(Some(driver_inode));
+    bus_device_bind_driver(
+        "platform:0",
+        &UART_DEV.id_table().name(),
+        &UART_DRV.id_table().name(),
+    )?;
     UART_DEV.set_state(DeviceState::Initialized);
+    device_uevent(
+        UART_DEV.as_ref(),
+        KObjectAction::Add,
+        &devpath,
+        Some("platform:0"),
+    );
     devfs_register(&UART_DEV.id_table().name(), UART_DEV.clone())?;
     DEVICE_MANAGER.add_device(UART_DEV.id_table().clone(), UART_DEV.clone());
     return Ok(());
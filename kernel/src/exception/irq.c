@@ -202,7 +202,7 @@ int irq_register(ul irq_num, void *arg, void (*handler)(ul irq_num, ul parameter
     p->controller = controller;
     if (p->irq_name == NULL)
     {
-        int namelen = sizeof(strlen(irq_name) + 1);
+        int namelen = strlen(irq_name) + 1;
         p->irq_name = (char *)kmalloc(namelen, 0);
         memset(p->irq_name, 0, namelen);
         strncpy(p->irq_name, irq_name, namelen);
@@ -243,6 +243,23 @@ int irq_unregister(ul irq_num)
     return 0;
 }
 
+/**
+ * @brief 获取中断向量对应的中断名
+ *
+ * @param irq_num 中断向量号
+ * @return const char* 中断名。若该中断向量没有被注册，则返回NULL
+ */
+const char *irq_get_name(ul irq_num)
+{
+    if (irq_num >= 32 && irq_num < 32 + IRQ_NUM)
+        return interrupt_desc[irq_num - 32].irq_name;
+    else if (irq_num >= 150 && irq_num < 150 + LOCAL_APIC_IRQ_NUM)
+        return local_apic_interrupt_desc[irq_num - 150].irq_name;
+    else if (irq_num >= 200 && irq_num < 200 + SMP_IRQ_NUM)
+        return SMP_IPI_desc[irq_num - 200].irq_name;
+    return NULL;
+}
+
 /**
  * @brief 初始化中断模块
  */
@@ -156,6 +156,14 @@ irq_desc_t SMP_IPI_desc[SMP_IRQ_NUM] = {0};
  */
 int irq_register(ul irq_num, void *arg, void (*handler)(ul irq_num, ul parameter, struct pt_regs *regs), ul paramater, hardware_intr_controller *controller, char *irq_name);
 
+/**
+ * @brief 获取中断向量对应的中断名
+ *
+ * @param irq_num 中断向量号
+ * @return const char* 中断名。若该中断向量没有被注册，则返回NULL
+ */
+const char *irq_get_name(ul irq_num);
+
 /**
  * @brief 中断注销函数
  * 
@@ -0,0 +1,56 @@
+use core::{
+    ffi::{c_char, CStr},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use alloc::string::{String, ToString};
+
+use crate::{include::bindings::bindings::MAX_CPU_NUM, smp::core::smp_get_processor_id};
+
+/// 中断向量的数量
+pub const IRQ_VECTOR_NUM: usize = 256;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicUsize = AtomicUsize::new(0);
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO_ROW: [AtomicUsize; IRQ_VECTOR_NUM] = [ZERO; IRQ_VECTOR_NUM];
+
+/// 每个cpu上，每个中断向量发生的次数
+static IRQ_COUNT: [[AtomicUsize; IRQ_VECTOR_NUM]; MAX_CPU_NUM as usize] =
+    [ZERO_ROW; MAX_CPU_NUM as usize];
+
+extern "C" {
+    fn irq_get_name(irq_num: u64) -> *const c_char;
+}
+
+/// @brief 记录当前cpu上发生了一次中断（由do_IRQ调用）
+///
+/// @param vector 中断向量号
+#[no_mangle]
+pub extern "C" fn rs_irq_stat_inc(vector: u64) {
+    let cpu = smp_get_processor_id() as usize;
+    if cpu >= MAX_CPU_NUM as usize || vector >= IRQ_VECTOR_NUM as u64 {
+        return;
+    }
+    IRQ_COUNT[cpu][vector as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+/// @brief 获取指定cpu上，指定中断向量发生的次数
+pub fn irq_count(cpu: usize, vector: usize) -> usize {
+    if cpu >= MAX_CPU_NUM as usize || vector >= IRQ_VECTOR_NUM {
+        return 0;
+    }
+    return IRQ_COUNT[cpu][vector].load(Ordering::Relaxed);
+}
+
+/// @brief 获取中断向量注册时使用的名称
+///
+/// @return 若该中断向量尚未被注册，则返回None
+pub fn irq_name(vector: usize) -> Option<String> {
+    let name = unsafe { irq_get_name(vector as u64) };
+    if name.is_null() {
+        return None;
+    }
+    let name = unsafe { CStr::from_ptr(name) };
+    return Some(name.to_string_lossy().to_string());
+}
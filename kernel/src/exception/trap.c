@@ -218,6 +218,11 @@ void do_page_fault(struct pt_regs *regs, unsigned long error_code)
 
     __asm__ __volatile__("movq	%%cr2,	%0" : "=r"(cr2)::"memory");
 
+    // 尝试处理用户地址空间内的缺页（文件映射的按需调页、写时复制）
+    if (!(error_code & 0x08) &&
+        rs_handle_user_page_fault(cr2, (error_code & 0x02) != 0, (error_code & 0x04) != 0) == 0)
+        return;
+
     kerror("do_page_fault(14),Error code :%#018lx,RSP:%#018lx, RBP=%#018lx, RIP:%#018lx CPU:%d, pid=%d\n", error_code,
            regs->rsp, regs->rbp, regs->rip, proc_current_cpu_id, current_pcb->pid);
     kerror("regs->rax = %#018lx\n", regs->rax);
@@ -4,15 +4,23 @@ pub mod zero_dev;
 
 use super::vfs::{
     core::{generate_inode_id, ROOT_INODE},
+    dcache::dcache,
     file::FileMode,
-    FileSystem, FileType, FsInfo, IndexNode, Metadata, PollStatus,
+    mount::{mount_list, MountFlags, MountRecord},
+    FileSystem, FileType, FsInfo, IndexNode, Metadata, PollStatus, MAX_PATHLEN,
 };
 use crate::{
+    arch::MMArch,
+    driver::base::device::{
+        uevent::{kobject_uevent_env, KObjectAction},
+        DeviceNumber,
+    },
     kerror, kinfo,
     libs::{
         once::Once,
         spinlock::{SpinLock, SpinLockGuard},
     },
+    mm::MemoryManagementArch,
     syscall::SystemError,
     time::TimeSpec,
 };
@@ -24,6 +32,16 @@ use alloc::{
 };
 
 const DEVFS_MAX_NAMELEN: usize = 64;
+/// DevFS的魔数（与Linux相同）
+pub const DEVFS_MAGIC: u64 = 0x1373;
+
+/// /dev下的标准输入输出的别名：(名称, 符号链接的目标)
+const DEV_STD_ALIASES: [(&str, &str); 4] = [
+    ("fd", "/proc/self/fd"),
+    ("stdin", "/proc/self/fd/0"),
+    ("stdout", "/proc/self/fd/1"),
+    ("stderr", "/proc/self/fd/2"),
+];
 
 /// @brief dev文件系统
 #[derive(Debug)]
@@ -45,6 +63,9 @@ impl FileSystem for DevFS {
         return FsInfo {
             blk_dev_id: 0,
             max_name_len: DEVFS_MAX_NAMELEN,
+            magic: DEVFS_MAGIC,
+            block_size: MMArch::PAGE_SIZE,
+            ..Default::default()
         };
     }
 }
@@ -92,6 +113,13 @@ impl DevFS {
         dev_root
             .add_dev("zero", LockedZeroInode::new())
             .expect("DevFS: Failed to register /dev/zero");
+
+        // 标准输入输出的别名（与Linux相同，通过procfs指向当前进程的文件描述符）
+        for (name, target) in DEV_STD_ALIASES {
+            dev_root
+                .add_symlink(name, target)
+                .unwrap_or_else(|_| panic!("DevFS: Failed to register /dev/{}", name));
+        }
     }
 
     /// @brief 在devfs内注册设备
@@ -209,6 +237,8 @@ pub struct DevFSInode {
     fs: Weak<DevFS>,
     /// INode 元数据
     metadata: Metadata,
+    /// 符号链接所指向的路径（仅当inode为符号链接时有效）
+    symlink_target: String,
 }
 
 impl DevFSInode {
@@ -229,6 +259,7 @@ impl DevFSInode {
             metadata: Metadata {
                 dev_id: 1,
                 inode_id: generate_inode_id(),
+                disk_ino: None,
                 size: 0,
                 blk_size: 0,
                 blocks: 0,
@@ -243,6 +274,7 @@ impl DevFSInode {
                 raw_dev: data_,
             },
             fs: Weak::default(),
+            symlink_target: String::new(),
         };
     }
 }
@@ -273,16 +305,43 @@ impl LockedDevFSInode {
         }
 
         this.children.insert(name.to_string(), dev);
+        dcache().invalidate_dir(this.metadata.inode_id);
         return Ok(());
     }
 
-    pub fn remove(&self, name: &str) -> Result<(), SystemError> {
-        let x = self
+    /// @brief 在当前目录下创建一个符号链接（例如/dev/stdin这类别名）
+    ///
+    /// @param name 符号链接的名称
+    /// @param target 符号链接所指向的路径
+    pub fn add_symlink(&self, name: &str, target: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+        if target.is_empty() {
+            return Err(SystemError::ENOENT);
+        }
+        if target.len() > MAX_PATHLEN {
+            return Err(SystemError::ENAMETOOLONG);
+        }
+
+        let guard: SpinLockGuard<DevFSInode> = self.0.lock();
+        let result = self.do_create_with_data(guard, name, FileType::SymLink, 0o777, 0)?;
+
+        let mut link = result
+            .as_any_ref()
+            .downcast_ref::<LockedDevFSInode>()
+            .unwrap()
             .0
-            .lock()
-            .children
-            .remove(name)
-            .ok_or(SystemError::ENOENT)?;
+            .lock();
+        link.symlink_target = String::from(target);
+        link.metadata.size = target.len() as i64;
+        drop(link);
+
+        return Ok(result);
+    }
+
+    pub fn remove(&self, name: &str) -> Result<(), SystemError> {
+        let mut guard = self.0.lock();
+        let x = guard.children.remove(name).ok_or(SystemError::ENOENT)?;
+        dcache().invalidate_dir(guard.metadata.inode_id);
+        drop(guard);
 
         drop(x);
         return Ok(());
@@ -313,6 +372,7 @@ impl LockedDevFSInode {
             metadata: Metadata {
                 dev_id: 0,
                 inode_id: generate_inode_id(),
+                disk_ino: None,
                 size: 0,
                 blk_size: 0,
                 blocks: 0,
@@ -327,6 +387,7 @@ impl LockedDevFSInode {
                 raw_dev: _data,
             },
             fs: guard.fs.clone(),
+            symlink_target: String::new(),
         })));
 
         // 初始化inode的自引用的weak指针
@@ -334,6 +395,7 @@ impl LockedDevFSInode {
 
         // 将子inode插入父inode的B树中
         guard.children.insert(String::from(_name), result.clone());
+        dcache().invalidate_dir(guard.metadata.inode_id);
         return Ok(result);
     }
 }
@@ -368,6 +430,10 @@ impl IndexNode for LockedDevFSInode {
         return self.do_create_with_data(guard, name, file_type, mode, data);
     }
 
+    fn symlink(&self, name: &str, target: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+        return self.add_symlink(name, target);
+    }
+
     fn find(&self, name: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
         let inode = self.0.lock();
 
@@ -472,13 +538,28 @@ impl IndexNode for LockedDevFSInode {
     }
 
     /// 读设备 - 应该调用设备的函数读写，而不是通过文件系统读写
+    ///
+    /// 唯一的例外是符号链接：读取符号链接，得到的是它所指向的路径
     fn read_at(
         &self,
-        _offset: usize,
-        _len: usize,
-        _buf: &mut [u8],
+        offset: usize,
+        len: usize,
+        buf: &mut [u8],
         _data: &mut super::vfs::file::FilePrivateData,
     ) -> Result<usize, SystemError> {
+        let inode: SpinLockGuard<DevFSInode> = self.0.lock();
+        if inode.metadata.file_type == FileType::SymLink {
+            let target = inode.symlink_target.as_bytes();
+            let start = target.len().min(offset);
+            let end = target.len().min(offset + len);
+            if buf.len() < (end - start) {
+                return Err(SystemError::ENOBUFS);
+            }
+            buf[0..(end - start)].copy_from_slice(&target[start..end]);
+            return Ok(end - start);
+        }
+        drop(inode);
+
         kerror!("DevFS: read_at is not supported!");
         Err(SystemError::EOPNOTSUPP_OR_ENOTSUP)
     }
@@ -524,13 +605,38 @@ macro_rules! devfs_exact_ref {
 }
 /// @brief devfs的设备注册函数
 pub fn devfs_register<T: DeviceINode>(name: &str, device: Arc<T>) -> Result<(), SystemError> {
-    return devfs_exact_ref!().register_device(name, device);
+    devfs_exact_ref!().register_device(name, device.clone())?;
+    devfs_uevent(name, device.metadata()?, KObjectAction::Add);
+    return Ok(());
 }
 
 /// @brief devfs的设备卸载函数
 #[allow(dead_code)]
 pub fn devfs_unregister<T: DeviceINode>(name: &str, device: Arc<T>) -> Result<(), SystemError> {
-    return devfs_exact_ref!().unregister_device(name, device);
+    devfs_exact_ref!().unregister_device(name, device.clone())?;
+    devfs_uevent(name, device.metadata()?, KObjectAction::Remove);
+    return Ok(());
+}
+
+/// @brief 广播devfs中设备节点的添加、移除事件
+///
+/// devfs中的设备节点不一定在sysfs中有对应的目录，因此DEVPATH使用设备节点相对于/dev的路径
+fn devfs_uevent(name: &str, metadata: Metadata, action: KObjectAction) {
+    let dir: &str = match metadata.file_type {
+        FileType::BlockDevice => "block",
+        _ => "char",
+    };
+    let devname: String = format!("{}/{}", dir, name);
+
+    let mut env: Vec<String> = Vec::new();
+    let devnum = DeviceNumber::new(metadata.raw_dev);
+    if devnum != DeviceNumber::default() {
+        env.push(format!("MAJOR={}", devnum.major()));
+        env.push(format!("MINOR={}", devnum.minor()));
+    }
+    env.push(format!("DEVNAME={}", devname));
+
+    kobject_uevent_env(action, &format!("/dev/{}", devname), &env);
 }
 
 pub fn devfs_init() -> Result<(), SystemError> {
@@ -541,11 +647,18 @@ pub fn devfs_init() -> Result<(), SystemError> {
         // 创建 devfs 实例
         let devfs: Arc<DevFS> = DevFS::new();
         // devfs 挂载
-        let _t = ROOT_INODE()
+        let mount_fs = ROOT_INODE()
             .find("dev")
             .expect("Cannot find /dev")
             .mount(devfs)
             .expect("Failed to mount devfs");
+        mount_list().insert(MountRecord::new(
+            "devfs",
+            "/dev",
+            "devfs",
+            MountFlags::empty(),
+            mount_fs,
+        ));
         kinfo!("DevFS mounted.");
         result = Some(Ok(()));
     });
@@ -36,6 +36,7 @@ impl LockedNullInode {
             metadata: Metadata {
                 dev_id: 1,
                 inode_id: generate_inode_id(),
+                disk_ino: None,
                 size: 0,
                 blk_size: 0,
                 blocks: 0,
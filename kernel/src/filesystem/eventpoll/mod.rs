@@ -0,0 +1,362 @@
+//! epoll
+//!
+//! 每个epoll实例是一个[`EventPoll`] inode。通过epoll_ctl添加的每个文件描述符对应一个[`EPollItem`]，
+//! 它作为poll等待者注册在目标文件的inode上。目标文件的状态发生变化时，EPollItem会唤醒在epoll_wait中等待的进程，
+//! 进程被唤醒后，再重新调用目标inode的poll方法，获取准确的状态。
+
+pub mod syscall;
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use alloc::{
+    collections::BTreeMap,
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+
+use crate::{libs::spinlock::SpinLock, syscall::SystemError, time::timer::next_n_us_timer_jiffies};
+
+use super::vfs::{
+    core::generate_inode_id,
+    file::FileMode,
+    poll::{poll_inode, poll_should_stop, PollEvents, PollSleeper, PollWaitQueue, PollWaiter},
+    pseudo::anon_inode_fs,
+    FilePrivateData, FileSystem, FileType, IndexNode, Metadata, PollStatus,
+};
+
+bitflags! {
+    /// @brief epoll的事件类型（与Linux的定义相同）
+    pub struct EPollEventType: u32 {
+        const EPOLLIN = 0x0000_0001;
+        const EPOLLPRI = 0x0000_0002;
+        const EPOLLOUT = 0x0000_0004;
+        const EPOLLERR = 0x0000_0008;
+        const EPOLLHUP = 0x0000_0010;
+        const EPOLLNVAL = 0x0000_0020;
+        const EPOLLRDNORM = 0x0000_0040;
+        const EPOLLRDBAND = 0x0000_0080;
+        const EPOLLWRNORM = 0x0000_0100;
+        const EPOLLWRBAND = 0x0000_0200;
+        const EPOLLMSG = 0x0000_0400;
+        const EPOLLRDHUP = 0x0000_2000;
+        /// 独占唤醒（目前与普通的等待相同）
+        const EPOLLEXCLUSIVE = 1u32 << 28;
+        /// 目前被忽略
+        const EPOLLWAKEUP = 1u32 << 29;
+        /// 事件被报告一次之后，就不再报告，直到通过EPOLL_CTL_MOD重新设置
+        const EPOLLONESHOT = 1u32 << 30;
+        /// 边缘触发
+        const EPOLLET = 1u32 << 31;
+
+        /// 控制epoll行为的标志位，而不是事件
+        const EPOLL_CTL_FLAGS = Self::EPOLLEXCLUSIVE.bits
+            | Self::EPOLLWAKEUP.bits
+            | Self::EPOLLONESHOT.bits
+            | Self::EPOLLET.bits;
+    }
+}
+
+impl From<PollEvents> for EPollEventType {
+    fn from(events: PollEvents) -> Self {
+        // poll和epoll的事件使用相同的定义
+        return EPollEventType::from_bits_truncate(events.bits() as u32);
+    }
+}
+
+/// @brief epoll_ctl、epoll_wait使用的结构体（与Linux相同，在x86_64上是紧凑排列的）
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EPollEvent {
+    /// 事件类型
+    pub events: u32,
+    /// 用户数据，内核不会解释它，只是在报告事件时原样返回
+    pub data: u64,
+}
+
+/// @brief epoll_ctl的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+pub enum EPollCtlOption {
+    /// 添加一个文件描述符
+    Add = 1,
+    /// 删除一个文件描述符
+    Del = 2,
+    /// 修改一个文件描述符关心的事件
+    Mod = 3,
+}
+
+/// @brief epoll中的一项，对应一个被监视的文件描述符
+#[derive(Debug)]
+pub struct EPollItem {
+    /// 被监视的文件的inode
+    inode: Weak<dyn IndexNode>,
+    /// 关心的事件以及用户数据
+    event: SpinLock<EPollEvent>,
+    /// 自从上一次报告以来，目标文件的状态是否可能发生了变化（用于边缘触发）
+    pending: AtomicBool,
+    /// 所属的epoll实例
+    epoll: Weak<EventPoll>,
+}
+
+impl PollWaiter for EPollItem {
+    fn wakeup(&self, status: PollStatus) {
+        self.pending.store(true, Ordering::SeqCst);
+        if let Some(epoll) = self.epoll.upgrade() {
+            epoll.notify(status);
+        }
+    }
+}
+
+impl EPollItem {
+    /// @brief 检查目标文件上是否发生了关心的事件
+    ///
+    /// @return Some(EPollEvent) 需要报告的事件
+    /// @return None 没有需要报告的事件
+    fn check(&self) -> Option<EPollEvent> {
+        let mut guard = self.event.lock();
+        let wanted = EPollEventType::from_bits_truncate(guard.events);
+        let interested = wanted - EPollEventType::EPOLL_CTL_FLAGS;
+        // 被EPOLLONESHOT禁用的项
+        if interested.is_empty() {
+            return None;
+        }
+        // 边缘触发：只有在目标文件的状态可能发生了变化之后，才检查它
+        if wanted.contains(EPollEventType::EPOLLET) && !self.pending.swap(false, Ordering::SeqCst) {
+            return None;
+        }
+
+        let inode: Arc<dyn IndexNode> = self.inode.upgrade()?;
+        let revents = EPollEventType::from(PollEvents::from(poll_inode(&inode)))
+            & (interested | EPollEventType::EPOLLERR | EPollEventType::EPOLLHUP);
+        if revents.is_empty() {
+            return None;
+        }
+
+        if wanted.contains(EPollEventType::EPOLLONESHOT) {
+            guard.events = (wanted & EPollEventType::EPOLL_CTL_FLAGS).bits();
+        }
+        return Some(EPollEvent {
+            events: revents.bits(),
+            data: guard.data,
+        });
+    }
+}
+
+/// @brief epoll实例
+#[derive(Debug)]
+pub struct EventPoll {
+    /// 被监视的文件描述符 -> 对应的项
+    items: SpinLock<BTreeMap<i32, Arc<EPollItem>>>,
+    /// 在epoll_wait中等待的进程
+    sleeper: Arc<PollSleeper>,
+    /// 通过poll、select、epoll等待这个epoll实例的进程
+    poll_wait_queue: PollWaitQueue,
+    /// inode元数据
+    metadata: Metadata,
+    self_ref: Weak<EventPoll>,
+}
+
+impl EventPoll {
+    /// epoll_wait一次最多返回的事件数量
+    pub const EP_MAX_EVENTS: usize = i32::MAX as usize / core::mem::size_of::<EPollEvent>();
+
+    pub fn new() -> Arc<Self> {
+        return Arc::new_cyclic(|self_ref| Self {
+            items: SpinLock::new(BTreeMap::new()),
+            sleeper: PollSleeper::new(),
+            poll_wait_queue: PollWaitQueue::INIT,
+            metadata: Metadata {
+                inode_id: generate_inode_id(),
+                mode: 0o600,
+                file_type: FileType::AnonInode,
+                ..Default::default()
+            },
+            self_ref: self_ref.clone(),
+        });
+    }
+
+    /// @brief 被监视的文件的状态可能发生了变化，唤醒等待者
+    fn notify(&self, status: PollStatus) {
+        self.sleeper.wakeup(status);
+        self.poll_wait_queue.wakeup(PollStatus::READ);
+    }
+
+    /// @brief 添加、删除或者修改一个被监视的文件描述符
+    ///
+    /// @param op 操作
+    /// @param fd 被监视的文件描述符
+    /// @param inode 被监视的文件的inode
+    /// @param event 关心的事件（对于Del操作，会被忽略）
+    pub fn ctl(
+        &self,
+        op: EPollCtlOption,
+        fd: i32,
+        inode: Arc<dyn IndexNode>,
+        event: EPollEvent,
+    ) -> Result<(), SystemError> {
+        let mut items = self.items.lock();
+        match op {
+            EPollCtlOption::Add => {
+                if items.contains_key(&fd) {
+                    return Err(SystemError::EEXIST);
+                }
+                if let Some(target) = inode.as_any_ref().downcast_ref::<EventPoll>() {
+                    // 不能监视自身。另外，只允许一层嵌套，从而保证epoll实例之间不会形成环
+                    if core::ptr::eq(target, self) {
+                        return Err(SystemError::EINVAL);
+                    }
+                    if target.has_nested_epoll() {
+                        return Err(SystemError::ELOOP);
+                    }
+                }
+                let item = Arc::new(EPollItem {
+                    inode: Arc::downgrade(&inode),
+                    event: SpinLock::new(event),
+                    pending: AtomicBool::new(true),
+                    epoll: self.self_ref.clone(),
+                });
+                let waiter: Arc<dyn PollWaiter> = item.clone();
+                // 不支持等待的文件（例如普通文件）不能被epoll监视
+                inode
+                    .add_poll_waiter(Arc::downgrade(&waiter))
+                    .map_err(|_| SystemError::EPERM)?;
+                items.insert(fd, item);
+            }
+            EPollCtlOption::Del => {
+                // 项被释放后，它在目标inode上的弱引用会自动失效
+                items.remove(&fd).ok_or(SystemError::ENOENT)?;
+            }
+            EPollCtlOption::Mod => {
+                let item = items.get(&fd).ok_or(SystemError::ENOENT)?;
+                *item.event.lock() = event;
+                item.pending.store(true, Ordering::SeqCst);
+            }
+        }
+        drop(items);
+
+        // 新的设置可能使已经就绪的事件需要被报告
+        if op != EPollCtlOption::Del {
+            self.notify(PollStatus::empty());
+        }
+        return Ok(());
+    }
+
+    /// @brief 收集已经发生的事件
+    fn collect_events(&self, events: &mut Vec<EPollEvent>, max_events: usize) {
+        // 在检查目标文件时不持有items的锁，防止与目标inode的回调形成锁的循环依赖
+        let items: Vec<Arc<EPollItem>> = self.items.lock().values().cloned().collect();
+        for item in items {
+            if events.len() >= max_events {
+                break;
+            }
+            if let Some(event) = item.check() {
+                events.push(event);
+            }
+        }
+    }
+
+    /// @brief 等待被监视的文件上发生事件
+    ///
+    /// @param max_events 最多返回的事件数量
+    /// @param timeout_us 超时时间（单位：微秒）。为None时表示永不超时，为Some(0)时表示不等待
+    ///
+    /// @return Ok(Vec<EPollEvent>) 发生的事件。超时时返回空数组
+    /// @return Err(EINTR) 等待的过程中收到了信号
+    pub fn wait(
+        &self,
+        max_events: usize,
+        timeout_us: Option<u64>,
+    ) -> Result<Vec<EPollEvent>, SystemError> {
+        let deadline: Option<u64> = timeout_us.map(next_n_us_timer_jiffies);
+        let mut events: Vec<EPollEvent> = Vec::new();
+        loop {
+            self.sleeper.reset();
+            self.collect_events(&mut events, max_events);
+            if !events.is_empty() || timeout_us == Some(0) || poll_should_stop(deadline)? {
+                return Ok(events);
+            }
+            self.sleeper.sleep(deadline);
+        }
+    }
+
+    /// @brief 判断epoll实例是否监视着其他的epoll实例
+    fn has_nested_epoll(&self) -> bool {
+        return self.items.lock().values().any(|item| {
+            item.inode
+                .upgrade()
+                .map(|inode| inode.as_any_ref().is::<EventPoll>())
+                .unwrap_or(false)
+        });
+    }
+}
+
+impl IndexNode for EventPoll {
+    fn open(&self, _data: &mut FilePrivateData, _mode: &FileMode) -> Result<(), SystemError> {
+        return Ok(());
+    }
+
+    fn close(&self, _data: &mut FilePrivateData) -> Result<(), SystemError> {
+        return Ok(());
+    }
+
+    fn read_at(
+        &self,
+        _offset: usize,
+        _len: usize,
+        _buf: &mut [u8],
+        _data: &mut FilePrivateData,
+    ) -> Result<usize, SystemError> {
+        return Err(SystemError::EINVAL);
+    }
+
+    fn write_at(
+        &self,
+        _offset: usize,
+        _len: usize,
+        _buf: &[u8],
+        _data: &mut FilePrivateData,
+    ) -> Result<usize, SystemError> {
+        return Err(SystemError::EINVAL);
+    }
+
+    /// @brief epoll实例中有事件可以报告时，它是可读的
+    fn poll(&self) -> Result<PollStatus, SystemError> {
+        let items: Vec<Arc<EPollItem>> = self.items.lock().values().cloned().collect();
+        for item in items {
+            let event = *item.event.lock();
+            let interested =
+                EPollEventType::from_bits_truncate(event.events) - EPollEventType::EPOLL_CTL_FLAGS;
+            let inode = match item.inode.upgrade() {
+                Some(inode) => inode,
+                None => continue,
+            };
+            let revents = EPollEventType::from(PollEvents::from(poll_inode(&inode)));
+            if revents.intersects(interested | EPollEventType::EPOLLERR | EPollEventType::EPOLLHUP)
+            {
+                return Ok(PollStatus::READ);
+            }
+        }
+        return Ok(PollStatus::empty());
+    }
+
+    fn add_poll_waiter(&self, waiter: Weak<dyn PollWaiter>) -> Result<(), SystemError> {
+        self.poll_wait_queue.register(waiter);
+        return Ok(());
+    }
+
+    fn metadata(&self) -> Result<Metadata, SystemError> {
+        return Ok(self.metadata.clone());
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        return anon_inode_fs();
+    }
+
+    fn as_any_ref(&self) -> &dyn core::any::Any {
+        self
+    }
+
+    fn list(&self) -> Result<Vec<String>, SystemError> {
+        return Err(SystemError::ENOTDIR);
+    }
+}
@@ -0,0 +1,187 @@
+use alloc::{sync::Arc, vec::Vec};
+
+use crate::{
+    arch::asm::current::current_pcb,
+    filesystem::vfs::{
+        file::{File, FileMode},
+        poll::with_sigmask,
+        IndexNode,
+    },
+    ipc::signal_types::sigset_t,
+    libs::casting::DowncastArc,
+    syscall::{
+        user_access::{UserBufferReader, UserBufferWriter},
+        Syscall, SystemError,
+    },
+};
+
+use super::{EPollCtlOption, EPollEvent, EventPoll};
+
+impl Syscall {
+    /// # epoll_create
+    ///
+    /// ## 描述
+    ///
+    /// 创建一个epoll实例。
+    ///
+    /// ## 参数
+    ///
+    /// - `size`：历史遗留参数，必须大于0，但是不会被使用
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回epoll实例的文件描述符，否则返回错误码.
+    pub fn epoll_create(size: i32) -> Result<usize, SystemError> {
+        if size <= 0 {
+            return Err(SystemError::EINVAL);
+        }
+        return Self::epoll_create1(0);
+    }
+
+    /// # epoll_create1
+    ///
+    /// ## 描述
+    ///
+    /// 创建一个epoll实例。
+    ///
+    /// ## 参数
+    ///
+    /// - `flags`：可以为0或者O_CLOEXEC
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回epoll实例的文件描述符，否则返回错误码.
+    pub fn epoll_create1(flags: u32) -> Result<usize, SystemError> {
+        let flags = FileMode::from_bits(flags).ok_or(SystemError::EINVAL)?;
+        if !(flags - FileMode::O_CLOEXEC).is_empty() {
+            return Err(SystemError::EINVAL);
+        }
+
+        let mut file = File::new(EventPoll::new(), FileMode::O_RDWR)?;
+        if flags.contains(FileMode::O_CLOEXEC) {
+            file.set_close_on_exec(true);
+        }
+        return current_pcb().alloc_fd(file, None).map(|fd| fd as usize);
+    }
+
+    /// # epoll_ctl
+    ///
+    /// ## 描述
+    ///
+    /// 在epoll实例中添加、删除或者修改被监视的文件描述符。
+    ///
+    /// ## 参数
+    ///
+    /// - `epfd`：epoll实例的文件描述符
+    /// - `op`：操作，可以为EPOLL_CTL_ADD、EPOLL_CTL_DEL、EPOLL_CTL_MOD
+    /// - `fd`：被监视的文件描述符
+    /// - `event`：用户空间的EPollEvent结构体。对于EPOLL_CTL_DEL，可以为空
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn epoll_ctl(
+        epfd: i32,
+        op: EPollCtlOption,
+        fd: i32,
+        event: *const EPollEvent,
+    ) -> Result<usize, SystemError> {
+        let epoll: Arc<EventPoll> = Self::get_epoll(epfd)?;
+        let inode: Arc<dyn IndexNode> = current_pcb()
+            .get_file_ref_by_fd(fd)
+            .ok_or(SystemError::EBADF)?
+            .inode();
+
+        let event: EPollEvent = if op == EPollCtlOption::Del {
+            EPollEvent::default()
+        } else {
+            let reader = UserBufferReader::new(event, core::mem::size_of::<EPollEvent>(), true)?;
+            *reader.read_one_from_user::<EPollEvent>(0)?
+        };
+
+        epoll.ctl(op, fd, inode, event)?;
+        return Ok(0);
+    }
+
+    /// # epoll_wait
+    ///
+    /// ## 描述
+    ///
+    /// 等待epoll实例所监视的文件描述符上发生事件。
+    ///
+    /// ## 参数
+    ///
+    /// - `epfd`：epoll实例的文件描述符
+    /// - `events`：用户空间的EPollEvent数组，用于存放发生的事件
+    /// - `max_events`：数组的长度，必须大于0
+    /// - `timeout`：超时时间（单位：毫秒）。小于0时表示永不超时，为0时表示立即返回
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回发生的事件的数量（超时时为0），否则返回错误码.
+    pub fn epoll_wait(
+        epfd: i32,
+        events: *mut EPollEvent,
+        max_events: i32,
+        timeout: i32,
+    ) -> Result<usize, SystemError> {
+        return Self::epoll_pwait(epfd, events, max_events, timeout, None);
+    }
+
+    /// # epoll_pwait
+    ///
+    /// ## 描述
+    ///
+    /// 与epoll_wait相同，但是可以在等待期间临时替换进程的信号屏蔽字。
+    ///
+    /// ## 参数
+    ///
+    /// - `epfd`、`events`、`max_events`、`timeout`：与epoll_wait相同
+    /// - `sigmask`：等待期间使用的信号屏蔽字。为None时，不替换信号屏蔽字
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回发生的事件的数量（超时时为0），否则返回错误码.
+    pub fn epoll_pwait(
+        epfd: i32,
+        events: *mut EPollEvent,
+        max_events: i32,
+        timeout: i32,
+        sigmask: Option<sigset_t>,
+    ) -> Result<usize, SystemError> {
+        if max_events <= 0 || max_events as usize > EventPoll::EP_MAX_EVENTS {
+            return Err(SystemError::EINVAL);
+        }
+        let max_events = max_events as usize;
+        let len = max_events * core::mem::size_of::<EPollEvent>();
+        // 在等待之前检查用户缓冲区，防止等到了事件之后才发现无法写回
+        UserBufferWriter::new(events, len, true)?;
+
+        let epoll: Arc<EventPoll> = Self::get_epoll(epfd)?;
+        let timeout_us: Option<u64> = if timeout < 0 {
+            None
+        } else {
+            Some(timeout as u64 * 1000)
+        };
+        let ready: Vec<EPollEvent> = with_sigmask(sigmask, || epoll.wait(max_events, timeout_us))?;
+
+        if !ready.is_empty() {
+            let mut writer = UserBufferWriter::new(
+                events,
+                ready.len() * core::mem::size_of::<EPollEvent>(),
+                true,
+            )?;
+            writer.copy_to_user(&ready, 0)?;
+        }
+        return Ok(ready.len());
+    }
+
+    /// @brief 根据文件描述符，获取epoll实例
+    fn get_epoll(epfd: i32) -> Result<Arc<EventPoll>, SystemError> {
+        let inode: Arc<dyn IndexNode> = current_pcb()
+            .get_file_ref_by_fd(epfd)
+            .ok_or(SystemError::EBADF)?
+            .inode();
+        return inode.downcast_arc::<EventPoll>().ok_or(SystemError::EINVAL);
+    }
+}
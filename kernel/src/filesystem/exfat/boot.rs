@@ -0,0 +1,276 @@
+#![allow(dead_code)]
+use alloc::{sync::Arc, vec::Vec};
+
+use crate::{
+    driver::base::block::{block_device::LBA_SIZE, disk_info::Partition, SeekFrom},
+    kerror,
+    libs::vec_cursor::VecCursor,
+    syscall::SystemError,
+};
+
+/// exFAT引导扇区中的文件系统名称
+pub const EXFAT_FS_NAME: &[u8; 8] = b"EXFAT   ";
+/// 引导扇区的跳转指令
+const EXFAT_JUMP_BOOT: [u8; 3] = [0xeb, 0x76, 0x90];
+/// 引导扇区结束标志
+const EXFAT_BOOT_SIGNATURE: u16 = 0xaa55;
+/// 主引导区域（包括校验和扇区）占用的扇区数
+const EXFAT_BOOT_REGION_SECTORS: usize = 12;
+
+/// VolumeFlags字段在引导扇区内的字节偏移量
+pub const VOLUME_FLAGS_OFFSET: u64 = 106;
+
+bitflags! {
+    /// @brief exFAT引导扇区中的VolumeFlags字段
+    pub struct VolumeFlags: u16 {
+        /// 为1时，使用第二个FAT表（仅当有两个FAT表时有效）
+        const ACTIVE_FAT = 1 << 0;
+        /// 卷正在被使用（没有被正常卸载）
+        const VOLUME_DIRTY = 1 << 1;
+        /// 驱动程序曾经在卷上遇到过介质错误
+        const MEDIA_FAILURE = 1 << 2;
+    }
+}
+
+/// @brief exFAT文件系统的引导扇区（Main Boot Sector）
+///
+/// 参考资料：https://learn.microsoft.com/en-us/windows/win32/fileio/exfat-specification
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExFatBootSector {
+    /// 跳转指令
+    pub jump_boot: [u8; 3],
+    /// 文件系统名称，必须为"EXFAT   "
+    pub fs_name: [u8; 8],
+    /// 分区在磁盘上的起始扇区（仅供参考，为0时应当被忽略）
+    pub partition_offset: u64,
+    /// 卷的大小（单位：扇区）
+    pub volume_length: u64,
+    /// 第一个FAT表在卷内的起始扇区
+    pub fat_offset: u32,
+    /// 每个FAT表占用的扇区数
+    pub fat_length: u32,
+    /// 簇堆（数据区）在卷内的起始扇区
+    pub cluster_heap_offset: u32,
+    /// 簇堆中的簇的数量
+    pub cluster_count: u32,
+    /// 根目录的第一个簇
+    pub first_cluster_of_root_directory: u32,
+    /// 卷序列号
+    pub volume_serial_number: u32,
+    /// 文件系统版本号。高字节为主版本号，低字节为次版本号
+    pub fs_revision: u16,
+    /// 卷的状态标志
+    pub volume_flags: VolumeFlags,
+    /// 每扇区字节数的以2为底的对数（9-12）
+    pub bytes_per_sector_shift: u8,
+    /// 每簇扇区数的以2为底的对数
+    pub sectors_per_cluster_shift: u8,
+    /// FAT表的数量（1或2）
+    pub number_of_fats: u8,
+    /// int0x13的驱动器号
+    pub drive_select: u8,
+    /// 簇堆的使用率（百分比），0xff表示未知
+    pub percent_in_use: u8,
+    /// 引导扇区结束标志0xAA55
+    pub boot_signature: u16,
+}
+
+impl Default for VolumeFlags {
+    fn default() -> Self {
+        return VolumeFlags::empty();
+    }
+}
+
+impl ExFatBootSector {
+    /// @brief 读取并检查分区上的exFAT引导扇区
+    ///
+    /// @return Ok(ExFatBootSector) 分区上是一个合法的exFAT文件系统
+    /// @return Err(EINVAL) 分区上不是exFAT文件系统，或者引导扇区已经损坏
+    pub fn new(partition: &Arc<Partition>) -> Result<ExFatBootSector, SystemError> {
+        let mut v: Vec<u8> = vec![0; LBA_SIZE];
+        partition
+            .disk()
+            .read_at(partition.lba_start as usize, 1, &mut v)?;
+
+        let mut cursor = VecCursor::new(v);
+        let mut bs = ExFatBootSector::default();
+
+        cursor.read_exact(&mut bs.jump_boot)?;
+        cursor.read_exact(&mut bs.fs_name)?;
+        if bs.jump_boot != EXFAT_JUMP_BOOT || &bs.fs_name != EXFAT_FS_NAME {
+            return Err(SystemError::EINVAL);
+        }
+
+        // BPB在exFAT中被废弃，这53个字节必须为0，以免被FAT驱动程序误认为是FAT卷
+        let mut must_be_zero = [0u8; 53];
+        cursor.read_exact(&mut must_be_zero)?;
+        if must_be_zero.iter().any(|x| *x != 0) {
+            kerror!("exFAT: MustBeZero field of the boot sector is not zero");
+            return Err(SystemError::EINVAL);
+        }
+
+        bs.partition_offset = cursor.read_u64()?;
+        bs.volume_length = cursor.read_u64()?;
+        bs.fat_offset = cursor.read_u32()?;
+        bs.fat_length = cursor.read_u32()?;
+        bs.cluster_heap_offset = cursor.read_u32()?;
+        bs.cluster_count = cursor.read_u32()?;
+        bs.first_cluster_of_root_directory = cursor.read_u32()?;
+        bs.volume_serial_number = cursor.read_u32()?;
+        bs.fs_revision = cursor.read_u16()?;
+        bs.volume_flags = VolumeFlags::from_bits_truncate(cursor.read_u16()?);
+        bs.bytes_per_sector_shift = cursor.read_u8()?;
+        bs.sectors_per_cluster_shift = cursor.read_u8()?;
+        bs.number_of_fats = cursor.read_u8()?;
+        bs.drive_select = cursor.read_u8()?;
+        bs.percent_in_use = cursor.read_u8()?;
+
+        cursor.seek(SeekFrom::SeekSet(510))?;
+        bs.boot_signature = cursor.read_u16()?;
+
+        bs.validate()?;
+        bs.verify_checksum(partition)?;
+        return Ok(bs);
+    }
+
+    /// @brief 判断分区上是否是exFAT文件系统（只检查文件系统名称，不检查其余字段）
+    pub fn probe(partition: &Arc<Partition>) -> Result<bool, SystemError> {
+        let mut v: Vec<u8> = vec![0; LBA_SIZE];
+        partition
+            .disk()
+            .read_at(partition.lba_start as usize, 1, &mut v)?;
+        return Ok(&v[3..11] == EXFAT_FS_NAME);
+    }
+
+    /// @brief 检查引导扇区中的各个字段是否合法
+    fn validate(&self) -> Result<(), SystemError> {
+        if self.boot_signature != EXFAT_BOOT_SIGNATURE {
+            kerror!("exFAT: invalid boot signature {:#x}", self.boot_signature);
+            return Err(SystemError::EINVAL);
+        }
+
+        if self.fs_revision >> 8 != 1 {
+            kerror!(
+                "exFAT: unsupported file system revision {}.{}",
+                self.fs_revision >> 8,
+                self.fs_revision & 0xff
+            );
+            return Err(SystemError::EINVAL);
+        }
+
+        if !(9..=12).contains(&self.bytes_per_sector_shift) {
+            kerror!(
+                "exFAT: invalid BytesPerSectorShift {}",
+                self.bytes_per_sector_shift
+            );
+            return Err(SystemError::EINVAL);
+        }
+
+        // 簇的大小最大为32MB
+        if self.sectors_per_cluster_shift > 25 - self.bytes_per_sector_shift {
+            kerror!(
+                "exFAT: invalid SectorsPerClusterShift {}",
+                self.sectors_per_cluster_shift
+            );
+            return Err(SystemError::EINVAL);
+        }
+
+        if self.number_of_fats != 1 && self.number_of_fats != 2 {
+            kerror!("exFAT: invalid NumberOfFats {}", self.number_of_fats);
+            return Err(SystemError::EINVAL);
+        }
+
+        // FAT表需要能容纳所有的簇（以及开头的2个保留项）
+        if (self.fat_length as u64) << self.bytes_per_sector_shift
+            < (self.cluster_count as u64 + 2) * 4
+        {
+            kerror!(
+                "exFAT: FAT is too small for {} clusters",
+                self.cluster_count
+            );
+            return Err(SystemError::EINVAL);
+        }
+
+        let heap_end: u64 = self.cluster_heap_offset as u64
+            + ((self.cluster_count as u64) << self.sectors_per_cluster_shift);
+        if heap_end > self.volume_length {
+            kerror!("exFAT: cluster heap exceeds the end of the volume");
+            return Err(SystemError::EINVAL);
+        }
+
+        if self.first_cluster_of_root_directory < 2
+            || self.first_cluster_of_root_directory > self.cluster_count + 1
+        {
+            kerror!(
+                "exFAT: invalid root directory cluster {}",
+                self.first_cluster_of_root_directory
+            );
+            return Err(SystemError::EINVAL);
+        }
+
+        return Ok(());
+    }
+
+    /// @brief 检查主引导区域的校验和
+    ///
+    /// 主引导区域的前11个扇区的校验和，重复地存储在第12个扇区中
+    fn verify_checksum(&self, partition: &Arc<Partition>) -> Result<(), SystemError> {
+        let bytes_per_sector: usize = self.bytes_per_sector() as usize;
+        let mut v: Vec<u8> = vec![0; bytes_per_sector * EXFAT_BOOT_REGION_SECTORS];
+        partition
+            .disk()
+            .read_at(partition.lba_start as usize, v.len() / LBA_SIZE, &mut v)?;
+
+        let (data, checksum_sector) = v.split_at(bytes_per_sector * 11);
+        let checksum: u32 = Self::boot_checksum(data);
+        for expected in checksum_sector.chunks_exact(4) {
+            if u32::from_le_bytes([expected[0], expected[1], expected[2], expected[3]]) != checksum
+            {
+                kerror!("exFAT: boot region checksum mismatch");
+                return Err(SystemError::EINVAL);
+            }
+        }
+        return Ok(());
+    }
+
+    /// @brief 计算主引导区域的校验和
+    ///
+    /// 引导扇区中的VolumeFlags和PercentInUse字段会随着卷的使用而变化，因此不参与计算
+    fn boot_checksum(data: &[u8]) -> u32 {
+        let mut checksum: u32 = 0;
+        for (i, byte) in data.iter().enumerate() {
+            if i == 106 || i == 107 || i == 112 {
+                continue;
+            }
+            checksum = checksum.rotate_right(1).wrapping_add(*byte as u32);
+        }
+        return checksum;
+    }
+
+    /// @brief 每扇区的字节数
+    #[inline]
+    pub fn bytes_per_sector(&self) -> u64 {
+        return 1 << self.bytes_per_sector_shift;
+    }
+
+    /// @brief 每簇的字节数
+    #[inline]
+    pub fn bytes_per_cluster(&self) -> u64 {
+        return 1 << (self.bytes_per_sector_shift + self.sectors_per_cluster_shift);
+    }
+
+    /// @brief 当前使用的FAT表在分区内的字节偏移量
+    pub fn active_fat_bytes_offset(&self) -> u64 {
+        let mut sector: u64 = self.fat_offset as u64;
+        if self.number_of_fats == 2 && self.volume_flags.contains(VolumeFlags::ACTIVE_FAT) {
+            sector += self.fat_length as u64;
+        }
+        return sector << self.bytes_per_sector_shift;
+    }
+
+    /// @brief 簇堆在分区内的字节偏移量
+    #[inline]
+    pub fn cluster_heap_bytes_offset(&self) -> u64 {
+        return (self.cluster_heap_offset as u64) << self.bytes_per_sector_shift;
+    }
+}
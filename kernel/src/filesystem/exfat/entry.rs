@@ -0,0 +1,576 @@
+#![allow(dead_code)]
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    filesystem::fat::utils::{fat_time_to_timespec, timespec_to_fat_time},
+    kwarn,
+    syscall::SystemError,
+    time::TimeSpec,
+};
+
+/// 每个目录项的大小（单位：字节）
+pub const DENTRY_SIZE: usize = 32;
+/// 文件名的最大长度（单位：UTF-16编码单元）
+pub const EXFAT_MAX_NAMELEN: usize = 255;
+/// 每个File Name目录项能够容纳的字符数
+const NAME_CHARS_PER_ENTRY: usize = 15;
+/// 一个目录项集合中，最多能有的目录项数量（File + Stream Extension + 17个File Name）
+const MAX_ENTRY_SET_SIZE: usize = 19;
+
+/// 目录的结尾（它以及它之后的目录项都没有被使用）
+pub const ENTRY_TYPE_END: u8 = 0x00;
+/// 分配位图
+pub const ENTRY_TYPE_BITMAP: u8 = 0x81;
+/// 大写字母表
+pub const ENTRY_TYPE_UPCASE: u8 = 0x82;
+/// 卷标
+pub const ENTRY_TYPE_VOLUME_LABEL: u8 = 0x83;
+/// 文件（目录项集合的第一项）
+pub const ENTRY_TYPE_FILE: u8 = 0x85;
+/// 流扩展（记录文件的簇链和大小）
+pub const ENTRY_TYPE_STREAM: u8 = 0xc0;
+/// 文件名
+pub const ENTRY_TYPE_NAME: u8 = 0xc1;
+/// 目录项类型中的InUse位。为0表示目录项已经被删除
+pub const ENTRY_IN_USE: u8 = 0x80;
+
+bitflags! {
+    /// @brief File目录项中的文件属性
+    pub struct FileAttributes: u16 {
+        const READ_ONLY = 1 << 0;
+        const HIDDEN = 1 << 1;
+        const SYSTEM = 1 << 2;
+        const DIRECTORY = 1 << 4;
+        const ARCHIVE = 1 << 5;
+    }
+}
+
+bitflags! {
+    /// @brief Stream Extension目录项中的GeneralSecondaryFlags字段
+    pub struct SecondaryFlags: u8 {
+        /// 文件可以拥有簇
+        const ALLOCATION_POSSIBLE = 1 << 0;
+        /// 文件的簇在簇堆中是连续的，FAT表中对应的表项没有意义
+        const NO_FAT_CHAIN = 1 << 1;
+    }
+}
+
+/// @brief 一个文件（或文件夹）在目录中的目录项集合
+///
+/// 集合由一个File目录项、一个Stream Extension目录项以及若干个File Name目录项组成，
+/// 之后可能还跟着一些本驱动程序不认识的次要目录项。这里保存整个集合的原始数据，
+/// 修改时只改动已知的字段，这样写回磁盘时不会丢失其他的目录项。
+#[derive(Debug, Clone)]
+pub struct ExFatEntrySet {
+    /// 整个目录项集合的原始数据
+    raw: Vec<u8>,
+    /// 目录项集合在所在目录中的字节偏移量
+    pub pos: u64,
+}
+
+impl ExFatEntrySet {
+    // File目录项中各个字段的偏移量
+    const SECONDARY_COUNT: usize = 1;
+    const SET_CHECKSUM: usize = 2;
+    const FILE_ATTRIBUTES: usize = 4;
+    const CREATE_TIMESTAMP: usize = 8;
+    const MODIFIED_TIMESTAMP: usize = 12;
+    const ACCESSED_TIMESTAMP: usize = 16;
+    const CREATE_10MS: usize = 20;
+    const MODIFIED_10MS: usize = 21;
+    const CREATE_UTC_OFFSET: usize = 22;
+    const MODIFIED_UTC_OFFSET: usize = 23;
+    const ACCESSED_UTC_OFFSET: usize = 24;
+
+    // Stream Extension目录项中各个字段的偏移量（相对于集合的起始位置）
+    const STREAM_FLAGS: usize = DENTRY_SIZE + 1;
+    const STREAM_NAME_LENGTH: usize = DENTRY_SIZE + 3;
+    const STREAM_NAME_HASH: usize = DENTRY_SIZE + 4;
+    const STREAM_VALID_DATA_LENGTH: usize = DENTRY_SIZE + 8;
+    const STREAM_FIRST_CLUSTER: usize = DENTRY_SIZE + 20;
+    const STREAM_DATA_LENGTH: usize = DENTRY_SIZE + 24;
+
+    /// @brief 从目录的数据中解析一个目录项集合
+    ///
+    /// @param data 目录的数据
+    /// @param pos 集合的第一个目录项（File目录项）在data中的偏移量
+    ///
+    /// @return Ok(ExFatEntrySet) 解析成功
+    /// @return Err(EINVAL) 目录项集合已经损坏
+    pub fn parse(data: &[u8], pos: usize) -> Result<Self, SystemError> {
+        if data[pos] != ENTRY_TYPE_FILE {
+            return Err(SystemError::EINVAL);
+        }
+        let secondary_count: usize = data[pos + Self::SECONDARY_COUNT] as usize;
+        if secondary_count < 2 || secondary_count >= MAX_ENTRY_SET_SIZE {
+            return Err(SystemError::EINVAL);
+        }
+        let end: usize = pos + (secondary_count + 1) * DENTRY_SIZE;
+        if end > data.len() {
+            return Err(SystemError::EINVAL);
+        }
+
+        let set = Self {
+            raw: data[pos..end].to_vec(),
+            pos: pos as u64,
+        };
+
+        if set.raw[DENTRY_SIZE] != ENTRY_TYPE_STREAM {
+            return Err(SystemError::EINVAL);
+        }
+        let name_length: usize = set.raw[Self::STREAM_NAME_LENGTH] as usize;
+        let name_entries: usize = (name_length + NAME_CHARS_PER_ENTRY - 1) / NAME_CHARS_PER_ENTRY;
+        if name_length == 0 || name_entries + 1 > secondary_count {
+            return Err(SystemError::EINVAL);
+        }
+        for i in 0..name_entries {
+            if set.raw[(i + 2) * DENTRY_SIZE] != ENTRY_TYPE_NAME {
+                return Err(SystemError::EINVAL);
+            }
+        }
+        if set.read_u16(Self::SET_CHECKSUM) != Self::checksum(&set.raw) {
+            return Err(SystemError::EINVAL);
+        }
+        return Ok(set);
+    }
+
+    /// @brief 为一个新的文件创建目录项集合（不包含簇链信息）
+    ///
+    /// @param name UTF-16编码的文件名
+    /// @param name_hash 文件名的哈希值
+    /// @param attributes 文件属性
+    pub fn new(name: &[u16], name_hash: u16, attributes: FileAttributes) -> Self {
+        let name_entries: usize = (name.len() + NAME_CHARS_PER_ENTRY - 1) / NAME_CHARS_PER_ENTRY;
+        let mut set = Self {
+            raw: vec![0; (name_entries + 2) * DENTRY_SIZE],
+            pos: 0,
+        };
+
+        set.raw[0] = ENTRY_TYPE_FILE;
+        set.raw[Self::SECONDARY_COUNT] = (name_entries + 1) as u8;
+        set.write_u16(Self::FILE_ATTRIBUTES, attributes.bits());
+        let now = TimeSpec::now();
+        set.set_create_time(&now);
+        set.set_modify_time(&now);
+        set.set_access_time(&now);
+
+        set.raw[DENTRY_SIZE] = ENTRY_TYPE_STREAM;
+        set.raw[Self::STREAM_FLAGS] = SecondaryFlags::ALLOCATION_POSSIBLE.bits();
+        set.set_name(name, name_hash);
+        set.update_checksum();
+        return set;
+    }
+
+    /// @brief 创建一个新的目录项集合，除了文件名之外，其余信息都与当前的集合相同
+    pub fn renamed(&self, name: &[u16], name_hash: u16) -> Self {
+        let old_name_entries: usize = self.name_entries();
+        let name_entries: usize = (name.len() + NAME_CHARS_PER_ENTRY - 1) / NAME_CHARS_PER_ENTRY;
+
+        // File和Stream Extension目录项保持不变，替换文件名，并保留文件名之后的其他次要目录项
+        let mut raw: Vec<u8> = self.raw[..2 * DENTRY_SIZE].to_vec();
+        raw.resize((name_entries + 2) * DENTRY_SIZE, 0);
+        raw.extend_from_slice(&self.raw[(old_name_entries + 2) * DENTRY_SIZE..]);
+
+        let mut set = Self { raw, pos: 0 };
+        set.raw[Self::SECONDARY_COUNT] = (set.num_entries() - 1) as u8;
+        set.set_name(name, name_hash);
+        set.update_checksum();
+        return set;
+    }
+
+    /// @brief 设置文件名（File Name目录项的空间需要已经分配好）
+    fn set_name(&mut self, name: &[u16], name_hash: u16) {
+        self.raw[Self::STREAM_NAME_LENGTH] = name.len() as u8;
+        self.write_u16(Self::STREAM_NAME_HASH, name_hash);
+        for (i, chunk) in name.chunks(NAME_CHARS_PER_ENTRY).enumerate() {
+            let base: usize = (i + 2) * DENTRY_SIZE;
+            self.raw[base] = ENTRY_TYPE_NAME;
+            self.raw[base + 1] = 0;
+            for (j, c) in chunk.iter().enumerate() {
+                self.write_u16(base + 2 + j * 2, *c);
+            }
+        }
+    }
+
+    /// @brief 计算目录项集合的校验和（跳过File目录项中的校验和字段本身）
+    fn checksum(raw: &[u8]) -> u16 {
+        let mut checksum: u16 = 0;
+        for (i, byte) in raw.iter().enumerate() {
+            if i == Self::SET_CHECKSUM || i == Self::SET_CHECKSUM + 1 {
+                continue;
+            }
+            checksum = checksum.rotate_right(1).wrapping_add(*byte as u16);
+        }
+        return checksum;
+    }
+
+    /// @brief 修改目录项集合之后，重新计算校验和
+    pub fn update_checksum(&mut self) {
+        let checksum: u16 = Self::checksum(&self.raw);
+        self.write_u16(Self::SET_CHECKSUM, checksum);
+    }
+
+    /// @brief 目录项集合的原始数据
+    #[inline]
+    pub fn raw(&self) -> &[u8] {
+        return &self.raw;
+    }
+
+    /// @brief 把目录项集合标记为已删除，返回标记之后的原始数据
+    pub fn deleted_raw(&self) -> Vec<u8> {
+        let mut raw: Vec<u8> = self.raw.clone();
+        for entry in raw.chunks_exact_mut(DENTRY_SIZE) {
+            entry[0] &= !ENTRY_IN_USE;
+        }
+        return raw;
+    }
+
+    /// @brief 目录项集合中的目录项数量
+    #[inline]
+    pub fn num_entries(&self) -> usize {
+        return self.raw.len() / DENTRY_SIZE;
+    }
+
+    /// @brief File Name目录项的数量
+    #[inline]
+    fn name_entries(&self) -> usize {
+        return (self.name_length() + NAME_CHARS_PER_ENTRY - 1) / NAME_CHARS_PER_ENTRY;
+    }
+
+    #[inline]
+    fn name_length(&self) -> usize {
+        return self.raw[Self::STREAM_NAME_LENGTH] as usize;
+    }
+
+    /// @brief 文件名的哈希值
+    #[inline]
+    pub fn name_hash(&self) -> u16 {
+        return self.read_u16(Self::STREAM_NAME_HASH);
+    }
+
+    /// @brief UTF-16编码的文件名
+    pub fn name_utf16(&self) -> Vec<u16> {
+        let len: usize = self.name_length();
+        let mut name: Vec<u16> = Vec::with_capacity(len);
+        for i in 0..len {
+            let base: usize =
+                (i / NAME_CHARS_PER_ENTRY + 2) * DENTRY_SIZE + 2 + (i % NAME_CHARS_PER_ENTRY) * 2;
+            name.push(self.read_u16(base));
+        }
+        return name;
+    }
+
+    /// @brief 文件名
+    pub fn name(&self) -> String {
+        return String::from_utf16_lossy(&self.name_utf16());
+    }
+
+    #[inline]
+    pub fn attributes(&self) -> FileAttributes {
+        return FileAttributes::from_bits_truncate(self.read_u16(Self::FILE_ATTRIBUTES));
+    }
+
+    #[inline]
+    pub fn is_dir(&self) -> bool {
+        return self.attributes().contains(FileAttributes::DIRECTORY);
+    }
+
+    #[inline]
+    pub fn first_cluster(&self) -> u32 {
+        return self.read_u32(Self::STREAM_FIRST_CLUSTER);
+    }
+
+    #[inline]
+    pub fn set_first_cluster(&mut self, cluster: u32) {
+        self.write_u32(Self::STREAM_FIRST_CLUSTER, cluster);
+    }
+
+    #[inline]
+    pub fn no_fat_chain(&self) -> bool {
+        return SecondaryFlags::from_bits_truncate(self.raw[Self::STREAM_FLAGS])
+            .contains(SecondaryFlags::NO_FAT_CHAIN);
+    }
+
+    pub fn set_no_fat_chain(&mut self, no_fat_chain: bool) {
+        let mut flags = SecondaryFlags::from_bits_truncate(self.raw[Self::STREAM_FLAGS]);
+        flags.set(SecondaryFlags::NO_FAT_CHAIN, no_fat_chain);
+        // 保留本驱动程序不认识的位
+        self.raw[Self::STREAM_FLAGS] =
+            (self.raw[Self::STREAM_FLAGS] & !SecondaryFlags::all().bits()) | flags.bits();
+    }
+
+    /// @brief 文件的大小（单位：字节）
+    #[inline]
+    pub fn data_length(&self) -> u64 {
+        return self.read_u64(Self::STREAM_DATA_LENGTH);
+    }
+
+    #[inline]
+    pub fn set_data_length(&mut self, len: u64) {
+        self.write_u64(Self::STREAM_DATA_LENGTH, len);
+    }
+
+    /// @brief 已经写入过数据的部分的长度。这之后的数据都被视为0
+    #[inline]
+    pub fn valid_data_length(&self) -> u64 {
+        return self.read_u64(Self::STREAM_VALID_DATA_LENGTH);
+    }
+
+    #[inline]
+    pub fn set_valid_data_length(&mut self, len: u64) {
+        self.write_u64(Self::STREAM_VALID_DATA_LENGTH, len);
+    }
+
+    /// @brief 获取文件的创建时间
+    pub fn create_time(&self) -> TimeSpec {
+        return exfat_time_to_timespec(
+            self.read_u32(Self::CREATE_TIMESTAMP),
+            self.raw[Self::CREATE_10MS],
+            self.raw[Self::CREATE_UTC_OFFSET],
+        );
+    }
+
+    /// @brief 获取文件的最后修改时间
+    pub fn modify_time(&self) -> TimeSpec {
+        return exfat_time_to_timespec(
+            self.read_u32(Self::MODIFIED_TIMESTAMP),
+            self.raw[Self::MODIFIED_10MS],
+            self.raw[Self::MODIFIED_UTC_OFFSET],
+        );
+    }
+
+    /// @brief 获取文件的最后访问时间（精度为2秒）
+    pub fn access_time(&self) -> TimeSpec {
+        return exfat_time_to_timespec(
+            self.read_u32(Self::ACCESSED_TIMESTAMP),
+            0,
+            self.raw[Self::ACCESSED_UTC_OFFSET],
+        );
+    }
+
+    pub fn set_create_time(&mut self, ts: &TimeSpec) {
+        let (timestamp, ten_ms, utc_offset) = timespec_to_exfat_time(ts);
+        self.write_u32(Self::CREATE_TIMESTAMP, timestamp);
+        self.raw[Self::CREATE_10MS] = ten_ms;
+        self.raw[Self::CREATE_UTC_OFFSET] = utc_offset;
+    }
+
+    pub fn set_modify_time(&mut self, ts: &TimeSpec) {
+        let (timestamp, ten_ms, utc_offset) = timespec_to_exfat_time(ts);
+        self.write_u32(Self::MODIFIED_TIMESTAMP, timestamp);
+        self.raw[Self::MODIFIED_10MS] = ten_ms;
+        self.raw[Self::MODIFIED_UTC_OFFSET] = utc_offset;
+    }
+
+    /// @brief 设置文件的最后访问时间
+    ///
+    /// @return 目录项是否发生了变化（访问时间只精确到2秒，短时间内的多次访问不需要写回磁盘）
+    pub fn set_access_time(&mut self, ts: &TimeSpec) -> bool {
+        let (timestamp, _, utc_offset) = timespec_to_exfat_time(ts);
+        if self.read_u32(Self::ACCESSED_TIMESTAMP) == timestamp
+            && self.raw[Self::ACCESSED_UTC_OFFSET] == utc_offset
+        {
+            return false;
+        }
+        self.write_u32(Self::ACCESSED_TIMESTAMP, timestamp);
+        self.raw[Self::ACCESSED_UTC_OFFSET] = utc_offset;
+        return true;
+    }
+
+    #[inline]
+    fn read_u16(&self, off: usize) -> u16 {
+        return u16::from_le_bytes([self.raw[off], self.raw[off + 1]]);
+    }
+
+    #[inline]
+    fn read_u32(&self, off: usize) -> u32 {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&self.raw[off..off + 4]);
+        return u32::from_le_bytes(bytes);
+    }
+
+    #[inline]
+    fn read_u64(&self, off: usize) -> u64 {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&self.raw[off..off + 8]);
+        return u64::from_le_bytes(bytes);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, off: usize, value: u16) {
+        self.raw[off..off + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u32(&mut self, off: usize, value: u32) {
+        self.raw[off..off + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u64(&mut self, off: usize, value: u64) {
+        self.raw[off..off + 8].copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// @brief 目录中的一项
+#[derive(Debug, Clone)]
+pub enum ExFatDirEntry {
+    /// 文件或者文件夹
+    File(ExFatEntrySet),
+    /// 分配位图（只出现在根目录中）
+    Bitmap {
+        /// BitmapFlags的第0位：为0表示第一个位图，为1表示第二个位图（仅用于TexFAT）
+        index: u8,
+        first_cluster: u32,
+        data_length: u64,
+    },
+    /// 大写字母表（只出现在根目录中）
+    Upcase {
+        checksum: u32,
+        first_cluster: u32,
+        data_length: u64,
+    },
+}
+
+/// @brief 遍历目录数据中的目录项
+///
+/// 已经被删除的目录项、本驱动程序不认识的目录项会被跳过。损坏的目录项集合会被跳过，并输出警告
+#[derive(Debug)]
+pub struct ExFatDirIter<'a> {
+    data: &'a [u8],
+    /// 下一个目录项在data中的偏移量
+    pos: usize,
+}
+
+impl<'a> ExFatDirIter<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        return Self { data, pos: 0 };
+    }
+}
+
+impl<'a> Iterator for ExFatDirIter<'a> {
+    type Item = ExFatDirEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos + DENTRY_SIZE <= self.data.len() {
+            let pos: usize = self.pos;
+            let entry: &[u8] = &self.data[pos..pos + DENTRY_SIZE];
+            self.pos += DENTRY_SIZE;
+
+            match entry[0] {
+                ENTRY_TYPE_END => {
+                    self.pos = self.data.len();
+                    return None;
+                }
+                ENTRY_TYPE_FILE => match ExFatEntrySet::parse(self.data, pos) {
+                    Ok(set) => {
+                        self.pos = pos + set.raw().len();
+                        return Some(ExFatDirEntry::File(set));
+                    }
+                    Err(_) => {
+                        kwarn!("exFAT: skipping corrupted entry set at offset {}", pos);
+                    }
+                },
+                ENTRY_TYPE_BITMAP => {
+                    return Some(ExFatDirEntry::Bitmap {
+                        index: entry[1] & 1,
+                        first_cluster: u32::from_le_bytes([
+                            entry[20], entry[21], entry[22], entry[23],
+                        ]),
+                        data_length: u64::from_le_bytes([
+                            entry[24], entry[25], entry[26], entry[27], entry[28], entry[29],
+                            entry[30], entry[31],
+                        ]),
+                    });
+                }
+                ENTRY_TYPE_UPCASE => {
+                    return Some(ExFatDirEntry::Upcase {
+                        checksum: u32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]),
+                        first_cluster: u32::from_le_bytes([
+                            entry[20], entry[21], entry[22], entry[23],
+                        ]),
+                        data_length: u64::from_le_bytes([
+                            entry[24], entry[25], entry[26], entry[27], entry[28], entry[29],
+                            entry[30], entry[31],
+                        ]),
+                    });
+                }
+                // 已删除的目录项、卷标、GUID等，以及不属于任何集合的次要目录项
+                _ => {}
+            }
+        }
+        return None;
+    }
+}
+
+/// @brief 在目录的数据中，寻找能够容纳count个连续目录项的空闲位置
+///
+/// @return Some(偏移量) 找到了空闲位置
+/// @return None 目录中没有足够的空间，需要扩展目录
+pub fn find_free_slots(data: &[u8], count: usize) -> Option<usize> {
+    let mut run_start: usize = 0;
+    let mut run_len: usize = 0;
+    for (i, entry) in data.chunks_exact(DENTRY_SIZE).enumerate() {
+        if entry[0] & ENTRY_IN_USE != 0 {
+            run_len = 0;
+            continue;
+        }
+        if run_len == 0 {
+            run_start = i * DENTRY_SIZE;
+        }
+        run_len += 1;
+        if run_len == count {
+            return Some(run_start);
+        }
+    }
+    return None;
+}
+
+/// @brief 把文件名转换为UTF-16编码，并检查它是否合法
+///
+/// @return Err(ENAMETOOLONG) 文件名太长
+/// @return Err(EINVAL) 文件名为空，或者包含exFAT不允许的字符
+pub fn name_to_utf16(name: &str) -> Result<Vec<u16>, SystemError> {
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(SystemError::EINVAL);
+    }
+    let name: Vec<u16> = name.encode_utf16().collect();
+    if name.len() > EXFAT_MAX_NAMELEN {
+        return Err(SystemError::ENAMETOOLONG);
+    }
+    for c in name.iter() {
+        let invalid = *c < 0x20
+            || matches!(
+                char::from_u32(*c as u32),
+                Some('"' | '*' | '/' | ':' | '<' | '>' | '?' | '\\' | '|')
+            );
+        if invalid {
+            return Err(SystemError::EINVAL);
+        }
+    }
+    return Ok(name);
+}
+
+/// @brief 把exFAT的时间戳转换为UTC时间
+///
+/// @param timestamp 日期和时间（高16位与FAT的日期格式相同，低16位与FAT的时间格式相同）
+/// @param ten_ms 10毫秒的个数（0-199）
+/// @param utc_offset 最高位为1时，低7位是以15分钟为单位的、有符号的时区偏移量
+fn exfat_time_to_timespec(timestamp: u32, ten_ms: u8, utc_offset: u8) -> TimeSpec {
+    let mut ts: TimeSpec = fat_time_to_timespec((timestamp >> 16) as u16, timestamp as u16, ten_ms);
+    if utc_offset & 0x80 != 0 {
+        // 把7位的有符号数扩展为8位
+        let offset: i64 = (((utc_offset << 1) as i8) >> 1) as i64;
+        ts.tv_sec -= offset * 15 * 60;
+    }
+    return ts;
+}
+
+/// @brief 把UTC时间转换为exFAT的时间戳
+///
+/// @return (日期和时间, 10毫秒的个数, 时区偏移量)
+fn timespec_to_exfat_time(ts: &TimeSpec) -> (u32, u8, u8) {
+    let (date, time, ten_ms) = timespec_to_fat_time(ts);
+    // 总是以UTC时间存储，时区偏移量为0
+    return (((date as u32) << 16) | time as u32, ten_ms, 0x80);
+}
@@ -0,0 +1,1557 @@
+#![allow(dead_code)]
+use core::{any::Any, cmp::min};
+
+use alloc::{
+    collections::BTreeMap,
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+
+use crate::{
+    arch::MMArch,
+    driver::base::block::{block_device::LBA_SIZE, disk_info::Partition},
+    filesystem::vfs::{
+        core::generate_inode_id,
+        file::{FileMode, FilePrivateData},
+        FileSystem, FileType, FsInfo, IndexNode, InodeId, Metadata, PollStatus,
+    },
+    kerror, kwarn,
+    libs::spinlock::{SpinLock, SpinLockGuard},
+    mm::{
+        page_cache::{CachedPage, PageCache, PageCacheBackend},
+        MemoryManagementArch,
+    },
+    syscall::SystemError,
+    time::TimeSpec,
+};
+
+use super::{
+    boot::{ExFatBootSector, VolumeFlags, VOLUME_FLAGS_OFFSET},
+    entry::{
+        find_free_slots, name_to_utf16, ExFatDirEntry, ExFatDirIter, ExFatEntrySet, FileAttributes,
+        DENTRY_SIZE, ENTRY_IN_USE, EXFAT_MAX_NAMELEN,
+    },
+    upcase::UpcaseTable,
+};
+
+/// exFAT文件系统的魔数（与Linux的EXFAT_SUPER_MAGIC相同）
+pub const EXFAT_MAGIC: u64 = 0x2011_bab0;
+/// 簇堆中的第一个簇的簇号
+const EXFAT_FIRST_CLUSTER: u32 = 2;
+/// FAT表中表示簇链结束的值
+const EXFAT_EOC: u32 = 0xffff_ffff;
+/// 清零簇时，每次写入的最大字节数
+const ZERO_CHUNK_SIZE: u64 = 1 << 20;
+
+/// @brief 簇链
+///
+/// exFAT的文件可以把NoFatChain标志置位，表示它的簇在簇堆中是连续的，此时FAT表中对应的表项没有意义。
+/// 否则，需要像FAT一样，沿着FAT表查找文件的每一个簇。
+#[derive(Debug, Clone, Default)]
+pub struct ExFatChain {
+    /// 第一个簇的簇号。为0表示簇链为空
+    pub first_cluster: u32,
+    /// 簇是否是连续的（不需要查询FAT表）
+    pub no_fat_chain: bool,
+    /// 从FAT表中读取到的簇号的缓存（仅在no_fat_chain为false时使用，按需加载）
+    clusters: Vec<u32>,
+}
+
+impl ExFatChain {
+    pub fn new(first_cluster: u32, no_fat_chain: bool) -> Self {
+        return Self {
+            first_cluster,
+            no_fat_chain,
+            clusters: Vec::new(),
+        };
+    }
+}
+
+/// @brief 分配位图（内存中的一份拷贝，修改时同时写回磁盘）
+#[derive(Debug, Default)]
+struct AllocationBitmap {
+    /// 位图的内容。第i位对应第i+2个簇，为1表示簇已经被使用
+    data: Vec<u8>,
+    /// 位图本身所在的簇链
+    chain: ExFatChain,
+    /// 空闲簇的数量
+    free_clusters: u64,
+    /// 下一次分配时，开始查找的位置（仅供加速查找）
+    next_free: u32,
+}
+
+impl AllocationBitmap {
+    #[inline]
+    fn is_used(&self, index: u32) -> bool {
+        return self.data[index as usize / 8] & (1 << (index % 8)) != 0;
+    }
+
+    #[inline]
+    fn set_used(&mut self, index: u32, used: bool) {
+        if used {
+            self.data[index as usize / 8] |= 1 << (index % 8);
+        } else {
+            self.data[index as usize / 8] &= !(1 << (index % 8));
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ExFatFileSystem {
+    /// 当前文件系统所在的分区
+    pub partition: Arc<Partition>,
+    /// 引导扇区
+    pub boot: ExFatBootSector,
+    /// 大写字母表
+    upcase: UpcaseTable,
+    /// 分配位图
+    bitmap: SpinLock<AllocationBitmap>,
+    /// 文件系统的根inode
+    root_inode: Arc<LockedExFatInode>,
+}
+
+/// exFAT文件系统的Inode
+#[derive(Debug)]
+pub struct LockedExFatInode(SpinLock<ExFatInode>);
+
+#[derive(Debug)]
+pub struct ExFatInode {
+    /// 指向父Inode的弱引用
+    parent: Weak<LockedExFatInode>,
+    /// 指向自身的弱引用
+    self_ref: Weak<LockedExFatInode>,
+    /// 子Inode的缓存。由于exFAT的文件名不区分大小写，key是经过大写字母表转换之后的文件名
+    children: BTreeMap<String, Arc<LockedExFatInode>>,
+    /// 当前inode的元数据
+    metadata: Metadata,
+    /// 指向inode所在的文件系统对象的指针
+    fs: Weak<ExFatFileSystem>,
+    /// 文件在父目录中的目录项集合。根目录没有目录项，为None
+    entry: Option<ExFatEntrySet>,
+    /// 目录项集合所在的目录（父目录）的簇链
+    parent_chain: ExFatChain,
+    /// 文件内容所在的簇链
+    chain: ExFatChain,
+    /// 根目录的大小（其他文件的大小记录在目录项中）
+    root_size: u64,
+    /// 文件的页缓存（文件夹没有页缓存）
+    page_cache: Option<Arc<PageCache>>,
+    /// 内存中的目录项（写入时间等）是否被修改过，需要在写回时刷入磁盘
+    entry_dirty: bool,
+    /// 文件的目录项是否已经从磁盘上删除（文件被unlink时仍被打开，簇要等到不再被使用时才回收）
+    unlinked: bool,
+}
+
+impl FileSystem for ExFatFileSystem {
+    fn root_inode(&self) -> Arc<dyn IndexNode> {
+        return self.root_inode.clone();
+    }
+
+    fn info(&self) -> FsInfo {
+        let free_clusters: u64 = self.bitmap.lock().free_clusters;
+        return FsInfo {
+            blk_dev_id: 0,
+            max_name_len: EXFAT_MAX_NAMELEN,
+            magic: EXFAT_MAGIC,
+            block_size: self.bytes_per_cluster() as usize,
+            total_blocks: self.boot.cluster_count as u64,
+            free_blocks: free_clusters,
+            avail_blocks: free_clusters,
+            // exFAT没有inode表，文件数量只受磁盘空间的限制
+            total_inodes: 0,
+            free_inodes: 0,
+        };
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl ExFatFileSystem {
+    pub fn new(partition: Arc<Partition>) -> Result<Arc<ExFatFileSystem>, SystemError> {
+        let boot: ExFatBootSector = ExFatBootSector::new(&partition)?;
+
+        let root_inode: Arc<LockedExFatInode> =
+            Arc::new(LockedExFatInode(SpinLock::new(ExFatInode {
+                parent: Weak::default(),
+                self_ref: Weak::default(),
+                children: BTreeMap::new(),
+                metadata: Metadata {
+                    dev_id: 0,
+                    inode_id: generate_inode_id(),
+                    disk_ino: None,
+                    size: 0,
+                    blk_size: boot.bytes_per_cluster() as usize,
+                    blocks: 0,
+                    atime: TimeSpec::default(),
+                    mtime: TimeSpec::default(),
+                    ctime: TimeSpec::default(),
+                    file_type: FileType::Dir,
+                    mode: 0o777,
+                    nlinks: 1,
+                    uid: 0,
+                    gid: 0,
+                    raw_dev: 0,
+                },
+                fs: Weak::default(),
+                entry: None,
+                parent_chain: ExFatChain::default(),
+                chain: ExFatChain::new(boot.first_cluster_of_root_directory, false),
+                root_size: 0,
+                page_cache: None,
+                entry_dirty: false,
+                unlinked: false,
+            })));
+
+        let mut fs = ExFatFileSystem {
+            partition,
+            boot,
+            upcase: UpcaseTable::ascii(),
+            bitmap: SpinLock::new(AllocationBitmap::default()),
+            root_inode,
+        };
+        fs.load_root_dir()?;
+        fs.mark_dirty()?;
+
+        let result: Arc<ExFatFileSystem> = Arc::new(fs);
+
+        // 对root inode加锁，并继续完成初始化工作
+        let mut root_guard: SpinLockGuard<ExFatInode> = result.root_inode.0.lock();
+        root_guard.parent = Arc::downgrade(&result.root_inode);
+        root_guard.self_ref = Arc::downgrade(&result.root_inode);
+        root_guard.fs = Arc::downgrade(&result);
+        root_guard.update_metadata(&result);
+        drop(root_guard);
+
+        return Ok(result);
+    }
+
+    /// @brief 读取根目录，加载其中的分配位图和大写字母表
+    fn load_root_dir(&mut self) -> Result<(), SystemError> {
+        let mut root_chain: ExFatChain =
+            ExFatChain::new(self.boot.first_cluster_of_root_directory, false);
+        let root_size: u64 = self.chain_len(&mut root_chain)? * self.bytes_per_cluster();
+        let mut data: Vec<u8> = vec![0; root_size as usize];
+        self.read_chain(&mut root_chain, 0, &mut data)?;
+
+        let mut bitmap: Option<(u32, u64)> = None;
+        let mut upcase: Option<(u32, u32, u64)> = None;
+        for entry in ExFatDirIter::new(&data) {
+            match entry {
+                // 第二个分配位图只在TexFAT中使用
+                ExFatDirEntry::Bitmap {
+                    index: 0,
+                    first_cluster,
+                    data_length,
+                } if bitmap.is_none() => bitmap = Some((first_cluster, data_length)),
+                ExFatDirEntry::Upcase {
+                    checksum,
+                    first_cluster,
+                    data_length,
+                } if upcase.is_none() => upcase = Some((checksum, first_cluster, data_length)),
+                _ => {}
+            }
+        }
+
+        // 加载分配位图
+        let (first_cluster, data_length) = bitmap.ok_or_else(|| {
+            kerror!("exFAT: allocation bitmap not found in the root directory");
+            SystemError::EINVAL
+        })?;
+        let bitmap_len: u64 = (self.boot.cluster_count as u64 + 7) / 8;
+        if data_length < bitmap_len || !self.is_valid_cluster(first_cluster) {
+            kerror!("exFAT: invalid allocation bitmap");
+            return Err(SystemError::EINVAL);
+        }
+        let mut chain: ExFatChain = ExFatChain::new(first_cluster, false);
+        let mut data: Vec<u8> = vec![0; bitmap_len as usize];
+        self.read_chain(&mut chain, 0, &mut data)?;
+        let mut bitmap = AllocationBitmap {
+            data,
+            chain,
+            free_clusters: 0,
+            next_free: 0,
+        };
+        bitmap.free_clusters = (0..self.boot.cluster_count)
+            .filter(|i| !bitmap.is_used(*i))
+            .count() as u64;
+        self.bitmap = SpinLock::new(bitmap);
+
+        // 加载大写字母表。表缺失或者损坏时，只按照ASCII字符处理大小写
+        match upcase {
+            Some((checksum, first_cluster, data_length))
+                if self.is_valid_cluster(first_cluster) =>
+            {
+                let mut chain: ExFatChain = ExFatChain::new(first_cluster, false);
+                let mut data: Vec<u8> = vec![0; data_length as usize];
+                self.read_chain(&mut chain, 0, &mut data)?;
+                match UpcaseTable::new(&data, checksum) {
+                    Ok(table) => self.upcase = table,
+                    Err(_) => kwarn!("exFAT: invalid up-case table, falling back to ASCII"),
+                }
+            }
+            _ => kwarn!("exFAT: up-case table not found, falling back to ASCII"),
+        }
+
+        self.root_inode.0.lock().root_size = root_size;
+        return Ok(());
+    }
+
+    /// @brief 把卷标记为正在使用。如果系统在卸载之前崩溃，下一次挂载时能够发现这一点
+    fn mark_dirty(&self) -> Result<(), SystemError> {
+        if self.boot.volume_flags.contains(VolumeFlags::VOLUME_DIRTY) {
+            kwarn!("exFAT: volume was not cleanly unmounted, consider running fsck.exfat on it");
+            return Ok(());
+        }
+        let flags: VolumeFlags = self.boot.volume_flags | VolumeFlags::VOLUME_DIRTY;
+        self.write_bytes(VOLUME_FLAGS_OFFSET, &flags.bits().to_le_bytes())?;
+        return Ok(());
+    }
+
+    /// @brief 执行文件系统卸载前的准备工作：清除卷的dirty标志，并把数据刷入磁盘
+    ///
+    /// 如果挂载之前卷就已经是dirty的，那么保留这个标志，留给fsck.exfat检查
+    pub fn umount(&mut self) -> Result<(), SystemError> {
+        if !self.boot.volume_flags.contains(VolumeFlags::VOLUME_DIRTY) {
+            self.write_bytes(
+                VOLUME_FLAGS_OFFSET,
+                &self.boot.volume_flags.bits().to_le_bytes(),
+            )?;
+        }
+        self.partition.disk().sync()?;
+        return Ok(());
+    }
+
+    /// @brief 计算每个簇有多少个字节
+    #[inline]
+    pub fn bytes_per_cluster(&self) -> u64 {
+        return self.boot.bytes_per_cluster();
+    }
+
+    /// @brief 判断簇号是否处于簇堆的范围内
+    #[inline]
+    pub fn is_valid_cluster(&self, cluster: u32) -> bool {
+        return cluster >= EXFAT_FIRST_CLUSTER
+            && cluster - EXFAT_FIRST_CLUSTER < self.boot.cluster_count;
+    }
+
+    /// @brief 获取簇在分区内的字节偏移量
+    #[inline]
+    pub fn cluster_bytes_offset(&self, cluster: u32) -> u64 {
+        return self.boot.cluster_heap_bytes_offset()
+            + (cluster - EXFAT_FIRST_CLUSTER) as u64 * self.bytes_per_cluster();
+    }
+
+    /// @brief 从分区内的指定字节偏移量开始读取数据
+    ///
+    /// 起始位置和长度不必与磁盘的逻辑块对齐
+    pub fn read_bytes(&self, offset: u64, buf: &mut [u8]) -> Result<(), SystemError> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let end: u64 = offset + buf.len() as u64;
+        let lba_start: u64 = offset / LBA_SIZE as u64;
+        let lba_end: u64 = (end + LBA_SIZE as u64 - 1) / LBA_SIZE as u64;
+        let count: usize = (lba_end - lba_start) as usize;
+        let lba: usize = (self.partition.lba_start + lba_start) as usize;
+
+        if offset % LBA_SIZE as u64 == 0 && end % LBA_SIZE as u64 == 0 {
+            self.partition.disk().read_at(lba, count, buf)?;
+        } else {
+            let mut tmp: Vec<u8> = vec![0; count * LBA_SIZE];
+            self.partition.disk().read_at(lba, count, &mut tmp)?;
+            let in_block_offset: usize = (offset % LBA_SIZE as u64) as usize;
+            buf.copy_from_slice(&tmp[in_block_offset..in_block_offset + buf.len()]);
+        }
+        return Ok(());
+    }
+
+    /// @brief 从分区内的指定字节偏移量开始写入数据
+    ///
+    /// 起始位置和长度不必与磁盘的逻辑块对齐。不完整的逻辑块会先被读出，修改之后再写回
+    pub fn write_bytes(&self, offset: u64, buf: &[u8]) -> Result<(), SystemError> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let end: u64 = offset + buf.len() as u64;
+        let lba_start: u64 = offset / LBA_SIZE as u64;
+        let lba_end: u64 = (end + LBA_SIZE as u64 - 1) / LBA_SIZE as u64;
+        let count: usize = (lba_end - lba_start) as usize;
+        let lba: usize = (self.partition.lba_start + lba_start) as usize;
+
+        if offset % LBA_SIZE as u64 == 0 && end % LBA_SIZE as u64 == 0 {
+            self.partition.disk().write_at(lba, count, buf)?;
+        } else {
+            let mut tmp: Vec<u8> = vec![0; count * LBA_SIZE];
+            // 只有首尾两个逻辑块可能是不完整的
+            self.partition
+                .disk()
+                .read_at(lba, 1, &mut tmp[..LBA_SIZE])?;
+            if count > 1 {
+                self.partition.disk().read_at(
+                    lba + count - 1,
+                    1,
+                    &mut tmp[(count - 1) * LBA_SIZE..],
+                )?;
+            }
+            let in_block_offset: usize = (offset % LBA_SIZE as u64) as usize;
+            tmp[in_block_offset..in_block_offset + buf.len()].copy_from_slice(buf);
+            self.partition.disk().write_at(lba, count, &tmp)?;
+        }
+        return Ok(());
+    }
+
+    /// @brief 读取簇在FAT表中的表项
+    pub fn get_fat_entry(&self, cluster: u32) -> Result<u32, SystemError> {
+        let mut buf = [0u8; 4];
+        self.read_bytes(
+            self.boot.active_fat_bytes_offset() + cluster as u64 * 4,
+            &mut buf,
+        )?;
+        return Ok(u32::from_le_bytes(buf));
+    }
+
+    /// @brief 设置簇在FAT表中的表项
+    pub fn set_fat_entry(&self, cluster: u32, value: u32) -> Result<(), SystemError> {
+        return self.write_bytes(
+            self.boot.active_fat_bytes_offset() + cluster as u64 * 4,
+            &value.to_le_bytes(),
+        );
+    }
+
+    /// @brief 获取簇链中的第index个簇（从0开始）
+    ///
+    /// @return Err(EIO) 簇链比index更短，或者簇链已经损坏
+    pub fn chain_cluster(&self, chain: &mut ExFatChain, index: u64) -> Result<u32, SystemError> {
+        if !self.is_valid_cluster(chain.first_cluster) {
+            return Err(SystemError::EIO);
+        }
+        if chain.no_fat_chain {
+            let cluster: u64 = chain.first_cluster as u64 + index;
+            if cluster > u32::MAX as u64 || !self.is_valid_cluster(cluster as u32) {
+                return Err(SystemError::EIO);
+            }
+            return Ok(cluster as u32);
+        }
+
+        if chain.clusters.is_empty() {
+            chain.clusters.push(chain.first_cluster);
+        }
+        while chain.clusters.len() as u64 <= index {
+            let last: u32 = *chain.clusters.last().unwrap();
+            let next: u32 = self.get_fat_entry(last)?;
+            // 簇链的长度不可能超过簇的总数，否则说明簇链成环了
+            if !self.is_valid_cluster(next)
+                || chain.clusters.len() >= self.boot.cluster_count as usize
+            {
+                kerror!(
+                    "exFAT: broken cluster chain starting at {}: cluster {} -> {:#x}",
+                    chain.first_cluster,
+                    last,
+                    next
+                );
+                return Err(SystemError::EIO);
+            }
+            chain.clusters.push(next);
+        }
+        return Ok(chain.clusters[index as usize]);
+    }
+
+    /// @brief 沿着FAT表计算簇链的长度（用于没有记录大小的根目录、分配位图等）
+    fn chain_len(&self, chain: &mut ExFatChain) -> Result<u64, SystemError> {
+        let mut len: u64 = 1;
+        loop {
+            let last: u32 = self.chain_cluster(chain, len - 1)?;
+            if self.get_fat_entry(last)? == EXFAT_EOC {
+                return Ok(len);
+            }
+            len += 1;
+        }
+    }
+
+    /// @brief 从簇链的指定偏移量开始读取数据
+    pub fn read_chain(
+        &self,
+        chain: &mut ExFatChain,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Result<(), SystemError> {
+        let mut done: usize = 0;
+        while done < buf.len() {
+            let (disk_offset, len) =
+                self.chain_extent(chain, offset + done as u64, buf.len() - done)?;
+            self.read_bytes(disk_offset, &mut buf[done..done + len])?;
+            done += len;
+        }
+        return Ok(());
+    }
+
+    /// @brief 从簇链的指定偏移量开始写入数据（簇链需要已经足够长）
+    pub fn write_chain(
+        &self,
+        chain: &mut ExFatChain,
+        offset: u64,
+        buf: &[u8],
+    ) -> Result<(), SystemError> {
+        let mut done: usize = 0;
+        while done < buf.len() {
+            let (disk_offset, len) =
+                self.chain_extent(chain, offset + done as u64, buf.len() - done)?;
+            self.write_bytes(disk_offset, &buf[done..done + len])?;
+            done += len;
+        }
+        return Ok(());
+    }
+
+    /// @brief 把簇链中的指定范围清零（簇链需要已经足够长）
+    fn zero_chain(&self, chain: &mut ExFatChain, offset: u64, len: u64) -> Result<(), SystemError> {
+        let zeros: Vec<u8> = vec![0; min(len, ZERO_CHUNK_SIZE) as usize];
+        let mut done: u64 = 0;
+        while done < len {
+            let count: u64 = min(len - done, zeros.len() as u64);
+            self.write_chain(chain, offset + done, &zeros[..count as usize])?;
+            done += count;
+        }
+        return Ok(());
+    }
+
+    /// @brief 找到簇链中，从offset开始的、在磁盘上连续的一段
+    ///
+    /// 物理上连续的簇会被合并，以减少磁盘请求的次数
+    ///
+    /// @return (这一段在分区内的字节偏移量, 这一段的长度)。长度不超过max_len
+    fn chain_extent(
+        &self,
+        chain: &mut ExFatChain,
+        offset: u64,
+        max_len: usize,
+    ) -> Result<(u64, usize), SystemError> {
+        let bytes_per_cluster: u64 = self.bytes_per_cluster();
+        let index: u64 = offset / bytes_per_cluster;
+        let in_cluster_offset: u64 = offset % bytes_per_cluster;
+        let first: u32 = self.chain_cluster(chain, index)?;
+
+        let mut len: u64 = min(bytes_per_cluster - in_cluster_offset, max_len as u64);
+        let mut next_index: u64 = index + 1;
+        while len < max_len as u64 {
+            if self.chain_cluster(chain, next_index)? as u64 != first as u64 + (next_index - index)
+            {
+                break;
+            }
+            len = min(len + bytes_per_cluster, max_len as u64);
+            next_index += 1;
+        }
+        return Ok((
+            self.cluster_bytes_offset(first) + in_cluster_offset,
+            len as usize,
+        ));
+    }
+
+    /// @brief 把簇链从cur个簇扩展到count个簇
+    ///
+    /// 新的簇会尽量紧跟在原有的簇之后，以保持NoFatChain。无法保持时，把簇链转换为FAT链
+    pub fn extend_chain(
+        &self,
+        chain: &mut ExFatChain,
+        cur: u64,
+        count: u64,
+    ) -> Result<(), SystemError> {
+        if count <= cur {
+            return Ok(());
+        }
+        let mut last: Option<u32> = match cur {
+            0 => None,
+            _ => Some(self.chain_cluster(chain, cur - 1)?),
+        };
+        let hint: u32 = last.map(|c| c + 1).unwrap_or(EXFAT_FIRST_CLUSTER);
+        let new_clusters: Vec<u32> = self.alloc_clusters(count - cur, hint)?;
+
+        let mut len: u64 = cur;
+        for cluster in new_clusters {
+            match last {
+                None => {
+                    chain.first_cluster = cluster;
+                    chain.no_fat_chain = true;
+                    chain.clusters.clear();
+                }
+                Some(l) if chain.no_fat_chain && cluster == l + 1 => {}
+                Some(l) => {
+                    if chain.no_fat_chain {
+                        self.convert_to_fat_chain(chain, len)?;
+                    }
+                    self.set_fat_entry(l, cluster)?;
+                    chain.clusters.push(cluster);
+                }
+            }
+            if !chain.no_fat_chain {
+                self.set_fat_entry(cluster, EXFAT_EOC)?;
+            }
+            last = Some(cluster);
+            len += 1;
+        }
+        return Ok(());
+    }
+
+    /// @brief 把连续的簇链转换为FAT链（在FAT表中记录每一个簇）
+    fn convert_to_fat_chain(&self, chain: &mut ExFatChain, len: u64) -> Result<(), SystemError> {
+        let first: u32 = chain.first_cluster;
+        chain.clusters.clear();
+        for i in 0..len as u32 {
+            let next: u32 = if (i as u64) + 1 < len {
+                first + i + 1
+            } else {
+                EXFAT_EOC
+            };
+            self.set_fat_entry(first + i, next)?;
+            chain.clusters.push(first + i);
+        }
+        chain.no_fat_chain = false;
+        return Ok(());
+    }
+
+    /// @brief 把簇链从cur个簇截断为count个簇，并释放被截掉的簇
+    pub fn truncate_chain(
+        &self,
+        chain: &mut ExFatChain,
+        cur: u64,
+        count: u64,
+    ) -> Result<(), SystemError> {
+        if count >= cur {
+            return Ok(());
+        }
+        let mut freed: Vec<u32> = Vec::with_capacity((cur - count) as usize);
+        for i in count..cur {
+            freed.push(self.chain_cluster(chain, i)?);
+        }
+
+        if count == 0 {
+            *chain = ExFatChain::default();
+        } else if !chain.no_fat_chain {
+            let last: u32 = self.chain_cluster(chain, count - 1)?;
+            self.set_fat_entry(last, EXFAT_EOC)?;
+            chain.clusters.truncate(count as usize);
+        }
+        return self.free_clusters(&freed);
+    }
+
+    /// @brief 在分配位图中分配count个簇
+    ///
+    /// @param hint 从这个簇开始查找空闲的簇
+    ///
+    /// @return Ok(Vec<u32>) 分配到的簇（按照簇号递增的顺序，查找到末尾之后会从头开始）
+    /// @return Err(ENOSPC) 没有足够的空闲簇
+    fn alloc_clusters(&self, count: u64, hint: u32) -> Result<Vec<u32>, SystemError> {
+        let mut bitmap: SpinLockGuard<AllocationBitmap> = self.bitmap.lock();
+        if bitmap.free_clusters < count {
+            return Err(SystemError::ENOSPC);
+        }
+
+        let total: u32 = self.boot.cluster_count;
+        let mut index: u32 = if self.is_valid_cluster(hint) {
+            hint - EXFAT_FIRST_CLUSTER
+        } else {
+            bitmap.next_free % total
+        };
+        let mut result: Vec<u32> = Vec::with_capacity(count as usize);
+        let (mut lo, mut hi): (u32, u32) = (u32::MAX, 0);
+        while (result.len() as u64) < count {
+            // 跳过已经全部被使用的字节
+            if index % 8 == 0 && index + 8 <= total && bitmap.data[index as usize / 8] == 0xff {
+                index = (index + 8) % total;
+                continue;
+            }
+            if !bitmap.is_used(index) {
+                bitmap.set_used(index, true);
+                result.push(index + EXFAT_FIRST_CLUSTER);
+                lo = lo.min(index);
+                hi = hi.max(index);
+            }
+            index = (index + 1) % total;
+        }
+        bitmap.free_clusters -= count;
+        bitmap.next_free = index;
+
+        self.flush_bitmap(&mut bitmap, lo / 8, hi / 8 + 1)?;
+        return Ok(result);
+    }
+
+    /// @brief 在分配位图中释放簇
+    fn free_clusters(&self, clusters: &[u32]) -> Result<(), SystemError> {
+        if clusters.is_empty() {
+            return Ok(());
+        }
+        let mut bitmap: SpinLockGuard<AllocationBitmap> = self.bitmap.lock();
+        let (mut lo, mut hi): (u32, u32) = (u32::MAX, 0);
+        for cluster in clusters {
+            let index: u32 = cluster - EXFAT_FIRST_CLUSTER;
+            if bitmap.is_used(index) {
+                bitmap.set_used(index, false);
+                bitmap.free_clusters += 1;
+            }
+            lo = lo.min(index);
+            hi = hi.max(index);
+        }
+        return self.flush_bitmap(&mut bitmap, lo / 8, hi / 8 + 1);
+    }
+
+    /// @brief 把分配位图中[start, end)范围内的字节写回磁盘
+    fn flush_bitmap(
+        &self,
+        bitmap: &mut AllocationBitmap,
+        start: u32,
+        end: u32,
+    ) -> Result<(), SystemError> {
+        let AllocationBitmap { data, chain, .. } = bitmap;
+        return self.write_chain(chain, start as u64, &data[start as usize..end as usize]);
+    }
+
+    /// @brief 获取文件名在inode缓存中的key（转换为大写）
+    fn name_key(&self, name: &[u16]) -> String {
+        return String::from_utf16_lossy(&self.upcase.upcase(name));
+    }
+}
+
+impl Drop for ExFatFileSystem {
+    fn drop(&mut self) {
+        let r = self.umount();
+        if r.is_err() {
+            kerror!(
+                "Umount exFAT filesystem failed: errno={:?}, FS detail:{self:?}",
+                r.unwrap_err()
+            );
+        }
+    }
+}
+
+impl ExFatInode {
+    /// @brief 文件的大小
+    #[inline]
+    fn size(&self) -> u64 {
+        return match &self.entry {
+            Some(e) => e.data_length(),
+            None => self.root_size,
+        };
+    }
+
+    /// @brief 已经写入过数据的部分的长度
+    #[inline]
+    fn valid_size(&self) -> u64 {
+        return match &self.entry {
+            Some(e) => e.valid_data_length(),
+            None => self.root_size,
+        };
+    }
+
+    /// @brief 设置文件的大小，以及已经写入过数据的部分的长度
+    fn set_size(&mut self, size: u64, valid_size: u64) {
+        match &mut self.entry {
+            Some(e) => {
+                e.set_data_length(size);
+                e.set_valid_data_length(valid_size);
+            }
+            None => self.root_size = size,
+        }
+    }
+
+    /// @brief 文件占用的簇的数量
+    #[inline]
+    fn num_clusters(&self, fs: &ExFatFileSystem) -> u64 {
+        let bytes_per_cluster: u64 = fs.bytes_per_cluster();
+        return (self.size() + bytes_per_cluster - 1) / bytes_per_cluster;
+    }
+
+    /// @brief 根据目录项，更新当前inode的元数据
+    fn update_metadata(&mut self, fs: &ExFatFileSystem) {
+        self.metadata.size = self.size() as i64;
+        self.metadata.blocks =
+            (self.num_clusters(fs) * fs.bytes_per_cluster() / LBA_SIZE as u64) as usize;
+        if let Some(e) = &self.entry {
+            self.metadata.atime = e.access_time();
+            self.metadata.mtime = e.modify_time();
+            // exFAT没有记录状态改变时间，因此用最后修改时间作为ctime
+            self.metadata.ctime = self.metadata.mtime;
+            self.metadata.mode = if e.attributes().contains(FileAttributes::READ_ONLY) {
+                0o555
+            } else {
+                0o777
+            };
+        }
+    }
+
+    /// @brief 把内存中的目录项集合写回磁盘
+    fn flush_entry(&mut self, fs: &ExFatFileSystem) -> Result<(), SystemError> {
+        // 目录项已经被删除，它原来所在的位置可能已经被其他文件使用了
+        if self.unlinked {
+            return Ok(());
+        }
+        let entry: &mut ExFatEntrySet = match &mut self.entry {
+            Some(e) => e,
+            // 根目录没有目录项
+            None => return Ok(()),
+        };
+        entry.set_first_cluster(self.chain.first_cluster);
+        entry.set_no_fat_chain(self.chain.no_fat_chain);
+        entry.update_checksum();
+        fs.write_chain(&mut self.parent_chain, entry.pos, entry.raw())?;
+        self.entry_dirty = false;
+        return Ok(());
+    }
+
+    /// @brief 从文件读取数据
+    ///
+    /// @return Ok(usize) 成功读取的字节数
+    fn read(
+        &mut self,
+        fs: &ExFatFileSystem,
+        buf: &mut [u8],
+        offset: u64,
+    ) -> Result<usize, SystemError> {
+        let size: u64 = self.size();
+        if offset >= size {
+            return Ok(0);
+        }
+        let len: usize = min(buf.len() as u64, size - offset) as usize;
+
+        // ValidDataLength之后的部分从来没有被写入过，读取的结果为0
+        let valid_size: u64 = self.valid_size();
+        let disk_len: usize = if offset < valid_size {
+            min(len as u64, valid_size - offset) as usize
+        } else {
+            0
+        };
+        fs.read_chain(&mut self.chain, offset, &mut buf[..disk_len])?;
+        buf[disk_len..len].fill(0);
+        return Ok(len);
+    }
+
+    /// @brief 向文件写入数据。如果写入的范围超出了文件的大小，会扩展文件
+    ///
+    /// @return Ok(usize) 成功写入的字节数
+    fn write(
+        &mut self,
+        fs: &ExFatFileSystem,
+        buf: &[u8],
+        offset: u64,
+    ) -> Result<usize, SystemError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let end: u64 = offset
+            .checked_add(buf.len() as u64)
+            .ok_or(SystemError::EFBIG)?;
+        let size: u64 = self.size();
+        let valid_size: u64 = self.valid_size();
+
+        if end > size {
+            let bytes_per_cluster: u64 = fs.bytes_per_cluster();
+            let count: u64 = (end + bytes_per_cluster - 1) / bytes_per_cluster;
+            fs.extend_chain(&mut self.chain, self.num_clusters(fs), count)?;
+        }
+        // 在ValidDataLength之后写入时，中间的部分需要先被清零
+        if offset > valid_size {
+            fs.zero_chain(&mut self.chain, valid_size, offset - valid_size)?;
+        }
+        fs.write_chain(&mut self.chain, offset, buf)?;
+
+        if end > size || end > valid_size {
+            self.set_size(size.max(end), valid_size.max(end));
+            self.flush_entry(fs)?;
+        }
+        return Ok(buf.len());
+    }
+
+    /// @brief 改变文件的大小
+    ///
+    /// 扩展文件时，只分配簇，不写入数据：新增的部分位于ValidDataLength之后，读取的结果为0
+    fn truncate(&mut self, fs: &ExFatFileSystem, len: u64) -> Result<(), SystemError> {
+        let size: u64 = self.size();
+        if len == size {
+            return Ok(());
+        }
+        let bytes_per_cluster: u64 = fs.bytes_per_cluster();
+        let count: u64 = (len + bytes_per_cluster - 1) / bytes_per_cluster;
+        let cur: u64 = self.num_clusters(fs);
+        if count > cur {
+            fs.extend_chain(&mut self.chain, cur, count)?;
+        } else {
+            fs.truncate_chain(&mut self.chain, cur, count)?;
+        }
+        let valid_size: u64 = min(self.valid_size(), len);
+        self.set_size(len, valid_size);
+        return self.flush_entry(fs);
+    }
+
+    /// @brief 把页缓存中的脏页写回磁盘
+    fn writeback_pages(&mut self) -> Result<(), SystemError> {
+        let page_cache: Arc<PageCache> = match &self.page_cache {
+            Some(pc) => pc.clone(),
+            None => return Ok(()),
+        };
+        let fs: Arc<ExFatFileSystem> = self.fs.upgrade().unwrap();
+
+        let file_size: usize = self.size() as usize;
+        page_cache.writeback(file_size, |index, data| {
+            self.write(&fs, data, (index * MMArch::PAGE_SIZE) as u64)?;
+            return Ok(());
+        })?;
+        // 只写入了页缓存的写操作，修改的写入时间还没有刷入磁盘
+        if self.entry_dirty {
+            self.flush_entry(&fs)?;
+        }
+        return Ok(());
+    }
+
+    /// @brief 读取目录中的所有数据
+    fn dir_data(&mut self, fs: &ExFatFileSystem) -> Result<Vec<u8>, SystemError> {
+        let mut data: Vec<u8> = vec![0; self.size() as usize];
+        fs.read_chain(&mut self.chain, 0, &mut data)?;
+        return Ok(data);
+    }
+
+    /// @brief 在目录中查找名为name的文件的目录项集合
+    fn find_entry(
+        &mut self,
+        fs: &ExFatFileSystem,
+        name: &[u16],
+    ) -> Result<ExFatEntrySet, SystemError> {
+        if self.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        let hash: u16 = fs.upcase.name_hash(name);
+        let data: Vec<u8> = self.dir_data(fs)?;
+        for entry in ExFatDirIter::new(&data) {
+            if let ExFatDirEntry::File(set) = entry {
+                // 先比较哈希值，避免逐个字符地比较文件名
+                if set.name_hash() == hash && fs.upcase.name_eq(&set.name_utf16(), name) {
+                    return Ok(set);
+                }
+            }
+        }
+        return Err(SystemError::ENOENT);
+    }
+
+    /// @brief 把目录项集合写入当前目录。如果目录中没有足够的空间，会扩展目录
+    ///
+    /// @param set 要写入的目录项集合，写入之后，它的pos字段会被更新
+    fn add_entry(
+        &mut self,
+        fs: &ExFatFileSystem,
+        set: &mut ExFatEntrySet,
+    ) -> Result<(), SystemError> {
+        let data: Vec<u8> = self.dir_data(fs)?;
+        let pos: u64 = match find_free_slots(&data, set.num_entries()) {
+            Some(pos) => pos as u64,
+            None => {
+                // 在目录的末尾添加新的簇。新的簇必须被清零，以标记目录的结尾
+                let size: u64 = self.size();
+                let bytes_per_cluster: u64 = fs.bytes_per_cluster();
+                let needed: u64 =
+                    (set.raw().len() as u64 + bytes_per_cluster - 1) / bytes_per_cluster;
+                let cur: u64 = self.num_clusters(fs);
+                fs.extend_chain(&mut self.chain, cur, cur + needed)?;
+                fs.zero_chain(&mut self.chain, size, needed * bytes_per_cluster)?;
+                let new_size: u64 = size + needed * bytes_per_cluster;
+                self.set_size(new_size, new_size);
+                self.flush_entry(fs)?;
+                self.metadata.size = new_size as i64;
+
+                // 目录的末尾可能有一些空闲的目录项，新的集合可以从那里开始
+                let mut pos: u64 = size;
+                while pos >= DENTRY_SIZE as u64
+                    && data[pos as usize - DENTRY_SIZE] & ENTRY_IN_USE == 0
+                {
+                    pos -= DENTRY_SIZE as u64;
+                }
+                pos
+            }
+        };
+
+        set.pos = pos;
+        set.update_checksum();
+        return fs.write_chain(&mut self.chain, pos, set.raw());
+    }
+
+    /// @brief 把目录项集合从当前目录中删除
+    fn remove_entry(
+        &mut self,
+        fs: &ExFatFileSystem,
+        set: &ExFatEntrySet,
+    ) -> Result<(), SystemError> {
+        return fs.write_chain(&mut self.chain, set.pos, &set.deleted_raw());
+    }
+
+    /// @brief 查找名为name的子inode（如果不在缓存中，会从磁盘读取，并加入缓存）
+    fn find(&mut self, name: &str) -> Result<Arc<LockedExFatInode>, SystemError> {
+        if self.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        let fs: Arc<ExFatFileSystem> = self.fs.upgrade().unwrap();
+        let uname: Vec<u16> = name_to_utf16(name).map_err(|e| match e {
+            SystemError::EINVAL => SystemError::ENOENT,
+            e => e,
+        })?;
+        let key: String = fs.name_key(&uname);
+        if let Some(inode) = self.children.get(&key) {
+            return Ok(inode.clone());
+        }
+
+        let set: ExFatEntrySet = self.find_entry(&fs, &uname)?;
+        let inode: Arc<LockedExFatInode> =
+            LockedExFatInode::new(&fs, self.self_ref.clone(), set, self.chain.clone());
+        self.children.insert(key, inode.clone());
+        return Ok(inode);
+    }
+
+    /// @brief 判断目录是否为空
+    fn is_empty_dir(&mut self, fs: &ExFatFileSystem) -> Result<bool, SystemError> {
+        let data: Vec<u8> = self.dir_data(fs)?;
+        return Ok(!ExFatDirIter::new(&data).any(|e| matches!(e, ExFatDirEntry::File(_))));
+    }
+}
+
+impl Drop for ExFatInode {
+    fn drop(&mut self) {
+        // 已经被删除的文件不再被任何人使用，此时才回收它的簇
+        if self.unlinked && self.chain.first_cluster != 0 {
+            if let Some(fs) = self.fs.upgrade() {
+                let cur: u64 = self.num_clusters(&fs);
+                if let Err(e) = fs.truncate_chain(&mut self.chain, cur, 0) {
+                    kerror!(
+                        "exFAT: failed to release clusters of unlinked file: {:?}",
+                        e
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl LockedExFatInode {
+    /// @brief 根据目录项集合，创建一个新的inode
+    ///
+    /// @param parent 父目录的inode
+    /// @param entry 文件在父目录中的目录项集合
+    /// @param parent_chain 父目录的簇链
+    pub fn new(
+        fs: &Arc<ExFatFileSystem>,
+        parent: Weak<LockedExFatInode>,
+        entry: ExFatEntrySet,
+        parent_chain: ExFatChain,
+    ) -> Arc<LockedExFatInode> {
+        let file_type: FileType = if entry.is_dir() {
+            FileType::Dir
+        } else {
+            FileType::File
+        };
+        let chain: ExFatChain = ExFatChain::new(entry.first_cluster(), entry.no_fat_chain());
+
+        let inode: Arc<LockedExFatInode> = Arc::new(LockedExFatInode(SpinLock::new(ExFatInode {
+            parent,
+            self_ref: Weak::default(),
+            children: BTreeMap::new(),
+            metadata: Metadata {
+                dev_id: 0,
+                inode_id: generate_inode_id(),
+                disk_ino: None,
+                size: 0,
+                blk_size: fs.bytes_per_cluster() as usize,
+                blocks: 0,
+                atime: TimeSpec::default(),
+                mtime: TimeSpec::default(),
+                ctime: TimeSpec::default(),
+                file_type,
+                mode: 0o777,
+                nlinks: 1,
+                uid: 0,
+                gid: 0,
+                raw_dev: 0,
+            },
+            fs: Arc::downgrade(fs),
+            entry: Some(entry),
+            parent_chain,
+            chain,
+            root_size: 0,
+            page_cache: None,
+            entry_dirty: false,
+            unlinked: false,
+        })));
+
+        let mut guard: SpinLockGuard<ExFatInode> = inode.0.lock();
+        guard.self_ref = Arc::downgrade(&inode);
+        if file_type == FileType::File {
+            let backend: Weak<dyn PageCacheBackend> = Arc::downgrade(&inode) as _;
+            guard.page_cache = Some(PageCache::new(backend));
+        }
+        guard.update_metadata(fs);
+        drop(guard);
+
+        return inode;
+    }
+}
+
+impl PageCacheBackend for LockedExFatInode {
+    fn writeback(&self) -> Result<(), SystemError> {
+        return self.0.lock().writeback_pages();
+    }
+
+    fn try_writeback(&self) -> bool {
+        match self.0.try_lock() {
+            Ok(mut guard) => return guard.writeback_pages().is_ok(),
+            Err(_) => return false,
+        }
+    }
+}
+
+impl IndexNode for LockedExFatInode {
+    fn read_at(
+        &self,
+        offset: usize,
+        len: usize,
+        buf: &mut [u8],
+        _data: &mut FilePrivateData,
+    ) -> Result<usize, SystemError> {
+        let mut guard: SpinLockGuard<ExFatInode> = self.0.lock();
+        if guard.metadata.file_type == FileType::Dir {
+            return Err(SystemError::EISDIR);
+        }
+        let fs: Arc<ExFatFileSystem> = guard.fs.upgrade().unwrap();
+        let page_cache: Option<Arc<PageCache>> = guard.page_cache.clone();
+
+        let file_size: usize = guard.size() as usize;
+        let r = match page_cache {
+            // 通过页缓存读取
+            Some(pc) => pc.read(offset, &mut buf[0..len], file_size, |index, page| {
+                guard.read(&fs, page, (index * MMArch::PAGE_SIZE) as u64)
+            }),
+            None => guard.read(&fs, &mut buf[0..len], offset as u64),
+        };
+        // 访问时间只精确到2秒，只有在它变化时才需要写回目录项
+        if r.is_ok() {
+            let changed: bool = match &mut guard.entry {
+                Some(e) => e.set_access_time(&TimeSpec::now()),
+                None => false,
+            };
+            if changed {
+                guard.flush_entry(&fs)?;
+            }
+        }
+        guard.update_metadata(&fs);
+        return r;
+    }
+
+    fn write_at(
+        &self,
+        offset: usize,
+        len: usize,
+        buf: &[u8],
+        _data: &mut FilePrivateData,
+    ) -> Result<usize, SystemError> {
+        let mut guard: SpinLockGuard<ExFatInode> = self.0.lock();
+        if guard.metadata.file_type == FileType::Dir {
+            return Err(SystemError::EISDIR);
+        }
+        let fs: Arc<ExFatFileSystem> = guard.fs.upgrade().unwrap();
+        let page_cache: Option<Arc<PageCache>> = guard.page_cache.clone();
+
+        // 修改时间等到写回页缓存时再刷入磁盘
+        if let Some(e) = &mut guard.entry {
+            e.set_modify_time(&TimeSpec::now());
+        }
+        guard.entry_dirty = true;
+
+        let file_size: usize = guard.size() as usize;
+        let r = match page_cache {
+            // 数据只写入页缓存，等到sync时再写回
+            Some(pc) => {
+                let end: usize = offset.checked_add(len).ok_or(SystemError::EFBIG)?;
+                // 扩展文件长度的写操作，先分配簇、更新目录项中的文件大小。
+                // 新增的部分位于ValidDataLength之后，读取的结果为0，写回页缓存时才真正写入磁盘
+                let r = if end > file_size {
+                    guard.truncate(&fs, end as u64)
+                } else {
+                    Ok(())
+                };
+                r.and_then(|_| {
+                    pc.write(offset, &buf[0..len], file_size, |index, page| {
+                        guard.read(&fs, page, (index * MMArch::PAGE_SIZE) as u64)
+                    })
+                })
+            }
+            None => guard.write(&fs, &buf[0..len], offset as u64),
+        };
+        guard.update_metadata(&fs);
+        return r;
+    }
+
+    fn poll(&self) -> Result<PollStatus, SystemError> {
+        let inode: SpinLockGuard<ExFatInode> = self.0.lock();
+        if inode.metadata.file_type == FileType::Dir {
+            return Err(SystemError::EISDIR);
+        }
+        return Ok(PollStatus::READ | PollStatus::WRITE);
+    }
+
+    fn create(
+        &self,
+        name: &str,
+        file_type: FileType,
+        _mode: u32,
+    ) -> Result<Arc<dyn IndexNode>, SystemError> {
+        // exFAT不支持文件权限的功能，因此忽略mode参数
+        let mut guard: SpinLockGuard<ExFatInode> = self.0.lock();
+        if guard.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        let attributes: FileAttributes = match file_type {
+            FileType::File => FileAttributes::ARCHIVE,
+            FileType::Dir => FileAttributes::DIRECTORY,
+            FileType::SymLink => return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP),
+            _ => return Err(SystemError::EINVAL),
+        };
+        let fs: Arc<ExFatFileSystem> = guard.fs.upgrade().unwrap();
+        let uname: Vec<u16> = name_to_utf16(name)?;
+        match guard.find_entry(&fs, &uname) {
+            Ok(_) => return Err(SystemError::EEXIST),
+            Err(SystemError::ENOENT) => {}
+            Err(e) => return Err(e),
+        }
+
+        let mut set: ExFatEntrySet =
+            ExFatEntrySet::new(&uname, fs.upcase.name_hash(&uname), attributes);
+        let mut chain: ExFatChain = ExFatChain::default();
+        if file_type == FileType::Dir {
+            // 文件夹至少占用一个簇。exFAT的文件夹中没有"."和".."目录项，因此只需要清零
+            let bytes_per_cluster: u64 = fs.bytes_per_cluster();
+            fs.extend_chain(&mut chain, 0, 1)?;
+            fs.zero_chain(&mut chain, 0, bytes_per_cluster)?;
+            set.set_first_cluster(chain.first_cluster);
+            set.set_no_fat_chain(chain.no_fat_chain);
+            set.set_data_length(bytes_per_cluster);
+            set.set_valid_data_length(bytes_per_cluster);
+        }
+
+        if let Err(e) = guard.add_entry(&fs, &mut set) {
+            let _ = fs.truncate_chain(&mut chain, if chain.first_cluster != 0 { 1 } else { 0 }, 0);
+            return Err(e);
+        }
+
+        let inode: Arc<LockedExFatInode> =
+            LockedExFatInode::new(&fs, guard.self_ref.clone(), set, guard.chain.clone());
+        guard.children.insert(fs.name_key(&uname), inode.clone());
+        guard.update_metadata(&fs);
+        return Ok(inode);
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        return self.0.lock().fs.upgrade().unwrap();
+    }
+
+    fn as_any_ref(&self) -> &dyn core::any::Any {
+        return self;
+    }
+
+    fn metadata(&self) -> Result<Metadata, SystemError> {
+        return Ok(self.0.lock().metadata.clone());
+    }
+
+    fn set_metadata(&self, metadata: &Metadata) -> Result<(), SystemError> {
+        let mut guard: SpinLockGuard<ExFatInode> = self.0.lock();
+        // exFAT不支持权限位和文件所有者，只能修改时间信息
+        if metadata.mode != guard.metadata.mode
+            || metadata.uid != guard.metadata.uid
+            || metadata.gid != guard.metadata.gid
+        {
+            return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+        }
+
+        let fs: Arc<ExFatFileSystem> = guard.fs.upgrade().unwrap();
+        match &mut guard.entry {
+            Some(e) => {
+                e.set_access_time(&metadata.atime);
+                e.set_modify_time(&metadata.mtime);
+            }
+            // 根目录没有目录项，也就没有时间信息
+            None => return Ok(()),
+        }
+        guard.flush_entry(&fs)?;
+        guard.update_metadata(&fs);
+        return Ok(());
+    }
+
+    fn resize(&self, len: usize) -> Result<(), SystemError> {
+        let mut guard: SpinLockGuard<ExFatInode> = self.0.lock();
+        if guard.metadata.file_type == FileType::Dir {
+            return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+        }
+        let fs: Arc<ExFatFileSystem> = guard.fs.upgrade().unwrap();
+        // 先把页缓存中的脏页写回，再修改磁盘上的文件
+        guard.writeback_pages()?;
+        let old_size: usize = guard.size() as usize;
+
+        guard.truncate(&fs, len as u64)?;
+        if len < old_size {
+            if let Some(pc) = guard.page_cache.clone() {
+                pc.truncate(len);
+            }
+        }
+        if let Some(e) = &mut guard.entry {
+            e.set_modify_time(&TimeSpec::now());
+        }
+        guard.flush_entry(&fs)?;
+        guard.update_metadata(&fs);
+        return Ok(());
+    }
+
+    fn list(&self) -> Result<Vec<String>, SystemError> {
+        let mut guard: SpinLockGuard<ExFatInode> = self.0.lock();
+        if guard.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        let fs: Arc<ExFatFileSystem> = guard.fs.upgrade().unwrap();
+        let data: Vec<u8> = guard.dir_data(&fs)?;
+
+        // exFAT的文件夹中没有"."和".."目录项
+        let mut ret: Vec<String> = vec![String::from("."), String::from("..")];
+        for entry in ExFatDirIter::new(&data) {
+            if let ExFatDirEntry::File(set) = entry {
+                let uname: Vec<u16> = set.name_utf16();
+                ret.push(String::from_utf16_lossy(&uname));
+
+                // 生成inode缓存，存入B树
+                let key: String = fs.name_key(&uname);
+                if !guard.children.contains_key(&key) {
+                    let inode: Arc<LockedExFatInode> = LockedExFatInode::new(
+                        &fs,
+                        guard.self_ref.clone(),
+                        set,
+                        guard.chain.clone(),
+                    );
+                    guard.children.insert(key, inode);
+                }
+            }
+        }
+        return Ok(ret);
+    }
+
+    fn find(&self, name: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+        let mut guard: SpinLockGuard<ExFatInode> = self.0.lock();
+        match name {
+            "" | "." => return Ok(guard.self_ref.upgrade().ok_or(SystemError::ENOENT)?),
+            ".." => return Ok(guard.parent.upgrade().ok_or(SystemError::ENOENT)?),
+            name => return Ok(guard.find(name)?),
+        }
+    }
+
+    fn open(&self, _data: &mut FilePrivateData, _mode: &FileMode) -> Result<(), SystemError> {
+        return Ok(());
+    }
+
+    fn close(&self, _data: &mut FilePrivateData) -> Result<(), SystemError> {
+        return Ok(());
+    }
+
+    fn unlink(&self, name: &str) -> Result<(), SystemError> {
+        let mut guard: SpinLockGuard<ExFatInode> = self.0.lock();
+        let fs: Arc<ExFatFileSystem> = guard.fs.upgrade().unwrap();
+        let target: Arc<LockedExFatInode> = guard.find(name)?;
+        // 对目标inode上锁，以防更改
+        let mut target_guard: SpinLockGuard<ExFatInode> = target.0.lock();
+        if target_guard.metadata.file_type == FileType::Dir {
+            return Err(SystemError::EISDIR);
+        }
+
+        // 从磁盘删除目录项。文件可能仍被打开，因此簇要等到inode被释放时才回收（见ExFatInode的drop）
+        let set: ExFatEntrySet = target_guard.entry.clone().unwrap();
+        guard.remove_entry(&fs, &set)?;
+        guard.children.remove(&fs.name_key(&set.name_utf16()));
+
+        target_guard.unlinked = true;
+        target_guard.metadata.nlinks = 0;
+        target_guard.metadata.ctime = TimeSpec::now();
+        return Ok(());
+    }
+
+    fn rmdir(&self, name: &str) -> Result<(), SystemError> {
+        let mut guard: SpinLockGuard<ExFatInode> = self.0.lock();
+        let fs: Arc<ExFatFileSystem> = guard.fs.upgrade().unwrap();
+        let target: Arc<LockedExFatInode> = guard.find(name)?;
+        // 对目标inode上锁，以防更改
+        let mut target_guard: SpinLockGuard<ExFatInode> = target.0.lock();
+        if target_guard.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        if !target_guard.is_empty_dir(&fs)? {
+            return Err(SystemError::ENOTEMPTY);
+        }
+
+        let set: ExFatEntrySet = target_guard.entry.clone().unwrap();
+        guard.remove_entry(&fs, &set)?;
+        guard.children.remove(&fs.name_key(&set.name_utf16()));
+
+        target_guard.unlinked = true;
+        target_guard.metadata.nlinks = 0;
+        target_guard.metadata.ctime = TimeSpec::now();
+        return Ok(());
+    }
+
+    fn move_(
+        &self,
+        old_name: &str,
+        target: &Arc<dyn IndexNode>,
+        new_name: &str,
+    ) -> Result<(), SystemError> {
+        // 目标目录必须也是exFAT文件系统的inode
+        let target: &LockedExFatInode = target
+            .downcast_ref::<LockedExFatInode>()
+            .ok_or(SystemError::EXDEV)?;
+
+        if old_name == "." || old_name == ".." || new_name == "." || new_name == ".." {
+            return Err(SystemError::EBUSY);
+        }
+
+        // 跨目录移动时，由VFS保证同一时刻只有一个跨目录的移动操作，因此这里按照固定的顺序加锁不会死锁
+        let mut guard: SpinLockGuard<ExFatInode> = self.0.lock();
+        let mut target_guard: Option<SpinLockGuard<ExFatInode>> = match core::ptr::eq(self, target)
+        {
+            true => None,
+            false => Some(target.0.lock()),
+        };
+        if let Some(t) = &target_guard {
+            if !guard.fs.ptr_eq(&t.fs) {
+                return Err(SystemError::EXDEV);
+            }
+            if t.metadata.file_type != FileType::Dir {
+                return Err(SystemError::ENOTDIR);
+            }
+        }
+
+        let fs: Arc<ExFatFileSystem> = guard.fs.upgrade().unwrap();
+        let child: Arc<LockedExFatInode> = guard.find(old_name)?;
+        let mut child_guard: SpinLockGuard<ExFatInode> = child.0.lock();
+        let old_set: ExFatEntrySet = child_guard.entry.clone().unwrap();
+        let new_uname: Vec<u16> = name_to_utf16(new_name)?;
+        let mut new_set: ExFatEntrySet =
+            old_set.renamed(&new_uname, fs.upcase.name_hash(&new_uname));
+
+        // 目标位置已经存在的文件会被替换。同一目录下只改变大小写的重命名，会找到文件自身，这种情况不算替换
+        let same_dir: bool = target_guard.is_none();
+        let victim: Option<Arc<LockedExFatInode>> = {
+            let dir: &mut ExFatInode = match &mut target_guard {
+                Some(t) => &mut **t,
+                None => &mut *guard,
+            };
+            match dir.find_entry(&fs, &new_uname) {
+                Ok(set) if same_dir && set.pos == old_set.pos => None,
+                Ok(_) => Some(dir.find(new_name)?),
+                Err(SystemError::ENOENT) => None,
+                Err(e) => return Err(e),
+            }
+        };
+        if let Some(v) = &victim {
+            if core::ptr::eq(Arc::as_ptr(v), self) || core::ptr::eq(Arc::as_ptr(v), target) {
+                return Err(SystemError::ENOTEMPTY);
+            }
+        }
+        let mut victim_guard: Option<SpinLockGuard<ExFatInode>> =
+            victim.as_ref().map(|v| v.0.lock());
+        if let Some(v) = &mut victim_guard {
+            match (
+                child_guard.metadata.file_type == FileType::Dir,
+                v.metadata.file_type == FileType::Dir,
+            ) {
+                (true, false) => return Err(SystemError::ENOTDIR),
+                (false, true) => return Err(SystemError::EISDIR),
+                (true, true) if !v.is_empty_dir(&fs)? => return Err(SystemError::ENOTEMPTY),
+                _ => {}
+            }
+        }
+        let victim_set: Option<ExFatEntrySet> =
+            victim_guard.as_ref().map(|v| v.entry.clone().unwrap());
+
+        // 先删除被替换的目录项集合，再写入新的目录项集合，最后删除旧的。
+        // 写入新的目录项集合失败时，恢复被替换的目录项集合，这样即使中途出错，文件也不会丢失
+        {
+            let dir: &mut ExFatInode = match &mut target_guard {
+                Some(t) => &mut **t,
+                None => &mut *guard,
+            };
+            if let Some(vs) = &victim_set {
+                dir.remove_entry(&fs, vs)?;
+            }
+            if let Err(e) = dir.add_entry(&fs, &mut new_set) {
+                if let (Some(vs), Some(v)) = (&victim_set, &mut victim_guard) {
+                    let mut restored: ExFatEntrySet = vs.clone();
+                    dir.add_entry(&fs, &mut restored)?;
+                    v.entry = Some(restored);
+                }
+                return Err(e);
+            }
+        }
+        guard.remove_entry(&fs, &old_set)?;
+        guard.children.remove(&fs.name_key(&old_set.name_utf16()));
+        match &mut target_guard {
+            None => {
+                guard
+                    .children
+                    .insert(fs.name_key(&new_uname), child.clone());
+                // 新的目录项可能位于扩展之后的部分，因此需要更新父目录的簇链
+                child_guard.parent_chain = guard.chain.clone();
+            }
+            Some(t) => {
+                t.children.insert(fs.name_key(&new_uname), child.clone());
+                child_guard.parent_chain = t.chain.clone();
+                child_guard.parent = t.self_ref.clone();
+            }
+        }
+        // 被替换的文件可能仍被打开，它的数据簇要等到inode被释放时才回收
+        if let Some(v) = &mut victim_guard {
+            v.unlinked = true;
+            v.metadata.nlinks = 0;
+            v.metadata.ctime = TimeSpec::now();
+        }
+        child_guard.entry = Some(new_set);
+        return Ok(());
+    }
+
+    fn sync(&self) -> Result<(), SystemError> {
+        return self.0.lock().writeback_pages();
+    }
+
+    fn get_page(&self, index: usize) -> Result<Arc<CachedPage>, SystemError> {
+        let mut guard: SpinLockGuard<ExFatInode> = self.0.lock();
+        let fs: Arc<ExFatFileSystem> = guard.fs.upgrade().unwrap();
+        let page_cache: Arc<PageCache> = guard.page_cache.clone().ok_or(SystemError::ENODEV)?;
+        let file_size: usize = guard.size() as usize;
+        return page_cache.get_or_read_page(index, file_size, &mut |index, page| {
+            guard.read(&fs, page, (index * MMArch::PAGE_SIZE) as u64)
+        });
+    }
+
+    fn get_entry_name(&self, ino: InodeId) -> Result<String, SystemError> {
+        let guard: SpinLockGuard<ExFatInode> = self.0.lock();
+        if guard.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        match ino {
+            0 => {
+                return Ok(String::from("."));
+            }
+            1 => {
+                return Ok(String::from(".."));
+            }
+            ino => {
+                for child in guard.children.values() {
+                    let child_guard: SpinLockGuard<ExFatInode> = child.0.lock();
+                    if child_guard.metadata.inode_id == ino {
+                        if let Some(e) = &child_guard.entry {
+                            return Ok(e.name());
+                        }
+                    }
+                }
+                return Err(SystemError::ENOENT);
+            }
+        }
+    }
+}
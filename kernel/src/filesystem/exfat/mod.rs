@@ -0,0 +1,4 @@
+pub mod boot;
+pub mod entry;
+pub mod fs;
+pub mod upcase;
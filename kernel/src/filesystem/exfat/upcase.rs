@@ -0,0 +1,108 @@
+use alloc::vec::Vec;
+
+use crate::{kwarn, syscall::SystemError};
+
+/// 大写字母表能够映射的字符数（覆盖整个UCS-2）
+const UPCASE_TABLE_SIZE: usize = 0x10000;
+/// 压缩的大写字母表中，表示“接下来的若干个字符映射到自身”的标记
+const UPCASE_IDENTITY_MARK: u16 = 0xffff;
+
+/// @brief exFAT的大写字母表（Up-case Table）
+///
+/// exFAT的文件名不区分大小写。比较文件名、计算文件名的哈希值之前，需要先用这张表把文件名转换为大写。
+/// 表在磁盘上以压缩的形式存储：0xFFFF之后的一个值表示，接下来的这么多个字符都映射到自身。
+#[derive(Debug)]
+pub struct UpcaseTable {
+    table: Vec<u16>,
+}
+
+impl UpcaseTable {
+    /// @brief 从磁盘上读取到的（可能经过压缩的）大写字母表创建
+    ///
+    /// @param data 大写字母表的原始数据
+    /// @param checksum 目录项中记录的校验和
+    pub fn new(data: &[u8], checksum: u32) -> Result<Self, SystemError> {
+        if Self::checksum(data) != checksum {
+            kwarn!("exFAT: up-case table checksum mismatch");
+            return Err(SystemError::EINVAL);
+        }
+
+        let mut table: Vec<u16> = Vec::with_capacity(UPCASE_TABLE_SIZE);
+        let mut iter = data
+            .chunks_exact(2)
+            .map(|x| u16::from_le_bytes([x[0], x[1]]));
+        while table.len() < UPCASE_TABLE_SIZE {
+            match iter.next() {
+                Some(UPCASE_IDENTITY_MARK) => {
+                    let count = iter.next().ok_or(SystemError::EINVAL)? as usize;
+                    let end = (table.len() + count).min(UPCASE_TABLE_SIZE);
+                    while table.len() < end {
+                        table.push(table.len() as u16);
+                    }
+                }
+                Some(c) => table.push(c),
+                None => break,
+            }
+        }
+        // 表中没有覆盖到的字符映射到自身
+        while table.len() < UPCASE_TABLE_SIZE {
+            table.push(table.len() as u16);
+        }
+        return Ok(Self { table });
+    }
+
+    /// @brief 创建一张只转换ASCII字母的大写字母表
+    ///
+    /// 卷上的大写字母表缺失或者损坏时，使用这张表。规范要求至少能够正确处理ASCII字符
+    pub fn ascii() -> Self {
+        let table: Vec<u16> = (0..UPCASE_TABLE_SIZE)
+            .map(|c| {
+                if (b'a' as usize..=b'z' as usize).contains(&c) {
+                    (c - 0x20) as u16
+                } else {
+                    c as u16
+                }
+            })
+            .collect();
+        return Self { table };
+    }
+
+    /// @brief 把一个UTF-16编码单元转换为大写
+    #[inline]
+    pub fn to_upper(&self, c: u16) -> u16 {
+        return self.table[c as usize];
+    }
+
+    /// @brief 把UTF-16编码的文件名转换为大写
+    pub fn upcase(&self, name: &[u16]) -> Vec<u16> {
+        return name.iter().map(|c| self.to_upper(*c)).collect();
+    }
+
+    /// @brief 不区分大小写地比较两个UTF-16编码的文件名
+    pub fn name_eq(&self, a: &[u16], b: &[u16]) -> bool {
+        return a.len() == b.len()
+            && a.iter()
+                .zip(b.iter())
+                .all(|(x, y)| self.to_upper(*x) == self.to_upper(*y));
+    }
+
+    /// @brief 计算文件名的哈希值（存储在Stream Extension目录项中，用于加速查找）
+    pub fn name_hash(&self, name: &[u16]) -> u16 {
+        let mut hash: u16 = 0;
+        for c in name {
+            for byte in self.to_upper(*c).to_le_bytes() {
+                hash = hash.rotate_right(1).wrapping_add(byte as u16);
+            }
+        }
+        return hash;
+    }
+
+    /// @brief 计算大写字母表的校验和
+    fn checksum(data: &[u8]) -> u32 {
+        let mut checksum: u32 = 0;
+        for byte in data {
+            checksum = checksum.rotate_right(1).wrapping_add(*byte as u32);
+        }
+        return checksum;
+    }
+}
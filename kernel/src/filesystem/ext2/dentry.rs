@@ -0,0 +1,248 @@
+#![allow(dead_code)]
+use alloc::vec::Vec;
+
+use crate::{filesystem::vfs::FileType, kwarn};
+
+/// 目录项头部（inode、rec_len、name_len、file_type）的大小
+pub const EXT2_DIR_ENTRY_HEADER_LEN: usize = 8;
+/// 文件名的最大长度
+pub const EXT2_NAME_LEN: usize = 255;
+
+/// 目录项中的文件类型（仅当文件系统具有FILETYPE特性时有效）
+pub const EXT2_FT_UNKNOWN: u8 = 0;
+pub const EXT2_FT_REG_FILE: u8 = 1;
+pub const EXT2_FT_DIR: u8 = 2;
+pub const EXT2_FT_CHRDEV: u8 = 3;
+pub const EXT2_FT_BLKDEV: u8 = 4;
+pub const EXT2_FT_FIFO: u8 = 5;
+pub const EXT2_FT_SOCK: u8 = 6;
+pub const EXT2_FT_SYMLINK: u8 = 7;
+
+/// @brief 计算名称长度为name_len的目录项，至少需要占用多少字节（按4字节对齐）
+#[inline]
+pub fn dir_entry_len(name_len: usize) -> usize {
+    return (EXT2_DIR_ENTRY_HEADER_LEN + name_len + 3) & !3;
+}
+
+/// @brief 根据文件类型，获取目录项中的文件类型
+pub fn file_type_to_dirent_type(file_type: FileType) -> u8 {
+    return match file_type {
+        FileType::File => EXT2_FT_REG_FILE,
+        FileType::Dir => EXT2_FT_DIR,
+        FileType::CharDevice => EXT2_FT_CHRDEV,
+        FileType::BlockDevice => EXT2_FT_BLKDEV,
+        FileType::Pipe => EXT2_FT_FIFO,
+        FileType::Socket => EXT2_FT_SOCK,
+        FileType::SymLink => EXT2_FT_SYMLINK,
+        FileType::AnonInode => EXT2_FT_UNKNOWN,
+    };
+}
+
+/// @brief ext2的目录项
+///
+/// 目录项不会跨越块的边界。每个目录项的rec_len字段记录了它到下一个目录项的距离，
+/// 块中最后一个目录项的rec_len会延伸到块的末尾。因此，目录项的实际长度可能小于rec_len，
+/// 多出来的部分可以用来存放新的目录项
+#[derive(Debug, Clone)]
+pub struct Ext2DirEntry {
+    /// 目录项指向的inode号。为0表示这是一个空闲的目录项
+    pub inode: u32,
+    /// 目录项占用的空间（到下一个目录项的距离）
+    pub rec_len: u16,
+    /// 文件类型
+    pub file_type: u8,
+    /// 文件名
+    pub name: Vec<u8>,
+}
+
+impl Ext2DirEntry {
+    pub fn new(inode: u32, name: &[u8], file_type: u8) -> Self {
+        return Self {
+            inode,
+            rec_len: dir_entry_len(name.len()) as u16,
+            file_type,
+            name: name.to_vec(),
+        };
+    }
+
+    /// @brief 从目录块的指定位置解析一个目录项
+    ///
+    /// @return None 目录项已经损坏（rec_len、name_len不合法，或者超出了块的末尾）
+    pub fn parse(block: &[u8], offset: usize) -> Option<Self> {
+        if offset + EXT2_DIR_ENTRY_HEADER_LEN > block.len() {
+            return None;
+        }
+        let data: &[u8] = &block[offset..];
+        let inode: u32 = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let rec_len: u16 = u16::from_le_bytes([data[4], data[5]]);
+        let name_len: usize = data[6] as usize;
+
+        if (rec_len as usize) < EXT2_DIR_ENTRY_HEADER_LEN
+            || rec_len % 4 != 0
+            || offset + rec_len as usize > block.len()
+            || dir_entry_len(name_len) > rec_len as usize
+        {
+            return None;
+        }
+        return Some(Self {
+            inode,
+            rec_len,
+            file_type: data[7],
+            name: data[EXT2_DIR_ENTRY_HEADER_LEN..EXT2_DIR_ENTRY_HEADER_LEN + name_len].to_vec(),
+        });
+    }
+
+    /// @brief 把目录项写入目录块的指定位置
+    pub fn write_to(&self, block: &mut [u8], offset: usize) {
+        let data: &mut [u8] = &mut block[offset..];
+        data[0..4].copy_from_slice(&self.inode.to_le_bytes());
+        data[4..6].copy_from_slice(&self.rec_len.to_le_bytes());
+        data[6] = self.name.len() as u8;
+        data[7] = self.file_type;
+        data[EXT2_DIR_ENTRY_HEADER_LEN..EXT2_DIR_ENTRY_HEADER_LEN + self.name.len()]
+            .copy_from_slice(&self.name);
+    }
+
+    /// @brief 目录项实际需要占用的字节数
+    #[inline]
+    pub fn actual_len(&self) -> usize {
+        return dir_entry_len(self.name.len());
+    }
+}
+
+/// @brief 遍历一个目录块中的所有（正在使用的）目录项
+///
+/// 迭代器返回 (目录项在块内的偏移量, 目录项)
+pub struct Ext2DirBlockIter<'a> {
+    block: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Ext2DirBlockIter<'a> {
+    pub fn new(block: &'a [u8]) -> Self {
+        return Self { block, offset: 0 };
+    }
+}
+
+impl<'a> Iterator for Ext2DirBlockIter<'a> {
+    type Item = (usize, Ext2DirEntry);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.offset < self.block.len() {
+            let offset: usize = self.offset;
+            let entry: Ext2DirEntry = match Ext2DirEntry::parse(self.block, offset) {
+                Some(e) => e,
+                None => {
+                    kwarn!("ext2: corrupted directory entry at offset {}", offset);
+                    self.offset = self.block.len();
+                    return None;
+                }
+            };
+            self.offset += entry.rec_len as usize;
+            if entry.inode != 0 {
+                return Some((offset, entry));
+            }
+        }
+        return None;
+    }
+}
+
+/// @brief 在目录块中插入一个目录项
+///
+/// 优先使用空闲的目录项；否则从某个目录项的rec_len中多余的空间里切分出新的目录项
+///
+/// @param entry 要插入的目录项（rec_len会被重新计算）
+///
+/// @return true 插入成功
+/// @return false 块中没有足够的空间
+pub fn insert_entry(block: &mut [u8], entry: &Ext2DirEntry) -> bool {
+    let needed: usize = entry.actual_len();
+    let mut offset: usize = 0;
+    while offset < block.len() {
+        let cur: Ext2DirEntry = match Ext2DirEntry::parse(block, offset) {
+            Some(e) => e,
+            None => return false,
+        };
+        let rec_len: usize = cur.rec_len as usize;
+
+        if cur.inode == 0 && rec_len >= needed {
+            // 空闲的目录项，直接占用它的全部空间
+            let mut new_entry: Ext2DirEntry = entry.clone();
+            new_entry.rec_len = rec_len as u16;
+            new_entry.write_to(block, offset);
+            return true;
+        }
+        if cur.inode != 0 && rec_len >= cur.actual_len() + needed {
+            // 把当前目录项的rec_len缩短为实际长度，剩余的空间给新的目录项
+            let mut shrunk: Ext2DirEntry = cur.clone();
+            shrunk.rec_len = cur.actual_len() as u16;
+            shrunk.write_to(block, offset);
+
+            let mut new_entry: Ext2DirEntry = entry.clone();
+            new_entry.rec_len = (rec_len - cur.actual_len()) as u16;
+            new_entry.write_to(block, offset + cur.actual_len());
+            return true;
+        }
+        offset += rec_len;
+    }
+    return false;
+}
+
+/// @brief 从目录块中删除名为name的目录项
+///
+/// 如果目录项是块中的第一个，那么把它的inode置为0；否则把它的空间合并到前一个目录项中
+///
+/// @return Some(inode号) 被删除的目录项指向的inode
+/// @return None 块中没有这个目录项
+pub fn remove_entry(block: &mut [u8], name: &[u8]) -> Option<u32> {
+    let mut prev: Option<(usize, Ext2DirEntry)> = None;
+    let mut offset: usize = 0;
+    while offset < block.len() {
+        let cur: Ext2DirEntry = Ext2DirEntry::parse(block, offset)?;
+        if cur.inode != 0 && cur.name == name {
+            match prev {
+                Some((prev_offset, mut prev_entry)) => {
+                    prev_entry.rec_len += cur.rec_len;
+                    prev_entry.write_to(block, prev_offset);
+                }
+                None => {
+                    let mut cur: Ext2DirEntry = cur.clone();
+                    cur.inode = 0;
+                    cur.write_to(block, offset);
+                }
+            }
+            return Some(cur.inode);
+        }
+        offset += cur.rec_len as usize;
+        prev = Some((offset - cur.rec_len as usize, cur));
+    }
+    return None;
+}
+
+/// @brief 创建一个只包含一个空闲目录项的目录块（用于扩展目录）
+pub fn empty_dir_block(block_size: usize) -> Vec<u8> {
+    let mut block: Vec<u8> = vec![0; block_size];
+    let entry = Ext2DirEntry {
+        inode: 0,
+        rec_len: block_size as u16,
+        file_type: EXT2_FT_UNKNOWN,
+        name: Vec::new(),
+    };
+    entry.write_to(&mut block, 0);
+    return block;
+}
+
+/// @brief 创建新目录的第一个块，其中包含"."和".."两个目录项
+///
+/// @param self_ino 新目录的inode号
+/// @param parent_ino 父目录的inode号
+/// @param file_type 目录项中的文件类型（文件系统不支持FILETYPE特性时为0）
+pub fn new_dir_block(block_size: usize, self_ino: u32, parent_ino: u32, file_type: u8) -> Vec<u8> {
+    let mut block: Vec<u8> = vec![0; block_size];
+    let dot = Ext2DirEntry::new(self_ino, b".", file_type);
+    let mut dotdot = Ext2DirEntry::new(parent_ino, b"..", file_type);
+    dotdot.rec_len = (block_size - dot.actual_len()) as u16;
+    dot.write_to(&mut block, 0);
+    dotdot.write_to(&mut block, dot.actual_len());
+    return block;
+}
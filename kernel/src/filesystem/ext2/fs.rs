@@ -0,0 +1,2019 @@
+#![allow(dead_code)]
+use core::{
+    any::Any,
+    cmp::min,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use alloc::{
+    collections::BTreeMap,
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+
+use crate::{
+    arch::MMArch,
+    driver::base::block::{block_device::LBA_SIZE, disk_info::Partition},
+    filesystem::vfs::{
+        core::generate_inode_id,
+        file::{FileMode, FilePrivateData},
+        FileSystem, FileType, FsInfo, IndexNode, InodeId, Metadata, PollStatus, MAX_PATHLEN,
+    },
+    kerror, kwarn,
+    libs::spinlock::{SpinLock, SpinLockGuard},
+    mm::{
+        page_cache::{CachedPage, PageCache, PageCacheBackend},
+        MemoryManagementArch,
+    },
+    syscall::SystemError,
+    time::TimeSpec,
+};
+
+use super::{
+    dentry::{
+        empty_dir_block, file_type_to_dirent_type, insert_entry, new_dir_block, remove_entry,
+        Ext2DirBlockIter, Ext2DirEntry, EXT2_NAME_LEN,
+    },
+    inode::{
+        ext2_time_to_timespec, file_type_to_mode, Ext2DiskInode, EXT2_DIND_BLOCK,
+        EXT2_FAST_SYMLINK_MAX, EXT2_IND_BLOCK, EXT2_LINK_MAX, EXT2_NDIR_BLOCKS, EXT2_TIND_BLOCK,
+        S_IFMT,
+    },
+    superblock::{
+        Ext2GroupDesc, Ext2SuperBlock, FeatureIncompat, FeatureRoCompat, EXT2_ERROR_FS,
+        EXT2_GOOD_OLD_REV, EXT2_GROUP_DESC_SIZE, EXT2_ROOT_INO, EXT2_VALID_FS,
+    },
+};
+
+/// ext2文件系统的魔数（与Linux的EXT2_SUPER_MAGIC相同）
+pub const EXT2_MAGIC_NUMBER: u64 = 0xef53;
+/// 没有LARGE_FILE特性时，文件的最大大小
+const EXT2_MAX_SMALL_FILE_SIZE: u64 = (1 << 31) - 1;
+/// 访问时间最多延迟多久更新（与Linux的relatime相同）
+const ATIME_UPDATE_INTERVAL: u32 = 24 * 60 * 60;
+
+/// @brief 块组描述符表
+#[derive(Debug)]
+struct Ext2Groups {
+    /// 解析之后的描述符
+    descs: Vec<Ext2GroupDesc>,
+    /// 描述符表的原始数据（写回时保留其中的保留字段）
+    raw: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct Ext2FileSystem {
+    /// 当前文件系统所在的分区
+    pub partition: Arc<Partition>,
+    /// 超级块
+    sb: SpinLock<Ext2SuperBlock>,
+    /// 块组描述符表
+    groups: SpinLock<Ext2Groups>,
+    /// 每个块的字节数
+    block_size: u64,
+    /// inode结构体的大小
+    inode_size: u64,
+    /// 文件系统是否以只读方式挂载
+    read_only: AtomicBool,
+    /// 挂载之前，文件系统是否处于正常卸载的状态
+    was_clean: bool,
+    /// 文件系统的根inode
+    root_inode: Arc<LockedExt2Inode>,
+    /// inode号 -> 内存中的inode。保证同一个inode（例如有多个硬链接的文件）在内存中只有一份
+    inodes: SpinLock<BTreeMap<u32, Weak<LockedExt2Inode>>>,
+}
+
+/// ext2文件系统的Inode
+#[derive(Debug)]
+pub struct LockedExt2Inode(SpinLock<Ext2Inode>);
+
+#[derive(Debug)]
+pub struct Ext2Inode {
+    /// 指向父Inode的弱引用（仅对文件夹有意义，文件可能有多个硬链接）
+    parent: Weak<LockedExt2Inode>,
+    /// 指向自身的弱引用
+    self_ref: Weak<LockedExt2Inode>,
+    /// 子Inode的缓存
+    children: BTreeMap<String, Arc<LockedExt2Inode>>,
+    /// 指向inode所在的文件系统对象的指针
+    fs: Weak<Ext2FileSystem>,
+    /// 磁盘上的inode号
+    ino: u32,
+    /// 磁盘上的inode结构体
+    disk: Ext2DiskInode,
+    /// 当前inode的元数据
+    metadata: Metadata,
+    /// 文件的页缓存（只有普通文件才有页缓存）
+    page_cache: Option<Arc<PageCache>>,
+    /// 内存中的inode结构体（修改时间等）是否被修改过，需要在写回时刷入磁盘
+    inode_dirty: bool,
+    /// 上一次为文件分配的块，下一次分配时从它之后开始查找，使文件的数据尽量连续
+    last_alloc: u32,
+}
+
+impl FileSystem for Ext2FileSystem {
+    fn root_inode(&self) -> Arc<dyn IndexNode> {
+        return self.root_inode.clone();
+    }
+
+    fn info(&self) -> FsInfo {
+        let sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+        return FsInfo {
+            blk_dev_id: 0,
+            max_name_len: EXT2_NAME_LEN,
+            magic: EXT2_MAGIC_NUMBER,
+            block_size: self.block_size as usize,
+            total_blocks: sb.blocks_count as u64,
+            free_blocks: sb.free_blocks_count as u64,
+            // 为超级用户保留的块，不能被普通用户使用
+            avail_blocks: sb.free_blocks_count.saturating_sub(sb.r_blocks_count) as u64,
+            total_inodes: sb.inodes_count as u64,
+            free_inodes: sb.free_inodes_count as u64,
+        };
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Ext2FileSystem {
+    pub fn new(partition: Arc<Partition>) -> Result<Arc<Ext2FileSystem>, SystemError> {
+        let sb: Ext2SuperBlock = Ext2SuperBlock::new(&partition)?;
+        let block_size: u64 = sb.block_size();
+
+        // 块组描述符表紧跟在超级块所在的块之后
+        let groups_count: usize = sb.groups_count() as usize;
+        let gdt_blocks: usize =
+            (groups_count * EXT2_GROUP_DESC_SIZE + block_size as usize - 1) / block_size as usize;
+        let mut raw: Vec<u8> = vec![0; gdt_blocks * block_size as usize];
+        partition.disk().read_at(
+            partition.lba_start as usize
+                + (sb.first_data_block as usize + 1) * (block_size as usize / LBA_SIZE),
+            raw.len() / LBA_SIZE,
+            &mut raw,
+        )?;
+        let mut descs: Vec<Ext2GroupDesc> = Vec::with_capacity(groups_count);
+        for i in 0..groups_count {
+            let desc: Ext2GroupDesc = Ext2GroupDesc::parse(&raw[i * EXT2_GROUP_DESC_SIZE..]);
+            if desc.block_bitmap >= sb.blocks_count
+                || desc.inode_bitmap >= sb.blocks_count
+                || desc.inode_table >= sb.blocks_count
+            {
+                kerror!("ext2: corrupted descriptor of block group {}", i);
+                return Err(SystemError::EINVAL);
+            }
+            descs.push(desc);
+        }
+
+        let was_clean: bool = sb.state & EXT2_VALID_FS != 0 && sb.state & EXT2_ERROR_FS == 0;
+        if !was_clean {
+            kwarn!("ext2: filesystem was not cleanly unmounted or has errors, running e2fsck is recommended");
+        }
+        let read_only: bool = sb.has_unsupported_ro_compat();
+        if read_only {
+            kwarn!(
+                "ext2: unsupported read-only compatible features {:#x}, mounting read-only",
+                sb.feature_ro_compat
+            );
+        }
+
+        let root_inode: Arc<LockedExt2Inode> =
+            Arc::new(LockedExt2Inode(SpinLock::new(Ext2Inode {
+                parent: Weak::default(),
+                self_ref: Weak::default(),
+                children: BTreeMap::new(),
+                fs: Weak::default(),
+                ino: EXT2_ROOT_INO,
+                disk: Ext2DiskInode::default(),
+                metadata: Metadata::default(),
+                page_cache: None,
+                inode_dirty: false,
+                last_alloc: 0,
+            })));
+
+        let result: Arc<Ext2FileSystem> = Arc::new(Ext2FileSystem {
+            partition,
+            inode_size: sb.inode_size as u64,
+            sb: SpinLock::new(sb),
+            groups: SpinLock::new(Ext2Groups { descs, raw }),
+            block_size,
+            read_only: AtomicBool::new(read_only),
+            was_clean,
+            root_inode,
+            inodes: SpinLock::new(BTreeMap::new()),
+        });
+
+        // 对root inode加锁，并继续完成初始化工作
+        let disk: Ext2DiskInode = result.read_disk_inode(EXT2_ROOT_INO)?;
+        if disk.file_type() != FileType::Dir {
+            kerror!("ext2: root inode is not a directory");
+            return Err(SystemError::EINVAL);
+        }
+        let mut root_guard: SpinLockGuard<Ext2Inode> = result.root_inode.0.lock();
+        root_guard.parent = Arc::downgrade(&result.root_inode);
+        root_guard.self_ref = Arc::downgrade(&result.root_inode);
+        root_guard.fs = Arc::downgrade(&result);
+        root_guard.disk = disk;
+        root_guard.metadata.inode_id = generate_inode_id();
+        root_guard.update_metadata(&result);
+        drop(root_guard);
+        result
+            .inodes
+            .lock()
+            .insert(EXT2_ROOT_INO, Arc::downgrade(&result.root_inode));
+
+        if !result.is_read_only() {
+            result.mark_mounted()?;
+        }
+        return Ok(result);
+    }
+
+    /// @brief 在超级块中记录文件系统已经被挂载
+    ///
+    /// 挂载期间，超级块中的VALID标志被清除。如果系统在卸载之前崩溃，下一次挂载时能够发现这一点
+    fn mark_mounted(&self) -> Result<(), SystemError> {
+        let mut sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+        sb.state &= !EXT2_VALID_FS;
+        sb.mnt_count = sb.mnt_count.wrapping_add(1);
+        sb.mtime = TimeSpec::now().tv_sec as u32;
+        return sb.write(&self.partition);
+    }
+
+    /// @brief 执行文件系统卸载前的准备工作：写回超级块中的空闲计数，并恢复VALID标志
+    ///
+    /// 如果挂载之前文件系统就不是正常卸载的状态，那么不设置VALID标志，留给e2fsck检查
+    pub fn umount(&mut self) -> Result<(), SystemError> {
+        if self.is_read_only() {
+            return Ok(());
+        }
+        let mut sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+        if self.was_clean {
+            sb.state |= EXT2_VALID_FS;
+        }
+        sb.wtime = TimeSpec::now().tv_sec as u32;
+        sb.write(&self.partition)?;
+        drop(sb);
+        self.partition.disk().sync()?;
+        return Ok(());
+    }
+
+    /// @brief 文件系统是否以只读方式挂载
+    #[inline]
+    pub fn is_read_only(&self) -> bool {
+        return self.read_only.load(Ordering::SeqCst);
+    }
+
+    /// @brief 每个块中能存放的块指针的数量
+    #[inline]
+    fn ptrs_per_block(&self) -> u64 {
+        return self.block_size / 4;
+    }
+
+    /// @brief 文件的最大大小
+    pub fn max_file_size(&self) -> u64 {
+        let sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+        if sb.rev_level == EXT2_GOOD_OLD_REV {
+            return EXT2_MAX_SMALL_FILE_SIZE;
+        }
+        let p: u64 = self.ptrs_per_block();
+        let blocks: u64 = EXT2_NDIR_BLOCKS as u64 + p + p * p + p * p * p;
+        // i_blocks以512字节为单位，且是32位的
+        let sectors_limit: u64 = (u32::MAX as u64) * 512;
+        return min(blocks * self.block_size, sectors_limit);
+    }
+
+    /// @brief 文件的大小超过2G时，需要在超级块中设置LARGE_FILE特性
+    fn set_large_file(&self) -> Result<(), SystemError> {
+        let mut sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+        if sb.has_ro_compat(FeatureRoCompat::LARGE_FILE) {
+            return Ok(());
+        }
+        sb.feature_ro_compat |= FeatureRoCompat::LARGE_FILE.bits();
+        return sb.write(&self.partition);
+    }
+
+    /// @brief 获取目录项中的文件类型（文件系统不支持FILETYPE特性时为0）
+    fn dirent_type(&self, file_type: FileType) -> u8 {
+        if self.sb.lock().has_incompat(FeatureIncompat::FILETYPE) {
+            return file_type_to_dirent_type(file_type);
+        }
+        return 0;
+    }
+
+    /// @brief 获取块在磁盘上的LBA地址
+    #[inline]
+    fn block_lba(&self, block: u32) -> usize {
+        return self.partition.lba_start as usize
+            + block as usize * (self.block_size as usize / LBA_SIZE);
+    }
+
+    /// @brief 读取一个块。buf的长度必须等于块大小
+    pub fn read_block(&self, block: u32, buf: &mut [u8]) -> Result<(), SystemError> {
+        self.partition.disk().read_at(
+            self.block_lba(block),
+            self.block_size as usize / LBA_SIZE,
+            buf,
+        )?;
+        return Ok(());
+    }
+
+    /// @brief 写入一个块。buf的长度必须等于块大小
+    pub fn write_block(&self, block: u32, buf: &[u8]) -> Result<(), SystemError> {
+        self.partition.disk().write_at(
+            self.block_lba(block),
+            self.block_size as usize / LBA_SIZE,
+            buf,
+        )?;
+        return Ok(());
+    }
+
+    /// @brief 把一个块清零
+    fn zero_block(&self, block: u32) -> Result<(), SystemError> {
+        return self.write_block(block, &vec![0; self.block_size as usize]);
+    }
+
+    /// @brief 读取间接块中的第index个块指针
+    ///
+    /// 只读取块指针所在的扇区，而不是整个块
+    fn read_block_ptr(&self, block: u32, index: u64) -> Result<u32, SystemError> {
+        let offset: usize = index as usize * 4;
+        let mut sector: Vec<u8> = vec![0; LBA_SIZE];
+        self.partition
+            .disk()
+            .read_at(self.block_lba(block) + offset / LBA_SIZE, 1, &mut sector)?;
+        let off: usize = offset % LBA_SIZE;
+        return Ok(u32::from_le_bytes([
+            sector[off],
+            sector[off + 1],
+            sector[off + 2],
+            sector[off + 3],
+        ]));
+    }
+
+    /// @brief 设置间接块中的第index个块指针
+    fn write_block_ptr(&self, block: u32, index: u64, value: u32) -> Result<(), SystemError> {
+        let offset: usize = index as usize * 4;
+        let lba: usize = self.block_lba(block) + offset / LBA_SIZE;
+        let mut sector: Vec<u8> = vec![0; LBA_SIZE];
+        self.partition.disk().read_at(lba, 1, &mut sector)?;
+        let off: usize = offset % LBA_SIZE;
+        sector[off..off + 4].copy_from_slice(&value.to_le_bytes());
+        self.partition.disk().write_at(lba, 1, &sector)?;
+        return Ok(());
+    }
+
+    /// @brief 获取inode在分区内的字节偏移量
+    fn inode_offset(&self, ino: u32) -> Result<u64, SystemError> {
+        let (inodes_count, inodes_per_group) = {
+            let sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+            (sb.inodes_count, sb.inodes_per_group)
+        };
+        if ino == 0 || ino > inodes_count {
+            kerror!("ext2: invalid inode number {}", ino);
+            return Err(SystemError::EIO);
+        }
+        let group: usize = ((ino - 1) / inodes_per_group) as usize;
+        let index: u64 = ((ino - 1) % inodes_per_group) as u64;
+        let table: u32 = self.groups.lock().descs[group].inode_table;
+        return Ok(table as u64 * self.block_size + index * self.inode_size);
+    }
+
+    /// @brief 从inode表中读取inode
+    pub fn read_disk_inode(&self, ino: u32) -> Result<Ext2DiskInode, SystemError> {
+        let offset: u64 = self.inode_offset(ino)?;
+        let in_sector: usize = (offset % LBA_SIZE as u64) as usize;
+        let count: usize = (in_sector + self.inode_size as usize + LBA_SIZE - 1) / LBA_SIZE;
+        let mut buf: Vec<u8> = vec![0; count * LBA_SIZE];
+        self.partition.disk().read_at(
+            self.partition.lba_start as usize + (offset / LBA_SIZE as u64) as usize,
+            count,
+            &mut buf,
+        )?;
+        return Ok(Ext2DiskInode::parse(
+            &buf[in_sector..in_sector + self.inode_size as usize],
+        ));
+    }
+
+    /// @brief 把inode写回inode表
+    pub fn write_disk_inode(&self, ino: u32, inode: &Ext2DiskInode) -> Result<(), SystemError> {
+        let offset: u64 = self.inode_offset(ino)?;
+        let in_sector: usize = (offset % LBA_SIZE as u64) as usize;
+        let count: usize = (in_sector + self.inode_size as usize + LBA_SIZE - 1) / LBA_SIZE;
+        let lba: usize = self.partition.lba_start as usize + (offset / LBA_SIZE as u64) as usize;
+        let mut buf: Vec<u8> = vec![0; count * LBA_SIZE];
+        self.partition.disk().read_at(lba, count, &mut buf)?;
+        buf[in_sector..in_sector + self.inode_size as usize].copy_from_slice(&inode.to_bytes());
+        self.partition.disk().write_at(lba, count, &buf)?;
+        return Ok(());
+    }
+
+    /// @brief 把第index个块组的描述符写回磁盘
+    fn write_group_desc(&self, groups: &mut Ext2Groups, index: usize) -> Result<(), SystemError> {
+        let offset: usize = index * EXT2_GROUP_DESC_SIZE;
+        groups.descs[index].write_to(&mut groups.raw[offset..offset + EXT2_GROUP_DESC_SIZE]);
+
+        // 只写回描述符所在的块
+        let bs: usize = self.block_size as usize;
+        let blk: usize = offset / bs;
+        let first_data_block: u32 = self.sb.lock().first_data_block;
+        return self.write_block(
+            first_data_block + 1 + blk as u32,
+            &groups.raw[blk * bs..(blk + 1) * bs],
+        );
+    }
+
+    /// @brief 获取inode所在的块组
+    #[inline]
+    fn inode_group(&self, ino: u32) -> usize {
+        return ((ino - 1) / self.sb.lock().inodes_per_group) as usize;
+    }
+
+    /// @brief 获取块组中的第一个块
+    fn group_first_block(&self, group: usize) -> u32 {
+        let sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+        return sb.first_data_block + group as u32 * sb.blocks_per_group;
+    }
+
+    /// @brief 分配一个块
+    ///
+    /// @param goal 优先分配这个块，或者在它之后的第一个空闲块
+    ///
+    /// @return Ok(u32) 分配到的块号
+    /// @return Err(ENOSPC) 没有空闲的块
+    fn alloc_block(&self, goal: u32) -> Result<u32, SystemError> {
+        let (first_data_block, blocks_count, blocks_per_group) = {
+            let sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+            (sb.first_data_block, sb.blocks_count, sb.blocks_per_group)
+        };
+        let goal: u32 = if goal >= first_data_block && goal < blocks_count {
+            goal - first_data_block
+        } else {
+            0
+        };
+
+        let mut groups: SpinLockGuard<Ext2Groups> = self.groups.lock();
+        let groups_count: usize = groups.descs.len();
+        let start_group: usize = (goal / blocks_per_group) as usize;
+        let mut bitmap: Vec<u8> = vec![0; self.block_size as usize];
+        for i in 0..groups_count {
+            let group: usize = (start_group + i) % groups_count;
+            if groups.descs[group].free_blocks_count == 0 {
+                continue;
+            }
+            // 最后一个块组可能不完整
+            let nbits: u32 = min(
+                blocks_per_group,
+                blocks_count - first_data_block - group as u32 * blocks_per_group,
+            );
+            let start_bit: u32 = if i == 0 { goal % blocks_per_group } else { 0 };
+
+            self.read_block(groups.descs[group].block_bitmap, &mut bitmap)?;
+            let bit: u32 = match find_zero_bit(&bitmap, nbits, start_bit) {
+                Some(bit) => bit,
+                None => {
+                    kwarn!(
+                        "ext2: block group {} has no free blocks, but its descriptor says {}",
+                        group,
+                        groups.descs[group].free_blocks_count
+                    );
+                    continue;
+                }
+            };
+            bitmap[bit as usize / 8] |= 1 << (bit % 8);
+            self.write_block(groups.descs[group].block_bitmap, &bitmap)?;
+            groups.descs[group].free_blocks_count -= 1;
+            self.write_group_desc(&mut groups, group)?;
+            drop(groups);
+
+            // 超级块中的空闲计数只在内存中更新，卸载时再写回
+            let mut sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+            sb.free_blocks_count = sb.free_blocks_count.saturating_sub(1);
+            return Ok(first_data_block + group as u32 * blocks_per_group + bit);
+        }
+        return Err(SystemError::ENOSPC);
+    }
+
+    /// @brief 释放一组块
+    fn free_blocks(&self, blocks: &[u32]) -> Result<(), SystemError> {
+        if blocks.is_empty() {
+            return Ok(());
+        }
+        let (first_data_block, blocks_count, blocks_per_group) = {
+            let sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+            (sb.first_data_block, sb.blocks_count, sb.blocks_per_group)
+        };
+
+        // 按照块组分组，每个块组的位图只需要读写一次
+        let mut by_group: BTreeMap<usize, Vec<u32>> = BTreeMap::new();
+        for block in blocks {
+            if *block < first_data_block || *block >= blocks_count {
+                kerror!("ext2: trying to free invalid block {}", block);
+                continue;
+            }
+            let rel: u32 = block - first_data_block;
+            by_group
+                .entry((rel / blocks_per_group) as usize)
+                .or_default()
+                .push(rel % blocks_per_group);
+        }
+
+        let mut freed: u32 = 0;
+        let mut groups: SpinLockGuard<Ext2Groups> = self.groups.lock();
+        let mut bitmap: Vec<u8> = vec![0; self.block_size as usize];
+        for (group, bits) in by_group {
+            self.read_block(groups.descs[group].block_bitmap, &mut bitmap)?;
+            for bit in bits {
+                if bitmap[bit as usize / 8] & (1 << (bit % 8)) == 0 {
+                    kwarn!(
+                        "ext2: block {} is already free",
+                        first_data_block + group as u32 * blocks_per_group + bit
+                    );
+                    continue;
+                }
+                bitmap[bit as usize / 8] &= !(1 << (bit % 8));
+                groups.descs[group].free_blocks_count += 1;
+                freed += 1;
+            }
+            self.write_block(groups.descs[group].block_bitmap, &bitmap)?;
+            self.write_group_desc(&mut groups, group)?;
+        }
+        drop(groups);
+
+        let mut sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+        sb.free_blocks_count += freed;
+        return Ok(());
+    }
+
+    /// @brief 分配一个inode
+    ///
+    /// 文件夹会被分散到空闲inode最多的块组中，其他文件优先和父目录放在同一个块组中
+    ///
+    /// @param parent_ino 父目录的inode号
+    /// @param is_dir 要分配的inode是否是文件夹
+    fn alloc_inode(&self, parent_ino: u32, is_dir: bool) -> Result<u32, SystemError> {
+        let (inodes_per_group, first_ino) = {
+            let sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+            (sb.inodes_per_group, sb.first_ino)
+        };
+        let mut groups: SpinLockGuard<Ext2Groups> = self.groups.lock();
+        let groups_count: usize = groups.descs.len();
+        let start_group: usize = if is_dir {
+            (0..groups_count)
+                .max_by_key(|g| groups.descs[*g].free_inodes_count)
+                .unwrap_or(0)
+        } else {
+            ((parent_ino - 1) / inodes_per_group) as usize
+        };
+
+        let mut bitmap: Vec<u8> = vec![0; self.block_size as usize];
+        for i in 0..groups_count {
+            let group: usize = (start_group + i) % groups_count;
+            if groups.descs[group].free_inodes_count == 0 {
+                continue;
+            }
+            self.read_block(groups.descs[group].inode_bitmap, &mut bitmap)?;
+            // 第一个块组中，first_ino之前的inode是保留的
+            let start_bit: u32 = if group == 0 { first_ino - 1 } else { 0 };
+            let bit: u32 = match find_zero_bit(&bitmap, inodes_per_group, start_bit) {
+                Some(bit) if group != 0 || bit >= start_bit => bit,
+                _ => continue,
+            };
+            bitmap[bit as usize / 8] |= 1 << (bit % 8);
+            self.write_block(groups.descs[group].inode_bitmap, &bitmap)?;
+            groups.descs[group].free_inodes_count -= 1;
+            if is_dir {
+                groups.descs[group].used_dirs_count += 1;
+            }
+            self.write_group_desc(&mut groups, group)?;
+            drop(groups);
+
+            let mut sb: SpinLockGuard<Ext2SuperBlock> = self.sb.lock();
+            sb.free_inodes_count = sb.free_inodes_count.saturating_sub(1);
+            return Ok(group as u32 * inodes_per_group + bit + 1);
+        }
+        return Err(SystemError::ENOSPC);
+    }
+
+    /// @brief 释放一个inode
+    fn free_inode(&self, ino: u32, is_dir: bool) -> Result<(), SystemError> {
+        let inodes_per_group: u32 = self.sb.lock().inodes_per_group;
+        let group: usize = ((ino - 1) / inodes_per_group) as usize;
+        let bit: u32 = (ino - 1) % inodes_per_group;
+
+        let mut groups: SpinLockGuard<Ext2Groups> = self.groups.lock();
+        let mut bitmap: Vec<u8> = vec![0; self.block_size as usize];
+        self.read_block(groups.descs[group].inode_bitmap, &mut bitmap)?;
+        if bitmap[bit as usize / 8] & (1 << (bit % 8)) == 0 {
+            kwarn!("ext2: inode {} is already free", ino);
+            return Ok(());
+        }
+        bitmap[bit as usize / 8] &= !(1 << (bit % 8));
+        self.write_block(groups.descs[group].inode_bitmap, &bitmap)?;
+        groups.descs[group].free_inodes_count += 1;
+        if is_dir {
+            groups.descs[group].used_dirs_count =
+                groups.descs[group].used_dirs_count.saturating_sub(1);
+        }
+        self.write_group_desc(&mut groups, group)?;
+        drop(groups);
+
+        self.sb.lock().free_inodes_count += 1;
+        return Ok(());
+    }
+
+    /// @brief 计算文件中的第index个块，在块指针树中的路径
+    ///
+    /// @return (路径, 路径的长度)。路径的第一项是i_block中的下标，其余各项是各级间接块中的下标
+    fn block_path(&self, index: u64) -> Result<([u64; 4], usize), SystemError> {
+        let p: u64 = self.ptrs_per_block();
+        let mut index: u64 = index;
+        if index < EXT2_NDIR_BLOCKS as u64 {
+            return Ok(([index, 0, 0, 0], 1));
+        }
+        index -= EXT2_NDIR_BLOCKS as u64;
+        if index < p {
+            return Ok(([EXT2_IND_BLOCK as u64, index, 0, 0], 2));
+        }
+        index -= p;
+        if index < p * p {
+            return Ok(([EXT2_DIND_BLOCK as u64, index / p, index % p, 0], 3));
+        }
+        index -= p * p;
+        if index < p * p * p {
+            return Ok((
+                [
+                    EXT2_TIND_BLOCK as u64,
+                    index / (p * p),
+                    (index / p) % p,
+                    index % p,
+                ],
+                4,
+            ));
+        }
+        return Err(SystemError::EFBIG);
+    }
+
+    /// @brief 释放一棵块指针子树中，从第start个数据块开始的所有块
+    ///
+    /// @param block 子树的根（一个间接块）
+    /// @param level 子树的层数（1表示一级间接块）
+    /// @param start 从子树中的第几个数据块开始释放
+    /// @param freed 被释放的块会被加入这个数组
+    ///
+    /// @return Ok(true) 整棵子树都被释放了（包括根），调用者需要把指向它的指针清零
+    fn free_tree(
+        &self,
+        block: u32,
+        level: u32,
+        start: u64,
+        freed: &mut Vec<u32>,
+    ) -> Result<bool, SystemError> {
+        let p: u64 = self.ptrs_per_block();
+        let span: u64 = p.pow(level - 1);
+        let mut data: Vec<u8> = vec![0; self.block_size as usize];
+        self.read_block(block, &mut data)?;
+
+        let mut modified: bool = false;
+        for i in 0..p {
+            if (i + 1) * span <= start {
+                continue;
+            }
+            let off: usize = i as usize * 4;
+            let ptr: u32 =
+                u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]]);
+            if ptr == 0 {
+                continue;
+            }
+            let released: bool = if level == 1 {
+                freed.push(ptr);
+                true
+            } else {
+                self.free_tree(ptr, level - 1, start.saturating_sub(i * span), freed)?
+            };
+            if released {
+                data[off..off + 4].copy_from_slice(&0u32.to_le_bytes());
+                modified = true;
+            }
+        }
+
+        if start == 0 {
+            freed.push(block);
+            return Ok(true);
+        }
+        if modified {
+            self.write_block(block, &data)?;
+        }
+        return Ok(false);
+    }
+
+    /// @brief 获取inode号对应的内存中的inode。如果它不在内存中，会从磁盘读取
+    ///
+    /// @param parent 父目录的inode（仅在需要从磁盘读取时使用）
+    fn get_inode(
+        self: &Arc<Self>,
+        ino: u32,
+        parent: Weak<LockedExt2Inode>,
+    ) -> Result<Arc<LockedExt2Inode>, SystemError> {
+        let mut inodes: SpinLockGuard<BTreeMap<u32, Weak<LockedExt2Inode>>> = self.inodes.lock();
+        if let Some(inode) = inodes.get(&ino).and_then(|w| w.upgrade()) {
+            return Ok(inode);
+        }
+
+        let disk: Ext2DiskInode = self.read_disk_inode(ino)?;
+        if disk.links_count == 0 || disk.mode == 0 {
+            kerror!("ext2: directory entry refers to deleted inode {}", ino);
+            return Err(SystemError::EIO);
+        }
+        let inode: Arc<LockedExt2Inode> = LockedExt2Inode::new(self, ino, disk, parent);
+        inodes.insert(ino, Arc::downgrade(&inode));
+        return Ok(inode);
+    }
+}
+
+impl Drop for Ext2FileSystem {
+    fn drop(&mut self) {
+        let r = self.umount();
+        if r.is_err() {
+            kerror!(
+                "Umount ext2 filesystem failed: errno={:?}, FS detail:{self:?}",
+                r.unwrap_err()
+            );
+        }
+    }
+}
+
+/// @brief 在位图的前nbits位中，从start开始查找第一个为0的位（查找到末尾之后，会从头开始）
+fn find_zero_bit(bitmap: &[u8], nbits: u32, start: u32) -> Option<u32> {
+    let start: u32 = if start < nbits { start } else { 0 };
+    return (start..nbits)
+        .chain(0..start)
+        .find(|bit| bitmap[*bit as usize / 8] & (1 << (bit % 8)) == 0);
+}
+
+/// @brief 检查文件名是否合法
+fn check_name(name: &str) -> Result<(), SystemError> {
+    if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+        return Err(SystemError::EINVAL);
+    }
+    if name.len() > EXT2_NAME_LEN {
+        return Err(SystemError::ENAMETOOLONG);
+    }
+    return Ok(());
+}
+
+impl Ext2Inode {
+    /// @brief 根据磁盘上的inode，更新当前inode的元数据
+    fn update_metadata(&mut self, fs: &Ext2FileSystem) {
+        let disk: &Ext2DiskInode = &self.disk;
+        // inode_id是全局唯一的，供目录项缓存使用；stat报告的是磁盘上的inode号
+        self.metadata.disk_ino = Some(self.ino as u64);
+        self.metadata.size = disk.size as i64;
+        self.metadata.blk_size = fs.block_size as usize;
+        self.metadata.blocks = disk.blocks as usize;
+        self.metadata.atime = ext2_time_to_timespec(disk.atime);
+        self.metadata.mtime = ext2_time_to_timespec(disk.mtime);
+        self.metadata.ctime = ext2_time_to_timespec(disk.ctime);
+        self.metadata.file_type = disk.file_type();
+        self.metadata.mode = (disk.mode & !S_IFMT) as u32;
+        self.metadata.nlinks = disk.links_count as usize;
+        self.metadata.uid = disk.uid as usize;
+        self.metadata.gid = disk.gid as usize;
+        self.metadata.raw_dev = match self.metadata.file_type {
+            FileType::CharDevice | FileType::BlockDevice => disk.rdev(),
+            _ => 0,
+        };
+    }
+
+    /// @brief 把inode写回磁盘
+    fn flush(&mut self, fs: &Ext2FileSystem) -> Result<(), SystemError> {
+        fs.write_disk_inode(self.ino, &self.disk)?;
+        self.inode_dirty = false;
+        self.update_metadata(fs);
+        return Ok(());
+    }
+
+    fn check_writable(&self) -> Result<(), SystemError> {
+        if self.fs.upgrade().unwrap().is_read_only() {
+            return Err(SystemError::EROFS);
+        }
+        return Ok(());
+    }
+
+    /// @brief 把修改时间和状态改变时间设置为当前时间（只修改内存中的inode）
+    fn touch_modified(&mut self) {
+        let now: u32 = TimeSpec::now().tv_sec as u32;
+        self.disk.mtime = now;
+        self.disk.ctime = now;
+        self.inode_dirty = true;
+    }
+
+    /// @brief 文件被读取之后，更新访问时间
+    ///
+    /// 与Linux的relatime相同：只有访问时间早于修改时间，或者距离上一次更新已经超过一天时，才写回磁盘
+    fn touch_accessed(&mut self, fs: &Ext2FileSystem) -> Result<(), SystemError> {
+        if fs.is_read_only() {
+            return Ok(());
+        }
+        let now: u32 = TimeSpec::now().tv_sec as u32;
+        let disk: &Ext2DiskInode = &self.disk;
+        if disk.atime > disk.mtime
+            && disk.atime > disk.ctime
+            && now.saturating_sub(disk.atime) < ATIME_UPDATE_INTERVAL
+        {
+            return Ok(());
+        }
+        self.disk.atime = now;
+        return self.flush(fs);
+    }
+
+    /// @brief 文件的数据是否存储在数据块中
+    ///
+    /// 设备文件的i_block存放的是设备号，快速符号链接的i_block存放的是目标路径，它们都没有数据块
+    fn has_data_blocks(&self, fs: &Ext2FileSystem) -> bool {
+        return match self.metadata.file_type {
+            FileType::File | FileType::Dir => true,
+            FileType::SymLink => !self.disk.is_fast_symlink(fs.block_size),
+            _ => false,
+        };
+    }
+
+    /// @brief 获取文件中的第index个块所在的磁盘块
+    ///
+    /// @return Ok(0) 这个块是文件中的空洞
+    fn bmap(&self, fs: &Ext2FileSystem, index: u64) -> Result<u32, SystemError> {
+        let (path, depth) = fs.block_path(index)?;
+        let mut block: u32 = self.disk.block[path[0] as usize];
+        for i in 1..depth {
+            if block == 0 {
+                return Ok(0);
+            }
+            block = fs.read_block_ptr(block, path[i])?;
+        }
+        return Ok(block);
+    }
+
+    /// @brief 获取文件中的第index个块所在的磁盘块。如果这个块还没有被分配，那么分配它（以及所需的间接块）
+    ///
+    /// @return Ok((磁盘块号, 是否是新分配的块))
+    fn bmap_alloc(&mut self, fs: &Ext2FileSystem, index: u64) -> Result<(u32, bool), SystemError> {
+        let (path, depth) = fs.block_path(index)?;
+        let sectors_per_block: u32 = (fs.block_size / 512) as u32;
+        let goal: u32 = match self.last_alloc {
+            0 => fs.group_first_block(fs.inode_group(self.ino)),
+            last => last + 1,
+        };
+
+        let mut block: u32 = self.disk.block[path[0] as usize];
+        let mut new: bool = false;
+        if block == 0 {
+            block = fs.alloc_block(goal)?;
+            self.disk.blocks += sectors_per_block;
+            self.disk.block[path[0] as usize] = block;
+            self.inode_dirty = true;
+            new = true;
+            // 新分配的间接块必须清零
+            if depth > 1 {
+                fs.zero_block(block)?;
+            }
+        }
+        for i in 1..depth {
+            let parent: u32 = block;
+            block = fs.read_block_ptr(parent, path[i])?;
+            new = block == 0;
+            if new {
+                block = fs.alloc_block(parent + 1)?;
+                self.disk.blocks += sectors_per_block;
+                self.inode_dirty = true;
+                if i < depth - 1 {
+                    fs.zero_block(block)?;
+                }
+                fs.write_block_ptr(parent, path[i], block)?;
+            }
+        }
+        if new {
+            self.last_alloc = block;
+        }
+        return Ok((block, new));
+    }
+
+    /// @brief 释放文件中，从第first个块开始的所有块
+    fn free_blocks_from(&mut self, fs: &Ext2FileSystem, first: u64) -> Result<(), SystemError> {
+        let p: u64 = fs.ptrs_per_block();
+        let mut freed: Vec<u32> = Vec::new();
+
+        for i in (first as usize)..EXT2_NDIR_BLOCKS {
+            if self.disk.block[i] != 0 {
+                freed.push(self.disk.block[i]);
+                self.disk.block[i] = 0;
+            }
+        }
+
+        let mut base: u64 = EXT2_NDIR_BLOCKS as u64;
+        let mut span: u64 = p;
+        for (level, slot) in [
+            (1, EXT2_IND_BLOCK),
+            (2, EXT2_DIND_BLOCK),
+            (3, EXT2_TIND_BLOCK),
+        ] {
+            if first < base + span && self.disk.block[slot] != 0 {
+                let start: u64 = first.saturating_sub(base);
+                if fs.free_tree(self.disk.block[slot], level, start, &mut freed)? {
+                    self.disk.block[slot] = 0;
+                }
+            }
+            base += span;
+            span *= p;
+        }
+
+        let sectors_per_block: u32 = (fs.block_size / 512) as u32;
+        self.disk.blocks = self
+            .disk
+            .blocks
+            .saturating_sub(freed.len() as u32 * sectors_per_block);
+        self.inode_dirty = true;
+        self.last_alloc = 0;
+        return fs.free_blocks(&freed);
+    }
+
+    /// @brief 从文件读取数据。文件中的空洞读取的结果为0
+    ///
+    /// @return Ok(usize) 成功读取的字节数
+    fn read(&self, fs: &Ext2FileSystem, buf: &mut [u8], offset: u64) -> Result<usize, SystemError> {
+        let size: u64 = self.disk.size;
+        if offset >= size {
+            return Ok(0);
+        }
+        let len: usize = min(buf.len() as u64, size - offset) as usize;
+        let bs: u64 = fs.block_size;
+        let mut block_buf: Vec<u8> = Vec::new();
+
+        let mut done: usize = 0;
+        while done < len {
+            let pos: u64 = offset + done as u64;
+            let in_block_offset: usize = (pos % bs) as usize;
+            let count: usize = min(bs as usize - in_block_offset, len - done);
+            let block: u32 = self.bmap(fs, pos / bs)?;
+
+            if block == 0 {
+                buf[done..done + count].fill(0);
+            } else if count == bs as usize {
+                fs.read_block(block, &mut buf[done..done + count])?;
+            } else {
+                block_buf.resize(bs as usize, 0);
+                fs.read_block(block, &mut block_buf)?;
+                buf[done..done + count]
+                    .copy_from_slice(&block_buf[in_block_offset..in_block_offset + count]);
+            }
+            done += count;
+        }
+        return Ok(len);
+    }
+
+    /// @brief 向文件写入数据。如果写入的范围超出了文件的大小，会扩展文件
+    ///
+    /// @return Ok(usize) 成功写入的字节数
+    fn write(
+        &mut self,
+        fs: &Ext2FileSystem,
+        buf: &[u8],
+        offset: u64,
+    ) -> Result<usize, SystemError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let end: u64 = offset
+            .checked_add(buf.len() as u64)
+            .ok_or(SystemError::EFBIG)?;
+        if end > fs.max_file_size() {
+            return Err(SystemError::EFBIG);
+        }
+        let bs: u64 = fs.block_size;
+        let mut block_buf: Vec<u8> = Vec::new();
+
+        let mut done: usize = 0;
+        while done < buf.len() {
+            let pos: u64 = offset + done as u64;
+            let in_block_offset: usize = (pos % bs) as usize;
+            let count: usize = min(bs as usize - in_block_offset, buf.len() - done);
+            let (block, new) = self.bmap_alloc(fs, pos / bs)?;
+
+            if count == bs as usize {
+                fs.write_block(block, &buf[done..done + count])?;
+            } else {
+                // 只写入块中的一部分：新分配的块先清零，已有的块先读出
+                block_buf.resize(bs as usize, 0);
+                if new {
+                    block_buf.fill(0);
+                } else {
+                    fs.read_block(block, &mut block_buf)?;
+                }
+                block_buf[in_block_offset..in_block_offset + count]
+                    .copy_from_slice(&buf[done..done + count]);
+                fs.write_block(block, &block_buf)?;
+            }
+            done += count;
+        }
+
+        if end > self.disk.size {
+            self.set_size(fs, end)?;
+        }
+        if self.inode_dirty {
+            self.flush(fs)?;
+        }
+        return Ok(buf.len());
+    }
+
+    /// @brief 设置文件的大小（只修改inode，不分配、释放块）
+    fn set_size(&mut self, fs: &Ext2FileSystem, size: u64) -> Result<(), SystemError> {
+        if size > EXT2_MAX_SMALL_FILE_SIZE {
+            fs.set_large_file()?;
+        }
+        self.disk.size = size;
+        self.inode_dirty = true;
+        return Ok(());
+    }
+
+    /// @brief 改变文件的大小
+    ///
+    /// 扩展文件时不分配块，新增的部分是文件中的空洞，读取的结果为0
+    fn truncate(&mut self, fs: &Ext2FileSystem, len: u64) -> Result<(), SystemError> {
+        if len > fs.max_file_size() {
+            return Err(SystemError::EFBIG);
+        }
+        let bs: u64 = fs.block_size;
+        if len < self.disk.size {
+            self.free_blocks_from(fs, (len + bs - 1) / bs)?;
+            // 最后一个块中超出文件大小的部分清零，以免以后扩展文件时，读到旧的数据
+            if len % bs != 0 {
+                let block: u32 = self.bmap(fs, len / bs)?;
+                if block != 0 {
+                    let mut block_buf: Vec<u8> = vec![0; bs as usize];
+                    fs.read_block(block, &mut block_buf)?;
+                    block_buf[(len % bs) as usize..].fill(0);
+                    fs.write_block(block, &block_buf)?;
+                }
+            }
+        }
+        self.set_size(fs, len)?;
+        return self.flush(fs);
+    }
+
+    /// @brief 把页缓存中的脏页写回磁盘
+    fn writeback_pages(&mut self) -> Result<(), SystemError> {
+        let fs: Arc<Ext2FileSystem> = self.fs.upgrade().unwrap();
+        if let Some(page_cache) = self.page_cache.clone() {
+            let file_size: usize = self.disk.size as usize;
+            page_cache.writeback(file_size, |index, data| {
+                self.write(&fs, data, (index * MMArch::PAGE_SIZE) as u64)?;
+                return Ok(());
+            })?;
+        }
+        // 只写入了页缓存的写操作，修改的时间还没有刷入磁盘
+        if self.inode_dirty {
+            self.flush(&fs)?;
+        }
+        return Ok(());
+    }
+
+    /// @brief 读取符号链接的目标路径
+    fn read_link(
+        &self,
+        fs: &Ext2FileSystem,
+        buf: &mut [u8],
+        offset: u64,
+    ) -> Result<usize, SystemError> {
+        if !self.disk.is_fast_symlink(fs.block_size) {
+            return self.read(fs, buf, offset);
+        }
+        let data: Vec<u8> = self.disk.inline_data();
+        let size: usize = min(self.disk.size as usize, data.len());
+        if offset as usize >= size {
+            return Ok(0);
+        }
+        let len: usize = min(buf.len(), size - offset as usize);
+        buf[..len].copy_from_slice(&data[offset as usize..offset as usize + len]);
+        return Ok(len);
+    }
+
+    /// @brief 读取目录中的第index个块
+    ///
+    /// @return Ok(磁盘块号)
+    fn read_dir_block(
+        &self,
+        fs: &Ext2FileSystem,
+        index: u64,
+        buf: &mut [u8],
+    ) -> Result<u32, SystemError> {
+        let block: u32 = self.bmap(fs, index)?;
+        if block == 0 {
+            kerror!("ext2: directory {} has a hole at block {}", self.ino, index);
+            return Err(SystemError::EIO);
+        }
+        fs.read_block(block, buf)?;
+        return Ok(block);
+    }
+
+    /// @brief 目录占用的块数
+    #[inline]
+    fn dir_blocks(&self, fs: &Ext2FileSystem) -> u64 {
+        return self.disk.size / fs.block_size;
+    }
+
+    /// @brief 读取目录中的所有目录项（包括"."和".."）
+    fn dir_entries(&self, fs: &Ext2FileSystem) -> Result<Vec<Ext2DirEntry>, SystemError> {
+        if self.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        let mut result: Vec<Ext2DirEntry> = Vec::new();
+        let mut buf: Vec<u8> = vec![0; fs.block_size as usize];
+        for index in 0..self.dir_blocks(fs) {
+            self.read_dir_block(fs, index, &mut buf)?;
+            result.extend(Ext2DirBlockIter::new(&buf).map(|(_, e)| e));
+        }
+        return Ok(result);
+    }
+
+    /// @brief 在目录中查找名为name的目录项
+    ///
+    /// @return Ok(inode号)
+    fn dir_lookup(&self, fs: &Ext2FileSystem, name: &[u8]) -> Result<u32, SystemError> {
+        let mut buf: Vec<u8> = vec![0; fs.block_size as usize];
+        for index in 0..self.dir_blocks(fs) {
+            self.read_dir_block(fs, index, &mut buf)?;
+            if let Some((_, e)) = Ext2DirBlockIter::new(&buf).find(|(_, e)| e.name == name) {
+                return Ok(e.inode);
+            }
+        }
+        return Err(SystemError::ENOENT);
+    }
+
+    /// @brief 向目录中添加一个目录项。如果目录中没有足够的空间，会为目录分配一个新的块
+    fn dir_add(&mut self, fs: &Ext2FileSystem, entry: &Ext2DirEntry) -> Result<(), SystemError> {
+        let bs: u64 = fs.block_size;
+        let mut buf: Vec<u8> = vec![0; bs as usize];
+        let mut done: bool = false;
+        for index in 0..self.dir_blocks(fs) {
+            let block: u32 = self.read_dir_block(fs, index, &mut buf)?;
+            if insert_entry(&mut buf, entry) {
+                fs.write_block(block, &buf)?;
+                done = true;
+                break;
+            }
+        }
+
+        if !done {
+            let (block, _) = self.bmap_alloc(fs, self.dir_blocks(fs))?;
+            let mut buf: Vec<u8> = empty_dir_block(bs as usize);
+            insert_entry(&mut buf, entry);
+            fs.write_block(block, &buf)?;
+            self.set_size(fs, self.disk.size + bs)?;
+        }
+        self.touch_modified();
+        return self.flush(fs);
+    }
+
+    /// @brief 从目录中删除名为name的目录项
+    ///
+    /// @return Ok(被删除的目录项指向的inode号)
+    fn dir_remove(&mut self, fs: &Ext2FileSystem, name: &[u8]) -> Result<u32, SystemError> {
+        let mut buf: Vec<u8> = vec![0; fs.block_size as usize];
+        for index in 0..self.dir_blocks(fs) {
+            let block: u32 = self.read_dir_block(fs, index, &mut buf)?;
+            if let Some(ino) = remove_entry(&mut buf, name) {
+                fs.write_block(block, &buf)?;
+                self.touch_modified();
+                self.flush(fs)?;
+                return Ok(ino);
+            }
+        }
+        return Err(SystemError::ENOENT);
+    }
+
+    /// @brief 把目录中名为name的目录项改为指向另一个inode
+    ///
+    /// 只需要改写一个块，因此重命名时可以用它原子地替换已经存在的目标
+    ///
+    /// @return Ok(目录项原来指向的inode号)
+    fn dir_set_entry(
+        &mut self,
+        fs: &Ext2FileSystem,
+        name: &[u8],
+        ino: u32,
+        file_type: u8,
+    ) -> Result<u32, SystemError> {
+        let mut buf: Vec<u8> = vec![0; fs.block_size as usize];
+        for index in 0..self.dir_blocks(fs) {
+            let block: u32 = self.read_dir_block(fs, index, &mut buf)?;
+            let found = Ext2DirBlockIter::new(&buf).find(|(_, e)| e.name == name);
+            if let Some((offset, mut entry)) = found {
+                let old_ino: u32 = entry.inode;
+                entry.inode = ino;
+                entry.file_type = file_type;
+                entry.write_to(&mut buf, offset);
+                fs.write_block(block, &buf)?;
+                self.touch_modified();
+                self.flush(fs)?;
+                return Ok(old_ino);
+            }
+        }
+        return Err(SystemError::ENOENT);
+    }
+
+    /// @brief 判断目录是否为空（只包含"."和".."）
+    fn dir_is_empty(&self, fs: &Ext2FileSystem) -> Result<bool, SystemError> {
+        return Ok(self
+            .dir_entries(fs)?
+            .iter()
+            .all(|e| e.name == b"." || e.name == b".."));
+    }
+
+    /// @brief 修改目录中".."目录项指向的inode（目录被移动到其他目录下时使用）
+    fn dir_set_parent(&mut self, fs: &Ext2FileSystem, parent_ino: u32) -> Result<(), SystemError> {
+        let mut buf: Vec<u8> = vec![0; fs.block_size as usize];
+        let block: u32 = self.read_dir_block(fs, 0, &mut buf)?;
+        let found = Ext2DirBlockIter::new(&buf).find(|(_, e)| e.name == b"..");
+        match found {
+            Some((offset, mut entry)) => {
+                entry.inode = parent_ino;
+                entry.write_to(&mut buf, offset);
+                return fs.write_block(block, &buf);
+            }
+            None => {
+                kerror!("ext2: directory {} has no '..' entry", self.ino);
+                return Err(SystemError::EIO);
+            }
+        }
+    }
+
+    /// @brief 查找名为name的子inode（如果不在缓存中，会从磁盘读取，并加入缓存）
+    fn find(&mut self, name: &str) -> Result<Arc<LockedExt2Inode>, SystemError> {
+        if self.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        if let Some(inode) = self.children.get(name) {
+            return Ok(inode.clone());
+        }
+        if name.len() > EXT2_NAME_LEN {
+            return Err(SystemError::ENAMETOOLONG);
+        }
+
+        let fs: Arc<Ext2FileSystem> = self.fs.upgrade().unwrap();
+        let ino: u32 = self.dir_lookup(&fs, name.as_bytes())?;
+        let inode: Arc<LockedExt2Inode> = fs.get_inode(ino, self.self_ref.clone())?;
+        self.children.insert(String::from(name), inode.clone());
+        return Ok(inode);
+    }
+
+    /// @brief 释放已经没有硬链接的inode所占用的块，以及inode本身
+    fn release(&mut self, fs: &Ext2FileSystem) -> Result<(), SystemError> {
+        // 扩展属性块可能被多个inode共享，这里不回收它
+        if self.has_data_blocks(fs) {
+            self.free_blocks_from(fs, 0)?;
+        }
+        self.disk.size = 0;
+        self.disk.dtime = TimeSpec::now().tv_sec as u32;
+        fs.write_disk_inode(self.ino, &self.disk)?;
+        return fs.free_inode(self.ino, self.metadata.file_type == FileType::Dir);
+    }
+}
+
+impl Drop for Ext2Inode {
+    fn drop(&mut self) {
+        let fs: Arc<Ext2FileSystem> = match self.fs.upgrade() {
+            Some(fs) => fs,
+            None => return,
+        };
+        let mut inodes = fs.inodes.lock();
+        if inodes.get(&self.ino).map(|w| w.strong_count() == 0) == Some(true) {
+            inodes.remove(&self.ino);
+        }
+        drop(inodes);
+
+        // 已经被删除的文件不再被任何人使用，此时才回收它的块和inode
+        if self.disk.links_count == 0 && !fs.is_read_only() {
+            if let Err(e) = self.release(&fs) {
+                kerror!("ext2: failed to release inode {}: {:?}", self.ino, e);
+            }
+        }
+    }
+}
+
+impl LockedExt2Inode {
+    /// @brief 根据磁盘上的inode，创建一个新的内存中的inode
+    pub fn new(
+        fs: &Arc<Ext2FileSystem>,
+        ino: u32,
+        disk: Ext2DiskInode,
+        parent: Weak<LockedExt2Inode>,
+    ) -> Arc<LockedExt2Inode> {
+        let file_type: FileType = disk.file_type();
+        let inode: Arc<LockedExt2Inode> = Arc::new(LockedExt2Inode(SpinLock::new(Ext2Inode {
+            parent,
+            self_ref: Weak::default(),
+            children: BTreeMap::new(),
+            fs: Arc::downgrade(fs),
+            ino,
+            disk,
+            metadata: Metadata {
+                inode_id: generate_inode_id(),
+                ..Default::default()
+            },
+            page_cache: None,
+            inode_dirty: false,
+            last_alloc: 0,
+        })));
+
+        let mut guard: SpinLockGuard<Ext2Inode> = inode.0.lock();
+        guard.self_ref = Arc::downgrade(&inode);
+        if file_type == FileType::File {
+            let backend: Weak<dyn PageCacheBackend> = Arc::downgrade(&inode) as _;
+            guard.page_cache = Some(PageCache::new(backend));
+        }
+        guard.update_metadata(fs);
+        drop(guard);
+
+        return inode;
+    }
+
+    /// @brief 获取inode在磁盘上的inode号
+    pub fn ino(&self) -> u32 {
+        return self.0.lock().ino;
+    }
+}
+
+impl PageCacheBackend for LockedExt2Inode {
+    fn writeback(&self) -> Result<(), SystemError> {
+        return self.0.lock().writeback_pages();
+    }
+
+    fn try_writeback(&self) -> bool {
+        match self.0.try_lock() {
+            Ok(mut guard) => return guard.writeback_pages().is_ok(),
+            Err(_) => return false,
+        }
+    }
+}
+
+impl IndexNode for LockedExt2Inode {
+    fn read_at(
+        &self,
+        offset: usize,
+        len: usize,
+        buf: &mut [u8],
+        _data: &mut FilePrivateData,
+    ) -> Result<usize, SystemError> {
+        let mut guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+        match guard.metadata.file_type {
+            FileType::File => {}
+            FileType::Dir => return Err(SystemError::EISDIR),
+            FileType::SymLink => return guard.read_link(&fs, &mut buf[0..len], offset as u64),
+            _ => return Err(SystemError::EINVAL),
+        }
+
+        let page_cache: Option<Arc<PageCache>> = guard.page_cache.clone();
+        let file_size: usize = guard.disk.size as usize;
+        let r = match page_cache {
+            // 通过页缓存读取
+            Some(pc) => pc.read(offset, &mut buf[0..len], file_size, |index, page| {
+                guard.read(&fs, page, (index * MMArch::PAGE_SIZE) as u64)
+            }),
+            None => guard.read(&fs, &mut buf[0..len], offset as u64),
+        };
+        if r.is_ok() {
+            guard.touch_accessed(&fs)?;
+        }
+        return r;
+    }
+
+    fn write_at(
+        &self,
+        offset: usize,
+        len: usize,
+        buf: &[u8],
+        _data: &mut FilePrivateData,
+    ) -> Result<usize, SystemError> {
+        let mut guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        guard.check_writable()?;
+        match guard.metadata.file_type {
+            FileType::File => {}
+            FileType::Dir => return Err(SystemError::EISDIR),
+            _ => return Err(SystemError::EINVAL),
+        }
+        let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+        let page_cache: Option<Arc<PageCache>> = guard.page_cache.clone();
+
+        // 修改时间和扩展后的文件大小，都等到写回页缓存时再刷入磁盘
+        guard.touch_modified();
+
+        let file_size: usize = guard.disk.size as usize;
+        let r = match page_cache {
+            // 数据只写入页缓存，等到sync时再写回
+            Some(pc) => {
+                let end: usize = offset.checked_add(len).ok_or(SystemError::EFBIG)?;
+                if end > file_size {
+                    // 扩展文件长度的写操作只修改inode中的文件大小，新增的部分先作为文件中的空洞，
+                    // 等到写回页缓存时再分配块
+                    if end as u64 > fs.max_file_size() {
+                        return Err(SystemError::EFBIG);
+                    }
+                    guard.set_size(&fs, end as u64)?;
+                }
+                pc.write(offset, &buf[0..len], file_size, |index, page| {
+                    guard.read(&fs, page, (index * MMArch::PAGE_SIZE) as u64)
+                })
+            }
+            None => guard.write(&fs, &buf[0..len], offset as u64),
+        };
+        guard.update_metadata(&fs);
+        return r;
+    }
+
+    fn poll(&self) -> Result<PollStatus, SystemError> {
+        let inode: SpinLockGuard<Ext2Inode> = self.0.lock();
+        if inode.metadata.file_type == FileType::Dir {
+            return Err(SystemError::EISDIR);
+        }
+        return Ok(PollStatus::READ | PollStatus::WRITE);
+    }
+
+    fn create_with_data(
+        &self,
+        name: &str,
+        file_type: FileType,
+        mode: u32,
+        data: usize,
+    ) -> Result<Arc<dyn IndexNode>, SystemError> {
+        let mut guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        guard.check_writable()?;
+        if guard.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        check_name(name)?;
+        match guard.find(name) {
+            Ok(_) => return Err(SystemError::EEXIST),
+            Err(SystemError::ENOENT) => {}
+            Err(e) => return Err(e),
+        }
+        let is_dir: bool = file_type == FileType::Dir;
+        // 子目录的".."会增加父目录的硬链接数
+        if is_dir && guard.disk.links_count >= EXT2_LINK_MAX {
+            return Err(SystemError::EMLINK);
+        }
+
+        let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+        let ino: u32 = fs.alloc_inode(guard.ino, is_dir)?;
+        let mut disk: Ext2DiskInode = Ext2DiskInode::new(
+            file_type_to_mode(file_type) | (mode & 0o7777) as u16,
+            fs.inode_size as usize,
+        );
+        match file_type {
+            FileType::CharDevice | FileType::BlockDevice => disk.set_rdev(data),
+            _ => {}
+        }
+        if let Err(e) = fs.write_disk_inode(ino, &disk) {
+            fs.free_inode(ino, is_dir)?;
+            return Err(e);
+        }
+
+        let inode: Arc<LockedExt2Inode> =
+            LockedExt2Inode::new(&fs, ino, disk, guard.self_ref.clone());
+        fs.inodes.lock().insert(ino, Arc::downgrade(&inode));
+
+        let mut inode_guard: SpinLockGuard<Ext2Inode> = inode.0.lock();
+        let r = (|| -> Result<(), SystemError> {
+            if is_dir {
+                // 新的目录中包含"."和".."两个目录项
+                let (block, _) = inode_guard.bmap_alloc(&fs, 0)?;
+                let buf: Vec<u8> = new_dir_block(
+                    fs.block_size as usize,
+                    ino,
+                    guard.ino,
+                    fs.dirent_type(FileType::Dir),
+                );
+                fs.write_block(block, &buf)?;
+                inode_guard.disk.links_count = 2;
+                inode_guard.set_size(&fs, fs.block_size)?;
+                inode_guard.flush(&fs)?;
+            }
+            let entry = Ext2DirEntry::new(ino, name.as_bytes(), fs.dirent_type(file_type));
+            return guard.dir_add(&fs, &entry);
+        })();
+        if let Err(e) = r {
+            // 新的inode会在被释放时回收
+            inode_guard.disk.links_count = 0;
+            return Err(e);
+        }
+        drop(inode_guard);
+
+        if is_dir {
+            guard.disk.links_count += 1;
+            guard.flush(&fs)?;
+        }
+        guard.children.insert(String::from(name), inode.clone());
+        return Ok(inode);
+    }
+
+    fn symlink(&self, name: &str, target: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+        if target.is_empty() {
+            return Err(SystemError::ENOENT);
+        }
+        let fs: Arc<Ext2FileSystem> = self.0.lock().fs.upgrade().unwrap();
+        if target.len() > MAX_PATHLEN || target.len() > fs.block_size as usize {
+            return Err(SystemError::ENAMETOOLONG);
+        }
+
+        let result: Arc<dyn IndexNode> = self.create(name, FileType::SymLink, 0o777)?;
+        let r = {
+            let mut guard: SpinLockGuard<Ext2Inode> =
+                result.downcast_ref::<LockedExt2Inode>().unwrap().0.lock();
+            if target.len() < EXT2_FAST_SYMLINK_MAX {
+                // 目标路径足够短时，直接存储在inode中（快速符号链接）
+                guard.disk.set_inline_data(target.as_bytes());
+                guard
+                    .set_size(&fs, target.len() as u64)
+                    .and_then(|_| guard.flush(&fs))
+            } else {
+                guard.write(&fs, target.as_bytes(), 0).map(|_| ())
+            }
+        };
+        if let Err(e) = r {
+            self.unlink(name)?;
+            return Err(e);
+        }
+        return Ok(result);
+    }
+
+    fn link(&self, name: &str, other: &Arc<dyn IndexNode>) -> Result<(), SystemError> {
+        let other: &LockedExt2Inode = other
+            .downcast_ref::<LockedExt2Inode>()
+            .ok_or(SystemError::EXDEV)?;
+        if core::ptr::eq(self, other) {
+            return Err(SystemError::EPERM);
+        }
+        let mut guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        guard.check_writable()?;
+        if guard.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        let mut other_guard: SpinLockGuard<Ext2Inode> = other.0.lock();
+        if !guard.fs.ptr_eq(&other_guard.fs) {
+            return Err(SystemError::EXDEV);
+        }
+        // 不允许为文件夹创建硬链接
+        if other_guard.metadata.file_type == FileType::Dir {
+            return Err(SystemError::EPERM);
+        }
+        if other_guard.disk.links_count >= EXT2_LINK_MAX {
+            return Err(SystemError::EMLINK);
+        }
+        check_name(name)?;
+        match guard.find(name) {
+            Ok(_) => return Err(SystemError::EEXIST),
+            Err(SystemError::ENOENT) => {}
+            Err(e) => return Err(e),
+        }
+
+        let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+        let entry = Ext2DirEntry::new(
+            other_guard.ino,
+            name.as_bytes(),
+            fs.dirent_type(other_guard.metadata.file_type),
+        );
+        guard.dir_add(&fs, &entry)?;
+
+        // 增加硬链接计数
+        other_guard.disk.links_count += 1;
+        other_guard.disk.ctime = TimeSpec::now().tv_sec as u32;
+        other_guard.flush(&fs)?;
+        guard
+            .children
+            .insert(String::from(name), other_guard.self_ref.upgrade().unwrap());
+        return Ok(());
+    }
+
+    fn unlink(&self, name: &str) -> Result<(), SystemError> {
+        let mut guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        guard.check_writable()?;
+        if guard.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        if name == "." || name == ".." {
+            return Err(SystemError::EISDIR);
+        }
+        let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+        let target: Arc<LockedExt2Inode> = guard.find(name)?;
+        // 对目标inode上锁，以防更改
+        let mut target_guard: SpinLockGuard<Ext2Inode> = target.0.lock();
+        if target_guard.metadata.file_type == FileType::Dir {
+            return Err(SystemError::EISDIR);
+        }
+
+        guard.dir_remove(&fs, name.as_bytes())?;
+        guard.children.remove(name);
+
+        // 减少硬链接计数。文件可能仍被打开，因此块要等到inode被释放时才回收（见Ext2Inode的drop）
+        target_guard.disk.links_count = target_guard.disk.links_count.saturating_sub(1);
+        target_guard.disk.ctime = TimeSpec::now().tv_sec as u32;
+        target_guard.flush(&fs)?;
+        return Ok(());
+    }
+
+    fn rmdir(&self, name: &str) -> Result<(), SystemError> {
+        let mut guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        guard.check_writable()?;
+        if guard.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        match name {
+            "." => return Err(SystemError::EINVAL),
+            ".." => return Err(SystemError::ENOTEMPTY),
+            _ => {}
+        }
+        let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+        let target: Arc<LockedExt2Inode> = guard.find(name)?;
+        // 对目标inode上锁，以防更改
+        let mut target_guard: SpinLockGuard<Ext2Inode> = target.0.lock();
+        if target_guard.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        if !target_guard.dir_is_empty(&fs)? {
+            return Err(SystemError::ENOTEMPTY);
+        }
+
+        guard.dir_remove(&fs, name.as_bytes())?;
+        guard.children.remove(name);
+
+        // 目录被删除后，不再有任何目录项指向它。它的".."也不再指向父目录
+        target_guard.disk.links_count = 0;
+        target_guard.disk.ctime = TimeSpec::now().tv_sec as u32;
+        target_guard.flush(&fs)?;
+        guard.disk.links_count = guard.disk.links_count.saturating_sub(1);
+        guard.flush(&fs)?;
+        return Ok(());
+    }
+
+    fn move_(
+        &self,
+        old_name: &str,
+        target: &Arc<dyn IndexNode>,
+        new_name: &str,
+    ) -> Result<(), SystemError> {
+        // 目标目录必须也是ext2文件系统的inode
+        let target: &LockedExt2Inode = target
+            .downcast_ref::<LockedExt2Inode>()
+            .ok_or(SystemError::EXDEV)?;
+
+        if old_name == "." || old_name == ".." || new_name == "." || new_name == ".." {
+            return Err(SystemError::EBUSY);
+        }
+        check_name(new_name)?;
+
+        // 跨目录移动时，由VFS保证同一时刻只有一个跨目录的移动操作，因此这里按照固定的顺序加锁不会死锁
+        let mut guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        let mut target_guard: Option<SpinLockGuard<Ext2Inode>> = match core::ptr::eq(self, target) {
+            true => None,
+            false => Some(target.0.lock()),
+        };
+        guard.check_writable()?;
+        if let Some(t) = &target_guard {
+            if !guard.fs.ptr_eq(&t.fs) {
+                return Err(SystemError::EXDEV);
+            }
+            if t.metadata.file_type != FileType::Dir {
+                return Err(SystemError::ENOTDIR);
+            }
+        }
+
+        let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+        if target_guard.is_none() && old_name == new_name {
+            return Ok(());
+        }
+        let child: Arc<LockedExt2Inode> = guard.find(old_name)?;
+
+        // 目标位置已经存在的文件会被原子地替换：直接把它的目录项改为指向被移动的inode
+        let victim: Option<Arc<LockedExt2Inode>> = {
+            let dir: &mut Ext2Inode = match &mut target_guard {
+                Some(t) => &mut **t,
+                None => &mut *guard,
+            };
+            match dir.find(new_name) {
+                Ok(v) => Some(v),
+                Err(SystemError::ENOENT) => None,
+                Err(e) => return Err(e),
+            }
+        };
+        if let Some(v) = &victim {
+            // 源与目标是同一个inode（互为硬链接），那么什么都不做
+            if Arc::ptr_eq(v, &child) {
+                return Ok(());
+            }
+            if core::ptr::eq(Arc::as_ptr(v), self) || core::ptr::eq(Arc::as_ptr(v), target) {
+                return Err(SystemError::ENOTEMPTY);
+            }
+        }
+
+        let mut child_guard: SpinLockGuard<Ext2Inode> = child.0.lock();
+        let mut victim_guard: Option<SpinLockGuard<Ext2Inode>> =
+            victim.as_ref().map(|v| v.0.lock());
+        let is_dir: bool = child_guard.metadata.file_type == FileType::Dir;
+        let victim_is_dir: bool = match &victim_guard {
+            Some(v) => {
+                let victim_is_dir: bool = v.metadata.file_type == FileType::Dir;
+                match (is_dir, victim_is_dir) {
+                    (true, false) => return Err(SystemError::ENOTDIR),
+                    (false, true) => return Err(SystemError::EISDIR),
+                    (true, true) if !v.dir_is_empty(&fs)? => return Err(SystemError::ENOTEMPTY),
+                    _ => {}
+                }
+                victim_is_dir
+            }
+            None => false,
+        };
+        let dirent_type: u8 = fs.dirent_type(child_guard.metadata.file_type);
+        let entry = Ext2DirEntry::new(child_guard.ino, new_name.as_bytes(), dirent_type);
+
+        // 先写入新的目录项（或者改写被替换的目录项），再删除旧的，这样即使中途出错，文件也不会丢失
+        match &mut target_guard {
+            None => {
+                if victim.is_some() {
+                    guard.dir_set_entry(&fs, new_name.as_bytes(), child_guard.ino, dirent_type)?;
+                } else {
+                    guard.dir_add(&fs, &entry)?;
+                }
+                guard.dir_remove(&fs, old_name.as_bytes())?;
+                guard.children.remove(old_name);
+                guard.children.insert(String::from(new_name), child.clone());
+                if victim_is_dir {
+                    // 被替换的目录的".."不再指向当前目录
+                    guard.disk.links_count = guard.disk.links_count.saturating_sub(1);
+                    guard.flush(&fs)?;
+                }
+            }
+            Some(t) => {
+                if is_dir && !victim_is_dir && t.disk.links_count >= EXT2_LINK_MAX {
+                    return Err(SystemError::EMLINK);
+                }
+                if victim.is_some() {
+                    t.dir_set_entry(&fs, new_name.as_bytes(), child_guard.ino, dirent_type)?;
+                } else {
+                    t.dir_add(&fs, &entry)?;
+                }
+                guard.dir_remove(&fs, old_name.as_bytes())?;
+                guard.children.remove(old_name);
+                t.children.insert(String::from(new_name), child.clone());
+
+                if is_dir {
+                    // 被移动的目录的".."改为指向新的父目录
+                    child_guard.dir_set_parent(&fs, t.ino)?;
+                    child_guard.parent = t.self_ref.clone();
+                    guard.disk.links_count = guard.disk.links_count.saturating_sub(1);
+                    guard.flush(&fs)?;
+                    // 被替换的目录的".."与被移动的目录的".."相互抵消
+                    if !victim_is_dir {
+                        t.disk.links_count += 1;
+                        t.flush(&fs)?;
+                    }
+                }
+            }
+        }
+        child_guard.disk.ctime = TimeSpec::now().tv_sec as u32;
+        child_guard.flush(&fs)?;
+
+        // 减少被替换的inode的硬链接计数。它可能仍被打开，因此块要等到inode被释放时才回收
+        if let Some(v) = &mut victim_guard {
+            v.disk.links_count = match victim_is_dir {
+                true => 0,
+                false => v.disk.links_count.saturating_sub(1),
+            };
+            v.disk.ctime = TimeSpec::now().tv_sec as u32;
+            v.flush(&fs)?;
+        }
+        return Ok(());
+    }
+
+    fn exchange(
+        &self,
+        old_name: &str,
+        target: &Arc<dyn IndexNode>,
+        new_name: &str,
+    ) -> Result<(), SystemError> {
+        let target: &LockedExt2Inode = target
+            .downcast_ref::<LockedExt2Inode>()
+            .ok_or(SystemError::EXDEV)?;
+
+        if old_name == "." || old_name == ".." || new_name == "." || new_name == ".." {
+            return Err(SystemError::EBUSY);
+        }
+
+        // 跨目录交换时，由VFS保证同一时刻只有一个跨目录的移动操作，因此这里按照固定的顺序加锁不会死锁
+        let mut guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        let mut target_guard: Option<SpinLockGuard<Ext2Inode>> = match core::ptr::eq(self, target) {
+            true => None,
+            false => Some(target.0.lock()),
+        };
+        guard.check_writable()?;
+        if let Some(t) = &target_guard {
+            if !guard.fs.ptr_eq(&t.fs) {
+                return Err(SystemError::EXDEV);
+            }
+            if t.metadata.file_type != FileType::Dir {
+                return Err(SystemError::ENOTDIR);
+            }
+        }
+
+        let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+        let a: Arc<LockedExt2Inode> = guard.find(old_name)?;
+        let b: Arc<LockedExt2Inode> = match &mut target_guard {
+            Some(t) => t.find(new_name)?,
+            None => guard.find(new_name)?,
+        };
+        if Arc::ptr_eq(&a, &b) {
+            return Ok(());
+        }
+        for inode in [&a, &b] {
+            if core::ptr::eq(Arc::as_ptr(inode), self) || core::ptr::eq(Arc::as_ptr(inode), target)
+            {
+                return Err(SystemError::EINVAL);
+            }
+        }
+
+        let mut a_guard: SpinLockGuard<Ext2Inode> = a.0.lock();
+        let mut b_guard: SpinLockGuard<Ext2Inode> = b.0.lock();
+        let a_type: u8 = fs.dirent_type(a_guard.metadata.file_type);
+        let b_type: u8 = fs.dirent_type(b_guard.metadata.file_type);
+        let a_is_dir: bool = a_guard.metadata.file_type == FileType::Dir;
+        let b_is_dir: bool = b_guard.metadata.file_type == FileType::Dir;
+
+        // 两个目录项互相改为指向对方原来的inode
+        guard.dir_set_entry(&fs, old_name.as_bytes(), b_guard.ino, b_type)?;
+        match &mut target_guard {
+            None => {
+                guard.dir_set_entry(&fs, new_name.as_bytes(), a_guard.ino, a_type)?;
+                guard.children.insert(String::from(old_name), b.clone());
+                guard.children.insert(String::from(new_name), a.clone());
+            }
+            Some(t) => {
+                t.dir_set_entry(&fs, new_name.as_bytes(), a_guard.ino, a_type)?;
+                guard.children.insert(String::from(old_name), b.clone());
+                t.children.insert(String::from(new_name), a.clone());
+
+                // 跨目录交换目录时，需要更新它们的".."，以及两个父目录的硬链接计数
+                if a_is_dir {
+                    a_guard.dir_set_parent(&fs, t.ino)?;
+                    a_guard.parent = t.self_ref.clone();
+                }
+                if b_is_dir {
+                    b_guard.dir_set_parent(&fs, guard.ino)?;
+                    b_guard.parent = guard.self_ref.clone();
+                }
+                if a_is_dir != b_is_dir {
+                    if a_is_dir {
+                        guard.disk.links_count = guard.disk.links_count.saturating_sub(1);
+                        t.disk.links_count += 1;
+                    } else {
+                        guard.disk.links_count += 1;
+                        t.disk.links_count = t.disk.links_count.saturating_sub(1);
+                    }
+                    guard.flush(&fs)?;
+                    t.flush(&fs)?;
+                }
+            }
+        }
+
+        let now: u32 = TimeSpec::now().tv_sec as u32;
+        a_guard.disk.ctime = now;
+        a_guard.flush(&fs)?;
+        b_guard.disk.ctime = now;
+        b_guard.flush(&fs)?;
+        return Ok(());
+    }
+
+    fn find(&self, name: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+        let mut guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        match name {
+            "" | "." => return Ok(guard.self_ref.upgrade().ok_or(SystemError::ENOENT)?),
+            ".." => return Ok(guard.parent.upgrade().ok_or(SystemError::ENOENT)?),
+            name => return Ok(guard.find(name)?),
+        }
+    }
+
+    fn get_entry_name(&self, ino: InodeId) -> Result<String, SystemError> {
+        let mut guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        if guard.metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        match ino {
+            0 => {
+                return Ok(String::from("."));
+            }
+            1 => {
+                return Ok(String::from(".."));
+            }
+            ino => {
+                for (name, child) in guard.children.iter() {
+                    if child.0.lock().metadata.inode_id == ino {
+                        return Ok(name.clone());
+                    }
+                }
+                // 子inode不在缓存中，逐个加载目录中的文件
+                let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+                for entry in guard.dir_entries(&fs)? {
+                    if entry.name == b"." || entry.name == b".." {
+                        continue;
+                    }
+                    let name: String = String::from_utf8_lossy(&entry.name).into_owned();
+                    let child: Arc<LockedExt2Inode> = guard.find(&name)?;
+                    if child.0.lock().metadata.inode_id == ino {
+                        return Ok(name);
+                    }
+                }
+                return Err(SystemError::ENOENT);
+            }
+        }
+    }
+
+    fn list(&self) -> Result<Vec<String>, SystemError> {
+        let guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+        // 磁盘上的目录中本来就包含"."和".."目录项
+        return Ok(guard
+            .dir_entries(&fs)?
+            .iter()
+            .map(|e| String::from_utf8_lossy(&e.name).into_owned())
+            .collect());
+    }
+
+    fn open(&self, _data: &mut FilePrivateData, _mode: &FileMode) -> Result<(), SystemError> {
+        return Ok(());
+    }
+
+    fn close(&self, _data: &mut FilePrivateData) -> Result<(), SystemError> {
+        return Ok(());
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        return self.0.lock().fs.upgrade().unwrap();
+    }
+
+    fn as_any_ref(&self) -> &dyn core::any::Any {
+        return self;
+    }
+
+    fn metadata(&self) -> Result<Metadata, SystemError> {
+        return Ok(self.0.lock().metadata.clone());
+    }
+
+    fn set_metadata(&self, metadata: &Metadata) -> Result<(), SystemError> {
+        let mut guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        guard.check_writable()?;
+        let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+        let disk: &mut Ext2DiskInode = &mut guard.disk;
+        disk.atime = metadata.atime.tv_sec as u32;
+        disk.mtime = metadata.mtime.tv_sec as u32;
+        disk.ctime = metadata.ctime.tv_sec as u32;
+        disk.mode = (disk.mode & S_IFMT) | (metadata.mode & 0o7777) as u16;
+        disk.uid = metadata.uid as u32;
+        disk.gid = metadata.gid as u32;
+        return guard.flush(&fs);
+    }
+
+    fn resize(&self, len: usize) -> Result<(), SystemError> {
+        let mut guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        guard.check_writable()?;
+        match guard.metadata.file_type {
+            FileType::File => {}
+            FileType::Dir => return Err(SystemError::EISDIR),
+            _ => return Err(SystemError::EINVAL),
+        }
+        let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+        // 先把页缓存中的脏页写回，再修改磁盘上的文件
+        guard.writeback_pages()?;
+        let old_size: usize = guard.disk.size as usize;
+
+        guard.touch_modified();
+        guard.truncate(&fs, len as u64)?;
+        if len < old_size {
+            if let Some(pc) = guard.page_cache.clone() {
+                pc.truncate(len);
+            }
+        }
+        return Ok(());
+    }
+
+    fn sync(&self) -> Result<(), SystemError> {
+        return self.0.lock().writeback_pages();
+    }
+
+    fn get_page(&self, index: usize) -> Result<Arc<CachedPage>, SystemError> {
+        let guard: SpinLockGuard<Ext2Inode> = self.0.lock();
+        let fs: Arc<Ext2FileSystem> = guard.fs.upgrade().unwrap();
+        let page_cache: Arc<PageCache> = guard.page_cache.clone().ok_or(SystemError::ENODEV)?;
+        let file_size: usize = guard.disk.size as usize;
+        return page_cache.get_or_read_page(index, file_size, &mut |index, page| {
+            guard.read(&fs, page, (index * MMArch::PAGE_SIZE) as u64)
+        });
+    }
+}
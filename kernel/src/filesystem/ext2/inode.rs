@@ -0,0 +1,235 @@
+#![allow(dead_code)]
+use alloc::vec::Vec;
+
+use crate::{filesystem::vfs::FileType, time::TimeSpec};
+
+/// inode中直接块指针的数量
+pub const EXT2_NDIR_BLOCKS: usize = 12;
+/// 一级间接块指针在i_block中的下标
+pub const EXT2_IND_BLOCK: usize = EXT2_NDIR_BLOCKS;
+/// 二级间接块指针在i_block中的下标
+pub const EXT2_DIND_BLOCK: usize = EXT2_IND_BLOCK + 1;
+/// 三级间接块指针在i_block中的下标
+pub const EXT2_TIND_BLOCK: usize = EXT2_DIND_BLOCK + 1;
+/// i_block数组的长度
+pub const EXT2_N_BLOCKS: usize = EXT2_TIND_BLOCK + 1;
+/// 快速符号链接（目标路径直接存储在i_block中）的最大长度
+pub const EXT2_FAST_SYMLINK_MAX: usize = EXT2_N_BLOCKS * 4;
+/// 文件的最大硬链接数
+pub const EXT2_LINK_MAX: u16 = 32000;
+
+/// i_mode中表示文件类型的位
+pub const S_IFMT: u16 = 0o170000;
+pub const S_IFSOCK: u16 = 0o140000;
+pub const S_IFLNK: u16 = 0o120000;
+pub const S_IFREG: u16 = 0o100000;
+pub const S_IFBLK: u16 = 0o060000;
+pub const S_IFDIR: u16 = 0o040000;
+pub const S_IFCHR: u16 = 0o020000;
+pub const S_IFIFO: u16 = 0o010000;
+
+/// @brief 磁盘上的inode结构体
+///
+/// 只解析驱动程序需要用到的字段。写回时，在读取到的原始数据的基础上修改，其余字段（例如扩展属性）保持不变
+#[derive(Debug, Clone, Default)]
+pub struct Ext2DiskInode {
+    /// 文件类型和权限
+    pub mode: u16,
+    /// 所有者的用户id
+    pub uid: u32,
+    /// 文件大小
+    pub size: u64,
+    /// 最后访问时间
+    pub atime: u32,
+    /// 状态改变时间
+    pub ctime: u32,
+    /// 最后修改时间
+    pub mtime: u32,
+    /// 删除时间
+    pub dtime: u32,
+    /// 所有者的组id
+    pub gid: u32,
+    /// 硬链接数
+    pub links_count: u16,
+    /// 占用的512字节扇区数（包括间接块）
+    pub blocks: u32,
+    /// inode标志
+    pub flags: u32,
+    /// 块指针：12个直接块，以及一级、二级、三级间接块
+    pub block: [u32; EXT2_N_BLOCKS],
+    /// 扩展属性所在的块
+    pub file_acl: u32,
+    /// inode的原始数据
+    raw: Vec<u8>,
+}
+
+impl Ext2DiskInode {
+    /// @brief 从inode表的原始数据中解析inode
+    pub fn parse(data: &[u8]) -> Ext2DiskInode {
+        let u32_at = |off: usize| {
+            u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]])
+        };
+        let u16_at = |off: usize| u16::from_le_bytes([data[off], data[off + 1]]);
+
+        let mode: u16 = u16_at(0);
+        let mut block = [0u32; EXT2_N_BLOCKS];
+        for (i, b) in block.iter_mut().enumerate() {
+            *b = u32_at(40 + i * 4);
+        }
+        let mut size: u64 = u32_at(4) as u64;
+        // 对于普通文件，i_dir_acl字段是文件大小的高32位
+        if mode & S_IFMT == S_IFREG {
+            size |= (u32_at(108) as u64) << 32;
+        }
+
+        return Ext2DiskInode {
+            mode,
+            uid: u16_at(2) as u32 | (u16_at(120) as u32) << 16,
+            size,
+            atime: u32_at(8),
+            ctime: u32_at(12),
+            mtime: u32_at(16),
+            dtime: u32_at(20),
+            gid: u16_at(24) as u32 | (u16_at(122) as u32) << 16,
+            links_count: u16_at(26),
+            blocks: u32_at(28),
+            flags: u32_at(32),
+            block,
+            file_acl: u32_at(104),
+            raw: data.to_vec(),
+        };
+    }
+
+    /// @brief 创建一个新的inode
+    ///
+    /// @param mode 文件类型和权限
+    /// @param inode_size inode结构体的大小
+    pub fn new(mode: u16, inode_size: usize) -> Ext2DiskInode {
+        let now: u32 = TimeSpec::now().tv_sec as u32;
+        return Ext2DiskInode {
+            mode,
+            atime: now,
+            ctime: now,
+            mtime: now,
+            links_count: 1,
+            raw: vec![0; inode_size],
+            ..Default::default()
+        };
+    }
+
+    /// @brief 生成inode的原始数据，用于写回inode表
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = self.raw.clone();
+        let mut put_u32 = |off: usize, v: u32| data[off..off + 4].copy_from_slice(&v.to_le_bytes());
+        put_u32(4, self.size as u32);
+        put_u32(8, self.atime);
+        put_u32(12, self.ctime);
+        put_u32(16, self.mtime);
+        put_u32(20, self.dtime);
+        put_u32(28, self.blocks);
+        put_u32(32, self.flags);
+        for (i, b) in self.block.iter().enumerate() {
+            put_u32(40 + i * 4, *b);
+        }
+        put_u32(104, self.file_acl);
+        if self.mode & S_IFMT == S_IFREG {
+            put_u32(108, (self.size >> 32) as u32);
+        }
+
+        let mut put_u16 = |off: usize, v: u16| data[off..off + 2].copy_from_slice(&v.to_le_bytes());
+        put_u16(0, self.mode);
+        put_u16(2, self.uid as u16);
+        put_u16(24, self.gid as u16);
+        put_u16(26, self.links_count);
+        put_u16(120, (self.uid >> 16) as u16);
+        put_u16(122, (self.gid >> 16) as u16);
+        return data;
+    }
+
+    /// @brief 获取文件的类型
+    pub fn file_type(&self) -> FileType {
+        return match self.mode & S_IFMT {
+            S_IFDIR => FileType::Dir,
+            S_IFLNK => FileType::SymLink,
+            S_IFCHR => FileType::CharDevice,
+            S_IFBLK => FileType::BlockDevice,
+            S_IFIFO => FileType::Pipe,
+            S_IFSOCK => FileType::Socket,
+            _ => FileType::File,
+        };
+    }
+
+    /// @brief 判断符号链接是否是快速符号链接（目标路径直接存储在i_block中，没有数据块）
+    ///
+    /// @param block_size 文件系统的块大小
+    pub fn is_fast_symlink(&self, block_size: u64) -> bool {
+        if self.mode & S_IFMT != S_IFLNK {
+            return false;
+        }
+        // 扩展属性块也会被计入i_blocks
+        let ea_blocks: u32 = match self.file_acl {
+            0 => 0,
+            _ => (block_size / 512) as u32,
+        };
+        return self.blocks == ea_blocks;
+    }
+
+    /// @brief 快速符号链接的目标路径所在的字节
+    pub fn inline_data(&self) -> Vec<u8> {
+        return self.block.iter().flat_map(|b| b.to_le_bytes()).collect();
+    }
+
+    /// @brief 把数据直接存储在i_block中（用于快速符号链接）
+    pub fn set_inline_data(&mut self, data: &[u8]) {
+        let mut buf = [0u8; EXT2_FAST_SYMLINK_MAX];
+        buf[..data.len()].copy_from_slice(data);
+        for (i, b) in self.block.iter_mut().enumerate() {
+            *b = u32::from_le_bytes([buf[i * 4], buf[i * 4 + 1], buf[i * 4 + 2], buf[i * 4 + 3]]);
+        }
+    }
+
+    /// @brief 获取设备文件的设备号
+    ///
+    /// 旧的编码方式（主、次设备号都小于256）存储在i_block[0]中，新的编码方式存储在i_block[1]中，
+    /// 两者都与Linux的new_encode_dev()兼容
+    pub fn rdev(&self) -> usize {
+        return match self.block[0] {
+            0 => self.block[1] as usize,
+            old => old as usize,
+        };
+    }
+
+    /// @brief 设置设备文件的设备号
+    pub fn set_rdev(&mut self, rdev: usize) {
+        let major: usize = (rdev >> 8) & 0xfff;
+        let minor: usize = (rdev & 0xff) | ((rdev >> 12) & !0xff);
+        if major < 256 && minor < 256 {
+            self.block[0] = rdev as u32;
+            self.block[1] = 0;
+        } else {
+            self.block[0] = 0;
+            self.block[1] = rdev as u32;
+        }
+    }
+}
+
+/// @brief 把ext2的时间戳转换为TimeSpec
+#[inline]
+pub fn ext2_time_to_timespec(time: u32) -> TimeSpec {
+    return TimeSpec::new(time as i64, 0);
+}
+
+/// @brief 根据文件类型，获取i_mode中的类型位
+pub fn file_type_to_mode(file_type: FileType) -> u16 {
+    return match file_type {
+        FileType::Dir => S_IFDIR,
+        FileType::SymLink => S_IFLNK,
+        FileType::CharDevice => S_IFCHR,
+        FileType::BlockDevice => S_IFBLK,
+        FileType::Pipe => S_IFIFO,
+        FileType::Socket => S_IFSOCK,
+        FileType::File => S_IFREG,
+        // 匿名inode不会被写入磁盘
+        FileType::AnonInode => 0,
+    };
+}
@@ -0,0 +1,4 @@
+pub mod dentry;
+pub mod fs;
+pub mod inode;
+pub mod superblock;
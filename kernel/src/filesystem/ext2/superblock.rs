@@ -0,0 +1,342 @@
+#![allow(dead_code)]
+use alloc::{sync::Arc, vec::Vec};
+
+use crate::{
+    driver::base::block::{block_device::LBA_SIZE, disk_info::Partition, SeekFrom},
+    kerror,
+    libs::vec_cursor::VecCursor,
+    syscall::SystemError,
+};
+
+/// ext2超级块的魔数
+pub const EXT2_MAGIC: u16 = 0xef53;
+/// 超级块在分区内的字节偏移量
+pub const EXT2_SUPERBLOCK_OFFSET: u64 = 1024;
+/// 超级块的大小
+pub const EXT2_SUPERBLOCK_SIZE: usize = 1024;
+/// 根目录的inode号
+pub const EXT2_ROOT_INO: u32 = 2;
+/// 修订版本0的inode大小
+pub const EXT2_GOOD_OLD_INODE_SIZE: u16 = 128;
+/// 修订版本0中，第一个可以被普通文件使用的inode号
+pub const EXT2_GOOD_OLD_FIRST_INO: u32 = 11;
+/// 修订版本0（不支持动态的inode大小和特性标志）
+pub const EXT2_GOOD_OLD_REV: u32 = 0;
+/// 块组描述符的大小
+pub const EXT2_GROUP_DESC_SIZE: usize = 32;
+
+/// 文件系统被正常卸载
+pub const EXT2_VALID_FS: u16 = 1;
+/// 文件系统中存在错误
+pub const EXT2_ERROR_FS: u16 = 2;
+
+bitflags! {
+    /// @brief 兼容特性：即使不支持，也可以正常读写文件系统
+    pub struct FeatureCompat: u32 {
+        const DIR_PREALLOC = 0x0001;
+        const IMAGIC_INODES = 0x0002;
+        const HAS_JOURNAL = 0x0004;
+        const EXT_ATTR = 0x0008;
+        const RESIZE_INODE = 0x0010;
+        const DIR_INDEX = 0x0020;
+    }
+}
+
+bitflags! {
+    /// @brief 不兼容特性：不支持其中的任何一个时，都不能挂载文件系统
+    pub struct FeatureIncompat: u32 {
+        const COMPRESSION = 0x0001;
+        const FILETYPE = 0x0002;
+        const RECOVER = 0x0004;
+        const JOURNAL_DEV = 0x0008;
+        const META_BG = 0x0010;
+        const EXTENTS = 0x0040;
+        const BIT64 = 0x0080;
+        const MMP = 0x0100;
+        const FLEX_BG = 0x0200;
+    }
+}
+
+bitflags! {
+    /// @brief 只读兼容特性：不支持其中的任何一个时，只能以只读方式挂载文件系统
+    pub struct FeatureRoCompat: u32 {
+        const SPARSE_SUPER = 0x0001;
+        const LARGE_FILE = 0x0002;
+        const BTREE_DIR = 0x0004;
+    }
+}
+
+/// 本驱动支持的不兼容特性
+pub const SUPPORTED_INCOMPAT: u32 = FeatureIncompat::FILETYPE.bits();
+/// 本驱动支持的只读兼容特性
+pub const SUPPORTED_RO_COMPAT: u32 = FeatureRoCompat::SPARSE_SUPER.bits()
+    | FeatureRoCompat::LARGE_FILE.bits()
+    | FeatureRoCompat::BTREE_DIR.bits();
+
+/// @brief ext2文件系统的超级块
+///
+/// 只解析驱动程序需要用到的字段。写回时，在读取到的原始数据的基础上修改，其余字段保持不变
+///
+/// 参考资料：https://www.nongnu.org/ext2-doc/ext2.html
+#[derive(Debug, Clone, Default)]
+pub struct Ext2SuperBlock {
+    /// inode的总数
+    pub inodes_count: u32,
+    /// 块的总数
+    pub blocks_count: u32,
+    /// 为超级用户保留的块数
+    pub r_blocks_count: u32,
+    /// 空闲块的数量
+    pub free_blocks_count: u32,
+    /// 空闲inode的数量
+    pub free_inodes_count: u32,
+    /// 第一个数据块（块大小为1K时为1，否则为0）
+    pub first_data_block: u32,
+    /// 块大小为 1024 << log_block_size
+    pub log_block_size: u32,
+    /// 每个块组中的块数
+    pub blocks_per_group: u32,
+    /// 每个块组中的inode数
+    pub inodes_per_group: u32,
+    /// 最后一次挂载的时间
+    pub mtime: u32,
+    /// 最后一次写入的时间
+    pub wtime: u32,
+    /// 自从上一次检查以来，被挂载的次数
+    pub mnt_count: u16,
+    /// 魔数，必须为0xEF53
+    pub magic: u16,
+    /// 文件系统的状态
+    pub state: u16,
+    /// 修订版本
+    pub rev_level: u32,
+    /// 第一个可以被普通文件使用的inode号
+    pub first_ino: u32,
+    /// inode结构体的大小
+    pub inode_size: u16,
+    /// 兼容特性
+    pub feature_compat: u32,
+    /// 不兼容特性
+    pub feature_incompat: u32,
+    /// 只读兼容特性
+    pub feature_ro_compat: u32,
+    /// 超级块的原始数据
+    raw: Vec<u8>,
+}
+
+impl Ext2SuperBlock {
+    /// @brief 读取并检查分区上的ext2超级块
+    ///
+    /// @return Ok(Ext2SuperBlock) 分区上是一个合法的ext2文件系统
+    /// @return Err(EINVAL) 分区上不是ext2文件系统，或者使用了不支持的特性
+    pub fn new(partition: &Arc<Partition>) -> Result<Ext2SuperBlock, SystemError> {
+        let raw: Vec<u8> = Self::read_raw(partition)?;
+        let mut cursor = VecCursor::new(raw.clone());
+        let mut sb = Ext2SuperBlock::default();
+
+        sb.inodes_count = cursor.read_u32()?;
+        sb.blocks_count = cursor.read_u32()?;
+        sb.r_blocks_count = cursor.read_u32()?;
+        sb.free_blocks_count = cursor.read_u32()?;
+        sb.free_inodes_count = cursor.read_u32()?;
+        sb.first_data_block = cursor.read_u32()?;
+        sb.log_block_size = cursor.read_u32()?;
+
+        cursor.seek(SeekFrom::SeekSet(32))?;
+        sb.blocks_per_group = cursor.read_u32()?;
+        cursor.seek(SeekFrom::SeekSet(40))?;
+        sb.inodes_per_group = cursor.read_u32()?;
+        sb.mtime = cursor.read_u32()?;
+        sb.wtime = cursor.read_u32()?;
+        sb.mnt_count = cursor.read_u16()?;
+        cursor.seek(SeekFrom::SeekSet(56))?;
+        sb.magic = cursor.read_u16()?;
+        sb.state = cursor.read_u16()?;
+
+        cursor.seek(SeekFrom::SeekSet(76))?;
+        sb.rev_level = cursor.read_u32()?;
+        if sb.rev_level == EXT2_GOOD_OLD_REV {
+            sb.first_ino = EXT2_GOOD_OLD_FIRST_INO;
+            sb.inode_size = EXT2_GOOD_OLD_INODE_SIZE;
+        } else {
+            cursor.seek(SeekFrom::SeekSet(84))?;
+            sb.first_ino = cursor.read_u32()?;
+            sb.inode_size = cursor.read_u16()?;
+            cursor.seek(SeekFrom::SeekSet(92))?;
+            sb.feature_compat = cursor.read_u32()?;
+            sb.feature_incompat = cursor.read_u32()?;
+            sb.feature_ro_compat = cursor.read_u32()?;
+        }
+        sb.raw = raw;
+
+        sb.validate()?;
+        return Ok(sb);
+    }
+
+    /// @brief 判断分区上是否是ext2文件系统（只检查魔数）
+    pub fn probe(partition: &Arc<Partition>) -> Result<bool, SystemError> {
+        let raw: Vec<u8> = Self::read_raw(partition)?;
+        return Ok(u16::from_le_bytes([raw[56], raw[57]]) == EXT2_MAGIC);
+    }
+
+    /// @brief 读取超级块的原始数据
+    fn read_raw(partition: &Arc<Partition>) -> Result<Vec<u8>, SystemError> {
+        let mut raw: Vec<u8> = vec![0; EXT2_SUPERBLOCK_SIZE];
+        partition.disk().read_at(
+            partition.lba_start as usize + EXT2_SUPERBLOCK_OFFSET as usize / LBA_SIZE,
+            EXT2_SUPERBLOCK_SIZE / LBA_SIZE,
+            &mut raw,
+        )?;
+        return Ok(raw);
+    }
+
+    /// @brief 检查超级块中的各个字段是否合法
+    fn validate(&self) -> Result<(), SystemError> {
+        if self.magic != EXT2_MAGIC {
+            return Err(SystemError::EINVAL);
+        }
+
+        // 块大小为64K时，目录项的rec_len需要特殊的编码，暂不支持
+        if self.log_block_size > 5 {
+            kerror!("ext2: invalid block size 1024 << {}", self.log_block_size);
+            return Err(SystemError::EINVAL);
+        }
+
+        if self.blocks_per_group == 0
+            || self.inodes_per_group == 0
+            || self.blocks_per_group as u64 > self.block_size() * 8
+            || self.inodes_per_group as u64 > self.block_size() * 8
+        {
+            kerror!(
+                "ext2: invalid group geometry: {} blocks, {} inodes per group",
+                self.blocks_per_group,
+                self.inodes_per_group
+            );
+            return Err(SystemError::EINVAL);
+        }
+
+        if self.inode_size < EXT2_GOOD_OLD_INODE_SIZE
+            || !self.inode_size.is_power_of_two()
+            || self.inode_size as u64 > self.block_size()
+        {
+            kerror!("ext2: invalid inode size {}", self.inode_size);
+            return Err(SystemError::EINVAL);
+        }
+
+        if self.first_data_block >= self.blocks_count {
+            kerror!("ext2: invalid first data block {}", self.first_data_block);
+            return Err(SystemError::EINVAL);
+        }
+
+        let unsupported: u32 = self.feature_incompat & !SUPPORTED_INCOMPAT;
+        if unsupported != 0 {
+            kerror!("ext2: unsupported incompatible features {:#x}", unsupported);
+            return Err(SystemError::EINVAL);
+        }
+
+        return Ok(());
+    }
+
+    /// @brief 判断文件系统是否使用了不支持的只读兼容特性（此时只能以只读方式挂载）
+    pub fn has_unsupported_ro_compat(&self) -> bool {
+        return self.feature_ro_compat & !SUPPORTED_RO_COMPAT != 0;
+    }
+
+    /// @brief 判断文件系统是否具有某个不兼容特性
+    #[inline]
+    pub fn has_incompat(&self, feature: FeatureIncompat) -> bool {
+        return self.feature_incompat & feature.bits() != 0;
+    }
+
+    /// @brief 判断文件系统是否具有某个只读兼容特性
+    #[inline]
+    pub fn has_ro_compat(&self, feature: FeatureRoCompat) -> bool {
+        return self.feature_ro_compat & feature.bits() != 0;
+    }
+
+    /// @brief 每个块的字节数
+    #[inline]
+    pub fn block_size(&self) -> u64 {
+        return 1024 << self.log_block_size;
+    }
+
+    /// @brief 块组的数量
+    #[inline]
+    pub fn groups_count(&self) -> u32 {
+        return (self.blocks_count - self.first_data_block + self.blocks_per_group - 1)
+            / self.blocks_per_group;
+    }
+
+    /// @brief 把超级块写回磁盘
+    ///
+    /// 只有运行过程中会被修改的字段会被更新，其余字段保持读取时的原样
+    pub fn write(&mut self, partition: &Arc<Partition>) -> Result<(), SystemError> {
+        let mut cursor = VecCursor::new(self.raw.clone());
+        cursor.seek(SeekFrom::SeekSet(12))?;
+        cursor.write_u32(self.free_blocks_count)?;
+        cursor.write_u32(self.free_inodes_count)?;
+        cursor.seek(SeekFrom::SeekSet(44))?;
+        cursor.write_u32(self.mtime)?;
+        cursor.write_u32(self.wtime)?;
+        cursor.write_u16(self.mnt_count)?;
+        cursor.seek(SeekFrom::SeekSet(58))?;
+        cursor.write_u16(self.state)?;
+        if self.rev_level != EXT2_GOOD_OLD_REV {
+            cursor.seek(SeekFrom::SeekSet(100))?;
+            cursor.write_u32(self.feature_ro_compat)?;
+        }
+        self.raw = cursor.get_ref().clone();
+
+        partition.disk().write_at(
+            partition.lba_start as usize + EXT2_SUPERBLOCK_OFFSET as usize / LBA_SIZE,
+            EXT2_SUPERBLOCK_SIZE / LBA_SIZE,
+            &self.raw,
+        )?;
+        return Ok(());
+    }
+}
+
+/// @brief 块组描述符
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ext2GroupDesc {
+    /// 块位图所在的块
+    pub block_bitmap: u32,
+    /// inode位图所在的块
+    pub inode_bitmap: u32,
+    /// inode表的第一个块
+    pub inode_table: u32,
+    /// 块组中空闲块的数量
+    pub free_blocks_count: u16,
+    /// 块组中空闲inode的数量
+    pub free_inodes_count: u16,
+    /// 块组中文件夹的数量
+    pub used_dirs_count: u16,
+}
+
+impl Ext2GroupDesc {
+    /// @brief 从块组描述符表的原始数据中解析一个描述符
+    pub fn parse(data: &[u8]) -> Ext2GroupDesc {
+        let u32_at = |off: usize| {
+            u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]])
+        };
+        let u16_at = |off: usize| u16::from_le_bytes([data[off], data[off + 1]]);
+        return Ext2GroupDesc {
+            block_bitmap: u32_at(0),
+            inode_bitmap: u32_at(4),
+            inode_table: u32_at(8),
+            free_blocks_count: u16_at(12),
+            free_inodes_count: u16_at(14),
+            used_dirs_count: u16_at(16),
+        };
+    }
+
+    /// @brief 把描述符写入块组描述符表的原始数据（保留字段保持不变）
+    pub fn write_to(&self, data: &mut [u8]) {
+        data[0..4].copy_from_slice(&self.block_bitmap.to_le_bytes());
+        data[4..8].copy_from_slice(&self.inode_bitmap.to_le_bytes());
+        data[8..12].copy_from_slice(&self.inode_table.to_le_bytes());
+        data[12..14].copy_from_slice(&self.free_blocks_count.to_le_bytes());
+        data[14..16].copy_from_slice(&self.free_inodes_count.to_le_bytes());
+        data[16..18].copy_from_slice(&self.used_dirs_count.to_le_bytes());
+    }
+}
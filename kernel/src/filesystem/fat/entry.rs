@@ -6,6 +6,7 @@ use crate::{
     kwarn,
     libs::vec_cursor::VecCursor,
     syscall::SystemError,
+    time::TimeSpec,
 };
 use alloc::{
     string::{String, ToString},
@@ -15,7 +16,7 @@ use alloc::{
 
 use super::{
     fs::{Cluster, FATFileSystem, MAX_FILE_SIZE},
-    utils::decode_u8_ascii,
+    utils::{decode_u8_ascii, fat_time_to_timespec, timespec_to_fat_time},
 };
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -56,6 +57,8 @@ pub struct FATFile {
     pub short_dir_entry: ShortDirEntry,
     /// 文件目录项的起始、终止簇。格式：(簇，簇内偏移量)
     pub loc: ((Cluster, u64), (Cluster, u64)),
+    /// 文件的目录项是否已经从磁盘上删除（文件被unlink时仍被打开，数据簇要等到不再被使用时才回收）
+    pub unlinked: bool,
 }
 
 impl FATFile {
@@ -71,6 +74,17 @@ impl FATFile {
         self.short_dir_entry.file_size = size;
     }
 
+    /// @brief 把内存中的短目录项（文件大小、时间等信息）写回磁盘
+    pub fn flush_dir_entry(&self, fs: &Arc<FATFileSystem>) -> Result<(), SystemError> {
+        // 目录项已经被删除，它原来所在的位置可能已经被其他文件使用了
+        if self.unlinked {
+            return Ok(());
+        }
+        // 计算短目录项在磁盘内的字节偏移量
+        let short_entry_offset = fs.cluster_bytes_offset(self.loc.1 .0) + self.loc.1 .1;
+        return self.short_dir_entry.flush(fs, short_entry_offset);
+    }
+
     /// @brief 从文件读取数据。读取的字节数与buf长度相等
     ///
     /// @param buf 输出缓冲区
@@ -144,7 +158,6 @@ impl FATFile {
                 break;
             }
         }
-        // todo: 更新时间信息
         return Ok(read_ok);
     }
 
@@ -213,7 +226,6 @@ impl FATFile {
                 break;
             }
         }
-        // todo: 更新时间信息
         return Ok(write_ok);
     }
 
@@ -226,7 +238,7 @@ impl FATFile {
     ///
     /// @return Ok(()) 经过操作后，offset后面具有长度至少为len的空闲空间
     /// @return Err(SystemError) 处理过程中出现了异常。
-    fn ensure_len(
+    pub fn ensure_len(
         &mut self,
         fs: &Arc<FATFileSystem>,
         offset: u64,
@@ -303,11 +315,9 @@ impl FATFile {
         // 计算文件的新大小
         let new_size = self.size() + extra_bytes;
         self.set_size(new_size as u32);
-        // 计算短目录项所在的位置，更新短目录项
-        let short_entry_offset = fs.cluster_bytes_offset(self.loc.1 .0) + self.loc.1 .1;
-        // todo: 更新时间信息
+        self.short_dir_entry.set_write_time(&TimeSpec::now());
         // 把短目录项写入磁盘
-        self.short_dir_entry.flush(fs, short_entry_offset)?;
+        self.flush_dir_entry(fs)?;
         return Ok(());
     }
 
@@ -357,9 +367,8 @@ impl FATFile {
         }
 
         self.set_size(new_size as u32);
-        // 计算短目录项在磁盘内的字节偏移量
-        let short_entry_offset = fs.cluster_bytes_offset((self.loc.1).0) + (self.loc.1).1;
-        self.short_dir_entry.flush(fs, short_entry_offset)?;
+        self.short_dir_entry.set_write_time(&TimeSpec::now());
+        self.flush_dir_entry(fs)?;
 
         return Ok(());
     }
@@ -398,6 +407,15 @@ impl FATDir {
         return self.root_offset.is_some();
     }
 
+    /// @brief 把内存中的短目录项写回磁盘（根目录没有短目录项，不做操作）
+    pub fn flush_dir_entry(&self, fs: &Arc<FATFileSystem>) -> Result<(), SystemError> {
+        if let (Some(short_dir_entry), Some(loc)) = (&self.short_dir_entry, &self.loc) {
+            let short_entry_offset = fs.cluster_bytes_offset(loc.1 .0) + loc.1 .1;
+            short_dir_entry.flush(fs, short_entry_offset)?;
+        }
+        return Ok(());
+    }
+
     /// @brief 获取当前目录所占用的大小
     pub fn size(&self, fs: &Arc<FATFileSystem>) -> u64 {
         return fs.num_clusters_chain(self.first_cluster) * fs.bytes_per_cluster();
@@ -610,6 +628,8 @@ impl FATDir {
                 LongDirEntry::validate_long_name(name)?;
                 // 目标目录项
                 let mut short_entry = ShortDirEntry::default();
+                let now = TimeSpec::now();
+                short_entry.init_times(&now);
 
                 let first_cluster: Cluster = fs.allocate_cluster(None)?;
                 short_entry.set_first_cluster(first_cluster);
@@ -621,8 +641,8 @@ impl FATDir {
                 dot_entry.name = ShortNameGenerator::new(".").generate().unwrap();
                 dot_entry.attributes.value = FileAttributes::DIRECTORY;
                 dot_entry.set_first_cluster(first_cluster);
+                dot_entry.init_times(&now);
 
-                // todo: 设置创建、访问时间
                 dot_entry.flush(&fs, fs.cluster_bytes_offset(first_cluster) + offset)?;
 
                 drop(dot_entry);
@@ -634,7 +654,7 @@ impl FATDir {
                 dot_dot_entry.name = ShortNameGenerator::new("..").generate().unwrap();
                 dot_dot_entry.attributes.value = FileAttributes::DIRECTORY;
                 dot_dot_entry.set_first_cluster(self.first_cluster);
-                // todo: 设置创建、访问时间
+                dot_dot_entry.init_times(&now);
 
                 dot_dot_entry.flush(&fs, fs.cluster_bytes_offset(first_cluster) + offset)?;
 
@@ -721,11 +741,25 @@ impl FATDir {
         attrs: FileAttributes,
         fs: Arc<FATFileSystem>,
     ) -> Result<FATDirEntry, SystemError> {
-        let mut short_dentry: ShortDirEntry = short_dentry.unwrap_or(ShortDirEntry::default());
+        let mut short_dentry: ShortDirEntry = short_dentry.unwrap_or_else(|| {
+            let mut dentry = ShortDirEntry::default();
+            dentry.init_times(&TimeSpec::now());
+            dentry
+        });
         short_dentry.name = short_name.clone();
         short_dentry.attributes = attrs;
 
-        // todo: 设置创建时间、修改时间
+        // 长文件名能够用短目录项（以及nt_res中的大小写标志位）完整地表示时，只需要创建一个短目录项
+        if let Some(nt_res) = ShortNameGenerator::case_flags(long_name, short_name) {
+            short_dentry.nt_res = nt_res;
+            let loc: (Cluster, u64) = self
+                .find_free_entries(1, fs.clone())?
+                .ok_or(SystemError::ENOSPC)?;
+            short_dentry.flush(&fs, fs.cluster_bytes_offset(loc.0) + loc.1)?;
+            return Ok(short_dentry.to_dir_entry(loc));
+        }
+        // 文件名的大小写由长目录项来保存
+        short_dentry.nt_res = 0;
 
         let mut long_name_gen: LongNameEntryGenerator =
             LongNameEntryGenerator::new(long_name, short_dentry.checksum());
@@ -851,11 +885,45 @@ impl FATDir {
 
     /// @brief 在当前目录内，重命名一个目录项
     ///
+    /// @param fs 当前目录所属的文件系统
+    /// @param old_name 原来的名字
+    /// @param new_name 新的名字
+    ///
+    /// @return Ok(FATDirEntry) 重命名后的目录项
+    /// @return Err(SystemError) 错误码
     pub fn rename(
         &self,
         fs: Arc<FATFileSystem>,
         old_name: &str,
         new_name: &str,
+    ) -> Result<FATDirEntry, SystemError> {
+        return self.rename_across(fs, self, old_name, new_name);
+    }
+
+    /// @brief 把当前目录内的一个目录项，移动到target目录下，并重命名为new_name
+    ///
+    /// 本函数会先删除原有的目录项，然后在target目录中创建新的长、短目录项，文件的数据簇保持不变。
+    /// 如果被移动的是文件夹，那么还会更新它的'..'目录项。
+    ///
+    /// 如果target中已经存在名为new_name的目录项，那么它会被替换：文件夹只能替换空文件夹，文件只能替换文件。
+    /// 任何一步失败时，原来的目录项以及被替换的目录项都会被恢复。被替换的文件夹的数据簇会被立即回收，
+    /// 被替换的文件的数据簇则由调用者负责回收（文件可能仍被打开）。
+    ///
+    /// 名字只有大小写不同时（例如foo -> FOO），目标目录项就是源目录项本身，此时只修改它的名字。
+    ///
+    /// @param fs 当前目录所属的文件系统
+    /// @param target 目标目录（可以与当前目录相同）
+    /// @param old_name 原来的名字
+    /// @param new_name 新的名字
+    ///
+    /// @return Ok(FATDirEntry) 移动后的目录项
+    /// @return Err(SystemError) 错误码
+    pub fn rename_across(
+        &self,
+        fs: Arc<FATFileSystem>,
+        target: &FATDir,
+        old_name: &str,
+        new_name: &str,
     ) -> Result<FATDirEntry, SystemError> {
         // 判断源目录项是否存在
         let old_dentry: FATDirEntry = if let FATDirEntryOrShortName::DirEntry(dentry) =
@@ -863,38 +931,126 @@ impl FATDir {
         {
             dentry
         } else {
-            // 如果目标目录项不存在，则返回错误
+            // 如果源目录项不存在，则返回错误
             return Err(SystemError::ENOENT);
         };
 
-        let short_name = if let FATDirEntryOrShortName::ShortName(s) =
-            self.check_existence(new_name, None, fs.clone())?
-        {
-            s
-        } else {
-            // 如果目标目录项存在，那么就返回错误
-            return Err(SystemError::EEXIST);
+        let se: ShortDirEntry = match old_dentry.short_dir_entry() {
+            Some(se) => se,
+            // 不允许对根目录项进行重命名
+            None => return Err(SystemError::EPERM),
+        };
+        let old_range = old_dentry.get_dir_range().ok_or(SystemError::EPERM)?;
+
+        LongDirEntry::validate_long_name(new_name)?;
+
+        // 目标位置已经存在的目录项（大小写不同的同一个目录项除外）
+        let victim: Option<FATDirEntry> =
+            match target.check_existence(new_name, None, fs.clone())? {
+                FATDirEntryOrShortName::DirEntry(e)
+                    if !(self.first_cluster == target.first_cluster
+                        && e.get_dir_range() == Some(old_range)) =>
+                {
+                    Some(e)
+                }
+                _ => None,
+            };
+        let victim_se: Option<(ShortDirEntry, ((Cluster, u64), (Cluster, u64)))> = match &victim {
+            Some(v) => {
+                match (old_dentry.is_dir(), v.is_dir()) {
+                    (true, false) => return Err(SystemError::ENOTDIR),
+                    (false, true) => return Err(SystemError::EISDIR),
+                    (true, true) if !v.to_dir()?.is_empty(fs.clone()) => {
+                        return Err(SystemError::ENOTEMPTY)
+                    }
+                    _ => {}
+                }
+                Some((
+                    v.short_dir_entry().ok_or(SystemError::EPERM)?,
+                    v.get_dir_range().ok_or(SystemError::EPERM)?,
+                ))
+            }
+            None => None,
         };
 
-        let old_short_dentry: Option<ShortDirEntry> = old_dentry.short_dir_entry();
-        if let Some(se) = old_short_dentry {
-            // 删除原来的目录项
-            self.remove(fs.clone(), old_dentry.name().as_str(), false)?;
-
-            // 创建新的目录项
-            let new_dentry: FATDirEntry = self.create_dir_entries(
-                new_name,
-                &short_name,
+        // 先删除被替换的目录项（数据簇暂不回收），再删除原来的目录项，然后在目标目录中创建新的目录项。
+        // 任何一步失败时，都把已经删除的目录项恢复，以免文件丢失
+        let restore_victim = || -> Result<(), SystemError> {
+            if let (Some(v), Some((vse, _))) = (&victim, &victim_se) {
+                target.create_dir_entries(
+                    &v.name(),
+                    &v.short_name_raw(),
+                    Some(*vse),
+                    vse.attributes,
+                    fs.clone(),
+                )?;
+            }
+            return Ok(());
+        };
+        let restore_old = || -> Result<(), SystemError> {
+            self.create_dir_entries(
+                &old_dentry.name(),
+                &old_dentry.short_name_raw(),
                 Some(se),
                 se.attributes,
                 fs.clone(),
             )?;
+            return Ok(());
+        };
 
-            return Ok(new_dentry);
-        } else {
-            // 不允许对根目录项进行重命名
-            return Err(SystemError::EPERM);
+        if let Some((_, victim_range)) = victim_se {
+            target.remove_dir_entries(fs.clone(), victim_range)?;
         }
+        if let Err(e) = self.remove_dir_entries(fs.clone(), old_range) {
+            restore_victim()?;
+            return Err(e);
+        }
+
+        // 原有的目录项都已经删除，为新的名字生成短文件名
+        let short_name: [u8; 11] = match target.check_existence(new_name, None, fs.clone()) {
+            Ok(FATDirEntryOrShortName::ShortName(s)) => s,
+            r => {
+                restore_old()?;
+                restore_victim()?;
+                return Err(r.err().unwrap_or(SystemError::EEXIST));
+            }
+        };
+
+        // 在目标目录中创建新的目录项
+        let new_dentry: FATDirEntry = match target.create_dir_entries(
+            new_name,
+            &short_name,
+            Some(se),
+            se.attributes,
+            fs.clone(),
+        ) {
+            Ok(e) => e,
+            Err(e) => {
+                restore_old()?;
+                restore_victim()?;
+                return Err(e);
+            }
+        };
+
+        // 被替换的文件夹已经是空的，直接回收它的数据簇
+        if let Some(v) = &victim {
+            if v.is_dir() && v.first_cluster().cluster_num >= 2 {
+                fs.deallocate_cluster_chain(v.first_cluster())?;
+            }
+        }
+
+        // 移动的是文件夹，并且父目录发生了变化，那么需要更新'..'目录项
+        if new_dentry.is_dir() && self.first_cluster != target.first_cluster {
+            let dir: FATDir = new_dentry.to_dir()?;
+            // '..'目录项是文件夹中的第二个目录项
+            let offset = fs.cluster_bytes_offset(dir.first_cluster) + FATRawDirEntry::DIR_ENTRY_LEN;
+            if let FATRawDirEntry::Short(mut dot_dot_entry) = get_raw_dir_entry(&fs, offset)? {
+                dot_dot_entry.set_first_cluster(target.first_cluster);
+                dot_dot_entry.flush(&fs, offset)?;
+            }
+        }
+
+        return Ok(new_dentry);
     }
 }
 
@@ -929,10 +1085,11 @@ pub struct ShortDirEntry {
     /// 目录项属性 (见 FileAttributes )
     attributes: FileAttributes,
 
-    /// Windows NT系统的保留字段。用来表示短目录项文件名。
+    /// Windows NT系统的保留字段。用来表示短目录项文件名的大小写。
     /// EXT|BASE => 8(BASE).3(EXT)
     /// BASE:LowerCase(8),UpperCase(0)
     /// EXT:LowerCase(16),UpperCase(0)
+    /// 见 ShortDirEntry::NT_LOWER_BASE 和 ShortDirEntry::NT_LOWER_EXT
     nt_res: u8,
 
     /// 文件创建时间的毫秒级时间戳
@@ -1128,6 +1285,11 @@ impl LongDirEntry {
 impl ShortDirEntry {
     const PADDING: u8 = ' ' as u8;
 
+    /// nt_res的标志位：基础名全部为小写
+    pub const NT_LOWER_BASE: u8 = 1 << 3;
+    /// nt_res的标志位：扩展名全部为小写
+    pub const NT_LOWER_EXT: u8 = 1 << 4;
+
     /// @brief 判断当前目录项是否为文件夹
     ///
     /// @return true 是文件夹
@@ -1157,14 +1319,30 @@ impl ShortDirEntry {
 
     /// @brief 将短目录项的名字转换为String
     fn name_to_string(&self) -> String {
+        return Self::format_name(&self.name, self.nt_res);
+    }
+
+    /// @brief 将短目录项名称转换为String
+    ///
+    /// @param short_name 短目录项名称
+    /// @param nt_res 大小写标志位。基础名、扩展名对应的标志位被置位时，将其转换为小写
+    fn format_name(short_name: &[u8; 11], nt_res: u8) -> String {
+        let mut short_name: [u8; 11] = *short_name;
+        if nt_res & Self::NT_LOWER_BASE != 0 {
+            short_name[..8].make_ascii_lowercase();
+        }
+        if nt_res & Self::NT_LOWER_EXT != 0 {
+            short_name[8..].make_ascii_lowercase();
+        }
+
         // 计算基础名的长度
-        let base_len = self.name[..8]
+        let base_len = short_name[..8]
             .iter()
             .rposition(|x| *x != ShortDirEntry::PADDING)
             .map(|len| len + 1)
             .unwrap_or(0);
         // 计算扩展名的长度
-        let ext_len = self.name[8..]
+        let ext_len = short_name[8..]
             .iter()
             .rposition(|x| *x != ShortDirEntry::PADDING)
             .map(|len| len + 1)
@@ -1173,12 +1351,12 @@ impl ShortDirEntry {
         // 声明存储完整名字的数组（包含“.”）
         let mut name = [ShortDirEntry::PADDING; 12];
         // 拷贝基础名
-        name[..base_len].copy_from_slice(&self.name[..base_len]);
+        name[..base_len].copy_from_slice(&short_name[..base_len]);
 
         // 拷贝扩展名，并计算总的长度
         let total_len = if ext_len > 0 {
             name[base_len] = '.' as u8;
-            name[base_len + 1..base_len + 1 + ext_len].copy_from_slice(&self.name[8..8 + ext_len]);
+            name[base_len + 1..base_len + 1 + ext_len].copy_from_slice(&short_name[8..8 + ext_len]);
             // 总长度为基础名长度+点号+扩展名长度
             base_len + 1 + ext_len
         } else {
@@ -1273,11 +1451,13 @@ impl ShortDirEntry {
     }
 
     /// @brief 计算短目录项的名称的校验和
+    ///
+    /// 算法见FAT规范：Sum = ((Sum & 1) ? 0x80 : 0) + (Sum >> 1) + c，运算结果按u8回绕
     fn checksum(&self) -> u8 {
-        let mut result = 0;
+        let mut result: u8 = 0;
 
         for c in &self.name {
-            result = (result << 7) + (result >> 1) + *c;
+            result = (result << 7).wrapping_add(result >> 1).wrapping_add(*c);
         }
         return result;
     }
@@ -1335,6 +1515,63 @@ impl ShortDirEntry {
         self.fst_clus_lo = (cluster.cluster_num & 0x0000ffff) as u16;
         self.fst_clus_hi = ((cluster.cluster_num & 0xffff0000) >> 16) as u16;
     }
+
+    /// @brief 获取短目录项的“文件大小”字段的值（文件夹的这个字段应当为0）
+    #[inline]
+    pub fn file_size(&self) -> u32 {
+        return self.file_size;
+    }
+
+    /// @brief 设置短目录项的“文件大小”字段的值
+    #[inline]
+    pub fn set_file_size(&mut self, size: u32) {
+        self.file_size = size;
+    }
+
+    /// @brief 获取文件的创建时间
+    pub fn create_time(&self) -> TimeSpec {
+        return fat_time_to_timespec(self.crt_date, self.crt_time, self.crt_time_tenth);
+    }
+
+    /// @brief 获取文件的最后写入时间（精度为2秒）
+    pub fn write_time(&self) -> TimeSpec {
+        return fat_time_to_timespec(self.wrt_date, self.wrt_time, 0);
+    }
+
+    /// @brief 获取文件的最后访问时间（只记录了日期）
+    pub fn access_time(&self) -> TimeSpec {
+        return fat_time_to_timespec(self.lst_acc_date, 0, 0);
+    }
+
+    /// @brief 设置文件的创建时间（仅仅更改内存中的值）
+    pub fn set_create_time(&mut self, ts: &TimeSpec) {
+        (self.crt_date, self.crt_time, self.crt_time_tenth) = timespec_to_fat_time(ts);
+    }
+
+    /// @brief 设置文件的最后写入时间（仅仅更改内存中的值）
+    pub fn set_write_time(&mut self, ts: &TimeSpec) {
+        (self.wrt_date, self.wrt_time, _) = timespec_to_fat_time(ts);
+    }
+
+    /// @brief 设置文件的最后访问日期（仅仅更改内存中的值）
+    ///
+    /// @return true 访问日期发生了变化，需要写回磁盘
+    /// @return false 访问日期没有变化
+    pub fn set_access_time(&mut self, ts: &TimeSpec) -> bool {
+        let (date, _, _) = timespec_to_fat_time(ts);
+        if date == self.lst_acc_date {
+            return false;
+        }
+        self.lst_acc_date = date;
+        return true;
+    }
+
+    /// @brief 把创建、写入、访问时间都设置为指定的时间（用于新创建的目录项）
+    pub fn init_times(&mut self, ts: &TimeSpec) {
+        self.set_create_time(ts);
+        self.set_write_time(ts);
+        self.set_access_time(ts);
+    }
 }
 
 /// @brief FAT文件系统标准定义的目录项
@@ -1589,8 +1826,8 @@ impl FATDirEntry {
             return Err(SystemError::EINVAL);
         }
 
-        if !long_name_entries[0].is_last() || !long_name_entries.last().unwrap().is_short() {
-            // 存在孤立的目录项，文件系统出现异常，因此返回错误，表明其只读。
+        if !long_name_entries.last().unwrap().is_short() {
+            // 长目录项后面没有短目录项，文件系统出现异常，因此返回错误，表明其只读。
             // TODO: 标记整个FAT文件系统为只读的
             return Err(SystemError::EROFS);
         }
@@ -1602,25 +1839,22 @@ impl FATDirEntry {
         };
 
         let mut extractor = LongNameExtractor::new();
-        for entry in &long_name_entries {
-            match entry {
-                &FATRawDirEntry::Long(l) => {
-                    extractor.process(l)?;
-                }
+        // 长目录项必须从ord最大的那个开始，且序号连续
+        let long_name_valid: bool = long_name_entries.first().map_or(false, |e| e.is_last())
+            && long_name_entries.iter().all(|entry| match entry {
+                &FATRawDirEntry::Long(l) => extractor.process(l).is_ok(),
+                _ => false,
+            });
 
-                _ => {
-                    return Err(SystemError::EROFS);
-                }
-            }
-        }
         // 检验校验和是否正确
-        if extractor.validate_checksum(&short_dentry) {
+        if long_name_valid && extractor.validate_checksum(&short_dentry) {
             // 校验和正确，返回一个长目录项
             return Ok(short_dentry.to_dir_entry_with_long_name(extractor.to_string(), loc));
-        } else {
-            // 校验和不相同，认为文件系统出错
-            return Err(SystemError::EROFS);
         }
+
+        // 长目录项损坏，或者校验和与短目录项不匹配（例如，短目录项被不支持长文件名的系统修改过）。
+        // 按照FAT规范，这些长目录项是孤立的，应当被忽略，只使用短目录项
+        return Ok(short_dentry.to_dir_entry(loc.1));
     }
 
     /// @brief 获取短目录项的名字
@@ -1829,33 +2063,45 @@ impl ShortNameGenerator {
     const NAME_FITS: u8 = (1 << 4);
 
     /// @brief 初始化一个短目录项名称生成器
+    ///
+    /// 按照FAT规范中的Basis-Name生成算法，将长文件名转换为8.3格式的基础名称：
+    /// 去掉开头的点号，以最后一个点号分隔基础名和扩展名，转换为大写，并将不合法的字符替换为'_'
     pub fn new(mut name: &str) -> Self {
         name = name.trim();
 
         let mut short_name: [u8; 11] = [0x20u8; 11];
-        if name == "." {
-            short_name[0] = '.' as u8;
+        if name == "." || name == ".." {
+            short_name[..name.len()].fill('.' as u8);
+            let flags = if name == "." {
+                Self::IS_DOT
+            } else {
+                Self::IS_DOTDOT
+            };
+            return ShortNameGenerator {
+                name: short_name,
+                flags: flags,
+                basename_len: name.len() as u8,
+                ..Default::default()
+            };
         }
 
-        if name == ".." {
-            short_name[0] = '.' as u8;
-            short_name[1] = '.' as u8;
-        }
+        // 去掉开头的点号，例如".bashrc"的基础名为"BASHRC"，而不是一个空的基础名
+        let stripped: &str = name.trim_start_matches('.');
 
         // @name_fits: 名称是否被完全拷贝
         // @basename_len: 基础名的长度
         // @is_lossy: 是否存在不合法的字符
-        let (name_fits, basename_len, is_lossy) = match name.rfind('.') {
+        let (name_fits, mut basename_len, mut is_lossy) = match stripped.rfind('.') {
             Some(index) => {
                 // 文件名里面有".", 且index为最右边的点号所在的下标（bytes index)
                 // 拷贝基础名
                 let (b_len, fits, b_lossy) =
-                    Self::copy_part(&mut short_name[..Self::SHORT_NAME_LEN], &name[..index]);
+                    Self::copy_part(&mut short_name[..Self::SHORT_NAME_LEN], &stripped[..index]);
 
                 // 拷贝扩展名
                 let (_, ext_fits, ext_lossy) = Self::copy_part(
                     &mut short_name[Self::SHORT_NAME_LEN..Self::SHORT_NAME_LEN + 3],
-                    &name[index + 1..],
+                    &stripped[index + 1..],
                 );
 
                 (fits && ext_fits, b_len, b_lossy || ext_lossy)
@@ -1863,23 +2109,24 @@ impl ShortNameGenerator {
             None => {
                 // 文件名中，不存在"."
                 let (b_len, fits, b_lossy) =
-                    Self::copy_part(&mut short_name[..Self::SHORT_NAME_LEN], &name);
+                    Self::copy_part(&mut short_name[..Self::SHORT_NAME_LEN], stripped);
                 (fits, b_len, b_lossy)
             }
         };
+        is_lossy = is_lossy || stripped.len() != name.len();
+
+        // 基础名不能为空
+        if basename_len == 0 {
+            short_name[0] = '_' as u8;
+            basename_len = 1;
+            is_lossy = true;
+        }
 
         let mut flags: u8 = 0;
         // 设置flags
         if is_lossy {
             flags |= Self::IS_LOSSY;
         }
-        if name == "." {
-            flags |= Self::IS_DOT;
-        }
-        if name == ".." {
-            flags |= Self::IS_DOTDOT;
-        }
-
         if name_fits {
             flags |= Self::NAME_FITS;
         }
@@ -1893,6 +2140,37 @@ impl ShortNameGenerator {
         };
     }
 
+    /// @brief 判断长文件名能否只用短目录项来表示
+    ///
+    /// 当长文件名是合法的8.3名称，并且基础名、扩展名各自全为大写或者全为小写时，
+    /// 可以通过短目录项的nt_res字段记录大小写，而不需要创建长目录项（与Windows NT的行为相同）
+    ///
+    /// @param name 长文件名
+    /// @param short_name 为该文件生成的短目录项名称
+    ///
+    /// @return Some(nt_res) 可以只用短目录项表示，返回需要写入nt_res字段的值
+    /// @return None 需要创建长目录项
+    pub fn case_flags(name: &str, short_name: &[u8; 11]) -> Option<u8> {
+        let (base, ext) = match name.rfind('.') {
+            Some(index) => (&name[..index], &name[index + 1..]),
+            None => (name, ""),
+        };
+
+        let mut nt_res: u8 = 0;
+        if base.bytes().any(|c| c.is_ascii_lowercase()) {
+            nt_res |= ShortDirEntry::NT_LOWER_BASE;
+        }
+        if ext.bytes().any(|c| c.is_ascii_lowercase()) {
+            nt_res |= ShortDirEntry::NT_LOWER_EXT;
+        }
+
+        // 大小写混合、存在不合法字符、或者使用了数字后缀的名称，都无法从短目录项还原
+        if ShortDirEntry::format_name(short_name, nt_res) == name {
+            return Some(nt_res);
+        }
+        return None;
+    }
+
     /// @brief 拷贝字符串到一个u8数组
     ///
     /// @return (u8, bool, bool)
@@ -1924,8 +2202,8 @@ impl ShortNameGenerator {
             // 判断是否存在不符合条件的字符
             lossy_conv = lossy_conv || c != cp;
 
-            // 拷贝字符
-            dest[dest_len] = c.to_ascii_uppercase() as u8;
+            // 拷贝字符（不合法的字符已经被替换为'_'）
+            dest[dest_len] = cp.to_ascii_uppercase() as u8;
             dest_len += 1;
         }
 
@@ -1934,13 +2212,14 @@ impl ShortNameGenerator {
     }
 
     fn fletcher_16_checksum(name: &str) -> u16 {
-        let mut sum1: u16 = 0;
-        let mut sum2: u16 = 0;
+        let mut sum1: u32 = 0;
+        let mut sum2: u32 = 0;
         for c in name.chars() {
-            sum1 = (sum1 + (c as u16)) % 0xff;
+            // 非ASCII字符的码点可能超过u16的范围，因此使用u32进行计算
+            sum1 = (sum1 + c as u32) % 0xff;
             sum2 = (sum1 + sum2) & 0xff;
         }
-        return (sum2 << 8) | sum1;
+        return ((sum2 << 8) | sum1) as u16;
     }
 
     /// @brief 更新生成器的状态
@@ -1976,7 +2255,7 @@ impl ShortNameGenerator {
         // === 检查是否存在短前缀+校验和的冲突，文件名形如：(TE021F~1.TXT)
         let prefix_len = min(self.basename_len, 2) as usize;
         let num_suffix: Option<u32> = if name[prefix_len + 4] as char == '~' {
-            (name[prefix_len + 1] as char).to_digit(10)
+            (name[prefix_len + 5] as char).to_digit(10)
         } else {
             None
         };
@@ -1,5 +1,9 @@
 #![allow(dead_code)]
-use core::{any::Any, fmt::Debug};
+use core::{
+    any::Any,
+    fmt::Debug,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use alloc::{
     collections::BTreeMap,
@@ -9,29 +13,39 @@ use alloc::{
 };
 
 use crate::{
+    arch::MMArch,
     driver::base::block::{block_device::LBA_SIZE, disk_info::Partition, SeekFrom},
     filesystem::vfs::{
         core::generate_inode_id,
         file::{FileMode, FilePrivateData},
-        FileSystem, FileType, IndexNode, InodeId, Metadata, PollStatus,
+        FileSystem, FileType, FsInfo, IndexNode, InodeId, Metadata, PollStatus,
     },
-    kerror,
+    kerror, kwarn,
     libs::{
         spinlock::{SpinLock, SpinLockGuard},
         vec_cursor::VecCursor,
     },
+    mm::{
+        page_cache::{CachedPage, PageCache, PageCacheBackend},
+        MemoryManagementArch,
+    },
     syscall::SystemError,
     time::TimeSpec,
 };
 
 use super::{
     bpb::{BiosParameterBlock, FATType},
-    entry::{FATDir, FATDirEntry, FATDirIter, FATEntry},
+    entry::{FATDir, FATDirEntry, FATDirIter, FATEntry, ShortDirEntry},
+    fsck::fsck,
     utils::RESERVED_CLUSTERS,
 };
 
 /// FAT32文件系统的最大的文件大小
 pub const MAX_FILE_SIZE: u64 = 0xffff_ffff;
+/// 长文件名的最大长度
+pub const FAT_MAX_NAMELEN: usize = 255;
+/// FAT文件系统的魔数（与Linux的MSDOS_SUPER_MAGIC相同）
+pub const FAT_MAGIC: u64 = 0x4d44;
 
 /// @brief 表示当前簇和上一个簇的关系的结构体
 /// 定义这样一个结构体的原因是，FAT文件系统的文件中，前后两个簇具有关联关系。
@@ -69,6 +83,10 @@ pub struct FATFileSystem {
     pub fs_info: Arc<LockedFATFsInfo>,
     /// 文件系统的根inode
     root_inode: Arc<LockedFATInode>,
+    /// 文件系统是否以只读方式挂载（一致性检查发现了无法修复的问题时，会被设置为true）
+    read_only: AtomicBool,
+    /// 卷是否已经被标记为正在使用（shut bit已被清除）。在挂载后第一次修改FAT表时设置，在sync、卸载时清除
+    in_use: AtomicBool,
 }
 
 /// FAT文件系统的Inode
@@ -83,6 +101,11 @@ impl LockedFATFsInfo {
     pub fn new(fs_info: FATFsInfo) -> Self {
         return Self(SpinLock::new(fs_info));
     }
+
+    #[inline]
+    pub fn lock(&self) -> SpinLockGuard<FATFsInfo> {
+        return self.0.lock();
+    }
 }
 
 #[derive(Debug)]
@@ -101,18 +124,28 @@ pub struct FATInode {
 
     /// 根据不同的Inode类型，创建不同的私有字段
     inode_type: FATDirEntry,
+
+    /// 文件的页缓存（文件夹没有页缓存）
+    page_cache: Option<Arc<PageCache>>,
+
+    /// 内存中的短目录项（写入时间等）是否被修改过，需要在写回时刷入磁盘
+    dentry_dirty: bool,
 }
 
 impl FATInode {
     /// @brief 更新当前inode的元数据
     pub fn update_metadata(&mut self) {
-        // todo: 更新文件的访问时间等信息
         match &self.inode_type {
             FATDirEntry::File(f) | FATDirEntry::VolId(f) => {
                 self.metadata.size = f.size() as i64;
+                Self::update_times(&mut self.metadata, &f.short_dir_entry);
             }
             FATDirEntry::Dir(d) => {
                 self.metadata.size = d.size(&self.fs.upgrade().unwrap().clone()) as i64;
+                // 根目录没有短目录项，因此也没有时间信息
+                if let Some(short_dir_entry) = &d.short_dir_entry {
+                    Self::update_times(&mut self.metadata, short_dir_entry);
+                }
             }
             FATDirEntry::UnInit => {
                 kerror!("update_metadata: Uninitialized FATDirEntry: {:?}", self);
@@ -121,6 +154,80 @@ impl FATInode {
         };
     }
 
+    /// @brief 根据短目录项中的时间信息，更新inode的时间戳
+    ///
+    /// FAT没有记录状态改变时间，因此用最后写入时间作为ctime
+    fn update_times(metadata: &mut Metadata, short_dir_entry: &ShortDirEntry) {
+        metadata.atime = short_dir_entry.access_time();
+        metadata.mtime = short_dir_entry.write_time();
+        metadata.ctime = metadata.mtime;
+    }
+
+    /// @brief 检查文件系统是否可写
+    ///
+    /// @return Err(EROFS) 文件系统以只读方式挂载
+    fn check_writable(&self) -> Result<(), SystemError> {
+        if self.fs.upgrade().unwrap().is_read_only() {
+            return Err(SystemError::EROFS);
+        }
+        return Ok(());
+    }
+
+    /// @brief 把页缓存中的脏页写回磁盘
+    ///
+    /// @return Err(EROFS) 存在脏页，但是文件系统以只读方式挂载
+    fn writeback_pages(&mut self) -> Result<(), SystemError> {
+        let page_cache: Arc<PageCache> = match &self.page_cache {
+            Some(pc) => pc.clone(),
+            None => return Ok(()),
+        };
+        let fs: Arc<FATFileSystem> = self.fs.upgrade().unwrap();
+
+        match &mut self.inode_type {
+            FATDirEntry::File(f) | FATDirEntry::VolId(f) => {
+                let file_size = f.size() as usize;
+                page_cache.writeback(file_size, |index, data| {
+                    // 只有存在脏页时才需要写入磁盘，只读的文件系统不能写入
+                    if fs.is_read_only() {
+                        return Err(SystemError::EROFS);
+                    }
+                    f.write(&fs, data, (index * MMArch::PAGE_SIZE) as u64)?;
+                    return Ok(());
+                })?;
+                // 只写入了页缓存的写操作，修改的写入时间还没有刷入磁盘
+                if self.dentry_dirty {
+                    f.flush_dir_entry(&fs)?;
+                    self.dentry_dirty = false;
+                }
+                return Ok(());
+            }
+            _ => return Ok(()),
+        }
+    }
+
+    /// @brief 查找重命名时将被替换的目标inode
+    ///
+    /// @return Ok(None) 目标不存在，或者它与被移动的inode是磁盘上的同一个目录项（例如名字只有大小写不同）
+    fn find_victim(
+        &mut self,
+        name: &str,
+        child: &Arc<LockedFATInode>,
+    ) -> Result<Option<Arc<LockedFATInode>>, SystemError> {
+        let victim: Arc<LockedFATInode> = match self.find(name) {
+            Ok(v) => v,
+            Err(SystemError::ENOENT) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if Arc::ptr_eq(&victim, child) {
+            return Ok(None);
+        }
+        let victim_range = victim.0.lock().inode_type.get_dir_range();
+        if victim_range.is_some() && victim_range == child.0.lock().inode_type.get_dir_range() {
+            return Ok(None);
+        }
+        return Ok(Some(victim));
+    }
+
     fn find(&mut self, name: &str) -> Result<Arc<LockedFATInode>, SystemError> {
         match &self.inode_type {
             FATDirEntry::Dir(d) => {
@@ -157,7 +264,38 @@ impl FATInode {
     }
 }
 
+impl Drop for FATInode {
+    fn drop(&mut self) {
+        // 已经被unlink的文件不再被任何人使用，此时才回收它的数据簇
+        if let FATDirEntry::File(f) | FATDirEntry::VolId(f) = &self.inode_type {
+            if f.unlinked && f.first_cluster.cluster_num >= RESERVED_CLUSTERS as u64 {
+                if let Some(fs) = self.fs.upgrade() {
+                    if let Err(e) = fs.deallocate_cluster_chain(f.first_cluster) {
+                        kerror!(
+                            "FATFS: failed to release clusters of unlinked file {}: {:?}",
+                            f.file_name,
+                            e
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
 impl LockedFATInode {
+    /// @brief 标记inode对应的目录项已经从磁盘上删除（被重命名的目标替换）
+    ///
+    /// 文件可能仍被打开，因此它的数据簇要等到inode被释放时才回收（见FATInode的drop）
+    fn mark_removed(&self) {
+        let mut guard: SpinLockGuard<FATInode> = self.0.lock();
+        if let FATDirEntry::File(f) | FATDirEntry::VolId(f) = &mut guard.inode_type {
+            f.unlinked = true;
+        }
+        guard.metadata.nlinks = 0;
+        guard.metadata.ctime = TimeSpec::now();
+    }
+
     pub fn new(
         fs: Arc<FATFileSystem>,
         parent: Weak<LockedFATInode>,
@@ -168,6 +306,7 @@ impl LockedFATInode {
         } else {
             FileType::File
         };
+        let has_page_cache = file_type == FileType::File;
 
         let inode: Arc<LockedFATInode> = Arc::new(LockedFATInode(SpinLock::new(FATInode {
             parent: parent,
@@ -178,6 +317,7 @@ impl LockedFATInode {
             metadata: Metadata {
                 dev_id: 0,
                 inode_id: generate_inode_id(),
+                disk_ino: None,
                 size: 0,
                 blk_size: fs.bpb.bytes_per_sector as usize,
                 blocks: if let FATType::FAT32(_) = fs.bpb.fat_type {
@@ -195,9 +335,15 @@ impl LockedFATInode {
                 gid: 0,
                 raw_dev: 0,
             },
+            page_cache: None,
+            dentry_dirty: false,
         })));
 
         inode.0.lock().self_ref = Arc::downgrade(&inode);
+        if has_page_cache {
+            let backend: Weak<dyn PageCacheBackend> = Arc::downgrade(&inode) as _;
+            inode.0.lock().page_cache = Some(PageCache::new(backend));
+        }
 
         inode.0.lock().update_metadata();
 
@@ -205,6 +351,19 @@ impl LockedFATInode {
     }
 }
 
+impl PageCacheBackend for LockedFATInode {
+    fn writeback(&self) -> Result<(), SystemError> {
+        return self.0.lock().writeback_pages();
+    }
+
+    fn try_writeback(&self) -> bool {
+        match self.0.try_lock() {
+            Ok(mut guard) => return guard.writeback_pages().is_ok(),
+            Err(_) => return false,
+        }
+    }
+}
+
 /// FsInfo结构体（内存中的一份拷贝，当卸载卷或者sync的时候，把它写入磁盘）
 #[derive(Debug)]
 pub struct FATFsInfo {
@@ -230,8 +389,22 @@ impl FileSystem for FATFileSystem {
         return self.root_inode.clone();
     }
 
-    fn info(&self) -> crate::filesystem::vfs::FsInfo {
-        todo!()
+    fn info(&self) -> FsInfo {
+        let total_clusters: u64 =
+            self.max_cluster_number().cluster_num - RESERVED_CLUSTERS as u64 + 1;
+        let free_clusters: u64 = self.count_free_clusters().unwrap_or(0);
+        return FsInfo {
+            blk_dev_id: 0,
+            max_name_len: FAT_MAX_NAMELEN,
+            magic: FAT_MAGIC,
+            block_size: self.bytes_per_cluster() as usize,
+            total_blocks: total_clusters,
+            free_blocks: free_clusters,
+            avail_blocks: free_clusters,
+            // FAT没有inode表，文件数量只受磁盘空间的限制
+            total_inodes: 0,
+            free_inodes: 0,
+        };
     }
 
     /// @brief 本函数用于实现动态转换。
@@ -239,6 +412,10 @@ impl FileSystem for FATFileSystem {
     fn as_any_ref(&self) -> &dyn Any {
         self
     }
+
+    fn sync(&self) -> Result<(), SystemError> {
+        return self.mark_clean();
+    }
 }
 
 impl FATFileSystem {
@@ -297,6 +474,7 @@ impl FATFileSystem {
             metadata: Metadata {
                 dev_id: 0,
                 inode_id: generate_inode_id(),
+                disk_ino: None,
                 size: 0,
                 blk_size: bpb.bytes_per_sector as usize,
                 blocks: if let FATType::FAT32(_) = bpb.fat_type {
@@ -322,6 +500,8 @@ impl FATFileSystem {
             first_data_sector,
             fs_info: Arc::new(LockedFATFsInfo::new(fs_info)),
             root_inode: root_inode,
+            read_only: AtomicBool::new(false),
+            in_use: AtomicBool::new(false),
         });
 
         // 对root inode加锁，并继续完成初始化工作
@@ -333,9 +513,90 @@ impl FATFileSystem {
         // 释放锁
         drop(root_guard);
 
+        result.check_on_mount()?;
+
         return Ok(result);
     }
 
+    /// @brief 挂载时检查卷的状态
+    ///
+    /// 如果卷上次没有被正常卸载，或者曾经出现过磁盘I/O错误，那么对它进行一致性检查并修复。
+    /// 存在无法修复的问题时，以只读方式挂载文件系统。干净的卷不做检查，也不会被写入。
+    ///
+    /// 挂载时不会清除shut bit：卷在第一次被修改时才会被标记为正在使用，见mark_in_use()
+    fn check_on_mount(self: &Arc<Self>) -> Result<(), SystemError> {
+        let shut_ok: bool = self.is_shut_bit_ok()?;
+        let hard_error_ok: bool = self.is_hard_error_bit_ok()?;
+        if shut_ok && hard_error_ok {
+            return Ok(());
+        }
+
+        kwarn!(
+            "FAT: volume is dirty (shut bit ok: {}, hard error bit ok: {}), running fsck",
+            shut_ok,
+            hard_error_ok
+        );
+        match fsck(self, true) {
+            Ok(report) => {
+                report.log();
+                if report.unrepaired > 0 {
+                    kwarn!("FAT: fsck left problems unrepaired, mounting read-only");
+                    self.set_read_only();
+                    return Ok(());
+                }
+            }
+            Err(e) => {
+                kerror!("FAT: fsck failed: {:?}, mounting read-only", e);
+                self.set_read_only();
+                return Ok(());
+            }
+        }
+
+        // 所有问题都已经被修复，卷重新变为干净的状态
+        return self.mark_clean();
+    }
+
+    /// @brief 在挂载后第一次修改FAT表时，清除shut bit，把卷标记为正在使用。
+    /// 如果系统在卸载（或者sync）之前崩溃，那么下一次挂载时，能够通过这一位发现卷没有被正常卸载
+    fn mark_in_use(&self) -> Result<(), SystemError> {
+        if self.in_use.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        if let Err(e) = self.clear_shut_bit() {
+            self.in_use.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+        return Ok(());
+    }
+
+    /// @brief 把FSInfo刷入磁盘，并设置shut bit，把卷标记为干净的。在sync、卸载时调用
+    pub fn mark_clean(&self) -> Result<(), SystemError> {
+        // 只读的文件系统不能写入磁盘。shut bit保持原样，下一次挂载时会再次检查它
+        if self.is_read_only() {
+            return Ok(());
+        }
+
+        self.fs_info.0.lock().flush(&self.partition)?;
+        self.set_shut_bit_ok()?;
+        self.set_hard_error_bit_ok()?;
+        self.partition.disk().sync()?;
+        // 在此之后对FAT表的修改，会再次把卷标记为正在使用
+        self.in_use.store(false, Ordering::SeqCst);
+        return Ok(());
+    }
+
+    /// @brief 文件系统是否以只读方式挂载
+    #[inline]
+    pub fn is_read_only(&self) -> bool {
+        return self.read_only.load(Ordering::SeqCst);
+    }
+
+    /// @brief 把文件系统设置为只读。设置之后，所有修改文件系统的操作都会返回EROFS
+    #[inline]
+    pub fn set_read_only(&self) {
+        self.read_only.store(true, Ordering::SeqCst);
+    }
+
     /// @brief 计算每个簇有多少个字节
     #[inline]
     pub fn bytes_per_cluster(&self) -> u64 {
@@ -774,7 +1035,7 @@ impl FATFileSystem {
     /// @return Ok(true) 正常
     /// @return Ok(false) 不正常
     /// @return Err(SystemError) 在判断时发生错误
-    pub fn is_shut_bit_ok(&mut self) -> Result<bool, SystemError> {
+    pub fn is_shut_bit_ok(&self) -> Result<bool, SystemError> {
         match self.bpb.fat_type {
             FATType::FAT32(_) => {
                 // 对于FAT32, error bit位于第一个扇区的第8字节。
@@ -797,7 +1058,7 @@ impl FATFileSystem {
     /// @return Ok(true) 正常
     /// @return Ok(false) 不正常
     /// @return Err(SystemError) 在判断时发生错误
-    pub fn is_hard_error_bit_ok(&mut self) -> Result<bool, SystemError> {
+    pub fn is_hard_error_bit_ok(&self) -> Result<bool, SystemError> {
         match self.bpb.fat_type {
             FATType::FAT32(_) => {
                 let bit = self.get_fat_entry_raw(Cluster::new(1))? & 0x0400_0000;
@@ -816,7 +1077,7 @@ impl FATFileSystem {
     ///
     /// @return Ok(()) 设置成功
     /// @return Err(SystemError) 在设置过程中，出现错误
-    pub fn set_shut_bit_ok(&mut self) -> Result<(), SystemError> {
+    pub fn set_shut_bit_ok(&self) -> Result<(), SystemError> {
         match self.bpb.fat_type {
             FATType::FAT32(_) => {
                 let raw_entry = self.get_fat_entry_raw(Cluster::new(1))? | 0x0800_0000;
@@ -834,12 +1095,35 @@ impl FATFileSystem {
         }
     }
 
+    /// @brief 清除文件系统的shut bit，表示卷正在被使用。
+    /// 如果系统在卸载之前崩溃，那么下一次挂载时，能够通过这一位发现卷没有被正常卸载
+    /// 参考资料：https://thestarman.pcministry.com/DOS/DirtyShutdownFlag.html
+    ///
+    /// @return Ok(()) 设置成功
+    /// @return Err(SystemError) 在设置过程中，出现错误
+    pub fn clear_shut_bit(&self) -> Result<(), SystemError> {
+        match self.bpb.fat_type {
+            FATType::FAT32(_) => {
+                let raw_entry = self.get_fat_entry_raw(Cluster::new(1))? & !0x0800_0000;
+                self.set_entry(Cluster::new(1), FATEntry::Next(Cluster::new(raw_entry)))?;
+                return Ok(());
+            }
+
+            FATType::FAT16(_) => {
+                let raw_entry = self.get_fat_entry_raw(Cluster::new(1))? & !0x8000;
+                self.set_entry(Cluster::new(1), FATEntry::Next(Cluster::new(raw_entry)))?;
+                return Ok(());
+            }
+            _ => return Ok(()),
+        }
+    }
+
     /// @brief 设置文件系统的hard error bit为正常状态
     /// 参考资料：https://thestarman.pcministry.com/DOS/DirtyShutdownFlag.html
     ///
     /// @return Ok(()) 设置成功
     /// @return Err(SystemError) 在设置过程中，出现错误
-    pub fn set_hard_error_bit_ok(&mut self) -> Result<(), SystemError> {
+    pub fn set_hard_error_bit_ok(&self) -> Result<(), SystemError> {
         match self.bpb.fat_type {
             FATType::FAT32(_) => {
                 let raw_entry = self.get_fat_entry_raw(Cluster::new(1))? | 0x0400_0000;
@@ -858,15 +1142,7 @@ impl FATFileSystem {
 
     /// @brief 执行文件系统卸载前的一些准备工作：设置好对应的标志位，并把缓存中的数据刷入磁盘
     pub fn umount(&mut self) -> Result<(), SystemError> {
-        self.fs_info.0.lock().flush(&self.partition)?;
-
-        self.set_shut_bit_ok()?;
-
-        self.set_hard_error_bit_ok()?;
-
-        self.partition.disk().sync()?;
-
-        return Ok(());
+        return self.mark_clean();
     }
 
     /// @brief 获取文件系统的最大簇号
@@ -904,6 +1180,36 @@ impl FATFileSystem {
         }
     }
 
+    /// @brief 获取文件系统中空闲簇的数量
+    ///
+    /// 优先使用FsInfo中记录的值。如果FsInfo中的值不可用（比如FAT12、FAT16没有FsInfo），
+    /// 则遍历FAT表进行统计，并把结果记录到FsInfo中，同时更新第一个空闲簇的位置
+    ///
+    /// @return Ok(u64) 空闲簇的数量
+    /// @return Err(SystemError) 读取FAT表时出现错误
+    pub fn count_free_clusters(&self) -> Result<u64, SystemError> {
+        let max_cluster: Cluster = self.max_cluster_number();
+        if let Some(count) = self.fs_info.0.lock().count_free_cluster(max_cluster) {
+            return Ok(count);
+        }
+
+        let mut count: u64 = 0;
+        let mut first_free: Option<u64> = None;
+        for cluster in RESERVED_CLUSTERS as u64..=max_cluster.cluster_num {
+            if let FATEntry::Unused = self.get_fat_entry(Cluster::new(cluster))? {
+                count += 1;
+                first_free.get_or_insert(cluster);
+            }
+        }
+
+        let mut fs_info = self.fs_info.0.lock();
+        fs_info.update_free_count_abs(count as u32);
+        if let Some(first_free) = first_free {
+            fs_info.update_next_free(first_free as u32);
+        }
+        return Ok(count);
+    }
+
     /// @brief 在文件系统中寻找一个簇号在给定的范围（左闭右开区间）内的空闲簇
     ///
     /// @param start_cluster 起始簇号
@@ -1039,6 +1345,11 @@ impl FATFileSystem {
     /// @param cluster 目标簇
     /// @param fat_entry 这个簇在FAT表中，存储的信息（下一个簇的簇号）
     pub fn set_entry(&self, cluster: Cluster, fat_entry: FATEntry) -> Result<(), SystemError> {
+        // 簇1的表项中保存的是卷的状态标志，修改其他表项时，把卷标记为正在使用
+        if cluster.cluster_num >= RESERVED_CLUSTERS as u64 {
+            self.mark_in_use()?;
+        }
+
         // fat表项在分区上的字节偏移量
         let fat_part_bytes_offset: u64 = self.bpb.fat_type.get_fat_bytes_offset(
             cluster,
@@ -1085,7 +1396,7 @@ impl FATFileSystem {
                 let raw_val: u16 = match fat_entry {
                     FATEntry::Unused => 0,
                     FATEntry::Bad => 0xfff7,
-                    FATEntry::EndOfChain => 0xfdff,
+                    FATEntry::EndOfChain => 0xffff,
                     FATEntry::Next(c) => c.cluster_num as u16,
                 };
 
@@ -1359,13 +1670,29 @@ impl IndexNode for LockedFATInode {
         _data: &mut FilePrivateData,
     ) -> Result<usize, SystemError> {
         let mut guard: SpinLockGuard<FATInode> = self.0.lock();
-        match &guard.inode_type {
+        let fs: Arc<FATFileSystem> = guard.fs.upgrade().unwrap();
+        let page_cache: Option<Arc<PageCache>> = guard.page_cache.clone();
+
+        match &mut guard.inode_type {
             FATDirEntry::File(f) | FATDirEntry::VolId(f) => {
-                let r = f.read(
-                    &guard.fs.upgrade().unwrap(),
-                    &mut buf[0..len],
-                    offset as u64,
-                );
+                let r = match page_cache {
+                    // 通过页缓存读取
+                    Some(pc) => pc.read(
+                        offset,
+                        &mut buf[0..len],
+                        f.size() as usize,
+                        |index, page| f.read(&fs, page, (index * MMArch::PAGE_SIZE) as u64),
+                    ),
+                    None => f.read(&fs, &mut buf[0..len], offset as u64),
+                };
+                // FAT只记录了最后访问的日期，因此只有在日期变化时才需要写回目录项
+                // 只读的文件系统不更新访问时间
+                if r.is_ok()
+                    && !fs.is_read_only()
+                    && f.short_dir_entry.set_access_time(&TimeSpec::now())
+                {
+                    f.flush_dir_entry(&fs)?;
+                }
                 guard.update_metadata();
                 return r;
             }
@@ -1387,11 +1714,47 @@ impl IndexNode for LockedFATInode {
         _data: &mut FilePrivateData,
     ) -> Result<usize, SystemError> {
         let mut guard: SpinLockGuard<FATInode> = self.0.lock();
+        guard.check_writable()?;
         let fs: &Arc<FATFileSystem> = &guard.fs.upgrade().unwrap();
+        let page_cache: Option<Arc<PageCache>> = guard.page_cache.clone();
 
         match &mut guard.inode_type {
             FATDirEntry::File(f) | FATDirEntry::VolId(f) => {
-                let r = f.write(fs, &buf[0..len], offset as u64);
+                let file_size = f.size() as usize;
+                let r = match page_cache {
+                    // 数据只写入页缓存，等到sync时再写回
+                    Some(pc) => {
+                        let mut r: Result<(), SystemError> = Ok(());
+                        if offset + len > file_size {
+                            // 扩展文件长度的写操作，需要先分配簇、更新目录项中的文件大小。
+                            // 新分配的簇中可能残留着旧的数据，因此先把文件末尾所在的页读入缓存，
+                            // 之后超出原文件大小的页都直接填0，而不从磁盘读取
+                            r = pc
+                                .get_or_read_page(
+                                    file_size / MMArch::PAGE_SIZE,
+                                    file_size,
+                                    &mut |index, page| {
+                                        f.read(fs, page, (index * MMArch::PAGE_SIZE) as u64)
+                                    },
+                                )
+                                .map(|_| ());
+                            if r.is_ok() {
+                                r = f.ensure_len(fs, offset as u64, len as u64);
+                            }
+                        }
+                        r.and_then(|_| {
+                            pc.write(offset, &buf[0..len], file_size, |index, page| {
+                                f.read(fs, page, (index * MMArch::PAGE_SIZE) as u64)
+                            })
+                        })
+                    }
+                    None => f.write(fs, &buf[0..len], offset as u64),
+                };
+                if r.is_ok() {
+                    // 扩展文件的写操作已经把目录项写回了磁盘，修改时间等到写回页缓存时再刷入
+                    f.short_dir_entry.set_write_time(&TimeSpec::now());
+                    guard.dentry_dirty = true;
+                }
                 guard.update_metadata();
                 return r;
             }
@@ -1425,6 +1788,7 @@ impl IndexNode for LockedFATInode {
     ) -> Result<Arc<dyn IndexNode>, SystemError> {
         // 由于FAT32不支持文件权限的功能，因此忽略mode参数
         let mut guard: SpinLockGuard<FATInode> = self.0.lock();
+        guard.check_writable()?;
         let fs: &Arc<FATFileSystem> = &guard.fs.upgrade().unwrap();
 
         match &mut guard.inode_type {
@@ -1462,10 +1826,48 @@ impl IndexNode for LockedFATInode {
     fn metadata(&self) -> Result<Metadata, SystemError> {
         return Ok(self.0.lock().metadata.clone());
     }
+
+    fn set_metadata(&self, metadata: &Metadata) -> Result<(), SystemError> {
+        let mut guard: SpinLockGuard<FATInode> = self.0.lock();
+        guard.check_writable()?;
+        // FAT不支持权限位和文件所有者，只能修改时间信息
+        if metadata.mode != guard.metadata.mode
+            || metadata.uid != guard.metadata.uid
+            || metadata.gid != guard.metadata.gid
+        {
+            return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+        }
+
+        let fs: Arc<FATFileSystem> = guard.fs.upgrade().unwrap();
+        match &mut guard.inode_type {
+            FATDirEntry::File(f) | FATDirEntry::VolId(f) => {
+                f.short_dir_entry.set_access_time(&metadata.atime);
+                f.short_dir_entry.set_write_time(&metadata.mtime);
+                f.flush_dir_entry(&fs)?;
+            }
+            FATDirEntry::Dir(d) => {
+                if let Some(short_dir_entry) = &mut d.short_dir_entry {
+                    short_dir_entry.set_access_time(&metadata.atime);
+                    short_dir_entry.set_write_time(&metadata.mtime);
+                }
+                d.flush_dir_entry(&fs)?;
+            }
+            FATDirEntry::UnInit => {
+                kerror!("FATFS: param: Inode_type uninitialized.");
+                return Err(SystemError::EROFS);
+            }
+        }
+        guard.update_metadata();
+        return Ok(());
+    }
     fn resize(&self, len: usize) -> Result<(), SystemError> {
         let mut guard: SpinLockGuard<FATInode> = self.0.lock();
+        guard.check_writable()?;
         let fs: &Arc<FATFileSystem> = &guard.fs.upgrade().unwrap();
         let old_size = guard.metadata.size as usize;
+        // 先把页缓存中的脏页写回，再修改磁盘上的文件
+        guard.writeback_pages()?;
+        let page_cache: Option<Arc<PageCache>> = guard.page_cache.clone();
 
         match &mut guard.inode_type {
             FATDirEntry::File(file) | FATDirEntry::VolId(file) => {
@@ -1489,6 +1891,9 @@ impl IndexNode for LockedFATInode {
                     }
                 } else {
                     file.truncate(fs, len as u64)?;
+                    if let Some(pc) = page_cache {
+                        pc.truncate(len);
+                    }
                 }
                 guard.update_metadata();
                 return Ok(());
@@ -1560,9 +1965,10 @@ impl IndexNode for LockedFATInode {
 
     fn unlink(&self, name: &str) -> Result<(), SystemError> {
         let mut guard: SpinLockGuard<FATInode> = self.0.lock();
+        guard.check_writable()?;
         let target: Arc<LockedFATInode> = guard.find(name)?;
         // 对目标inode上锁，以防更改
-        let target_guard: SpinLockGuard<FATInode> = target.0.lock();
+        let mut target_guard: SpinLockGuard<FATInode> = target.0.lock();
         // 先从缓存删除
         guard.children.remove(&name.to_uppercase());
 
@@ -1579,14 +1985,20 @@ impl IndexNode for LockedFATInode {
         // 检查文件是否存在
         dir.check_existence(name, Some(false), guard.fs.upgrade().unwrap())?;
 
-        // 再从磁盘删除
-        let r = dir.remove(guard.fs.upgrade().unwrap().clone(), name, true);
+        // 再从磁盘删除目录项。文件可能仍被打开，因此数据簇要等到inode被释放时才回收（见FATInode的drop）
+        dir.remove(guard.fs.upgrade().unwrap().clone(), name, false)?;
+        if let FATDirEntry::File(f) | FATDirEntry::VolId(f) = &mut target_guard.inode_type {
+            f.unlinked = true;
+        }
+        target_guard.metadata.nlinks = 0;
+        target_guard.metadata.ctime = TimeSpec::now();
         drop(target_guard);
-        return r;
+        return Ok(());
     }
 
     fn rmdir(&self, name: &str) -> Result<(), SystemError> {
         let mut guard: SpinLockGuard<FATInode> = self.0.lock();
+        guard.check_writable()?;
         let target: Arc<LockedFATInode> = guard.find(name)?;
         // 对目标inode上锁，以防更改
         let target_guard: SpinLockGuard<FATInode> = target.0.lock();
@@ -1622,6 +2034,111 @@ impl IndexNode for LockedFATInode {
         }
     }
 
+    fn move_(
+        &self,
+        old_name: &str,
+        target: &Arc<dyn IndexNode>,
+        new_name: &str,
+    ) -> Result<(), SystemError> {
+        // 目标目录必须也是FAT文件系统的inode
+        let target: &LockedFATInode = target
+            .downcast_ref::<LockedFATInode>()
+            .ok_or(SystemError::EXDEV)?;
+
+        if old_name == "." || old_name == ".." || new_name == "." || new_name == ".." {
+            return Err(SystemError::EBUSY);
+        }
+
+        // 在同一个目录下重命名
+        if core::ptr::eq(self, target) {
+            let mut guard: SpinLockGuard<FATInode> = self.0.lock();
+            guard.check_writable()?;
+            let dir: FATDir = match &guard.inode_type {
+                FATDirEntry::File(_) | FATDirEntry::VolId(_) => {
+                    return Err(SystemError::ENOTDIR);
+                }
+                FATDirEntry::Dir(d) => d.clone(),
+                FATDirEntry::UnInit => {
+                    kerror!("FATFS: param: Inode_type uninitialized.");
+                    return Err(SystemError::EROFS);
+                }
+            };
+            // 获取被移动的inode，以及将被替换的inode（如果不在缓存中，会被加入缓存）
+            let child: Arc<LockedFATInode> = guard.find(old_name)?;
+            let victim: Option<Arc<LockedFATInode>> = guard.find_victim(new_name, &child)?;
+            let new_entry: FATDirEntry =
+                dir.rename(guard.fs.upgrade().unwrap(), old_name, new_name)?;
+
+            // 目录项在磁盘上的位置发生了变化，需要更新inode中保存的目录项
+            child.0.lock().inode_type = new_entry;
+            guard.children.remove(&old_name.to_uppercase());
+            guard.children.insert(new_name.to_uppercase(), child);
+            if let Some(victim) = victim {
+                victim.mark_removed();
+            }
+            return Ok(());
+        }
+
+        // 跨目录移动。由VFS保证同一时刻只有一个跨目录的移动操作，因此这里按照固定的顺序加锁不会死锁
+        let mut guard: SpinLockGuard<FATInode> = self.0.lock();
+        let mut target_guard: SpinLockGuard<FATInode> = target.0.lock();
+        guard.check_writable()?;
+        if !guard.fs.ptr_eq(&target_guard.fs) {
+            return Err(SystemError::EXDEV);
+        }
+
+        let (dir, target_dir): (FATDir, FATDir) =
+            match (&guard.inode_type, &target_guard.inode_type) {
+                (FATDirEntry::Dir(d), FATDirEntry::Dir(t)) => (d.clone(), t.clone()),
+                (FATDirEntry::UnInit, _) | (_, FATDirEntry::UnInit) => {
+                    kerror!("FATFS: param: Inode_type uninitialized.");
+                    return Err(SystemError::EROFS);
+                }
+                _ => return Err(SystemError::ENOTDIR),
+            };
+
+        let child: Arc<LockedFATInode> = guard.find(old_name)?;
+        let victim: Option<Arc<LockedFATInode>> = target_guard.find_victim(new_name, &child)?;
+        let new_entry: FATDirEntry =
+            dir.rename_across(guard.fs.upgrade().unwrap(), &target_dir, old_name, new_name)?;
+
+        {
+            let mut child_guard: SpinLockGuard<FATInode> = child.0.lock();
+            // 被移动的是文件夹，那么要更新它的父目录
+            if let FATDirEntry::Dir(_) = new_entry {
+                child_guard.parent = target_guard.self_ref.clone();
+            }
+            child_guard.inode_type = new_entry;
+        }
+        guard.children.remove(&old_name.to_uppercase());
+        target_guard.children.insert(new_name.to_uppercase(), child);
+        if let Some(victim) = victim {
+            victim.mark_removed();
+        }
+        return Ok(());
+    }
+
+    fn sync(&self) -> Result<(), SystemError> {
+        return self.0.lock().writeback_pages();
+    }
+
+    fn get_page(&self, index: usize) -> Result<Arc<CachedPage>, SystemError> {
+        let guard: SpinLockGuard<FATInode> = self.0.lock();
+        let fs: Arc<FATFileSystem> = guard.fs.upgrade().unwrap();
+        let page_cache: Arc<PageCache> = guard.page_cache.clone().ok_or(SystemError::ENODEV)?;
+
+        match &guard.inode_type {
+            FATDirEntry::File(f) | FATDirEntry::VolId(f) => {
+                return page_cache.get_or_read_page(
+                    index,
+                    f.size() as usize,
+                    &mut |index, page| f.read(&fs, page, (index * MMArch::PAGE_SIZE) as u64),
+                );
+            }
+            _ => return Err(SystemError::ENODEV),
+        }
+    }
+
     fn get_entry_name(&self, ino: InodeId) -> Result<String, SystemError> {
         let guard: SpinLockGuard<FATInode> = self.0.lock();
         if guard.metadata.file_type != FileType::Dir {
@@ -0,0 +1,668 @@
+use alloc::{format, string::String, sync::Arc, vec::Vec};
+
+use crate::{kinfo, kwarn, syscall::SystemError};
+
+use super::{
+    bpb::FATType,
+    entry::{FATDir, FATDirEntry, FATEntry, FATFile},
+    fs::{Cluster, FATFileSystem},
+    utils::RESERVED_CLUSTERS,
+};
+
+/// 每次从磁盘读取FAT表时，读取的扇区数
+const FAT_READ_SECTORS: u64 = 64;
+
+/// @brief FAT文件系统一致性检查的结果
+#[derive(Debug, Default, Clone)]
+pub struct FsckReport {
+    /// 检查过的文件数量
+    pub files: u64,
+    /// 检查过的文件夹数量（包括根目录）
+    pub dirs: u64,
+    /// 丢失的簇链（在FAT表中被占用，但是不属于任何文件）的数量
+    pub lost_chains: u64,
+    /// 丢失的簇的数量
+    pub lost_clusters: u64,
+    /// 交叉链接（同时属于多个文件，或者簇链成环）的次数
+    pub cross_linked: u64,
+    /// 损坏的簇链（指向非法的簇号、空闲簇或者坏簇）的数量
+    pub broken_chains: u64,
+    /// 文件大小与簇链长度不符的文件的数量
+    pub bad_file_sizes: u64,
+    /// 大小字段不为0的文件夹的数量
+    pub bad_dir_sizes: u64,
+    /// FSInfo中记录的空闲簇数量与实际不符时，记录(FSInfo中的值, 实际的值)
+    pub free_count_mismatch: Option<(u64, u64)>,
+    /// 已经修复的问题的数量
+    pub repaired: u64,
+    /// 没有修复的问题的数量
+    pub unrepaired: u64,
+}
+
+impl FsckReport {
+    /// @brief 文件系统是否没有任何问题
+    pub fn is_clean(&self) -> bool {
+        return self.repaired == 0 && self.unrepaired == 0;
+    }
+
+    /// @brief 把检查结果输出到内核日志
+    pub fn log(&self) {
+        kinfo!(
+            "FAT fsck: checked {} files, {} directories",
+            self.files,
+            self.dirs
+        );
+        if self.is_clean() {
+            kinfo!("FAT fsck: filesystem is clean");
+            return;
+        }
+        if self.lost_chains > 0 {
+            kwarn!(
+                "FAT fsck: {} lost cluster chains ({} clusters)",
+                self.lost_chains,
+                self.lost_clusters
+            );
+        }
+        if self.cross_linked > 0 {
+            kwarn!("FAT fsck: {} cross-linked clusters", self.cross_linked);
+        }
+        if self.broken_chains > 0 {
+            kwarn!("FAT fsck: {} broken cluster chains", self.broken_chains);
+        }
+        if self.bad_file_sizes > 0 {
+            kwarn!("FAT fsck: {} files with wrong size", self.bad_file_sizes);
+        }
+        if self.bad_dir_sizes > 0 {
+            kwarn!(
+                "FAT fsck: {} directories with non-zero size",
+                self.bad_dir_sizes
+            );
+        }
+        if let Some((recorded, actual)) = self.free_count_mismatch {
+            kwarn!(
+                "FAT fsck: FSInfo free cluster count is {}, should be {}",
+                recorded,
+                actual
+            );
+        }
+        kinfo!(
+            "FAT fsck: {} problems repaired, {} left unrepaired",
+            self.repaired,
+            self.unrepaired
+        );
+    }
+}
+
+/// @brief 检查FAT文件系统的一致性
+///
+/// 检查的内容包括：丢失的簇链、交叉链接的簇、损坏的簇链、文件/文件夹的大小，以及FSInfo中的空闲簇数量。
+/// 修复的方式与dosfsck相同：在出错的位置截断簇链，按照簇链的长度修正文件大小，释放丢失的簇链
+///
+/// 本函数应当在文件系统被使用之前（挂载时）调用
+///
+/// @param fs 要检查的文件系统
+/// @param repair 是否修复发现的问题。为false时，只检查而不写入磁盘
+///
+/// @return Ok(FsckReport) 检查结果
+/// @return Err(SystemError) 读写磁盘时出现错误
+pub fn fsck(fs: &Arc<FATFileSystem>, repair: bool) -> Result<FsckReport, SystemError> {
+    let mut checker = FsckChecker::new(fs, repair)?;
+    checker.check_tree()?;
+    checker.check_lost_clusters()?;
+    checker.check_free_count()?;
+    return Ok(checker.report);
+}
+
+/// @brief 每个簇占用一位的位图
+struct ClusterBitmap {
+    bits: Vec<u64>,
+}
+
+impl ClusterBitmap {
+    fn new(len: usize) -> Self {
+        return Self {
+            bits: vec![0u64; (len + 63) / 64],
+        };
+    }
+
+    #[inline]
+    fn get(&self, cluster: u64) -> bool {
+        return self.bits[(cluster / 64) as usize] & (1 << (cluster % 64)) != 0;
+    }
+
+    #[inline]
+    fn set(&mut self, cluster: u64, val: bool) {
+        let word = &mut self.bits[(cluster / 64) as usize];
+        if val {
+            *word |= 1 << (cluster % 64);
+        } else {
+            *word &= !(1 << (cluster % 64));
+        }
+    }
+}
+
+/// @brief FAT文件系统的检查器
+struct FsckChecker<'a> {
+    fs: &'a Arc<FATFileSystem>,
+    /// 是否修复发现的问题
+    repair: bool,
+    /// FAT表项的数量（最大簇号+1）
+    num_entries: u64,
+    /// 当前被读入内存的一段FAT表，起始位置为fat_window_start（相对于FAT表开头的字节偏移量）。
+    /// FAT表可能很大，因此每次只读取FAT_READ_SECTORS个扇区
+    fat_window: Vec<u8>,
+    fat_window_start: Option<u64>,
+    /// 簇是否已经被某个文件（或文件夹）引用
+    referenced: ClusterBitmap,
+    report: FsckReport,
+}
+
+impl<'a> FsckChecker<'a> {
+    fn new(fs: &'a Arc<FATFileSystem>, repair: bool) -> Result<Self, SystemError> {
+        let num_entries: u64 = fs.max_cluster_number().cluster_num + 1;
+        return Ok(Self {
+            fs,
+            repair,
+            num_entries,
+            fat_window: Vec::new(),
+            fat_window_start: None,
+            referenced: ClusterBitmap::new(num_entries as usize),
+            report: FsckReport::default(),
+        });
+    }
+
+    /// @brief 读取FAT表中的一个字节
+    ///
+    /// 逐个簇调用get_fat_entry()需要为每个表项读取一次磁盘，因此这里按扇区批量读取，并缓存最近读取的一段
+    ///
+    /// @param offset 相对于FAT表开头的字节偏移量
+    fn fat_byte(&mut self, offset: u64) -> Result<u8, SystemError> {
+        let bytes_per_sec: u64 = self.fs.bpb.bytes_per_sector as u64;
+        let window_bytes: u64 = FAT_READ_SECTORS * bytes_per_sec;
+        let start: u64 = offset / window_bytes * window_bytes;
+        if self.fat_window_start != Some(start) {
+            self.fat_window_start = None;
+            let sector: u64 = start / bytes_per_sec;
+            let n: u64 = FAT_READ_SECTORS.min(self.fs.fat_size() - sector);
+            self.fat_window.resize((n * bytes_per_sec) as usize, 0);
+            self.fs.partition.disk().read_at(
+                self.fs
+                    .get_lba_from_offset(self.fs.fat_start_sector() + sector),
+                n as usize * self.fs.lba_per_sector(),
+                &mut self.fat_window,
+            )?;
+            self.fat_window_start = Some(start);
+        }
+        return Ok(self.fat_window[(offset - start) as usize]);
+    }
+
+    /// @brief 如果FAT表中的一个字节已经被读入内存，那么更新它
+    fn patch_fat_byte(&mut self, offset: u64, val: u8) {
+        if let Some(start) = self.fat_window_start {
+            if offset >= start && offset < start + self.fat_window.len() as u64 {
+                self.fat_window[(offset - start) as usize] = val;
+            }
+        }
+    }
+
+    /// @brief 读取原始的FAT表项值
+    fn raw_entry(&mut self, cluster: u64) -> Result<u32, SystemError> {
+        match self.fs.bpb.fat_type {
+            FATType::FAT12(_) => {
+                // 每个表项占用1.5字节，奇数的簇取高12位
+                let off = cluster + cluster / 2;
+                let val = u16::from_le_bytes([self.fat_byte(off)?, self.fat_byte(off + 1)?]);
+                return Ok(fat12_entry(val, cluster));
+            }
+            FATType::FAT16(_) => {
+                let off = cluster * 2;
+                let val = u16::from_le_bytes([self.fat_byte(off)?, self.fat_byte(off + 1)?]);
+                return Ok(val as u32);
+            }
+            FATType::FAT32(_) => {
+                let off = cluster * 4;
+                let mut bytes = [0u8; 4];
+                for (i, b) in bytes.iter_mut().enumerate() {
+                    *b = self.fat_byte(off + i as u64)?;
+                }
+                return Ok(u32::from_le_bytes(bytes) & 0x0fff_ffff);
+            }
+        }
+    }
+
+    /// @brief 读取并解析FAT表项
+    fn entry(&mut self, cluster: u64) -> Result<FATEntry, SystemError> {
+        let raw: u32 = self.raw_entry(cluster)?;
+        return Ok(parse_entry(&self.fs.bpb.fat_type, cluster, raw));
+    }
+
+    /// @brief 修改磁盘上的FAT表项，并同步更新已经读入内存的部分
+    fn set_entry(&mut self, cluster: u64, entry: FATEntry) -> Result<(), SystemError> {
+        let raw: u32 = encode_entry(&self.fs.bpb.fat_type, &entry);
+        self.fs.set_entry(Cluster::new(cluster), entry)?;
+
+        match self.fs.bpb.fat_type {
+            FATType::FAT12(_) => {
+                let off = cluster + cluster / 2;
+                let old = u16::from_le_bytes([self.fat_byte(off)?, self.fat_byte(off + 1)?]);
+                let new = if cluster & 1 != 0 {
+                    (old & 0x000f) | ((raw as u16) << 4)
+                } else {
+                    (old & 0xf000) | (raw as u16 & 0x0fff)
+                };
+                let [lo, hi] = new.to_le_bytes();
+                self.patch_fat_byte(off, lo);
+                self.patch_fat_byte(off + 1, hi);
+            }
+            FATType::FAT16(_) => {
+                let off = cluster * 2;
+                for (i, b) in (raw as u16).to_le_bytes().iter().enumerate() {
+                    self.patch_fat_byte(off + i as u64, *b);
+                }
+            }
+            FATType::FAT32(_) => {
+                // FAT32表项的高4位是保留的，不会被修改
+                let off = cluster * 4;
+                let high = self.fat_byte(off + 3)? & 0xf0;
+                let mut bytes = raw.to_le_bytes();
+                bytes[3] = (bytes[3] & 0x0f) | high;
+                for (i, b) in bytes.iter().enumerate() {
+                    self.patch_fat_byte(off + i as u64, *b);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    /// @brief 判断簇号是否处于数据区的范围内
+    #[inline]
+    fn is_valid_cluster(&self, cluster: u64) -> bool {
+        return cluster >= RESERVED_CLUSTERS as u64 && cluster < self.num_entries;
+    }
+
+    /// @brief 记录一个问题。如果需要修复，则调用fix来修复它
+    fn problem<F>(&mut self, fix: F) -> Result<(), SystemError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), SystemError>,
+    {
+        if self.repair {
+            fix(self)?;
+            self.report.repaired += 1;
+        } else {
+            self.report.unrepaired += 1;
+        }
+        return Ok(());
+    }
+
+    /// @brief 沿着簇链标记被引用的簇
+    ///
+    /// 遇到非法的簇号、空闲簇、坏簇，或者已经被引用过的簇时，在它之前截断簇链
+    ///
+    /// @param first 簇链的第一个簇
+    /// @param path 簇链所属的文件的路径（用于输出日志）
+    ///
+    /// @return 簇链中有效的簇的数量。返回0表示第一个簇就是无效的，需要由调用者修正目录项
+    fn walk_chain(&mut self, first: u64, path: &str) -> Result<u64, SystemError> {
+        let mut prev: Option<u64> = None;
+        let mut cluster: u64 = first;
+        let mut count: u64 = 0;
+        loop {
+            let reason: Option<&str> = if !self.is_valid_cluster(cluster) {
+                self.report.broken_chains += 1;
+                Some("points to an invalid cluster")
+            } else if self.referenced.get(cluster) {
+                self.report.cross_linked += 1;
+                Some("is cross-linked")
+            } else {
+                match self.entry(cluster)? {
+                    FATEntry::Unused | FATEntry::Bad => {
+                        self.report.broken_chains += 1;
+                        Some("points to a free or bad cluster")
+                    }
+                    _ => None,
+                }
+            };
+
+            if let Some(reason) = reason {
+                kwarn!(
+                    "FAT fsck: cluster chain of '{}' {} at cluster {}, truncating",
+                    path,
+                    reason,
+                    cluster
+                );
+                if let Some(prev) = prev {
+                    self.problem(|c| c.set_entry(prev, FATEntry::EndOfChain))?;
+                }
+                return Ok(count);
+            }
+
+            self.referenced.set(cluster, true);
+            count += 1;
+            match self.entry(cluster)? {
+                FATEntry::Next(next) => {
+                    prev = Some(cluster);
+                    cluster = next.cluster_num;
+                }
+                _ => return Ok(count),
+            }
+        }
+    }
+
+    /// @brief 释放簇链中，从第n个簇（下标从0开始）开始的所有簇。n大于0时，第n-1个簇成为簇链的末尾
+    fn free_chain_from(&mut self, first: u64, n: u64) -> Result<(), SystemError> {
+        let mut clusters: Vec<u64> = Vec::new();
+        let mut cluster: u64 = first;
+        loop {
+            clusters.push(cluster);
+            match self.entry(cluster)? {
+                FATEntry::Next(next) => cluster = next.cluster_num,
+                _ => break,
+            }
+        }
+
+        if n > 0 {
+            self.set_entry(clusters[n as usize - 1], FATEntry::EndOfChain)?;
+        }
+        for c in &clusters[n as usize..] {
+            self.set_entry(*c, FATEntry::Unused)?;
+            self.referenced.set(*c, false);
+        }
+        return Ok(());
+    }
+
+    /// @brief 遍历整个目录树，检查每个文件和文件夹的簇链
+    fn check_tree(&mut self) -> Result<(), SystemError> {
+        let root: FATDir = self.fs.root_dir();
+        // FAT12/FAT16的根目录位于固定的区域，没有簇链
+        if !root.is_root() {
+            let first = root.first_cluster.cluster_num;
+            let unrepaired = self.report.unrepaired;
+            if self.walk_chain(first, "/")? == 0 {
+                // 根目录的簇链损坏，无法修复
+                self.report.unrepaired += 1;
+                return Err(SystemError::EIO);
+            }
+            if self.report.unrepaired != unrepaired {
+                // 没有修复的簇链可能成环，无法安全地遍历根目录
+                return Err(SystemError::EIO);
+            }
+        }
+
+        let mut stack: Vec<(FATDir, String)> = vec![(root, String::new())];
+        while let Some((dir, path)) = stack.pop() {
+            self.report.dirs += 1;
+            let entries: Vec<FATDirEntry> = dir.to_iter(self.fs.clone()).collect();
+            for entry in entries {
+                let short_name = entry.short_name();
+                if short_name == "." || short_name == ".." || entry.is_vol_id() {
+                    continue;
+                }
+                let entry_path: String = format!("{}/{}", path, entry.name());
+                match entry {
+                    FATDirEntry::File(f) => self.check_file(f, &entry_path)?,
+                    FATDirEntry::Dir(d) => {
+                        if let Some(d) = self.check_dir(d, &entry_path)? {
+                            stack.push((d, entry_path));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    /// @brief 检查文件的簇链和大小
+    fn check_file(&mut self, mut f: FATFile, path: &str) -> Result<(), SystemError> {
+        self.report.files += 1;
+        let bytes_per_cluster: u64 = self.fs.bytes_per_cluster();
+        let first: u64 = f.first_cluster.cluster_num;
+        let mut dirty = false;
+
+        let mut count: u64 = 0;
+        if first != 0 {
+            count = self.walk_chain(first, path)?;
+            if count == 0 {
+                // 第一个簇就是无效的，文件的内容无法恢复，因此把它变为一个空文件
+                self.problem(|_| {
+                    f.first_cluster = Cluster::new(0);
+                    f.short_dir_entry.set_first_cluster(Cluster::new(0));
+                    f.set_size(0);
+                    dirty = true;
+                    return Ok(());
+                })?;
+            }
+        }
+
+        let size: u64 = f.size();
+        let expected: u64 = (size + bytes_per_cluster - 1) / bytes_per_cluster;
+        if count > expected && f.first_cluster.cluster_num != 0 {
+            // 簇链比文件大小所需的更长，释放多余的簇
+            kwarn!(
+                "FAT fsck: '{}' has {} clusters but its size is {}, freeing the extra clusters",
+                path,
+                count,
+                size
+            );
+            self.report.bad_file_sizes += 1;
+            self.problem(|c| {
+                c.free_chain_from(first, expected)?;
+                if expected == 0 {
+                    f.first_cluster = Cluster::new(0);
+                    f.short_dir_entry.set_first_cluster(Cluster::new(0));
+                    dirty = true;
+                }
+                return Ok(());
+            })?;
+        } else if count < expected {
+            // 文件大小超出了簇链的长度，按照簇链的长度截断文件
+            let new_size: u64 = count * bytes_per_cluster;
+            kwarn!(
+                "FAT fsck: '{}' has size {} but only {} clusters, truncating to {}",
+                path,
+                size,
+                count,
+                new_size
+            );
+            self.report.bad_file_sizes += 1;
+            self.problem(|_| {
+                f.set_size(new_size as u32);
+                dirty = true;
+                return Ok(());
+            })?;
+        }
+
+        if dirty {
+            f.flush_dir_entry(self.fs)?;
+        }
+        return Ok(());
+    }
+
+    /// @brief 检查文件夹的簇链和大小
+    ///
+    /// @return Ok(Some(FATDir)) 需要继续检查这个文件夹中的内容
+    /// @return Ok(None) 文件夹已经损坏，不再检查其中的内容
+    fn check_dir(&mut self, mut d: FATDir, path: &str) -> Result<Option<FATDir>, SystemError> {
+        let first: u64 = d.first_cluster.cluster_num;
+        let unrepaired = self.report.unrepaired;
+        if first == 0 || self.walk_chain(first, path)? == 0 {
+            // 文件夹没有任何有效的簇，无法恢复。为了避免误删数据，不做修复
+            kwarn!(
+                "FAT fsck: directory '{}' has no valid clusters, leaving it unrepaired",
+                path
+            );
+            if first == 0 {
+                self.report.broken_chains += 1;
+            }
+            self.report.unrepaired += 1;
+            return Ok(None);
+        }
+        // 没有修复的簇链可能成环，不再遍历这个文件夹中的内容
+        let descend: bool = self.report.unrepaired == unrepaired;
+
+        // 按照FAT规范，文件夹的大小字段必须为0
+        let size: u32 = d.short_dir_entry.map(|e| e.file_size()).unwrap_or(0);
+        if size != 0 {
+            kwarn!(
+                "FAT fsck: directory '{}' has size {}, should be 0",
+                path,
+                size
+            );
+            self.report.bad_dir_sizes += 1;
+            self.problem(|c| {
+                if let Some(e) = &mut d.short_dir_entry {
+                    e.set_file_size(0);
+                }
+                return d.flush_dir_entry(c.fs);
+            })?;
+        }
+        if !descend {
+            return Ok(None);
+        }
+        return Ok(Some(d));
+    }
+
+    /// @brief 检查并释放丢失的簇链
+    ///
+    /// 丢失的簇在FAT表中被标记为已使用，但是不属于任何文件（例如，在写入过程中系统崩溃）
+    fn check_lost_clusters(&mut self) -> Result<(), SystemError> {
+        // 第一遍：统计丢失的簇，并标记被另一个丢失的簇指向的簇（它们不是簇链的开头）
+        let mut lost: u64 = 0;
+        let mut pointed = ClusterBitmap::new(self.num_entries as usize);
+        for cluster in RESERVED_CLUSTERS as u64..self.num_entries {
+            if self.referenced.get(cluster) {
+                continue;
+            }
+            match self.entry(cluster)? {
+                FATEntry::Next(next) => {
+                    lost += 1;
+                    if self.is_valid_cluster(next.cluster_num) {
+                        pointed.set(next.cluster_num, true);
+                    }
+                }
+                FATEntry::EndOfChain => lost += 1,
+                _ => {}
+            }
+        }
+        if lost == 0 {
+            return Ok(());
+        }
+
+        // 第二遍：统计簇链的数量，需要修复时，释放丢失的簇
+        let mut heads: u64 = 0;
+        for cluster in RESERVED_CLUSTERS as u64..self.num_entries {
+            if self.referenced.get(cluster) {
+                continue;
+            }
+            match self.entry(cluster)? {
+                FATEntry::Next(_) | FATEntry::EndOfChain => {
+                    if !pointed.get(cluster) {
+                        heads += 1;
+                    }
+                    if self.repair {
+                        self.set_entry(cluster, FATEntry::Unused)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // 成环的丢失簇链没有开头，至少算作一条
+        self.report.lost_chains = heads.max(1);
+        self.report.lost_clusters = lost;
+        kwarn!(
+            "FAT fsck: found {} lost clusters in {} chains",
+            lost,
+            self.report.lost_chains
+        );
+
+        // 每条丢失的簇链都算作一个问题
+        if self.repair {
+            self.report.repaired += self.report.lost_chains;
+        } else {
+            self.report.unrepaired += self.report.lost_chains;
+        }
+        return Ok(());
+    }
+
+    /// @brief 检查FSInfo中记录的空闲簇数量
+    fn check_free_count(&mut self) -> Result<(), SystemError> {
+        let mut free: u64 = 0;
+        let mut first_free: Option<u64> = None;
+        for cluster in RESERVED_CLUSTERS as u64..self.num_entries {
+            if let FATEntry::Unused = self.entry(cluster)? {
+                free += 1;
+                first_free.get_or_insert(cluster);
+            }
+        }
+
+        let max_cluster: Cluster = self.fs.max_cluster_number();
+        let recorded: Option<u64> = self.fs.fs_info.lock().count_free_cluster(max_cluster);
+        match recorded {
+            Some(recorded) if recorded != free => {
+                self.report.free_count_mismatch = Some((recorded, free));
+                self.problem(|c| c.write_free_count(free, first_free))?;
+            }
+            // FSInfo中没有记录空闲簇数量（或者记录的值不合理）时，顺便把它补上
+            None if self.repair => self.write_free_count(free, first_free)?,
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    /// @brief 把空闲簇的数量以及第一个空闲簇的位置写入FSInfo
+    fn write_free_count(&mut self, free: u64, first_free: Option<u64>) -> Result<(), SystemError> {
+        let mut fs_info = self.fs.fs_info.lock();
+        fs_info.update_free_count_abs(free as u32);
+        if let Some(first_free) = first_free {
+            fs_info.update_next_free(first_free as u32);
+        }
+        return fs_info.flush(&self.fs.partition);
+    }
+}
+
+/// @brief 从FAT12的两个字节中，取出簇号为cluster的表项。奇数的簇取高12位
+#[inline]
+fn fat12_entry(val: u16, cluster: u64) -> u32 {
+    if cluster & 1 != 0 {
+        return (val >> 4) as u32;
+    } else {
+        return (val & 0x0fff) as u32;
+    }
+}
+
+/// @brief 解析原始的FAT表项值
+fn parse_entry(fat_type: &FATType, cluster: u64, raw: u32) -> FATEntry {
+    let (bad, eoc): (u32, u32) = match fat_type {
+        FATType::FAT12(_) => (0x0ff7, 0x0ff8),
+        FATType::FAT16(_) => (0xfff7, 0xfff8),
+        FATType::FAT32(_) => (0x0fff_fff7, 0x0fff_fff8),
+    };
+    return match raw {
+        0 => FATEntry::Unused,
+        x if x == bad => FATEntry::Bad,
+        x if x >= eoc => FATEntry::EndOfChain,
+        x => FATEntry::Next(Cluster {
+            cluster_num: x as u64,
+            parent_cluster: cluster,
+        }),
+    };
+}
+
+/// @brief 把FAT表项编码为原始的值
+fn encode_entry(fat_type: &FATType, entry: &FATEntry) -> u32 {
+    return match entry {
+        FATEntry::Unused => 0,
+        FATEntry::Bad => match fat_type {
+            FATType::FAT12(_) => 0x0ff7,
+            FATType::FAT16(_) => 0xfff7,
+            FATType::FAT32(_) => 0x0fff_fff7,
+        },
+        FATEntry::EndOfChain => match fat_type {
+            FATType::FAT12(_) => 0x0fff,
+            FATType::FAT16(_) => 0xffff,
+            FATType::FAT32(_) => 0x0fff_ffff,
+        },
+        FATEntry::Next(c) => c.cluster_num as u32,
+    };
+}
@@ -1,4 +1,5 @@
 pub mod bpb;
 pub mod entry;
 pub mod fs;
+pub mod fsck;
 pub mod utils;
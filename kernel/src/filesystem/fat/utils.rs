@@ -1,5 +1,7 @@
 use core::char::REPLACEMENT_CHARACTER;
 
+use crate::time::TimeSpec;
+
 /// FAT文件系统保留开头的2个簇
 pub const RESERVED_CLUSTERS: u32 = 2;
 
@@ -13,3 +15,86 @@ pub fn decode_u8_ascii(value: u8) -> char {
         return REPLACEMENT_CHARACTER;
     }
 }
+
+/// FAT时间戳能够表示的最早的年份
+const FAT_EPOCH_YEAR: i64 = 1980;
+/// FAT时间戳能够表示的最晚的年份
+const FAT_MAX_YEAR: i64 = FAT_EPOCH_YEAR + 127;
+/// 一天所包含的秒数
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// @brief 计算公历日期距离1970年1月1日的天数
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // 把一年的起点移到3月1日，这样闰日就是一年中的最后一天
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    return era * 146097 + day_of_era - 719468;
+}
+
+/// @brief 根据距离1970年1月1日的天数，计算公历日期
+///
+/// @return (年, 月, 日)
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = if days >= 0 { days } else { days - 146096 } / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    return (year, month, day);
+}
+
+/// @brief 把FAT目录项中的日期、时间转换为时间戳
+///
+/// FAT没有记录时区，这里把它当作UTC时间。日期为0（即没有记录）时，返回1970年1月1日。
+///
+/// @param date 日期（bit 15-9: 从1980年开始的年数，bit 8-5: 月，bit 4-0: 日）
+/// @param time 时间（bit 15-11: 时，bit 10-5: 分，bit 4-0: 秒/2）
+/// @param tenth 10毫秒的个数（0-199，仅创建时间有这个字段）
+pub fn fat_time_to_timespec(date: u16, time: u16, tenth: u8) -> TimeSpec {
+    let year = FAT_EPOCH_YEAR + (date >> 9) as i64;
+    let month = ((date >> 5) & 0xf) as i64;
+    let day = (date & 0x1f) as i64;
+    if month == 0 || day == 0 {
+        return TimeSpec::default();
+    }
+
+    let hour = (time >> 11) as i64;
+    let minute = ((time >> 5) & 0x3f) as i64;
+    let second = ((time & 0x1f) as i64) * 2 + (tenth as i64) / 100;
+
+    let secs =
+        days_from_civil(year, month, day) * SECS_PER_DAY + hour * 3600 + minute * 60 + second;
+    return TimeSpec::new(secs, ((tenth as i64) % 100) * 10_000_000);
+}
+
+/// @brief 把时间戳转换为FAT目录项中的日期、时间
+///
+/// 超出FAT能够表示的范围（1980年-2107年）的时间会被截断到边界上。
+///
+/// @return (日期, 时间, 10毫秒的个数)
+pub fn timespec_to_fat_time(ts: &TimeSpec) -> (u16, u16, u8) {
+    let (mut year, mut month, mut day) = civil_from_days(ts.tv_sec.div_euclid(SECS_PER_DAY));
+    let mut secs = ts.tv_sec.rem_euclid(SECS_PER_DAY);
+    let mut nsec = ts.tv_nsec;
+
+    if year < FAT_EPOCH_YEAR {
+        (year, month, day, secs, nsec) = (FAT_EPOCH_YEAR, 1, 1, 0, 0);
+    } else if year > FAT_MAX_YEAR {
+        (year, month, day, secs, nsec) = (FAT_MAX_YEAR, 12, 31, SECS_PER_DAY - 1, 999_999_999);
+    }
+
+    let date = (((year - FAT_EPOCH_YEAR) as u16) << 9) | ((month as u16) << 5) | (day as u16);
+    let time = (((secs / 3600) as u16) << 11)
+        | ((((secs % 3600) / 60) as u16) << 5)
+        | (((secs % 60) / 2) as u16);
+    let tenth = ((secs % 2) * 100 + nsec / 10_000_000) as u8;
+    return (date, time, tenth);
+}
@@ -0,0 +1,405 @@
+#![allow(dead_code)]
+use core::fmt;
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    driver::base::block::block_device::{BlockDevice, LBA_SIZE},
+    kerror, kwarn,
+    syscall::SystemError,
+};
+
+use super::mbr::MbrDiskPartionTable;
+
+/// GPT头的签名（"EFI PART"）
+pub const GPT_SIGNATURE: u64 = 0x5452_4150_2049_4645;
+/// 主GPT头所在的LBA
+pub const GPT_PRIMARY_HEADER_LBA: u64 = 1;
+/// GPT头的最小大小（UEFI规范定义的字段的总长度）
+pub const GPT_HEADER_MIN_SIZE: usize = 92;
+/// 分区表项的最小大小
+pub const GPT_ENTRY_MIN_SIZE: usize = 128;
+/// 分区名称的最大长度（UTF-16代码单元）
+pub const GPT_NAME_LEN: usize = 36;
+/// 分区表项数组的最大大小。规范只要求不小于16KiB，这里限制它的上限，以免损坏的GPT头导致分配过多的内存
+const GPT_MAX_ENTRY_ARRAY_SIZE: usize = 1 << 20;
+
+/// @brief GPT中使用的GUID
+///
+/// 磁盘上的存储格式为：前三个字段是小端序，后8个字节按原样存储
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct GptGuid(pub [u8; 16]);
+
+impl GptGuid {
+    /// 未使用的分区表项的类型GUID
+    pub const UNUSED: GptGuid = GptGuid([0; 16]);
+    /// EFI系统分区
+    pub const EFI_SYSTEM: GptGuid = GptGuid::from_fields(
+        0xC12A7328,
+        0xF81F,
+        0x11D2,
+        [0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9, 0x3B],
+    );
+    /// Microsoft基本数据分区（FAT、exFAT、NTFS）
+    pub const MICROSOFT_BASIC_DATA: GptGuid = GptGuid::from_fields(
+        0xEBD0A0A2,
+        0xB9E5,
+        0x4433,
+        [0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26, 0x99, 0xC7],
+    );
+    /// Linux文件系统数据分区
+    pub const LINUX_FILESYSTEM: GptGuid = GptGuid::from_fields(
+        0x0FC63DAF,
+        0x8483,
+        0x4772,
+        [0x8E, 0x79, 0x3D, 0x69, 0xD8, 0x47, 0x7D, 0xE4],
+    );
+
+    /// @brief 根据GUID的文本形式中的各个字段，构造GUID
+    pub const fn from_fields(d1: u32, d2: u16, d3: u16, d4: [u8; 8]) -> GptGuid {
+        let a = d1.to_le_bytes();
+        let b = d2.to_le_bytes();
+        let c = d3.to_le_bytes();
+        return GptGuid([
+            a[0], a[1], a[2], a[3], b[0], b[1], c[0], c[1], d4[0], d4[1], d4[2], d4[3], d4[4],
+            d4[5], d4[6], d4[7],
+        ]);
+    }
+
+    #[inline]
+    pub fn is_unused(&self) -> bool {
+        return *self == GptGuid::UNUSED;
+    }
+}
+
+impl fmt::Debug for GptGuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let g: &[u8; 16] = &self.0;
+        return write!(
+            f,
+            "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+            u32::from_le_bytes([g[0], g[1], g[2], g[3]]),
+            u16::from_le_bytes([g[4], g[5]]),
+            u16::from_le_bytes([g[6], g[7]]),
+            g[8],
+            g[9],
+            g[10],
+            g[11],
+            g[12],
+            g[13],
+            g[14],
+            g[15]
+        );
+    }
+}
+
+impl fmt::Display for GptGuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return fmt::Debug::fmt(self, f);
+    }
+}
+
+/// @brief GPT头
+#[derive(Debug, Clone)]
+pub struct GptHeader {
+    pub revision: u32,
+    /// GPT头的大小（CRC32的计算范围）
+    pub header_size: u32,
+    pub header_crc32: u32,
+    /// 当前GPT头所在的LBA
+    pub my_lba: u64,
+    /// 另一个GPT头（主GPT头对应备份GPT头，反之亦然）所在的LBA
+    pub alternate_lba: u64,
+    /// 可以被分区使用的第一个LBA
+    pub first_usable_lba: u64,
+    /// 可以被分区使用的最后一个LBA
+    pub last_usable_lba: u64,
+    pub disk_guid: GptGuid,
+    /// 分区表项数组的起始LBA
+    pub partition_entry_lba: u64,
+    /// 分区表项的数量
+    pub num_partition_entries: u32,
+    /// 每个分区表项的大小
+    pub size_of_partition_entry: u32,
+    /// 分区表项数组的CRC32
+    pub partition_entry_array_crc32: u32,
+}
+
+impl GptHeader {
+    /// @brief 从磁盘的指定LBA读取GPT头，并检查它的合法性
+    ///
+    /// @return Err(EINVAL) GPT头的签名、大小、CRC32或者LBA不正确
+    pub fn read(disk: &dyn BlockDevice, lba: u64) -> Result<GptHeader, SystemError> {
+        let mut buf: Vec<u8> = vec![0; LBA_SIZE];
+        disk.read_at(lba as usize, 1, &mut buf)?;
+
+        let u32_at =
+            |off: usize| u32::from_le_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]]);
+        let u64_at = |off: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&buf[off..off + 8]);
+            u64::from_le_bytes(bytes)
+        };
+
+        if u64_at(0) != GPT_SIGNATURE {
+            return Err(SystemError::EINVAL);
+        }
+        let header_size: u32 = u32_at(12);
+        if (header_size as usize) < GPT_HEADER_MIN_SIZE || header_size as usize > LBA_SIZE {
+            kwarn!("gpt: invalid header size {} at LBA {}", header_size, lba);
+            return Err(SystemError::EINVAL);
+        }
+
+        // 计算CRC32时，CRC32字段本身视为0
+        let header_crc32: u32 = u32_at(16);
+        let mut raw: Vec<u8> = buf[..header_size as usize].to_vec();
+        raw[16..20].fill(0);
+        if gpt_crc32(&raw) != header_crc32 {
+            kwarn!("gpt: header checksum mismatch at LBA {}", lba);
+            return Err(SystemError::EINVAL);
+        }
+
+        let mut disk_guid: GptGuid = GptGuid::default();
+        disk_guid.0.copy_from_slice(&buf[56..72]);
+        let header = GptHeader {
+            revision: u32_at(8),
+            header_size,
+            header_crc32,
+            my_lba: u64_at(24),
+            alternate_lba: u64_at(32),
+            first_usable_lba: u64_at(40),
+            last_usable_lba: u64_at(48),
+            disk_guid,
+            partition_entry_lba: u64_at(72),
+            num_partition_entries: u32_at(80),
+            size_of_partition_entry: u32_at(84),
+            partition_entry_array_crc32: u32_at(88),
+        };
+
+        if header.my_lba != lba {
+            kwarn!(
+                "gpt: header at LBA {} claims to be at LBA {}",
+                lba,
+                header.my_lba
+            );
+            return Err(SystemError::EINVAL);
+        }
+        let entry_size: usize = header.size_of_partition_entry as usize;
+        if entry_size < GPT_ENTRY_MIN_SIZE
+            || !entry_size.is_power_of_two()
+            || header.entry_array_size() > GPT_MAX_ENTRY_ARRAY_SIZE
+        {
+            kwarn!(
+                "gpt: unsupported partition entry array ({} entries of {} bytes) at LBA {}",
+                header.num_partition_entries,
+                entry_size,
+                lba
+            );
+            return Err(SystemError::EINVAL);
+        }
+        if header.first_usable_lba > header.last_usable_lba {
+            kwarn!("gpt: invalid usable LBA range in header at LBA {}", lba);
+            return Err(SystemError::EINVAL);
+        }
+        return Ok(header);
+    }
+
+    /// @brief 分区表项数组的大小（字节）
+    #[inline]
+    pub fn entry_array_size(&self) -> usize {
+        return self.num_partition_entries as usize * self.size_of_partition_entry as usize;
+    }
+}
+
+/// @brief GPT分区表项
+#[derive(Debug, Clone)]
+pub struct GptPartitionEntry {
+    /// 分区类型GUID。为全0表示这个表项未被使用
+    pub type_guid: GptGuid,
+    /// 分区的唯一GUID
+    pub unique_guid: GptGuid,
+    /// 分区的第一个LBA
+    pub starting_lba: u64,
+    /// 分区的最后一个LBA（包含在分区内）
+    pub ending_lba: u64,
+    /// 分区属性
+    pub attributes: u64,
+    /// 分区名称（磁盘上以UTF-16LE存储）
+    pub name: String,
+}
+
+impl GptPartitionEntry {
+    /// @brief 从分区表项数组中解析一个表项
+    pub fn parse(data: &[u8]) -> GptPartitionEntry {
+        let u64_at = |off: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&data[off..off + 8]);
+            u64::from_le_bytes(bytes)
+        };
+        let mut type_guid: GptGuid = GptGuid::default();
+        type_guid.0.copy_from_slice(&data[0..16]);
+        let mut unique_guid: GptGuid = GptGuid::default();
+        unique_guid.0.copy_from_slice(&data[16..32]);
+
+        // 名称以NUL结尾（名称占满36个代码单元时没有NUL）
+        let name_units = (0..GPT_NAME_LEN)
+            .map(|i| u16::from_le_bytes([data[56 + i * 2], data[57 + i * 2]]))
+            .take_while(|c| *c != 0);
+        let name: String = char::decode_utf16(name_units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+
+        return GptPartitionEntry {
+            type_guid,
+            unique_guid,
+            starting_lba: u64_at(32),
+            ending_lba: u64_at(40),
+            attributes: u64_at(48),
+            name,
+        };
+    }
+
+    #[inline]
+    pub fn is_unused(&self) -> bool {
+        return self.type_guid.is_unused();
+    }
+
+    /// @brief 分区占用的扇区数
+    #[inline]
+    pub fn sectors_num(&self) -> u64 {
+        return self.ending_lba - self.starting_lba + 1;
+    }
+}
+
+/// @brief GPT分区表
+#[derive(Debug, Clone)]
+pub struct GptPartitionTable {
+    /// 读取分区表项时使用的GPT头（主GPT头损坏时，为备份GPT头）
+    pub header: GptHeader,
+    /// 分区表项。下标就是表项在数组中的位置，未使用的表项也包含在内
+    pub entries: Vec<GptPartitionEntry>,
+}
+
+impl GptPartitionTable {
+    /// @brief 读取磁盘上的GPT分区表
+    ///
+    /// 首先尝试主GPT头和它指向的分区表项数组。如果它们损坏了，则使用备份GPT头。
+    /// 主GPT头损坏时，备份GPT头的位置从保护性MBR中的分区大小推算得到。
+    ///
+    /// @param disk 磁盘
+    /// @param mbr 磁盘的第0个扇区中的（保护性）MBR
+    pub fn read(
+        disk: &dyn BlockDevice,
+        mbr: &MbrDiskPartionTable,
+    ) -> Result<GptPartitionTable, SystemError> {
+        let primary = GptHeader::read(disk, GPT_PRIMARY_HEADER_LBA);
+        if let Ok(header) = &primary {
+            match Self::read_entries(disk, header) {
+                Ok(entries) => {
+                    // 备份GPT头损坏不影响使用，但需要提示用户修复
+                    if GptHeader::read(disk, header.alternate_lba).is_err() {
+                        kwarn!(
+                            "gpt: backup header at LBA {} is invalid",
+                            header.alternate_lba
+                        );
+                    }
+                    return Ok(GptPartitionTable {
+                        header: header.clone(),
+                        entries,
+                    });
+                }
+                Err(_) => {
+                    kwarn!("gpt: primary partition entry array is corrupted");
+                }
+            }
+        } else {
+            kwarn!("gpt: primary header is invalid");
+        }
+
+        let backup_lba: u64 = match &primary {
+            Ok(header) => header.alternate_lba,
+            Err(_) => mbr.protective_last_lba().ok_or_else(|| {
+                kerror!("gpt: cannot locate the backup header");
+                SystemError::EINVAL
+            })?,
+        };
+        let header: GptHeader = GptHeader::read(disk, backup_lba)?;
+        let entries: Vec<GptPartitionEntry> = Self::read_entries(disk, &header)?;
+        kwarn!("gpt: using backup header at LBA {}", backup_lba);
+        return Ok(GptPartitionTable { header, entries });
+    }
+
+    /// @brief 读取GPT头指向的分区表项数组，并检查它的CRC32
+    fn read_entries(
+        disk: &dyn BlockDevice,
+        header: &GptHeader,
+    ) -> Result<Vec<GptPartitionEntry>, SystemError> {
+        let size: usize = header.entry_array_size();
+        let count: usize = (size + LBA_SIZE - 1) / LBA_SIZE;
+        let mut buf: Vec<u8> = vec![0; count * LBA_SIZE];
+        disk.read_at(header.partition_entry_lba as usize, count, &mut buf)?;
+        if gpt_crc32(&buf[..size]) != header.partition_entry_array_crc32 {
+            kwarn!(
+                "gpt: partition entry array checksum mismatch at LBA {}",
+                header.partition_entry_lba
+            );
+            return Err(SystemError::EINVAL);
+        }
+
+        let mut entries: Vec<GptPartitionEntry> = Vec::new();
+        for (i, raw) in buf[..size]
+            .chunks_exact(header.size_of_partition_entry as usize)
+            .enumerate()
+        {
+            let mut entry: GptPartitionEntry = GptPartitionEntry::parse(raw);
+            if !entry.is_unused()
+                && (entry.starting_lba > entry.ending_lba
+                    || entry.starting_lba < header.first_usable_lba
+                    || entry.ending_lba > header.last_usable_lba)
+            {
+                kwarn!(
+                    "gpt: partition {} has invalid range {}..={}, ignored",
+                    i,
+                    entry.starting_lba,
+                    entry.ending_lba
+                );
+                entry.type_guid = GptGuid::UNUSED;
+            }
+            entries.push(entry);
+        }
+        return Ok(entries);
+    }
+}
+
+/// @brief 计算GPT使用的CRC32（IEEE 802.3，反射多项式0xEDB88320，初始值与结果异或值均为0xFFFFFFFF）
+///
+/// libs/crc32.c中的crc32()是不反射的版本，不能用于GPT
+pub fn gpt_crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = !0;
+    for b in data {
+        crc = CRC32_TABLE[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    return !crc;
+}
+
+static CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i: usize = 0;
+    while i < 256 {
+        let mut c: u32 = i as u32;
+        let mut k: usize = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    return table;
+}
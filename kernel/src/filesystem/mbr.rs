@@ -1,6 +1,11 @@
 #![allow(dead_code)]
 use core::default::Default;
 
+/// MBR扇区末尾的签名
+pub const MBR_SIGNATURE: u16 = 0xaa55;
+/// 保护性MBR中，覆盖整个GPT磁盘的分区的类型ID
+pub const MBR_PART_TYPE_GPT_PROTECTIVE: u8 = 0xee;
+
 /// @brief MBR硬盘分区表项的结构
 #[repr(packed)]
 #[derive(Debug, Clone, Copy)]
@@ -39,6 +44,36 @@ pub struct MbrDiskPartionTable {
     pub bs_trailsig: u16,
 }
 
+impl MbrDiskPartionTable {
+    /// @brief 判断是否是保护性MBR（磁盘使用GPT分区表）
+    ///
+    /// 混合MBR除了保护性分区之外，还包含其他分区，此时同样以GPT为准
+    pub fn is_protective(&self) -> bool {
+        let signature: u16 = self.bs_trailsig;
+        return signature == MBR_SIGNATURE
+            && self
+                .dpte
+                .iter()
+                .any(|e| e.part_type == MBR_PART_TYPE_GPT_PROTECTIVE);
+    }
+
+    /// @brief 获取保护性分区的最后一个LBA（即备份GPT头所在的LBA）
+    ///
+    /// @return None 没有保护性分区，或者磁盘超过了MBR能够表示的大小
+    pub fn protective_last_lba(&self) -> Option<u64> {
+        let entry: &MbrDiskPartitionTableEntry = self
+            .dpte
+            .iter()
+            .find(|e| e.part_type == MBR_PART_TYPE_GPT_PROTECTIVE)?;
+        let starting_lba: u32 = entry.starting_lba;
+        let total_sectors: u32 = entry.total_sectors;
+        if total_sectors == 0 || total_sectors == u32::MAX {
+            return None;
+        }
+        return Some(starting_lba as u64 + total_sectors as u64 - 1);
+    }
+}
+
 impl Default for MbrDiskPartitionTableEntry {
     fn default() -> Self {
         MbrDiskPartitionTableEntry {
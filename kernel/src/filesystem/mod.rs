@@ -1,5 +1,9 @@
 pub mod devfs;
+pub mod eventpoll;
+pub mod exfat;
+pub mod ext2;
 pub mod fat;
+pub mod gpt;
 pub mod mbr;
 pub mod procfs;
 pub mod ramfs;
@@ -10,33 +10,81 @@ use alloc::{
 };
 
 use crate::{
-    arch::asm::current::current_pcb,
+    arch::{asm::current::current_pcb, cpu::CpuInfo, mm::LockedFrameAllocator, MMArch},
+    exception::irqstat::{irq_count, irq_name, IRQ_VECTOR_NUM},
     filesystem::vfs::{
         core::{generate_inode_id, ROOT_INODE},
+        dcache::dcache,
         FileType,
     },
-    include::bindings::bindings::{pid_t, process_find_pcb_by_pid},
+    include::bindings::bindings::{
+        pid_t, process_control_block, process_find_pcb_by_pid, process_global_pid,
+        smp_get_total_cpu, Cpu_tsc_freq, PROC_INTERRUPTIBLE, PROC_STOPPED, PROC_UNINTERRUPTIBLE,
+        PROC_ZOMBIE,
+    },
     kerror, kinfo,
     libs::{
         once::Once,
         spinlock::{SpinLock, SpinLockGuard},
     },
+    mm::{
+        allocator::{
+            kernel_allocator::kernel_heap_pages,
+            page_frame::{FrameAllocator, PageFrameUsage},
+        },
+        page_cache::page_cache_nr_pages,
+        ucontext::{InnerAddressSpace, LockedVMA},
+        MemoryManagementArch, VirtAddr,
+    },
+    sched::loadavg::{get_avenrun, nr_running, FIXED_1, FSHIFT},
     syscall::SystemError,
-    time::TimeSpec,
+    time::{timer::clock, TimeSpec},
 };
 
 use super::vfs::{
-    file::{FileMode, FilePrivateData},
+    file::{File, FileDescriptorVec, FileMode, FilePrivateData},
+    mount::{mount_list, MountFlags, MountRecord},
+    seq_file::{seq_read, seq_write, SeqFileState, SeqOperations},
     FileSystem, FsInfo, IndexNode, InodeId, Metadata, PollStatus,
 };
 
 /// @brief 进程文件类型
 /// @usage 用于定义进程文件夹下的各类文件类型
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ProcFileType {
     ///展示进程状态信息
     ProcStatus = 0,
+    ///进程的参数列表（以\0分隔）
+    ProcCmdline = 2,
+    ///进程的环境变量列表（以\0分隔）
+    ProcEnviron = 3,
+    ///进程地址空间中的各个映射区域
+    ProcMaps = 4,
+    ///与Linux格式相同的进程状态信息（单行）
+    ProcStat = 5,
+    ///进程的内存占用情况（单位：页）
+    ProcStatm = 6,
+    ///进程打开的文件描述符所在的目录
+    ProcFdDir = 7,
+    ///指向文件描述符所对应的文件的符号链接
+    ProcFdLink = 8,
+    ///指向进程当前工作目录的符号链接
+    ProcCwd = 9,
+    ///系统的内存使用情况
+    ProcMeminfo = 10,
+    ///系统中各个处理器的信息
+    ProcCpuinfo = 11,
+    ///系统的运行时间
+    ProcUptime = 12,
+    ///系统的平均负载
+    ProcLoadavg = 13,
+    ///各个中断在每个处理器上发生的次数
+    ProcInterrupts = 14,
+    ///内核的版本信息
+    ProcVersion = 15,
+    ///指向当前进程的目录的符号链接
+    ProcSelf = 16,
     //todo: 其他文件类型
     ///默认文件类型
     Default,
@@ -46,6 +94,21 @@ impl From<u8> for ProcFileType {
     fn from(value: u8) -> Self {
         match value {
             0 => ProcFileType::ProcStatus,
+            2 => ProcFileType::ProcCmdline,
+            3 => ProcFileType::ProcEnviron,
+            4 => ProcFileType::ProcMaps,
+            5 => ProcFileType::ProcStat,
+            6 => ProcFileType::ProcStatm,
+            7 => ProcFileType::ProcFdDir,
+            8 => ProcFileType::ProcFdLink,
+            9 => ProcFileType::ProcCwd,
+            10 => ProcFileType::ProcMeminfo,
+            11 => ProcFileType::ProcCpuinfo,
+            12 => ProcFileType::ProcUptime,
+            13 => ProcFileType::ProcLoadavg,
+            14 => ProcFileType::ProcInterrupts,
+            15 => ProcFileType::ProcVersion,
+            16 => ProcFileType::ProcSelf,
             _ => ProcFileType::Default,
         }
     }
@@ -58,11 +121,37 @@ pub struct InodeInfo {
     pid: i64,
     ///文件类型
     ftype: ProcFileType,
+    ///fd目录下的符号链接所对应的文件描述符
+    fd: i32,
     //其他需要传入的信息在此定义
 }
 
+/// 进程目录下的各个文件：(文件名, 文件类型, 权限, procfs文件类型)
+const PROC_PID_ENTRIES: [(&str, FileType, u32, ProcFileType); 8] = [
+    ("status", FileType::File, 0o777, ProcFileType::ProcStatus),
+    ("cmdline", FileType::File, 0o444, ProcFileType::ProcCmdline),
+    ("environ", FileType::File, 0o400, ProcFileType::ProcEnviron),
+    ("maps", FileType::File, 0o444, ProcFileType::ProcMaps),
+    ("stat", FileType::File, 0o444, ProcFileType::ProcStat),
+    ("statm", FileType::File, 0o444, ProcFileType::ProcStatm),
+    ("fd", FileType::Dir, 0o500, ProcFileType::ProcFdDir),
+    ("cwd", FileType::SymLink, 0o777, ProcFileType::ProcCwd),
+];
+
+/// procfs根目录下，系统全局的各个文件：(文件名, procfs文件类型)
+const PROC_ROOT_ENTRIES: [(&str, ProcFileType); 6] = [
+    ("meminfo", ProcFileType::ProcMeminfo),
+    ("cpuinfo", ProcFileType::ProcCpuinfo),
+    ("uptime", ProcFileType::ProcUptime),
+    ("loadavg", ProcFileType::ProcLoadavg),
+    ("interrupts", ProcFileType::ProcInterrupts),
+    ("version", ProcFileType::ProcVersion),
+];
+
 /// @brief procfs的inode名称的最大长度
 const PROCFS_MAX_NAMELEN: usize = 64;
+/// ProcFS的魔数（与Linux相同）
+pub const PROCFS_MAGIC: u64 = 0x9fa0;
 
 /// @brief procfs文件系统的Inode结构体
 #[derive(Debug)]
@@ -103,6 +192,8 @@ pub struct ProcFSInode {
     fs: Weak<ProcFS>,
     /// 储存私有信息
     fdata: InodeInfo,
+    /// 属性文件的操作（通过[`procfs_create_attr`]注册的文件）
+    attr: Option<Arc<dyn SeqOperations>>,
 }
 
 /// 对ProcFSInode实现获取各类文件信息的函数
@@ -115,39 +206,28 @@ impl ProcFSInode {
     }
     // todo:其他数据获取函数实现
 
+    /// @brief 获取当前inode所属的进程的pcb
+    fn pcb(&self) -> Result<&'static mut process_control_block, SystemError> {
+        let pid: i64 = self.fdata.pid;
+        return unsafe { process_find_pcb_by_pid(pid).as_mut() }.ok_or_else(|| {
+            kerror!(
+                "ProcFS: Cannot find pcb for pid {} when accessing {:?}.",
+                pid,
+                self.fdata.ftype
+            );
+            SystemError::ESRCH
+        });
+    }
+
     /// @brief 打开status文件
     ///
     fn open_status(&self, pdata: &mut ProcfsFilePrivateData) -> Result<i64, SystemError> {
         // 获取该pid对应的pcb结构体
-        let pid: &i64 = &self.fdata.pid;
-        let pcb = unsafe { process_find_pcb_by_pid(*pid).as_mut() };
-        let pcb = if pcb.is_none() {
-            kerror!(
-                "ProcFS: Cannot find pcb for pid {} when opening its 'status' file.",
-                pid
-            );
-            return Err(SystemError::ESRCH);
-        } else {
-            pcb.unwrap()
-        };
+        let pcb = self.pcb()?;
         // 传入数据
         let pdata: &mut Vec<u8> = &mut pdata.data;
-        // ！！！！！由于目前有bug，不能获取到pcb的name，因此暂时用'Unknown'代替
-        let tmp_name: Vec<u8> = "Unknown".as_bytes().to_vec();
-        // kdebug!("pcb.name={:?}", pcb.name);
-        // let mut tmp_name: Vec<u8> = Vec::with_capacity(pcb.name.len());
-        // for val in pcb.name.iter() {
-        //     tmp_name.push(*val as u8);
-        // }
 
-        pdata.append(
-            &mut format!(
-                "Name:\t{}",
-                String::from_utf8(tmp_name).unwrap_or("NULL".to_string())
-            )
-            .as_bytes()
-            .to_owned(),
-        );
+        pdata.append(&mut format!("Name:\t{}", pcb.name()).as_bytes().to_owned());
         pdata.append(&mut format!("\nstate:\t{}", pcb.state).as_bytes().to_owned());
         pdata.append(&mut format!("\npid:\t{}", pcb.pid).as_bytes().to_owned());
         pdata.append(
@@ -172,15 +252,19 @@ impl ProcFSInode {
                 .to_owned(),
         );
 
-        let binding = current_pcb().address_space().unwrap();
-        let address_space_guard = binding.read();
         // todo: 当前进程运行过程中占用内存的峰值
         let hiwater_vm: u64 = 0;
-        // 进程代码段的大小
-        let text = (address_space_guard.end_code - address_space_guard.start_code) / 1024;
-        // 进程数据段的大小
-        let data = (address_space_guard.end_data - address_space_guard.start_data) / 1024;
-        drop(address_space_guard);
+        // 进程代码段、数据段的大小。内核线程没有用户地址空间，因此都为0
+        let (text, data) = match pcb.address_space() {
+            Some(binding) => {
+                let address_space_guard = binding.read();
+                (
+                    (address_space_guard.end_code - address_space_guard.start_code) / 1024,
+                    (address_space_guard.end_data - address_space_guard.start_data) / 1024,
+                )
+            }
+            None => (0, 0),
+        };
 
         pdata.append(
             &mut format!("\nVmPeak:\t{} kB", hiwater_vm)
@@ -196,6 +280,388 @@ impl ProcFSInode {
         return Ok((pdata.len() * size_of::<u8>()) as i64);
     }
 
+    /// @brief 打开cmdline或environ文件
+    ///
+    /// 与Linux相同，每个参数（环境变量）之后都跟着一个\0
+    fn open_cmdline(&self, pdata: &mut ProcfsFilePrivateData) -> Result<i64, SystemError> {
+        let pcb = self.pcb()?;
+        let pdata: &mut Vec<u8> = &mut pdata.data;
+        // 内核线程没有用户地址空间，其cmdline为空
+        if let Some(address_space) = pcb.address_space() {
+            let guard = address_space.read();
+            let strings: &Vec<String> = match self.fdata.ftype {
+                ProcFileType::ProcEnviron => &guard.envs,
+                _ => &guard.args,
+            };
+            for s in strings.iter() {
+                pdata.extend_from_slice(s.as_bytes());
+                pdata.push(0);
+            }
+        }
+
+        return Ok((pdata.len() * size_of::<u8>()) as i64);
+    }
+
+    /// @brief 打开maps文件
+    ///
+    /// 每一行的格式为：起始地址-结束地址 权限 文件内偏移量 设备号 inode号 路径
+    fn open_maps(&self, pdata: &mut ProcfsFilePrivateData) -> Result<i64, SystemError> {
+        let pcb = self.pcb()?;
+        let pdata: &mut Vec<u8> = &mut pdata.data;
+        let address_space = match pcb.address_space() {
+            Some(a) => a,
+            None => return Ok(0),
+        };
+        let guard = address_space.read();
+        let stack_sp: Option<VirtAddr> = guard.user_stack.as_ref().map(|s| s.sp());
+
+        // 按照起始地址的顺序输出
+        let mut vmas: Vec<Arc<LockedVMA>> = guard.mappings.iter_vmas().cloned().collect();
+        vmas.sort_by_key(|vma| vma.lock().region().start());
+
+        for vma in vmas {
+            let vma = vma.lock();
+            let region = vma.region();
+            let flags = vma.flags();
+            let (shared, offset, ino, path) = match vma.file() {
+                Some(file) => (
+                    file.shared(),
+                    file.offset(),
+                    file.inode().metadata().map(|m| m.ino()).unwrap_or(0),
+                    String::from(file.path().unwrap_or("")),
+                ),
+                None => {
+                    let path = if region.start() >= guard.brk_start && region.end() <= guard.brk {
+                        "[heap]"
+                    } else if stack_sp.map_or(false, |sp| region.contains(sp)) {
+                        "[stack]"
+                    } else {
+                        ""
+                    };
+                    (false, 0, 0, String::from(path))
+                }
+            };
+
+            let line = format!(
+                "{:08x}-{:08x} {}{}{}{} {:08x} 00:00 {} {}",
+                region.start().data(),
+                region.end().data(),
+                if flags.present() { 'r' } else { '-' },
+                if flags.has_write() { 'w' } else { '-' },
+                if flags.has_execute() { 'x' } else { '-' },
+                if shared { 's' } else { 'p' },
+                offset,
+                ino,
+                path
+            );
+            pdata.extend_from_slice(line.trim_end().as_bytes());
+            pdata.push(b'\n');
+        }
+
+        return Ok((pdata.len() * size_of::<u8>()) as i64);
+    }
+
+    /// @brief 统计进程地址空间的内存占用情况
+    ///
+    /// @return (总页数, 已经映射到物理内存的页数, 其中属于共享文件映射的页数)
+    fn vm_usage(address_space: &InnerAddressSpace) -> (usize, usize, usize) {
+        let mut total: usize = 0;
+        let mut resident: usize = 0;
+        let mut shared: usize = 0;
+        for vma in address_space.mappings.iter_vmas() {
+            let vma = vma.lock();
+            let is_shared: bool = vma.file().map_or(false, |f| f.shared());
+            for page in vma.pages() {
+                total += 1;
+                if address_space
+                    .user_mapper
+                    .utable
+                    .translate(page.virt_address())
+                    .is_some()
+                {
+                    resident += 1;
+                    if is_shared {
+                        shared += 1;
+                    }
+                }
+            }
+        }
+        return (total, resident, shared);
+    }
+
+    /// @brief 打开stat文件
+    ///
+    /// 字段的顺序与Linux相同，内核暂未统计的字段均为0
+    fn open_stat(&self, pdata: &mut ProcfsFilePrivateData) -> Result<i64, SystemError> {
+        let pcb = self.pcb()?;
+        let pdata: &mut Vec<u8> = &mut pdata.data;
+
+        let state: u64 = pcb.state;
+        let state_char: char = if state & PROC_ZOMBIE as u64 != 0 {
+            'Z'
+        } else if state & PROC_STOPPED as u64 != 0 {
+            'T'
+        } else if state & PROC_UNINTERRUPTIBLE as u64 != 0 {
+            'D'
+        } else if state & PROC_INTERRUPTIBLE as u64 != 0 {
+            'S'
+        } else {
+            'R'
+        };
+        let ppid: pid_t = unsafe { pcb.parent_pcb.as_ref() }.map_or(0, |p| p.pid);
+        let (vsize, rss) = match pcb.address_space() {
+            Some(a) => {
+                let (total, resident, _) = Self::vm_usage(&a.read());
+                (total * MMArch::PAGE_SIZE, resident)
+            }
+            None => (0, 0),
+        };
+
+        // pid (comm) state ppid pgrp session tty_nr tpgid flags
+        // minflt cminflt majflt cmajflt utime stime cutime cstime
+        // priority nice num_threads itrealvalue starttime vsize rss
+        let line = format!(
+            "{} ({}) {} {} {} {} 0 -1 {} 0 0 0 0 0 0 0 0 {} 0 1 0 0 {} {}\n",
+            pcb.pid,
+            pcb.name(),
+            state_char,
+            ppid,
+            pcb.pid,
+            pcb.pid,
+            pcb.flags,
+            pcb.priority,
+            vsize,
+            rss
+        );
+        pdata.extend_from_slice(line.as_bytes());
+
+        return Ok((pdata.len() * size_of::<u8>()) as i64);
+    }
+
+    /// @brief 打开statm文件
+    ///
+    /// 各字段依次为：总页数 常驻内存的页数 共享的页数 代码段的页数 0 数据段和栈的页数 0
+    fn open_statm(&self, pdata: &mut ProcfsFilePrivateData) -> Result<i64, SystemError> {
+        let pcb = self.pcb()?;
+        let pdata: &mut Vec<u8> = &mut pdata.data;
+
+        let (size, resident, shared, text, data) = match pcb.address_space() {
+            Some(a) => {
+                let guard = a.read();
+                let (size, resident, shared) = Self::vm_usage(&guard);
+                let text = (guard.end_code - guard.start_code) / MMArch::PAGE_SIZE;
+                let data = ((guard.end_data - guard.start_data) + (guard.brk - guard.brk_start))
+                    / MMArch::PAGE_SIZE
+                    + guard
+                        .user_stack
+                        .as_ref()
+                        .map_or(0, |s| s.stack_size() / MMArch::PAGE_SIZE);
+                (size, resident, shared, text, data)
+            }
+            None => (0, 0, 0, 0, 0),
+        };
+
+        let line = format!("{} {} {} {} 0 {} 0\n", size, resident, shared, text, data);
+        pdata.extend_from_slice(line.as_bytes());
+
+        return Ok((pdata.len() * size_of::<u8>()) as i64);
+    }
+
+    /// @brief 获取符号链接类型的inode所指向的路径
+    ///
+    /// 这些符号链接的目标随着进程的状态动态变化，因此在每次读取时重新生成
+    fn link_target(&self) -> Result<String, SystemError> {
+        match self.fdata.ftype {
+            ProcFileType::ProcFdLink => {
+                let pcb = self.pcb()?;
+                let fd_table = FileDescriptorVec::from_pcb(pcb).ok_or(SystemError::ENOENT)?;
+                if !FileDescriptorVec::validate_fd(self.fdata.fd) {
+                    return Err(SystemError::ENOENT);
+                }
+                let file: &File = fd_table.fds[self.fdata.fd as usize]
+                    .as_ref()
+                    .ok_or(SystemError::ENOENT)?;
+                if let Some(path) = file.path() {
+                    return Ok(String::from(path));
+                }
+                // 没有路径的文件，与Linux一样，以“类型:[inode号]”的形式表示
+                let ino: u64 = file.inode().metadata().map_or(0, |m| m.ino());
+                let kind: &str = match file.file_type() {
+                    FileType::Pipe => "pipe",
+                    FileType::Socket => "socket",
+                    _ => "anon_inode",
+                };
+                return Ok(format!("{}:[{}]", kind, ino));
+            }
+            ProcFileType::ProcCwd => return Ok(String::from(self.pcb()?.cwd().path())),
+            // 访问/proc/self的进程，就是它所指向的进程
+            ProcFileType::ProcSelf => return Ok(current_pcb().pid.to_string()),
+            _ => return Err(SystemError::EINVAL),
+        }
+    }
+
+    /// @brief 获取进程当前打开的所有文件描述符
+    fn open_fds(&self) -> Result<Vec<i32>, SystemError> {
+        let pcb = self.pcb()?;
+        let fds: Vec<i32> = match FileDescriptorVec::from_pcb(pcb) {
+            Some(fd_table) => fd_table
+                .fds
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| f.is_some())
+                .map(|(fd, _)| fd as i32)
+                .collect(),
+            None => Vec::new(),
+        };
+        return Ok(fds);
+    }
+
+    /// @brief 打开meminfo文件
+    fn open_meminfo(&self, pdata: &mut ProcfsFilePrivateData) -> Result<i64, SystemError> {
+        let pdata: &mut Vec<u8> = &mut pdata.data;
+        let usage: PageFrameUsage = unsafe { LockedFrameAllocator.usage() };
+        let page_kb: usize = MMArch::PAGE_SIZE / 1024;
+
+        let total: usize = usage.total().data() * page_kb;
+        let free: usize = usage.free().data() * page_kb;
+        let cached: usize = page_cache_nr_pages() * page_kb;
+        // 页缓存中的页面能够被回收，因此也算作可用内存
+        let available: usize = free + cached;
+        // 目前内核堆占用的页面可以视为slab占用的内存
+        let slab: usize = kernel_heap_pages().data() * page_kb;
+
+        for (name, value) in [
+            ("MemTotal", total),
+            ("MemFree", free),
+            ("MemAvailable", available),
+            ("Cached", cached),
+            ("Slab", slab),
+        ] {
+            let line = format!("{:<16}{:>8} kB\n", format!("{}:", name), value);
+            pdata.extend_from_slice(line.as_bytes());
+        }
+
+        return Ok((pdata.len() * size_of::<u8>()) as i64);
+    }
+
+    /// @brief 打开cpuinfo文件
+    fn open_cpuinfo(&self, pdata: &mut ProcfsFilePrivateData) -> Result<i64, SystemError> {
+        let pdata: &mut Vec<u8> = &mut pdata.data;
+        // TODO: 目前只读取当前处理器的CPUID，并假定所有处理器都是相同的
+        let info: CpuInfo = CpuInfo::current();
+        // Cpu_tsc_freq的单位为Hz
+        let khz: u64 = unsafe { Cpu_tsc_freq } / 1000;
+        let cpu_num: u32 = unsafe { smp_get_total_cpu() };
+
+        for cpu in 0..cpu_num {
+            let mut block = String::new();
+            block.push_str(&format!("processor\t: {}\n", cpu));
+            block.push_str(&format!("vendor_id\t: {}\n", info.vendor_id));
+            block.push_str(&format!("cpu family\t: {}\n", info.family));
+            block.push_str(&format!("model\t\t: {}\n", info.model));
+            block.push_str(&format!("model name\t: {}\n", info.model_name));
+            block.push_str(&format!("stepping\t: {}\n", info.stepping));
+            block.push_str(&format!("cpu MHz\t\t: {}.{:03}\n", khz / 1000, khz % 1000));
+            block.push_str(&format!("flags\t\t: {}\n", info.flags.join(" ")));
+            block.push_str(&format!(
+                "address sizes\t: {} bits physical, {} bits virtual\n\n",
+                info.phys_addr_bits, info.virt_addr_bits
+            ));
+            pdata.extend_from_slice(block.as_bytes());
+        }
+
+        return Ok((pdata.len() * size_of::<u8>()) as i64);
+    }
+
+    /// @brief 打开uptime文件
+    ///
+    /// 各字段依次为：系统运行的时间 处理器空闲的时间（单位：秒）
+    fn open_uptime(&self, pdata: &mut ProcfsFilePrivateData) -> Result<i64, SystemError> {
+        let pdata: &mut Vec<u8> = &mut pdata.data;
+        // 定时器时间片的单位为微秒
+        let uptime_us: u64 = clock();
+        // TODO: 目前还没有统计处理器的空闲时间
+        let line = format!(
+            "{}.{:02} 0.00\n",
+            uptime_us / 1000000,
+            (uptime_us % 1000000) / 10000
+        );
+        pdata.extend_from_slice(line.as_bytes());
+
+        return Ok((pdata.len() * size_of::<u8>()) as i64);
+    }
+
+    /// @brief 打开loadavg文件
+    ///
+    /// 各字段依次为：最近1、5、15分钟的平均负载 可运行的进程数/进程总数 最近分配的pid
+    fn open_loadavg(&self, pdata: &mut ProcfsFilePrivateData) -> Result<i64, SystemError> {
+        let pdata: &mut Vec<u8> = &mut pdata.data;
+        let mut line = String::new();
+        for avg in get_avenrun() {
+            line.push_str(&format!(
+                "{}.{:02} ",
+                avg >> FSHIFT,
+                ((avg & (FIXED_1 - 1)) * 100) >> FSHIFT
+            ));
+        }
+
+        // 每个进程在procfs的根目录下都有一个以pid命名的文件夹
+        let nr_processes: usize = match self.fs.upgrade() {
+            Some(fs) => fs
+                .root_inode()
+                .list()?
+                .iter()
+                .filter(|name| name.parse::<pid_t>().is_ok())
+                .count(),
+            None => 0,
+        };
+        let last_pid: i64 = unsafe { process_global_pid } - 1;
+        line.push_str(&format!("{}/{} {}\n", nr_running(), nr_processes, last_pid));
+        pdata.extend_from_slice(line.as_bytes());
+
+        return Ok((pdata.len() * size_of::<u8>()) as i64);
+    }
+
+    /// @brief 打开interrupts文件
+    ///
+    /// 只列出已经注册过的，或者发生过的中断向量
+    fn open_interrupts(&self, pdata: &mut ProcfsFilePrivateData) -> Result<i64, SystemError> {
+        let pdata: &mut Vec<u8> = &mut pdata.data;
+        let cpu_num: usize = unsafe { smp_get_total_cpu() } as usize;
+
+        let mut header = String::from("    ");
+        for cpu in 0..cpu_num {
+            header.push_str(&format!(" {:>10}", format!("CPU{}", cpu)));
+        }
+        header.push('\n');
+        pdata.extend_from_slice(header.as_bytes());
+
+        for vector in 0..IRQ_VECTOR_NUM {
+            let counts: Vec<usize> = (0..cpu_num).map(|cpu| irq_count(cpu, vector)).collect();
+            let name: Option<String> = irq_name(vector);
+            if name.is_none() && counts.iter().all(|c| *c == 0) {
+                continue;
+            }
+            let mut line = format!("{:>3}:", vector);
+            for count in counts {
+                line.push_str(&format!(" {:>10}", count));
+            }
+            line.push_str(&format!("  {}\n", name.unwrap_or_default()));
+            pdata.extend_from_slice(line.as_bytes());
+        }
+
+        return Ok((pdata.len() * size_of::<u8>()) as i64);
+    }
+
+    /// @brief 打开version文件
+    fn open_version(&self, pdata: &mut ProcfsFilePrivateData) -> Result<i64, SystemError> {
+        let pdata: &mut Vec<u8> = &mut pdata.data;
+        let line = format!("DragonOS version {} (x86_64)\n", env!("CARGO_PKG_VERSION"));
+        pdata.extend_from_slice(line.as_bytes());
+
+        return Ok((pdata.len() * size_of::<u8>()) as i64);
+    }
+
     /// status文件读取函数
     fn read_status(
         &self,
@@ -228,6 +694,9 @@ impl FileSystem for ProcFS {
         return FsInfo {
             blk_dev_id: 0,
             max_name_len: PROCFS_MAX_NAMELEN,
+            magic: PROCFS_MAGIC,
+            block_size: MMArch::PAGE_SIZE,
+            ..Default::default()
         };
     }
 
@@ -248,6 +717,7 @@ impl ProcFS {
                 metadata: Metadata {
                     dev_id: 0,
                     inode_id: generate_inode_id(),
+                    disk_ino: None,
                     size: 0,
                     blk_size: 0,
                     blocks: 0,
@@ -265,7 +735,9 @@ impl ProcFS {
                 fdata: InodeInfo {
                     pid: 0,
                     ftype: ProcFileType::Default,
+                    fd: -1,
                 },
+                attr: None,
             })));
 
         let result: Arc<ProcFS> = Arc::new(ProcFS { root_inode: root });
@@ -278,27 +750,128 @@ impl ProcFS {
         // 释放锁
         drop(root_guard);
 
+        // 创建mounts等系统全局的文件
+        result
+            .create_attr("mounts", 0o444, Arc::new(ProcMounts))
+            .expect("Failed to create /proc/mounts");
+        for (name, ftype) in PROC_ROOT_ENTRIES {
+            let binding: Arc<dyn IndexNode> = result
+                .root_inode()
+                .create(name, FileType::File, 0o444)
+                .unwrap_or_else(|_| panic!("Failed to create /proc/{}", name));
+            let file: &LockedProcFSInode = binding
+                .as_any_ref()
+                .downcast_ref::<LockedProcFSInode>()
+                .unwrap();
+            file.0.lock().fdata.ftype = ftype;
+        }
+        let binding: Arc<dyn IndexNode> = result
+            .root_inode()
+            .create("self", FileType::SymLink, 0o777)
+            .expect("Failed to create /proc/self");
+        binding
+            .as_any_ref()
+            .downcast_ref::<LockedProcFSInode>()
+            .unwrap()
+            .0
+            .lock()
+            .fdata
+            .ftype = ProcFileType::ProcSelf;
+
         return result;
     }
 
+    /// @brief 在procfs中创建一个属性文件
+    ///
+    /// @param path 相对于procfs根目录的路径，路径中不存在的目录会被自动创建
+    /// @param mode 文件的权限
+    /// @param ops 属性文件的操作
+    pub fn create_attr(
+        &self,
+        path: &str,
+        mode: u32,
+        ops: Arc<dyn SeqOperations>,
+    ) -> Result<(), SystemError> {
+        let (dirs, name) = match path.trim_matches('/').rsplit_once('/') {
+            Some((dirs, name)) => (dirs, name),
+            None => ("", path.trim_matches('/')),
+        };
+        if name.is_empty() {
+            return Err(SystemError::EINVAL);
+        }
+
+        let mut parent: Arc<dyn IndexNode> = self.root_inode();
+        for dir in dirs.split('/').filter(|d| !d.is_empty()) {
+            parent = match parent.find(dir) {
+                Ok(inode) => inode,
+                Err(SystemError::ENOENT) => parent.create(dir, FileType::Dir, 0o555)?,
+                Err(e) => return Err(e),
+            };
+        }
+
+        let binding: Arc<dyn IndexNode> = parent.create(name, FileType::File, mode)?;
+        let file: &LockedProcFSInode = binding
+            .as_any_ref()
+            .downcast_ref::<LockedProcFSInode>()
+            .unwrap();
+        file.0.lock().attr = Some(ops);
+        return Ok(());
+    }
+
+    /// @brief 删除procfs中的一个属性文件
+    ///
+    /// @param path 相对于procfs根目录的路径
+    pub fn remove_attr(&self, path: &str) -> Result<(), SystemError> {
+        let (dirs, name) = match path.trim_matches('/').rsplit_once('/') {
+            Some((dirs, name)) => (dirs, name),
+            None => ("", path.trim_matches('/')),
+        };
+        let mut parent: Arc<dyn IndexNode> = self.root_inode();
+        for dir in dirs.split('/').filter(|d| !d.is_empty()) {
+            parent = parent.find(dir)?;
+        }
+
+        let target: Arc<dyn IndexNode> = parent.find(name)?;
+        let is_attr: bool = target
+            .as_any_ref()
+            .downcast_ref::<LockedProcFSInode>()
+            .map_or(false, |f| f.0.lock().attr.is_some());
+        if !is_attr {
+            return Err(SystemError::EPERM);
+        }
+        return parent.unlink(name);
+    }
+
     /// @brief 进程注册函数
     /// @usage 在进程中调用并创建进程对应文件
     pub fn register_pid(&self, pid: i64) -> Result<(), SystemError> {
+        // 进程文件夹及其中的文件属于该进程的有效用户和有效组，这样进程才能访问自己的environ、fd等只有所有者可读的文件
+        let cred = unsafe { process_find_pcb_by_pid(pid).as_ref() }
+            .ok_or(SystemError::ESRCH)?
+            .cred();
+        let (uid, gid) = (cred.euid as usize, cred.egid as usize);
+
         // 获取当前inode
         let proc: Arc<dyn IndexNode> = self.root_inode();
         // 创建对应进程文件夹
-        let _pf: Arc<dyn IndexNode> = proc.create(&pid.to_string(), FileType::Dir, 0o777)?;
+        let _pf: Arc<dyn IndexNode> = proc.create(&pid.to_string(), FileType::Dir, 0o555)?;
+        let mut metadata: Metadata = _pf.metadata()?;
+        metadata.uid = uid;
+        metadata.gid = gid;
+        _pf.set_metadata(&metadata)?;
         // 创建相关文件
-        // status文件
-        let binding: Arc<dyn IndexNode> = _pf.create("status", FileType::File, 0o777)?;
-        let _sf: &LockedProcFSInode = binding
-            .as_any_ref()
-            .downcast_ref::<LockedProcFSInode>()
-            .unwrap();
-        _sf.0.lock().fdata.pid = pid;
-        _sf.0.lock().fdata.ftype = ProcFileType::ProcStatus;
-
-        //todo: 创建其他文件
+        for (name, file_type, mode, ftype) in PROC_PID_ENTRIES {
+            let binding: Arc<dyn IndexNode> = _pf.create(name, file_type, mode)?;
+            let _sf: &LockedProcFSInode = binding
+                .as_any_ref()
+                .downcast_ref::<LockedProcFSInode>()
+                .unwrap();
+            let mut guard: SpinLockGuard<ProcFSInode> = _sf.0.lock();
+            guard.fdata.pid = pid;
+            guard.fdata.ftype = ftype;
+            guard.metadata.uid = uid;
+            guard.metadata.gid = gid;
+        }
 
         return Ok(());
     }
@@ -310,11 +883,10 @@ impl ProcFS {
         let proc: Arc<dyn IndexNode> = self.root_inode();
         // 获取进程文件夹
         let pid_dir: Arc<dyn IndexNode> = proc.find(&format!("{}", pid))?;
-        // 删除进程文件夹下文件
-        pid_dir.unlink("status")?;
-
-        // 查看进程文件是否还存在
-        // let pf= pid_dir.find("status").expect("Cannot find status");
+        // 删除进程文件夹下文件（fd目录下的符号链接会随着fd目录一起被释放）
+        for (name, _, _, _) in PROC_PID_ENTRIES {
+            pid_dir.unlink(name)?;
+        }
 
         // 删除进程文件夹
         proc.unlink(&format!("{}", pid))?;
@@ -323,19 +895,86 @@ impl ProcFS {
     }
 }
 
+impl LockedProcFSInode {
+    /// @brief 根据进程当前打开的文件描述符，更新fd目录下的符号链接
+    ///
+    /// 进程打开、关闭文件时不会通知procfs，因此在每次访问fd目录时更新
+    fn refresh_fd_dir(&self) -> Result<(), SystemError> {
+        let guard: SpinLockGuard<ProcFSInode> = self.0.lock();
+        if guard.fdata.ftype != ProcFileType::ProcFdDir {
+            return Ok(());
+        }
+        let pid: i64 = guard.fdata.pid;
+        // 符号链接与fd目录属于同一个用户
+        let (uid, gid) = (guard.metadata.uid, guard.metadata.gid);
+        let fds: Vec<String> = guard.open_fds()?.iter().map(|fd| fd.to_string()).collect();
+        let stale: Vec<String> = guard
+            .children
+            .keys()
+            .filter(|name| !fds.contains(*name))
+            .cloned()
+            .collect();
+        let missing: Vec<String> = fds
+            .into_iter()
+            .filter(|name| !guard.children.contains_key(name))
+            .collect();
+        drop(guard);
+
+        for name in stale {
+            self.unlink(&name)?;
+        }
+        for name in missing {
+            let binding: Arc<dyn IndexNode> = match self.create(&name, FileType::SymLink, 0o700) {
+                Ok(inode) => inode,
+                // 其他进程同时更新了fd目录
+                Err(SystemError::EEXIST) => continue,
+                Err(e) => return Err(e),
+            };
+            let link: &LockedProcFSInode = binding
+                .as_any_ref()
+                .downcast_ref::<LockedProcFSInode>()
+                .unwrap();
+            let mut link_guard: SpinLockGuard<ProcFSInode> = link.0.lock();
+            link_guard.fdata.pid = pid;
+            link_guard.fdata.ftype = ProcFileType::ProcFdLink;
+            link_guard.fdata.fd = name.parse().unwrap();
+            link_guard.metadata.uid = uid;
+            link_guard.metadata.gid = gid;
+        }
+        return Ok(());
+    }
+}
+
 impl IndexNode for LockedProcFSInode {
     fn open(&self, data: &mut FilePrivateData, _mode: &FileMode) -> Result<(), SystemError> {
         // 加锁
         let mut inode: SpinLockGuard<ProcFSInode> = self.0.lock();
 
-        // 如果inode类型为文件夹，则直接返回成功
-        if let FileType::Dir = inode.metadata.file_type {
+        // 如果inode类型为文件夹或符号链接，则直接返回成功
+        if let FileType::Dir | FileType::SymLink = inode.metadata.file_type {
+            return Ok(());
+        }
+        // 属性文件的内容在读取时才生成
+        if inode.attr.is_some() {
+            *data = FilePrivateData::Seq(SeqFileState::new());
             return Ok(());
         }
         let mut private_data = ProcfsFilePrivateData::new();
         // 根据文件类型获取相应数据
         let file_size = match inode.fdata.ftype {
             ProcFileType::ProcStatus => inode.open_status(&mut private_data)?,
+            ProcFileType::ProcCmdline | ProcFileType::ProcEnviron => {
+                inode.open_cmdline(&mut private_data)?
+            }
+            ProcFileType::ProcMaps => inode.open_maps(&mut private_data)?,
+            ProcFileType::ProcStat => inode.open_stat(&mut private_data)?,
+            ProcFileType::ProcStatm => inode.open_statm(&mut private_data)?,
+            ProcFileType::ProcMeminfo => inode.open_meminfo(&mut private_data)?,
+            ProcFileType::ProcCpuinfo => inode.open_cpuinfo(&mut private_data)?,
+            ProcFileType::ProcUptime => inode.open_uptime(&mut private_data)?,
+            ProcFileType::ProcLoadavg => inode.open_loadavg(&mut private_data)?,
+            ProcFileType::ProcInterrupts => inode.open_interrupts(&mut private_data)?,
+            ProcFileType::ProcVersion => inode.open_version(&mut private_data)?,
             _ => {
                 todo!()
             }
@@ -349,8 +988,11 @@ impl IndexNode for LockedProcFSInode {
 
     fn close(&self, data: &mut FilePrivateData) -> Result<(), SystemError> {
         let guard: SpinLockGuard<ProcFSInode> = self.0.lock();
-        // 如果inode类型为文件夹，则直接返回成功
-        if let FileType::Dir = guard.metadata.file_type {
+        // 如果inode类型为文件夹或符号链接，则直接返回成功
+        if let FileType::Dir | FileType::SymLink = guard.metadata.file_type {
+            return Ok(());
+        }
+        if guard.attr.is_some() {
             return Ok(());
         }
         // 获取数据信息
@@ -383,6 +1025,29 @@ impl IndexNode for LockedProcFSInode {
             return Err(SystemError::EISDIR);
         }
 
+        // 符号链接的内容不依赖于打开文件时生成的数据（read_link不会打开文件）
+        if inode.metadata.file_type == FileType::SymLink {
+            let target: String = inode.link_target()?;
+            let start = target.len().min(offset);
+            let end = target.len().min(offset + len);
+            let src = &target.as_bytes()[start..end];
+            buf[0..src.len()].copy_from_slice(src);
+            return Ok(src.len());
+        }
+
+        // 属性文件：释放锁之后再调用show回调，因为回调中可能会访问procfs
+        let attr: Option<Arc<dyn SeqOperations>> = inode.attr.clone();
+        if let Some(attr) = attr {
+            drop(inode);
+            let state = match data {
+                FilePrivateData::Seq(s) => s,
+                _ => {
+                    panic!("ProcFS: FilePrivateData mismatch!");
+                }
+            };
+            return seq_read(attr.as_ref(), state, offset, len, buf);
+        }
+
         // 获取数据信息
         let private_data = match data {
             FilePrivateData::Procfs(p) => p,
@@ -393,8 +1058,22 @@ impl IndexNode for LockedProcFSInode {
 
         // 根据文件类型读取相应数据
         match inode.fdata.ftype {
-            ProcFileType::ProcStatus => return inode.read_status(offset, len, buf, private_data),
-            ProcFileType::Default => (),
+            // 这些文件的内容在打开时生成，保存在文件的私有数据中
+            ProcFileType::ProcStatus
+            | ProcFileType::ProcCmdline
+            | ProcFileType::ProcEnviron
+            | ProcFileType::ProcMaps
+            | ProcFileType::ProcStat
+            | ProcFileType::ProcStatm
+            | ProcFileType::ProcMeminfo
+            | ProcFileType::ProcCpuinfo
+            | ProcFileType::ProcUptime
+            | ProcFileType::ProcLoadavg
+            | ProcFileType::ProcInterrupts
+            | ProcFileType::ProcVersion => {
+                return inode.read_status(offset, len, buf, private_data)
+            }
+            _ => (),
         };
 
         // 默认读取
@@ -415,11 +1094,18 @@ impl IndexNode for LockedProcFSInode {
     fn write_at(
         &self,
         _offset: usize,
-        _len: usize,
-        _buf: &[u8],
+        len: usize,
+        buf: &[u8],
         _data: &mut FilePrivateData,
     ) -> Result<usize, SystemError> {
-        return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+        if buf.len() < len {
+            return Err(SystemError::EINVAL);
+        }
+        let attr: Option<Arc<dyn SeqOperations>> = self.0.lock().attr.clone();
+        match attr {
+            Some(attr) => return seq_write(attr.as_ref(), &buf[0..len]),
+            None => return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP),
+        }
     }
 
     fn poll(&self) -> Result<PollStatus, SystemError> {
@@ -444,7 +1130,11 @@ impl IndexNode for LockedProcFSInode {
 
     fn metadata(&self) -> Result<Metadata, SystemError> {
         let inode = self.0.lock();
-        let metadata = inode.metadata.clone();
+        let mut metadata = inode.metadata.clone();
+        // 符号链接的大小就是其目标路径的长度（read_link依赖于此）
+        if metadata.file_type == FileType::SymLink {
+            metadata.size = inode.link_target().map_or(0, |t| t.len() as i64);
+        }
 
         return Ok(metadata);
     }
@@ -499,6 +1189,7 @@ impl IndexNode for LockedProcFSInode {
                 metadata: Metadata {
                     dev_id: 0,
                     inode_id: generate_inode_id(),
+                    disk_ino: None,
                     size: 0,
                     blk_size: 0,
                     blocks: 0,
@@ -516,7 +1207,9 @@ impl IndexNode for LockedProcFSInode {
                 fdata: InodeInfo {
                     pid: 0,
                     ftype: ProcFileType::Default,
+                    fd: -1,
                 },
+                attr: None,
             })));
 
         // 初始化inode的自引用的weak指针
@@ -524,6 +1217,8 @@ impl IndexNode for LockedProcFSInode {
 
         // 将子inode插入父inode的B树中
         inode.children.insert(String::from(name), result.clone());
+        // procfs的目录项会在VFS之外被创建，因此需要在这里令目录项缓存失效
+        dcache().invalidate_dir(inode.metadata.inode_id);
 
         return Ok(result);
     }
@@ -576,6 +1271,7 @@ impl IndexNode for LockedProcFSInode {
         to_delete.0.lock().metadata.nlinks -= 1;
         // 在当前目录中删除这个子目录项
         inode.children.remove(name);
+        dcache().invalidate_dir(inode.metadata.inode_id);
         return Ok(());
     }
 
@@ -589,6 +1285,7 @@ impl IndexNode for LockedProcFSInode {
     }
 
     fn find(&self, name: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+        self.refresh_fd_dir()?;
         let inode = self.0.lock();
 
         if inode.metadata.file_type != FileType::Dir {
@@ -610,6 +1307,10 @@ impl IndexNode for LockedProcFSInode {
         }
     }
 
+    fn dentry_cacheable(&self) -> bool {
+        return self.0.lock().fdata.ftype != ProcFileType::ProcFdDir;
+    }
+
     fn get_entry_name(&self, ino: InodeId) -> Result<String, SystemError> {
         let inode: SpinLockGuard<ProcFSInode> = self.0.lock();
         if inode.metadata.file_type != FileType::Dir {
@@ -648,6 +1349,7 @@ impl IndexNode for LockedProcFSInode {
             return Err(SystemError::ENOTDIR);
         }
 
+        self.refresh_fd_dir()?;
         let mut keys: Vec<String> = Vec::new();
         keys.push(String::from("."));
         keys.push(String::from(".."));
@@ -657,6 +1359,63 @@ impl IndexNode for LockedProcFSInode {
     }
 }
 
+/// /proc/mounts：每个挂载点对应一条记录
+#[derive(Debug)]
+struct ProcMounts;
+
+impl SeqOperations for ProcMounts {
+    fn show(&self, index: usize, buf: &mut String) -> Result<bool, SystemError> {
+        match mount_list().records().get(index) {
+            Some(record) => {
+                buf.push_str(&record.to_mounts_line());
+                return Ok(true);
+            }
+            None => return Ok(false),
+        }
+    }
+}
+
+/// @brief 获取procfs实例
+fn procfs_instance() -> Result<Arc<dyn FileSystem>, SystemError> {
+    let procfs_inode: Arc<dyn IndexNode> = ROOT_INODE().find("proc")?;
+    let procfs_inode: &LockedProcFSInode = procfs_inode
+        .downcast_ref::<LockedProcFSInode>()
+        .ok_or(SystemError::ENOENT)?;
+    return Ok(procfs_inode.fs());
+}
+
+/// @brief 在procfs中注册一个属性文件，供其他子系统对外提供信息或可调整的参数
+///
+/// ## 示例
+///
+/// ```
+/// procfs_create_attr(
+///     "sys/kernel/foo",
+///     0o644,
+///     Arc::new(Attribute::new(|buf| { buf.push_str("0\n"); Ok(()) })),
+/// )?;
+/// ```
+///
+/// @param path 相对于/proc的路径，路径中不存在的目录会被自动创建
+/// @param mode 文件的权限
+/// @param ops 属性文件的操作
+pub fn procfs_create_attr(
+    path: &str,
+    mode: u32,
+    ops: Arc<dyn SeqOperations>,
+) -> Result<(), SystemError> {
+    let fs = procfs_instance()?;
+    let procfs: &ProcFS = fs.as_any_ref().downcast_ref::<ProcFS>().unwrap();
+    return procfs.create_attr(path, mode, ops);
+}
+
+/// @brief 删除通过[`procfs_create_attr`]注册的属性文件
+pub fn procfs_remove_attr(path: &str) -> Result<(), SystemError> {
+    let fs = procfs_instance()?;
+    let procfs: &ProcFS = fs.as_any_ref().downcast_ref::<ProcFS>().unwrap();
+    return procfs.remove_attr(path);
+}
+
 #[no_mangle]
 pub extern "C" fn rs_procfs_register_pid(pid: pid_t) -> u64 {
     let r = procfs_register_pid(pid);
@@ -717,11 +1476,18 @@ pub fn procfs_init() -> Result<(), SystemError> {
         let procfs: Arc<ProcFS> = ProcFS::new();
 
         // sysfs 挂载
-        let _t = ROOT_INODE()
+        let mount_fs = ROOT_INODE()
             .find("proc")
             .expect("Cannot find /proc")
             .mount(procfs)
             .expect("Failed to mount proc");
+        mount_list().insert(MountRecord::new(
+            "proc",
+            "/proc",
+            "proc",
+            MountFlags::empty(),
+            mount_fs,
+        ));
         kinfo!("ProcFS mounted.");
         result = Some(Ok(()));
     });
@@ -1,4 +1,7 @@
-use core::any::Any;
+use core::{
+    any::Any,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use alloc::{
     collections::BTreeMap,
@@ -8,28 +11,59 @@ use alloc::{
 };
 
 use crate::{
+    arch::{mm::LockedFrameAllocator, MMArch},
     filesystem::vfs::{core::generate_inode_id, FileType},
     libs::spinlock::{SpinLock, SpinLockGuard},
+    mm::{
+        allocator::page_frame::FrameAllocator,
+        page_cache::{CachedPage, PageCache, PageCacheBackend},
+        MemoryManagementArch,
+    },
     syscall::SystemError,
     time::TimeSpec,
 };
 
+use self::sparse::SparseData;
+
 use super::vfs::{
     file::FilePrivateData, FileSystem, FsInfo, IndexNode, InodeId, Metadata, PollStatus,
+    MAX_PATHLEN,
 };
 
+mod sparse;
+pub mod tmpfs;
+
 /// RamFS的inode名称的最大长度
 const RAMFS_MAX_NAMELEN: usize = 64;
+/// RamFS的魔数（与Linux相同）
+pub const RAMFS_MAGIC: u64 = 0x858458f6;
 
 /// @brief 内存文件系统的Inode结构体
 #[derive(Debug)]
 struct LockedRamFSInode(SpinLock<RamFSInode>);
 
+/// @brief 内存文件系统的容量限制
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RamFSLimits {
+    /// 文件数据最多能占用的页数，None表示不限制
+    pub max_pages: Option<u64>,
+    /// 最多能创建的inode数量（包括根目录），None表示不限制
+    pub max_inodes: Option<u64>,
+}
+
 /// @brief 内存文件系统结构体
 #[derive(Debug)]
 pub struct RamFS {
     /// RamFS的root inode
     root_inode: Arc<LockedRamFSInode>,
+    /// 通过statfs报告的魔数
+    magic: u64,
+    /// 容量限制
+    limits: RamFSLimits,
+    /// 文件数据已经占用的页数
+    used_pages: AtomicU64,
+    /// 已经创建的inode数量
+    used_inodes: AtomicU64,
 }
 
 /// @brief 内存文件系统的Inode结构体(不包含锁)
@@ -45,12 +79,14 @@ pub struct RamFSInode {
     self_ref: Weak<LockedRamFSInode>,
     /// 子Inode的B树
     children: BTreeMap<String, Arc<LockedRamFSInode>>,
-    /// 当前inode的数据部分
-    data: Vec<u8>,
+    /// 当前inode的数据部分。文件是稀疏存储的，空洞不占用内存
+    data: SparseData,
     /// 当前inode的元数据
     metadata: Metadata,
     /// 指向inode所在的文件系统对象的指针
     fs: Weak<RamFS>,
+    /// 文件的页缓存。只有当文件被映射到用户空间时才会创建，此后文件的内容以页缓存中的为准
+    page_cache: Option<Arc<PageCache>>,
 }
 
 impl FileSystem for RamFS {
@@ -59,9 +95,33 @@ impl FileSystem for RamFS {
     }
 
     fn info(&self) -> FsInfo {
+        let used_pages = self.used_pages.load(Ordering::SeqCst);
+        let used_inodes = self.used_inodes.load(Ordering::SeqCst);
+
+        let total_blocks = match self.limits.max_pages {
+            Some(max) => max,
+            None => {
+                // 没有容量限制时，可用空间就是系统中空闲的物理页
+                let free_pages = unsafe { LockedFrameAllocator.usage() }.free().data() as u64;
+                used_pages + free_pages
+            }
+        };
+        let free_blocks = total_blocks.saturating_sub(used_pages);
+        let (total_inodes, free_inodes) = match self.limits.max_inodes {
+            Some(max) => (max, max.saturating_sub(used_inodes)),
+            None => (used_inodes, 0),
+        };
+
         return FsInfo {
             blk_dev_id: 0,
             max_name_len: RAMFS_MAX_NAMELEN,
+            magic: self.magic,
+            block_size: MMArch::PAGE_SIZE,
+            total_blocks,
+            free_blocks,
+            avail_blocks: free_blocks,
+            total_inodes,
+            free_inodes,
         };
     }
 
@@ -74,15 +134,25 @@ impl FileSystem for RamFS {
 
 impl RamFS {
     pub fn new() -> Arc<Self> {
+        return Self::new_with_limits(RAMFS_MAGIC, RamFSLimits::default(), 0o777);
+    }
+
+    /// @brief 创建一个有容量限制的内存文件系统
+    ///
+    /// @param magic 通过statfs报告的魔数
+    /// @param limits 容量限制
+    /// @param mode 根目录的权限
+    pub fn new_with_limits(magic: u64, limits: RamFSLimits, mode: u32) -> Arc<Self> {
         // 初始化root inode
         let root: Arc<LockedRamFSInode> = Arc::new(LockedRamFSInode(SpinLock::new(RamFSInode {
             parent: Weak::default(),
             self_ref: Weak::default(),
             children: BTreeMap::new(),
-            data: Vec::new(),
+            data: SparseData::default(),
             metadata: Metadata {
                 dev_id: 0,
                 inode_id: generate_inode_id(),
+                disk_ino: None,
                 size: 0,
                 blk_size: 0,
                 blocks: 0,
@@ -90,16 +160,25 @@ impl RamFS {
                 mtime: TimeSpec::default(),
                 ctime: TimeSpec::default(),
                 file_type: FileType::Dir,
-                mode: 0o777,
-                nlinks: 1,
+                mode,
+                // 目录的链接数为2（父目录中的目录项，以及它自己的"."）加上子目录的数量
+                nlinks: 2,
                 uid: 0,
                 gid: 0,
                 raw_dev: 0,
             },
             fs: Weak::default(),
+            page_cache: None,
         })));
 
-        let result: Arc<RamFS> = Arc::new(RamFS { root_inode: root });
+        let result: Arc<RamFS> = Arc::new(RamFS {
+            root_inode: root,
+            magic,
+            limits,
+            used_pages: AtomicU64::new(0),
+            // 根目录
+            used_inodes: AtomicU64::new(1),
+        });
 
         // 对root inode加锁，并继续完成初始化工作
         let mut root_guard: SpinLockGuard<RamFSInode> = result.root_inode.0.lock();
@@ -111,6 +190,163 @@ impl RamFS {
 
         return result;
     }
+
+    /// @brief 为文件数据申请pages个页的容量
+    ///
+    /// @return 超出容量限制时，返回ENOSPC
+    fn charge_pages(&self, pages: u64) -> Result<(), SystemError> {
+        if pages == 0 {
+            return Ok(());
+        }
+        let max = self.limits.max_pages;
+        return self
+            .used_pages
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| match max {
+                Some(max) if used + pages > max => None,
+                _ => Some(used + pages),
+            })
+            .map(|_| ())
+            .map_err(|_| SystemError::ENOSPC);
+    }
+
+    /// @brief 归还pages个页的容量
+    fn uncharge_pages(&self, pages: u64) {
+        self.used_pages.fetch_sub(pages, Ordering::SeqCst);
+    }
+
+    /// @brief 为新的inode申请容量
+    ///
+    /// @return inode数量达到上限时，返回ENOSPC
+    fn charge_inode(&self) -> Result<(), SystemError> {
+        let max = self.limits.max_inodes;
+        return self
+            .used_inodes
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| match max {
+                Some(max) if used >= max => None,
+                _ => Some(used + 1),
+            })
+            .map(|_| ())
+            .map_err(|_| SystemError::ENOSPC);
+    }
+}
+
+impl RamFSInode {
+    /// @brief 文件内容（对于目录来说，是目录项）被修改后，更新修改时间和状态改变时间
+    #[inline]
+    fn touch_modified(&mut self, now: TimeSpec) {
+        self.metadata.mtime = now;
+        self.metadata.ctime = now;
+    }
+
+    /// @brief 从data中读取文件的第index页
+    fn read_page(&self, index: usize, page: &mut [u8]) -> Result<usize, SystemError> {
+        return Ok(self.data.read(index * MMArch::PAGE_SIZE, page));
+    }
+
+    /// @brief 向data中写入数据，并向文件系统申请新分配的页的容量
+    ///
+    /// @return 超出文件系统的容量限制时，返回ENOSPC，且不会写入任何数据
+    fn write_data(&mut self, offset: usize, buf: &[u8]) -> Result<(), SystemError> {
+        let pages = self.data.pages_to_allocate(offset, buf.len()) as u64;
+        if let Some(fs) = self.fs.upgrade() {
+            fs.charge_pages(pages)?;
+        }
+        self.data.write(offset, buf);
+        return Ok(());
+    }
+
+    /// @brief 设置data的长度，并把被释放的页的容量归还给文件系统
+    fn resize_data(&mut self, len: usize) {
+        let freed = self.data.resize(len) as u64;
+        if let Some(fs) = self.fs.upgrade() {
+            fs.uncharge_pages(freed);
+        }
+    }
+
+    /// @brief 把页缓存中的脏页写回data
+    fn writeback_pages(&mut self) -> Result<(), SystemError> {
+        let page_cache: Arc<PageCache> = match &self.page_cache {
+            Some(pc) => pc.clone(),
+            None => return Ok(()),
+        };
+        let fs: Option<Arc<RamFS>> = self.fs.upgrade();
+        let data: &mut SparseData = &mut self.data;
+        return page_cache.writeback(data.len(), |index, buf| {
+            let start = index * MMArch::PAGE_SIZE;
+            // 页缓存中的页已经占用了内存，因此即使超出容量限制，也要写回
+            if let Some(fs) = &fs {
+                let pages = data.pages_to_allocate(start, buf.len()) as u64;
+                fs.used_pages.fetch_add(pages, Ordering::SeqCst);
+            }
+            data.write(start, buf);
+            return Ok(());
+        });
+    }
+}
+
+impl Drop for RamFSInode {
+    fn drop(&mut self) {
+        // inode被释放时，把它占用的容量归还给文件系统
+        if let Some(fs) = self.fs.upgrade() {
+            fs.uncharge_pages(self.data.allocated_pages() as u64);
+            fs.used_inodes.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl LockedRamFSInode {
+    /// @brief 检查重命名时，已经存在的目标能否被child替换
+    ///
+    /// @return 目标是否为文件夹
+    fn check_victim(
+        child: &Arc<LockedRamFSInode>,
+        victim: Option<&Arc<LockedRamFSInode>>,
+    ) -> Result<bool, SystemError> {
+        let victim: &Arc<LockedRamFSInode> = match victim {
+            Some(v) => v,
+            None => return Ok(false),
+        };
+        let child_is_dir = child.0.lock().metadata.file_type == FileType::Dir;
+        let victim_guard: SpinLockGuard<RamFSInode> = victim.0.lock();
+        let victim_is_dir = victim_guard.metadata.file_type == FileType::Dir;
+        if child_is_dir && !victim_is_dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        if !child_is_dir && victim_is_dir {
+            return Err(SystemError::EISDIR);
+        }
+        // 只有空文件夹才能被替换
+        if victim_is_dir && !victim_guard.children.is_empty() {
+            return Err(SystemError::ENOTEMPTY);
+        }
+        return Ok(victim_is_dir);
+    }
+
+    /// @brief 重命名替换掉已经存在的目标后，减少被替换的inode的硬链接计数
+    fn drop_victim(victim: Option<Arc<LockedRamFSInode>>, is_dir: bool, now: TimeSpec) {
+        if let Some(victim) = victim {
+            let mut guard: SpinLockGuard<RamFSInode> = victim.0.lock();
+            if is_dir {
+                guard.metadata.nlinks = 0;
+            } else {
+                guard.metadata.nlinks -= 1;
+            }
+            guard.metadata.ctime = now;
+        }
+    }
+}
+
+impl PageCacheBackend for LockedRamFSInode {
+    fn writeback(&self) -> Result<(), SystemError> {
+        return self.0.lock().writeback_pages();
+    }
+
+    fn try_writeback(&self) -> bool {
+        match self.0.try_lock() {
+            Ok(mut guard) => return guard.writeback_pages().is_ok(),
+            Err(_) => return false,
+        }
+    }
 }
 
 impl IndexNode for LockedRamFSInode {
@@ -124,7 +360,11 @@ impl IndexNode for LockedRamFSInode {
 
         //当前文件长度大于_len才进行截断，否则不操作
         if inode.data.len() > len {
-            inode.data.resize(len, 0);
+            inode.resize_data(len);
+            if let Some(pc) = &inode.page_cache {
+                pc.truncate(len);
+            }
+            inode.touch_modified(TimeSpec::now());
         }
         return Ok(());
     }
@@ -151,26 +391,25 @@ impl IndexNode for LockedRamFSInode {
         if buf.len() < len {
             return Err(SystemError::EINVAL);
         }
+        let now = TimeSpec::now();
         // 加锁
-        let inode: SpinLockGuard<RamFSInode> = self.0.lock();
+        let mut inode: SpinLockGuard<RamFSInode> = self.0.lock();
 
         // 检查当前inode是否为一个文件夹，如果是的话，就返回错误
         if inode.metadata.file_type == FileType::Dir {
             return Err(SystemError::EISDIR);
         }
+        inode.metadata.atime = now;
 
-        let start = inode.data.len().min(offset);
-        let end = inode.data.len().min(offset + len);
-
-        // buffer空间不足
-        if buf.len() < (end - start) {
-            return Err(SystemError::ENOBUFS);
+        // 文件被映射过，通过页缓存读取
+        if let Some(pc) = inode.page_cache.clone() {
+            return pc.read(offset, &mut buf[0..len], inode.data.len(), |index, page| {
+                inode.read_page(index, page)
+            });
         }
 
-        // 拷贝数据
-        let src = &inode.data[start..end];
-        buf[0..src.len()].copy_from_slice(src);
-        return Ok(src.len());
+        // 拷贝数据，空洞读出来是0
+        return Ok(inode.data.read(offset, &mut buf[0..len]));
     }
 
     fn write_at(
@@ -184,6 +423,7 @@ impl IndexNode for LockedRamFSInode {
             return Err(SystemError::EINVAL);
         }
 
+        let now = TimeSpec::now();
         // 加锁
         let mut inode: SpinLockGuard<RamFSInode> = self.0.lock();
 
@@ -191,16 +431,14 @@ impl IndexNode for LockedRamFSInode {
         if inode.metadata.file_type == FileType::Dir {
             return Err(SystemError::EISDIR);
         }
+        // 写入的数据超出文件系统的容量限制时，返回ENOSPC
+        inode.write_data(offset, &buf[0..len])?;
+        inode.touch_modified(now);
 
-        let data: &mut Vec<u8> = &mut inode.data;
-
-        // 如果文件大小比原来的大，那就resize这个数组
-        if offset + len > data.len() {
-            data.resize(offset + len, 0);
+        // 同步更新页缓存中的页
+        if let Some(pc) = &inode.page_cache {
+            pc.update(offset, &buf[0..len]);
         }
-
-        let target = &mut data[offset..offset + len];
-        target.copy_from_slice(&buf[0..len]);
         return Ok(len);
     }
 
@@ -228,6 +466,9 @@ impl IndexNode for LockedRamFSInode {
         let inode = self.0.lock();
         let mut metadata = inode.metadata.clone();
         metadata.size = inode.data.len() as i64;
+        // 空洞不占用空间，因此只统计已经分配的页（st_blocks以512字节为单位）
+        metadata.blk_size = MMArch::PAGE_SIZE;
+        metadata.blocks = inode.data.allocated_pages() * (MMArch::PAGE_SIZE / 512);
 
         return Ok(metadata);
     }
@@ -247,7 +488,12 @@ impl IndexNode for LockedRamFSInode {
     fn resize(&self, len: usize) -> Result<(), SystemError> {
         let mut inode = self.0.lock();
         if inode.metadata.file_type == FileType::File {
-            inode.data.resize(len, 0);
+            // 先把页缓存中超出文件大小的部分清零，这样扩展文件时，新增的部分也是0
+            if let Some(pc) = &inode.page_cache {
+                pc.truncate(len.min(inode.data.len()));
+            }
+            inode.resize_data(len);
+            inode.touch_modified(TimeSpec::now());
             return Ok(());
         } else {
             return Err(SystemError::EINVAL);
@@ -261,6 +507,7 @@ impl IndexNode for LockedRamFSInode {
         mode: u32,
         data: usize,
     ) -> Result<Arc<dyn IndexNode>, SystemError> {
+        let now = TimeSpec::now();
         // 获取当前inode
         let mut inode = self.0.lock();
         // 如果当前inode不是文件夹，则返回
@@ -271,30 +518,35 @@ impl IndexNode for LockedRamFSInode {
         if inode.children.contains_key(name) {
             return Err(SystemError::EEXIST);
         }
+        // inode数量达到上限时，返回ENOSPC
+        let fs: Arc<RamFS> = inode.fs.upgrade().unwrap();
+        fs.charge_inode()?;
 
         // 创建inode
         let result: Arc<LockedRamFSInode> = Arc::new(LockedRamFSInode(SpinLock::new(RamFSInode {
             parent: inode.self_ref.clone(),
             self_ref: Weak::default(),
             children: BTreeMap::new(),
-            data: Vec::new(),
+            data: SparseData::default(),
             metadata: Metadata {
                 dev_id: 0,
                 inode_id: generate_inode_id(),
+                disk_ino: None,
                 size: 0,
                 blk_size: 0,
                 blocks: 0,
-                atime: TimeSpec::default(),
-                mtime: TimeSpec::default(),
-                ctime: TimeSpec::default(),
+                atime: now,
+                mtime: now,
+                ctime: now,
                 file_type: file_type,
                 mode: mode,
-                nlinks: 1,
+                nlinks: if file_type == FileType::Dir { 2 } else { 1 },
                 uid: 0,
                 gid: 0,
                 raw_dev: data,
             },
             fs: inode.fs.clone(),
+            page_cache: None,
         })));
 
         // 初始化inode的自引用的weak指针
@@ -302,6 +554,35 @@ impl IndexNode for LockedRamFSInode {
 
         // 将子inode插入父inode的B树中
         inode.children.insert(String::from(name), result.clone());
+        // 子目录的".."指向当前目录
+        if file_type == FileType::Dir {
+            inode.metadata.nlinks += 1;
+        }
+        inode.touch_modified(now);
+
+        return Ok(result);
+    }
+
+    fn symlink(&self, name: &str, target: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+        if target.is_empty() {
+            return Err(SystemError::ENOENT);
+        }
+        if target.len() > MAX_PATHLEN {
+            return Err(SystemError::ENAMETOOLONG);
+        }
+
+        let result: Arc<dyn IndexNode> = self.create(name, FileType::SymLink, 0o777)?;
+        // 符号链接所指向的路径，就是它的数据部分
+        let r = result
+            .downcast_ref::<LockedRamFSInode>()
+            .unwrap()
+            .0
+            .lock()
+            .write_data(0, target.as_bytes());
+        if let Err(e) = r {
+            self.unlink(name)?;
+            return Err(e);
+        }
 
         return Ok(result);
     }
@@ -334,6 +615,9 @@ impl IndexNode for LockedRamFSInode {
 
         // 增加硬链接计数
         other_locked.metadata.nlinks += 1;
+        let now = TimeSpec::now();
+        other_locked.metadata.ctime = now;
+        inode.touch_modified(now);
         return Ok(());
     }
 
@@ -353,10 +637,15 @@ impl IndexNode for LockedRamFSInode {
         if to_delete.0.lock().metadata.file_type == FileType::Dir {
             return Err(SystemError::EPERM);
         }
+        let now = TimeSpec::now();
         // 减少硬链接计数
-        to_delete.0.lock().metadata.nlinks -= 1;
+        let mut to_delete_guard = to_delete.0.lock();
+        to_delete_guard.metadata.nlinks -= 1;
+        to_delete_guard.metadata.ctime = now;
+        drop(to_delete_guard);
         // 在当前目录中删除这个子目录项
         inode.children.remove(name);
+        inode.touch_modified(now);
         return Ok(());
     }
 
@@ -372,9 +661,16 @@ impl IndexNode for LockedRamFSInode {
             return Err(SystemError::ENOTDIR);
         }
 
-        to_delete.0.lock().metadata.nlinks -= 1;
-        // 在当前目录中删除这个子目录项
+        let now = TimeSpec::now();
+        // 目录被删除后，不再有任何目录项指向它
+        let mut to_delete_guard = to_delete.0.lock();
+        to_delete_guard.metadata.nlinks = 0;
+        to_delete_guard.metadata.ctime = now;
+        drop(to_delete_guard);
+        // 在当前目录中删除这个子目录项，并减去被删除的目录的".."
         inode.children.remove(name);
+        inode.metadata.nlinks -= 1;
+        inode.touch_modified(now);
         return Ok(());
     }
 
@@ -384,16 +680,184 @@ impl IndexNode for LockedRamFSInode {
         target: &Arc<dyn IndexNode>,
         new_name: &str,
     ) -> Result<(), SystemError> {
-        let old_inode: Arc<dyn IndexNode> = self.find(old_name)?;
+        // 不允许跨文件系统移动
+        let target: &LockedRamFSInode = target
+            .downcast_ref::<LockedRamFSInode>()
+            .ok_or(SystemError::EXDEV)?;
+
+        if old_name == "." || old_name == ".." || new_name == "." || new_name == ".." {
+            return Err(SystemError::EBUSY);
+        }
+
+        let now = TimeSpec::now();
+        // 在同一个目录下重命名
+        if core::ptr::eq(self, target) {
+            let mut inode: SpinLockGuard<RamFSInode> = self.0.lock();
+            if inode.metadata.file_type != FileType::Dir {
+                return Err(SystemError::ENOTDIR);
+            }
+            let child: Arc<LockedRamFSInode> = inode
+                .children
+                .get(old_name)
+                .cloned()
+                .ok_or(SystemError::ENOENT)?;
+            if old_name == new_name {
+                return Ok(());
+            }
+            let victim: Option<Arc<LockedRamFSInode>> = inode.children.get(new_name).cloned();
+            // 新旧名称指向同一个inode（硬链接），什么都不用做
+            if victim.as_ref().map_or(false, |v| Arc::ptr_eq(v, &child)) {
+                return Ok(());
+            }
+            let victim_is_dir: bool = Self::check_victim(&child, victim.as_ref())?;
+            if victim_is_dir {
+                // 被替换的目录的".."不再指向当前目录
+                inode.metadata.nlinks -= 1;
+            }
+            inode.children.remove(old_name);
+            child.0.lock().metadata.ctime = now;
+            // 插入新的目录项时，原子地替换掉已经存在的目标
+            inode.children.insert(String::from(new_name), child);
+            inode.touch_modified(now);
+            drop(inode);
+            Self::drop_victim(victim, victim_is_dir, now);
+            return Ok(());
+        }
+
+        // 跨目录移动。由VFS保证同一时刻只有一个跨目录的移动操作，因此这里按照固定的顺序加锁不会死锁
+        let mut inode: SpinLockGuard<RamFSInode> = self.0.lock();
+        let mut target_inode: SpinLockGuard<RamFSInode> = target.0.lock();
+        if inode.metadata.file_type != FileType::Dir
+            || target_inode.metadata.file_type != FileType::Dir
+        {
+            return Err(SystemError::ENOTDIR);
+        }
+        let child: Arc<LockedRamFSInode> = inode
+            .children
+            .get(old_name)
+            .cloned()
+            .ok_or(SystemError::ENOENT)?;
+        let victim: Option<Arc<LockedRamFSInode>> = target_inode.children.get(new_name).cloned();
+        if victim.as_ref().map_or(false, |v| Arc::ptr_eq(v, &child)) {
+            return Ok(());
+        }
+        if let Some(v) = &victim {
+            if core::ptr::eq(Arc::as_ptr(v), self) {
+                return Err(SystemError::ENOTEMPTY);
+            }
+        }
+        let victim_is_dir: bool = Self::check_victim(&child, victim.as_ref())?;
+
+        inode.children.remove(old_name);
+        {
+            // 被移动的是文件夹，那么要更新它的父目录
+            let mut child_guard: SpinLockGuard<RamFSInode> = child.0.lock();
+            if child_guard.metadata.file_type == FileType::Dir {
+                child_guard.parent = target_inode.self_ref.clone();
+                // 子目录的".."从原目录指向了目标目录
+                inode.metadata.nlinks -= 1;
+                target_inode.metadata.nlinks += 1;
+            }
+            child_guard.metadata.ctime = now;
+        }
+        if victim_is_dir {
+            target_inode.metadata.nlinks -= 1;
+        }
+        target_inode.children.insert(String::from(new_name), child);
+        inode.touch_modified(now);
+        target_inode.touch_modified(now);
+        drop(target_inode);
+        drop(inode);
+        Self::drop_victim(victim, victim_is_dir, now);
+        return Ok(());
+    }
+
+    fn exchange(
+        &self,
+        old_name: &str,
+        target: &Arc<dyn IndexNode>,
+        new_name: &str,
+    ) -> Result<(), SystemError> {
+        let target: &LockedRamFSInode = target
+            .downcast_ref::<LockedRamFSInode>()
+            .ok_or(SystemError::EXDEV)?;
+
+        if old_name == "." || old_name == ".." || new_name == "." || new_name == ".." {
+            return Err(SystemError::EBUSY);
+        }
+
+        let now = TimeSpec::now();
+        if core::ptr::eq(self, target) {
+            let mut inode: SpinLockGuard<RamFSInode> = self.0.lock();
+            if inode.metadata.file_type != FileType::Dir {
+                return Err(SystemError::ENOTDIR);
+            }
+            let a = inode
+                .children
+                .get(old_name)
+                .cloned()
+                .ok_or(SystemError::ENOENT)?;
+            let b = inode
+                .children
+                .get(new_name)
+                .cloned()
+                .ok_or(SystemError::ENOENT)?;
+            a.0.lock().metadata.ctime = now;
+            b.0.lock().metadata.ctime = now;
+            inode.children.insert(String::from(old_name), b);
+            inode.children.insert(String::from(new_name), a);
+            inode.touch_modified(now);
+            return Ok(());
+        }
+
+        // 跨目录交换。由VFS保证同一时刻只有一个跨目录的移动操作，因此这里按照固定的顺序加锁不会死锁
+        let mut inode: SpinLockGuard<RamFSInode> = self.0.lock();
+        let mut target_inode: SpinLockGuard<RamFSInode> = target.0.lock();
+        if inode.metadata.file_type != FileType::Dir
+            || target_inode.metadata.file_type != FileType::Dir
+        {
+            return Err(SystemError::ENOTDIR);
+        }
+        let a = inode
+            .children
+            .get(old_name)
+            .cloned()
+            .ok_or(SystemError::ENOENT)?;
+        let b = target_inode
+            .children
+            .get(new_name)
+            .cloned()
+            .ok_or(SystemError::ENOENT)?;
+        for child in [&a, &b] {
+            if core::ptr::eq(Arc::as_ptr(child), self) || core::ptr::eq(Arc::as_ptr(child), target)
+            {
+                return Err(SystemError::EINVAL);
+            }
+        }
 
-        // 在新的目录下创建一个硬链接
-        target.link(new_name, &old_inode)?;
-        // 取消现有的目录下的这个硬链接
-        if let Err(err) = self.unlink(old_name) {
-            // 如果取消失败，那就取消新的目录下的硬链接
-            target.unlink(new_name)?;
-            return Err(err);
+        {
+            // 被交换的文件夹的父目录发生了变化，两个父目录的硬链接计数也要相应地调整
+            let mut a_guard: SpinLockGuard<RamFSInode> = a.0.lock();
+            if a_guard.metadata.file_type == FileType::Dir {
+                a_guard.parent = target_inode.self_ref.clone();
+                inode.metadata.nlinks -= 1;
+                target_inode.metadata.nlinks += 1;
+            }
+            a_guard.metadata.ctime = now;
+        }
+        {
+            let mut b_guard: SpinLockGuard<RamFSInode> = b.0.lock();
+            if b_guard.metadata.file_type == FileType::Dir {
+                b_guard.parent = inode.self_ref.clone();
+                target_inode.metadata.nlinks -= 1;
+                inode.metadata.nlinks += 1;
+            }
+            b_guard.metadata.ctime = now;
         }
+        inode.children.insert(String::from(old_name), b);
+        target_inode.children.insert(String::from(new_name), a);
+        inode.touch_modified(now);
+        target_inode.touch_modified(now);
         return Ok(());
     }
 
@@ -464,4 +928,25 @@ impl IndexNode for LockedRamFSInode {
 
         return Ok(keys);
     }
+
+    fn sync(&self) -> Result<(), SystemError> {
+        return self.0.lock().writeback_pages();
+    }
+
+    fn get_page(&self, index: usize) -> Result<Arc<CachedPage>, SystemError> {
+        let mut inode: SpinLockGuard<RamFSInode> = self.0.lock();
+        if inode.metadata.file_type != FileType::File {
+            return Err(SystemError::ENODEV);
+        }
+
+        // 文件第一次被映射时，才创建页缓存
+        if inode.page_cache.is_none() {
+            let backend: Weak<dyn PageCacheBackend> = inode.self_ref.clone() as _;
+            inode.page_cache = Some(PageCache::new(backend));
+        }
+        let page_cache: Arc<PageCache> = inode.page_cache.clone().unwrap();
+        return page_cache.get_or_read_page(index, inode.data.len(), &mut |index, page| {
+            inode.read_page(index, page)
+        });
+    }
 }
@@ -0,0 +1,107 @@
+use alloc::{boxed::Box, collections::BTreeMap};
+
+use crate::{arch::MMArch, mm::MemoryManagementArch};
+
+/// @brief 稀疏存储的文件数据
+///
+/// 文件的内容按页存放，只有被写入过的页才会分配内存。没有分配内存的页（空洞）读出来全是0，
+/// 并且不占用文件系统的容量。
+#[derive(Debug, Default)]
+pub struct SparseData {
+    /// 页号 -> 页的内容
+    pages: BTreeMap<usize, Box<[u8]>>,
+    /// 文件的长度（单位：字节）
+    len: usize,
+}
+
+impl SparseData {
+    /// @brief 获取文件的长度
+    #[inline]
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    /// @brief 获取已经分配了内存的页数
+    #[inline]
+    pub fn allocated_pages(&self) -> usize {
+        return self.pages.len();
+    }
+
+    /// @brief 计算向[offset, offset+len)写入数据时，需要新分配的页数
+    pub fn pages_to_allocate(&self, offset: usize, len: usize) -> usize {
+        if len == 0 {
+            return 0;
+        }
+        let first = offset / MMArch::PAGE_SIZE;
+        let last = (offset + len - 1) / MMArch::PAGE_SIZE;
+        let allocated = self.pages.range(first..=last).count();
+        return (last - first + 1) - allocated;
+    }
+
+    /// @brief 从offset处读取数据，读取的长度不超过文件的末尾
+    ///
+    /// @return 读取到的字节数
+    pub fn read(&self, offset: usize, buf: &mut [u8]) -> usize {
+        let start = self.len.min(offset);
+        let end = self.len.min(offset + buf.len());
+
+        let mut pos = start;
+        while pos < end {
+            let index = pos / MMArch::PAGE_SIZE;
+            let in_page = pos % MMArch::PAGE_SIZE;
+            let n = (MMArch::PAGE_SIZE - in_page).min(end - pos);
+            let dst = &mut buf[pos - start..pos - start + n];
+            match self.pages.get(&index) {
+                Some(page) => dst.copy_from_slice(&page[in_page..in_page + n]),
+                // 空洞
+                None => dst.fill(0),
+            }
+            pos += n;
+        }
+        return end - start;
+    }
+
+    /// @brief 向offset处写入数据，必要时扩展文件的长度
+    ///
+    /// 调用者应当先通过pages_to_allocate检查容量
+    pub fn write(&mut self, offset: usize, buf: &[u8]) {
+        let end = offset + buf.len();
+        let mut pos = offset;
+        while pos < end {
+            let index = pos / MMArch::PAGE_SIZE;
+            let in_page = pos % MMArch::PAGE_SIZE;
+            let n = (MMArch::PAGE_SIZE - in_page).min(end - pos);
+            let page = self
+                .pages
+                .entry(index)
+                .or_insert_with(|| vec![0u8; MMArch::PAGE_SIZE].into_boxed_slice());
+            page[in_page..in_page + n].copy_from_slice(&buf[pos - offset..pos - offset + n]);
+            pos += n;
+        }
+        self.len = self.len.max(end);
+    }
+
+    /// @brief 设置文件的长度。扩展的部分是空洞，不会分配内存
+    ///
+    /// @return 被释放的页数
+    pub fn resize(&mut self, len: usize) -> usize {
+        if len >= self.len {
+            self.len = len;
+            return 0;
+        }
+
+        // 释放新的文件末尾之后的所有整页
+        let first_freed = (len + MMArch::PAGE_SIZE - 1) / MMArch::PAGE_SIZE;
+        let freed = self.pages.split_off(&first_freed).len();
+
+        // 把最后一页中超出文件末尾的部分清零，这样以后再扩展文件时，读出来的仍然是0
+        let in_page = len % MMArch::PAGE_SIZE;
+        if in_page != 0 {
+            if let Some(page) = self.pages.get_mut(&(len / MMArch::PAGE_SIZE)) {
+                page[in_page..].fill(0);
+            }
+        }
+        self.len = len;
+        return freed;
+    }
+}
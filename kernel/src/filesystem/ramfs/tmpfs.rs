@@ -0,0 +1,104 @@
+use alloc::sync::Arc;
+
+use crate::{
+    arch::{mm::LockedFrameAllocator, MMArch},
+    mm::{allocator::page_frame::FrameAllocator, MemoryManagementArch},
+    syscall::SystemError,
+};
+
+use super::{RamFS, RamFSLimits};
+
+/// tmpfs的魔数（与Linux相同）
+pub const TMPFS_MAGIC: u64 = 0x01021994;
+
+/// @brief tmpfs的挂载选项
+#[derive(Debug, Clone, Copy)]
+pub struct TmpfsOptions {
+    /// 文件数据最多能占用的页数，0表示不限制
+    pub max_pages: u64,
+    /// 最多能创建的inode数量，0表示不限制
+    pub max_inodes: u64,
+    /// 根目录的权限
+    pub mode: u32,
+}
+
+impl Default for TmpfsOptions {
+    /// 默认的容量是物理内存的一半，inode的数量是物理内存页数的一半（与Linux相同）
+    fn default() -> Self {
+        let half = Self::total_pages() / 2;
+        return Self {
+            max_pages: half,
+            max_inodes: half,
+            mode: 0o1777,
+        };
+    }
+}
+
+impl TmpfsOptions {
+    /// @brief 解析挂载时传入的选项字符串
+    ///
+    /// 支持的选项（以逗号分隔）：
+    /// - size=<字节数>[k|m|g|%]：文件数据的容量上限，%表示占物理内存的百分比，0表示不限制
+    /// - nr_inodes=<数量>[k|m|g]：inode的数量上限，0表示不限制
+    /// - mode=<八进制权限>：根目录的权限
+    ///
+    /// @return Err(EINVAL) 选项的格式错误，或者存在不支持的选项
+    pub fn parse(data: &str) -> Result<Self, SystemError> {
+        let mut options = Self::default();
+        for opt in data.split(',').filter(|s| !s.is_empty()) {
+            let (key, value) = opt.split_once('=').ok_or(SystemError::EINVAL)?;
+            match key {
+                "size" => {
+                    let bytes = match value.strip_suffix('%') {
+                        Some(percent) => {
+                            let percent: u64 = percent.parse().map_err(|_| SystemError::EINVAL)?;
+                            Self::total_pages() * percent / 100 * MMArch::PAGE_SIZE as u64
+                        }
+                        None => Self::parse_size(value)?,
+                    };
+                    // 向上取整到页
+                    options.max_pages =
+                        (bytes + MMArch::PAGE_SIZE as u64 - 1) / MMArch::PAGE_SIZE as u64;
+                }
+                "nr_inodes" => {
+                    options.max_inodes = Self::parse_size(value)?;
+                }
+                "mode" => {
+                    let mode = u32::from_str_radix(value, 8).map_err(|_| SystemError::EINVAL)?;
+                    options.mode = mode & 0o7777;
+                }
+                _ => return Err(SystemError::EINVAL),
+            }
+        }
+        return Ok(options);
+    }
+
+    /// @brief 解析带有k/m/g后缀的数值
+    fn parse_size(value: &str) -> Result<u64, SystemError> {
+        let (num, shift) = match value.as_bytes().last() {
+            Some(b'k' | b'K') => (&value[..value.len() - 1], 10),
+            Some(b'm' | b'M') => (&value[..value.len() - 1], 20),
+            Some(b'g' | b'G') => (&value[..value.len() - 1], 30),
+            _ => (value, 0),
+        };
+        let num: u64 = num.parse().map_err(|_| SystemError::EINVAL)?;
+        return num.checked_shl(shift).ok_or(SystemError::EINVAL);
+    }
+
+    /// @brief 获取系统中物理页的总数
+    fn total_pages() -> u64 {
+        return unsafe { LockedFrameAllocator.usage() }.total().data() as u64;
+    }
+}
+
+/// @brief 创建一个tmpfs实例
+///
+/// @param data 挂载选项字符串，参见TmpfsOptions::parse
+pub fn tmpfs_new(data: &str) -> Result<Arc<RamFS>, SystemError> {
+    let options = TmpfsOptions::parse(data)?;
+    let limits = RamFSLimits {
+        max_pages: Some(options.max_pages).filter(|x| *x != 0),
+        max_inodes: Some(options.max_inodes).filter(|x| *x != 0),
+    };
+    return Ok(RamFS::new_with_limits(TMPFS_MAGIC, limits, options.mode));
+}
@@ -1,4 +1,4 @@
-use super::{LockedSysFSInode, SYS_BUS_INODE};
+use super::{file::sysfs_create_link, LockedSysFSInode, SYS_BUS_INODE};
 use crate::{filesystem::vfs::IndexNode, kdebug, syscall::SystemError};
 use alloc::sync::Arc;
 
@@ -69,21 +69,51 @@ pub fn bus_driver_register(bus_name: &str, name: &str) -> Result<Arc<dyn IndexNo
     }
 }
 
-/// @brief: 在相应总线的driver下生成驱动文件夹
+/// @brief: 在相应总线的devices下生成设备文件夹，并在其中创建指向总线的subsystem链接
 /// @parameter bus_name: 总线名
-///            name: 驱动名
-/// @return: 操作成功，返回drivers inode，操作失败，返回错误码
+///            name: 设备名
+/// @return: 操作成功，返回device inode，操作失败，返回错误码
 pub fn bus_device_register(bus_name: &str, name: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
-    match SYS_BUS_INODE().find(bus_name) {
+    let device_dir: Arc<dyn IndexNode> = match SYS_BUS_INODE().find(bus_name) {
         Ok(platform) => match platform.find("devices") {
             Ok(device) => device
                 .as_any_ref()
                 .downcast_ref::<LockedSysFSInode>()
                 .ok_or(SystemError::E2BIG)
                 .unwrap()
-                .add_dir(name),
+                .add_dir(name)?,
             Err(_) => return Err(SystemError::EXDEV),
         },
         Err(_) => return Err(SystemError::EXDEV),
-    }
+    };
+    sysfs_create_link(&device_dir, "subsystem", &format!("/sys/bus/{}", bus_name))?;
+    return Ok(device_dir);
+}
+
+/// @brief: 将总线上的设备与驱动绑定：在设备文件夹下创建指向驱动的driver链接，
+///         并在驱动文件夹下创建指向设备的链接
+/// @parameter bus_name: 总线名
+///            device_name: 设备名
+///            driver_name: 驱动名
+/// @return: 操作成功，返回()，操作失败，返回错误码
+pub fn bus_device_bind_driver(
+    bus_name: &str,
+    device_name: &str,
+    driver_name: &str,
+) -> Result<(), SystemError> {
+    let bus: Arc<dyn IndexNode> = SYS_BUS_INODE().find(bus_name)?;
+    let device_dir: Arc<dyn IndexNode> = bus.find("devices")?.find(device_name)?;
+    let driver_dir: Arc<dyn IndexNode> = bus.find("drivers")?.find(driver_name)?;
+
+    sysfs_create_link(
+        &device_dir,
+        "driver",
+        &format!("/sys/bus/{}/drivers/{}", bus_name, driver_name),
+    )?;
+    sysfs_create_link(
+        &driver_dir,
+        device_name,
+        &format!("/sys/bus/{}/devices/{}", bus_name, device_name),
+    )?;
+    return Ok(());
 }
@@ -1,6 +1,6 @@
-use super::{LockedSysFSInode, SYS_CLASS_INODE};
+use super::{file::sysfs_create_link, LockedSysFSInode, SYS_CLASS_INODE};
 use crate::{filesystem::vfs::IndexNode, syscall::SystemError};
-use alloc::sync::Arc;
+use alloc::{string::String, sync::Arc};
 
 /// @brief: 注册class，在sys/class下生成文件夹
 /// @parameter class_name: 类文件夹名
@@ -32,7 +32,7 @@ pub fn sys_class_unregister(class_name: &str) -> Result<(), SystemError> {
         .remove(class_name)
 }
 
-/// @brief: 注册device，在对应类下操作设备文件夹
+/// @brief: 注册device，在对应类下操作设备文件夹，并在其中创建指向类的subsystem链接
 /// @parameter class: 类文件夹inode
 /// @parameter device_name: 设备文件夹名
 /// @return: 操作成功，返回inode，操作失败，返回错误码
@@ -42,12 +42,19 @@ pub fn class_device_register(
     class: Arc<dyn IndexNode>,
     device_name: &str,
 ) -> Result<Arc<dyn IndexNode>, SystemError> {
-    class
+    let class_name: String = SYS_CLASS_INODE().get_entry_name(class.metadata()?.inode_id)?;
+    let device_dir: Arc<dyn IndexNode> = class
         .as_any_ref()
         .downcast_ref::<LockedSysFSInode>()
         .ok_or(SystemError::E2BIG)
         .unwrap()
-        .add_dir(device_name)
+        .add_dir(device_name)?;
+    sysfs_create_link(
+        &device_dir,
+        "subsystem",
+        &format!("/sys/class/{}", class_name),
+    )?;
+    return Ok(device_dir);
 }
 
 /// @brief: 操作device，在对应类下删除设备文件夹
@@ -0,0 +1,52 @@
+use super::LockedSysFSInode;
+use crate::{
+    filesystem::vfs::{seq_file::SeqOperations, IndexNode},
+    syscall::SystemError,
+};
+use alloc::sync::Arc;
+
+/// @brief: 在sysfs的目录下创建属性文件，供各个子系统对外提供信息或可调整的参数
+/// @parameter dir: sysfs中的目录
+///            name: 属性文件名
+///            mode: 属性文件的权限
+///            ops: 属性文件的操作（可以使用`Attribute`提供show/store回调）
+/// @return: 操作成功，返回属性文件的inode，操作失败，返回错误码
+pub fn sysfs_create_file(
+    dir: &Arc<dyn IndexNode>,
+    name: &str,
+    mode: u32,
+    ops: Arc<dyn SeqOperations>,
+) -> Result<Arc<dyn IndexNode>, SystemError> {
+    dir.as_any_ref()
+        .downcast_ref::<LockedSysFSInode>()
+        .ok_or(SystemError::EINVAL)?
+        .add_attr(name, mode, ops)
+}
+
+/// @brief: 在sysfs的目录下创建符号链接
+/// @parameter dir: sysfs中的目录
+///            name: 符号链接名
+///            target: 符号链接指向的路径
+/// @return: 操作成功，返回符号链接的inode，操作失败，返回错误码
+pub fn sysfs_create_link(
+    dir: &Arc<dyn IndexNode>,
+    name: &str,
+    target: &str,
+) -> Result<Arc<dyn IndexNode>, SystemError> {
+    dir.as_any_ref()
+        .downcast_ref::<LockedSysFSInode>()
+        .ok_or(SystemError::EINVAL)?
+        .add_link(name, target)
+}
+
+/// @brief: 删除sysfs目录下的属性文件或符号链接
+/// @parameter dir: sysfs中的目录
+///            name: 属性文件名
+/// @return: 操作成功，返回()，操作失败，返回错误码
+#[allow(dead_code)]
+pub fn sysfs_remove_file(dir: &Arc<dyn IndexNode>, name: &str) -> Result<(), SystemError> {
+    dir.as_any_ref()
+        .downcast_ref::<LockedSysFSInode>()
+        .ok_or(SystemError::EINVAL)?
+        .remove(name)
+}
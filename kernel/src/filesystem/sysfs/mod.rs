@@ -1,8 +1,13 @@
 use super::vfs::{
-    core::generate_inode_id, file::FileMode, FileSystem, FileType, FsInfo, IndexNode, Metadata,
-    PollStatus,
+    core::generate_inode_id,
+    dcache::dcache,
+    file::FileMode,
+    mount::{mount_list, MountFlags, MountRecord},
+    seq_file::{seq_read, seq_write, SeqFileState, SeqOperations},
+    FilePrivateData, FileSystem, FileType, FsInfo, IndexNode, Metadata, PollStatus,
 };
 use crate::{
+    arch::MMArch,
     driver::base::platform::platform_bus_init,
     filesystem::{sysfs::bus::sys_bus_init, vfs::ROOT_INODE},
     kdebug, kinfo,
@@ -10,6 +15,7 @@ use crate::{
         once::Once,
         spinlock::{SpinLock, SpinLockGuard},
     },
+    mm::MemoryManagementArch,
     syscall::SystemError,
     time::TimeSpec,
 };
@@ -25,9 +31,12 @@ use core::ptr::null_mut;
 pub mod bus;
 pub mod class;
 pub mod devices;
+pub mod file;
 pub mod fs;
 
 const SYSFS_MAX_NAMELEN: usize = 64;
+/// SysFS的魔数（与Linux相同）
+pub const SYSFS_MAGIC: u64 = 0x62656572;
 
 static mut __SYS_DEVICES_INODE: *mut Arc<dyn IndexNode> = null_mut();
 static mut __SYS_BUS_INODE: *mut Arc<dyn IndexNode> = null_mut();
@@ -90,6 +99,9 @@ impl FileSystem for SysFS {
         return FsInfo {
             blk_dev_id: 0,
             max_name_len: SYSFS_MAX_NAMELEN,
+            magic: SYSFS_MAGIC,
+            block_size: MMArch::PAGE_SIZE,
+            ..Default::default()
         };
     }
 }
@@ -158,9 +170,13 @@ impl IndexNode for LockedSysFSInode {
 
     fn open(
         &self,
-        _data: &mut super::vfs::FilePrivateData,
+        data: &mut super::vfs::FilePrivateData,
         _mode: &FileMode,
     ) -> Result<(), SystemError> {
+        // 属性文件的内容在读取时才生成
+        if self.0.lock().attr.is_some() {
+            *data = FilePrivateData::Seq(SeqFileState::new());
+        }
         return Ok(());
     }
 
@@ -170,22 +186,53 @@ impl IndexNode for LockedSysFSInode {
 
     fn read_at(
         &self,
-        _offset: usize,
-        _len: usize,
-        _buf: &mut [u8],
-        _data: &mut super::vfs::FilePrivateData,
+        offset: usize,
+        len: usize,
+        buf: &mut [u8],
+        data: &mut super::vfs::FilePrivateData,
     ) -> Result<usize, SystemError> {
-        return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+        let guard: SpinLockGuard<SysFSInode> = self.0.lock();
+        if guard.metadata.file_type == FileType::Dir {
+            return Err(SystemError::EISDIR);
+        }
+        // 符号链接的内容就是其指向的路径（read_link不会打开文件）
+        if let Some(target) = guard.link_target.as_ref() {
+            let start = target.len().min(offset);
+            let end = target.len().min(offset + len);
+            let src = &target.as_bytes()[start..end];
+            buf[0..src.len()].copy_from_slice(src);
+            return Ok(src.len());
+        }
+        let attr: Arc<dyn SeqOperations> = guard
+            .attr
+            .clone()
+            .ok_or(SystemError::EOPNOTSUPP_OR_ENOTSUP)?;
+        // 释放锁之后再调用show回调，因为回调中可能会访问sysfs
+        drop(guard);
+
+        match data {
+            FilePrivateData::Seq(state) => return seq_read(attr.as_ref(), state, offset, len, buf),
+            _ => return Err(SystemError::EINVAL),
+        }
     }
 
     fn write_at(
         &self,
         _offset: usize,
-        _len: usize,
-        _buf: &[u8],
+        len: usize,
+        buf: &[u8],
         _data: &mut super::vfs::FilePrivateData,
     ) -> Result<usize, SystemError> {
-        return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+        if buf.len() < len {
+            return Err(SystemError::EINVAL);
+        }
+        let attr: Arc<dyn SeqOperations> = self
+            .0
+            .lock()
+            .attr
+            .clone()
+            .ok_or(SystemError::EOPNOTSUPP_OR_ENOTSUP)?;
+        return seq_write(attr.as_ref(), &buf[0..len]);
     }
 
     fn poll(&self) -> Result<super::vfs::PollStatus, SystemError> {
@@ -310,6 +357,7 @@ impl LockedSysFSInode {
             metadata: Metadata {
                 dev_id: 0,
                 inode_id: generate_inode_id(),
+                disk_ino: None,
                 size: 0,
                 blk_size: 0,
                 blocks: 0,
@@ -324,6 +372,8 @@ impl LockedSysFSInode {
                 raw_dev: _data,
             },
             fs: guard.fs.clone(),
+            attr: None,
+            link_target: None,
         })));
 
         // 初始化inode的自引用的weak指针
@@ -331,6 +381,7 @@ impl LockedSysFSInode {
 
         // 将子inode插入父inode的B树中
         guard.children.insert(String::from(_name), result.clone());
+        dcache().invalidate_dir(guard.metadata.inode_id);
         return Ok(result);
     }
 
@@ -354,6 +405,50 @@ impl LockedSysFSInode {
         };
     }
 
+    /// @brief 在当前目录下，创建一个属性文件
+    /// @param name: 文件名
+    /// @param mode: 文件的权限
+    /// @param ops: 属性文件的操作
+    /// @return 成功返回属性文件的inode, 失败返回Err(错误码)
+    pub fn add_attr(
+        &self,
+        name: &str,
+        mode: u32,
+        ops: Arc<dyn SeqOperations>,
+    ) -> Result<Arc<dyn IndexNode>, SystemError> {
+        let guard: SpinLockGuard<SysFSInode> = self.0.lock();
+        let inode: Arc<dyn IndexNode> =
+            self.do_create_with_data(guard, name, FileType::File, mode, 0)?;
+        inode
+            .as_any_ref()
+            .downcast_ref::<LockedSysFSInode>()
+            .unwrap()
+            .0
+            .lock()
+            .attr = Some(ops);
+        return Ok(inode);
+    }
+
+    /// @brief 在当前目录下，创建一个符号链接
+    /// @param name: 符号链接的名称
+    /// @param target: 符号链接指向的路径
+    /// @return 成功返回符号链接的inode, 失败返回Err(错误码)
+    pub fn add_link(&self, name: &str, target: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+        let guard: SpinLockGuard<SysFSInode> = self.0.lock();
+        let inode: Arc<dyn IndexNode> =
+            self.do_create_with_data(guard, name, FileType::SymLink, 0o777, 0)?;
+        let mut link_guard: SpinLockGuard<SysFSInode> = inode
+            .as_any_ref()
+            .downcast_ref::<LockedSysFSInode>()
+            .unwrap()
+            .0
+            .lock();
+        link_guard.metadata.size = target.len() as i64;
+        link_guard.link_target = Some(String::from(target));
+        drop(link_guard);
+        return Ok(inode);
+    }
+
     /// @brief 在当前目录下，创建一个二进制文件
     /// @param name: 文件名
     /// @return 成功返回Ok(()), 失败返回Err(错误码)
@@ -367,6 +462,7 @@ impl LockedSysFSInode {
         }
 
         this.children.insert(name.to_string(), file);
+        dcache().invalidate_dir(this.metadata.inode_id);
         return Ok(());
     }
 
@@ -387,12 +483,10 @@ impl LockedSysFSInode {
     }
 
     pub fn remove(&self, name: &str) -> Result<(), SystemError> {
-        let x = self
-            .0
-            .lock()
-            .children
-            .remove(name)
-            .ok_or(SystemError::ENOENT)?;
+        let mut guard = self.0.lock();
+        let x = guard.children.remove(name).ok_or(SystemError::ENOENT)?;
+        dcache().invalidate_dir(guard.metadata.inode_id);
+        drop(guard);
 
         drop(x);
         return Ok(());
@@ -412,6 +506,10 @@ pub struct SysFSInode {
     fs: Weak<SysFS>,
     /// INode 元数据
     metadata: Metadata,
+    /// 属性文件的操作
+    attr: Option<Arc<dyn SeqOperations>>,
+    /// 符号链接指向的路径
+    link_target: Option<String>,
 }
 
 impl SysFSInode {
@@ -432,6 +530,7 @@ impl SysFSInode {
             metadata: Metadata {
                 dev_id: 1,
                 inode_id: generate_inode_id(),
+                disk_ino: None,
                 size: 0,
                 blk_size: 0,
                 blocks: 0,
@@ -446,6 +545,8 @@ impl SysFSInode {
                 raw_dev: data_,
             },
             fs: Weak::default(),
+            attr: None,
+            link_target: None,
         };
     }
 }
@@ -459,11 +560,18 @@ pub fn sysfs_init() -> Result<(), SystemError> {
         let sysfs: Arc<SysFS> = SysFS::new();
 
         // sysfs 挂载
-        let _t = ROOT_INODE()
+        let mount_fs = ROOT_INODE()
             .find("sys")
             .expect("Cannot find /sys")
             .mount(sysfs)
             .expect("Failed to mount sysfs");
+        mount_list().insert(MountRecord::new(
+            "sysfs",
+            "/sys",
+            "sysfs",
+            MountFlags::empty(),
+            mount_fs,
+        ));
         kinfo!("SysFS mounted.");
 
         // 初始化platform总线
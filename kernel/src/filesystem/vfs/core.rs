@@ -4,27 +4,46 @@ use core::{
     sync::atomic::{AtomicUsize, Ordering},
 };
 
-use alloc::{boxed::Box, format, string::ToString, sync::Arc};
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    sync::{Arc, Weak},
+    vec::Vec,
+};
 
 use crate::{
+    arch::asm::current::current_pcb,
     driver::{
         base::block::disk_info::Partition,
         disk::ahci::{self},
     },
     filesystem::{
-        devfs::devfs_init,
+        devfs::{devfs_init, DevFS},
+        exfat::{boot::ExFatBootSector, fs::ExFatFileSystem},
+        ext2::{fs::Ext2FileSystem, superblock::Ext2SuperBlock},
         fat::fs::FATFileSystem,
-        procfs::procfs_init,
-        ramfs::RamFS,
-        sysfs::sysfs_init,
-        vfs::{mount::MountFS, FileSystem, FileType},
+        procfs::{procfs_init, ProcFS},
+        ramfs::{tmpfs::tmpfs_new, RamFS},
+        sysfs::{sysfs_init, SysFS},
+        vfs::{
+            mount::{mount_list, MountFS, MountFlags, MountRecord, UmountFlags},
+            FileSystem, FileType,
+        },
     },
-    include::bindings::bindings::PAGE_4K_SIZE,
+    include::bindings::bindings::AT_FDCWD,
     kdebug, kerror, kinfo,
+    libs::{mutex::Mutex, spinlock::SpinLock},
+    mm::page_cache::page_cache_sync_all,
     syscall::SystemError,
 };
 
-use super::{file::FileMode, utils::rsplit_path, IndexNode, InodeId};
+use super::{
+    file::FileMode,
+    permission::{apply_umask, init_inode_owner, may_delete, may_modify_dir},
+    utils::{normalize_path, user_path_at, user_path_string_at},
+    IndexNode, InodeId, RenameFlags,
+};
 
 /// @brief 原子地生成新的Inode号。
 /// 请注意，所有的inode号都需要通过该函数来生成.全局的inode号，除了以下两个特殊的以外，都是唯一的
@@ -53,6 +72,13 @@ pub extern "C" fn vfs_init() -> i32 {
     let ramfs = RamFS::new();
     let mount_fs = MountFS::new(ramfs, None);
     let root_inode = Box::leak(Box::new(mount_fs.root_inode()));
+    mount_list().insert(MountRecord::new(
+        "rootfs",
+        "/",
+        "ramfs",
+        MountFlags::empty(),
+        mount_fs,
+    ));
 
     unsafe {
         __ROOT_INODE = root_inode;
@@ -76,6 +102,9 @@ pub extern "C" fn vfs_init() -> i32 {
 
     sysfs_init().expect("Failed to initialize sysfs");
 
+    tmpfs_mount_init(root_inode, "tmp", "mode=1777").expect("Failed to mount tmpfs on /tmp");
+    tmpfs_mount_init(root_inode, "run", "mode=755").expect("Failed to mount tmpfs on /run");
+
     let root_inode = ROOT_INODE().list().expect("VFS init failed");
     if root_inode.len() > 0 {
         kinfo!("Successfully initialized VFS!");
@@ -83,6 +112,28 @@ pub extern "C" fn vfs_init() -> i32 {
     return 0;
 }
 
+/// @brief 在根目录下创建挂载点，并在其上挂载一个tmpfs
+///
+/// @param root_inode 根目录的inode
+/// @param name 挂载点在根目录下的名称
+/// @param data tmpfs的挂载参数
+fn tmpfs_mount_init(
+    root_inode: &Arc<dyn IndexNode>,
+    name: &str,
+    data: &str,
+) -> Result<(), SystemError> {
+    let mountpoint: Arc<dyn IndexNode> = root_inode.create(name, FileType::Dir, 0o777)?;
+    let mount_fs: Arc<MountFS> = mountpoint.mount(tmpfs_new(data)?)?;
+    mount_list().insert(MountRecord::new(
+        "tmpfs",
+        &format!("/{name}"),
+        "tmpfs",
+        MountFlags::NOSUID | MountFlags::NODEV,
+        mount_fs,
+    ));
+    return Ok(());
+}
+
 /// @brief 真正执行伪文件系统迁移的过程
 ///
 /// @param mountpoint_name 在根目录下的挂载点的名称
@@ -101,15 +152,30 @@ fn do_migrate(
         r.unwrap()
     };
     // 迁移挂载点
-    mountpoint
+    let new_mount_fs: Arc<MountFS> = mountpoint
         .mount(fs.inner_filesystem())
         .expect(format!("Failed to migrate {mountpoint_name} ").as_str());
+    // 更新挂载表
+    if let Some(mut record) = mount_list().find_by_fs(fs) {
+        record.mount_fs = new_mount_fs;
+        mount_list().insert(record);
+    }
     return Ok(());
 }
 
 /// @brief 迁移伪文件系统的inode
 /// 请注意，为了避免删掉了伪文件系统内的信息，因此没有在原root inode那里调用unlink.
-fn migrate_virtual_filesystem(new_fs: Arc<dyn FileSystem>) -> Result<(), SystemError> {
+///
+/// @param new_fs 新的根文件系统
+/// @param source 新的根文件系统所在的设备（用于记录在挂载表中）
+/// @param fstype 新的根文件系统的类型名（用于记录在挂载表中）
+/// @param flags 新的根文件系统的挂载标志（用于记录在挂载表中）
+fn migrate_virtual_filesystem(
+    new_fs: Arc<dyn FileSystem>,
+    source: &str,
+    fstype: &str,
+    flags: MountFlags,
+) -> Result<(), SystemError> {
     kinfo!("VFS: Migrating filesystems...");
 
     // ==== 在这里获取要被迁移的文件系统的inode ===
@@ -119,15 +185,22 @@ fn migrate_virtual_filesystem(new_fs: Arc<dyn FileSystem>) -> Result<(), SystemE
     let dev: &MountFS = binding.as_any_ref().downcast_ref::<MountFS>().unwrap();
     let binding = ROOT_INODE().find("sys").expect("SysFs not mounted!").fs();
     let sys: &MountFS = binding.as_any_ref().downcast_ref::<MountFS>().unwrap();
+    let binding = ROOT_INODE().find("tmp").expect("/tmp not mounted!").fs();
+    let tmp: &MountFS = binding.as_any_ref().downcast_ref::<MountFS>().unwrap();
+    let binding = ROOT_INODE().find("run").expect("/run not mounted!").fs();
+    let run: &MountFS = binding.as_any_ref().downcast_ref::<MountFS>().unwrap();
 
     let new_fs = MountFS::new(new_fs, None);
     // 获取新的根文件系统的根节点的引用
     let new_root_inode = Box::leak(Box::new(new_fs.root_inode()));
+    mount_list().insert(MountRecord::new(source, "/", fstype, flags, new_fs.clone()));
 
     // 把上述文件系统,迁移到新的文件系统下
     do_migrate(new_root_inode.clone(), "proc", proc)?;
     do_migrate(new_root_inode.clone(), "dev", dev)?;
     do_migrate(new_root_inode.clone(), "sys", sys)?;
+    do_migrate(new_root_inode.clone(), "tmp", tmp)?;
+    do_migrate(new_root_inode.clone(), "run", run)?;
 
     unsafe {
         // drop旧的Root inode
@@ -146,7 +219,7 @@ fn migrate_virtual_filesystem(new_fs: Arc<dyn FileSystem>) -> Result<(), SystemE
 
 #[no_mangle]
 pub extern "C" fn mount_root_fs() -> i32 {
-    kinfo!("Try to mount FAT32 as root fs...");
+    kinfo!("Try to mount root fs...");
     let partiton: Arc<Partition> = ahci::get_disks_by_name("ahci_disk_0".to_string())
         .unwrap()
         .0
@@ -154,49 +227,363 @@ pub extern "C" fn mount_root_fs() -> i32 {
         .partitions[0]
         .clone();
 
-    let fatfs: Result<Arc<FATFileSystem>, SystemError> = FATFileSystem::new(partiton);
-    if fatfs.is_err() {
-        kerror!(
-            "Failed to initialize fatfs, code={:?}",
-            fatfs.as_ref().err()
-        );
+    let r = probe_filesystem(partiton);
+    if r.is_err() {
+        kerror!("Failed to initialize root fs, code={:?}", r.as_ref().err());
         loop {
             spin_loop();
         }
     }
-    let fatfs: Arc<FATFileSystem> = fatfs.unwrap();
-    let r = migrate_virtual_filesystem(fatfs);
+    let (rootfs, fstype) = r.unwrap();
+    register_block_fs(&rootfs);
+    // 根文件系统存在无法修复的错误时，会以只读方式挂载
+    let flags: MountFlags = fs_mount_flags(&rootfs, MountFlags::empty());
+    let r = migrate_virtual_filesystem(rootfs, "ahci_disk_0", fstype, flags);
     if r.is_err() {
-        kerror!("Failed to migrate virtual filesystem to FAT32!");
+        kerror!("Failed to migrate virtual filesystem to {fstype}!");
         loop {
             spin_loop();
         }
     }
-    kinfo!("Successfully migrate rootfs to FAT32!");
+    kinfo!("Successfully migrate rootfs to {fstype}!");
 
     return 0;
 }
 
-/// @brief 创建文件/文件夹
-pub fn do_mkdir(path: &str, _mode: FileMode) -> Result<u64, SystemError> {
-    // 文件名过长
-    if path.len() > PAGE_4K_SIZE as usize {
-        return Err(SystemError::ENAMETOOLONG);
+/// @brief 根据分区上的引导扇区，判断分区上的文件系统类型，并创建文件系统实例
+///
+/// @return Ok((文件系统实例, 规范的类型名))
+fn probe_filesystem(
+    partition: Arc<Partition>,
+) -> Result<(Arc<dyn FileSystem>, &'static str), SystemError> {
+    if ExFatBootSector::probe(&partition)? {
+        return Ok((ExFatFileSystem::new(partition)?, "exfat"));
+    }
+    if Ext2SuperBlock::probe(&partition)? {
+        return Ok((Ext2FileSystem::new(partition)?, "ext2"));
+    }
+    return Ok((FATFileSystem::new(partition)?, "vfat"));
+}
+
+/// @brief 根据文件系统实例的状态，调整挂载标志
+///
+/// FAT文件系统在挂载时发现了无法修复的错误，ext2文件系统具有不支持的只读兼容特性时，会以只读方式挂载
+fn fs_mount_flags(fs: &Arc<dyn FileSystem>, flags: MountFlags) -> MountFlags {
+    let any = fs.as_any_ref();
+    let read_only: bool = match (
+        any.downcast_ref::<FATFileSystem>(),
+        any.downcast_ref::<Ext2FileSystem>(),
+    ) {
+        (Some(fat), _) => fat.is_read_only(),
+        (_, Some(ext2)) => ext2.is_read_only(),
+        _ => false,
+    };
+    if read_only {
+        return flags | MountFlags::RDONLY;
+    }
+    return flags;
+}
+
+/// @brief 获取块设备文件系统所在的分区。伪文件系统返回None
+fn fs_partition(fs: &Arc<dyn FileSystem>) -> Option<Arc<Partition>> {
+    let any = fs.as_any_ref();
+    if let Some(fat) = any.downcast_ref::<FATFileSystem>() {
+        return Some(fat.partition.clone());
+    }
+    if let Some(exfat) = any.downcast_ref::<ExFatFileSystem>() {
+        return Some(exfat.partition.clone());
+    }
+    if let Some(ext2) = any.downcast_ref::<Ext2FileSystem>() {
+        return Some(ext2.partition.clone());
+    }
+    return None;
+}
+
+/// 挂载、卸载操作的全局锁
+static MOUNT_LOCK: Mutex<()> = Mutex::new(());
+
+lazy_static! {
+    /// 系统中所有块设备文件系统的实例（包括已经被惰性卸载、但仍在被使用的实例）
+    static ref BLOCK_FS_INSTANCES: SpinLock<Vec<Weak<dyn FileSystem>>> = SpinLock::new(Vec::new());
+}
+
+/// @brief 记录一个新创建的文件系统实例。伪文件系统不需要记录
+fn register_block_fs(fs: &Arc<dyn FileSystem>) {
+    if fs_partition(fs).is_some() {
+        let mut guard = BLOCK_FS_INSTANCES.lock();
+        guard.retain(|weak| weak.strong_count() > 0);
+        guard.push(Arc::downgrade(fs));
+    }
+}
+
+/// @brief 判断分区上是否已经存在一个文件系统实例
+///
+/// 同一个分区上的两个实例会各自缓存FAT表、位图等元数据，交替写入会损坏磁盘上的文件系统
+fn partition_in_use(partition: &Arc<Partition>) -> bool {
+    return BLOCK_FS_INSTANCES.lock().iter().any(|weak| {
+        weak.upgrade()
+            .and_then(|fs| fs_partition(&fs))
+            .map_or(false, |p| Arc::ptr_eq(&p, partition))
+    });
+}
+
+/// @brief 根据设备名称，获取对应的磁盘分区
+///
+/// 设备名称的格式为：[/dev/]<磁盘名>[p<分区号>]，例如ahci_disk_0p1。
+/// 分区号从1开始，省略分区号时，使用磁盘上的第一个分区。
+fn get_partition_by_name(source: &str) -> Result<Arc<Partition>, SystemError> {
+    let name: &str = source.trim_start_matches("/dev/");
+    if name.is_empty() {
+        return Err(SystemError::ENOENT);
+    }
+
+    // 分离磁盘名和分区号
+    let (disk_name, part_idx): (&str, usize) = match name.rfind('p') {
+        Some(pos)
+            if pos + 1 < name.len()
+                && name[pos + 1..].bytes().all(|c| c.is_ascii_digit())
+                && name[..pos].ends_with(|c: char| c.is_ascii_digit()) =>
+        {
+            let idx: usize = name[pos + 1..].parse().map_err(|_| SystemError::EINVAL)?;
+            if idx == 0 {
+                return Err(SystemError::ENXIO);
+            }
+            (&name[..pos], idx - 1)
+        }
+        _ => (name, 0),
+    };
+
+    let disk = ahci::get_disks_by_name(disk_name.to_string())?;
+    let guard = disk.0.lock();
+    return guard
+        .partitions
+        .get(part_idx)
+        .cloned()
+        .ok_or(SystemError::ENXIO);
+}
+
+/// @brief 获取一个尚未被挂载的分区
+///
+/// @return Err(EBUSY) 分区上的文件系统已经被挂载（或者被惰性卸载后仍在使用）
+fn get_unused_partition(source: &str) -> Result<Arc<Partition>, SystemError> {
+    let partition: Arc<Partition> = get_partition_by_name(source)?;
+    if partition_in_use(&partition) {
+        return Err(SystemError::EBUSY);
+    }
+    return Ok(partition);
+}
+
+/// @brief 根据文件系统的类型名，创建（或者获取）一个文件系统实例
+///
+/// procfs、devfs、sysfs在系统中只有一个实例，如果它们已经被挂载，那么返回已有的实例。
+/// 块设备上的文件系统，同一个分区只能被挂载一次。
+///
+/// @param fstype 文件系统的类型名
+/// @param source 被挂载的设备
+/// @param data 文件系统相关的挂载参数
+///
+/// @return Ok((文件系统实例, 规范的类型名))
+/// @return Err(ENODEV) 不支持这种类型的文件系统
+/// @return Err(EBUSY) 分区已经被挂载
+fn get_filesystem_by_type(
+    fstype: &str,
+    source: &str,
+    data: &str,
+) -> Result<(Arc<dyn FileSystem>, &'static str), SystemError> {
+    match fstype {
+        "vfat" | "fat" | "fat32" => {
+            let partition: Arc<Partition> = get_unused_partition(source)?;
+            return Ok((FATFileSystem::new(partition)?, "vfat"));
+        }
+        "exfat" => {
+            let partition: Arc<Partition> = get_unused_partition(source)?;
+            return Ok((ExFatFileSystem::new(partition)?, "exfat"));
+        }
+        "ext2" => {
+            let partition: Arc<Partition> = get_unused_partition(source)?;
+            return Ok((Ext2FileSystem::new(partition)?, "ext2"));
+        }
+        // 根据分区上的引导扇区，自动识别文件系统的类型
+        "auto" => {
+            let partition: Arc<Partition> = get_unused_partition(source)?;
+            return probe_filesystem(partition);
+        }
+        "ramfs" => {
+            return Ok((RamFS::new(), "ramfs"));
+        }
+        "tmpfs" => {
+            return Ok((tmpfs_new(data)?, "tmpfs"));
+        }
+        "proc" | "procfs" => {
+            let fs: Arc<dyn FileSystem> = match mount_list().find_by_type("proc") {
+                Some(r) => r.mount_fs.inner_filesystem(),
+                None => ProcFS::new(),
+            };
+            return Ok((fs, "proc"));
+        }
+        "devfs" | "devtmpfs" => {
+            let fs: Arc<dyn FileSystem> = match mount_list().find_by_type("devfs") {
+                Some(r) => r.mount_fs.inner_filesystem(),
+                None => DevFS::new(),
+            };
+            return Ok((fs, "devfs"));
+        }
+        "sysfs" => {
+            let fs: Arc<dyn FileSystem> = match mount_list().find_by_type("sysfs") {
+                Some(r) => r.mount_fs.inner_filesystem(),
+                None => SysFS::new(),
+            };
+            return Ok((fs, "sysfs"));
+        }
+        _ => return Err(SystemError::ENODEV),
+    }
+}
+
+/// @brief 挂载文件系统
+///
+/// @param source 被挂载的设备。对于伪文件系统，这个参数只会被记录在挂载表中
+/// @param target 挂载点的路径
+/// @param fstype 文件系统的类型名
+/// @param flags 挂载标志
+/// @param data 文件系统相关的挂载参数
+///
+/// @return Err(EPERM) 当前进程不是超级用户
+pub fn do_mount(
+    source: &str,
+    target: &str,
+    fstype: &str,
+    flags: MountFlags,
+    data: &str,
+) -> Result<u64, SystemError> {
+    if !current_pcb().cred().is_root() {
+        return Err(SystemError::EPERM);
+    }
+    // todo: 支持重新挂载、绑定挂载
+    if flags.intersects(MountFlags::REMOUNT | MountFlags::BIND) {
+        return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+    }
+
+    let _guard = MOUNT_LOCK.lock();
+
+    let (base, target) = user_path_at(AT_FDCWD, target)?;
+    let mountpoint: Arc<dyn IndexNode> = base.lookup(target)?;
+    if mountpoint.metadata()?.file_type != FileType::Dir {
+        return Err(SystemError::ENOTDIR);
+    }
+
+    // 挂载点已经是某个文件系统的根目录（包括根文件系统），暂不支持在其上叠加挂载
+    let mnt_fs: Arc<dyn FileSystem> = mountpoint.fs();
+    if let Some(mnt_fs) = mnt_fs.as_any_ref().downcast_ref::<MountFS>() {
+        if mnt_fs.is_root_inode(&mountpoint)? {
+            return Err(SystemError::EBUSY);
+        }
+    }
+
+    let (fs, fstype): (Arc<dyn FileSystem>, &str) = get_filesystem_by_type(fstype, source, data)?;
+    register_block_fs(&fs);
+    let flags: MountFlags = fs_mount_flags(&fs, flags);
+    let new_mount_fs: Arc<MountFS> = mountpoint.mount(fs)?;
+
+    let source = if source.is_empty() { fstype } else { source };
+    let target: String =
+        user_path_string_at(AT_FDCWD, target).unwrap_or_else(|| normalize_path(target));
+    mount_list().insert(MountRecord::new(
+        source,
+        &target,
+        fstype,
+        flags,
+        new_mount_fs,
+    ));
+    return Ok(0);
+}
+
+/// @brief 卸载文件系统
+///
+/// @param target 要卸载的文件系统的挂载点
+/// @param flags 卸载标志
+///
+/// @return Err(EINVAL) target不是一个挂载点
+/// @return Err(EBUSY) 文件系统正在被使用（并且没有指定UmountFlags::DETACH）
+/// @return Err(EPERM) 当前进程不是超级用户
+pub fn do_umount2(target: &str, flags: UmountFlags) -> Result<u64, SystemError> {
+    if !current_pcb().cred().is_root() {
+        return Err(SystemError::EPERM);
+    }
+    if flags.contains(UmountFlags::EXPIRE) {
+        if flags.intersects(UmountFlags::FORCE | UmountFlags::DETACH) {
+            return Err(SystemError::EINVAL);
+        }
+        // todo: 支持MNT_EXPIRE
+        return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+    }
+    // todo: 支持MNT_FORCE。目前没有文件系统能够中止正在进行的操作，为了不让调用者误以为强制卸载生效，直接拒绝
+    if flags.contains(UmountFlags::FORCE) {
+        return Err(SystemError::EINVAL);
     }
 
-    let inode: Result<Arc<dyn IndexNode>, SystemError> = ROOT_INODE().lookup(path);
+    let _guard = MOUNT_LOCK.lock();
+
+    let (base, target) = user_path_at(AT_FDCWD, target)?;
+    let inode: Arc<dyn IndexNode> = if flags.contains(UmountFlags::NOFOLLOW) {
+        base.lookup_nofollow(target)?
+    } else {
+        base.lookup(target)?
+    };
+
+    // target必须是一个文件系统的根目录
+    let fs: Arc<dyn FileSystem> = inode.fs();
+    let mount_fs: &MountFS = fs
+        .as_any_ref()
+        .downcast_ref::<MountFS>()
+        .ok_or(SystemError::EINVAL)?;
+    if !mount_fs.is_root_inode(&inode)? {
+        return Err(SystemError::EINVAL);
+    }
+    let record: MountRecord = mount_list()
+        .find_by_fs(mount_fs)
+        .ok_or(SystemError::EINVAL)?;
+    drop(inode);
+    drop(fs);
+
+    if !flags.contains(UmountFlags::DETACH) {
+        if record.mount_fs.has_submounts() {
+            return Err(SystemError::EBUSY);
+        }
+        // 被打开的文件、文件映射、进程的当前工作目录都持有活跃引用，此时文件系统正在被使用
+        if record.mount_fs.active_count() > 0 {
+            return Err(SystemError::EBUSY);
+        }
+    }
+
+    // 把页缓存中的脏页写回，避免文件系统被释放时丢失数据
+    page_cache_sync_all()?;
+
+    record.mount_fs.umount()?;
+    // 惰性卸载时，挂载在它之下的文件系统也随之不可见了
+    mount_list().remove_subtree(&record.mount_fs);
+    return Ok(0);
+}
+
+/// @brief 创建文件夹
+///
+/// @param dirfd 解析相对路径时的起始目录的文件描述符（可以为AT_FDCWD）
+/// @param path 文件夹的路径
+/// @param mode 新文件夹的权限位（会被umask屏蔽）
+pub fn do_mkdir_at(dirfd: i32, path: &str, mode: u32) -> Result<u64, SystemError> {
+    let (base, path) = user_path_at(dirfd, path)?;
+    let inode: Result<Arc<dyn IndexNode>, SystemError> = base.lookup(path);
 
     if inode.is_err() {
         let errno = inode.unwrap_err();
         // 文件不存在，且需要创建
         if errno == SystemError::ENOENT {
-            let (filename, parent_path) = rsplit_path(path);
             // 查找父目录
-            let parent_inode: Arc<dyn IndexNode> =
-                ROOT_INODE().lookup(parent_path.unwrap_or("/"))?;
+            let (parent_inode, filename) = base.lookup_parent(path)?;
+            may_modify_dir(&parent_inode)?;
             // 创建文件夹
-            let _create_inode: Arc<dyn IndexNode> =
-                parent_inode.create(filename, FileType::Dir, 0o777)?;
+            let create_inode: Arc<dyn IndexNode> =
+                parent_inode.create(filename, FileType::Dir, apply_umask(mode))?;
+            init_inode_owner(&create_inode)?;
         } else {
             // 不需要创建文件，因此返回错误码
             return Err(errno);
@@ -207,13 +594,12 @@ pub fn do_mkdir(path: &str, _mode: FileMode) -> Result<u64, SystemError> {
 }
 
 /// @brief 删除文件夹
-pub fn do_remove_dir(path: &str) -> Result<u64, SystemError> {
-    // 文件名过长
-    if path.len() > PAGE_4K_SIZE as usize {
-        return Err(SystemError::ENAMETOOLONG);
-    }
-
-    let inode: Result<Arc<dyn IndexNode>, SystemError> = ROOT_INODE().lookup(path);
+///
+/// @param dirfd 解析相对路径时的起始目录的文件描述符（可以为AT_FDCWD）
+/// @param path 文件夹的路径
+pub fn do_remove_dir(dirfd: i32, path: &str) -> Result<u64, SystemError> {
+    let (base, path) = user_path_at(dirfd, path)?;
+    let inode: Result<Arc<dyn IndexNode>, SystemError> = base.lookup_nofollow(path);
 
     if inode.is_err() {
         let errno = inode.unwrap_err();
@@ -223,19 +609,16 @@ pub fn do_remove_dir(path: &str) -> Result<u64, SystemError> {
         }
     }
 
-    let (filename, parent_path) = rsplit_path(path);
     // 查找父目录
-    let parent_inode: Arc<dyn IndexNode> = ROOT_INODE().lookup(parent_path.unwrap_or("/"))?;
-
-    if parent_inode.metadata()?.file_type != FileType::Dir {
-        return Err(SystemError::ENOTDIR);
-    }
+    let (parent_inode, filename) = base.lookup_parent(path)?;
 
     let target_inode: Arc<dyn IndexNode> = parent_inode.find(filename)?;
     if target_inode.metadata()?.file_type != FileType::Dir {
         return Err(SystemError::ENOTDIR);
     }
 
+    may_delete(&parent_inode, &target_inode)?;
+
     // 删除文件夹
     parent_inode.rmdir(filename)?;
 
@@ -243,13 +626,13 @@ pub fn do_remove_dir(path: &str) -> Result<u64, SystemError> {
 }
 
 /// @brief 删除文件
-pub fn do_unlink_at(path: &str, _mode: FileMode) -> Result<u64, SystemError> {
-    // 文件名过长
-    if path.len() > PAGE_4K_SIZE as usize {
-        return Err(SystemError::ENAMETOOLONG);
-    }
-
-    let inode: Result<Arc<dyn IndexNode>, SystemError> = ROOT_INODE().lookup(path);
+///
+/// @param dirfd 解析相对路径时的起始目录的文件描述符（可以为AT_FDCWD）
+/// @param path 文件的路径
+pub fn do_unlink_at(dirfd: i32, path: &str, _mode: FileMode) -> Result<u64, SystemError> {
+    let (base, path) = user_path_at(dirfd, path)?;
+    // 删除的是目录项本身，因此不跟随路径最后一级的符号链接
+    let inode: Result<Arc<dyn IndexNode>, SystemError> = base.lookup_nofollow(path);
 
     if inode.is_err() {
         let errno = inode.clone().unwrap_err();
@@ -258,21 +641,217 @@ pub fn do_unlink_at(path: &str, _mode: FileMode) -> Result<u64, SystemError> {
             return Err(SystemError::ENOENT);
         }
     }
+    let inode: Arc<dyn IndexNode> = inode?;
     // 禁止在目录上unlink
-    if inode.unwrap().metadata()?.file_type == FileType::Dir {
+    if inode.metadata()?.file_type == FileType::Dir {
         return Err(SystemError::EPERM);
     }
 
-    let (filename, parent_path) = rsplit_path(path);
     // 查找父目录
-    let parent_inode: Arc<dyn IndexNode> = ROOT_INODE().lookup(parent_path.unwrap_or("/"))?;
-
-    if parent_inode.metadata()?.file_type != FileType::Dir {
-        return Err(SystemError::ENOTDIR);
-    }
+    let (parent_inode, filename) = base.lookup_parent(path)?;
+    may_delete(&parent_inode, &inode)?;
 
     // 删除文件
     parent_inode.unlink(filename)?;
 
     return Ok(0);
 }
+
+/// 重命名操作的全局锁。
+///
+/// 跨目录的重命名需要同时对源目录、目标目录加锁，并且需要检查目录之间的祖先关系，
+/// 因此同一时刻只允许一个重命名操作进行，以免出现死锁或者把目录移动到自己的子目录下。
+static RENAME_LOCK: Mutex<()> = Mutex::new(());
+
+/// @brief 重命名文件/文件夹
+///
+/// @param old_dirfd 解析oldpath时的起始目录的文件描述符（可以为AT_FDCWD）
+/// @param oldpath 原来的路径
+/// @param new_dirfd 解析newpath时的起始目录的文件描述符（可以为AT_FDCWD）
+/// @param newpath 新的路径
+/// @param flags 标志位，见RenameFlags
+pub fn do_rename_at(
+    old_dirfd: i32,
+    oldpath: &str,
+    new_dirfd: i32,
+    newpath: &str,
+    flags: RenameFlags,
+) -> Result<u64, SystemError> {
+    if flags.contains(RenameFlags::NOREPLACE | RenameFlags::EXCHANGE) {
+        return Err(SystemError::EINVAL);
+    }
+    // todo: 支持RENAME_WHITEOUT
+    if flags.contains(RenameFlags::WHITEOUT) {
+        return Err(SystemError::EINVAL);
+    }
+
+    let (old_base, oldpath) = user_path_at(old_dirfd, oldpath)?;
+    let (new_base, newpath) = user_path_at(new_dirfd, newpath)?;
+
+    let (old_parent, old_name) = old_base.lookup_parent(oldpath)?;
+    let (new_parent, new_name) = new_base.lookup_parent(newpath)?;
+
+    if old_name.is_empty() || old_name == "." || old_name == ".." {
+        return Err(SystemError::EBUSY);
+    }
+    if new_name.is_empty() || new_name == "." || new_name == ".." {
+        return Err(SystemError::EBUSY);
+    }
+
+    // 不能跨越文件系统进行重命名
+    if Arc::as_ptr(&old_parent.fs()) as *const u8 != Arc::as_ptr(&new_parent.fs()) as *const u8 {
+        return Err(SystemError::EXDEV);
+    }
+
+    let _guard = RENAME_LOCK.lock();
+
+    // 确认要移动的目录项存在
+    let old_inode: Arc<dyn IndexNode> = old_parent.find(old_name)?;
+    let old_md = old_inode.metadata()?;
+    let new_inode: Option<Arc<dyn IndexNode>> = match new_parent.find(new_name) {
+        Ok(inode) => Some(inode),
+        Err(SystemError::ENOENT) => None,
+        Err(e) => return Err(e),
+    };
+
+    // 不能把一个目录移动到它自己的子目录下
+    if old_md.file_type == FileType::Dir && is_ancestor_of(old_md.inode_id, &new_parent)? {
+        return Err(SystemError::EINVAL);
+    }
+
+    // 要能从原目录中删除原来的目录项，并且能在目标目录中创建目录项。已经存在的目标会被替换（或者交换），因此也要能删除它
+    may_delete(&old_parent, &old_inode)?;
+    may_modify_dir(&new_parent)?;
+    if let Some(new_inode) = &new_inode {
+        may_delete(&new_parent, new_inode)?;
+    }
+
+    let same_parent: bool = old_parent.metadata()?.inode_id == new_parent.metadata()?.inode_id;
+
+    if flags.contains(RenameFlags::EXCHANGE) {
+        let new_inode: Arc<dyn IndexNode> = new_inode.ok_or(SystemError::ENOENT)?;
+        let new_md = new_inode.metadata()?;
+        if new_md.inode_id == old_md.inode_id {
+            return Ok(0);
+        }
+        if new_md.file_type == FileType::Dir && is_ancestor_of(new_md.inode_id, &old_parent)? {
+            return Err(SystemError::EINVAL);
+        }
+        // 交换由具体的文件系统原子地完成
+        old_parent.exchange(old_name, &new_parent, new_name)?;
+        return Ok(0);
+    }
+
+    if let Some(new_inode) = new_inode {
+        if flags.contains(RenameFlags::NOREPLACE) {
+            return Err(SystemError::EEXIST);
+        }
+
+        let new_md = new_inode.metadata()?;
+        if new_md.inode_id == old_md.inode_id {
+            // 在不区分大小写的文件系统中，新旧名称可能指向同一个目录项（例如foo->FOO），
+            // 这时只需要修改名称。文件夹以及只有一个硬链接的文件，只可能有一个目录项
+            let same_entry = old_md.file_type == FileType::Dir || old_md.nlinks <= 1;
+            if !(same_parent && old_name != new_name && same_entry) {
+                // 源与目标是同一个inode的两个硬链接，那么什么都不做
+                return Ok(0);
+            }
+        } else {
+            match (
+                old_md.file_type == FileType::Dir,
+                new_md.file_type == FileType::Dir,
+            ) {
+                (true, false) => return Err(SystemError::ENOTDIR),
+                (false, true) => return Err(SystemError::EISDIR),
+                _ => {}
+            }
+            // 被替换的目录是源目录自身，它一定不为空
+            if new_md.inode_id == old_parent.metadata()?.inode_id {
+                return Err(SystemError::ENOTEMPTY);
+            }
+        }
+    } else if same_parent && old_name == new_name {
+        return Ok(0);
+    }
+
+    // 已经存在的目标，由具体的文件系统在move_中原子地替换
+    old_parent.move_(old_name, &new_parent, new_name)?;
+
+    return Ok(0);
+}
+
+/// @brief 判断inode号为ancestor的目录，是否为dir自身或者dir的祖先目录
+fn is_ancestor_of(ancestor: InodeId, dir: &Arc<dyn IndexNode>) -> Result<bool, SystemError> {
+    let mut current: Arc<dyn IndexNode> = dir.clone();
+    loop {
+        let current_id = current.metadata()?.inode_id;
+        if current_id == ancestor {
+            return Ok(true);
+        }
+        let parent: Arc<dyn IndexNode> = match current.find("..") {
+            Ok(p) => p,
+            // 部分文件系统的根目录没有父目录
+            Err(SystemError::ENOENT) => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        // 到达根目录
+        if parent.metadata()?.inode_id == current_id {
+            return Ok(false);
+        }
+        current = parent;
+    }
+}
+
+/// @brief 创建硬链接
+///
+/// @param old_dirfd 解析oldpath时的起始目录的文件描述符（可以为AT_FDCWD）
+/// @param oldpath 已经存在的文件的路径
+/// @param new_dirfd 解析newpath时的起始目录的文件描述符（可以为AT_FDCWD）
+/// @param newpath 新的硬链接的路径
+/// @param follow 如果oldpath的最后一级是符号链接，是否跟随它
+pub fn do_link_at(
+    old_dirfd: i32,
+    oldpath: &str,
+    new_dirfd: i32,
+    newpath: &str,
+    follow: bool,
+) -> Result<u64, SystemError> {
+    let (old_base, oldpath) = user_path_at(old_dirfd, oldpath)?;
+    let old_inode: Arc<dyn IndexNode> = if follow {
+        old_base.lookup(oldpath)?
+    } else {
+        old_base.lookup_nofollow(oldpath)?
+    };
+
+    return do_link_inode(old_inode, new_dirfd, newpath);
+}
+
+/// @brief 在newpath处，创建一个指向inode的硬链接
+pub fn do_link_inode(
+    inode: Arc<dyn IndexNode>,
+    new_dirfd: i32,
+    newpath: &str,
+) -> Result<u64, SystemError> {
+    let metadata = inode.metadata()?;
+    // 目录不允许有硬链接
+    if metadata.file_type == FileType::Dir {
+        return Err(SystemError::EPERM);
+    }
+    // 已经被删除（但仍被打开）的文件不能再被链接回文件系统中
+    if metadata.nlinks == 0 {
+        return Err(SystemError::ENOENT);
+    }
+
+    let (new_base, newpath) = user_path_at(new_dirfd, newpath)?;
+    let (new_parent, new_name) = new_base.lookup_parent(newpath)?;
+
+    // 不能跨越文件系统创建硬链接
+    if Arc::as_ptr(&inode.fs()) as *const u8 != Arc::as_ptr(&new_parent.fs()) as *const u8 {
+        return Err(SystemError::EXDEV);
+    }
+    may_modify_dir(&new_parent)?;
+
+    new_parent.link(new_name, &inode)?;
+
+    return Ok(0);
+}
@@ -0,0 +1,212 @@
+//! 目录项缓存(dentry cache)
+//!
+//! 路径查找时，每一级目录都要调用一次下层文件系统的`find()`。对于FAT这类文件系统，
+//! 在缓存未命中时需要重新扫描目录所在的簇，开销很大。
+//!
+//! 本模块以（父目录的inode号, 目录项名称）为键，缓存查找结果：
+//! - 正向缓存：目录项存在，缓存其inode的弱引用（不会阻止已被删除的inode被释放）
+//! - 负向缓存：目录项不存在，缓存ENOENT
+//!
+//! 缓存项数量超过上限时，按LRU策略淘汰。
+//! 目录内容发生变化时（create/link/unlink/rmdir/move_/mount等），需要调用本模块的
+//! invalidate系列函数，使得相关的缓存项失效。
+
+use alloc::{
+    collections::BTreeMap,
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+
+use crate::{libs::spinlock::SpinLock, syscall::SystemError};
+
+use super::{IndexNode, InodeId};
+
+/// 目录项缓存最多容纳的缓存项数量
+pub const DCACHE_MAX_ENTRIES: usize = 4096;
+
+lazy_static! {
+    /// 全局的目录项缓存
+    static ref DENTRY_CACHE: DentryCache = DentryCache::new(DCACHE_MAX_ENTRIES);
+}
+
+/// @brief 获取全局的目录项缓存
+#[inline(always)]
+pub fn dcache() -> &'static DentryCache {
+    return &DENTRY_CACHE;
+}
+
+/// 缓存项的键：(父目录的inode号, 目录项名称)
+type DentryKey = (InodeId, String);
+
+/// @brief 一个缓存项
+#[derive(Debug)]
+struct Dentry {
+    /// 查找结果。为None时表示这是一个负向缓存项（目录项不存在）
+    inode: Option<Weak<dyn IndexNode>>,
+    /// 最近一次被访问时的时间戳（用于LRU淘汰）
+    stamp: u64,
+}
+
+#[derive(Debug)]
+struct InnerDentryCache {
+    /// 键 -> 缓存项
+    entries: BTreeMap<DentryKey, Dentry>,
+    /// 时间戳 -> 键。第一个元素就是最久未被使用的缓存项
+    lru: BTreeMap<u64, DentryKey>,
+    /// 下一个要分配的时间戳
+    next_stamp: u64,
+    /// 最大缓存项数量
+    capacity: usize,
+    /// 命中次数
+    hits: usize,
+    /// 未命中次数
+    misses: usize,
+}
+
+/// @brief 目录项缓存
+#[derive(Debug)]
+pub struct DentryCache(SpinLock<InnerDentryCache>);
+
+impl DentryCache {
+    pub fn new(capacity: usize) -> Self {
+        return Self(SpinLock::new(InnerDentryCache {
+            entries: BTreeMap::new(),
+            lru: BTreeMap::new(),
+            next_stamp: 0,
+            capacity,
+            hits: 0,
+            misses: 0,
+        }));
+    }
+
+    /// @brief 在缓存中查找目录项
+    ///
+    /// @param parent 父目录的inode号
+    /// @param name 目录项名称
+    ///
+    /// @return None 缓存未命中（若正向缓存项所指向的inode已被释放，也视为未命中）
+    /// @return Some(Ok(inode)) 命中正向缓存项
+    /// @return Some(Err(ENOENT)) 命中负向缓存项
+    pub fn lookup(
+        &self,
+        parent: InodeId,
+        name: &str,
+    ) -> Option<Result<Arc<dyn IndexNode>, SystemError>> {
+        let mut guard = self.0.lock();
+        let key: DentryKey = (parent, String::from(name));
+        let stamp = guard.next_stamp;
+
+        let (old_stamp, result) = match guard.entries.get_mut(&key) {
+            Some(dentry) => {
+                let result = match &dentry.inode {
+                    Some(weak) => weak.upgrade().ok_or(SystemError::ENOENT),
+                    None => Err(SystemError::ENOENT),
+                };
+                let old_stamp = dentry.stamp;
+                if result.is_err() && dentry.inode.is_some() {
+                    // inode已经被释放，移除这个失效的缓存项
+                    guard.entries.remove(&key);
+                    guard.lru.remove(&old_stamp);
+                    guard.misses += 1;
+                    return None;
+                }
+                dentry.stamp = stamp;
+                (old_stamp, result)
+            }
+            None => {
+                guard.misses += 1;
+                return None;
+            }
+        };
+
+        // 更新LRU信息
+        guard.lru.remove(&old_stamp);
+        guard.lru.insert(stamp, key);
+        guard.next_stamp += 1;
+        guard.hits += 1;
+
+        return Some(result);
+    }
+
+    /// @brief 向缓存中加入一个缓存项。如果该键已经存在，则覆盖原来的缓存项
+    ///
+    /// @param parent 父目录的inode号
+    /// @param name 目录项名称
+    /// @param inode 查找的结果（缓存中只保存其弱引用）。为None时表示加入负向缓存项
+    pub fn insert(&self, parent: InodeId, name: &str, inode: Option<&Arc<dyn IndexNode>>) {
+        let mut guard = self.0.lock();
+        let key: DentryKey = (parent, String::from(name));
+        let stamp = guard.next_stamp;
+        guard.next_stamp += 1;
+
+        let inode: Option<Weak<dyn IndexNode>> = inode.map(Arc::downgrade);
+        if let Some(old) = guard.entries.insert(key.clone(), Dentry { inode, stamp }) {
+            guard.lru.remove(&old.stamp);
+        }
+        guard.lru.insert(stamp, key);
+
+        // 超出容量，淘汰最久未被使用的缓存项
+        while guard.entries.len() > guard.capacity {
+            let oldest = match guard.lru.pop_first() {
+                Some((_, k)) => k,
+                None => break,
+            };
+            guard.entries.remove(&oldest);
+        }
+    }
+
+    /// @brief 使指定的缓存项失效
+    pub fn invalidate(&self, parent: InodeId, name: &str) {
+        let mut guard = self.0.lock();
+        let removed = guard.entries.remove(&(parent, String::from(name)));
+        if let Some(dentry) = &removed {
+            guard.lru.remove(&dentry.stamp);
+        }
+        drop(guard);
+        drop(removed);
+    }
+
+    /// @brief 使指定目录下的所有缓存项失效
+    ///
+    /// 由于文件系统可能对名称大小写不敏感（例如FAT），同一个目录项可能以多个名称被缓存，
+    /// 因此当目录的内容改变时，应当使用本函数令整个目录的缓存项都失效。
+    pub fn invalidate_dir(&self, parent: InodeId) {
+        let mut guard = self.0.lock();
+        let keys: Vec<DentryKey> = guard
+            .entries
+            .range((parent, String::new())..)
+            .take_while(|(k, _)| k.0 == parent)
+            .map(|(k, _)| k.clone())
+            .collect();
+
+        let mut removed: Vec<Dentry> = Vec::with_capacity(keys.len());
+        for k in keys.iter() {
+            if let Some(dentry) = guard.entries.remove(k) {
+                guard.lru.remove(&dentry.stamp);
+                removed.push(dentry);
+            }
+        }
+        drop(guard);
+        drop(removed);
+    }
+
+    /// @brief 使所有缓存项失效
+    ///
+    /// 挂载、卸载文件系统时，挂载树发生变化，需要调用本函数
+    pub fn invalidate_all(&self) {
+        let mut guard = self.0.lock();
+        let entries = core::mem::take(&mut guard.entries);
+        guard.lru.clear();
+        drop(guard);
+        drop(entries);
+    }
+
+    /// @brief 获取缓存的统计信息
+    ///
+    /// @return (缓存项数量, 命中次数, 未命中次数)
+    pub fn stat(&self) -> (usize, usize, usize) {
+        let guard = self.0.lock();
+        return (guard.entries.len(), guard.hits, guard.misses);
+    }
+}
@@ -14,7 +14,41 @@ use crate::{
     syscall::SystemError,
 };
 
-use super::{Dirent, FileType, IndexNode, Metadata};
+use super::{
+    lock::{self, FileLockOwner},
+    mount::MountActiveRef,
+    seq_file::SeqFileState,
+    Dirent, FileType, IndexNode, InodeId, Metadata,
+};
+
+/// 读写普通文件时，所使用的内核缓冲区的最大大小
+const FILE_BOUNCE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// @brief 打开的文件描述（open file description）的标识
+///
+/// 同一次open得到的File对象，在dup、fork时会被复制，但它们共享同一个OpenFileDescription。
+/// flock锁属于打开的文件描述，因此以它的地址作为锁的持有者。当最后一个共享它的File对象被关闭时，释放flock锁。
+#[derive(Debug)]
+struct OpenFileDescription {
+    /// 文件对应的inode号
+    ino: InodeId,
+}
+
+impl OpenFileDescription {
+    /// @brief 获取在这个打开的文件描述上持有的flock锁的持有者
+    #[inline]
+    fn flock_owner(&self) -> FileLockOwner {
+        return FileLockOwner::Flock(self as *const OpenFileDescription as usize);
+    }
+}
+
+impl Drop for OpenFileDescription {
+    fn drop(&mut self) {
+        if lock::file_lock_in_use() {
+            lock::file_lock_release(self.ino, self.flock_owner());
+        }
+    }
+}
 
 /// 文件私有信息的枚举类型
 #[derive(Debug, Clone)]
@@ -25,6 +59,8 @@ pub enum FilePrivateData {
     DevFS(DevicePrivateData),
     /// tty设备文件的私有信息
     Tty(TtyFilePrivateData),
+    /// 属性文件（procfs、sysfs等）的读取状态
+    Seq(SeqFileState),
     /// 不需要文件私有信息
     Unused,
 }
@@ -103,6 +139,12 @@ pub struct File {
     file_type: FileType,
     /// readdir时候用的，暂存的本次循环中，所有子目录项的名字的数组
     readdir_subdirs_name: Vec<String>,
+    /// 打开文件时所使用的绝对路径（如果已知的话）
+    path: Option<String>,
+    /// 对文件所在的挂载文件系统的活跃引用，文件被打开期间，文件系统不能被卸载
+    mount_ref: Option<MountActiveRef>,
+    /// 打开的文件描述，被dup、fork得到的File对象共享
+    description: Arc<OpenFileDescription>,
     pub private_data: FilePrivateData,
 }
 
@@ -112,13 +154,20 @@ impl File {
     /// @param inode 文件对象对应的inode
     /// @param mode 文件的打开模式
     pub fn new(inode: Arc<dyn IndexNode>, mode: FileMode) -> Result<Self, SystemError> {
-        let file_type: FileType = inode.metadata()?.file_type;
+        let metadata: Metadata = inode.metadata()?;
+        let file_type: FileType = metadata.file_type;
+        let mount_ref: Option<MountActiveRef> = MountActiveRef::new(&inode);
         let mut f = File {
             inode,
             offset: 0,
             mode,
             file_type,
             readdir_subdirs_name: Vec::new(),
+            path: None,
+            mount_ref,
+            description: Arc::new(OpenFileDescription {
+                ino: metadata.inode_id,
+            }),
             private_data: FilePrivateData::default(),
         };
         // kdebug!("inode:{:?}",f.inode);
@@ -134,21 +183,7 @@ impl File {
     /// @return Ok(usize) 成功读取的字节数
     /// @return Err(SystemError) 错误码
     pub fn read(&mut self, len: usize, buf: &mut [u8]) -> Result<usize, SystemError> {
-        // 先检查本文件在权限等规则下，是否可读取。
-        self.readable()?;
-
-        if buf.len() < len {
-            return Err(SystemError::ENOBUFS);
-        }
-
-        // 如果文件指针已经超过了文件大小，则返回0
-        if self.offset > self.inode.metadata()?.size as usize {
-            return Ok(0);
-        }
-
-        let len = self
-            .inode
-            .read_at(self.offset, len, buf, &mut self.private_data)?;
+        let len = self.do_read(self.offset, len, buf)?;
         self.offset += len;
         return Ok(len);
     }
@@ -161,22 +196,137 @@ impl File {
     /// @return Ok(usize) 成功写入的字节数
     /// @return Err(SystemError) 错误码
     pub fn write(&mut self, len: usize, buf: &[u8]) -> Result<usize, SystemError> {
+        let len = self.do_write(self.offset, len, buf)?;
+        self.offset += len;
+        return Ok(len);
+    }
+
+    /// @brief 从文件的指定位置读取数据到buffer中，不改变文件指针
+    ///
+    /// @param offset 开始读取的位置
+    /// @param len 要读取的字节数
+    /// @param buf 目标buffer
+    ///
+    /// @return Ok(usize) 成功读取的字节数
+    /// @return Err(SystemError) 错误码。对于不可定位的文件，返回ESPIPE
+    pub fn pread(
+        &mut self,
+        offset: usize,
+        len: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, SystemError> {
+        self.check_seekable()?;
+        return self.do_read(offset, len, buf);
+    }
+
+    /// @brief 从buffer向文件的指定位置写入数据，不改变文件指针
+    ///
+    /// @param offset 开始写入的位置
+    /// @param len 要写入的字节数
+    /// @param buf 源数据buffer
+    ///
+    /// @return Ok(usize) 成功写入的字节数
+    /// @return Err(SystemError) 错误码。对于不可定位的文件，返回ESPIPE
+    pub fn pwrite(&mut self, offset: usize, len: usize, buf: &[u8]) -> Result<usize, SystemError> {
+        self.check_seekable()?;
+        return self.do_write(offset, len, buf);
+    }
+
+    fn do_read(&mut self, offset: usize, len: usize, buf: &mut [u8]) -> Result<usize, SystemError> {
+        // 先检查本文件在权限等规则下，是否可读取。
+        self.readable()?;
+
+        if buf.len() < len {
+            return Err(SystemError::ENOBUFS);
+        }
+
+        // 如果读取位置已经超过了文件大小，则返回0
+        if offset > self.inode.metadata()?.size as usize {
+            return Ok(0);
+        }
+
+        if self.file_type != FileType::File {
+            return self.inode.read_at(offset, len, buf, &mut self.private_data);
+        }
+
+        // 文件系统会在持有inode的锁时拷贝数据。如果buf是同一个文件的、还没有被映射的mmap区域，
+        // 拷贝时触发的缺页需要再次获取inode的锁（从页缓存中获取页），从而导致死锁。
+        // 因此先读入内核的缓冲区，释放inode的锁之后，再拷贝到buf中
+        let mut bounce: Vec<u8> = vec![0; core::cmp::min(len, FILE_BOUNCE_BUFFER_SIZE)];
+        let mut done = 0;
+        while done < len {
+            let count = core::cmp::min(len - done, bounce.len());
+            let n = match self.inode.read_at(
+                offset + done,
+                count,
+                &mut bounce[0..count],
+                &mut self.private_data,
+            ) {
+                Ok(n) => n,
+                Err(e) if done == 0 => return Err(e),
+                Err(_) => break,
+            };
+            buf[done..done + n].copy_from_slice(&bounce[0..n]);
+            done += n;
+            if n < count {
+                break;
+            }
+        }
+        return Ok(done);
+    }
+
+    fn do_write(&mut self, offset: usize, len: usize, buf: &[u8]) -> Result<usize, SystemError> {
         // 先检查本文件在权限等规则下，是否可写入。
         self.writeable()?;
         if buf.len() < len {
             return Err(SystemError::ENOBUFS);
         }
 
-        // 如果文件指针已经超过了文件大小，则需要扩展文件大小
+        // 如果写入位置已经超过了文件大小，则需要扩展文件大小
         let file_size = self.inode.metadata()?.size as usize;
-        if self.offset > file_size {
-            self.inode.resize(self.offset)?;
+        if offset > file_size {
+            self.inode.resize(offset)?;
+        }
+
+        if self.file_type != FileType::File {
+            return self
+                .inode
+                .write_at(offset, len, buf, &mut self.private_data);
+        }
+
+        // 与do_read相同，在获取inode的锁之前，先把数据拷贝到内核的缓冲区中
+        let mut bounce: Vec<u8> = Vec::with_capacity(core::cmp::min(len, FILE_BOUNCE_BUFFER_SIZE));
+        let mut done = 0;
+        while done < len {
+            let count = core::cmp::min(len - done, FILE_BOUNCE_BUFFER_SIZE);
+            bounce.clear();
+            bounce.extend_from_slice(&buf[done..done + count]);
+            let n = match self
+                .inode
+                .write_at(offset + done, count, &bounce, &mut self.private_data)
+            {
+                Ok(n) => n,
+                Err(e) if done == 0 => return Err(e),
+                Err(_) => break,
+            };
+            done += n;
+            if n < count {
+                break;
+            }
+        }
+        return Ok(done);
+    }
+
+    /// @brief 检查文件是否支持定位读写（管道、字符设备、socket、匿名inode不支持）
+    fn check_seekable(&self) -> Result<(), SystemError> {
+        match self.inode.metadata()?.file_type {
+            FileType::Pipe | FileType::CharDevice | FileType::Socket | FileType::AnonInode => {
+                return Err(SystemError::ESPIPE);
+            }
+            _ => {
+                return Ok(());
+            }
         }
-        let len = self
-            .inode
-            .write_at(self.offset, len, buf, &mut self.private_data)?;
-        self.offset += len;
-        return Ok(len);
     }
 
     /// @brief 获取文件的元数据
@@ -276,7 +426,7 @@ impl File {
         let name_bytes: &[u8] = name.as_bytes();
 
         self.offset += 1;
-        dirent.d_ino = sub_inode.metadata().unwrap().inode_id as u64;
+        dirent.d_ino = sub_inode.metadata().unwrap().ino();
         dirent.d_off = 0;
         dirent.d_reclen = 0;
         dirent.d_type = sub_inode.metadata().unwrap().file_type.get_file_type_num() as u8;
@@ -307,6 +457,9 @@ impl File {
             mode: self.mode.clone(),
             file_type: self.file_type.clone(),
             readdir_subdirs_name: self.readdir_subdirs_name.clone(),
+            path: self.path.clone(),
+            mount_ref: self.mount_ref.clone(),
+            description: self.description.clone(),
             private_data: self.private_data.clone(),
         });
         // 调用inode的open方法，让inode知道有新的文件打开了这个inode
@@ -329,6 +482,24 @@ impl File {
         return self.mode;
     }
 
+    /// @brief 获取在这个文件上持有的flock锁的持有者。dup、fork得到的File对象，返回相同的持有者
+    #[inline]
+    pub fn flock_owner(&self) -> FileLockOwner {
+        return self.description.flock_owner();
+    }
+
+    /// @brief 获取打开文件时所使用的绝对路径。对于管道、socket等没有路径的文件，返回None
+    #[inline]
+    pub fn path(&self) -> Option<&str> {
+        return self.path.as_deref();
+    }
+
+    /// @brief 设置文件的绝对路径
+    #[inline]
+    pub fn set_path(&mut self, path: Option<String>) {
+        self.path = path;
+    }
+
     /// 获取文件是否在execve时关闭
     #[inline]
     pub fn close_on_exec(&self) -> bool {
@@ -373,6 +544,15 @@ impl File {
 
 impl Drop for File {
     fn drop(&mut self) {
+        // 释放文件锁：进程关闭文件时，它在这个inode上的POSIX记录锁都会被释放。
+        // flock锁则在最后一个共享打开的文件描述的File对象被关闭时，由OpenFileDescription释放
+        if lock::file_lock_in_use() {
+            lock::file_lock_release(
+                self.description.ino,
+                FileLockOwner::Posix(current_pcb().pid),
+            );
+        }
+
         let r: Result<(), SystemError> = self.inode.close(&mut self.private_data);
         // 打印错误信息
         if r.is_err() {
@@ -440,7 +620,7 @@ impl FileDescriptorVec {
     /// @return false 不合法
     #[inline]
     pub fn validate_fd(fd: i32) -> bool {
-        if fd < 0 || fd as usize > FileDescriptorVec::PROCESS_MAX_FD {
+        if fd < 0 || fd as usize >= FileDescriptorVec::PROCESS_MAX_FD {
             return false;
         } else {
             return true;
@@ -0,0 +1,285 @@
+//! 文件锁
+//!
+//! 支持两种文件锁：
+//! - POSIX记录锁（fcntl的`F_GETLK`、`F_SETLK`、`F_SETLKW`）：锁定文件中的一段字节，属于进程。
+//!   进程关闭指向该inode的任意一个文件描述符，或者进程退出时，它在该inode上的所有记录锁都会被释放。
+//! - BSD文件锁（flock）：锁定整个文件，属于打开的文件对象，在文件对象被关闭时释放。
+//!
+//! 与Linux相同，这两种锁互不影响。
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use crate::{
+    arch::asm::current::current_pcb,
+    include::bindings::bindings::PROC_INTERRUPTIBLE,
+    ipc::signal::has_sig_pending,
+    libs::{spinlock::SpinLock, wait_queue::WaitQueue},
+    syscall::SystemError,
+};
+
+use super::InodeId;
+
+/// 共享锁（读锁）
+pub const F_RDLCK: i16 = 0;
+/// 独占锁（写锁）
+pub const F_WRLCK: i16 = 1;
+/// 解锁
+pub const F_UNLCK: i16 = 2;
+
+/// flock: 共享锁
+pub const LOCK_SH: u32 = 1;
+/// flock: 独占锁
+pub const LOCK_EX: u32 = 2;
+/// flock: 非阻塞
+pub const LOCK_NB: u32 = 4;
+/// flock: 解锁
+pub const LOCK_UN: u32 = 8;
+
+/// 锁定范围一直到文件末尾（包括文件以后增长的部分）
+pub const FILE_LOCK_EOF: u64 = u64::MAX;
+
+/// @brief 文件锁的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileLockType {
+    /// 共享锁
+    Read,
+    /// 独占锁
+    Write,
+}
+
+/// @brief 文件锁的持有者
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileLockOwner {
+    /// POSIX记录锁，属于进程（值为pid）
+    Posix(i64),
+    /// flock锁，属于打开的文件对象（值为文件对象的标识）
+    Flock(usize),
+}
+
+/// @brief 文件锁
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileLock {
+    pub owner: FileLockOwner,
+    pub lock_type: FileLockType,
+    /// 锁定范围的起始位置
+    pub start: u64,
+    /// 锁定范围的结束位置（包含）。值为`FILE_LOCK_EOF`时表示一直到文件末尾
+    pub end: u64,
+}
+
+impl FileLock {
+    pub fn new(owner: FileLockOwner, lock_type: FileLockType, start: u64, end: u64) -> Self {
+        return Self {
+            owner,
+            lock_type,
+            start,
+            end,
+        };
+    }
+
+    #[inline]
+    fn is_posix(&self) -> bool {
+        return matches!(self.owner, FileLockOwner::Posix(_));
+    }
+
+    #[inline]
+    fn overlaps(&self, start: u64, end: u64) -> bool {
+        return self.start <= end && start <= self.end;
+    }
+
+    /// @brief 判断两个锁是否冲突
+    ///
+    /// 同一个持有者的锁之间不会冲突，POSIX记录锁与flock锁之间也不会冲突
+    fn conflicts_with(&self, other: &FileLock) -> bool {
+        if self.owner == other.owner || self.is_posix() != other.is_posix() {
+            return false;
+        }
+        if !self.overlaps(other.start, other.end) {
+            return false;
+        }
+        return self.lock_type == FileLockType::Write || other.lock_type == FileLockType::Write;
+    }
+}
+
+/// @brief 文件锁管理器
+#[derive(Debug)]
+struct FileLockManager {
+    /// inode号 -> 该inode上的所有锁
+    locks: BTreeMap<InodeId, Vec<FileLock>>,
+    /// 正在等待POSIX记录锁的进程的pid -> 阻塞它的锁的持有者的pid（用于死锁检测）
+    waiting: BTreeMap<i64, i64>,
+}
+
+impl FileLockManager {
+    /// @brief 查找inode上与lock冲突的第一个锁
+    fn find_conflict(&self, ino: InodeId, lock: &FileLock) -> Option<FileLock> {
+        return self
+            .locks
+            .get(&ino)?
+            .iter()
+            .find(|l| l.conflicts_with(lock))
+            .cloned();
+    }
+
+    /// @brief 如果进程pid等待被blocker持有的锁，判断是否会形成死锁
+    fn would_deadlock(&self, pid: i64, blocker: i64) -> bool {
+        let mut owner = blocker;
+        // 等待关系中的进程数量是有限的，沿着等待链走，如果回到了pid，就说明形成了环
+        for _ in 0..=self.waiting.len() {
+            if owner == pid {
+                return true;
+            }
+            match self.waiting.get(&owner) {
+                Some(next) => owner = *next,
+                None => return false,
+            }
+        }
+        return false;
+    }
+
+    /// @brief 移除持有者在[start, end]范围内的锁，必要时对已有的锁进行拆分
+    fn remove(&mut self, ino: InodeId, owner: FileLockOwner, start: u64, end: u64) {
+        let locks: &mut Vec<FileLock> = match self.locks.get_mut(&ino) {
+            Some(locks) => locks,
+            None => return,
+        };
+
+        let mut result: Vec<FileLock> = Vec::with_capacity(locks.len() + 1);
+        for l in locks.drain(..) {
+            if l.owner != owner || !l.overlaps(start, end) {
+                result.push(l);
+                continue;
+            }
+            // 保留被移除范围之外的部分
+            if l.start < start {
+                result.push(FileLock::new(owner, l.lock_type, l.start, start - 1));
+            }
+            if l.end > end {
+                result.push(FileLock::new(owner, l.lock_type, end + 1, l.end));
+            }
+        }
+
+        if result.is_empty() {
+            self.locks.remove(&ino);
+        } else {
+            *locks = result;
+        }
+    }
+
+    /// @brief 加入一个锁，调用者需要保证它与其他持有者的锁不冲突
+    ///
+    /// 持有者在这个范围内原有的锁会被替换。相邻或重叠的、类型相同的锁会被合并
+    fn insert(&mut self, ino: InodeId, lock: FileLock) {
+        self.remove(ino, lock.owner, lock.start, lock.end);
+
+        let locks: &mut Vec<FileLock> = self.locks.entry(ino).or_insert_with(Vec::new);
+        let mut merged = lock;
+        locks.retain(|l| {
+            let adjacent = (l.end != FILE_LOCK_EOF && l.end + 1 == merged.start)
+                || (merged.end != FILE_LOCK_EOF && merged.end + 1 == l.start);
+            if l.owner == merged.owner
+                && l.lock_type == merged.lock_type
+                && (adjacent || l.overlaps(merged.start, merged.end))
+            {
+                merged.start = merged.start.min(l.start);
+                merged.end = merged.end.max(l.end);
+                return false;
+            }
+            return true;
+        });
+        locks.push(merged);
+    }
+}
+
+lazy_static! {
+    static ref FILE_LOCKS: SpinLock<FileLockManager> = SpinLock::new(FileLockManager {
+        locks: BTreeMap::new(),
+        waiting: BTreeMap::new(),
+    });
+    /// 等待文件锁的进程的等待队列
+    static ref FILE_LOCK_WAIT_QUEUE: WaitQueue = WaitQueue::INIT;
+}
+
+/// @brief 获取inode上与lock冲突的第一个锁（用于F_GETLK）
+///
+/// @return Some(FileLock) 冲突的锁
+/// @return None 没有冲突，lock可以被加上
+pub fn file_lock_test(ino: InodeId, lock: &FileLock) -> Option<FileLock> {
+    return FILE_LOCKS.lock().find_conflict(ino, lock);
+}
+
+/// @brief 在inode上加锁
+///
+/// @param ino inode号
+/// @param lock 要加的锁。持有者在这个范围内原有的锁会被替换（也就是说，可以进行锁的升级与降级）
+/// @param wait 存在冲突时，是否等待冲突的锁被释放
+///
+/// @return Ok(()) 加锁成功
+/// @return Err(EAGAIN_OR_EWOULDBLOCK) 存在冲突，并且wait为false
+/// @return Err(EDEADLK) 等待会导致死锁（仅检测POSIX记录锁）
+/// @return Err(EINTR) 等待的过程中被信号打断
+pub fn file_lock_set(ino: InodeId, lock: FileLock, wait: bool) -> Result<(), SystemError> {
+    loop {
+        let mut guard = FILE_LOCKS.lock();
+        let conflict: FileLock = match guard.find_conflict(ino, &lock) {
+            Some(conflict) => conflict,
+            None => {
+                if let FileLockOwner::Posix(pid) = lock.owner {
+                    guard.waiting.remove(&pid);
+                }
+                guard.insert(ino, lock);
+                // 锁的降级可能使其他进程可以加锁
+                drop(guard);
+                FILE_LOCK_WAIT_QUEUE.wakeup_all(PROC_INTERRUPTIBLE as u64);
+                return Ok(());
+            }
+        };
+
+        if !wait {
+            return Err(SystemError::EAGAIN_OR_EWOULDBLOCK);
+        }
+
+        if let (FileLockOwner::Posix(pid), FileLockOwner::Posix(blocker)) =
+            (lock.owner, conflict.owner)
+        {
+            if guard.would_deadlock(pid, blocker) {
+                guard.waiting.remove(&pid);
+                return Err(SystemError::EDEADLK);
+            }
+            guard.waiting.insert(pid, blocker);
+        }
+
+        FILE_LOCK_WAIT_QUEUE.sleep_unlock_spinlock(guard);
+
+        if has_sig_pending(current_pcb()) {
+            if let FileLockOwner::Posix(pid) = lock.owner {
+                FILE_LOCKS.lock().waiting.remove(&pid);
+            }
+            return Err(SystemError::EINTR);
+        }
+    }
+}
+
+/// @brief 释放持有者在inode的[start, end]范围内的锁
+pub fn file_unlock(ino: InodeId, owner: FileLockOwner, start: u64, end: u64) {
+    FILE_LOCKS.lock().remove(ino, owner, start, end);
+    FILE_LOCK_WAIT_QUEUE.wakeup_all(PROC_INTERRUPTIBLE as u64);
+}
+
+/// @brief 释放持有者在inode上的所有锁（用于关闭文件）
+pub fn file_lock_release(ino: InodeId, owner: FileLockOwner) {
+    let mut guard = FILE_LOCKS.lock();
+    if !guard.locks.contains_key(&ino) {
+        return;
+    }
+    guard.remove(ino, owner, 0, FILE_LOCK_EOF);
+    drop(guard);
+    FILE_LOCK_WAIT_QUEUE.wakeup_all(PROC_INTERRUPTIBLE as u64);
+}
+
+/// @brief 判断系统中是否有文件锁
+///
+/// 关闭文件时会先调用本函数，没有任何文件锁时，就不必获取inode号并释放锁了
+pub fn file_lock_in_use() -> bool {
+    return !FILE_LOCKS.lock().locks.is_empty();
+}
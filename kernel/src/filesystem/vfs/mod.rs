@@ -1,24 +1,46 @@
 #![allow(dead_code)]
 
 pub mod core;
+pub mod dcache;
 pub mod fcntl;
 pub mod file;
+pub mod lock;
 pub mod mount;
+pub mod permission;
+pub mod poll;
+pub mod pseudo;
+pub mod seq_file;
 pub mod syscall;
-mod utils;
+pub mod utils;
 
 use ::core::{any::Any, fmt::Debug};
 
-use alloc::{string::String, sync::Arc, vec::Vec};
-
-use crate::{libs::casting::DowncastArc, syscall::SystemError, time::TimeSpec};
-
-use self::{core::generate_inode_id, file::FileMode};
+use alloc::{
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+
+use crate::{
+    arch::asm::current::current_pcb, libs::casting::DowncastArc, mm::page_cache::CachedPage,
+    process::cred::Cred, syscall::SystemError, time::TimeSpec,
+};
+
+use self::{
+    core::generate_inode_id,
+    file::FileMode,
+    permission::{check_permission, PermissionMask},
+    poll::PollWaiter,
+    utils::rsplit_path,
+};
 pub use self::{core::ROOT_INODE, file::FilePrivateData, mount::MountFS};
 
 /// vfs容许的最大的路径名称长度
 pub const MAX_PATHLEN: usize = 1024;
 
+/// 解析路径时，最多能跟随的符号链接的数量。超过这个数量则返回ELOOP
+pub const VFS_MAX_FOLLOW_SYMLINK_TIMES: usize = 8;
+
 /// 定义inode号的类型为usize
 pub type InodeId = usize;
 
@@ -39,6 +61,8 @@ pub enum FileType {
     SymLink,
     /// 套接字
     Socket,
+    /// 匿名inode（如epoll），不对应任何文件系统中的文件
+    AnonInode,
 }
 
 /* these are defined by POSIX and also present in glibc's dirent.h */
@@ -72,6 +96,7 @@ impl FileType {
             FileType::Pipe => DT_FIFO,
             FileType::SymLink => DT_LNK,
             FileType::Socket => DT_SOCK,
+            FileType::AnonInode => DT_UNKNOWN,
         };
     }
 }
@@ -85,6 +110,18 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// @brief renameat2系统调用的标志位
+    pub struct RenameFlags: u32 {
+        /// 如果目标路径已经存在，则返回EEXIST，而不是覆盖它
+        const NOREPLACE = 1u32 << 0;
+        /// 原子地交换源路径与目标路径（两者都必须存在）
+        const EXCHANGE = 1u32 << 1;
+        /// 在源路径处创建一个whiteout对象（目前不支持）
+        const WHITEOUT = 1u32 << 2;
+    }
+}
+
 pub trait IndexNode: Any + Sync + Send + Debug {
     /// @brief 打开文件
     ///
@@ -143,6 +180,17 @@ pub trait IndexNode: Any + Sync + Send + Debug {
     /// @return PollStatus结构体
     fn poll(&self) -> Result<PollStatus, SystemError>;
 
+    /// @brief 注册一个poll等待者。当inode的状态可能发生变化时，inode应当回调等待者的wakeup方法
+    ///
+    /// @param _waiter 等待者的弱引用
+    ///
+    /// @return 成功：Ok()
+    ///         失败：Err(错误码)。不支持的inode（例如状态永远不会改变的普通文件）返回EOPNOTSUPP_OR_ENOTSUP
+    fn add_poll_waiter(&self, _waiter: Weak<dyn PollWaiter>) -> Result<(), SystemError> {
+        // 若文件系统没有实现此方法，则返回“不支持”
+        return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+    }
+
     /// @brief 获取inode的元数据
     ///
     /// @return 成功：Ok(inode的元数据)
@@ -211,6 +259,18 @@ pub trait IndexNode: Any + Sync + Send + Debug {
         return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
     }
 
+    /// @brief 在当前目录下创建一个符号链接
+    ///
+    /// @param name 符号链接的名称
+    /// @param target 符号链接所指向的路径（不要求该路径存在）
+    ///
+    /// @return 创建成功：返回Ok(新的符号链接的inode的Arc指针)
+    /// @return 创建失败：返回Err(错误码)
+    fn symlink(&self, _name: &str, _target: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+        // 若文件系统没有实现此方法，则返回“不支持”
+        return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+    }
+
     /// @brief 在当前目录下，创建一个名为Name的硬链接，指向另一个IndexNode
     ///
     /// @param name 硬链接的名称
@@ -246,6 +306,10 @@ pub trait IndexNode: Any + Sync + Send + Debug {
 
     /// @brief 将指定名称的子目录项的文件内容，移动到target这个目录下。如果_old_name所指向的inode与_target的相同，那么则直接执行重命名的操作。
     ///
+    /// 如果target中已经存在名为new_name的目录项，那么它会被原子地替换掉：文件只能替换文件，
+    /// 文件夹只能替换空文件夹。对于不区分大小写的文件系统，如果new_name与old_name指向同一个目录项，
+    /// 那么这是一次只改变大小写的重命名。
+    ///
     /// @param old_name 旧的名字
     ///
     /// @param target 移动到指定的inode
@@ -264,6 +328,26 @@ pub trait IndexNode: Any + Sync + Send + Debug {
         return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
     }
 
+    /// @brief 原子地交换当前目录下的old_name与target目录下的new_name（RENAME_EXCHANGE）
+    ///
+    /// @param old_name 当前目录下的名字
+    ///
+    /// @param target 另一个目录
+    ///
+    /// @param new_name target目录下的名字
+    ///
+    /// @return 成功: Ok()
+    ///         失败: Err(错误码)
+    fn exchange(
+        &self,
+        _old_name: &str,
+        _target: &Arc<dyn IndexNode>,
+        _new_name: &str,
+    ) -> Result<(), SystemError> {
+        // 若文件系统没有实现此方法，则不支持RENAME_EXCHANGE
+        return Err(SystemError::EINVAL);
+    }
+
     /// @brief 寻找一个名为Name的inode
     ///
     /// @param name 要寻找的inode的名称
@@ -275,6 +359,14 @@ pub trait IndexNode: Any + Sync + Send + Debug {
         return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
     }
 
+    /// @brief 当前目录下的查找结果能否被目录项缓存所缓存
+    ///
+    /// 目录的内容如果会随着内核状态动态变化，而文件系统又无法在变化时令缓存失效（例如/proc/<pid>/fd），
+    /// 那么应当返回false
+    fn dentry_cacheable(&self) -> bool {
+        return true;
+    }
+
     /// @brief 根据inode号，获取子目录项的名字
     ///
     /// @param ino inode号
@@ -338,6 +430,16 @@ pub trait IndexNode: Any + Sync + Send + Debug {
     fn sync(&self) -> Result<(), SystemError> {
         return Ok(());
     }
+
+    /// @brief 获取文件的第index页，用于把文件映射到用户空间
+    ///
+    /// 返回的页位于inode的页缓存中，页被标记为脏页之后，对它的修改会被写回文件
+    ///
+    /// @param index 页在文件内的序号
+    fn get_page(&self, _index: usize) -> Result<Arc<CachedPage>, SystemError> {
+        // 若文件系统没有实现此方法，则说明它不支持文件映射
+        return Err(SystemError::ENODEV);
+    }
 }
 
 impl DowncastArc for dyn IndexNode {
@@ -353,20 +455,90 @@ impl dyn IndexNode {
         return self.as_any_ref().downcast_ref::<T>();
     }
 
-    /// @brief 查找文件（不考虑符号链接）
+    /// @brief 查找文件（跟随路径中的所有符号链接）
     ///
     /// @param path 文件路径
     ///
     /// @return Ok(Arc<dyn IndexNode>) 要寻找的目录项的inode
     /// @return Err(SystemError) 错误码
     pub fn lookup(&self, path: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
-        return self.lookup_follow_symlink(path, 0);
+        return self.lookup_follow_symlink(path, VFS_MAX_FOLLOW_SYMLINK_TIMES);
+    }
+
+    /// @brief 查找文件。若路径的最后一级是符号链接，则返回符号链接本身，而不跟随它
+    ///
+    /// 路径中间的符号链接仍然会被跟随。lstat、readlink、unlink等操作需要使用本函数
+    ///
+    /// @param path 文件路径
+    ///
+    /// @return Ok(Arc<dyn IndexNode>) 要寻找的目录项的inode
+    /// @return Err(SystemError) 错误码
+    pub fn lookup_nofollow(&self, path: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+        let (parent_inode, filename) = self.lookup_parent(path)?;
+        return parent_inode.find(filename);
+    }
+
+    /// @brief 查找路径的父目录
+    ///
+    /// 举例：对于 /123/456/789，本函数返回 /123/456 对应的inode，以及789
+    ///
+    /// @param path 文件路径
+    ///
+    /// @return Ok((Arc<dyn IndexNode>, &str)) 父目录的inode，以及路径最后一级的名称
+    /// @return Err(SystemError) 错误码
+    pub fn lookup_parent<'a>(
+        &self,
+        path: &'a str,
+    ) -> Result<(Arc<dyn IndexNode>, &'a str), SystemError> {
+        let (filename, parent_path) = rsplit_path(path);
+        let parent_path = match (path.starts_with('/'), parent_path) {
+            (true, Some(p)) => String::from("/") + p,
+            (true, None) => String::from("/"),
+            (false, Some(p)) => String::from(p),
+            (false, None) => String::from("."),
+        };
+
+        let parent_inode: Arc<dyn IndexNode> = self.lookup(&parent_path)?;
+        let parent_metadata: Metadata = parent_inode.metadata()?;
+        if parent_metadata.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
+        }
+        // 调用者会在父目录中查找、创建或者删除最后一级的目录项，因此需要对父目录有搜索权限
+        check_permission(
+            &parent_metadata,
+            &current_pcb().cred(),
+            PermissionMask::MAY_EXEC,
+        )?;
+        return Ok((parent_inode, filename));
+    }
+
+    /// @brief 读取符号链接所指向的路径
+    ///
+    /// @return Ok(String) 符号链接的内容
+    /// @return Err(EINVAL) 当前inode不是符号链接
+    pub fn read_link(&self) -> Result<String, SystemError> {
+        let metadata = self.metadata()?;
+        if metadata.file_type != FileType::SymLink {
+            return Err(SystemError::EINVAL);
+        }
+
+        let len = metadata.size as usize;
+        if len > MAX_PATHLEN {
+            return Err(SystemError::ENAMETOOLONG);
+        }
+        let mut content = vec![0u8; len];
+        let len = self.read_at(0, len, &mut content, &mut FilePrivateData::Unused)?;
+
+        // 将读到的数据转换为utf8字符串（先转为str，再转为String）
+        return Ok(String::from(
+            ::core::str::from_utf8(&content[..len]).map_err(|_| SystemError::EINVAL)?,
+        ));
     }
 
     /// @brief 查找文件（考虑符号链接）
     ///
     /// @param path 文件路径
-    /// @param max_follow_times 最大经过的符号链接的大小
+    /// @param max_follow_times 最多能跟随的符号链接的数量。若路径中的符号链接超过这个数量，则返回ELOOP
     ///
     /// @return Ok(Arc<dyn IndexNode>) 要寻找的目录项的inode
     /// @return Err(SystemError) 错误码
@@ -389,10 +561,12 @@ impl dyn IndexNode {
             (self.find(".")?, String::from(path))
         };
 
+        let cred: Arc<Cred> = current_pcb().cred();
         // 逐级查找文件
         while !rest_path.is_empty() {
+            let metadata: Metadata = result.metadata()?;
             // 当前这一级不是文件夹
-            if result.metadata()?.file_type != FileType::Dir {
+            if metadata.file_type != FileType::Dir {
                 return Err(SystemError::ENOTDIR);
             }
 
@@ -417,21 +591,24 @@ impl dyn IndexNode {
                 continue;
             }
 
+            // 在目录中查找目录项，需要对目录有搜索权限
+            check_permission(&metadata, &cred, PermissionMask::MAY_EXEC)?;
             let inode = result.find(&name)?;
 
             // 处理符号链接的问题
-            if inode.metadata()?.file_type == FileType::SymLink && max_follow_times > 0 {
-                let mut content = [0u8; 256];
-                // 读取符号链接
-                let len = inode.read_at(0, 256, &mut content, &mut FilePrivateData::Unused)?;
-
-                // 将读到的数据转换为utf8字符串（先转为str，再转为String）
-                let link_path = String::from(
-                    ::core::str::from_utf8(&content[..len]).map_err(|_| SystemError::ENOTDIR)?,
-                );
-
-                let new_path = link_path + "/" + &rest_path;
-                // 继续查找符号链接
+            if inode.metadata()?.file_type == FileType::SymLink {
+                // 经过的符号链接过多，可能存在循环
+                if max_follow_times == 0 {
+                    return Err(SystemError::ELOOP);
+                }
+
+                let link_path = inode.read_link()?;
+                let new_path = if rest_path.is_empty() {
+                    link_path
+                } else {
+                    link_path + "/" + &rest_path
+                };
+                // 继续查找符号链接（相对路径的符号链接，是相对于其所在的目录而言的）
                 return result.lookup_follow_symlink(&new_path, max_follow_times - 1);
             } else {
                 result = inode;
@@ -450,9 +627,13 @@ pub struct Metadata {
     /// 当前inode所在的文件系统的设备号
     pub dev_id: usize,
 
-    /// inode号
+    /// inode号。在整个系统中唯一，供目录项缓存、挂载点等内核内部的机制使用
     pub inode_id: InodeId,
 
+    /// 文件系统自身的inode号（例如ext2磁盘上的inode号），通过stat、getdents报告给用户程序。
+    /// 为None时，报告inode_id
+    pub disk_ino: Option<u64>,
+
     /// Inode的大小
     /// 文件：文件大小（单位：字节）
     /// 目录：目录项中的文件、文件夹数量
@@ -497,6 +678,7 @@ impl Default for Metadata {
         return Self {
             dev_id: 0,
             inode_id: 0,
+            disk_ino: None,
             size: 0,
             blk_size: 0,
             blocks: 0,
@@ -524,14 +706,33 @@ pub trait FileSystem: Any + Sync + Send + Debug {
     /// @brief 本函数用于实现动态转换。
     /// 具体的文件系统在实现本函数时，最简单的方式就是：直接返回self
     fn as_any_ref(&self) -> &dyn Any;
+
+    /// @brief 把文件系统的元数据同步到存储设备上
+    fn sync(&self) -> Result<(), SystemError> {
+        return Ok(());
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, Clone)]
 pub struct FsInfo {
     /// 文件系统所在的块设备的id
     pub blk_dev_id: usize,
     /// 文件名的最大长度
     pub max_name_len: usize,
+    /// 文件系统的类型（魔数，与Linux的statfs.f_type相同）
+    pub magic: u64,
+    /// 块大小（单位：字节）
+    pub block_size: usize,
+    /// 总的块数
+    pub total_blocks: u64,
+    /// 空闲的块数
+    pub free_blocks: u64,
+    /// 普通用户可用的空闲块数
+    pub avail_blocks: u64,
+    /// 总的inode数
+    pub total_inodes: u64,
+    /// 空闲的inode数
+    pub free_inodes: u64,
 }
 
 /// @brief 整合主设备号+次设备号
@@ -551,10 +752,17 @@ pub struct Dirent {
 }
 
 impl Metadata {
+    /// @brief 获取报告给用户程序的inode号（stat的st_ino、getdents的d_ino）
+    #[inline]
+    pub fn ino(&self) -> u64 {
+        return self.disk_ino.unwrap_or(self.inode_id as u64);
+    }
+
     pub fn new(file_type: FileType, mode: u32) -> Self {
         Metadata {
             dev_id: 0,
             inode_id: generate_inode_id(),
+            disk_ino: None,
             size: 0,
             blk_size: 0,
             blocks: 0,
@@ -1,13 +1,22 @@
-use core::any::Any;
+use core::{
+    any::Any,
+    sync::atomic::{AtomicU32, AtomicUsize, Ordering},
+};
 
 use alloc::{
     collections::BTreeMap,
+    format,
+    string::String,
     sync::{Arc, Weak},
+    vec::Vec,
 };
 
-use crate::{libs::spinlock::SpinLock, syscall::SystemError};
+use crate::{libs::spinlock::SpinLock, mm::page_cache::CachedPage, syscall::SystemError};
 
-use super::{file::FileMode, FilePrivateData, FileSystem, FileType, IndexNode, InodeId};
+use super::{
+    dcache::dcache, file::FileMode, poll::PollWaiter, FilePrivateData, FileSystem, FileType,
+    IndexNode, InodeId,
+};
 
 /// @brief 挂载文件系统
 /// 挂载文件系统的时候，套了MountFS这一层，以实现文件系统的递归挂载
@@ -21,6 +30,10 @@ pub struct MountFS {
     self_mountpoint: Option<Arc<MountFSInode>>,
     /// 指向当前MountFS的弱引用
     self_ref: Weak<MountFS>,
+    /// 挂载标志（在挂载项被加入挂载表时设置）
+    flags: AtomicU32,
+    /// 活跃引用的数量（被打开的文件、文件映射、进程的当前工作目录）
+    active_count: AtomicUsize,
 }
 
 /// @brief MountFS的Index Node 注意，这个IndexNode只是一个中间层。它的目的是将具体文件系统的Inode与挂载机制连接在一起。
@@ -44,6 +57,8 @@ impl MountFS {
             mountpoints: SpinLock::new(BTreeMap::new()),
             self_mountpoint: self_mountpoint,
             self_ref: Weak::default(),
+            flags: AtomicU32::new(0),
+            active_count: AtomicUsize::new(0),
         }
         .wrap();
     }
@@ -79,6 +94,279 @@ impl MountFS {
     pub fn inner_filesystem(&self) -> Arc<dyn FileSystem> {
         return self.inner_filesystem.clone();
     }
+
+    /// @brief 判断inode是否为当前文件系统的根inode
+    pub fn is_root_inode(&self, inode: &Arc<dyn IndexNode>) -> Result<bool, SystemError> {
+        return Ok(
+            self.inner_filesystem.root_inode().metadata()?.inode_id == inode.metadata()?.inode_id
+        );
+    }
+
+    /// @brief 判断当前文件系统是否被直接或者间接地挂载在ancestor之下
+    pub fn is_descendant_of(&self, ancestor: &MountFS) -> bool {
+        let mut current: Option<Arc<MountFS>> = self
+            .self_mountpoint
+            .as_ref()
+            .map(|inode| inode.mount_fs.clone());
+        while let Some(fs) = current {
+            if core::ptr::eq(Arc::as_ptr(&fs), ancestor as *const MountFS) {
+                return true;
+            }
+            current = fs
+                .self_mountpoint
+                .as_ref()
+                .map(|inode| inode.mount_fs.clone());
+        }
+        return false;
+    }
+
+    /// @brief 获取挂载标志
+    #[inline]
+    pub fn flags(&self) -> MountFlags {
+        return MountFlags::from_bits_truncate(self.flags.load(Ordering::SeqCst));
+    }
+
+    /// @brief 设置挂载标志
+    #[inline]
+    pub fn set_flags(&self, flags: MountFlags) {
+        self.flags.store(flags.bits(), Ordering::SeqCst);
+    }
+
+    /// @brief 获取活跃引用的数量。数量不为0时，文件系统正在被使用
+    #[inline]
+    pub fn active_count(&self) -> usize {
+        return self.active_count.load(Ordering::SeqCst);
+    }
+
+    /// @brief 判断当前文件系统之下，是否还挂载有其他文件系统
+    pub fn has_submounts(&self) -> bool {
+        return !self.mountpoints.lock().is_empty();
+    }
+
+    /// @brief 把当前文件系统从它的挂载点上卸载
+    ///
+    /// 本函数只负责把当前MountFS从挂载树上摘下来，并不检查文件系统是否正在被使用。
+    /// 已经打开的文件仍然持有对MountFS的引用，因此在它们被关闭之前，文件系统不会被释放。
+    ///
+    /// @return Ok(()) 卸载成功
+    /// @return Err(EINVAL) 当前文件系统是根文件系统，或者它并不在挂载树上
+    pub fn umount(&self) -> Result<(), SystemError> {
+        let mountpoint: &Arc<MountFSInode> =
+            self.self_mountpoint.as_ref().ok_or(SystemError::EINVAL)?;
+        let inode_id: InodeId = mountpoint.metadata()?.inode_id;
+
+        let mut mountpoints = mountpoint.mount_fs.mountpoints.lock();
+        match mountpoints.get(&inode_id) {
+            Some(fs) if core::ptr::eq(Arc::as_ptr(fs), self as *const Self) => {
+                mountpoints.remove(&inode_id);
+            }
+            _ => return Err(SystemError::EINVAL),
+        }
+        drop(mountpoints);
+
+        // 挂载树发生了变化，缓存中指向被卸载的文件系统的inode已经过时
+        dcache().invalidate_all();
+        return Ok(());
+    }
+}
+
+bitflags! {
+    /// @brief mount系统调用的标志位
+    pub struct MountFlags: u32 {
+        /// 以只读方式挂载
+        const RDONLY = 1 << 0;
+        /// 忽略suid和sgid位
+        const NOSUID = 1 << 1;
+        /// 不允许访问设备文件
+        const NODEV = 1 << 2;
+        /// 不允许执行程序
+        const NOEXEC = 1 << 3;
+        /// 同步写入
+        const SYNCHRONOUS = 1 << 4;
+        /// 重新挂载一个已经挂载的文件系统
+        const REMOUNT = 1 << 5;
+        /// 不更新访问时间
+        const NOATIME = 1 << 10;
+        /// 绑定挂载
+        const BIND = 1 << 12;
+    }
+}
+
+bitflags! {
+    /// @brief umount2系统调用的标志位
+    pub struct UmountFlags: u32 {
+        /// 强制卸载
+        const FORCE = 1 << 0;
+        /// 惰性卸载：立即把文件系统从挂载树上摘下，等到它不再被使用时再释放
+        const DETACH = 1 << 1;
+        /// 把挂载点标记为过期
+        const EXPIRE = 1 << 2;
+        /// 如果target是符号链接，不跟随它
+        const NOFOLLOW = 1 << 3;
+    }
+}
+
+/// @brief 对挂载文件系统的活跃引用
+///
+/// 被打开的文件、文件映射、进程的当前工作目录各持有一个活跃引用。
+/// 只要还存在活跃引用，文件系统就处于被使用的状态，不能被非惰性地卸载。
+#[derive(Debug)]
+pub struct MountActiveRef(Arc<MountFS>);
+
+impl MountActiveRef {
+    /// @brief 为inode所在的挂载文件系统创建一个活跃引用
+    ///
+    /// @return None inode不在挂载树上（例如管道、socket）
+    pub fn new(inode: &Arc<dyn IndexNode>) -> Option<Self> {
+        let fs: Arc<dyn FileSystem> = inode.fs();
+        let mount_fs: Arc<MountFS> = fs
+            .as_any_ref()
+            .downcast_ref::<MountFS>()?
+            .self_ref
+            .upgrade()?;
+        mount_fs.active_count.fetch_add(1, Ordering::SeqCst);
+        return Some(MountActiveRef(mount_fs));
+    }
+}
+
+impl Clone for MountActiveRef {
+    fn clone(&self) -> Self {
+        self.0.active_count.fetch_add(1, Ordering::SeqCst);
+        return MountActiveRef(self.0.clone());
+    }
+}
+
+impl Drop for MountActiveRef {
+    fn drop(&mut self) {
+        self.0.active_count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// @brief 获取inode所在的挂载点的挂载标志。不在挂载树上的inode（例如管道），返回空的标志
+pub fn inode_mount_flags(inode: &Arc<dyn IndexNode>) -> MountFlags {
+    let fs: Arc<dyn FileSystem> = inode.fs();
+    return fs
+        .as_any_ref()
+        .downcast_ref::<MountFS>()
+        .map(|mount_fs| mount_fs.flags())
+        .unwrap_or(MountFlags::empty());
+}
+
+/// @brief 挂载表中的一项
+#[derive(Debug, Clone)]
+pub struct MountRecord {
+    /// 被挂载的设备。对于伪文件系统，这里是文件系统的名称
+    pub source: String,
+    /// 挂载点的绝对路径
+    pub target: String,
+    /// 文件系统的类型名
+    pub fstype: String,
+    /// 挂载时使用的标志位
+    pub flags: MountFlags,
+    /// 对应的挂载文件系统
+    pub mount_fs: Arc<MountFS>,
+}
+
+impl MountRecord {
+    pub fn new(
+        source: &str,
+        target: &str,
+        fstype: &str,
+        flags: MountFlags,
+        mount_fs: Arc<MountFS>,
+    ) -> Self {
+        return MountRecord {
+            source: String::from(source),
+            target: String::from(target),
+            fstype: String::from(fstype),
+            flags,
+            mount_fs,
+        };
+    }
+
+    /// @brief 生成这个挂载项在/proc/mounts中对应的一行
+    pub fn to_mounts_line(&self) -> String {
+        let opt = if self.flags.contains(MountFlags::RDONLY) {
+            "ro"
+        } else {
+            "rw"
+        };
+        return format!(
+            "{} {} {} {} 0 0\n",
+            self.source, self.target, self.fstype, opt
+        );
+    }
+}
+
+lazy_static! {
+    /// 全局的挂载表
+    static ref MOUNT_LIST: MountList = MountList(SpinLock::new(Vec::new()));
+}
+
+/// @brief 获取全局的挂载表
+#[inline(always)]
+pub fn mount_list() -> &'static MountList {
+    return &MOUNT_LIST;
+}
+
+/// @brief 挂载表。按照挂载的先后顺序，记录系统中所有的挂载项
+#[derive(Debug)]
+pub struct MountList(SpinLock<Vec<MountRecord>>);
+
+impl MountList {
+    /// @brief 向挂载表中加入一项。如果挂载点已经在表中，那么替换原来的项
+    pub fn insert(&self, record: MountRecord) {
+        record.mount_fs.set_flags(record.flags);
+        let mut guard = self.0.lock();
+        let old = match guard.iter().position(|r| r.target == record.target) {
+            Some(idx) => Some(core::mem::replace(&mut guard[idx], record)),
+            None => {
+                guard.push(record);
+                None
+            }
+        };
+        drop(guard);
+        drop(old);
+    }
+
+    /// @brief 从挂载表中删除指定的挂载文件系统，以及挂载在它之下的所有文件系统对应的项
+    ///
+    /// @return 被删除的挂载项
+    pub fn remove_subtree(&self, mount_fs: &Arc<MountFS>) -> Vec<MountRecord> {
+        let mut guard = self.0.lock();
+        let (removed, remain): (Vec<MountRecord>, Vec<MountRecord>) =
+            core::mem::take(&mut *guard).into_iter().partition(|r| {
+                Arc::ptr_eq(&r.mount_fs, mount_fs) || r.mount_fs.is_descendant_of(mount_fs)
+            });
+        *guard = remain;
+        return removed;
+    }
+
+    /// @brief 根据挂载文件系统，查找对应的挂载项
+    pub fn find_by_fs(&self, mount_fs: &MountFS) -> Option<MountRecord> {
+        return self
+            .0
+            .lock()
+            .iter()
+            .find(|r| core::ptr::eq(Arc::as_ptr(&r.mount_fs), mount_fs as *const MountFS))
+            .cloned();
+    }
+
+    /// @brief 查找最近一次挂载的、类型为fstype的文件系统
+    pub fn find_by_type(&self, fstype: &str) -> Option<MountRecord> {
+        return self
+            .0
+            .lock()
+            .iter()
+            .rev()
+            .find(|r| r.fstype == fstype)
+            .cloned();
+    }
+
+    /// @brief 获取挂载表中的所有项
+    pub fn records(&self) -> Vec<MountRecord> {
+        return self.0.lock().clone();
+    }
 }
 
 impl MountFSInode {
@@ -106,6 +394,39 @@ impl MountFSInode {
             == self.inner_inode.metadata()?.inode_id);
     }
 
+    /// @brief 检查是否可以修改当前inode所在的文件系统
+    ///
+    /// @return Err(EROFS) 文件系统是以只读方式挂载的
+    #[inline]
+    fn check_writable(&self) -> Result<(), SystemError> {
+        if self.mount_fs.flags().contains(MountFlags::RDONLY) {
+            return Err(SystemError::EROFS);
+        }
+        return Ok(());
+    }
+
+    /// @brief 重命名时，检查被移动的目录项和被替换的目录项是否为挂载点
+    ///
+    /// @return Err(EBUSY) 其中一个目录项是挂载点
+    fn check_rename_busy(
+        &self,
+        old_name: &str,
+        target: &Arc<dyn IndexNode>,
+        new_name: &str,
+    ) -> Result<(), SystemError> {
+        let inode_id = self.inner_inode.find(old_name)?.metadata()?.inode_id;
+        if self.mount_fs.mountpoints.lock().contains_key(&inode_id) {
+            return Err(SystemError::EBUSY);
+        }
+        // 在挂载点上查找时，会得到被挂载的文件系统的根inode，它属于另一个MountFS
+        if let Ok(victim) = target.find(new_name) {
+            if Arc::as_ptr(&victim.fs()) as *const u8 != Arc::as_ptr(&target.fs()) as *const u8 {
+                return Err(SystemError::EBUSY);
+            }
+        }
+        return Ok(());
+    }
+
     /// @brief 在挂载树上进行inode替换。
     /// 如果当前inode是父MountFS内的一个挂载点，那么，本函数将会返回挂载到这个挂载点下的文件系统的root inode.
     /// 如果当前inode在父MountFS内，但不是挂载点，那么说明在这里不需要进行inode替换，因此直接返回当前inode。
@@ -124,6 +445,24 @@ impl MountFSInode {
 
 impl IndexNode for MountFSInode {
     fn open(&self, data: &mut FilePrivateData, mode: &FileMode) -> Result<(), SystemError> {
+        let flags: MountFlags = self.mount_fs.flags();
+        if flags.intersects(MountFlags::RDONLY | MountFlags::NODEV) {
+            match self.inner_inode.metadata()?.file_type {
+                // 设置了nodev的挂载点上的设备文件不能被打开
+                FileType::CharDevice | FileType::BlockDevice
+                    if flags.contains(MountFlags::NODEV) =>
+                {
+                    return Err(SystemError::EACCES);
+                }
+                // 只读挂载时，不能以写方式打开普通文件（设备文件、管道等不受影响）
+                FileType::File | FileType::Dir | FileType::SymLink
+                    if mode.accmode() != FileMode::O_RDONLY.bits() =>
+                {
+                    self.check_writable()?;
+                }
+                _ => {}
+            }
+        }
         return self.inner_inode.open(data, mode);
     }
 
@@ -138,12 +477,18 @@ impl IndexNode for MountFSInode {
         mode: u32,
         data: usize,
     ) -> Result<Arc<dyn IndexNode>, SystemError> {
-        return self
+        self.check_writable()?;
+        let self_id: InodeId = self.metadata()?.inode_id;
+        let r = self
             .inner_inode
             .create_with_data(name, file_type, mode, data);
+        // 新的目录项可能存在负向缓存
+        dcache().invalidate_dir(self_id);
+        return r;
     }
 
     fn truncate(&self, len: usize) -> Result<(), SystemError> {
+        self.check_writable()?;
         return self.inner_inode.truncate(len);
     }
 
@@ -172,6 +517,11 @@ impl IndexNode for MountFSInode {
         return self.inner_inode.poll();
     }
 
+    #[inline]
+    fn add_poll_waiter(&self, waiter: Weak<dyn PollWaiter>) -> Result<(), SystemError> {
+        return self.inner_inode.add_poll_waiter(waiter);
+    }
+
     #[inline]
     fn fs(&self) -> Arc<dyn FileSystem> {
         return self.mount_fs.clone();
@@ -189,11 +539,13 @@ impl IndexNode for MountFSInode {
 
     #[inline]
     fn set_metadata(&self, metadata: &super::Metadata) -> Result<(), SystemError> {
+        self.check_writable()?;
         return self.inner_inode.set_metadata(metadata);
     }
 
     #[inline]
     fn resize(&self, len: usize) -> Result<(), SystemError> {
+        self.check_writable()?;
         return self.inner_inode.resize(len);
     }
 
@@ -204,8 +556,28 @@ impl IndexNode for MountFSInode {
         file_type: FileType,
         mode: u32,
     ) -> Result<Arc<dyn IndexNode>, SystemError> {
+        self.check_writable()?;
+        let self_id: InodeId = self.metadata()?.inode_id;
+        let r = self.inner_inode.create(name, file_type, mode);
+        // 新的目录项可能存在负向缓存
+        dcache().invalidate_dir(self_id);
+        let inner_inode = r?;
         return Ok(MountFSInode {
-            inner_inode: self.inner_inode.create(name, file_type, mode)?,
+            inner_inode,
+            mount_fs: self.mount_fs.clone(),
+            self_ref: Weak::default(),
+        }
+        .wrap());
+    }
+
+    fn symlink(&self, name: &str, target: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+        self.check_writable()?;
+        let self_id: InodeId = self.metadata()?.inode_id;
+        let r = self.inner_inode.symlink(name, target);
+        dcache().invalidate_dir(self_id);
+        let inner_inode = r?;
+        return Ok(MountFSInode {
+            inner_inode,
             mount_fs: self.mount_fs.clone(),
             self_ref: Weak::default(),
         }
@@ -213,32 +585,45 @@ impl IndexNode for MountFSInode {
     }
 
     fn link(&self, name: &str, other: &Arc<dyn IndexNode>) -> Result<(), SystemError> {
-        return self.inner_inode.link(name, other);
+        self.check_writable()?;
+        let self_id: InodeId = self.metadata()?.inode_id;
+        let r = self.inner_inode.link(name, other);
+        dcache().invalidate_dir(self_id);
+        return r;
     }
 
     /// @brief 在挂载文件系统中删除文件/文件夹
     #[inline]
     fn unlink(&self, name: &str) -> Result<(), SystemError> {
+        self.check_writable()?;
         let inode_id = self.inner_inode.find(name)?.metadata()?.inode_id;
 
         // 先检查这个inode是否为一个挂载点，如果当前inode是一个挂载点，那么就不能删除这个inode
         if self.mount_fs.mountpoints.lock().contains_key(&inode_id) {
             return Err(SystemError::EBUSY);
         }
+        let self_id: InodeId = self.metadata()?.inode_id;
         // 调用内层的inode的方法来删除这个inode
-        return self.inner_inode.unlink(name);
+        let r = self.inner_inode.unlink(name);
+        dcache().invalidate_dir(self_id);
+        return r;
     }
 
     #[inline]
     fn rmdir(&self, name: &str) -> Result<(), SystemError> {
+        self.check_writable()?;
         let inode_id = self.inner_inode.find(name)?.metadata()?.inode_id;
 
         // 先检查这个inode是否为一个挂载点，如果当前inode是一个挂载点，那么就不能删除这个inode
         if self.mount_fs.mountpoints.lock().contains_key(&inode_id) {
             return Err(SystemError::EBUSY);
         }
+        let self_id: InodeId = self.metadata()?.inode_id;
         // 调用内层的rmdir的方法来删除这个inode
         let r = self.inner_inode.rmdir(name);
+        // 被删除的目录及其下面的缓存项都要失效
+        dcache().invalidate_dir(self_id);
+        dcache().invalidate_dir(inode_id);
 
         return r;
     }
@@ -250,7 +635,32 @@ impl IndexNode for MountFSInode {
         target: &Arc<dyn IndexNode>,
         new_name: &str,
     ) -> Result<(), SystemError> {
-        return self.inner_inode.move_(old_name, target, new_name);
+        self.check_writable()?;
+        self.check_rename_busy(old_name, target, new_name)?;
+        let self_id: InodeId = self.metadata()?.inode_id;
+        let target_id: InodeId = target.metadata()?.inode_id;
+        let r = self.inner_inode.move_(old_name, target, new_name);
+        // 源目录和目标目录的缓存项都要失效
+        dcache().invalidate_dir(self_id);
+        dcache().invalidate_dir(target_id);
+        return r;
+    }
+
+    fn exchange(
+        &self,
+        old_name: &str,
+        target: &Arc<dyn IndexNode>,
+        new_name: &str,
+    ) -> Result<(), SystemError> {
+        self.check_writable()?;
+        self.check_rename_busy(old_name, target, new_name)?;
+        let self_id: InodeId = self.metadata()?.inode_id;
+        let target_id: InodeId = target.metadata()?.inode_id;
+        let r = self.inner_inode.exchange(old_name, target, new_name);
+        // 源目录和目标目录的缓存项都要失效
+        dcache().invalidate_dir(self_id);
+        dcache().invalidate_dir(target_id);
+        return r;
     }
 
     fn find(&self, name: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
@@ -281,15 +691,42 @@ impl IndexNode for MountFSInode {
             }
             // 在当前目录下查找
             _ => {
-                // 直接调用当前inode所在的文件系统的find方法进行查找
+                // 先在目录项缓存中查找
+                let parent_id: InodeId = self.metadata()?.inode_id;
+                let cacheable: bool = self.inner_inode.dentry_cacheable();
+                // 缓存中保存的是内层文件系统的inode
+                let cached: Option<Result<Arc<dyn IndexNode>, SystemError>> = if cacheable {
+                    dcache().lookup(parent_id, name)
+                } else {
+                    None
+                };
+
+                let r: Result<Arc<dyn IndexNode>, SystemError> = match cached {
+                    Some(r) => r,
+                    None => {
+                        // 直接调用当前inode所在的文件系统的find方法进行查找
+                        let r = self.inner_inode.find(name);
+                        // 将查找结果加入目录项缓存
+                        match &r {
+                            _ if !cacheable => {}
+                            Ok(inner_inode) => dcache().insert(parent_id, name, Some(inner_inode)),
+                            Err(SystemError::ENOENT) => dcache().insert(parent_id, name, None),
+                            Err(_) => {}
+                        }
+                        r
+                    }
+                };
+
                 // 由于向下查找可能会跨越文件系统的边界，因此需要尝试替换inode
-                return Ok(MountFSInode {
-                    inner_inode: self.inner_inode.find(name)?,
-                    mount_fs: self.mount_fs.clone(),
-                    self_ref: Weak::default(),
-                }
-                .wrap()
-                .overlaid_inode());
+                return r.map(|inner_inode| {
+                    MountFSInode {
+                        inner_inode,
+                        mount_fs: self.mount_fs.clone(),
+                        self_ref: Weak::default(),
+                    }
+                    .wrap()
+                    .overlaid_inode() as Arc<dyn IndexNode>
+                });
             }
         }
     }
@@ -317,6 +754,15 @@ impl IndexNode for MountFSInode {
         return self.inner_inode.list();
     }
 
+    #[inline]
+    fn sync(&self) -> Result<(), SystemError> {
+        return self.inner_inode.sync();
+    }
+
+    fn get_page(&self, index: usize) -> Result<Arc<CachedPage>, SystemError> {
+        return self.inner_inode.get_page(index);
+    }
+
     /// @brief 在当前inode下，挂载一个文件系统
     ///
     /// @return Ok(Arc<MountFS>) 挂载成功，返回指向MountFS的指针
@@ -333,6 +779,8 @@ impl IndexNode for MountFSInode {
             .mountpoints
             .lock()
             .insert(metadata.inode_id, new_mount_fs.clone());
+        // 挂载树发生了变化，缓存中指向挂载点的inode已经过时
+        dcache().invalidate_all();
         return Ok(new_mount_fs);
     }
 }
@@ -355,4 +803,8 @@ impl FileSystem for MountFS {
     fn as_any_ref(&self) -> &dyn Any {
         self
     }
+
+    fn sync(&self) -> Result<(), SystemError> {
+        return self.inner_filesystem.sync();
+    }
 }
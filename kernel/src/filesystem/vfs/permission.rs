@@ -0,0 +1,146 @@
+//! 基于uid/gid和权限位的访问检查
+
+use alloc::sync::Arc;
+
+use crate::{arch::asm::current::current_pcb, process::cred::Cred, syscall::SystemError};
+
+use super::{
+    mount::{inode_mount_flags, MountFlags},
+    syscall::ModeType,
+    FileType, IndexNode, Metadata,
+};
+
+bitflags! {
+    /// 要检查的访问权限（与access系统调用的mode参数的取值相同）
+    pub struct PermissionMask: u32 {
+        /// 可执行（对于目录来说，是可搜索）
+        const MAY_EXEC = 1;
+        /// 可写
+        const MAY_WRITE = 2;
+        /// 可读
+        const MAY_READ = 4;
+    }
+}
+
+/// @brief 根据inode的元数据，检查给定的凭证是否具有指定的访问权限
+///
+/// 超级用户可以读写任何文件，但是只有在文件至少有一个执行权限位被设置（或者文件是目录）时，才可以执行它。
+///
+/// @param metadata inode的元数据
+/// @param cred 进行访问的凭证
+/// @param mask 要检查的访问权限
+///
+/// @return 有权限则返回Ok(())，否则返回EACCES
+pub fn check_permission(
+    metadata: &Metadata,
+    cred: &Cred,
+    mask: PermissionMask,
+) -> Result<(), SystemError> {
+    let mode = metadata.mode;
+
+    if cred.is_root() {
+        if !mask.contains(PermissionMask::MAY_EXEC)
+            || metadata.file_type == FileType::Dir
+            || (mode & (ModeType::S_IXUSR | ModeType::S_IXGRP | ModeType::S_IXOTH).bits()) != 0
+        {
+            return Ok(());
+        }
+        return Err(SystemError::EACCES);
+    }
+
+    // 按照“所有者-组-其他用户”的顺序，选出适用于当前凭证的那一组权限位
+    let granted: u32 = if metadata.uid == cred.euid as usize {
+        (mode >> 6) & 0o7
+    } else if cred.in_group(metadata.gid as u32) {
+        (mode >> 3) & 0o7
+    } else {
+        mode & 0o7
+    };
+
+    if (granted & mask.bits()) == mask.bits() {
+        return Ok(());
+    }
+    return Err(SystemError::EACCES);
+}
+
+/// @brief 检查当前进程是否具有访问inode的指定权限
+///
+/// 如果inode所在的文件系统是以只读方式挂载的，那么普通文件、目录、符号链接都不可写（返回EROFS）
+pub fn inode_permission(
+    inode: &Arc<dyn IndexNode>,
+    mask: PermissionMask,
+) -> Result<(), SystemError> {
+    let metadata: Metadata = inode.metadata()?;
+    if mask.contains(PermissionMask::MAY_WRITE)
+        && matches!(
+            metadata.file_type,
+            FileType::File | FileType::Dir | FileType::SymLink
+        )
+        && inode_mount_flags(inode).contains(MountFlags::RDONLY)
+    {
+        return Err(SystemError::EROFS);
+    }
+    return check_permission(&metadata, &current_pcb().cred(), mask);
+}
+
+/// @brief 检查当前进程是否可以在目录中创建或者删除目录项（需要对目录有写和搜索权限）
+pub fn may_modify_dir(dir: &Arc<dyn IndexNode>) -> Result<(), SystemError> {
+    return inode_permission(dir, PermissionMask::MAY_WRITE | PermissionMask::MAY_EXEC);
+}
+
+/// @brief 检查当前进程是否可以从目录中删除指定的目录项
+///
+/// 除了需要对目录有写和搜索权限之外，如果目录设置了粘滞位，那么只有超级用户、目录的所有者和文件的所有者可以删除它。
+///
+/// @param dir 父目录
+/// @param victim 要被删除的目录项对应的inode
+pub fn may_delete(
+    dir: &Arc<dyn IndexNode>,
+    victim: &Arc<dyn IndexNode>,
+) -> Result<(), SystemError> {
+    may_modify_dir(dir)?;
+
+    let cred = current_pcb().cred();
+    let dir_metadata = dir.metadata()?;
+    if cred.is_root() || (dir_metadata.mode & ModeType::S_ISVTX.bits()) == 0 {
+        return Ok(());
+    }
+
+    let euid = cred.euid as usize;
+    if dir_metadata.uid == euid || victim.metadata()?.uid == euid {
+        return Ok(());
+    }
+    return Err(SystemError::EPERM);
+}
+
+/// @brief 检查当前进程是否为inode的所有者（或者是超级用户），用于chmod等只有所有者才可以进行的操作
+pub fn check_owner(metadata: &Metadata, cred: &Cred) -> Result<(), SystemError> {
+    if cred.is_root() || metadata.uid == cred.euid as usize {
+        return Ok(());
+    }
+    return Err(SystemError::EPERM);
+}
+
+/// @brief 把新创建的inode的所有者设置为当前进程的有效用户ID和有效组ID
+///
+/// 不支持记录所有者的文件系统（比如FAT）会返回EOPNOTSUPP_OR_ENOTSUP，这种情况下保持文件系统的默认值。
+pub fn init_inode_owner(inode: &Arc<dyn IndexNode>) -> Result<(), SystemError> {
+    let cred = current_pcb().cred();
+    let mut metadata = inode.metadata()?;
+    if metadata.uid == cred.euid as usize && metadata.gid == cred.egid as usize {
+        return Ok(());
+    }
+
+    metadata.uid = cred.euid as usize;
+    metadata.gid = cred.egid as usize;
+    match inode.set_metadata(&metadata) {
+        Ok(_) | Err(SystemError::EOPNOTSUPP_OR_ENOTSUP) => return Ok(()),
+        Err(e) => return Err(e),
+    }
+}
+
+/// @brief 计算新创建的文件的权限位（去掉umask中被屏蔽的位）
+#[inline]
+pub fn apply_umask(mode: u32) -> u32 {
+    return mode & 0o7777 & !current_pcb().cred().umask;
+}
@@ -0,0 +1,414 @@
+//! poll机制
+//!
+//! 进程通过poll、select、epoll等待多个文件时，会把一个实现了[`PollWaiter`]的对象注册到这些文件的inode上。
+//! inode的状态发生变化时（例如管道中有了新数据、tty收到了输入、网卡收到了数据包），
+//! inode通过[`PollWaitQueue::wakeup`]回调这些对象，从而唤醒等待的进程，而不需要忙等。
+
+use core::{
+    fmt::Debug,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use alloc::{
+    sync::{Arc, Weak},
+    vec,
+    vec::Vec,
+};
+
+use crate::{
+    arch::{asm::current::current_pcb, sched::sched, CurrentIrqArch},
+    exception::InterruptArch,
+    include::bindings::bindings::PROC_INTERRUPTIBLE,
+    ipc::{
+        signal::{has_sig_pending, set_current_sig_blocked},
+        signal_types::sigset_t,
+    },
+    libs::{spinlock::SpinLock, wait_queue::WaitQueue},
+    syscall::{
+        user_access::{UserBufferReader, UserBufferWriter},
+        SystemError,
+    },
+    time::timer::{clock, next_n_us_timer_jiffies, Timer, WakeUpHelper},
+};
+
+use super::{IndexNode, PollStatus};
+
+bitflags! {
+    /// @brief poll系统调用使用的事件类型（与Linux的定义相同）
+    pub struct PollEvents: u16 {
+        /// 有数据可读
+        const POLLIN = 0x0001;
+        /// 有紧急数据可读
+        const POLLPRI = 0x0002;
+        /// 可以写入数据
+        const POLLOUT = 0x0004;
+        /// 发生错误（总是会被报告）
+        const POLLERR = 0x0008;
+        /// 对端已经挂断（总是会被报告）
+        const POLLHUP = 0x0010;
+        /// 文件描述符无效（总是会被报告）
+        const POLLNVAL = 0x0020;
+        const POLLRDNORM = 0x0040;
+        const POLLRDBAND = 0x0080;
+        const POLLWRNORM = 0x0100;
+        const POLLWRBAND = 0x0200;
+    }
+}
+
+impl From<PollStatus> for PollEvents {
+    fn from(status: PollStatus) -> Self {
+        let mut events = PollEvents::empty();
+        if status.contains(PollStatus::READ) {
+            events.insert(PollEvents::POLLIN | PollEvents::POLLRDNORM);
+        }
+        if status.contains(PollStatus::WRITE) {
+            events.insert(PollEvents::POLLOUT | PollEvents::POLLWRNORM);
+        }
+        if status.contains(PollStatus::ERROR) {
+            events.insert(PollEvents::POLLERR);
+        }
+        return events;
+    }
+}
+
+/// @brief poll系统调用的参数（与Linux的struct pollfd相同）
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PollFd {
+    /// 文件描述符。小于0时，该项会被忽略
+    pub fd: i32,
+    /// 关心的事件
+    pub events: u16,
+    /// 发生的事件（由内核填写）
+    pub revents: u16,
+}
+
+/// @brief 等待inode状态变化的对象
+pub trait PollWaiter: Send + Sync + Debug {
+    /// @brief inode的状态可能发生了变化时，本函数会被调用
+    ///
+    /// 本函数可能在中断上下文中被调用，因此不能睡眠，也不能获取可能被其他进程长时间持有的锁
+    ///
+    /// @param status 回调时inode的状态（仅供参考，等待者应当重新调用inode的poll方法获取准确的状态）
+    fn wakeup(&self, status: PollStatus);
+}
+
+/// @brief inode上的poll等待者列表
+///
+/// 列表中只保存等待者的弱引用，等待者被释放后，它会在下一次注册或者唤醒时被移除，因此不需要手动注销。
+#[derive(Debug)]
+pub struct PollWaitQueue(SpinLock<Vec<Weak<dyn PollWaiter>>>);
+
+impl PollWaitQueue {
+    pub const INIT: PollWaitQueue = PollWaitQueue(SpinLock::new(Vec::new()));
+
+    /// @brief 注册一个等待者
+    pub fn register(&self, waiter: Weak<dyn PollWaiter>) {
+        let mut guard = self.0.lock_irqsave();
+        guard.retain(|w| w.strong_count() > 0);
+        guard.push(waiter);
+    }
+
+    /// @brief 回调所有仍然存活的等待者
+    ///
+    /// @param status inode当前的状态
+    pub fn wakeup(&self, status: PollStatus) {
+        let mut guard = self.0.lock_irqsave();
+        if guard.is_empty() {
+            return;
+        }
+        let mut waiters: Vec<Arc<dyn PollWaiter>> = Vec::with_capacity(guard.len());
+        guard.retain(|w| match w.upgrade() {
+            Some(w) => {
+                waiters.push(w);
+                true
+            }
+            None => false,
+        });
+        // 在锁外进行回调，防止等待者在回调中再次访问本列表而造成死锁
+        drop(guard);
+
+        for w in waiters {
+            w.wakeup(status);
+        }
+    }
+}
+
+/// @brief 在poll、select、epoll_wait中睡眠的进程所使用的等待者
+///
+/// 被任意一个inode回调之后，在它上面睡眠的所有进程都会被唤醒
+#[derive(Debug)]
+pub struct PollSleeper {
+    /// 自从上一次reset以来，是否被回调过
+    woken: AtomicBool,
+    wait_queue: WaitQueue,
+}
+
+impl PollSleeper {
+    pub fn new() -> Arc<Self> {
+        return Arc::new(Self {
+            woken: AtomicBool::new(false),
+            wait_queue: WaitQueue::INIT,
+        });
+    }
+
+    /// @brief 清除唤醒标志。应当在每一轮检查文件状态之前调用
+    #[inline]
+    pub fn reset(&self) {
+        self.woken.store(false, Ordering::SeqCst);
+    }
+
+    /// @brief 让当前进程睡眠，直到被回调、超时，或者收到信号
+    ///
+    /// 如果在reset之后已经被回调过，则不会睡眠，调用者应当重新检查文件的状态
+    ///
+    /// @param deadline 超时的时刻（单位：jiffies）。为None时表示永不超时
+    pub fn sleep(&self, deadline: Option<u64>) {
+        let irq_guard = unsafe { CurrentIrqArch::save_and_disable_irq() };
+        if self.woken.load(Ordering::SeqCst) {
+            return;
+        }
+
+        if let Some(deadline) = deadline {
+            let timer: Arc<Timer> = Timer::new(WakeUpHelper::new(current_pcb()), deadline);
+            timer.activate();
+        }
+        unsafe {
+            self.wait_queue.sleep_without_schedule();
+        }
+        drop(irq_guard);
+        sched();
+    }
+}
+
+impl PollWaiter for PollSleeper {
+    fn wakeup(&self, _status: PollStatus) {
+        self.woken.store(true, Ordering::SeqCst);
+        self.wait_queue.wakeup_all(PROC_INTERRUPTIBLE as u64);
+    }
+}
+
+/// @brief 获取inode的状态
+///
+/// 没有实现poll方法的inode（例如普通文件）被认为总是可读、可写的
+pub fn poll_inode(inode: &Arc<dyn IndexNode>) -> PollStatus {
+    return inode.poll().unwrap_or(PollStatus::READ | PollStatus::WRITE);
+}
+
+/// @brief 判断在等待文件的过程中，是否应该返回
+///
+/// @param deadline 超时的时刻（单位：jiffies）
+///
+/// @return Ok(true) 已经超时
+/// @return Ok(false) 没有超时，可以继续等待
+/// @return Err(EINTR) 收到了信号
+pub fn poll_should_stop(deadline: Option<u64>) -> Result<bool, SystemError> {
+    if let Some(deadline) = deadline {
+        if clock() >= deadline {
+            return Ok(true);
+        }
+    }
+    if has_sig_pending(current_pcb()) {
+        return Err(SystemError::EINTR);
+    }
+    return Ok(false);
+}
+
+/// @brief 等待fds中的文件描述符上发生指定的事件（poll、select的核心逻辑）
+///
+/// @param fds 要等待的文件描述符。返回时，每一项的revents会被填写
+/// @param timeout_us 超时时间（单位：微秒）。为None时表示永不超时，为Some(0)时表示不等待
+///
+/// @return Ok(usize) revents不为0的项的数量。超时时返回0
+/// @return Err(EINTR) 等待的过程中收到了信号
+pub fn do_poll(fds: &mut [PollFd], timeout_us: Option<u64>) -> Result<usize, SystemError> {
+    let sleeper: Arc<PollSleeper> = PollSleeper::new();
+    let waiter: Arc<dyn PollWaiter> = sleeper.clone();
+    let deadline: Option<u64> = timeout_us.map(next_n_us_timer_jiffies);
+    let mut registered = false;
+
+    loop {
+        sleeper.reset();
+        let mut count = 0;
+        for pfd in fds.iter_mut() {
+            pfd.revents = 0;
+            if pfd.fd < 0 {
+                continue;
+            }
+
+            let revents: PollEvents = match current_pcb().get_file_ref_by_fd(pfd.fd) {
+                Some(file) => {
+                    let inode: Arc<dyn IndexNode> = file.inode();
+                    // 只需要在第一轮注册等待者。不支持等待的inode总是就绪的，忽略错误即可
+                    if !registered {
+                        inode.add_poll_waiter(Arc::downgrade(&waiter)).ok();
+                    }
+                    let mask = PollEvents::from_bits_truncate(pfd.events)
+                        | PollEvents::POLLERR
+                        | PollEvents::POLLHUP;
+                    PollEvents::from(poll_inode(&inode)) & mask
+                }
+                None => PollEvents::POLLNVAL,
+            };
+
+            if !revents.is_empty() {
+                pfd.revents = revents.bits();
+                count += 1;
+            }
+        }
+        registered = true;
+
+        if count > 0 || timeout_us == Some(0) || poll_should_stop(deadline)? {
+            return Ok(count);
+        }
+
+        sleeper.sleep(deadline);
+    }
+}
+
+/// fd_set中每个元素的位数
+const FD_SET_BITS: usize = u64::BITS as usize;
+
+/// @brief 从用户空间读取一个fd_set
+///
+/// @param set 用户空间的fd_set指针，可以为空
+/// @param words fd_set的长度（单位：u64）
+fn read_fd_set(set: *const u64, words: usize) -> Result<Option<Vec<u64>>, SystemError> {
+    if set.is_null() {
+        return Ok(None);
+    }
+    let mut result: Vec<u64> = vec![0; words];
+    if words > 0 {
+        let reader = UserBufferReader::new(set, words * core::mem::size_of::<u64>(), true)?;
+        reader.copy_from_user(&mut result, 0)?;
+    }
+    return Ok(Some(result));
+}
+
+/// @brief 把fd_set写回用户空间
+fn write_fd_set(set: *mut u64, data: &Option<Vec<u64>>) -> Result<(), SystemError> {
+    if let Some(data) = data {
+        if !data.is_empty() {
+            let mut writer =
+                UserBufferWriter::new(set, data.len() * core::mem::size_of::<u64>(), true)?;
+            writer.copy_to_user(data, 0)?;
+        }
+    }
+    return Ok(());
+}
+
+#[inline]
+fn fd_set_contains(set: &Option<Vec<u64>>, fd: usize) -> bool {
+    return match set {
+        Some(set) => set[fd / FD_SET_BITS] & (1 << (fd % FD_SET_BITS)) != 0,
+        None => false,
+    };
+}
+
+/// @brief select、pselect6的核心逻辑
+///
+/// @param nfds 要检查的文件描述符的范围：[0, nfds)
+/// @param readfds 用户空间的fd_set，等待可读。返回时，只保留已经可读的文件描述符
+/// @param writefds 用户空间的fd_set，等待可写。返回时，只保留已经可写的文件描述符
+/// @param exceptfds 用户空间的fd_set，等待异常条件。返回时，只保留发生了异常条件的文件描述符
+/// @param timeout_us 超时时间（单位：微秒）。为None时表示永不超时
+///
+/// @return Ok(usize) 三个fd_set中被置位的总位数
+/// @return Err(EBADF) fd_set中包含无效的文件描述符
+/// @return Err(EINTR) 等待的过程中收到了信号
+pub fn do_select(
+    nfds: usize,
+    readfds: *mut u64,
+    writefds: *mut u64,
+    exceptfds: *mut u64,
+    timeout_us: Option<u64>,
+) -> Result<usize, SystemError> {
+    let words = (nfds + FD_SET_BITS - 1) / FD_SET_BITS;
+    let read_set = read_fd_set(readfds, words)?;
+    let write_set = read_fd_set(writefds, words)?;
+    let except_set = read_fd_set(exceptfds, words)?;
+
+    // 把fd_set转换为poll的参数
+    let mut poll_fds: Vec<PollFd> = Vec::new();
+    for fd in 0..nfds {
+        let mut events = PollEvents::empty();
+        if fd_set_contains(&read_set, fd) {
+            events.insert(PollEvents::POLLIN);
+        }
+        if fd_set_contains(&write_set, fd) {
+            events.insert(PollEvents::POLLOUT);
+        }
+        if fd_set_contains(&except_set, fd) {
+            events.insert(PollEvents::POLLPRI);
+        }
+        if !events.is_empty() {
+            poll_fds.push(PollFd {
+                fd: fd as i32,
+                events: events.bits(),
+                revents: 0,
+            });
+        }
+    }
+
+    do_poll(&mut poll_fds, timeout_us)?;
+
+    let result_set = |set: &Option<Vec<u64>>| set.as_ref().map(|_| vec![0u64; words]);
+    let mut read_result = result_set(&read_set);
+    let mut write_result = result_set(&write_set);
+    let mut except_result = result_set(&except_set);
+    let mut count = 0;
+    for pfd in poll_fds.iter() {
+        let revents = PollEvents::from_bits_truncate(pfd.revents);
+        if revents.contains(PollEvents::POLLNVAL) {
+            return Err(SystemError::EBADF);
+        }
+        let events = PollEvents::from_bits_truncate(pfd.events);
+        let fd = pfd.fd as usize;
+        let bit: u64 = 1 << (fd % FD_SET_BITS);
+
+        let checks = [
+            (
+                &mut read_result,
+                PollEvents::POLLIN,
+                PollEvents::POLLIN | PollEvents::POLLHUP | PollEvents::POLLERR,
+            ),
+            (
+                &mut write_result,
+                PollEvents::POLLOUT,
+                PollEvents::POLLOUT | PollEvents::POLLERR,
+            ),
+            (&mut except_result, PollEvents::POLLPRI, PollEvents::POLLPRI),
+        ];
+        for (result, wanted, ready) in checks {
+            if events.contains(wanted) && revents.intersects(ready) {
+                if let Some(result) = result {
+                    result[fd / FD_SET_BITS] |= bit;
+                    count += 1;
+                }
+            }
+        }
+    }
+
+    write_fd_set(readfds, &read_result)?;
+    write_fd_set(writefds, &write_result)?;
+    write_fd_set(exceptfds, &except_result)?;
+    return Ok(count);
+}
+
+/// @brief 在执行f期间，把当前进程的信号屏蔽字临时替换为sigmask（用于pselect6、epoll_pwait）
+///
+/// @param sigmask 新的信号屏蔽字。为None时，不替换信号屏蔽字
+pub fn with_sigmask<F, R>(sigmask: Option<sigset_t>, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let mut sigmask = match sigmask {
+        Some(sigmask) => sigmask,
+        None => return f(),
+    };
+    let mut old: sigset_t = current_pcb().sig_blocked;
+    set_current_sig_blocked(&mut sigmask);
+    let r = f();
+    set_current_sig_blocked(&mut old);
+    return r;
+}
@@ -0,0 +1,164 @@
+//! 内核内部的伪文件系统
+//!
+//! 管道、socket、epoll等inode并不位于任何被挂载的文件系统中。与Linux的pipefs、sockfs、anon_inodefs一样，
+//! 它们的fs()返回一个内核内部的伪文件系统，这样fstatfs、linkat等需要访问inode所在文件系统的操作，
+//! 也能得到一个合法的结果，而不会让内核崩溃。
+//!
+//! 伪文件系统不会被挂载到目录树上，它的根inode是一个空目录。
+
+use core::any::Any;
+
+use alloc::{
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+
+use crate::{syscall::SystemError, time::TimeSpec};
+
+use super::{
+    core::generate_inode_id, FilePrivateData, FileSystem, FileType, FsInfo, IndexNode, Metadata,
+    PollStatus,
+};
+
+/// anon_inodefs的魔数（与Linux相同）
+pub const ANON_INODE_FS_MAGIC: u64 = 0x09041934;
+/// pipefs的魔数（与Linux相同）
+pub const PIPEFS_MAGIC: u64 = 0x50495045;
+/// sockfs的魔数（与Linux相同）
+pub const SOCKFS_MAGIC: u64 = 0x534F434B;
+
+lazy_static! {
+    static ref ANON_INODE_FS: Arc<PseudoFS> = PseudoFS::new("anon_inodefs", ANON_INODE_FS_MAGIC);
+    static ref PIPE_FS: Arc<PseudoFS> = PseudoFS::new("pipefs", PIPEFS_MAGIC);
+    static ref SOCK_FS: Arc<PseudoFS> = PseudoFS::new("sockfs", SOCKFS_MAGIC);
+}
+
+/// @brief 获取匿名inode（epoll等）所属的伪文件系统
+pub fn anon_inode_fs() -> Arc<dyn FileSystem> {
+    return ANON_INODE_FS.clone();
+}
+
+/// @brief 获取匿名管道所属的伪文件系统
+pub fn pipe_fs() -> Arc<dyn FileSystem> {
+    return PIPE_FS.clone();
+}
+
+/// @brief 获取socket所属的伪文件系统
+pub fn sock_fs() -> Arc<dyn FileSystem> {
+    return SOCK_FS.clone();
+}
+
+/// @brief 内核内部的伪文件系统
+#[derive(Debug)]
+pub struct PseudoFS {
+    /// 文件系统的名称
+    name: &'static str,
+    /// 文件系统的魔数
+    magic: u64,
+    /// 根inode（一个空目录）
+    root_inode: Arc<PseudoRootInode>,
+}
+
+impl PseudoFS {
+    pub fn new(name: &'static str, magic: u64) -> Arc<Self> {
+        return Arc::new_cyclic(|self_ref| PseudoFS {
+            name,
+            magic,
+            root_inode: Arc::new(PseudoRootInode {
+                metadata: Metadata {
+                    dev_id: 0,
+                    inode_id: generate_inode_id(),
+                    disk_ino: None,
+                    size: 0,
+                    blk_size: 0,
+                    blocks: 0,
+                    atime: TimeSpec::default(),
+                    mtime: TimeSpec::default(),
+                    ctime: TimeSpec::default(),
+                    file_type: FileType::Dir,
+                    mode: 0o555,
+                    nlinks: 2,
+                    uid: 0,
+                    gid: 0,
+                    raw_dev: 0,
+                },
+                fs: self_ref.clone(),
+            }),
+        });
+    }
+
+    /// @brief 获取文件系统的名称
+    pub fn name(&self) -> &'static str {
+        return self.name;
+    }
+}
+
+impl FileSystem for PseudoFS {
+    fn root_inode(&self) -> Arc<dyn IndexNode> {
+        return self.root_inode.clone();
+    }
+
+    fn info(&self) -> FsInfo {
+        return FsInfo {
+            blk_dev_id: 0,
+            max_name_len: 255,
+            magic: self.magic,
+            block_size: 4096,
+            ..Default::default()
+        };
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// @brief 伪文件系统的根inode
+#[derive(Debug)]
+pub struct PseudoRootInode {
+    metadata: Metadata,
+    fs: Weak<PseudoFS>,
+}
+
+impl IndexNode for PseudoRootInode {
+    fn read_at(
+        &self,
+        _offset: usize,
+        _len: usize,
+        _buf: &mut [u8],
+        _data: &mut FilePrivateData,
+    ) -> Result<usize, SystemError> {
+        return Err(SystemError::EISDIR);
+    }
+
+    fn write_at(
+        &self,
+        _offset: usize,
+        _len: usize,
+        _buf: &[u8],
+        _data: &mut FilePrivateData,
+    ) -> Result<usize, SystemError> {
+        return Err(SystemError::EISDIR);
+    }
+
+    fn poll(&self) -> Result<PollStatus, SystemError> {
+        return Err(SystemError::EISDIR);
+    }
+
+    fn metadata(&self) -> Result<Metadata, SystemError> {
+        return Ok(self.metadata.clone());
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        return self.fs.upgrade().unwrap();
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn list(&self) -> Result<Vec<String>, SystemError> {
+        return Ok(Vec::new());
+    }
+}
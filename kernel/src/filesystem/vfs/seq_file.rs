@@ -0,0 +1,181 @@
+//! 类似于Linux的seq_file的属性文件框架
+//!
+//! procfs、sysfs等伪文件系统中的文件，其内容都是在读取时由内核动态生成的。
+//! 子系统只需要实现[`SeqOperations`]（或者直接使用[`Attribute`]提供的show/store回调），
+//! 然后在对应的文件系统中注册，就能够对外提供可读写的属性文件，而不需要修改文件系统的代码。
+//!
+//! 文件的内容由若干条记录组成，读取时按需逐条生成，因此较大的列表也不需要在打开文件时一次性生成。
+
+use core::fmt::Debug;
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+use crate::syscall::SystemError;
+
+/// @brief 属性文件的操作
+pub trait SeqOperations: Send + Sync + Debug {
+    /// @brief 将第index条记录输出到buf中
+    ///
+    /// 对于只有一条记录的属性文件，只需要处理index为0的情况
+    ///
+    /// @return Ok(true) 成功输出了一条记录
+    /// @return Ok(false) 已经没有更多的记录了
+    fn show(&self, index: usize, buf: &mut String) -> Result<bool, SystemError>;
+
+    /// @brief 向属性文件写入数据
+    ///
+    /// @return 成功时返回被处理的字节数
+    fn store(&self, _buf: &[u8]) -> Result<usize, SystemError> {
+        return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+    }
+}
+
+type ShowFn = Box<dyn Fn(&mut String) -> Result<(), SystemError> + Send + Sync>;
+type StoreFn = Box<dyn Fn(&str) -> Result<(), SystemError> + Send + Sync>;
+
+/// @brief 由一对show/store回调组成的，只有一条记录的属性
+///
+/// ## 示例
+///
+/// ```
+/// let attr = Attribute::new(|buf| {
+///     buf.push_str("1\n");
+///     return Ok(());
+/// })
+/// .with_store(|value| {
+///     let _v: usize = value.trim().parse().map_err(|_| SystemError::EINVAL)?;
+///     return Ok(());
+/// });
+/// ```
+pub struct Attribute {
+    show: ShowFn,
+    store: Option<StoreFn>,
+}
+
+impl Attribute {
+    /// @brief 创建一个只读的属性
+    pub fn new<F>(show: F) -> Self
+    where
+        F: Fn(&mut String) -> Result<(), SystemError> + Send + Sync + 'static,
+    {
+        return Self {
+            show: Box::new(show),
+            store: None,
+        };
+    }
+
+    /// @brief 为属性设置写入时的回调
+    pub fn with_store<F>(mut self, store: F) -> Self
+    where
+        F: Fn(&str) -> Result<(), SystemError> + Send + Sync + 'static,
+    {
+        self.store = Some(Box::new(store));
+        return self;
+    }
+}
+
+impl Debug for Attribute {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Attribute")
+            .field("writable", &self.store.is_some())
+            .finish()
+    }
+}
+
+impl SeqOperations for Attribute {
+    fn show(&self, index: usize, buf: &mut String) -> Result<bool, SystemError> {
+        if index != 0 {
+            return Ok(false);
+        }
+        (self.show)(buf)?;
+        return Ok(true);
+    }
+
+    fn store(&self, buf: &[u8]) -> Result<usize, SystemError> {
+        let store: &StoreFn = self
+            .store
+            .as_ref()
+            .ok_or(SystemError::EOPNOTSUPP_OR_ENOTSUP)?;
+        let value: &str = core::str::from_utf8(buf).map_err(|_| SystemError::EINVAL)?;
+        store(value)?;
+        return Ok(buf.len());
+    }
+}
+
+/// @brief 属性文件被打开后的读取状态（保存在文件的私有数据中）
+#[derive(Debug, Clone, Default)]
+pub struct SeqFileState {
+    /// 下一条要生成的记录
+    next_index: usize,
+    /// 已经生成，但还没有被读取完的数据
+    buf: Vec<u8>,
+    /// buf中第一个字节在文件中的偏移量
+    buf_start: usize,
+    /// 是否已经生成了所有的记录
+    eof: bool,
+}
+
+impl SeqFileState {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// @brief 生成下一条记录
+    ///
+    /// @return 没有更多的记录时，返回Ok(false)
+    fn fill_next(&mut self, ops: &dyn SeqOperations) -> Result<bool, SystemError> {
+        if self.eof {
+            return Ok(false);
+        }
+        let mut record = String::new();
+        if !ops.show(self.next_index, &mut record)? {
+            self.eof = true;
+            return Ok(false);
+        }
+        self.next_index += 1;
+        self.buf.extend_from_slice(record.as_bytes());
+        return Ok(true);
+    }
+}
+
+/// @brief 从属性文件的offset处，读取最多len字节的数据
+///
+/// 顺序读取时，每次只会生成所需要的记录。若向前seek，则从第一条记录开始重新生成。
+pub fn seq_read(
+    ops: &dyn SeqOperations,
+    state: &mut SeqFileState,
+    offset: usize,
+    len: usize,
+    buf: &mut [u8],
+) -> Result<usize, SystemError> {
+    if buf.len() < len {
+        return Err(SystemError::EINVAL);
+    }
+
+    if offset < state.buf_start {
+        *state = SeqFileState::new();
+    }
+
+    // 丢弃offset之前的数据，直到缓冲区中包含offset处的数据
+    while offset >= state.buf_start + state.buf.len() {
+        state.buf_start += state.buf.len();
+        state.buf.clear();
+        if !state.fill_next(ops)? {
+            return Ok(0);
+        }
+    }
+    state.buf.drain(..offset - state.buf_start);
+    state.buf_start = offset;
+
+    // 生成足够的记录
+    while state.buf.len() < len && state.fill_next(ops)? {}
+
+    let n = state.buf.len().min(len);
+    buf[0..n].copy_from_slice(&state.buf[0..n]);
+    return Ok(n);
+}
+
+/// @brief 向属性文件写入数据
+pub fn seq_write(ops: &dyn SeqOperations, buf: &[u8]) -> Result<usize, SystemError> {
+    return ops.store(buf);
+}
@@ -1,21 +1,43 @@
-use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
 
 use crate::{
     arch::asm::current::current_pcb,
     driver::base::block::SeekFrom,
     filesystem::vfs::file::FileDescriptorVec,
-    include::bindings::bindings::{verify_area, AT_REMOVEDIR, PAGE_4K_SIZE, PROC_MAX_FD_NUM},
+    include::bindings::bindings::{
+        verify_area, AT_EACCESS, AT_EMPTY_PATH, AT_FDCWD, AT_REMOVEDIR, AT_SYMLINK_FOLLOW,
+        AT_SYMLINK_NOFOLLOW, PROC_MAX_FD_NUM,
+    },
+    ipc::signal_types::sigset_t,
     kerror,
-    syscall::{Syscall, SystemError},
-    time::TimeSpec,
+    mm::{page_cache::page_cache_sync_all, verify_area_writable, VirtAddr},
+    process::cwd::WorkingDir,
+    syscall::{
+        user_access::{UserBufferReader, UserBufferWriter},
+        Syscall, SystemError,
+    },
+    time::{syscall::PosixTimeval, TimeSpec},
 };
 
 use super::{
-    core::{do_mkdir, do_remove_dir, do_unlink_at},
+    core::{
+        do_link_at, do_link_inode, do_mkdir_at, do_mount, do_remove_dir, do_rename_at, do_umount2,
+        do_unlink_at,
+    },
     fcntl::{FcntlCommand, FD_CLOEXEC},
     file::{File, FileMode},
-    utils::rsplit_path,
-    Dirent, FileType, IndexNode, ROOT_INODE,
+    lock::{
+        file_lock_set, file_lock_test, file_unlock, FileLock, FileLockOwner, FileLockType,
+        FILE_LOCK_EOF, F_RDLCK, F_UNLCK, F_WRLCK, LOCK_EX, LOCK_NB, LOCK_SH, LOCK_UN,
+    },
+    mount::{inode_mount_flags, mount_list, MountFlags, UmountFlags},
+    permission::{
+        apply_umask, check_owner, check_permission, init_inode_owner, inode_permission,
+        may_modify_dir, PermissionMask,
+    },
+    poll::{do_poll, do_select, with_sigmask, PollFd},
+    utils::{user_path_at, user_path_empty_at, user_path_string_at},
+    Dirent, FileSystem, FileType, FsInfo, IndexNode, InodeId, Metadata, RenameFlags, MAX_PATHLEN,
 };
 
 pub const SEEK_SET: u32 = 0;
@@ -119,22 +141,268 @@ impl PosixKstat {
         }
     }
 }
+impl PosixKstat {
+    /// @brief 根据inode的元数据，填写文件信息结构体
+    fn from_metadata(metadata: &Metadata) -> Self {
+        let mut kstat = PosixKstat::new();
+        kstat.size = metadata.size as i64;
+        kstat.dev_id = metadata.dev_id as u64;
+        kstat.inode = metadata.ino();
+        kstat.blcok_size = metadata.blk_size as i64;
+        kstat.blocks = metadata.blocks as u64;
+
+        kstat.atime.tv_sec = metadata.atime.tv_sec;
+        kstat.atime.tv_nsec = metadata.atime.tv_nsec;
+        kstat.mtime.tv_sec = metadata.mtime.tv_sec;
+        kstat.mtime.tv_nsec = metadata.mtime.tv_nsec;
+        kstat.ctime.tv_sec = metadata.ctime.tv_sec;
+        kstat.ctime.tv_nsec = metadata.ctime.tv_nsec;
+
+        kstat.nlink = metadata.nlinks as u64;
+        kstat.uid = metadata.uid as i32;
+        kstat.gid = metadata.gid as i32;
+        kstat.rdev = metadata.raw_dev as i64;
+        kstat.mode = ModeType::from_metadata(metadata);
+        return kstat;
+    }
+}
+
+impl ModeType {
+    /// @brief 根据inode的元数据，生成包含文件类型位和权限位的mode
+    fn from_metadata(metadata: &Metadata) -> Self {
+        let mut mode = ModeType::from_bits_truncate(metadata.mode);
+        match metadata.file_type {
+            FileType::File => mode.insert(ModeType::S_IFREG),
+            FileType::Dir => mode.insert(ModeType::S_IFDIR),
+            FileType::BlockDevice => mode.insert(ModeType::S_IFBLK),
+            FileType::CharDevice => mode.insert(ModeType::S_IFCHR),
+            FileType::SymLink => mode.insert(ModeType::S_IFLNK),
+            FileType::Socket => mode.insert(ModeType::S_IFSOCK),
+            FileType::Pipe => mode.insert(ModeType::S_IFIFO),
+            // 与Linux一致，匿名inode的mode中没有文件类型位
+            FileType::AnonInode => {}
+        }
+        return mode;
+    }
+}
+
+/// statx系统调用的mask参数：请求获取的字段
+pub const STATX_TYPE: u32 = 0x0001;
+pub const STATX_MODE: u32 = 0x0002;
+pub const STATX_NLINK: u32 = 0x0004;
+pub const STATX_UID: u32 = 0x0008;
+pub const STATX_GID: u32 = 0x0010;
+pub const STATX_ATIME: u32 = 0x0020;
+pub const STATX_MTIME: u32 = 0x0040;
+pub const STATX_CTIME: u32 = 0x0080;
+pub const STATX_INO: u32 = 0x0100;
+pub const STATX_SIZE: u32 = 0x0200;
+pub const STATX_BLOCKS: u32 = 0x0400;
+/// stat系统调用能获取到的所有字段
+pub const STATX_BASIC_STATS: u32 = 0x07ff;
+/// 保留给将来扩展statx结构体使用，用户不能设置这个位
+pub const STATX__RESERVED: u32 = 0x8000_0000;
+
+/// statx的flags参数中，不自动挂载路径的最后一级（目前没有自动挂载，因此忽略它）
+pub const AT_NO_AUTOMOUNT: u32 = 0x800;
+/// statx的flags参数中，与网络文件系统同步的方式（本地文件系统忽略它）
+pub const AT_STATX_SYNC_TYPE: u32 = 0x6000;
+
+/// utimensat的时间参数：设置为当前时间
+pub const UTIME_NOW: i64 = (1 << 30) - 1;
+/// utimensat的时间参数：不修改这个时间
+pub const UTIME_OMIT: i64 = (1 << 30) - 2;
+
+/// statx结构体中的时间戳
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PosixStatxTimestamp {
+    pub tv_sec: i64,
+    pub tv_nsec: u32,
+    pub __reserved: i32,
+}
+
+impl From<&TimeSpec> for PosixStatxTimestamp {
+    fn from(ts: &TimeSpec) -> Self {
+        return Self {
+            tv_sec: ts.tv_sec,
+            tv_nsec: ts.tv_nsec as u32,
+            __reserved: 0,
+        };
+    }
+}
+
+/// # statx系统调用使用的文件信息结构体（与Linux的struct statx相同，共256字节）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PosixStatx {
+    /// 结构体中已经被填写的字段
+    pub mask: u32,
+    /// 文件系统块大小
+    pub blksize: u32,
+    /// 文件的属性（STATX_ATTR_*）
+    pub attributes: u64,
+    /// 硬链接数
+    pub nlink: u32,
+    /// 所有者用户ID
+    pub uid: u32,
+    /// 所有者组ID
+    pub gid: u32,
+    /// 文件类型和权限
+    pub mode: u16,
+    pub __spare0: u16,
+    /// inode号
+    pub ino: u64,
+    /// 文件大小
+    pub size: u64,
+    /// 分配的512B块数
+    pub blocks: u64,
+    /// attributes中有效的位
+    pub attributes_mask: u64,
+    /// 最后访问时间
+    pub atime: PosixStatxTimestamp,
+    /// 创建时间
+    pub btime: PosixStatxTimestamp,
+    /// 最后状态变化时间
+    pub ctime: PosixStatxTimestamp,
+    /// 最后修改时间
+    pub mtime: PosixStatxTimestamp,
+    /// 设备文件的主设备号
+    pub rdev_major: u32,
+    /// 设备文件的次设备号
+    pub rdev_minor: u32,
+    /// 文件所在设备的主设备号
+    pub dev_major: u32,
+    /// 文件所在设备的次设备号
+    pub dev_minor: u32,
+    pub __spare2: [u64; 14],
+}
+
+impl PosixStatx {
+    /// @brief 根据inode的元数据，填写statx结构体
+    ///
+    /// 元数据中已经包含了stat的所有字段，因此总是返回STATX_BASIC_STATS，不论用户请求了哪些字段
+    fn from_metadata(metadata: &Metadata) -> Self {
+        let mut statx = PosixStatx::default();
+        statx.mask = STATX_BASIC_STATS;
+        statx.blksize = metadata.blk_size as u32;
+        statx.nlink = metadata.nlinks as u32;
+        statx.uid = metadata.uid as u32;
+        statx.gid = metadata.gid as u32;
+        statx.mode = ModeType::from_metadata(metadata).bits() as u16;
+        statx.ino = metadata.ino();
+        statx.size = metadata.size as u64;
+        statx.blocks = metadata.blocks as u64;
+        statx.atime = PosixStatxTimestamp::from(&metadata.atime);
+        statx.ctime = PosixStatxTimestamp::from(&metadata.ctime);
+        statx.mtime = PosixStatxTimestamp::from(&metadata.mtime);
+        (statx.rdev_major, statx.rdev_minor) = Self::split_dev(metadata.raw_dev as u64);
+        (statx.dev_major, statx.dev_minor) = Self::split_dev(metadata.dev_id as u64);
+        return statx;
+    }
+
+    /// @brief 按照Linux的编码方式，把设备号拆分为主设备号和次设备号
+    fn split_dev(dev: u64) -> (u32, u32) {
+        let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+        let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+        return (major as u32, minor as u32);
+    }
+}
+
+/// # statfs系统调用使用的文件系统信息结构体（与Linux x86_64的struct statfs相同）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PosixStatfs {
+    /// 文件系统的类型（魔数）
+    pub f_type: i64,
+    /// 块大小
+    pub f_bsize: i64,
+    /// 总的块数
+    pub f_blocks: u64,
+    /// 空闲的块数
+    pub f_bfree: u64,
+    /// 普通用户可用的空闲块数
+    pub f_bavail: u64,
+    /// 总的inode数
+    pub f_files: u64,
+    /// 空闲的inode数
+    pub f_ffree: u64,
+    /// 文件系统ID
+    pub f_fsid: [i32; 2],
+    /// 文件名的最大长度
+    pub f_namelen: i64,
+    /// 片段大小
+    pub f_frsize: i64,
+    /// 挂载标志（ST_*，与MountFlags中对应的位相同）
+    pub f_flags: i64,
+    pub f_spare: [i64; 4],
+}
+
+impl PosixStatfs {
+    /// @brief 根据文件系统的信息和挂载标志，填写statfs结构体
+    fn new(info: &FsInfo, mount_flags: MountFlags) -> Self {
+        // 只有这些挂载标志会被报告给用户
+        let reported = MountFlags::RDONLY
+            | MountFlags::NOSUID
+            | MountFlags::NODEV
+            | MountFlags::NOEXEC
+            | MountFlags::SYNCHRONOUS
+            | MountFlags::NOATIME;
+
+        let mut statfs = PosixStatfs::default();
+        statfs.f_type = info.magic as i64;
+        statfs.f_bsize = info.block_size as i64;
+        statfs.f_blocks = info.total_blocks;
+        statfs.f_bfree = info.free_blocks;
+        statfs.f_bavail = info.avail_blocks;
+        statfs.f_files = info.total_inodes;
+        statfs.f_ffree = info.free_inodes;
+        statfs.f_fsid = [info.blk_dev_id as i32, (info.blk_dev_id >> 32) as i32];
+        statfs.f_namelen = info.max_name_len as i64;
+        statfs.f_frsize = info.block_size as i64;
+        statfs.f_flags = (mount_flags & reported).bits() as i64;
+        return statfs;
+    }
+}
+
 impl Syscall {
     /// @brief 为当前进程打开一个文件
     ///
     /// @param path 文件路径
     /// @param o_flags 打开文件的标志位
+    /// @param create_mode 创建文件时，新文件的权限位（会被umask屏蔽）
     ///
     /// @return 文件描述符编号，或者是错误码
-    pub fn open(path: &str, mode: FileMode) -> Result<usize, SystemError> {
-        // kdebug!("open: path: {}, mode: {:?}", path, mode);
-        // 文件名过长
-        if path.len() > PAGE_4K_SIZE as usize {
-            return Err(SystemError::ENAMETOOLONG);
-        }
+    pub fn open(path: &str, mode: FileMode, create_mode: u32) -> Result<usize, SystemError> {
+        return Self::openat(AT_FDCWD, path, mode, create_mode);
+    }
+
+    /// @brief 为当前进程打开一个文件。相对路径将从dirfd所指向的目录开始解析
+    ///
+    /// @param dirfd 目录的文件描述符（可以为AT_FDCWD）
+    /// @param path 文件路径
+    /// @param o_flags 打开文件的标志位
+    /// @param create_mode 创建文件时，新文件的权限位（会被umask屏蔽）
+    ///
+    /// @return 文件描述符编号，或者是错误码
+    pub fn openat(
+        dirfd: i32,
+        path: &str,
+        mode: FileMode,
+        create_mode: u32,
+    ) -> Result<usize, SystemError> {
+        let (base, path) = user_path_at(dirfd, path)?;
 
-        let inode: Result<Arc<dyn IndexNode>, SystemError> = ROOT_INODE().lookup(path);
+        // 如果指定了O_NOFOLLOW，则不跟随路径最后一级的符号链接
+        let inode: Result<Arc<dyn IndexNode>, SystemError> = if mode.contains(FileMode::O_NOFOLLOW)
+        {
+            base.lookup_nofollow(path)
+        } else {
+            base.lookup(path)
+        };
 
+        // 新创建的文件不需要再检查访问权限（与Linux相同，即使新文件的权限位不允许写入，本次打开也能成功）
+        let mut created = false;
         let inode: Arc<dyn IndexNode> = if inode.is_err() {
             let errno = inode.unwrap_err();
             // 文件不存在，且需要创建
@@ -142,13 +410,14 @@ impl Syscall {
                 && !mode.contains(FileMode::O_DIRECTORY)
                 && errno == SystemError::ENOENT
             {
-                let (filename, parent_path) = rsplit_path(path);
                 // 查找父目录
-                let parent_inode: Arc<dyn IndexNode> =
-                    ROOT_INODE().lookup(parent_path.unwrap_or("/"))?;
+                let (parent_inode, filename) = base.lookup_parent(path)?;
+                may_modify_dir(&parent_inode)?;
                 // 创建文件
                 let inode: Arc<dyn IndexNode> =
-                    parent_inode.create(filename, FileType::File, 0o777)?;
+                    parent_inode.create(filename, FileType::File, apply_umask(create_mode))?;
+                init_inode_owner(&inode)?;
+                created = true;
                 inode
             } else {
                 // 不需要创建文件，因此返回错误码
@@ -159,11 +428,25 @@ impl Syscall {
         };
 
         let file_type: FileType = inode.metadata()?.file_type;
+        // 不能打开符号链接本身（只有指定了O_NOFOLLOW时，才会走到这里）
+        if file_type == FileType::SymLink {
+            return Err(SystemError::ELOOP);
+        }
         // 如果要打开的是文件夹，而目标不是文件夹
         if mode.contains(FileMode::O_DIRECTORY) && file_type != FileType::Dir {
             return Err(SystemError::ENOTDIR);
         }
 
+        // 检查当前进程对文件的访问权限
+        if !created {
+            let mask = match mode & FileMode::O_ACCMODE {
+                FileMode::O_WRONLY => PermissionMask::MAY_WRITE,
+                FileMode::O_RDWR => PermissionMask::MAY_READ | PermissionMask::MAY_WRITE,
+                _ => PermissionMask::MAY_READ,
+            };
+            inode_permission(&inode, mask)?;
+        }
+
         // 如果O_TRUNC，并且，打开模式包含O_RDWR或O_WRONLY，清空文件
         if mode.contains(FileMode::O_TRUNC)
             && (mode.contains(FileMode::O_RDWR) || mode.contains(FileMode::O_WRONLY))
@@ -174,6 +457,7 @@ impl Syscall {
 
         // 创建文件对象
         let mut file: File = File::new(inode, mode)?;
+        file.set_path(user_path_string_at(dirfd, path));
 
         // 打开模式为“追加”
         if mode.contains(FileMode::O_APPEND) {
@@ -232,6 +516,139 @@ impl Syscall {
         return file.write(buf.len(), buf);
     }
 
+    /// @brief 根据文件描述符，把文件数据依次读取到多个用户缓冲区中
+    ///
+    /// @param fd 文件描述符编号
+    /// @param iov 用户空间的IoVec数组
+    /// @param iovcnt IoVec的数量
+    ///
+    /// @return Ok(usize) 成功读取的数据的字节数
+    /// @return Err(SystemError) 读取失败，返回posix错误码
+    pub fn readv(fd: i32, iov: *const IoVec, iovcnt: usize) -> Result<usize, SystemError> {
+        // 检查每个缓冲区地址是否合法，生成iovecs
+        let mut iovs = unsafe { IoVecs::from_user(iov, iovcnt, true)? };
+        let mut buf = iovs.new_buf(true);
+
+        let file: &mut File = current_pcb()
+            .get_file_mut_by_fd(fd)
+            .ok_or(SystemError::EBADF)?;
+        let n = file.read(buf.len(), &mut buf)?;
+
+        iovs.scatter(&buf[..n]);
+        return Ok(n);
+    }
+
+    /// @brief 根据文件描述符，把多个用户缓冲区中的数据依次写入文件
+    ///
+    /// @param fd 文件描述符编号
+    /// @param iov 用户空间的IoVec数组
+    /// @param iovcnt IoVec的数量
+    ///
+    /// @return Ok(usize) 成功写入的数据的字节数
+    /// @return Err(SystemError) 写入失败，返回posix错误码
+    pub fn writev(fd: i32, iov: *const IoVec, iovcnt: usize) -> Result<usize, SystemError> {
+        let iovs = unsafe { IoVecs::from_user(iov, iovcnt, false)? };
+        let data = iovs.gather();
+
+        let file: &mut File = current_pcb()
+            .get_file_mut_by_fd(fd)
+            .ok_or(SystemError::EBADF)?;
+        return file.write(data.len(), &data);
+    }
+
+    /// @brief 从文件的指定位置读取数据，不改变文件指针
+    ///
+    /// @param fd 文件描述符编号
+    /// @param buf 输出缓冲区
+    /// @param offset 开始读取的位置
+    ///
+    /// @return Ok(usize) 成功读取的数据的字节数
+    /// @return Err(SystemError) 读取失败，返回posix错误码
+    pub fn pread64(fd: i32, buf: &mut [u8], offset: i64) -> Result<usize, SystemError> {
+        if offset < 0 {
+            return Err(SystemError::EINVAL);
+        }
+        let file: &mut File = current_pcb()
+            .get_file_mut_by_fd(fd)
+            .ok_or(SystemError::EBADF)?;
+        return file.pread(offset as usize, buf.len(), buf);
+    }
+
+    /// @brief 向文件的指定位置写入数据，不改变文件指针
+    ///
+    /// @param fd 文件描述符编号
+    /// @param buf 输入缓冲区
+    /// @param offset 开始写入的位置
+    ///
+    /// @return Ok(usize) 成功写入的数据的字节数
+    /// @return Err(SystemError) 写入失败，返回posix错误码
+    pub fn pwrite64(fd: i32, buf: &[u8], offset: i64) -> Result<usize, SystemError> {
+        if offset < 0 {
+            return Err(SystemError::EINVAL);
+        }
+        let file: &mut File = current_pcb()
+            .get_file_mut_by_fd(fd)
+            .ok_or(SystemError::EBADF)?;
+        return file.pwrite(offset as usize, buf.len(), buf);
+    }
+
+    /// @brief 从文件的指定位置读取数据到多个用户缓冲区中，不改变文件指针
+    ///
+    /// @param fd 文件描述符编号
+    /// @param iov 用户空间的IoVec数组
+    /// @param iovcnt IoVec的数量
+    /// @param offset 开始读取的位置
+    ///
+    /// @return Ok(usize) 成功读取的数据的字节数
+    /// @return Err(SystemError) 读取失败，返回posix错误码
+    pub fn preadv(
+        fd: i32,
+        iov: *const IoVec,
+        iovcnt: usize,
+        offset: i64,
+    ) -> Result<usize, SystemError> {
+        if offset < 0 {
+            return Err(SystemError::EINVAL);
+        }
+        let mut iovs = unsafe { IoVecs::from_user(iov, iovcnt, true)? };
+        let mut buf = iovs.new_buf(true);
+
+        let file: &mut File = current_pcb()
+            .get_file_mut_by_fd(fd)
+            .ok_or(SystemError::EBADF)?;
+        let n = file.pread(offset as usize, buf.len(), &mut buf)?;
+
+        iovs.scatter(&buf[..n]);
+        return Ok(n);
+    }
+
+    /// @brief 把多个用户缓冲区中的数据写入到文件的指定位置，不改变文件指针
+    ///
+    /// @param fd 文件描述符编号
+    /// @param iov 用户空间的IoVec数组
+    /// @param iovcnt IoVec的数量
+    /// @param offset 开始写入的位置
+    ///
+    /// @return Ok(usize) 成功写入的数据的字节数
+    /// @return Err(SystemError) 写入失败，返回posix错误码
+    pub fn pwritev(
+        fd: i32,
+        iov: *const IoVec,
+        iovcnt: usize,
+        offset: i64,
+    ) -> Result<usize, SystemError> {
+        if offset < 0 {
+            return Err(SystemError::EINVAL);
+        }
+        let iovs = unsafe { IoVecs::from_user(iov, iovcnt, false)? };
+        let data = iovs.gather();
+
+        let file: &mut File = current_pcb()
+            .get_file_mut_by_fd(fd)
+            .ok_or(SystemError::EBADF)?;
+        return file.pwrite(offset as usize, data.len(), &data);
+    }
+
     /// @brief 调整文件操作指针的位置
     ///
     /// @param fd 文件描述符编号
@@ -272,29 +689,17 @@ impl Syscall {
     ///  
     /// ENAMETOOLONG |        路径过长        
     pub fn chdir(dest_path: &str) -> Result<usize, SystemError> {
-        // Copy path to kernel space to avoid some security issues
-        let path: Box<&str> = Box::new(dest_path);
-        let inode = match ROOT_INODE().lookup(&path) {
-            Err(e) => {
-                kerror!("Change Directory Failed, Error = {:?}", e);
-                return Err(SystemError::ENOENT);
-            }
-            Ok(i) => i,
-        };
-
-        match inode.metadata() {
-            Err(e) => {
-                kerror!("INode Get MetaData Failed, Error = {:?}", e);
-                return Err(SystemError::ENOENT);
-            }
-            Ok(i) => {
-                if let FileType::Dir = i.file_type {
-                    return Ok(0);
-                } else {
-                    return Err(SystemError::ENOTDIR);
-                }
-            }
+        let (base, path) = user_path_at(AT_FDCWD, dest_path)?;
+        let inode: Arc<dyn IndexNode> = base.lookup(path)?;
+        if inode.metadata()?.file_type != FileType::Dir {
+            return Err(SystemError::ENOTDIR);
         }
+        // 进入目录需要对它有搜索权限
+        inode_permission(&inode, PermissionMask::MAY_EXEC)?;
+
+        let path: String = user_path_string_at(AT_FDCWD, path).ok_or(SystemError::ENOENT)?;
+        current_pcb().set_cwd(WorkingDir::new(inode, path));
+        return Ok(0);
     }
 
     /// @brief 获取目录中的数据
@@ -331,19 +736,30 @@ impl Syscall {
     ///
     /// @return uint64_t 负数错误码 / 0表示成功
     pub fn mkdir(path: &str, mode: usize) -> Result<usize, SystemError> {
-        return do_mkdir(path, FileMode::from_bits_truncate(mode as u32)).map(|x| x as usize);
+        return Self::mkdirat(AT_FDCWD, path, mode);
+    }
+
+    /// @brief 创建文件夹。相对路径将从dirfd所指向的目录开始解析
+    ///
+    /// @param dirfd 目录的文件描述符（可以为AT_FDCWD）
+    /// @param path 文件夹的路径
+    /// @param mode 模式
+    ///
+    /// @return 成功返回0，失败返回错误码
+    pub fn mkdirat(dirfd: i32, path: &str, mode: usize) -> Result<usize, SystemError> {
+        return do_mkdir_at(dirfd, path, mode as u32).map(|x| x as usize);
     }
 
     /// **删除文件夹、取消文件的链接、删除文件的系统调用**
     ///
     /// ## 参数
     ///
-    /// - `dirfd`：文件夹的文件描述符.目前暂未实现
+    /// - `dirfd`：解析相对路径时的起始目录的文件描述符（可以为AT_FDCWD）
     /// - `pathname`：文件夹的路径
     /// - `flags`：标志位
     ///
     ///
-    pub fn unlinkat(_dirfd: i32, pathname: &str, flags: u32) -> Result<usize, SystemError> {
+    pub fn unlinkat(dirfd: i32, pathname: &str, flags: u32) -> Result<usize, SystemError> {
         // kdebug!("sys_unlink_at={path:?}");
         if (flags & (!AT_REMOVEDIR)) != 0 {
             return Err(SystemError::EINVAL);
@@ -351,7 +767,7 @@ impl Syscall {
 
         if (flags & AT_REMOVEDIR) > 0 {
             // kdebug!("rmdir");
-            match do_remove_dir(&pathname) {
+            match do_remove_dir(dirfd, &pathname) {
                 Err(err) => {
                     kerror!("Failed to Remove Directory, Error Code = {:?}", err);
                     return Err(err);
@@ -362,7 +778,7 @@ impl Syscall {
             }
         }
 
-        match do_unlink_at(&pathname, FileMode::from_bits_truncate(flags as u32)) {
+        match do_unlink_at(dirfd, &pathname, FileMode::from_bits_truncate(flags as u32)) {
             Err(err) => {
                 kerror!("Failed to Remove Directory, Error Code = {:?}", err);
                 return Err(err);
@@ -373,6 +789,24 @@ impl Syscall {
         }
     }
 
+    /// # unlink
+    ///
+    /// ## 描述
+    ///
+    /// 删除文件的一个目录项。如果这是文件的最后一个硬链接，并且文件没有被打开，那么文件的数据会被释放；
+    /// 如果文件仍被打开，那么数据要等到最后一个文件描述符被关闭时才会被释放。
+    ///
+    /// ## 参数
+    ///
+    /// - `pathname`：文件的路径
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn unlink(pathname: &str) -> Result<usize, SystemError> {
+        return Self::unlinkat(AT_FDCWD, pathname, 0);
+    }
+
     /// @brief 根据提供的文件描述符的fd，复制对应的文件结构体，并返回新复制的文件结构体对应的fd
     pub fn dup(oldfd: i32) -> Result<usize, SystemError> {
         if let Some(fds) = FileDescriptorVec::from_pcb(current_pcb()) {
@@ -543,131 +977,1280 @@ impl Syscall {
         }
     }
 
-    /// # ftruncate
+    /// # fcntl_lock
     ///
     /// ## 描述
     ///
-    /// 改变文件大小.
-    /// 如果文件大小大于原来的大小，那么文件的内容将会被扩展到指定的大小，新的空间将会用0填充.
-    /// 如果文件大小小于原来的大小，那么文件的内容将会被截断到指定的大小.
+    /// 处理fcntl的F_GETLK、F_SETLK、F_SETLKW命令，对文件的一段字节进行加锁、解锁或者测试能否加锁。
     ///
     /// ## 参数
     ///
     /// - `fd`：文件描述符
-    /// - `len`：文件大小
+    /// - `cmd`：fcntl命令，只能是GetLock、SetLock、SetLockWait
+    /// - `flock`：用户空间的PosixFlock结构体。对于F_GETLK，冲突的锁的信息会被写回这个结构体
     ///
     /// ## 返回值
     ///
     /// 如果成功，返回0，否则返回错误码.
-    pub fn ftruncate(fd: i32, len: usize) -> Result<usize, SystemError> {
-        if let Some(fds) = FileDescriptorVec::from_pcb(current_pcb()) {
-            if FileDescriptorVec::validate_fd(fd) {
-                if let Some(file) = &mut fds.fds[fd as usize] {
-                    let r = file.ftruncate(len).map(|_| 0);
-                    return r;
-                }
-                return Err(SystemError::EBADF);
-            }
+    pub fn fcntl_lock(
+        fd: i32,
+        cmd: FcntlCommand,
+        flock: *mut PosixFlock,
+    ) -> Result<usize, SystemError> {
+        let file: &mut File = current_pcb()
+            .get_file_mut_by_fd(fd)
+            .ok_or(SystemError::EBADF)?;
+        let user_flock: PosixFlock = unsafe { *flock };
+
+        let lock_type: Option<FileLockType> = match user_flock.l_type {
+            F_RDLCK => Some(FileLockType::Read),
+            F_WRLCK => Some(FileLockType::Write),
+            F_UNLCK => None,
+            _ => return Err(SystemError::EINVAL),
+        };
+
+        // 计算锁定范围
+        let base: i64 = match user_flock.l_whence as u32 {
+            SEEK_SET => 0,
+            SEEK_CUR => file.lseek(SeekFrom::SeekCurrent(0))? as i64,
+            SEEK_END => file.metadata()?.size,
+            _ => return Err(SystemError::EINVAL),
+        };
+        let start: i64 = base
+            .checked_add(user_flock.l_start)
+            .ok_or(SystemError::EOVERFLOW)?;
+        let (start, end): (i64, u64) = if user_flock.l_len > 0 {
+            let end = start
+                .checked_add(user_flock.l_len - 1)
+                .ok_or(SystemError::EOVERFLOW)?;
+            (start, end as u64)
+        } else if user_flock.l_len == 0 {
+            (start, FILE_LOCK_EOF)
+        } else {
+            // l_len为负数时，锁定的范围是[start+l_len, start-1]
+            (start + user_flock.l_len, (start - 1) as u64)
+        };
+        if start < 0 {
+            return Err(SystemError::EINVAL);
         }
-        return Err(SystemError::EBADF);
-    }
-    fn do_fstat(fd: i32) -> Result<PosixKstat, SystemError> {
-        let cur = current_pcb();
-        match cur.get_file_ref_by_fd(fd) {
-            Some(file) => {
-                let mut kstat = PosixKstat::new();
-                // 获取文件信息
-                match file.metadata() {
-                    Ok(metadata) => {
-                        kstat.size = metadata.size as i64;
-                        kstat.dev_id = metadata.dev_id as u64;
-                        kstat.inode = metadata.inode_id as u64;
-                        kstat.blcok_size = metadata.blk_size as i64;
-                        kstat.blocks = metadata.blocks as u64;
-
-                        kstat.atime.tv_sec = metadata.atime.tv_sec;
-                        kstat.atime.tv_nsec = metadata.atime.tv_nsec;
-                        kstat.mtime.tv_sec = metadata.mtime.tv_sec;
-                        kstat.mtime.tv_nsec = metadata.mtime.tv_nsec;
-                        kstat.ctime.tv_sec = metadata.ctime.tv_sec;
-                        kstat.ctime.tv_nsec = metadata.ctime.tv_nsec;
-
-                        kstat.nlink = metadata.nlinks as u64;
-                        kstat.uid = metadata.uid as i32;
-                        kstat.gid = metadata.gid as i32;
-                        kstat.rdev = metadata.raw_dev as i64;
-                        kstat.mode.bits = metadata.mode;
-                        match file.file_type() {
-                            FileType::File => kstat.mode.insert(ModeType::S_IFMT),
-                            FileType::Dir => kstat.mode.insert(ModeType::S_IFDIR),
-                            FileType::BlockDevice => kstat.mode.insert(ModeType::S_IFBLK),
-                            FileType::CharDevice => kstat.mode.insert(ModeType::S_IFCHR),
-                            FileType::SymLink => kstat.mode.insert(ModeType::S_IFLNK),
-                            FileType::Socket => kstat.mode.insert(ModeType::S_IFSOCK),
-                            FileType::Pipe => kstat.mode.insert(ModeType::S_IFIFO),
-                        }
+        let start = start as u64;
+
+        let ino: InodeId = file.metadata()?.inode_id;
+        let owner = FileLockOwner::Posix(current_pcb().pid);
+
+        match cmd {
+            FcntlCommand::GetLock => {
+                let lock_type = lock_type.ok_or(SystemError::EINVAL)?;
+                let mut result = user_flock;
+                match file_lock_test(ino, &FileLock::new(owner, lock_type, start, end)) {
+                    Some(conflict) => {
+                        result.l_type = match conflict.lock_type {
+                            FileLockType::Read => F_RDLCK,
+                            FileLockType::Write => F_WRLCK,
+                        };
+                        result.l_whence = SEEK_SET as i16;
+                        result.l_start = conflict.start as i64;
+                        result.l_len = if conflict.end == FILE_LOCK_EOF {
+                            0
+                        } else {
+                            (conflict.end - conflict.start + 1) as i64
+                        };
+                        result.l_pid = match conflict.owner {
+                            FileLockOwner::Posix(pid) => pid as i32,
+                            FileLockOwner::Flock(_) => -1,
+                        };
+                    }
+                    None => {
+                        result.l_type = F_UNLCK;
                     }
-                    Err(e) => return Err(e),
                 }
-
-                return Ok(kstat);
-            }
-            None => {
-                return Err(SystemError::EINVAL);
+                unsafe { *flock = result };
+                return Ok(0);
             }
-        }
-    }
-    pub fn fstat(fd: i32, usr_kstat: *mut PosixKstat) -> Result<usize, SystemError> {
-        match Self::do_fstat(fd) {
-            Ok(kstat) => {
-                if usr_kstat.is_null() {
-                    return Err(SystemError::EFAULT);
-                }
-                unsafe {
-                    *usr_kstat = kstat;
+            FcntlCommand::SetLock | FcntlCommand::SetLockWait => {
+                let lock_type = match lock_type {
+                    Some(lock_type) => lock_type,
+                    None => {
+                        file_unlock(ino, owner, start, end);
+                        return Ok(0);
+                    }
+                };
+                // 加读锁要求文件以可读方式打开，加写锁要求文件以可写方式打开
+                let access = match lock_type {
+                    FileLockType::Read => file.readable(),
+                    FileLockType::Write => file.writeable(),
+                };
+                if access.is_err() {
+                    return Err(SystemError::EBADF);
                 }
+                file_lock_set(
+                    ino,
+                    FileLock::new(owner, lock_type, start, end),
+                    cmd == FcntlCommand::SetLockWait,
+                )?;
                 return Ok(0);
             }
-            Err(e) => return Err(e),
+            _ => return Err(SystemError::EINVAL),
         }
     }
-}
 
-#[repr(C)]
-#[derive(Debug, Clone, Copy)]
-pub struct IoVec {
-    /// 缓冲区的起始地址
-    pub iov_base: *mut u8,
-    /// 缓冲区的长度
-    pub iov_len: usize,
-}
+    /// # flock
+    ///
+    /// ## 描述
+    ///
+    /// 对整个文件加上或者解除BSD文件锁。锁属于打开的文件对象，在文件对象被关闭时自动释放。
+    ///
+    /// ## 参数
+    ///
+    /// - `fd`：文件描述符
+    /// - `operation`：LOCK_SH、LOCK_EX、LOCK_UN之一，可以与LOCK_NB组合
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn flock(fd: i32, operation: u32) -> Result<usize, SystemError> {
+        let file: &File = current_pcb()
+            .get_file_ref_by_fd(fd)
+            .ok_or(SystemError::EBADF)?;
+        let ino: InodeId = file.metadata()?.inode_id;
+        let owner = file.flock_owner();
 
-/// 用于存储多个来自用户空间的IoVec
-///
-/// 由于目前内核中的文件系统还不支持分散读写，所以暂时只支持将用户空间的IoVec聚合成一个缓冲区，然后进行操作。
-/// TODO：支持分散读写
-#[derive(Debug)]
-pub struct IoVecs(Vec<&'static mut [u8]>);
+        let lock_type = match operation & !LOCK_NB {
+            LOCK_SH => FileLockType::Read,
+            LOCK_EX => FileLockType::Write,
+            LOCK_UN => {
+                file_unlock(ino, owner, 0, FILE_LOCK_EOF);
+                return Ok(0);
+            }
+            _ => return Err(SystemError::EINVAL),
+        };
+        file_lock_set(
+            ino,
+            FileLock::new(owner, lock_type, 0, FILE_LOCK_EOF),
+            operation & LOCK_NB == 0,
+        )?;
+        return Ok(0);
+    }
 
-impl IoVecs {
-    /// 从用户空间的IoVec中构造IoVecs
+    /// # poll
     ///
-    /// @param iov 用户空间的IoVec
-    /// @param iovcnt 用户空间的IoVec的数量
-    /// @param readv 是否为readv系统调用
+    /// ## 描述
     ///
-    /// @return 构造成功返回IoVecs，否则返回错误码
-    pub unsafe fn from_user(
-        iov: *const IoVec,
-        iovcnt: usize,
-        _readv: bool,
-    ) -> Result<Self, SystemError> {
-        // 检查iov指针所在空间是否合法
-        if !verify_area(
-            iov as usize as u64,
-            (iovcnt * core::mem::size_of::<IoVec>()) as u64,
-        ) {
-            return Err(SystemError::EFAULT);
+    /// 等待一组文件描述符上发生指定的事件。
+    ///
+    /// ## 参数
+    ///
+    /// - `fds`：用户空间的PollFd数组
+    /// - `nfds`：数组的长度
+    /// - `timeout`：超时时间（单位：毫秒）。小于0时表示永不超时，为0时表示立即返回
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回发生了事件的文件描述符的数量（超时时为0），否则返回错误码.
+    pub fn poll(fds: *mut PollFd, nfds: u32, timeout: i32) -> Result<usize, SystemError> {
+        let nfds = nfds as usize;
+        if nfds > FileDescriptorVec::PROCESS_MAX_FD {
+            return Err(SystemError::EINVAL);
+        }
+        let len = nfds * core::mem::size_of::<PollFd>();
+        let mut poll_fds: Vec<PollFd> = Vec::with_capacity(nfds);
+        if nfds > 0 {
+            let reader = UserBufferReader::new(fds as *const PollFd, len, true)?;
+            poll_fds.extend_from_slice(reader.read_from_user::<PollFd>(0)?);
+        }
+
+        let timeout_us: Option<u64> = if timeout < 0 {
+            None
+        } else {
+            Some(timeout as u64 * 1000)
+        };
+        let r: usize = do_poll(&mut poll_fds, timeout_us)?;
+
+        if nfds > 0 {
+            let mut writer = UserBufferWriter::new(fds, len, true)?;
+            writer.copy_to_user(&poll_fds, 0)?;
+        }
+        return Ok(r);
+    }
+
+    /// # select
+    ///
+    /// ## 描述
+    ///
+    /// 等待一组文件描述符变为可读、可写，或者发生异常条件。
+    ///
+    /// ## 参数
+    ///
+    /// - `nfds`：要检查的文件描述符的范围：[0, nfds)
+    /// - `readfds`：等待可读的文件描述符集合，可以为空
+    /// - `writefds`：等待可写的文件描述符集合，可以为空
+    /// - `exceptfds`：等待异常条件的文件描述符集合，可以为空
+    /// - `timeout`：超时时间，为空时表示永不超时（目前不会把剩余时间写回）
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回三个集合中就绪的文件描述符的总数（超时时为0），否则返回错误码.
+    pub fn select(
+        nfds: i32,
+        readfds: *mut u64,
+        writefds: *mut u64,
+        exceptfds: *mut u64,
+        timeout: *const PosixTimeval,
+    ) -> Result<usize, SystemError> {
+        if nfds < 0 || nfds as usize > FileDescriptorVec::PROCESS_MAX_FD {
+            return Err(SystemError::EINVAL);
+        }
+        let timeout_us: Option<u64> = if timeout.is_null() {
+            None
+        } else {
+            let reader =
+                UserBufferReader::new(timeout, core::mem::size_of::<PosixTimeval>(), true)?;
+            let tv: PosixTimeval = *reader.read_one_from_user::<PosixTimeval>(0)?;
+            if tv.tv_sec < 0 || tv.tv_usec < 0 {
+                return Err(SystemError::EINVAL);
+            }
+            Some(tv.tv_sec as u64 * 1000000 + tv.tv_usec as u64)
+        };
+        return do_select(nfds as usize, readfds, writefds, exceptfds, timeout_us);
+    }
+
+    /// # pselect6
+    ///
+    /// ## 描述
+    ///
+    /// 与select相同，但是超时时间的精度为纳秒，并且可以在等待期间临时替换进程的信号屏蔽字。
+    ///
+    /// ## 参数
+    ///
+    /// - `nfds`、`readfds`、`writefds`、`exceptfds`：与select相同
+    /// - `timeout`：超时时间，为空时表示永不超时
+    /// - `sigmask`：用户空间的PselectSigmask结构体，可以为空
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回三个集合中就绪的文件描述符的总数（超时时为0），否则返回错误码.
+    pub fn pselect6(
+        nfds: i32,
+        readfds: *mut u64,
+        writefds: *mut u64,
+        exceptfds: *mut u64,
+        timeout: *const TimeSpec,
+        sigmask: *const PselectSigmask,
+    ) -> Result<usize, SystemError> {
+        if nfds < 0 || nfds as usize > FileDescriptorVec::PROCESS_MAX_FD {
+            return Err(SystemError::EINVAL);
+        }
+        let timeout_us: Option<u64> = if timeout.is_null() {
+            None
+        } else {
+            let reader = UserBufferReader::new(timeout, core::mem::size_of::<TimeSpec>(), true)?;
+            let ts: TimeSpec = *reader.read_one_from_user::<TimeSpec>(0)?;
+            if ts.tv_sec < 0 || ts.tv_nsec < 0 || ts.tv_nsec >= 1000000000 {
+                return Err(SystemError::EINVAL);
+            }
+            Some(ts.tv_sec as u64 * 1000000 + (ts.tv_nsec as u64 + 999) / 1000)
+        };
+
+        let sigmask: Option<sigset_t> = if sigmask.is_null() {
+            None
+        } else {
+            let reader =
+                UserBufferReader::new(sigmask, core::mem::size_of::<PselectSigmask>(), true)?;
+            let arg: PselectSigmask = *reader.read_one_from_user::<PselectSigmask>(0)?;
+            Self::read_sigmask(arg.ss, arg.ss_len)?
+        };
+
+        return with_sigmask(sigmask, || {
+            do_select(nfds as usize, readfds, writefds, exceptfds, timeout_us)
+        });
+    }
+
+    /// @brief 从用户空间读取信号屏蔽字
+    ///
+    /// @param set 用户空间的sigset_t指针，可以为空
+    /// @param size sigset_t的大小，必须与内核中的sigset_t大小相同
+    ///
+    /// @return Ok(None) set为空
+    /// @return Ok(Some(sigset_t)) 读取到的信号屏蔽字
+    pub fn read_sigmask(
+        set: *const sigset_t,
+        size: usize,
+    ) -> Result<Option<sigset_t>, SystemError> {
+        if set.is_null() {
+            return Ok(None);
+        }
+        if size != core::mem::size_of::<sigset_t>() {
+            return Err(SystemError::EINVAL);
+        }
+        let reader = UserBufferReader::new(set, size, true)?;
+        return Ok(Some(*reader.read_one_from_user::<sigset_t>(0)?));
+    }
+
+    /// # ftruncate
+    ///
+    /// ## 描述
+    ///
+    /// 改变文件大小.
+    /// 如果文件大小大于原来的大小，那么文件的内容将会被扩展到指定的大小，新的空间将会用0填充.
+    /// 如果文件大小小于原来的大小，那么文件的内容将会被截断到指定的大小.
+    ///
+    /// ## 参数
+    ///
+    /// - `fd`：文件描述符
+    /// - `len`：文件大小
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn ftruncate(fd: i32, len: usize) -> Result<usize, SystemError> {
+        if let Some(fds) = FileDescriptorVec::from_pcb(current_pcb()) {
+            if FileDescriptorVec::validate_fd(fd) {
+                if let Some(file) = &mut fds.fds[fd as usize] {
+                    let r = file.ftruncate(len).map(|_| 0);
+                    return r;
+                }
+                return Err(SystemError::EBADF);
+            }
+        }
+        return Err(SystemError::EBADF);
+    }
+    fn do_fstat(fd: i32) -> Result<PosixKstat, SystemError> {
+        let cur = current_pcb();
+        match cur.get_file_ref_by_fd(fd) {
+            Some(file) => {
+                // 获取文件信息
+                return Ok(PosixKstat::from_metadata(&file.metadata()?));
+            }
+            None => {
+                return Err(SystemError::EBADF);
+            }
+        }
+    }
+    pub fn fstat(fd: i32, usr_kstat: *mut PosixKstat) -> Result<usize, SystemError> {
+        match Self::do_fstat(fd) {
+            Ok(kstat) => {
+                if usr_kstat.is_null() {
+                    return Err(SystemError::EFAULT);
+                }
+                unsafe {
+                    *usr_kstat = kstat;
+                }
+                return Ok(0);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    /// # stat
+    ///
+    /// ## 描述
+    ///
+    /// 根据路径获取文件信息。如果路径的最后一级是符号链接，那么获取的是它所指向的文件的信息。
+    ///
+    /// ## 参数
+    ///
+    /// - `path`：文件路径
+    /// - `usr_kstat`：用户空间的文件信息结构体
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn stat(path: &str, usr_kstat: *mut PosixKstat) -> Result<usize, SystemError> {
+        return Self::fstatat(AT_FDCWD, path, usr_kstat, 0);
+    }
+
+    /// # lstat
+    ///
+    /// ## 描述
+    ///
+    /// 根据路径获取文件信息。与stat不同的是，如果路径的最后一级是符号链接，
+    /// 那么获取的是符号链接本身的信息，而不是它所指向的文件的信息。
+    ///
+    /// ## 参数
+    ///
+    /// - `path`：文件路径
+    /// - `usr_kstat`：用户空间的文件信息结构体
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn lstat(path: &str, usr_kstat: *mut PosixKstat) -> Result<usize, SystemError> {
+        return Self::fstatat(AT_FDCWD, path, usr_kstat, AT_SYMLINK_NOFOLLOW);
+    }
+
+    /// # symlink
+    ///
+    /// ## 描述
+    ///
+    /// 创建一个名为linkpath的符号链接，指向target。target不需要存在。
+    ///
+    /// ## 参数
+    ///
+    /// - `target`：符号链接所指向的路径
+    /// - `linkpath`：要创建的符号链接的路径
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn symlink(target: &str, linkpath: &str) -> Result<usize, SystemError> {
+        if target.is_empty() || linkpath.is_empty() {
+            return Err(SystemError::ENOENT);
+        }
+        if target.len() > MAX_PATHLEN {
+            return Err(SystemError::ENAMETOOLONG);
+        }
+
+        // 查找父目录
+        let (base, linkpath) = user_path_at(AT_FDCWD, linkpath)?;
+        let (parent_inode, filename) = base.lookup_parent(linkpath)?;
+        may_modify_dir(&parent_inode)?;
+        let inode: Arc<dyn IndexNode> = parent_inode.symlink(filename, target)?;
+        init_inode_owner(&inode)?;
+
+        return Ok(0);
+    }
+
+    /// # readlink
+    ///
+    /// ## 描述
+    ///
+    /// 读取符号链接所指向的路径。写入buf的内容不以'\0'结尾，若buf的空间不足，则内容会被截断。
+    ///
+    /// ## 参数
+    ///
+    /// - `path`：符号链接的路径
+    /// - `buf`：用户空间的输出缓冲区
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回写入buf的字节数，否则返回错误码.
+    pub fn readlink(path: &str, buf: &mut [u8]) -> Result<usize, SystemError> {
+        if buf.is_empty() {
+            return Err(SystemError::EINVAL);
+        }
+
+        let (base, path) = user_path_at(AT_FDCWD, path)?;
+        let inode: Arc<dyn IndexNode> = base.lookup_nofollow(path)?;
+        let link_path = inode.read_link()?;
+
+        let len = core::cmp::min(link_path.len(), buf.len());
+        buf[..len].copy_from_slice(&link_path.as_bytes()[..len]);
+        return Ok(len);
+    }
+
+    /// # fstatat
+    ///
+    /// ## 描述
+    ///
+    /// 根据路径获取文件信息。相对路径将从dirfd所指向的目录开始解析。
+    ///
+    /// ## 参数
+    ///
+    /// - `dirfd`：目录的文件描述符（可以为AT_FDCWD）
+    /// - `path`：文件路径
+    /// - `usr_kstat`：用户空间的文件信息结构体
+    /// - `flags`：标志位，可以为AT_SYMLINK_NOFOLLOW、AT_EMPTY_PATH的组合
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn fstatat(
+        dirfd: i32,
+        path: &str,
+        usr_kstat: *mut PosixKstat,
+        flags: u32,
+    ) -> Result<usize, SystemError> {
+        if (flags & !(AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH)) != 0 {
+            return Err(SystemError::EINVAL);
+        }
+        if usr_kstat.is_null() {
+            return Err(SystemError::EFAULT);
+        }
+
+        let inode: Arc<dyn IndexNode> = Self::lookup_at(dirfd, path, flags)?;
+        let kstat = PosixKstat::from_metadata(&inode.metadata()?);
+        unsafe {
+            *usr_kstat = kstat;
+        }
+        return Ok(0);
+    }
+
+    /// # statx
+    ///
+    /// ## 描述
+    ///
+    /// 根据路径获取文件的扩展信息。路径的解析方式与fstatat相同。
+    ///
+    /// ## 参数
+    ///
+    /// - `dirfd`：目录的文件描述符（可以为AT_FDCWD）
+    /// - `path`：文件路径
+    /// - `flags`：标志位，可以为AT_SYMLINK_NOFOLLOW、AT_EMPTY_PATH、AT_NO_AUTOMOUNT、AT_STATX_SYNC_TYPE的组合
+    /// - `mask`：请求获取的字段（STATX_*）。实际获取到的字段记录在结构体的mask字段中
+    /// - `usr_statx`：用户空间的statx结构体
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn statx(
+        dirfd: i32,
+        path: &str,
+        flags: u32,
+        mask: u32,
+        usr_statx: *mut PosixStatx,
+    ) -> Result<usize, SystemError> {
+        if (flags & !(AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH | AT_NO_AUTOMOUNT | AT_STATX_SYNC_TYPE))
+            != 0
+            || (flags & AT_STATX_SYNC_TYPE) == AT_STATX_SYNC_TYPE
+            || (mask & STATX__RESERVED) != 0
+        {
+            return Err(SystemError::EINVAL);
+        }
+        if usr_statx.is_null() {
+            return Err(SystemError::EFAULT);
+        }
+
+        let inode: Arc<dyn IndexNode> = Self::lookup_at(dirfd, path, flags)?;
+        let statx = PosixStatx::from_metadata(&inode.metadata()?);
+        unsafe {
+            *usr_statx = statx;
+        }
+        return Ok(0);
+    }
+
+    /// # utimensat
+    ///
+    /// ## 描述
+    ///
+    /// 以纳秒精度修改文件的最后访问时间和最后修改时间，同时把状态变化时间设置为当前时间。
+    ///
+    /// ## 参数
+    ///
+    /// - `dirfd`：目录的文件描述符（可以为AT_FDCWD）
+    /// - `path`：文件路径。为None时，修改dirfd本身所指向的文件（即futimens）
+    /// - `times`：用户空间的数组，times[0]为访问时间，times[1]为修改时间。tv_nsec可以为UTIME_NOW或UTIME_OMIT。
+    ///   为空指针时，把两个时间都设置为当前时间
+    /// - `flags`：标志位，可以为AT_SYMLINK_NOFOLLOW、AT_EMPTY_PATH的组合
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn utimensat(
+        dirfd: i32,
+        path: Option<&str>,
+        times: *const TimeSpec,
+        flags: u32,
+    ) -> Result<usize, SystemError> {
+        if (flags & !(AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH)) != 0 {
+            return Err(SystemError::EINVAL);
+        }
+
+        let times: Option<[TimeSpec; 2]> = if times.is_null() {
+            None
+        } else {
+            let reader = UserBufferReader::new(times, core::mem::size_of::<[TimeSpec; 2]>(), true)?;
+            let times = reader.read_from_user::<TimeSpec>(0)?;
+            for t in times {
+                if t.tv_nsec != UTIME_NOW
+                    && t.tv_nsec != UTIME_OMIT
+                    && (t.tv_nsec < 0 || t.tv_nsec >= 1_000_000_000)
+                {
+                    return Err(SystemError::EINVAL);
+                }
+            }
+            Some([times[0], times[1]])
+        };
+
+        let inode: Arc<dyn IndexNode> = match path {
+            Some(path) => Self::lookup_at(dirfd, path, flags)?,
+            None => {
+                // 没有指定路径时，修改的是dirfd所指向的文件本身
+                if dirfd == AT_FDCWD {
+                    return Err(SystemError::EFAULT);
+                }
+                if (flags & AT_SYMLINK_NOFOLLOW) != 0 {
+                    return Err(SystemError::EINVAL);
+                }
+                user_path_empty_at(dirfd)?
+            }
+        };
+        return Self::do_utimens(&inode, times);
+    }
+
+    /// # futimens
+    ///
+    /// ## 描述
+    ///
+    /// 修改文件描述符所指向的文件的最后访问时间和最后修改时间。
+    ///
+    /// ## 参数
+    ///
+    /// - `fd`：文件描述符
+    /// - `times`：与utimensat相同
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn futimens(fd: i32, times: *const TimeSpec) -> Result<usize, SystemError> {
+        return Self::utimensat(fd, None, times, 0);
+    }
+
+    /// # statfs
+    ///
+    /// ## 描述
+    ///
+    /// 获取路径所在的文件系统的信息（块大小、空闲块数等）。
+    ///
+    /// ## 参数
+    ///
+    /// - `path`：文件系统中任意一个文件的路径
+    /// - `usr_statfs`：用户空间的statfs结构体
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn statfs(path: &str, usr_statfs: *mut PosixStatfs) -> Result<usize, SystemError> {
+        let inode: Arc<dyn IndexNode> = Self::lookup_at(AT_FDCWD, path, 0)?;
+        return Self::do_statfs(&inode, usr_statfs);
+    }
+
+    /// # fstatfs
+    ///
+    /// ## 描述
+    ///
+    /// 获取文件描述符所指向的文件所在的文件系统的信息。
+    ///
+    /// ## 参数
+    ///
+    /// - `fd`：文件描述符
+    /// - `usr_statfs`：用户空间的statfs结构体
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn fstatfs(fd: i32, usr_statfs: *mut PosixStatfs) -> Result<usize, SystemError> {
+        let inode: Arc<dyn IndexNode> = current_pcb()
+            .get_file_ref_by_fd(fd)
+            .ok_or(SystemError::EBADF)?
+            .inode();
+        return Self::do_statfs(&inode, usr_statfs);
+    }
+
+    /// @brief 获取inode所在的文件系统的信息，并写入用户空间
+    fn do_statfs(
+        inode: &Arc<dyn IndexNode>,
+        usr_statfs: *mut PosixStatfs,
+    ) -> Result<usize, SystemError> {
+        if usr_statfs.is_null() {
+            return Err(SystemError::EFAULT);
+        }
+
+        let fs: Arc<dyn FileSystem> = inode.fs();
+        let mount_flags: MountFlags = inode_mount_flags(inode);
+
+        let statfs = PosixStatfs::new(&fs.info(), mount_flags);
+        unsafe {
+            *usr_statfs = statfs;
+        }
+        return Ok(0);
+    }
+
+    /// # renameat
+    ///
+    /// ## 描述
+    ///
+    /// 重命名文件/文件夹。相对路径将分别从olddirfd、newdirfd所指向的目录开始解析。
+    ///
+    /// ## 参数
+    ///
+    /// - `olddirfd`：解析oldpath时的起始目录的文件描述符（可以为AT_FDCWD）
+    /// - `oldpath`：原来的路径
+    /// - `newdirfd`：解析newpath时的起始目录的文件描述符（可以为AT_FDCWD）
+    /// - `newpath`：新的路径
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn renameat(
+        olddirfd: i32,
+        oldpath: &str,
+        newdirfd: i32,
+        newpath: &str,
+    ) -> Result<usize, SystemError> {
+        return Self::renameat2(olddirfd, oldpath, newdirfd, newpath, 0);
+    }
+
+    /// # rename
+    ///
+    /// ## 描述
+    ///
+    /// 重命名文件/文件夹。如果newpath已经存在，那么它将被替换。
+    ///
+    /// ## 参数
+    ///
+    /// - `oldpath`：原来的路径
+    /// - `newpath`：新的路径
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn rename(oldpath: &str, newpath: &str) -> Result<usize, SystemError> {
+        return Self::renameat2(AT_FDCWD, oldpath, AT_FDCWD, newpath, 0);
+    }
+
+    /// # renameat2
+    ///
+    /// ## 描述
+    ///
+    /// 与renameat相同，但是可以通过flags控制重命名的行为。
+    ///
+    /// ## 参数
+    ///
+    /// - `olddirfd`：解析oldpath时的起始目录的文件描述符（可以为AT_FDCWD）
+    /// - `oldpath`：原来的路径
+    /// - `newdirfd`：解析newpath时的起始目录的文件描述符（可以为AT_FDCWD）
+    /// - `newpath`：新的路径
+    /// - `flags`：标志位，可以为RENAME_NOREPLACE、RENAME_EXCHANGE之一
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn renameat2(
+        olddirfd: i32,
+        oldpath: &str,
+        newdirfd: i32,
+        newpath: &str,
+        flags: u32,
+    ) -> Result<usize, SystemError> {
+        if oldpath.is_empty() || newpath.is_empty() {
+            return Err(SystemError::ENOENT);
+        }
+        let flags = RenameFlags::from_bits(flags).ok_or(SystemError::EINVAL)?;
+        return do_rename_at(olddirfd, oldpath, newdirfd, newpath, flags).map(|x| x as usize);
+    }
+
+    /// # fsync
+    ///
+    /// ## 描述
+    ///
+    /// 把fd所指向的文件在内存中被修改过的数据，写回存储设备。
+    ///
+    /// ## 参数
+    ///
+    /// - `fd`：文件描述符
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn fsync(fd: i32) -> Result<usize, SystemError> {
+        let file: &File = current_pcb()
+            .get_file_ref_by_fd(fd)
+            .ok_or(SystemError::EBADF)?;
+        file.inode().sync()?;
+        return Ok(0);
+    }
+
+    /// # sync
+    ///
+    /// ## 描述
+    ///
+    /// 把所有文件的页缓存中的脏页，以及所有已挂载的文件系统的元数据，写回存储设备。
+    ///
+    /// ## 返回值
+    ///
+    /// 总是返回0
+    pub fn sync() -> Result<usize, SystemError> {
+        if let Err(e) = page_cache_sync_all() {
+            kerror!("sync: failed to write back page cache, err={e:?}");
+        }
+        for record in mount_list().records() {
+            if let Err(e) = record.mount_fs.sync() {
+                kerror!("sync: failed to sync {}, err={e:?}", record.target);
+            }
+        }
+        return Ok(0);
+    }
+
+    /// # mount
+    ///
+    /// ## 描述
+    ///
+    /// 把一个文件系统挂载到target所指向的目录上。
+    ///
+    /// ## 参数
+    ///
+    /// - `source`：被挂载的设备（例如ahci_disk_0p1）。对于伪文件系统，可以为空
+    /// - `target`：挂载点的路径
+    /// - `fstype`：文件系统的类型名，可以为vfat、ramfs、tmpfs、proc、devfs、sysfs
+    /// - `flags`：挂载标志
+    /// - `data`：文件系统相关的挂载参数，以逗号分隔（例如tmpfs的size=16m,mode=1777）
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn mount(
+        source: &str,
+        target: &str,
+        fstype: &str,
+        flags: u32,
+        data: &str,
+    ) -> Result<usize, SystemError> {
+        if target.is_empty() || fstype.is_empty() {
+            return Err(SystemError::EINVAL);
+        }
+        let flags = MountFlags::from_bits(flags).ok_or(SystemError::EINVAL)?;
+        return do_mount(source, target, fstype, flags, data).map(|x| x as usize);
+    }
+
+    /// # umount2
+    ///
+    /// ## 描述
+    ///
+    /// 卸载挂载在target上的文件系统。
+    ///
+    /// ## 参数
+    ///
+    /// - `target`：挂载点的路径
+    /// - `flags`：卸载标志，可以为MNT_DETACH、MNT_EXPIRE、UMOUNT_NOFOLLOW的组合（暂不支持MNT_FORCE）
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn umount2(target: &str, flags: u32) -> Result<usize, SystemError> {
+        if target.is_empty() {
+            return Err(SystemError::ENOENT);
+        }
+        let flags = UmountFlags::from_bits(flags).ok_or(SystemError::EINVAL)?;
+        return do_umount2(target, flags).map(|x| x as usize);
+    }
+
+    /// # linkat
+    ///
+    /// ## 描述
+    ///
+    /// 创建硬链接。相对路径将分别从olddirfd、newdirfd所指向的目录开始解析。
+    ///
+    /// ## 参数
+    ///
+    /// - `olddirfd`：解析oldpath时的起始目录的文件描述符（可以为AT_FDCWD）
+    /// - `oldpath`：已经存在的文件的路径
+    /// - `newdirfd`：解析newpath时的起始目录的文件描述符（可以为AT_FDCWD）
+    /// - `newpath`：新的硬链接的路径
+    /// - `flags`：标志位，可以为AT_SYMLINK_FOLLOW、AT_EMPTY_PATH的组合
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn linkat(
+        olddirfd: i32,
+        oldpath: &str,
+        newdirfd: i32,
+        newpath: &str,
+        flags: u32,
+    ) -> Result<usize, SystemError> {
+        if (flags & !(AT_SYMLINK_FOLLOW | AT_EMPTY_PATH)) != 0 {
+            return Err(SystemError::EINVAL);
+        }
+        if newpath.is_empty() {
+            return Err(SystemError::ENOENT);
+        }
+
+        if oldpath.is_empty() {
+            // 为olddirfd所指向的文件创建硬链接
+            if (flags & AT_EMPTY_PATH) == 0 {
+                return Err(SystemError::ENOENT);
+            }
+            let inode: Arc<dyn IndexNode> = user_path_empty_at(olddirfd)?;
+            return do_link_inode(inode, newdirfd, newpath).map(|x| x as usize);
+        }
+
+        let follow = (flags & AT_SYMLINK_FOLLOW) != 0;
+        return do_link_at(olddirfd, oldpath, newdirfd, newpath, follow).map(|x| x as usize);
+    }
+
+    /// # link
+    ///
+    /// ## 描述
+    ///
+    /// 为已经存在的文件创建一个新的硬链接。如果oldpath是符号链接，那么链接的是符号链接本身。
+    ///
+    /// ## 参数
+    ///
+    /// - `oldpath`：已经存在的文件的路径
+    /// - `newpath`：新的硬链接的路径
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn link(oldpath: &str, newpath: &str) -> Result<usize, SystemError> {
+        if oldpath.is_empty() {
+            return Err(SystemError::ENOENT);
+        }
+        return Self::linkat(AT_FDCWD, oldpath, AT_FDCWD, newpath, 0);
+    }
+
+    /// # faccessat
+    ///
+    /// ## 描述
+    ///
+    /// 检查进程是否能够访问指定的文件。相对路径将从dirfd所指向的目录开始解析。
+    ///
+    /// ## 参数
+    ///
+    /// - `dirfd`：目录的文件描述符（可以为AT_FDCWD）
+    /// - `path`：文件路径
+    /// - `mode`：要检查的访问权限（F_OK、R_OK、W_OK、X_OK的组合）
+    /// - `flags`：标志位，可以为AT_EACCESS、AT_SYMLINK_NOFOLLOW、AT_EMPTY_PATH的组合
+    ///
+    /// ## 返回值
+    ///
+    /// 如果可以访问，返回0，否则返回错误码.
+    pub fn faccessat(dirfd: i32, path: &str, mode: u32, flags: u32) -> Result<usize, SystemError> {
+        if (mode & !0o7) != 0 {
+            return Err(SystemError::EINVAL);
+        }
+        if (flags & !(AT_EACCESS | AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH)) != 0 {
+            return Err(SystemError::EINVAL);
+        }
+        let inode: Arc<dyn IndexNode> = Self::lookup_at(dirfd, path, flags)?;
+
+        // F_OK: 只检查文件是否存在
+        if mode == 0 {
+            return Ok(0);
+        }
+
+        // 默认使用真实用户ID和真实组ID进行检查，指定了AT_EACCESS时使用有效用户ID和有效组ID
+        let cred = current_pcb().cred();
+        let cred = if (flags & AT_EACCESS) != 0 {
+            (*cred).clone()
+        } else {
+            cred.real()
+        };
+        check_permission(
+            &inode.metadata()?,
+            &cred,
+            PermissionMask::from_bits_truncate(mode),
+        )?;
+        return Ok(0);
+    }
+
+    /// # access
+    ///
+    /// ## 描述
+    ///
+    /// 使用进程的真实用户ID和真实组ID，检查进程是否能够访问指定的文件。
+    ///
+    /// ## 参数
+    ///
+    /// - `path`：文件路径
+    /// - `mode`：要检查的访问权限（F_OK、R_OK、W_OK、X_OK的组合）
+    ///
+    /// ## 返回值
+    ///
+    /// 如果可以访问，返回0，否则返回错误码.
+    pub fn access(path: &str, mode: u32) -> Result<usize, SystemError> {
+        if path.is_empty() {
+            return Err(SystemError::ENOENT);
+        }
+        return Self::faccessat(AT_FDCWD, path, mode, 0);
+    }
+
+    /// # chmod
+    ///
+    /// ## 描述
+    ///
+    /// 修改文件的权限位。只有文件的所有者和超级用户可以修改。
+    ///
+    /// ## 参数
+    ///
+    /// - `path`：文件路径
+    /// - `mode`：新的权限位
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn chmod(path: &str, mode: u32) -> Result<usize, SystemError> {
+        let inode: Arc<dyn IndexNode> = Self::lookup_for_attr(path)?;
+        return Self::do_chmod(&inode, mode);
+    }
+
+    /// # fchmod
+    ///
+    /// ## 描述
+    ///
+    /// 修改文件描述符所指向的文件的权限位。
+    ///
+    /// ## 参数
+    ///
+    /// - `fd`：文件描述符
+    /// - `mode`：新的权限位
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn fchmod(fd: i32, mode: u32) -> Result<usize, SystemError> {
+        let inode: Arc<dyn IndexNode> = current_pcb()
+            .get_file_ref_by_fd(fd)
+            .ok_or(SystemError::EBADF)?
+            .inode();
+        return Self::do_chmod(&inode, mode);
+    }
+
+    /// # chown
+    ///
+    /// ## 描述
+    ///
+    /// 修改文件的所有者和所属的组。只有超级用户可以修改文件的所有者；
+    /// 文件的所有者可以把文件所属的组修改为自己所在的任意一个组。
+    ///
+    /// ## 参数
+    ///
+    /// - `path`：文件路径
+    /// - `owner`：新的所有者。为-1时不修改
+    /// - `group`：新的组。为-1时不修改
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn chown(path: &str, owner: u32, group: u32) -> Result<usize, SystemError> {
+        let inode: Arc<dyn IndexNode> = Self::lookup_for_attr(path)?;
+        return Self::do_chown(&inode, owner, group);
+    }
+
+    /// # fchown
+    ///
+    /// ## 描述
+    ///
+    /// 修改文件描述符所指向的文件的所有者和所属的组。
+    ///
+    /// ## 参数
+    ///
+    /// - `fd`：文件描述符
+    /// - `owner`、`group`：与chown相同
+    ///
+    /// ## 返回值
+    ///
+    /// 如果成功，返回0，否则返回错误码.
+    pub fn fchown(fd: i32, owner: u32, group: u32) -> Result<usize, SystemError> {
+        let inode: Arc<dyn IndexNode> = current_pcb()
+            .get_file_ref_by_fd(fd)
+            .ok_or(SystemError::EBADF)?
+            .inode();
+        return Self::do_chown(&inode, owner, group);
+    }
+
+    /// # umask
+    ///
+    /// ## 描述
+    ///
+    /// 设置进程的文件创建掩码。
+    ///
+    /// ## 参数
+    ///
+    /// - `mask`：新的掩码（只有低9位有效）
+    ///
+    /// ## 返回值
+    ///
+    /// 返回原来的掩码.
+    pub fn umask(mask: u32) -> Result<usize, SystemError> {
+        let pcb = current_pcb();
+        let cred = pcb.cred();
+        let old = cred.umask;
+
+        let mut new_cred = (*cred).clone();
+        new_cred.umask = mask & 0o777;
+        pcb.set_cred(Arc::new(new_cred));
+        return Ok(old as usize);
+    }
+
+    /// @brief 查找chmod、chown要操作的文件（跟随符号链接）
+    fn lookup_for_attr(path: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+        let (base, path) = user_path_at(AT_FDCWD, path)?;
+        return base.lookup(path);
+    }
+
+    /// @brief 修改inode的权限位
+    fn do_chmod(inode: &Arc<dyn IndexNode>, mode: u32) -> Result<usize, SystemError> {
+        let cred = current_pcb().cred();
+        let mut metadata = inode.metadata()?;
+        check_owner(&metadata, &cred)?;
+
+        let mut mode = mode & 0o7777;
+        // 不属于文件所在的组的普通用户，不能设置S_ISGID位
+        if !cred.is_root() && !cred.in_group(metadata.gid as u32) {
+            mode &= !ModeType::S_ISGID.bits();
+        }
+        metadata.mode = mode;
+        metadata.ctime = TimeSpec::now();
+        inode.set_metadata(&metadata)?;
+        return Ok(0);
+    }
+
+    /// @brief 修改inode的所有者和所属的组
+    fn do_chown(inode: &Arc<dyn IndexNode>, owner: u32, group: u32) -> Result<usize, SystemError> {
+        let cred = current_pcb().cred();
+        let mut metadata = inode.metadata()?;
+
+        let uid = if owner == u32::MAX {
+            metadata.uid
+        } else {
+            owner as usize
+        };
+        let gid = if group == u32::MAX {
+            metadata.gid
+        } else {
+            group as usize
+        };
+
+        if !cred.is_root() {
+            // 普通用户不能修改文件的所有者
+            if uid != metadata.uid {
+                return Err(SystemError::EPERM);
+            }
+            // 只有文件的所有者可以修改文件所属的组，并且只能修改为自己所在的组
+            if gid != metadata.gid
+                && (metadata.uid != cred.euid as usize || !cred.in_group(gid as u32))
+            {
+                return Err(SystemError::EPERM);
+            }
+        }
+
+        // 修改普通文件的所有者后，清除它的S_ISUID位；如果它可以被组执行，还需要清除S_ISGID位
+        if metadata.file_type != FileType::Dir {
+            metadata.mode &= !ModeType::S_ISUID.bits();
+            if (metadata.mode & ModeType::S_IXGRP.bits()) != 0 {
+                metadata.mode &= !ModeType::S_ISGID.bits();
+            }
+        }
+        metadata.uid = uid;
+        metadata.gid = gid;
+        metadata.ctime = TimeSpec::now();
+        inode.set_metadata(&metadata)?;
+        return Ok(0);
+    }
+
+    /// @brief 修改inode的访问时间和修改时间
+    ///
+    /// @param times 为None时，把两个时间都设置为当前时间
+    fn do_utimens(
+        inode: &Arc<dyn IndexNode>,
+        times: Option<[TimeSpec; 2]>,
+    ) -> Result<usize, SystemError> {
+        let cred = current_pcb().cred();
+        let mut metadata = inode.metadata()?;
+        let now = TimeSpec::now();
+
+        let times = times.unwrap_or([TimeSpec::new(0, UTIME_NOW), TimeSpec::new(0, UTIME_NOW)]);
+        if times.iter().all(|t| t.tv_nsec == UTIME_OMIT) {
+            return Ok(0);
+        }
+
+        if times.iter().all(|t| t.tv_nsec == UTIME_NOW) {
+            // 把时间设置为当前时间，只需要对文件有写权限
+            if check_owner(&metadata, &cred).is_err() {
+                check_permission(&metadata, &cred, PermissionMask::MAY_WRITE)?;
+            }
+        } else {
+            // 设置为任意的时间，需要是文件的所有者
+            check_owner(&metadata, &cred)?;
+        }
+
+        let new_time = |t: &TimeSpec, old: TimeSpec| match t.tv_nsec {
+            UTIME_NOW => now,
+            UTIME_OMIT => old,
+            _ => *t,
+        };
+        metadata.atime = new_time(&times[0], metadata.atime);
+        metadata.mtime = new_time(&times[1], metadata.mtime);
+        metadata.ctime = now;
+        inode.set_metadata(&metadata)?;
+        return Ok(0);
+    }
+
+    /// @brief 根据dirfd和路径查找inode（用于fstatat、statx等*at系统调用）
+    ///
+    /// @param flags AT_EMPTY_PATH：路径为空时返回dirfd本身；AT_SYMLINK_NOFOLLOW：不跟随路径最后一级的符号链接
+    fn lookup_at(dirfd: i32, path: &str, flags: u32) -> Result<Arc<dyn IndexNode>, SystemError> {
+        if path.is_empty() && (flags & AT_EMPTY_PATH) != 0 {
+            return user_path_empty_at(dirfd);
+        }
+
+        let (base, path) = user_path_at(dirfd, path)?;
+        if (flags & AT_SYMLINK_NOFOLLOW) != 0 {
+            return base.lookup_nofollow(path);
+        }
+        return base.lookup(path);
+    }
+}
+
+/// pselect6的第6个参数（与Linux相同）
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PselectSigmask {
+    /// 等待期间使用的信号屏蔽字，可以为空
+    pub ss: *const sigset_t,
+    /// sigset_t的大小
+    pub ss_len: usize,
+}
+
+/// fcntl的F_GETLK、F_SETLK、F_SETLKW命令使用的结构体（与Linux的struct flock兼容）
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PosixFlock {
+    /// 锁的类型：F_RDLCK、F_WRLCK、F_UNLCK
+    pub l_type: i16,
+    /// l_start的基准位置：SEEK_SET、SEEK_CUR、SEEK_END
+    pub l_whence: i16,
+    /// 锁定范围的起始偏移量
+    pub l_start: i64,
+    /// 锁定的字节数，为0表示一直到文件末尾
+    pub l_len: i64,
+    /// 持有冲突的锁的进程的pid（仅用于F_GETLK）
+    pub l_pid: i32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IoVec {
+    /// 缓冲区的起始地址
+    pub iov_base: *mut u8,
+    /// 缓冲区的长度
+    pub iov_len: usize,
+}
+
+/// 用于存储多个来自用户空间的IoVec
+///
+/// 由于目前内核中的文件系统还不支持分散读写，所以暂时只支持将用户空间的IoVec聚合成一个缓冲区，然后进行操作。
+/// TODO：支持分散读写
+#[derive(Debug)]
+pub struct IoVecs(Vec<&'static mut [u8]>);
+
+impl IoVecs {
+    /// 单次系统调用允许的IoVec的最大数量
+    pub const IOV_MAX: usize = 1024;
+
+    /// 从用户空间的IoVec中构造IoVecs
+    ///
+    /// @param iov 用户空间的IoVec
+    /// @param iovcnt 用户空间的IoVec的数量
+    /// @param readv 是否为readv系统调用
+    ///
+    /// @return 构造成功返回IoVecs，否则返回错误码
+    pub unsafe fn from_user(
+        iov: *const IoVec,
+        iovcnt: usize,
+        readv: bool,
+    ) -> Result<Self, SystemError> {
+        if iovcnt > Self::IOV_MAX {
+            return Err(SystemError::EINVAL);
+        }
+
+        // 检查iov指针所在空间是否合法
+        if !verify_area(
+            iov as usize as u64,
+            (iovcnt * core::mem::size_of::<IoVec>()) as u64,
+        ) {
+            return Err(SystemError::EFAULT);
         }
 
         // 将用户空间的IoVec转换为引用（注意：这里的引用是静态的，因为用户空间的IoVec不会被释放）
@@ -684,6 +2267,12 @@ impl IoVecs {
             if !verify_area(iov.iov_base as usize as u64, iov.iov_len as u64) {
                 return Err(SystemError::EFAULT);
             }
+            // readv会向缓冲区写入数据
+            if readv
+                && verify_area_writable(VirtAddr::new(iov.iov_base as usize), iov.iov_len).is_err()
+            {
+                return Err(SystemError::EFAULT);
+            }
 
             slices.push(core::slice::from_raw_parts_mut(iov.iov_base, iov.iov_len));
         }
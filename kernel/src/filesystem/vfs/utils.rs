@@ -1,3 +1,11 @@
+use alloc::{format, string::String, sync::Arc, vec::Vec};
+
+use crate::{
+    arch::asm::current::current_pcb, include::bindings::bindings::AT_FDCWD, syscall::SystemError,
+};
+
+use super::{FileType, IndexNode, MAX_PATHLEN, ROOT_INODE};
+
 /// @brief 切分路径字符串，返回最左侧那一级的目录名和剩余的部分。
 ///
 /// 举例：对于 /123/456/789/   本函数返回的第一个值为123, 第二个值为456/789
@@ -19,3 +27,98 @@ pub fn rsplit_path(path: &str) -> (&str, Option<&str>) {
 
     return (comp, rest_opt);
 }
+
+/// @brief 把路径转换为规范的绝对路径（不解析符号链接）
+///
+/// 举例：对于 //123/./456/../789/   本函数返回 /123/789
+pub fn normalize_path(path: &str) -> String {
+    let mut comps: Vec<&str> = Vec::new();
+    for comp in path.split('/') {
+        match comp {
+            "" | "." => {}
+            ".." => {
+                comps.pop();
+            }
+            c => comps.push(c),
+        }
+    }
+
+    let mut result = String::new();
+    for comp in comps {
+        result.push('/');
+        result.push_str(comp);
+    }
+    if result.is_empty() {
+        result.push('/');
+    }
+    return result;
+}
+
+/// @brief 解析系统调用的路径参数，获取解析该路径时所使用的起始目录
+///
+/// 所有接受路径参数的系统调用都通过本函数对路径进行相同的预处理：去掉首尾的空白字符，检查路径的长度，
+/// 然后按照以下规则选择起始目录：
+/// - 如果path是绝对路径，则忽略dirfd，从根目录开始解析
+/// - 如果dirfd为AT_FDCWD，则从当前工作目录开始解析
+/// - 否则，从dirfd所指向的目录开始解析
+///
+/// @param dirfd 目录的文件描述符
+/// @param path 要解析的路径
+///
+/// @return Ok((Arc<dyn IndexNode>, &str)) 起始目录的inode，以及预处理之后的路径
+/// @return Err(ENAMETOOLONG) 路径过长
+/// @return Err(ENOENT) 路径为空
+/// @return Err(EBADF) dirfd不是一个有效的文件描述符
+/// @return Err(ENOTDIR) dirfd所指向的文件不是目录
+pub fn user_path_at(dirfd: i32, path: &str) -> Result<(Arc<dyn IndexNode>, &str), SystemError> {
+    let path: &str = path.trim();
+    if path.len() > MAX_PATHLEN {
+        return Err(SystemError::ENAMETOOLONG);
+    }
+    if path.is_empty() {
+        return Err(SystemError::ENOENT);
+    }
+
+    if path.starts_with('/') {
+        return Ok((ROOT_INODE(), path));
+    }
+    let inode: Arc<dyn IndexNode> = user_path_empty_at(dirfd)?;
+    if inode.metadata()?.file_type != FileType::Dir {
+        return Err(SystemError::ENOTDIR);
+    }
+    return Ok((inode, path));
+}
+
+/// @brief 获取dirfd本身所对应的inode（用于AT_EMPTY_PATH）
+///
+/// @return Ok(Arc<dyn IndexNode>) dirfd为AT_FDCWD时，返回当前工作目录的inode，否则返回dirfd所指向的文件的inode
+/// @return Err(EBADF) dirfd不是一个有效的文件描述符
+pub fn user_path_empty_at(dirfd: i32) -> Result<Arc<dyn IndexNode>, SystemError> {
+    if dirfd == AT_FDCWD {
+        return Ok(current_pcb().cwd().inode());
+    }
+    return current_pcb()
+        .get_file_ref_by_fd(dirfd)
+        .map(|file| file.inode())
+        .ok_or(SystemError::EBADF);
+}
+
+/// @brief 根据dirfd和路径，获取该路径对应的规范的绝对路径（不解析符号链接）
+///
+/// 路径的预处理、起始目录的选择规则与[`user_path_at`]相同
+///
+/// @return Some(String) 绝对路径
+/// @return None dirfd无效，或者dirfd所指向的文件的路径未知
+pub fn user_path_string_at(dirfd: i32, path: &str) -> Option<String> {
+    let path: &str = path.trim();
+    if path.starts_with('/') {
+        return Some(normalize_path(path));
+    }
+
+    let pcb = current_pcb();
+    if dirfd == AT_FDCWD {
+        return Some(normalize_path(&format!("{}/{}", pcb.cwd().path(), path)));
+    }
+    let file = pcb.get_file_ref_by_fd(dirfd)?;
+    return Some(normalize_path(&format!("{}/{}", file.path()?, path)));
+}
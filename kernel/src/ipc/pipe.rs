@@ -2,8 +2,11 @@ use crate::{
     arch::{sched::sched, CurrentIrqArch},
     exception::InterruptArch,
     filesystem::vfs::{
-        core::generate_inode_id, file::FileMode, FilePrivateData, FileSystem, FileType, IndexNode,
-        Metadata, PollStatus,
+        core::generate_inode_id,
+        file::FileMode,
+        poll::{PollWaitQueue, PollWaiter},
+        pseudo::pipe_fs,
+        FilePrivateData, FileSystem, FileType, IndexNode, Metadata, PollStatus,
     },
     include::bindings::bindings::PROC_INTERRUPTIBLE,
     libs::{spinlock::SpinLock, wait_queue::WaitQueue},
@@ -29,6 +32,8 @@ pub struct InnerPipeInode {
     write_pos: i32,
     read_wait_queue: WaitQueue,
     write_wait_queue: WaitQueue,
+    /// 通过poll、select、epoll等待这个管道的进程
+    poll_wait_queue: PollWaitQueue,
     data: [u8; PIPE_BUFF_SIZE],
     /// INode 元数据
     metadata: Metadata,
@@ -44,11 +49,13 @@ impl LockedPipeInode {
             write_pos: 0,
             read_wait_queue: WaitQueue::INIT,
             write_wait_queue: WaitQueue::INIT,
+            poll_wait_queue: PollWaitQueue::INIT,
             data: [0; PIPE_BUFF_SIZE],
 
             metadata: Metadata {
                 dev_id: 0,
                 inode_id: generate_inode_id(),
+                disk_ino: None,
                 size: PIPE_BUFF_SIZE as i64,
                 blk_size: 0,
                 blocks: 0,
@@ -73,6 +80,20 @@ impl LockedPipeInode {
     }
 }
 
+impl InnerPipeInode {
+    /// @brief 获取管道当前的状态：有数据时可读，有剩余空间时可写
+    fn poll_status(&self) -> PollStatus {
+        let mut status = PollStatus::empty();
+        if self.valid_cnt > 0 {
+            status.insert(PollStatus::READ);
+        }
+        if (self.valid_cnt as usize) < PIPE_BUFF_SIZE {
+            status.insert(PollStatus::WRITE);
+        }
+        return status;
+    }
+}
+
 impl IndexNode for LockedPipeInode {
     fn read_at(
         &self,
@@ -133,6 +154,7 @@ impl IndexNode for LockedPipeInode {
 
         //读完后解锁并唤醒等待在写等待队列中的进程
         inode.write_wait_queue.wakeup(PROC_INTERRUPTIBLE.into());
+        inode.poll_wait_queue.wakeup(inode.poll_status());
         //返回读取的字节数
         return Ok(num);
     }
@@ -209,12 +231,18 @@ impl IndexNode for LockedPipeInode {
 
         // 读完后解锁并唤醒等待在读等待队列中的进程
         inode.read_wait_queue.wakeup(PROC_INTERRUPTIBLE.into());
+        inode.poll_wait_queue.wakeup(inode.poll_status());
         // 返回写入的字节数
         return Ok(len);
     }
 
     fn poll(&self) -> Result<PollStatus, crate::syscall::SystemError> {
-        return Ok(PollStatus::READ | PollStatus::WRITE);
+        return Ok(self.0.lock().poll_status());
+    }
+
+    fn add_poll_waiter(&self, waiter: Weak<dyn PollWaiter>) -> Result<(), SystemError> {
+        self.0.lock().poll_wait_queue.register(waiter);
+        return Ok(());
     }
 
     fn as_any_ref(&self) -> &dyn core::any::Any {
@@ -232,7 +260,7 @@ impl IndexNode for LockedPipeInode {
     }
 
     fn fs(&self) -> Arc<(dyn FileSystem)> {
-        todo!()
+        return pipe_fs();
     }
 
     fn list(&self) -> Result<alloc::vec::Vec<alloc::string::String>, SystemError> {
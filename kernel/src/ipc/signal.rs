@@ -355,7 +355,7 @@ fn sig_fatal(pcb: &process_control_block, sig: SignalNumber) -> bool {
 
 /// @brief 判断某个进程是否有信号正在等待处理
 #[inline]
-fn has_sig_pending(pcb: &process_control_block) -> bool {
+pub fn has_sig_pending(pcb: &process_control_block) -> bool {
     let ptr = &sigpending::convert_ref(&(*pcb).sig_pending).unwrap().signal;
     if unsafe { read_volatile(ptr) } != 0 {
         return true;
@@ -895,7 +895,8 @@ pub fn sys_rt_sigreturn(regs: &mut pt_regs) -> u64 {
     return regs.rax;
 }
 
-fn set_current_sig_blocked(new_set: &mut sigset_t) {
+/// @brief 设置当前进程的信号屏蔽字（SIGKILL和SIGSTOP不能被屏蔽）
+pub fn set_current_sig_blocked(new_set: &mut sigset_t) {
     sigset_delmask(
         new_set,
         sigmask(SignalNumber::SIGKILL) | sigmask(SignalNumber::SIGSTOP),
@@ -65,6 +65,8 @@ impl<A> PageList<A> {
 pub struct BuddyAllocator<A> {
     // 存放每个阶的空闲“链表”的头部地址
     free_area: [PhysAddr; (MAX_ORDER - MIN_ORDER) as usize],
+    // 交给伙伴系统管理的页面总数
+    total: PageFrameCount,
     phantom: PhantomData<A>,
 }
 
@@ -230,6 +232,7 @@ impl<A: MemoryManagementArch> BuddyAllocator<A> {
         // Self::print_free_area(free_area);
         let allocator = Self {
             free_area,
+            total: pages_to_buddy,
             phantom: PhantomData,
         };
 
@@ -661,8 +664,21 @@ impl<A: MemoryManagementArch> FrameAllocator for BuddyAllocator<A> {
         self.buddy_free(base, order);
     }
 
+    /// 统计各阶空闲链表中的伙伴块，得到空闲的页面数
+    ///
+    /// 从伙伴系统中分配出来、用于存放空闲链表的页面，会被视为已使用的页面
     unsafe fn usage(&self) -> PageFrameUsage {
-        todo!("BuddyAllocator::usage")
+        let mut free: usize = 0;
+        for (index, first_page_list_paddr) in self.free_area.iter().enumerate() {
+            let mut page_list_paddr: PhysAddr = *first_page_list_paddr;
+            while !page_list_paddr.is_null() {
+                let page_list: PageList<A> = Self::read_page(page_list_paddr);
+                free += page_list.entry_num << index;
+                page_list_paddr = page_list.next_page;
+            }
+        }
+        let used = PageFrameCount::new(self.total.data().saturating_sub(free));
+        return PageFrameUsage::new(used, self.total);
     }
 }
 
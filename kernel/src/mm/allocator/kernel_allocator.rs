@@ -8,6 +8,7 @@ use core::{
     alloc::{AllocError, GlobalAlloc, Layout},
     intrinsics::unlikely,
     ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
 use super::page_frame::{FrameAllocator, PageFrameCount};
@@ -21,6 +22,14 @@ pub trait LocalAlloc {
 
 pub struct KernelAllocator;
 
+/// 内核堆当前从伙伴系统中申请的页面数
+static KERNEL_HEAP_PAGES: AtomicUsize = AtomicUsize::new(0);
+
+/// @brief 获取内核堆当前占用的页面数
+pub fn kernel_heap_pages() -> PageFrameCount {
+    return PageFrameCount::new(KERNEL_HEAP_PAGES.load(Ordering::Relaxed));
+}
+
 impl KernelAllocator {
     unsafe fn alloc_in_buddy(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
         // 计算需要申请的页数，向上取整
@@ -34,6 +43,7 @@ impl KernelAllocator {
         if unlikely(virt_addr.is_null()) {
             return Err(AllocError);
         }
+        KERNEL_HEAP_PAGES.fetch_add(allocated_frame_count.data(), Ordering::Relaxed);
 
         let slice = unsafe {
             core::slice::from_raw_parts_mut(
@@ -50,6 +60,7 @@ impl KernelAllocator {
         let page_frame_count = PageFrameCount::new(count);
         let phy_addr = MMArch::virt_2_phys(VirtAddr::new(ptr as usize)).unwrap();
         LockedFrameAllocator.free(phy_addr, page_frame_count);
+        KERNEL_HEAP_PAGES.fetch_sub(page_frame_count.data(), Ordering::Relaxed);
     }
 }
 
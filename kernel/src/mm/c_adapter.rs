@@ -2,12 +2,16 @@
 
 use core::intrinsics::unlikely;
 
-use alloc::vec::Vec;
+use alloc::{sync::Arc, vec::Vec};
 use hashbrown::HashMap;
 
 use crate::{
-    arch::mm::LowAddressRemapping,
+    arch::{asm::current::current_pcb, mm::LowAddressRemapping},
     include::bindings::bindings::{gfp_t, PAGE_U_S},
+    ipc::{
+        signal::signal_kill_something_info,
+        signal_types::{si_code_val, siginfo, SignalNumber},
+    },
     kerror,
     libs::{align::page_align_up, spinlock::SpinLock},
     mm::MMArch,
@@ -133,3 +137,47 @@ pub unsafe extern "C" fn rs_unmap_at_low_addr() -> usize {
     LowAddressRemapping::unmap_at_low_address(true);
     return 0;
 }
+
+/// [EXTERN TO C] 处理用户地址空间内的缺页异常（文件映射的按需调页、写时复制）
+///
+/// @param address 触发缺页的虚拟地址
+/// @param write 缺页是否由写操作引起
+/// @param user 缺页是否发生在用户态
+///
+/// @return 0 缺页已被处理（或者已经向进程发送了SIGBUS），可以返回
+/// @return 负数 无法处理该缺页
+#[no_mangle]
+pub unsafe extern "C" fn rs_handle_user_page_fault(address: usize, write: bool, user: bool) -> i32 {
+    let vaddr = VirtAddr::new(address);
+    if !vaddr.check_user() {
+        return SystemError::EFAULT.to_posix_errno();
+    }
+
+    let address_space = match current_pcb().address_space() {
+        Some(address_space) => address_space,
+        None => return SystemError::EFAULT.to_posix_errno(),
+    };
+    let owner = Arc::downgrade(&address_space);
+    let r = address_space
+        .write()
+        .handle_page_fault(vaddr, write, &owner);
+    match r {
+        Ok(_) => return 0,
+        // 地址不属于任何VMA，或者访问权限不满足
+        Err(SystemError::EFAULT) => return SystemError::EFAULT.to_posix_errno(),
+        // 访问超出了文件的末尾，或者无法读取文件的内容。
+        // 用户态的访问产生SIGBUS，内核态的访问则无法恢复，交给调用者处理
+        Err(e) => {
+            if !user {
+                return e.to_posix_errno();
+            }
+            let mut info = siginfo::new(SignalNumber::SIGBUS, 0, si_code_val::SI_KERNEL);
+            if let Err(e) =
+                signal_kill_something_info(SignalNumber::SIGBUS, Some(&mut info), current_pcb().pid)
+            {
+                return e.to_posix_errno();
+            }
+            return 0;
+        }
+    }
+}
@@ -8,6 +8,7 @@
 extern void rs_pseudo_map_phys(uint64_t virt_addr, uint64_t phys_addr, uint64_t size);
 extern void rs_map_phys(uint64_t virt_addr, uint64_t phys_addr, uint64_t size, uint64_t flags);
 extern uint64_t rs_unmap_at_low_addr();
+extern int rs_handle_user_page_fault(uint64_t address, bool write, bool user);
 
 // 内核层的起始地址
 #define PAGE_OFFSET 0xffff800000000000UL
@@ -1,7 +1,7 @@
 use alloc::sync::Arc;
 
 use crate::{
-    arch::MMArch,
+    arch::{asm::current::current_pcb, MMArch},
     include::bindings::bindings::{process_control_block, PAGE_OFFSET},
     syscall::SystemError,
 };
@@ -27,6 +27,7 @@ pub mod kernel_mapper;
 pub mod mmio_buddy;
 pub mod no_init;
 pub mod page;
+pub mod page_cache;
 pub mod percpu;
 pub mod syscall;
 pub mod ucontext;
@@ -637,6 +638,27 @@ pub fn verify_area(addr: VirtAddr, size: usize) -> Result<(), SystemError> {
 
     return Ok(());
 }
+
+/// ## 判断用户空间的缓冲区是否可以被内核写入
+///
+/// 内核开启了CR0.WP，向只读的用户页写入会触发缺页。来自只读映射的页所触发的缺页无法被处理，
+/// 会导致进程被杀死。因此，内核在向用户缓冲区写入之前，需要通过本函数检查整个缓冲区都位于可写的映射之内。
+///
+/// 如果缓冲区不在用户空间内，或者存在未被映射、不可写的部分，返回Err(SystemError::EFAULT)
+pub fn verify_area_writable(addr: VirtAddr, size: usize) -> Result<(), SystemError> {
+    verify_area(addr, size)?;
+    if size == 0 {
+        return Ok(());
+    }
+
+    // 内核线程没有用户地址空间，不需要检查
+    let address_space = match current_pcb().address_space() {
+        Some(address_space) => address_space,
+        None => return Ok(()),
+    };
+    let r = address_space.read().check_user_writable(addr, size);
+    return r;
+}
 // ====== 重构内存管理、进程管理后，请删除这几行 BEGIN ======
 //BUG pcb问题
 unsafe impl Send for process_control_block {}
@@ -0,0 +1,530 @@
+//! 页缓存(page cache)
+//!
+//! 每个需要缓存的inode都拥有一个PageCache对象，它以“页在文件内的序号”为键，
+//! 缓存文件内容所在的物理页。文件的读写操作会先经过页缓存：
+//! - 读：如果页不在缓存中，则从存储设备读取一整页，放入缓存；
+//! - 写：把数据写入缓存中的页，并把页标记为“脏页”，等到sync/fsync或者内存紧张时再写回。
+//!
+//! 缓存的页数超过上限，或者任何一次物理页分配失败时（见page_cache_reclaim），会从所有的页缓存中回收干净的页。
+//! 如果干净的页不够，还会要求页缓存的拥有者（具体文件系统的inode）把脏页写回，然后再回收。
+
+use core::{
+    fmt::Debug,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use alloc::{
+    collections::BTreeMap,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+
+use crate::{
+    arch::{CurrentIrqArch, MMArch},
+    exception::InterruptArch,
+    kerror,
+    libs::spinlock::SpinLock,
+    mm::{
+        allocator::page_frame::{
+            allocate_page_frames, deallocate_page_frames, PageFrameCount, PhysPageFrame,
+        },
+        ucontext::AddressSpace,
+        MemoryManagementArch, PhysAddr, VirtAddr,
+    },
+    syscall::SystemError,
+};
+
+/// 所有页缓存中的页加起来，最多占用的页数（64MB）
+pub const PAGE_CACHE_MAX_PAGES: usize = 16384;
+/// 每次回收时，尝试回收的页数
+const PAGE_CACHE_SHRINK_BATCH: usize = PAGE_CACHE_MAX_PAGES / 8;
+
+/// 当前所有页缓存中的页的数量
+static PAGE_CACHE_PAGES: AtomicUsize = AtomicUsize::new(0);
+/// 是否有人正在因为物理页分配失败而回收页缓存
+static RECLAIMING: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    /// 系统中所有的页缓存
+    static ref PAGE_CACHE_LIST: SpinLock<Vec<Weak<PageCache>>> = SpinLock::new(Vec::new());
+}
+
+/// @brief 页缓存的拥有者需要实现的trait
+///
+/// 页缓存本身不知道如何访问存储设备，当需要把脏页写回时，会通过本trait通知它的拥有者。
+pub trait PageCacheBackend: Send + Sync + Debug {
+    /// @brief 把页缓存中所有的脏页写回存储设备（可以阻塞）
+    fn writeback(&self) -> Result<(), SystemError>;
+
+    /// @brief 尝试把页缓存中所有的脏页写回存储设备
+    ///
+    /// 本函数在回收内存时被调用，此时调用者可能正持有其他inode的锁，
+    /// 因此实现者不能阻塞等待锁，获取锁失败时应当直接返回false
+    ///
+    /// @return true 完成了写回
+    /// @return false 没有获取到锁，未进行写回
+    fn try_writeback(&self) -> bool;
+}
+
+/// @brief 页缓存中的一个页
+#[derive(Debug)]
+pub struct CachedPage {
+    /// 页所在的物理地址
+    paddr: PhysAddr,
+    /// 页的内容是否被修改过，并且还没有写回
+    dirty: AtomicBool,
+    /// 把本页映射为可写的共享文件映射（所在的地址空间，虚拟地址）
+    ///
+    /// 通过可写的映射写入页时不会经过内核，因此写回之前需要先把这些映射设置为只读，
+    /// 这样之后的写操作才能通过缺页异常重新把页标记为脏页
+    writable_mappings: SpinLock<Vec<(Weak<AddressSpace>, VirtAddr)>>,
+}
+
+impl CachedPage {
+    /// @brief 分配一个新的、内容全为0的页
+    ///
+    /// 如果页缓存已经超出上限，会先尝试回收页缓存（物理页分配失败时的回收由页帧分配器负责）
+    fn new() -> Result<Arc<Self>, SystemError> {
+        if PAGE_CACHE_PAGES.load(Ordering::Relaxed) >= PAGE_CACHE_MAX_PAGES {
+            page_cache_shrink(PAGE_CACHE_SHRINK_BATCH);
+        }
+
+        let paddr: PhysAddr = unsafe { allocate_page_frames(PageFrameCount::new(1)) }
+            .ok_or(SystemError::ENOMEM)?
+            .0;
+
+        unsafe {
+            let vaddr = MMArch::phys_2_virt(paddr).unwrap();
+            MMArch::write_bytes(vaddr, 0, MMArch::PAGE_SIZE);
+        }
+        PAGE_CACHE_PAGES.fetch_add(1, Ordering::Relaxed);
+
+        return Ok(Arc::new(CachedPage {
+            paddr,
+            dirty: AtomicBool::new(false),
+            writable_mappings: SpinLock::new(Vec::new()),
+        }));
+    }
+
+    /// @brief 获取页的物理地址
+    #[inline]
+    pub fn phys_address(&self) -> PhysAddr {
+        return self.paddr;
+    }
+
+    /// @brief 以只读切片的形式访问页的内容
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe {
+            let vaddr = MMArch::phys_2_virt(self.paddr).unwrap();
+            return core::slice::from_raw_parts(vaddr.data() as *const u8, MMArch::PAGE_SIZE);
+        }
+    }
+
+    /// @brief 以可变切片的形式访问页的内容
+    ///
+    /// ## Safety
+    ///
+    /// 调用者需要保证同一时刻只有一个写者（一般由页缓存的拥有者的锁来保证）
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn as_mut_slice(&self) -> &mut [u8] {
+        let vaddr = MMArch::phys_2_virt(self.paddr).unwrap();
+        return core::slice::from_raw_parts_mut(vaddr.data() as *mut u8, MMArch::PAGE_SIZE);
+    }
+
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        return self.dirty.load(Ordering::Acquire);
+    }
+
+    #[inline]
+    pub fn set_dirty(&self, dirty: bool) {
+        self.dirty.store(dirty, Ordering::Release);
+    }
+
+    /// @brief 记录本页在address_space的vaddr处被映射为可写
+    pub fn add_writable_mapping(&self, address_space: Weak<AddressSpace>, vaddr: VirtAddr) {
+        let mut mappings = self.writable_mappings.lock();
+        let exists = mappings
+            .iter()
+            .any(|(a, v)| *v == vaddr && Weak::ptr_eq(a, &address_space));
+        if !exists {
+            mappings.push((address_space, vaddr));
+        }
+    }
+
+    /// @brief 把所有可写的映射设置为只读
+    ///
+    /// 调用者可能正持有某个地址空间的锁（例如在缺页处理中回收内存），因此这里不会阻塞等待地址空间的锁
+    ///
+    /// @return true 本页已经没有可写的映射了
+    /// @return false 有地址空间的锁获取失败，本页可能仍然被映射为可写
+    fn write_protect(&self) -> bool {
+        let mut all_protected = true;
+        self.writable_mappings
+            .lock()
+            .retain(|(address_space, vaddr)| {
+                let address_space = match address_space.upgrade() {
+                    Some(a) => a,
+                    None => return false,
+                };
+                match address_space.try_write() {
+                    Some(mut guard) => {
+                        guard.write_protect_page(*vaddr, self.paddr);
+                        return false;
+                    }
+                    None => {
+                        all_protected = false;
+                        return true;
+                    }
+                }
+            });
+        return all_protected;
+    }
+}
+
+impl Drop for CachedPage {
+    fn drop(&mut self) {
+        unsafe {
+            deallocate_page_frames(PhysPageFrame::new(self.paddr), PageFrameCount::new(1));
+        }
+        PAGE_CACHE_PAGES.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// @brief 一个inode的页缓存
+#[derive(Debug)]
+pub struct PageCache {
+    /// 页在文件内的序号 -> 页
+    pages: SpinLock<BTreeMap<usize, Arc<CachedPage>>>,
+    /// 页缓存的拥有者
+    backend: Weak<dyn PageCacheBackend>,
+}
+
+impl PageCache {
+    /// @brief 创建一个新的页缓存，并把它加入全局的页缓存列表
+    ///
+    /// @param backend 页缓存的拥有者，用于在回收内存时写回脏页
+    pub fn new(backend: Weak<dyn PageCacheBackend>) -> Arc<Self> {
+        let result = Arc::new(PageCache {
+            pages: SpinLock::new(BTreeMap::new()),
+            backend,
+        });
+
+        let mut list = PAGE_CACHE_LIST.lock();
+        // 顺便清理已经被释放的页缓存
+        if list.len() >= 64 && list.len().is_power_of_two() {
+            list.retain(|x| x.strong_count() > 0);
+        }
+        list.push(Arc::downgrade(&result));
+        return result;
+    }
+
+    /// @brief 获取页缓存中的页（不会从存储设备读取）
+    pub fn get_page(&self, index: usize) -> Option<Arc<CachedPage>> {
+        return self.pages.lock().get(&index).cloned();
+    }
+
+    /// @brief 获取页缓存中的页。如果页不在缓存中，则调用read_page从存储设备读取
+    ///
+    /// @param index 页在文件内的序号
+    /// @param file_size 文件在存储设备上的大小。超出文件大小的页不会被读取，而是直接填0
+    /// @param read_page 从存储设备读取一页的函数，参数为（页的序号，缓冲区），返回读取到的字节数
+    pub fn get_or_read_page<F>(
+        &self,
+        index: usize,
+        file_size: usize,
+        read_page: &mut F,
+    ) -> Result<Arc<CachedPage>, SystemError>
+    where
+        F: FnMut(usize, &mut [u8]) -> Result<usize, SystemError>,
+    {
+        if let Some(page) = self.get_page(index) {
+            return Ok(page);
+        }
+
+        // 在不持有锁的情况下分配页、读取磁盘
+        let page: Arc<CachedPage> = CachedPage::new()?;
+        if index * MMArch::PAGE_SIZE < file_size {
+            read_page(index, unsafe { page.as_mut_slice() })?;
+        }
+
+        let mut guard = self.pages.lock();
+        // 其他人可能已经把这一页读进来了，此时以缓存中的为准
+        let page = guard.entry(index).or_insert(page).clone();
+        return Ok(page);
+    }
+
+    /// @brief 通过页缓存读取文件
+    ///
+    /// @param offset 读取的起始位置在文件中的偏移量
+    /// @param buf 输出缓冲区
+    /// @param file_size 文件的大小
+    /// @param read_page 从存储设备读取一页的函数
+    ///
+    /// @return Ok(usize) 成功读取的字节数
+    pub fn read<F>(
+        &self,
+        offset: usize,
+        buf: &mut [u8],
+        file_size: usize,
+        mut read_page: F,
+    ) -> Result<usize, SystemError>
+    where
+        F: FnMut(usize, &mut [u8]) -> Result<usize, SystemError>,
+    {
+        if offset >= file_size {
+            return Ok(0);
+        }
+        let len = core::cmp::min(buf.len(), file_size - offset);
+
+        let mut done = 0;
+        while done < len {
+            let pos = offset + done;
+            let index = pos / MMArch::PAGE_SIZE;
+            let in_page_offset = pos % MMArch::PAGE_SIZE;
+            let count = core::cmp::min(len - done, MMArch::PAGE_SIZE - in_page_offset);
+
+            let page = self.get_or_read_page(index, file_size, &mut read_page)?;
+            buf[done..done + count]
+                .copy_from_slice(&page.as_slice()[in_page_offset..in_page_offset + count]);
+            done += count;
+        }
+        return Ok(done);
+    }
+
+    /// @brief 通过页缓存写入文件。数据只会被写入缓存，并把页标记为脏页
+    ///
+    /// 请注意，本函数不会改变文件的大小，调用者需要保证写入的范围在文件大小之内，
+    /// 或者自行处理文件大小的变化。
+    ///
+    /// @param offset 写入的起始位置在文件中的偏移量
+    /// @param buf 输入缓冲区
+    /// @param file_size 文件在存储设备上的大小
+    /// @param read_page 从存储设备读取一页的函数（当只写入一页中的一部分时，需要先读出整页）
+    ///
+    /// @return Ok(usize) 成功写入的字节数
+    pub fn write<F>(
+        &self,
+        offset: usize,
+        buf: &[u8],
+        file_size: usize,
+        mut read_page: F,
+    ) -> Result<usize, SystemError>
+    where
+        F: FnMut(usize, &mut [u8]) -> Result<usize, SystemError>,
+    {
+        let mut done = 0;
+        while done < buf.len() {
+            let pos = offset + done;
+            let index = pos / MMArch::PAGE_SIZE;
+            let in_page_offset = pos % MMArch::PAGE_SIZE;
+            let count = core::cmp::min(buf.len() - done, MMArch::PAGE_SIZE - in_page_offset);
+
+            let page = if count == MMArch::PAGE_SIZE {
+                // 整页都会被覆盖，不需要从存储设备读取
+                self.get_or_read_page(index, 0, &mut read_page)?
+            } else {
+                self.get_or_read_page(index, file_size, &mut read_page)?
+            };
+
+            unsafe {
+                page.as_mut_slice()[in_page_offset..in_page_offset + count]
+                    .copy_from_slice(&buf[done..done + count]);
+            }
+            page.set_dirty(true);
+            done += count;
+        }
+        return Ok(done);
+    }
+
+    /// @brief 数据已经被直接写入了存储设备，更新缓存中对应的页（不改变页的脏标志）
+    ///
+    /// @param offset 写入的起始位置在文件中的偏移量
+    /// @param buf 已经写入存储设备的数据
+    pub fn update(&self, offset: usize, buf: &[u8]) {
+        let mut done = 0;
+        while done < buf.len() {
+            let pos = offset + done;
+            let index = pos / MMArch::PAGE_SIZE;
+            let in_page_offset = pos % MMArch::PAGE_SIZE;
+            let count = core::cmp::min(buf.len() - done, MMArch::PAGE_SIZE - in_page_offset);
+
+            if let Some(page) = self.get_page(index) {
+                unsafe {
+                    page.as_mut_slice()[in_page_offset..in_page_offset + count]
+                        .copy_from_slice(&buf[done..done + count]);
+                }
+            }
+            done += count;
+        }
+    }
+
+    /// @brief 把所有的脏页写回存储设备
+    ///
+    /// @param file_size 文件的大小。每一页中，只有文件大小以内的部分会被写回
+    /// @param write_page 把一页写回存储设备的函数，参数为（页的序号，要写入的数据）
+    pub fn writeback<F>(&self, file_size: usize, mut write_page: F) -> Result<(), SystemError>
+    where
+        F: FnMut(usize, &[u8]) -> Result<(), SystemError>,
+    {
+        let dirty_pages: Vec<(usize, Arc<CachedPage>)> = self
+            .pages
+            .lock()
+            .iter()
+            .filter(|(_, page)| page.is_dirty())
+            .map(|(index, page)| (*index, page.clone()))
+            .collect();
+
+        for (index, page) in dirty_pages {
+            let start = index * MMArch::PAGE_SIZE;
+            if start >= file_size {
+                page.set_dirty(false);
+                continue;
+            }
+            let len = core::cmp::min(MMArch::PAGE_SIZE, file_size - start);
+
+            // 先清除脏标志，这样在写回期间被再次修改的页，仍然会保持为脏页。
+            // 仍然被映射为可写的页，随时可能被修改，因此写回之后仍然保持为脏页
+            if page.write_protect() {
+                page.set_dirty(false);
+            }
+            if let Err(e) = write_page(index, &page.as_slice()[..len]) {
+                page.set_dirty(true);
+                kerror!("PageCache: Failed to write back page {index}, err={e:?}");
+                return Err(e);
+            }
+        }
+        return Ok(());
+    }
+
+    /// @brief 文件被截断之后，丢弃超出文件大小的页，并把最后一页中超出文件大小的部分清零
+    ///
+    /// @param len 新的文件大小
+    pub fn truncate(&self, len: usize) {
+        let first_removed = (len + MMArch::PAGE_SIZE - 1) / MMArch::PAGE_SIZE;
+        let mut guard = self.pages.lock();
+        let removed: BTreeMap<usize, Arc<CachedPage>> = guard.split_off(&first_removed);
+
+        if len % MMArch::PAGE_SIZE != 0 {
+            if let Some(page) = guard.get(&(len / MMArch::PAGE_SIZE)) {
+                unsafe {
+                    page.as_mut_slice()[len % MMArch::PAGE_SIZE..].fill(0);
+                }
+            }
+        }
+        drop(guard);
+        drop(removed);
+    }
+
+    /// @brief 回收干净的、没有被其他地方引用的页
+    ///
+    /// @param max 最多回收的页数
+    ///
+    /// @return 回收的页数
+    pub fn evict_clean(&self, max: usize) -> usize {
+        // 回收可能发生在持有页缓存的锁的时候（例如在读取文件时分配页），此时不能阻塞
+        let mut guard = match self.pages.try_lock() {
+            Ok(guard) => guard,
+            Err(_) => return 0,
+        };
+        let victims: Vec<usize> = guard
+            .iter()
+            .filter(|(_, page)| !page.is_dirty() && Arc::strong_count(page) == 1)
+            .map(|(index, _)| *index)
+            .take(max)
+            .collect();
+
+        let mut removed: Vec<Arc<CachedPage>> = Vec::with_capacity(victims.len());
+        for index in victims.iter() {
+            if let Some(page) = guard.remove(index) {
+                removed.push(page);
+            }
+        }
+        drop(guard);
+
+        return removed.len();
+    }
+
+    /// @brief 获取页缓存中页的数量
+    pub fn nr_pages(&self) -> usize {
+        return self.pages.lock().len();
+    }
+}
+
+/// @brief 获取系统中所有仍然存活的页缓存
+fn all_page_caches() -> Vec<Arc<PageCache>> {
+    let mut list = PAGE_CACHE_LIST.lock();
+    list.retain(|x| x.strong_count() > 0);
+    return list.iter().filter_map(|x| x.upgrade()).collect();
+}
+
+/// @brief 回收页缓存
+///
+/// 先回收干净的页，如果不够的话，再要求页缓存的拥有者写回脏页，然后再回收
+///
+/// @param target 希望回收的页数
+///
+/// @return 实际回收的页数
+pub fn page_cache_shrink(target: usize) -> usize {
+    let caches: Vec<Arc<PageCache>> = all_page_caches();
+    let mut freed = 0;
+
+    for cache in caches.iter() {
+        if freed >= target {
+            return freed;
+        }
+        freed += cache.evict_clean(target - freed);
+    }
+
+    for cache in caches.iter() {
+        if freed >= target {
+            break;
+        }
+        if let Some(backend) = cache.backend.upgrade() {
+            if backend.try_writeback() {
+                freed += cache.evict_clean(target - freed);
+            }
+        }
+    }
+    return freed;
+}
+
+/// @brief 物理页分配失败时，回收页缓存，以便调用者重试分配
+///
+/// 回收的过程中会分配、释放内存，还可能写回脏页，因此同一时刻只允许一个回收者，以免递归；
+/// 中断被关闭时（中断上下文中，或者持有spinlock_irqsave时）也不进行回收。
+///
+/// @param count 分配失败的页数
+///
+/// @return true 回收到了页，调用者可以重试分配
+/// @return false 没有进行回收，或者没有回收到页
+pub fn page_cache_reclaim(count: PageFrameCount) -> bool {
+    if !CurrentIrqArch::is_irq_enabled() || PAGE_CACHE_PAGES.load(Ordering::Relaxed) == 0 {
+        return false;
+    }
+    if RECLAIMING.swap(true, Ordering::Acquire) {
+        return false;
+    }
+    let freed = page_cache_shrink(core::cmp::max(count.data(), PAGE_CACHE_SHRINK_BATCH));
+    RECLAIMING.store(false, Ordering::Release);
+    return freed > 0;
+}
+
+/// @brief 把所有页缓存中的脏页写回存储设备（用于sync系统调用）
+pub fn page_cache_sync_all() -> Result<(), SystemError> {
+    let mut result = Ok(());
+    for cache in all_page_caches() {
+        if let Some(backend) = cache.backend.upgrade() {
+            // 即使某个文件写回失败，也要继续写回其他文件
+            if let Err(e) = backend.writeback() {
+                result = Err(e);
+            }
+        }
+    }
+    return result;
+}
+
+/// @brief 获取所有页缓存占用的页数
+pub fn page_cache_nr_pages() -> usize {
+    return PAGE_CACHE_PAGES.load(Ordering::Relaxed);
+}
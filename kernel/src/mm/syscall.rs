@@ -1,9 +1,14 @@
 use core::intrinsics::unlikely;
 
-use alloc::sync::Arc;
+use alloc::{string::String, sync::Arc};
 
 use crate::{
-    arch::MMArch,
+    arch::{asm::current::current_pcb, MMArch},
+    filesystem::vfs::{
+        file::{File, FileMode},
+        mount::{inode_mount_flags, MountFlags},
+        FileType, IndexNode,
+    },
     kerror,
     libs::align::{check_aligned, page_align_up},
     mm::MemoryManagementArch,
@@ -64,6 +69,16 @@ bitflags! {
         const MAP_UNINITIALIZED = 0x4000000;
 
     }
+
+    /// msync flags
+    pub struct MsFlags: u64 {
+        /// sync memory asynchronously
+        const MS_ASYNC = 1;
+        /// invalidate the caches
+        const MS_INVALIDATE = 2;
+        /// synchronous memory sync
+        const MS_SYNC = 4;
+    }
 }
 
 impl Syscall {
@@ -109,8 +124,8 @@ impl Syscall {
     /// - `len`：映射的长度
     /// - `prot`：保护标志
     /// - `flags`：映射标志
-    /// - `fd`：文件描述符（暂时不支持）
-    /// - `offset`：文件偏移量 （暂时不支持）
+    /// - `fd`：文件描述符（匿名映射时忽略）
+    /// - `offset`：文件偏移量，需要页对齐（匿名映射时忽略）
     ///
     /// ## 返回值
     ///
@@ -120,8 +135,8 @@ impl Syscall {
         len: usize,
         prot_flags: usize,
         map_flags: usize,
-        _fd: i32,
-        _offset: usize,
+        fd: i32,
+        offset: usize,
     ) -> Result<usize, SystemError> {
         let map_flags = MapFlags::from_bits_truncate(map_flags as u64);
         let prot_flags = ProtFlags::from_bits_truncate(prot_flags as u64);
@@ -135,17 +150,16 @@ impl Syscall {
             );
             return Err(SystemError::EINVAL);
         }
-        // 暂时不支持除匿名页以外的映射
-        if !map_flags.contains(MapFlags::MAP_ANONYMOUS) {
-            kerror!("mmap: not support file mapping");
-            return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
-        }
-
         // 暂时不支持巨页映射
         if map_flags.contains(MapFlags::MAP_HUGETLB) {
             kerror!("mmap: not support huge page mapping");
             return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
         }
+
+        if !map_flags.contains(MapFlags::MAP_ANONYMOUS) {
+            return Self::mmap_file(start_vaddr, len, prot_flags, map_flags, fd, offset);
+        }
+
         let current_address_space = AddressSpace::current()?;
         let start_page = current_address_space.write().map_anonymous(
             start_vaddr,
@@ -157,6 +171,66 @@ impl Syscall {
         return Ok(start_page.virt_address().data());
     }
 
+    /// ## 把文件映射到当前进程的地址空间
+    ///
+    /// 参数的含义与[`Syscall::mmap`]相同
+    fn mmap_file(
+        start_vaddr: VirtAddr,
+        len: usize,
+        prot_flags: ProtFlags,
+        map_flags: MapFlags,
+        fd: i32,
+        offset: usize,
+    ) -> Result<usize, SystemError> {
+        // MAP_SHARED和MAP_PRIVATE必须指定其中一个
+        if map_flags.contains(MapFlags::MAP_SHARED) == map_flags.contains(MapFlags::MAP_PRIVATE) {
+            return Err(SystemError::EINVAL);
+        }
+        if !check_aligned(offset, MMArch::PAGE_SIZE) {
+            return Err(SystemError::EINVAL);
+        }
+
+        let file: &File = current_pcb()
+            .get_file_ref_by_fd(fd)
+            .ok_or(SystemError::EBADF)?;
+        if file.file_type() != FileType::File {
+            return Err(SystemError::ENODEV);
+        }
+
+        // 文件必须是可读的。共享的可写映射，还要求文件是以读写方式打开的
+        let accmode = file.mode().accmode();
+        if accmode == FileMode::O_WRONLY.bits() {
+            return Err(SystemError::EACCES);
+        }
+        if map_flags.contains(MapFlags::MAP_SHARED)
+            && prot_flags.contains(ProtFlags::PROT_WRITE)
+            && accmode != FileMode::O_RDWR.bits()
+        {
+            return Err(SystemError::EACCES);
+        }
+
+        let inode: Arc<dyn IndexNode> = file.inode();
+        // 设置了noexec的挂载点上的文件，不能被映射为可执行
+        if prot_flags.contains(ProtFlags::PROT_EXEC)
+            && inode_mount_flags(&inode).contains(MountFlags::NOEXEC)
+        {
+            return Err(SystemError::EPERM);
+        }
+        let path: Option<String> = file.path().map(String::from);
+        let current_address_space = AddressSpace::current()?;
+        let start_page = current_address_space.write().map_file(
+            start_vaddr,
+            len,
+            prot_flags,
+            map_flags,
+            inode,
+            offset,
+            path,
+            true,
+        )?;
+        return Ok(start_page.virt_address().data());
+    }
+
     /// ## munmap系统调用
     ///
     /// ## 参数
@@ -223,4 +297,47 @@ impl Syscall {
             .map_err(|_| SystemError::EINVAL)?;
         return Ok(0);
     }
+
+    /// ## msync系统调用
+    ///
+    /// 把共享文件映射中被修改过的内容写回文件
+    ///
+    /// ## 参数
+    ///
+    /// - `start_vaddr`：起始地址(需要页对齐)
+    /// - `len`：长度(已经对齐到页)
+    /// - `flags`：`MS_ASYNC`、`MS_SYNC`、`MS_INVALIDATE`的组合。`MS_ASYNC`和`MS_SYNC`不能同时指定
+    ///
+    /// ## 返回值
+    ///
+    /// 成功时返回0，失败时返回错误码
+    pub fn msync(start_vaddr: VirtAddr, len: usize, flags: usize) -> Result<usize, SystemError> {
+        if !start_vaddr.check_aligned(MMArch::PAGE_SIZE) {
+            return Err(SystemError::EINVAL);
+        }
+        let flags = MsFlags::from_bits(flags as u64).ok_or(SystemError::EINVAL)?;
+        if flags.contains(MsFlags::MS_ASYNC | MsFlags::MS_SYNC) {
+            return Err(SystemError::EINVAL);
+        }
+        if unlikely(verify_area(start_vaddr, len).is_err()) {
+            return Err(SystemError::ENOMEM);
+        }
+        if len == 0 {
+            return Ok(0);
+        }
+
+        let current_address_space: Arc<AddressSpace> = AddressSpace::current()?;
+        let inodes = current_address_space.write().msync(
+            VirtPageFrame::new(start_vaddr),
+            PageFrameCount::new(len / MMArch::PAGE_SIZE),
+        )?;
+
+        // MS_ASYNC只需要标记脏页，它们会在之后的sync中被写回。写回文件时不需要持有地址空间的锁
+        if !flags.contains(MsFlags::MS_ASYNC) {
+            for inode in inodes {
+                inode.sync()?;
+            }
+        }
+        return Ok(0);
+    }
 }
@@ -10,6 +10,7 @@ use core::{
 
 use alloc::{
     collections::BTreeMap,
+    string::String,
     sync::{Arc, Weak},
     vec::Vec,
 };
@@ -18,8 +19,9 @@ use hashbrown::HashSet;
 use crate::{
     arch::{asm::current::current_pcb, mm::PageMapper, CurrentIrqArch, MMArch},
     exception::InterruptArch,
+    filesystem::vfs::{mount::MountActiveRef, IndexNode},
     libs::{
-        align::page_align_up,
+        align::{check_aligned, page_align_up},
         rwlock::{RwLock, RwLockWriteGuard},
         spinlock::{SpinLock, SpinLockGuard},
     },
@@ -31,8 +33,9 @@ use super::{
         deallocate_page_frames, PageFrameCount, PhysPageFrame, VirtPageFrame, VirtPageFrameIter,
     },
     page::{Flusher, InactiveFlusher, PageFlags, PageFlushAll},
+    page_cache::CachedPage,
     syscall::{MapFlags, ProtFlags},
-    MemoryManagementArch, PageTableKind, VirtAddr, VirtRegion,
+    MemoryManagementArch, PageTableKind, PhysAddr, VirtAddr, VirtRegion,
 };
 
 /// MMAP_MIN_ADDR的默认值
@@ -115,6 +118,11 @@ pub struct InnerAddressSpace {
     pub end_code: VirtAddr,
     pub start_data: VirtAddr,
     pub end_data: VirtAddr,
+
+    /// 进程执行execve时的参数列表
+    pub args: Vec<String>,
+    /// 进程执行execve时的环境变量列表
+    pub envs: Vec<String>,
 }
 
 impl InnerAddressSpace {
@@ -132,6 +140,8 @@ impl InnerAddressSpace {
             end_code: VirtAddr(0),
             start_data: VirtAddr(0),
             end_data: VirtAddr(0),
+            args: Vec::new(),
+            envs: Vec::new(),
         };
         if create_stack {
             // kdebug!("to create user stack.");
@@ -156,13 +166,22 @@ impl InnerAddressSpace {
             new_guard.user_stack = Some(self.user_stack.as_ref().unwrap().clone_info_only());
         }
         let _current_stack_size = self.user_stack.as_ref().unwrap().stack_size();
+        new_guard.args = self.args.clone();
+        new_guard.envs = self.envs.clone();
 
         let current_mapper = &mut self.user_mapper.utable;
 
         for vma in self.mappings.vmas.iter() {
-            // TODO: 增加对VMA是否为文件映射的判断，如果是的话，就跳过
-
             let vma_guard: SpinLockGuard<'_, VMA> = vma.lock();
+
+            // 文件映射的VMA：只拷贝已经映射到页表中的页
+            if vma_guard.file.is_some() {
+                let new_vma = vma_guard
+                    .clone_file_mapping(current_mapper, &mut new_guard.user_mapper.utable)?;
+                new_guard.mappings.vmas.insert(new_vma);
+                continue;
+            }
+
             let old_flags = vma_guard.flags();
             let tmp_flags: PageFlags<MMArch> = PageFlags::new().set_write(true);
 
@@ -275,6 +294,67 @@ impl InnerAddressSpace {
         return Ok(start_page);
     }
 
+    /// 进行文件映射
+    ///
+    /// 文件的内容不会被立即读入，而是在进程访问到对应的页时，通过缺页异常按需映射。
+    ///
+    /// ## 参数
+    ///
+    /// - `start_vaddr`：映射的起始地址
+    /// - `len`：映射的长度
+    /// - `prot_flags`：保护标志
+    /// - `map_flags`：映射标志。如果包含`MAP_SHARED`，则对映射区域的修改会写回文件，
+    ///   否则对映射区域的修改只对当前进程可见（写时复制）。如果包含`MAP_POPULATE`，则立即映射所有的页
+    /// - `inode`：要映射的文件
+    /// - `offset`：映射的起始位置在文件内的偏移量（需要页对齐）
+    /// - `path`：被映射的文件的路径（如果已知的话），用于在/proc/<pid>/maps中展示
+    /// - `round_to_min`：是否将`start_vaddr`对齐到`mmap_min`，含义与[`InnerAddressSpace::map_anonymous`]相同
+    #[allow(clippy::too_many_arguments)]
+    pub fn map_file(
+        &mut self,
+        start_vaddr: VirtAddr,
+        len: usize,
+        prot_flags: ProtFlags,
+        map_flags: MapFlags,
+        inode: Arc<dyn IndexNode>,
+        offset: usize,
+        path: Option<String>,
+        round_to_min: bool,
+    ) -> Result<VirtPageFrame, SystemError> {
+        if !check_aligned(offset, MMArch::PAGE_SIZE) {
+            return Err(SystemError::EINVAL);
+        }
+
+        let addr = start_vaddr.data() & (!MMArch::PAGE_OFFSET_MASK);
+        let hint = if addr == 0 {
+            None
+        } else if round_to_min && addr < DEFAULT_MMAP_MIN_ADDR {
+            Some(VirtAddr::new(page_align_up(DEFAULT_MMAP_MIN_ADDR)))
+        } else {
+            Some(VirtAddr::new(addr))
+        };
+
+        let len = page_align_up(len);
+        let shared = map_flags.contains(MapFlags::MAP_SHARED);
+        let populate = map_flags.contains(MapFlags::MAP_POPULATE);
+
+        let start_page: VirtPageFrame = self.mmap(
+            hint,
+            PageFrameCount::from_bytes(len).unwrap(),
+            prot_flags,
+            map_flags,
+            move |page, count, flags, mapper, flusher| {
+                let mut file = VmaFile::new(inode, offset, shared);
+                file.set_path(path);
+                Ok(VMA::file_mapped(
+                    page, count, flags, file, populate, mapper, flusher,
+                )?)
+            },
+        )?;
+
+        return Ok(start_page);
+    }
+
     /// 向进程的地址空间映射页面
     ///
     /// # 参数
@@ -463,6 +543,131 @@ impl InnerAddressSpace {
         return Ok(());
     }
 
+    /// 把文件映射区域内被修改过的页标记为脏页
+    ///
+    /// ## 参数
+    ///
+    /// - `start_page`：起始页帧
+    /// - `page_count`：页帧数量
+    ///
+    /// ## 返回值
+    ///
+    /// 返回范围内的共享文件映射所对应的文件，调用者需要在释放地址空间的锁之后，再把它们写回
+    ///
+    /// ## Errors
+    ///
+    /// - `ENOMEM`：范围内存在没有被映射的地址
+    pub fn msync(
+        &mut self,
+        start_page: VirtPageFrame,
+        page_count: PageFrameCount,
+    ) -> Result<Vec<Arc<dyn IndexNode>>, SystemError> {
+        let region = VirtRegion::new(start_page.virt_address(), page_count.bytes());
+        let mut flusher: PageFlushAll<MMArch> = PageFlushAll::new();
+
+        let mut vmas: Vec<Arc<LockedVMA>> = self.mappings.conflicts(region).collect();
+        vmas.sort_by_key(|vma| vma.lock().region.start());
+
+        // 检查范围内是否存在空洞
+        let mut covered = region.start();
+        for vma in vmas.iter() {
+            let vma_region = vma.lock().region;
+            if vma_region.start() > covered {
+                return Err(SystemError::ENOMEM);
+            }
+            covered = cmp::max(covered, vma_region.end());
+        }
+        if covered < region.end() {
+            return Err(SystemError::ENOMEM);
+        }
+
+        let mut inodes: Vec<Arc<dyn IndexNode>> = Vec::new();
+        for vma in vmas {
+            let guard = vma.lock();
+            if let Some(inode) =
+                guard.mark_dirty_pages(&region, &mut self.user_mapper.utable, &mut flusher)
+            {
+                inodes.push(inode);
+            }
+        }
+        return Ok(inodes);
+    }
+
+    /// 处理用户地址空间内的缺页异常
+    ///
+    /// ## 参数
+    ///
+    /// - `address`：触发缺页的虚拟地址
+    /// - `write`：缺页是否由写操作引起
+    /// - `owner`：当前地址空间自身的弱引用，用于记录可写的共享文件页被映射到了哪里
+    ///
+    /// ## Errors
+    ///
+    /// - `EFAULT`：地址不属于任何VMA，或者访问权限不满足
+    /// - `EIO`：访问的页超出了文件的末尾
+    /// - 其他：从文件读取页时发生的错误
+    pub fn handle_page_fault(
+        &mut self,
+        address: VirtAddr,
+        write: bool,
+        owner: &Weak<AddressSpace>,
+    ) -> Result<(), SystemError> {
+        let vma: Arc<LockedVMA> = self.mappings.contains(address).ok_or(SystemError::EFAULT)?;
+        let mut guard = vma.lock();
+
+        // 匿名映射的页总是已经被映射，因此只有文件映射会产生可以处理的缺页
+        if guard.file.is_none() {
+            return Err(SystemError::EFAULT);
+        }
+
+        let page = VirtAddr::new(address.data() & (!MMArch::PAGE_OFFSET_MASK));
+        let mut flusher: PageFlushAll<MMArch> = PageFlushAll::new();
+        return guard.map_file_page(
+            page,
+            write,
+            Some(owner),
+            &mut self.user_mapper.utable,
+            &mut flusher,
+        );
+    }
+
+    /// 如果vaddr处映射的是paddr处的物理页，并且是可写的，那么把它重新设置为只读
+    ///
+    /// 用于在页缓存的脏页写回之后，让之后对该页的写操作能够通过缺页异常重新把页标记为脏页
+    pub fn write_protect_page(&mut self, vaddr: VirtAddr, paddr: PhysAddr) {
+        let mapper = &mut self.user_mapper.utable;
+        if let Some((mapped_paddr, flags)) = mapper.translate(vaddr) {
+            if mapped_paddr == paddr && flags.has_write() {
+                if let Some(r) = unsafe { mapper.remap(vaddr, flags.set_write(false)) } {
+                    let mut flusher: PageFlushAll<MMArch> = PageFlushAll::new();
+                    flusher.consume(r);
+                }
+            }
+        }
+    }
+
+    /// 检查用户地址空间中的[addr, addr + len)是否全部位于可写的VMA之内
+    ///
+    /// 写时复制、尚未标记为脏页的共享文件页虽然在页表中是只读的，但是它们所在的VMA是可写的，
+    /// 内核向这些页写入时触发的缺页可以被正常处理
+    ///
+    /// ## Errors
+    ///
+    /// - `EFAULT`：范围内存在没有被映射、或者不可写的地址
+    pub fn check_user_writable(&self, addr: VirtAddr, len: usize) -> Result<(), SystemError> {
+        let end = addr + len;
+        let mut covered = addr;
+        while covered < end {
+            let vma: Arc<LockedVMA> = self.mappings.contains(covered).ok_or(SystemError::EFAULT)?;
+            let guard = vma.lock();
+            if !guard.flags().has_write() {
+                return Err(SystemError::EFAULT);
+            }
+            covered = guard.region().end();
+        }
+        return Ok(());
+    }
+
     /// 创建新的用户栈
     ///
     /// ## 参数
@@ -603,7 +808,6 @@ impl UserMappings {
     /// 判断当前进程的VMA内，是否有包含指定的虚拟地址的VMA。
     ///
     /// 如果有，返回包含指定虚拟地址的VMA的Arc指针，否则返回None。
-    #[allow(dead_code)]
     pub fn contains(&self, vaddr: VirtAddr) -> Option<Arc<LockedVMA>> {
         for v in self.vmas.iter() {
             let guard = v.lock();
@@ -823,29 +1027,21 @@ impl LockedVMA {
         &self,
         flags: PageFlags<MMArch>,
         mapper: &mut PageMapper,
-        mut flusher: impl Flusher<MMArch>,
+        flusher: impl Flusher<MMArch>,
     ) -> Result<(), SystemError> {
-        let mut guard = self.lock();
-        assert!(guard.mapped);
-        for page in guard.region.pages() {
-            // 暂时要求所有的页帧都已经映射到页表
-            // TODO: 引入Lazy Mapping, 通过缺页中断来映射页帧，这里就不必要求所有的页帧都已经映射到页表了
-            let r = unsafe {
-                mapper
-                    .remap(page.virt_address(), flags)
-                    .expect("Failed to remap, beacuse of some page is not mapped")
-            };
-            flusher.consume(r);
-        }
-        guard.flags = flags;
-        return Ok(());
+        return self.lock().remap(flags, mapper, flusher);
     }
 
     pub fn unmap(&self, mapper: &mut PageMapper, mut flusher: impl Flusher<MMArch>) {
-        // todo: 如果当前vma与文件相关，完善文件相关的逻辑
-
         let mut guard = self.lock();
         assert!(guard.mapped);
+
+        if guard.file.is_some() {
+            guard.unmap_file_pages(mapper, &mut flusher);
+            guard.mapped = false;
+            return;
+        }
+
         for page in guard.region.pages() {
             let (paddr, _, flush) = unsafe { mapper.unmap_phys(page.virt_address(), true) }
                 .expect("Failed to unmap, beacuse of some page is not mapped");
@@ -907,7 +1103,7 @@ impl LockedVMA {
 
         let before: Option<Arc<LockedVMA>> = guard.region.before(&region).map(|virt_region| {
             let mut vma: VMA = unsafe { guard.clone() };
-            vma.region = virt_region;
+            vma.shrink_to(virt_region);
 
             let vma: Arc<LockedVMA> = LockedVMA::new(vma);
             vma
@@ -915,13 +1111,13 @@ impl LockedVMA {
 
         let after: Option<Arc<LockedVMA>> = guard.region.after(&region).map(|virt_region| {
             let mut vma: VMA = unsafe { guard.clone() };
-            vma.region = virt_region;
+            vma.shrink_to(virt_region);
 
             let vma: Arc<LockedVMA> = LockedVMA::new(vma);
             vma
         });
 
-        guard.region = region;
+        guard.shrink_to(region);
 
         // TODO: 重新设置before、after这两个VMA里面的物理页的anon_vma
 
@@ -941,6 +1137,8 @@ pub struct VMA {
     /// VMA所属的用户地址空间
     user_address_space: Option<Weak<AddressSpace>>,
     self_ref: Weak<LockedVMA>,
+    /// 文件映射的信息（匿名映射为None）
+    file: Option<VmaFile>,
 }
 
 impl core::hash::Hash for VMA {
@@ -969,6 +1167,7 @@ impl VMA {
             mapped: self.mapped,
             user_address_space: self.user_address_space.clone(),
             self_ref: self.self_ref.clone(),
+            file: self.file.clone(),
         };
     }
 
@@ -993,8 +1192,19 @@ impl VMA {
         assert!(self.mapped);
         for page in self.region.pages() {
             // kdebug!("remap page {:?}", page.virt_address());
-            // 暂时要求所有的页帧都已经映射到页表
-            // TODO: 引入Lazy Mapping, 通过缺页中断来映射页帧，这里就不必要求所有的页帧都已经映射到页表了
+            // 文件映射的页是按需映射的，跳过还没有映射的页
+            if let Some(file) = &self.file {
+                if mapper.translate(page.virt_address()).is_none() {
+                    continue;
+                }
+                let page_flags = file.page_flags(page.virt_address(), flags);
+                let r = unsafe { mapper.remap(page.virt_address(), page_flags) }
+                    .expect("Failed to remap file page");
+                flusher.consume(r);
+                continue;
+            }
+
+            // 暂时要求匿名映射的所有页帧都已经映射到页表
             let r = unsafe {
                 mapper
                     .remap(page.virt_address(), flags)
@@ -1018,6 +1228,245 @@ impl VMA {
             && (self.flags.has_execute() || !prot_flags.contains(ProtFlags::PROT_EXEC));
     }
 
+    /// 获取当前VMA的文件映射信息
+    pub fn file(&self) -> Option<&VmaFile> {
+        return self.file.as_ref();
+    }
+
+    /// 把当前VMA的范围缩小为region，并相应地调整文件映射的信息
+    ///
+    /// region必须在当前VMA的范围内
+    fn shrink_to(&mut self, region: VirtRegion) {
+        if let Some(file) = self.file.as_mut() {
+            file.offset += region.start() - self.region.start();
+            file.pages.retain(|vaddr, _| region.contains(*vaddr));
+        }
+        self.region = region;
+    }
+
+    /// 在文件映射的VMA中，映射一个页（用于处理缺页异常）
+    ///
+    /// - 页不存在：从文件的页缓存中获取页并映射。私有映射的写操作会直接得到页的副本
+    /// - 页存在：说明是对只读页的写操作。共享映射会把页标记为脏页，私有映射则进行写时复制
+    ///
+    /// ## 参数
+    ///
+    /// - `vaddr`：要映射的页的虚拟地址（页对齐）
+    /// - `write`：缺页是否由写操作引起
+    /// - `owner`：VMA所在的地址空间。共享映射的页被映射为可写时，需要记录在页中
+    /// - `mapper`：页表映射器
+    /// - `flusher`：页表项刷新器
+    ///
+    /// ## Errors
+    ///
+    /// - `EFAULT`：访问权限不满足
+    /// - `EIO`：页超出了文件的末尾
+    /// - `ENOMEM`：内存不足
+    pub fn map_file_page(
+        &mut self,
+        vaddr: VirtAddr,
+        write: bool,
+        owner: Option<&Weak<AddressSpace>>,
+        mapper: &mut PageMapper,
+        mut flusher: impl Flusher<MMArch>,
+    ) -> Result<(), SystemError> {
+        if write && !self.flags.has_write() {
+            return Err(SystemError::EFAULT);
+        }
+        // 没有记录映射位置的话，无法在写回之后重新设置为只读，因此不能映射为可写
+        if write && owner.is_none() {
+            return Err(SystemError::EFAULT);
+        }
+        let vma_start = self.region.start();
+        let flags = self.flags;
+        let file: &mut VmaFile = self.file.as_mut().expect("Not a file mapping");
+
+        if mapper.translate(vaddr).is_none() {
+            let index = file.page_index(vma_start, vaddr);
+            // 访问超出文件末尾的页，与Linux一样产生SIGBUS
+            let file_size = file.inode.metadata()?.size as usize;
+            if index * MMArch::PAGE_SIZE >= file_size {
+                return Err(SystemError::EIO);
+            }
+
+            let page: Arc<CachedPage> = file.inode.get_page(index)?;
+            if write && !file.shared {
+                // 私有映射的写操作，直接映射页的副本
+                return Self::map_copied_page(vaddr, &page, flags, mapper, flusher);
+            }
+
+            let paddr = page.phys_address();
+            let page_flags = if write {
+                page.set_dirty(true);
+                page.add_writable_mapping(owner.unwrap().clone(), vaddr);
+                flags
+            } else {
+                // 读操作以只读的方式映射，以便在写入时进行脏页标记或者写时复制
+                flags.set_write(false)
+            };
+            file.pages.insert(vaddr, page);
+
+            match unsafe { mapper.map_phys(vaddr, paddr, page_flags) } {
+                Some(r) => flusher.consume(r),
+                None => {
+                    file.pages.remove(&vaddr);
+                    return Err(SystemError::ENOMEM);
+                }
+            }
+            return Ok(());
+        }
+
+        if !write {
+            // 页已经存在，并且不是写操作，说明是权限错误
+            return Err(SystemError::EFAULT);
+        }
+
+        if file.shared {
+            let page = file.pages.get(&vaddr).ok_or(SystemError::EFAULT)?;
+            page.set_dirty(true);
+            page.add_writable_mapping(owner.unwrap().clone(), vaddr);
+            let r = unsafe { mapper.remap(vaddr, flags) }.ok_or(SystemError::EFAULT)?;
+            flusher.consume(r);
+            return Ok(());
+        }
+
+        // 写时复制：把页缓存中的页替换为进程私有的副本
+        let page: Arc<CachedPage> = file.pages.remove(&vaddr).ok_or(SystemError::EFAULT)?;
+        let (_, _, r) = unsafe { mapper.unmap_phys(vaddr, false) }.ok_or(SystemError::EFAULT)?;
+        flusher.consume(r);
+        return Self::map_copied_page(vaddr, &page, flags, mapper, flusher);
+    }
+
+    /// 分配一个新的物理页，拷贝page的内容，并映射到vaddr
+    fn map_copied_page(
+        vaddr: VirtAddr,
+        page: &CachedPage,
+        flags: PageFlags<MMArch>,
+        mapper: &mut PageMapper,
+        mut flusher: impl Flusher<MMArch>,
+    ) -> Result<(), SystemError> {
+        let r = unsafe { mapper.map(vaddr, flags) }.ok_or(SystemError::ENOMEM)?;
+        flusher.consume(r);
+
+        let paddr = mapper.translate(vaddr).unwrap().0;
+        unsafe {
+            let dst = MMArch::phys_2_virt(paddr).unwrap().data() as *mut u8;
+            dst.copy_from_nonoverlapping(page.as_slice().as_ptr(), MMArch::PAGE_SIZE);
+        }
+        return Ok(());
+    }
+
+    /// 取消文件映射的VMA中所有已映射的页
+    ///
+    /// 页缓存中的页只是被释放引用，私有的副本则会被释放。
+    /// 被映射为可写的共享页会被标记为脏页，这样它们会在下一次sync时被写回
+    fn unmap_file_pages(&mut self, mapper: &mut PageMapper, mut flusher: impl Flusher<MMArch>) {
+        let file: &mut VmaFile = self.file.as_mut().expect("Not a file mapping");
+        for page in self.region.pages() {
+            let vaddr = page.virt_address();
+            let (paddr, flags, flush) = match unsafe { mapper.unmap_phys(vaddr, true) } {
+                Some(x) => x,
+                None => continue,
+            };
+            flusher.consume(flush);
+
+            match file.pages.remove(&vaddr) {
+                Some(cached) => {
+                    if file.shared && flags.has_write() {
+                        cached.set_dirty(true);
+                    }
+                }
+                None => unsafe {
+                    deallocate_page_frames(PhysPageFrame::new(paddr), PageFrameCount::new(1));
+                },
+            }
+        }
+    }
+
+    /// 把共享文件映射中，位于region内的、可写的页标记为脏页，并重新设置为只读
+    ///
+    /// 设置为只读之后，进程再次写入这些页时，会通过缺页异常重新标记为脏页
+    ///
+    /// ## 返回值
+    ///
+    /// 如果当前VMA是共享文件映射，返回被映射的文件，否则返回None
+    pub fn mark_dirty_pages(
+        &self,
+        region: &VirtRegion,
+        mapper: &mut PageMapper,
+        mut flusher: impl Flusher<MMArch>,
+    ) -> Option<Arc<dyn IndexNode>> {
+        let file: &VmaFile = self.file.as_ref().filter(|f| f.shared)?;
+        for (vaddr, page) in file.pages.iter() {
+            if !region.contains(*vaddr) {
+                continue;
+            }
+            if let Some((_, flags)) = mapper.translate(*vaddr) {
+                if flags.has_write() {
+                    page.set_dirty(true);
+                    if let Some(r) = unsafe { mapper.remap(*vaddr, flags.set_write(false)) } {
+                        flusher.consume(r);
+                    }
+                }
+            }
+        }
+        return Some(file.inode.clone());
+    }
+
+    /// 在新的地址空间中，创建当前文件映射VMA的副本（用于fork）
+    ///
+    /// 页缓存中的页由两个地址空间共同映射，私有的副本则会被拷贝一份
+    ///
+    /// ## 参数
+    ///
+    /// - `src_mapper`：当前VMA所在地址空间的页表映射器
+    /// - `dst_mapper`：新的地址空间的页表映射器
+    pub fn clone_file_mapping(
+        &self,
+        src_mapper: &PageMapper,
+        dst_mapper: &mut PageMapper,
+    ) -> Result<Arc<LockedVMA>, SystemError> {
+        let file: &VmaFile = self.file.as_ref().expect("Not a file mapping");
+        let mut new_file = VmaFile::new(file.inode.clone(), file.offset, file.shared);
+        new_file.set_path(file.path.clone());
+        // 新的地址空间还没有被激活，不需要刷新TLB
+        let mut flusher = ();
+
+        for page in self.pages() {
+            let vaddr = page.virt_address();
+            let (paddr, flags) = match src_mapper.translate(vaddr) {
+                Some(x) => x,
+                None => continue,
+            };
+
+            if let Some(cached) = file.pages.get(&vaddr) {
+                // 页缓存中的页在新的地址空间中以只读方式映射，写入时再通过缺页异常处理
+                let r = unsafe { dst_mapper.map_phys(vaddr, paddr, flags.set_write(false)) }
+                    .ok_or(SystemError::ENOMEM)?;
+                flusher.consume(r);
+                new_file.pages.insert(vaddr, cached.clone());
+            } else {
+                let r = unsafe { dst_mapper.map(vaddr, flags) }.ok_or(SystemError::ENOMEM)?;
+                flusher.consume(r);
+                let new_paddr = dst_mapper.translate(vaddr).unwrap().0;
+                unsafe {
+                    let src = MMArch::phys_2_virt(paddr).unwrap().data() as *const u8;
+                    let dst = MMArch::phys_2_virt(new_paddr).unwrap().data() as *mut u8;
+                    dst.copy_from_nonoverlapping(src, MMArch::PAGE_SIZE);
+                }
+            }
+        }
+
+        return Ok(LockedVMA::new(VMA {
+            region: self.region,
+            flags: self.flags,
+            mapped: true,
+            user_address_space: None,
+            self_ref: Weak::default(),
+            file: Some(new_file),
+        }));
+    }
+
     /// 把物理地址映射到虚拟地址
     ///
     /// @param phys 要映射的物理地址
@@ -1065,6 +1514,7 @@ impl VMA {
             mapped: true,
             user_address_space: None,
             self_ref: Weak::default(),
+            file: None,
         });
         return Ok(r);
     }
@@ -1113,6 +1563,7 @@ impl VMA {
             mapped: true,
             user_address_space: None,
             self_ref: Weak::default(),
+            file: None,
         });
         drop(flusher);
         // kdebug!("VMA::zeroed: flusher dropped");
@@ -1133,6 +1584,128 @@ impl VMA {
     }
 }
 
+impl VMA {
+    /// 创建一个文件映射的VMA
+    ///
+    /// @param destination 要映射到的虚拟地址
+    /// @param page_count 要映射的页帧数量
+    /// @param flags 页面标志位
+    /// @param file 文件映射的信息
+    /// @param populate 是否立即映射所有的页。如果为false，则在缺页时再按需映射
+    /// @param mapper 页表映射器
+    /// @param flusher 页表项刷新器
+    ///
+    /// @return 返回映射后的虚拟内存区域
+    pub fn file_mapped(
+        destination: VirtPageFrame,
+        page_count: PageFrameCount,
+        flags: PageFlags<MMArch>,
+        file: VmaFile,
+        populate: bool,
+        mapper: &mut PageMapper,
+        mut flusher: impl Flusher<MMArch>,
+    ) -> Result<Arc<LockedVMA>, SystemError> {
+        let mut vma = VMA {
+            region: VirtRegion::new(destination.virt_address(), page_count.bytes()),
+            flags,
+            mapped: true,
+            user_address_space: None,
+            self_ref: Weak::default(),
+            file: Some(file),
+        };
+
+        if populate {
+            for page in vma.pages() {
+                match vma.map_file_page(page.virt_address(), false, None, mapper, &mut flusher) {
+                    Ok(_) => {}
+                    // 文件末尾之后的页不需要预先映射，访问时再产生SIGBUS
+                    Err(SystemError::EIO) => break,
+                    Err(e) => {
+                        vma.unmap_file_pages(mapper, &mut flusher);
+                        vma.mapped = false;
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        return Ok(LockedVMA::new(vma));
+    }
+}
+
+/// @brief VMA的文件映射信息
+#[derive(Debug, Clone)]
+pub struct VmaFile {
+    /// 被映射的文件
+    inode: Arc<dyn IndexNode>,
+    /// VMA的起始地址在文件内对应的偏移量（页对齐）
+    offset: usize,
+    /// 是否为共享映射。对共享映射的修改会写回文件，私有映射则在写入时复制出进程私有的页
+    shared: bool,
+    /// 被映射的文件的路径（如果已知的话）
+    path: Option<String>,
+    /// 已经映射到页表中的、来自页缓存的页（虚拟地址 -> 页）
+    ///
+    /// 私有映射的页被写时复制之后，会从这里移除
+    pages: BTreeMap<VirtAddr, Arc<CachedPage>>,
+    /// 对文件所在的挂载文件系统的活跃引用，映射存在期间，文件系统不能被卸载
+    _mount_ref: Option<MountActiveRef>,
+}
+
+impl VmaFile {
+    pub fn new(inode: Arc<dyn IndexNode>, offset: usize, shared: bool) -> Self {
+        let mount_ref: Option<MountActiveRef> = MountActiveRef::new(&inode);
+        return Self {
+            _mount_ref: mount_ref,
+            inode,
+            offset,
+            shared,
+            path: None,
+            pages: BTreeMap::new(),
+        };
+    }
+
+    #[inline]
+    pub fn inode(&self) -> &Arc<dyn IndexNode> {
+        return &self.inode;
+    }
+
+    #[inline]
+    pub fn offset(&self) -> usize {
+        return self.offset;
+    }
+
+    #[inline]
+    pub fn shared(&self) -> bool {
+        return self.shared;
+    }
+
+    #[inline]
+    pub fn path(&self) -> Option<&str> {
+        return self.path.as_deref();
+    }
+
+    #[inline]
+    pub fn set_path(&mut self, path: Option<String>) {
+        self.path = path;
+    }
+
+    /// 获取虚拟地址对应的页在文件内的序号
+    fn page_index(&self, vma_start: VirtAddr, vaddr: VirtAddr) -> usize {
+        return (self.offset + (vaddr - vma_start)) / MMArch::PAGE_SIZE;
+    }
+
+    /// 获取重新映射vaddr处的页时应当使用的页表项标志
+    ///
+    /// 来自页缓存的页总是以只读方式重新映射：私有映射在写入时复制，
+    /// 共享映射则在写入时通过缺页异常标记脏页，并记录可写的映射
+    fn page_flags(&self, vaddr: VirtAddr, flags: PageFlags<MMArch>) -> PageFlags<MMArch> {
+        if self.pages.contains_key(&vaddr) {
+            return flags.set_write(false);
+        }
+        return flags;
+    }
+}
+
 impl Drop for VMA {
     fn drop(&mut self) {
         // 当VMA被释放时，需要确保它已经被从页表中解除映射
@@ -17,3 +17,24 @@ impl LinkLayerEndpoint {
         Self { interface }
     }
 }
+
+/// @brief netlink端点
+#[derive(Debug, Clone)]
+pub struct NetlinkEndpoint {
+    /// 端口号（内核为0）
+    pub pid: u32,
+    /// 订阅的组播组（位图）
+    pub groups: u32,
+}
+
+impl NetlinkEndpoint {
+    /// @brief 创建一个netlink端点
+    ///
+    /// @param pid 端口号
+    /// @param groups 组播组的位图
+    ///
+    /// @return 返回创建的netlink端点
+    pub fn new(pid: u32, groups: u32) -> Self {
+        Self { pid, groups }
+    }
+}
@@ -59,7 +59,8 @@ pub enum Endpoint {
     LinkLayer(endpoints::LinkLayerEndpoint),
     /// 网络层端点
     Ip(Option<IpEndpoint>),
-    // todo: 增加NetLink机制后，增加NetLink端点
+    /// netlink端点
+    Netlink(endpoints::NetlinkEndpoint),
 }
 
 pub trait Socket: Sync + Send + Debug {
@@ -3,6 +3,7 @@ use smoltcp::{socket::dhcpv4, wire};
 
 use crate::{
     driver::net::NetDriver,
+    filesystem::vfs::PollStatus,
     kdebug, kinfo, kwarn,
     libs::rwlock::RwLockReadGuard,
     net::NET_DRIVERS,
@@ -10,7 +11,7 @@ use crate::{
     time::timer::{next_n_ms_timer_jiffies, Timer, TimerFunction},
 };
 
-use super::socket::{SOCKET_SET, SOCKET_WAITQUEUE};
+use super::socket::{SOCKET_POLL_WAITQUEUE, SOCKET_SET, SOCKET_WAITQUEUE};
 
 /// The network poll function, which will be called by timer.
 ///
@@ -127,6 +128,8 @@ pub fn poll_ifaces() {
         iface.poll(&mut sockets).ok();
     }
     SOCKET_WAITQUEUE.wakeup_all((-1i64) as u64);
+    drop(sockets);
+    SOCKET_POLL_WAITQUEUE.wakeup(PollStatus::READ | PollStatus::WRITE);
 }
 
 /// 对ifaces进行轮询，最多对SOCKET_SET尝试times次加锁。
@@ -155,6 +158,8 @@ pub fn poll_ifaces_try_lock(times: u16) -> Result<(), SystemError> {
             iface.poll(&mut sockets).ok();
         }
         SOCKET_WAITQUEUE.wakeup_all((-1i64) as u64);
+        drop(sockets);
+        SOCKET_POLL_WAITQUEUE.wakeup(PollStatus::READ | PollStatus::WRITE);
         return Ok(());
     }
 
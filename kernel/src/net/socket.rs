@@ -1,5 +1,10 @@
 #![allow(dead_code)]
-use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use alloc::{
+    boxed::Box,
+    collections::VecDeque,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
 use hashbrown::HashMap;
 use smoltcp::{
     iface::{SocketHandle, SocketSet},
@@ -8,9 +13,14 @@ use smoltcp::{
 };
 
 use crate::{
-    arch::rand::rand,
+    arch::{asm::current::current_pcb, rand::rand},
     driver::net::NetDriver,
-    filesystem::vfs::{FileType, IndexNode, Metadata, PollStatus},
+    filesystem::vfs::{
+        poll::{PollWaitQueue, PollWaiter},
+        pseudo::sock_fs,
+        FileType, IndexNode, Metadata, PollStatus,
+    },
+    ipc::signal::has_sig_pending,
     kerror, kwarn,
     libs::{
         spinlock::{SpinLock, SpinLockGuard},
@@ -19,13 +29,18 @@ use crate::{
     syscall::SystemError,
 };
 
-use super::{net_core::poll_ifaces, Endpoint, Protocol, Socket, NET_DRIVERS};
+use super::{
+    endpoints::NetlinkEndpoint, net_core::poll_ifaces, Endpoint, Protocol, Socket, NET_DRIVERS,
+};
 
 lazy_static! {
     /// 所有socket的集合
     /// TODO: 优化这里，自己实现SocketSet！！！现在这样的话，不管全局有多少个网卡，每个时间点都只会有1个进程能够访问socket
     pub static ref SOCKET_SET: SpinLock<SocketSet<'static >> = SpinLock::new(SocketSet::new(vec![]));
     pub static ref SOCKET_WAITQUEUE: WaitQueue = WaitQueue::INIT;
+    /// 通过poll、select、epoll等待socket的进程。
+    /// 由于每次轮询网卡之后，所有socket的状态都可能发生变化，因此所有socket共用这个等待队列
+    pub static ref SOCKET_POLL_WAITQUEUE: PollWaitQueue = PollWaitQueue::INIT;
     /// 端口管理器
     pub static ref PORT_MANAGER: PortManager = PortManager::new();
 }
@@ -75,6 +90,7 @@ impl PortManager {
                 SocketType::UdpSocket => self.udp_port_table.lock(),
                 SocketType::TcpSocket => self.tcp_port_table.lock(),
                 SocketType::RawSocket => panic!("RawSocket cann't get a port"),
+                SocketType::NetlinkSocket => panic!("NetlinkSocket cann't get a port"),
             };
             if let None = listen_table_guard.get(&port) {
                 drop(listen_table_guard);
@@ -99,6 +115,7 @@ impl PortManager {
                 SocketType::UdpSocket => self.udp_port_table.lock(),
                 SocketType::TcpSocket => self.tcp_port_table.lock(),
                 SocketType::RawSocket => panic!("RawSocket cann't bind a port"),
+                SocketType::NetlinkSocket => panic!("NetlinkSocket cann't bind a port"),
             };
             match listen_table_guard.get(&port) {
                 Some(_) => return Err(SystemError::EADDRINUSE),
@@ -114,7 +131,7 @@ impl PortManager {
         let mut listen_table_guard = match socket_type {
             SocketType::UdpSocket => self.udp_port_table.lock(),
             SocketType::TcpSocket => self.tcp_port_table.lock(),
-            SocketType::RawSocket => return Ok(()),
+            SocketType::RawSocket | SocketType::NetlinkSocket => return Ok(()),
         };
         listen_table_guard.remove(&port);
         drop(listen_table_guard);
@@ -162,6 +179,8 @@ pub enum SocketType {
     TcpSocket,
     /// 用于Udp通信的 Socket
     UdpSocket,
+    /// 用于内核与用户空间通信的 Netlink Socket
+    NetlinkSocket,
 }
 
 bitflags! {
@@ -572,7 +591,7 @@ impl Socket for UdpSocket {
         let sockets = SOCKET_SET.lock();
         let socket = sockets.get::<udp::Socket>(self.handle.0);
 
-        return (socket.can_send(), socket.can_recv(), false);
+        return (socket.can_recv(), socket.can_send(), false);
     }
 
     /// @brief
@@ -795,7 +814,16 @@ impl Socket for TcpSocket {
         } else if !socket.is_open() {
             error = true;
         } else {
-            if socket.may_recv() {
+            // 有数据可读，或者对端已经关闭了连接（此时read会立即返回0）时，socket是可读的
+            if socket.can_recv()
+                || matches!(
+                    socket.state(),
+                    tcp::State::CloseWait
+                        | tcp::State::LastAck
+                        | tcp::State::Closing
+                        | tcp::State::TimeWait
+                )
+            {
                 input = true;
             }
             if socket.can_send() {
@@ -1000,6 +1028,170 @@ impl Socket for TcpSocket {
     }
 }
 
+/// 内核对象（设备）的热插拔事件的netlink协议
+pub const NETLINK_KOBJECT_UEVENT: usize = 15;
+
+lazy_static! {
+    /// 所有netlink socket的接收队列（用于向订阅了组播组的socket广播消息）
+    static ref NETLINK_SOCKETS: SpinLock<Vec<Weak<SpinLock<NetlinkQueue>>>> =
+        SpinLock::new(Vec::new());
+}
+
+/// @brief netlink socket的接收队列
+#[derive(Debug)]
+struct NetlinkQueue {
+    /// netlink协议号
+    protocol: usize,
+    /// 绑定的端口号
+    pid: u32,
+    /// 订阅的组播组（位图）
+    groups: u32,
+    /// 尚未被读取的消息
+    messages: VecDeque<Vec<u8>>,
+}
+
+/// @brief 表示netlink socket。目前只支持接收内核广播的uevent（NETLINK_KOBJECT_UEVENT）
+///
+/// ref: https://man7.org/linux/man-pages/man7/netlink.7.html
+#[derive(Debug, Clone)]
+pub struct NetlinkSocket {
+    /// 接收队列。通过dup等方式复制的socket，共享同一个接收队列
+    queue: Arc<SpinLock<NetlinkQueue>>,
+    /// socket的metadata
+    metadata: SocketMetadata,
+}
+
+impl NetlinkSocket {
+    /// 接收队列中最多缓存的消息数量，超过之后新的消息会被丢弃
+    pub const MAX_QUEUED_MESSAGES: usize = 256;
+    /// 默认的接收缓冲区的大小
+    pub const DEFAULT_RX_BUF_SIZE: usize = 64 * 1024;
+
+    /// @brief 创建一个netlink socket
+    ///
+    /// @param protocol netlink协议号
+    /// @param options socket的选项
+    ///
+    /// @return 协议不被支持时，返回EPROTONOSUPPORT
+    pub fn new(protocol: usize, options: SocketOptions) -> Result<Self, SystemError> {
+        if protocol != NETLINK_KOBJECT_UEVENT {
+            return Err(SystemError::EPROTONOSUPPORT);
+        }
+        let queue = Arc::new(SpinLock::new(NetlinkQueue {
+            protocol,
+            pid: 0,
+            groups: 0,
+            messages: VecDeque::new(),
+        }));
+        NETLINK_SOCKETS.lock().push(Arc::downgrade(&queue));
+
+        let metadata = SocketMetadata::new(
+            SocketType::NetlinkSocket,
+            0,
+            Self::DEFAULT_RX_BUF_SIZE,
+            0,
+            options,
+        );
+        return Ok(Self { queue, metadata });
+    }
+}
+
+impl Socket for NetlinkSocket {
+    fn read(&self, buf: &mut [u8]) -> (Result<usize, SystemError>, Endpoint) {
+        loop {
+            let mut queue = self.queue.lock();
+            if let Some(msg) = queue.messages.pop_front() {
+                // 与数据报一样，缓冲区放不下的部分会被丢弃
+                let len = msg.len().min(buf.len());
+                buf[0..len].copy_from_slice(&msg[0..len]);
+                // 消息都来自于内核（端口号为0）
+                return (
+                    Ok(len),
+                    Endpoint::Netlink(NetlinkEndpoint::new(0, queue.groups)),
+                );
+            }
+            let endpoint = Endpoint::Netlink(NetlinkEndpoint::new(0, queue.groups));
+            if !self.metadata.options.contains(SocketOptions::BLOCK) {
+                // 如果是非阻塞的socket，就返回错误
+                return (Err(SystemError::EAGAIN_OR_EWOULDBLOCK), endpoint);
+            }
+            // 没有消息可读，并且收到了信号，那么不再等待
+            if has_sig_pending(current_pcb()) {
+                return (Err(SystemError::EINTR), endpoint);
+            }
+            SOCKET_WAITQUEUE.sleep_unlock_spinlock(queue);
+        }
+    }
+
+    fn write(&self, _buf: &[u8], _to: Option<Endpoint>) -> Result<usize, SystemError> {
+        // TODO: 支持用户空间向内核或其他进程发送netlink消息
+        return Err(SystemError::EOPNOTSUPP_OR_ENOTSUP);
+    }
+
+    fn connect(&mut self, _endpoint: Endpoint) -> Result<(), SystemError> {
+        return Ok(());
+    }
+
+    fn bind(&mut self, endpoint: Endpoint) -> Result<(), SystemError> {
+        if let Endpoint::Netlink(endpoint) = endpoint {
+            let mut queue = self.queue.lock();
+            queue.pid = endpoint.pid;
+            queue.groups = endpoint.groups;
+            return Ok(());
+        }
+        return Err(SystemError::EINVAL);
+    }
+
+    fn endpoint(&self) -> Option<Endpoint> {
+        let queue = self.queue.lock();
+        return Some(Endpoint::Netlink(NetlinkEndpoint::new(
+            queue.pid,
+            queue.groups,
+        )));
+    }
+
+    fn poll(&self) -> (bool, bool, bool) {
+        return (!self.queue.lock().messages.is_empty(), false, false);
+    }
+
+    fn metadata(&self) -> Result<SocketMetadata, SystemError> {
+        Ok(self.metadata.clone())
+    }
+
+    fn box_clone(&self) -> alloc::boxed::Box<dyn Socket> {
+        return Box::new(self.clone());
+    }
+}
+
+/// @brief 向订阅了指定组播组的netlink socket广播一条消息
+///
+/// @param protocol netlink协议号
+/// @param group 组播组的编号（从1开始）
+/// @param msg 消息内容
+pub fn netlink_broadcast(protocol: usize, group: u32, msg: &[u8]) {
+    if group == 0 || group > 32 {
+        return;
+    }
+    let mask: u32 = 1 << (group - 1);
+
+    let mut sockets = NETLINK_SOCKETS.lock();
+    // 顺便清理已经被关闭的socket
+    sockets.retain(|q| q.strong_count() > 0);
+    for queue in sockets.iter().filter_map(|q| q.upgrade()) {
+        let mut queue = queue.lock();
+        if queue.protocol != protocol || queue.groups & mask == 0 {
+            continue;
+        }
+        if queue.messages.len() < NetlinkSocket::MAX_QUEUED_MESSAGES {
+            queue.messages.push_back(msg.to_vec());
+        }
+    }
+    drop(sockets);
+
+    SOCKET_WAITQUEUE.wakeup_all((-1i64) as u64);
+    SOCKET_POLL_WAITQUEUE.wakeup(PollStatus::READ);
+}
+
 /// @brief 地址族的枚举
 ///
 /// 参考：https://opengrok.ringotek.cn/xref/linux-5.19.10/include/linux/socket.h#180
@@ -1201,8 +1393,13 @@ impl IndexNode for SocketInode {
         return Ok(result);
     }
 
+    fn add_poll_waiter(&self, waiter: Weak<dyn PollWaiter>) -> Result<(), SystemError> {
+        SOCKET_POLL_WAITQUEUE.register(waiter);
+        return Ok(());
+    }
+
     fn fs(&self) -> alloc::sync::Arc<dyn crate::filesystem::vfs::FileSystem> {
-        todo!()
+        return sock_fs();
     }
 
     fn as_any_ref(&self) -> &dyn core::any::Any {
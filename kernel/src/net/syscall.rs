@@ -17,7 +17,10 @@ use crate::{
 };
 
 use super::{
-    socket::{PosixSocketType, RawSocket, SocketInode, SocketOptions, TcpSocket, UdpSocket},
+    endpoints::NetlinkEndpoint,
+    socket::{
+        NetlinkSocket, PosixSocketType, RawSocket, SocketInode, SocketOptions, TcpSocket, UdpSocket,
+    },
     Endpoint, Protocol, ShutdownType, Socket,
 };
 
@@ -33,6 +36,8 @@ impl Syscall {
         protocol: usize,
     ) -> Result<usize, SystemError> {
         let address_family = AddressFamily::try_from(address_family as u16)?;
+        // SOCK_NONBLOCK与O_NONBLOCK的值相同
+        let nonblock: bool = socket_type & FileMode::O_NONBLOCK.bits() as usize != 0;
         let socket_type = PosixSocketType::try_from((socket_type & 0xf) as u8)?;
         // kdebug!("do_socket: address_family: {address_family:?}, socket_type: {socket_type:?}, protocol: {protocol}");
         // 根据地址族和socket类型创建socket
@@ -49,6 +54,20 @@ impl Syscall {
                     return Err(SystemError::EINVAL);
                 }
             },
+            AddressFamily::Netlink => match socket_type {
+                PosixSocketType::Datagram | PosixSocketType::Raw => {
+                    // 除非指定了SOCK_NONBLOCK，否则读取时会一直等待，直到有消息到达
+                    let options = if nonblock {
+                        SocketOptions::empty()
+                    } else {
+                        SocketOptions::BLOCK
+                    };
+                    Box::new(NetlinkSocket::new(protocol, options)?)
+                }
+                _ => {
+                    return Err(SystemError::ESOCKTNOSUPPORT);
+                }
+            },
             _ => {
                 // kdebug!("do_socket: EAFNOSUPPORT");
                 return Err(SystemError::EAFNOSUPPORT);
@@ -506,8 +525,11 @@ impl SockAddr {
                     return Err(SystemError::EINVAL);
                 }
                 AddressFamily::Netlink => {
-                    // TODO: support netlink socket
-                    return Err(SystemError::EINVAL);
+                    let addr_nl: SockAddrNl = addr.addr_nl;
+                    return Ok(Endpoint::Netlink(NetlinkEndpoint::new(
+                        addr_nl.nl_pid,
+                        addr_nl.nl_groups,
+                    )));
                 }
                 AddressFamily::Unix => {
                     return Err(SystemError::EINVAL);
@@ -613,10 +635,18 @@ impl From<Endpoint> for SockAddr {
                 };
 
                 return SockAddr { addr_ll };
-            } // _ => {
-              //     // todo: support other endpoint, like Netlink...
-              //     unimplemented!("not support {value:?}");
-              // }
+            }
+
+            Endpoint::Netlink(netlink_endpoint) => {
+                let addr_nl = SockAddrNl {
+                    nl_family: AddressFamily::Netlink as u16,
+                    nl_pad: 0,
+                    nl_pid: netlink_endpoint.pid,
+                    nl_groups: netlink_endpoint.groups,
+                };
+
+                return SockAddr { addr_nl };
+            }
         }
     }
 }
@@ -0,0 +1,135 @@
+//! 进程的身份凭证
+//!
+//! 每个进程都持有一份`Cred`，记录了进程的真实、有效、保存的用户ID和组ID，附加组，以及文件创建掩码。
+//! 凭证一旦被设置到pcb中就不会再被修改：修改凭证时，需要先复制一份，修改之后再整体替换掉pcb中的凭证。
+//! 因此，fork出来的子进程可以直接与父进程共享同一份凭证。
+
+use alloc::{sync::Arc, vec::Vec};
+
+use crate::syscall::SystemError;
+
+/// 超级用户的uid
+pub const ROOT_UID: u32 = 0;
+/// 超级用户组的gid
+pub const ROOT_GID: u32 = 0;
+
+/// 附加组的最大数量
+pub const NGROUPS_MAX: usize = 65536;
+
+/// 进程默认的文件创建掩码
+pub const DEFAULT_UMASK: u32 = 0o022;
+
+/// @brief 进程的身份凭证
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cred {
+    /// 真实用户ID
+    pub uid: u32,
+    /// 有效用户ID
+    pub euid: u32,
+    /// 保存的用户ID
+    pub suid: u32,
+    /// 真实组ID
+    pub gid: u32,
+    /// 有效组ID
+    pub egid: u32,
+    /// 保存的组ID
+    pub sgid: u32,
+    /// 附加组
+    pub groups: Vec<u32>,
+    /// 文件创建掩码
+    pub umask: u32,
+}
+
+impl Cred {
+    /// @brief 创建超级用户的凭证（初始进程、内核线程使用）
+    pub fn root() -> Self {
+        return Self {
+            uid: ROOT_UID,
+            euid: ROOT_UID,
+            suid: ROOT_UID,
+            gid: ROOT_GID,
+            egid: ROOT_GID,
+            sgid: ROOT_GID,
+            groups: Vec::new(),
+            umask: DEFAULT_UMASK,
+        };
+    }
+
+    /// @brief 判断当前凭证是否具有超级用户的权限（有效用户ID为0）
+    #[inline]
+    pub fn is_root(&self) -> bool {
+        return self.euid == ROOT_UID;
+    }
+
+    /// @brief 判断当前凭证是否属于指定的组（有效组ID或者附加组）
+    pub fn in_group(&self, gid: u32) -> bool {
+        return self.egid == gid || self.groups.contains(&gid);
+    }
+
+    /// @brief 获取一份以真实用户ID、真实组ID作为有效ID的凭证（用于access系统调用）
+    pub fn real(&self) -> Self {
+        let mut cred = self.clone();
+        cred.euid = self.uid;
+        cred.egid = self.gid;
+        return cred;
+    }
+
+    /// @brief 按照setuid的语义，生成新的凭证
+    ///
+    /// 超级用户会同时设置真实、有效、保存的用户ID；普通用户只能把有效用户ID设置为自己的真实用户ID或者保存的用户ID
+    pub fn with_uid(&self, uid: u32) -> Result<Arc<Self>, SystemError> {
+        let mut cred = self.clone();
+        if self.is_root() {
+            cred.uid = uid;
+            cred.euid = uid;
+            cred.suid = uid;
+        } else if uid == self.uid || uid == self.suid {
+            cred.euid = uid;
+        } else {
+            return Err(SystemError::EPERM);
+        }
+        return Ok(Arc::new(cred));
+    }
+
+    /// @brief 按照setgid的语义，生成新的凭证
+    ///
+    /// 超级用户会同时设置真实、有效、保存的组ID；普通用户只能把有效组ID设置为自己的真实组ID或者保存的组ID
+    pub fn with_gid(&self, gid: u32) -> Result<Arc<Self>, SystemError> {
+        let mut cred = self.clone();
+        if self.is_root() {
+            cred.gid = gid;
+            cred.egid = gid;
+            cred.sgid = gid;
+        } else if gid == self.gid || gid == self.sgid {
+            cred.egid = gid;
+        } else {
+            return Err(SystemError::EPERM);
+        }
+        return Ok(Arc::new(cred));
+    }
+
+    /// @brief 生成执行新程序时使用的凭证
+    ///
+    /// 如果可执行文件设置了S_ISUID（S_ISGID）位，那么有效用户ID（有效组ID）变为文件的所有者（所属组）。
+    /// 保存的用户ID和组ID被设置为新的有效ID
+    ///
+    /// @param euid 文件设置了S_ISUID位时，为文件的所有者，否则为None
+    /// @param egid 文件设置了S_ISGID位时，为文件所属的组，否则为None
+    pub fn for_exec(&self, euid: Option<u32>, egid: Option<u32>) -> Arc<Self> {
+        let mut cred = self.clone();
+        if let Some(euid) = euid {
+            cred.euid = euid;
+        }
+        if let Some(egid) = egid {
+            cred.egid = egid;
+        }
+        cred.suid = cred.euid;
+        cred.sgid = cred.egid;
+        return Arc::new(cred);
+    }
+}
+
+lazy_static! {
+    /// 初始进程的凭证。pcb中没有设置凭证的进程（初始进程和内核线程）都使用这个凭证
+    pub static ref INIT_CRED: Arc<Cred> = Arc::new(Cred::root());
+}
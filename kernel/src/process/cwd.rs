@@ -0,0 +1,49 @@
+//! 进程的当前工作目录
+//!
+//! 与凭证一样，工作目录一旦被设置到pcb中就不会再被修改：chdir时，会创建一个新的`WorkingDir`，
+//! 然后整体替换掉pcb中的工作目录。因此，fork出来的子进程可以直接与父进程共享同一份工作目录。
+
+use alloc::{string::String, sync::Arc};
+
+use crate::filesystem::vfs::{mount::MountActiveRef, IndexNode, ROOT_INODE};
+
+/// @brief 进程的当前工作目录
+#[derive(Debug)]
+pub struct WorkingDir {
+    /// 工作目录的inode
+    inode: Arc<dyn IndexNode>,
+    /// 工作目录的规范的绝对路径
+    path: String,
+    /// 对工作目录所在的挂载文件系统的活跃引用，进程位于该文件系统中时，它不能被卸载
+    _mount_ref: Option<MountActiveRef>,
+}
+
+impl WorkingDir {
+    /// @brief 创建一个工作目录
+    ///
+    /// @param inode 工作目录的inode（调用者需要保证它是一个目录）
+    /// @param path 工作目录的规范的绝对路径
+    pub fn new(inode: Arc<dyn IndexNode>, path: String) -> Arc<Self> {
+        let mount_ref: Option<MountActiveRef> = MountActiveRef::new(&inode);
+        return Arc::new(Self {
+            inode,
+            path,
+            _mount_ref: mount_ref,
+        });
+    }
+
+    /// @brief 以根目录作为工作目录
+    pub fn root() -> Arc<Self> {
+        return Self::new(ROOT_INODE(), String::from("/"));
+    }
+
+    #[inline]
+    pub fn inode(&self) -> Arc<dyn IndexNode> {
+        return self.inode.clone();
+    }
+
+    #[inline]
+    pub fn path(&self) -> &str {
+        return &self.path;
+    }
+}
@@ -3,16 +3,24 @@ use core::{fmt::Debug, ptr::null};
 use alloc::{collections::BTreeMap, string::String, sync::Arc, vec::Vec};
 
 use crate::{
+    arch::asm::current::current_pcb,
     driver::base::block::SeekFrom,
     filesystem::vfs::{
         file::{File, FileMode},
-        ROOT_INODE,
+        mount::{inode_mount_flags, MountFlags},
+        permission::{check_permission, PermissionMask},
+        syscall::ModeType,
+        utils::user_path_at,
+        FileType, IndexNode,
     },
+    include::bindings::bindings::AT_FDCWD,
+    kerror,
     libs::elf::ELF_LOADER,
     mm::{
         ucontext::{AddressSpace, UserStack},
         VirtAddr,
     },
+    process::cred::Cred,
     syscall::SystemError,
 };
 
@@ -157,10 +165,60 @@ impl<'a> ExecParam<'a> {
     }
 }
 
-/// ## 加载二进制文件
-pub fn load_binary_file(param: &mut ExecParam) -> Result<BinaryLoaderResult, SystemError> {
-    let inode = ROOT_INODE().lookup(param.file_path)?;
+/// ## 查找要执行的文件
+///
+/// 权限检查和加载都使用这里得到的inode，而不是分别按路径查找，以免文件在两者之间被替换
+pub fn lookup_exec_file(file_path: &str) -> Result<Arc<dyn IndexNode>, SystemError> {
+    let (base, file_path) = user_path_at(AT_FDCWD, file_path)?;
+    return base.lookup(file_path);
+}
+
+/// ## 检查当前进程是否有权限执行指定的文件
+///
+/// 只有普通文件可以被执行，文件不能位于设置了noexec的挂载点上，并且当前进程需要对它有执行权限
+///
+/// ## 返回值
+///
+/// 新程序运行时使用的凭证。文件的S_ISUID、S_ISGID位会被考虑在内，除非文件位于设置了nosuid的挂载点上
+pub fn check_exec_permission(inode: &Arc<dyn IndexNode>) -> Result<Arc<Cred>, SystemError> {
+    let metadata = inode.metadata()?;
+    if metadata.file_type != FileType::File {
+        return Err(SystemError::EACCES);
+    }
+    let mount_flags: MountFlags = inode_mount_flags(inode);
+    if mount_flags.contains(MountFlags::NOEXEC) {
+        return Err(SystemError::EACCES);
+    }
+    let cred: Arc<Cred> = current_pcb().cred();
+    check_permission(&metadata, &cred, PermissionMask::MAY_EXEC)?;
 
+    if mount_flags.contains(MountFlags::NOSUID) {
+        return Ok(cred.for_exec(None, None));
+    }
+    let euid: Option<u32> = if metadata.mode & ModeType::S_ISUID.bits() != 0 {
+        Some(metadata.uid as u32)
+    } else {
+        None
+    };
+    // 没有组执行权限的S_ISGID位表示强制锁，而不是setgid
+    let setgid_bits: u32 = ModeType::S_ISGID.bits() | ModeType::S_IXGRP.bits();
+    let egid: Option<u32> = if metadata.mode & setgid_bits == setgid_bits {
+        Some(metadata.gid as u32)
+    } else {
+        None
+    };
+    return Ok(cred.for_exec(euid, egid));
+}
+
+/// ## 加载二进制文件
+///
+/// ## 参数
+///
+/// - `inode`：要加载的文件（由lookup_exec_file查找得到）
+pub fn load_binary_file(
+    param: &mut ExecParam,
+    inode: Arc<dyn IndexNode>,
+) -> Result<BinaryLoaderResult, SystemError> {
     // 读取文件头部，用于判断文件类型
     let file = File::new(inode, FileMode::O_RDONLY)?;
     param.file = Some(file);
@@ -186,9 +244,10 @@ pub fn load_binary_file(param: &mut ExecParam) -> Result<BinaryLoaderResult, Sys
     assert!(param.vm().is_current());
     // kdebug!("load_binary_file: to load with param: {:?}", param);
 
-    let result: BinaryLoaderResult = loader
-        .load(param, &head_buf)
-        .unwrap_or_else(|e| panic!("load_binary_file failed: error: {e:?}, param: {param:?}"));
+    let result: BinaryLoaderResult = loader.load(param, &head_buf).map_err(|e| {
+        kerror!("load_binary_file failed: error: {e:?}, param: {param:?}");
+        Into::<SystemError>::into(e)
+    })?;
 
     // kdebug!("load_binary_file: load success");
     return Ok(result);
@@ -22,6 +22,10 @@ extern int process_copy_sighand(uint64_t clone_flags, struct process_control_blo
 extern int process_copy_signal(uint64_t clone_flags, struct process_control_block *pcb);
 extern void process_exit_sighand(struct process_control_block *pcb);
 extern void process_exit_signal(struct process_control_block *pcb);
+extern int process_copy_cred(uint64_t clone_flags, struct process_control_block *pcb);
+extern void process_exit_cred(struct process_control_block *pcb);
+extern int process_copy_cwd(uint64_t clone_flags, struct process_control_block *pcb);
+extern void process_exit_cwd(struct process_control_block *pcb);
 
 /**
  * @brief fork当前进程
@@ -107,6 +111,16 @@ unsigned long do_fork(struct pt_regs *regs, unsigned long clone_flags, unsigned
     if (retval)
         goto copy_flags_failed;
 
+    // 拷贝身份凭证
+    retval = process_copy_cred(clone_flags, tsk);
+    if (retval)
+        goto copy_cred_failed;
+
+    // 拷贝当前工作目录
+    retval = process_copy_cwd(clone_flags, tsk);
+    if (retval)
+        goto copy_cwd_failed;
+
     // 拷贝内存空间分布结构体
     retval = process_copy_mm(clone_flags, tsk);
     if (retval)
@@ -158,6 +172,12 @@ copy_signal_failed:;
 copy_mm_failed:;
     // 回收内存空间分布结构体
     process_exit_mm(tsk);
+copy_cwd_failed:;
+    // 回收当前工作目录
+    process_exit_cwd(tsk);
+copy_cred_failed:;
+    // 回收身份凭证
+    process_exit_cred(tsk);
 copy_flags_failed:;
     kfree(tsk);
     return retval;
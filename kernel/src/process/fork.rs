@@ -1,6 +1,6 @@
 use core::{ffi::c_void, ptr::null_mut, sync::atomic::compiler_fence};
 
-use alloc::boxed::Box;
+use alloc::{boxed::Box, sync::Arc};
 
 use crate::{
     arch::asm::current::current_pcb,
@@ -132,6 +132,47 @@ pub extern "C" fn process_exit_sighand(pcb: *mut process_control_block) {
     }
 }
 
+/// 拷贝进程的身份凭证
+///
+/// fork时，新的pcb是从父进程的pcb直接复制过来的，其中的凭证指针与父进程相同。
+/// 由于凭证在设置之后不会被修改，因此子进程与父进程共享同一份凭证，这里只需要增加它的引用计数。
+#[no_mangle]
+pub extern "C" fn process_copy_cred(_clone_flags: u64, pcb: *mut process_control_block) -> i32 {
+    let pcb = unsafe { pcb.as_mut() }.unwrap();
+    pcb.cred = Arc::into_raw(current_pcb().cred()) as *mut c_void;
+    return 0;
+}
+
+/// 回收进程的身份凭证
+#[no_mangle]
+pub extern "C" fn process_exit_cred(pcb: *mut process_control_block) {
+    unsafe { (*pcb).drop_cred() };
+}
+
+/// 拷贝进程的当前工作目录
+///
+/// 与凭证相同，工作目录在设置之后不会被修改，因此子进程与父进程共享同一份工作目录。
+/// 父进程没有设置工作目录时，子进程也不设置（即位于根目录）
+#[no_mangle]
+pub extern "C" fn process_copy_cwd(_clone_flags: u64, pcb: *mut process_control_block) -> i32 {
+    let pcb = unsafe { pcb.as_mut() }.unwrap();
+    let parent = current_pcb();
+    pcb.cwd = if parent.cwd.is_null() {
+        null_mut()
+    } else {
+        Arc::into_raw(parent.cwd()) as *mut c_void
+    };
+    return 0;
+}
+
+/// 回收进程的当前工作目录
+///
+/// 进程退出时就会调用本函数，这样僵尸进程不会阻止它的工作目录所在的文件系统被卸载
+#[no_mangle]
+pub extern "C" fn process_exit_cwd(pcb: *mut process_control_block) {
+    unsafe { (*pcb).drop_cwd() };
+}
+
 /// 拷贝进程的地址空间
 ///
 /// ## 参数
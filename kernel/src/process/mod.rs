@@ -13,6 +13,8 @@ use crate::{
 
 pub mod abi;
 pub mod c_adapter;
+pub mod cred;
+pub mod cwd;
 pub mod exec;
 pub mod fork;
 pub mod initial_proc;
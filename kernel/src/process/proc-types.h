@@ -137,6 +137,10 @@ struct process_control_block
     void *fp_state;
     // 指向进程的地址空间的arc指针.
     void *address_space;
+    // 指向进程的身份凭证的arc指针（由Rust进行管理）。为NULL时，使用初始进程的凭证
+    void *cred;
+    // 指向进程的当前工作目录的arc指针（由Rust进行管理）。为NULL时，当前工作目录为根目录
+    void *cwd;
 };
 
 // 将进程的pcb和内核栈融合到一起,8字节对齐
@@ -46,6 +46,8 @@ extern void process_exit_signal(struct process_control_block *pcb);
 extern void initial_proc_init_signal(struct process_control_block *pcb);
 extern void rs_process_exit_fpstate(struct process_control_block *pcb);
 extern void rs_drop_address_space(struct process_control_block *pcb);
+extern void process_exit_cred(struct process_control_block *pcb);
+extern void process_exit_cwd(struct process_control_block *pcb);
 extern int process_init_files();
 extern int rs_init_stdio();
 extern uint64_t rs_do_execve(const char *filename, const char *const argv[], const char *const envp[], struct pt_regs *regs);
@@ -58,7 +60,7 @@ extern uint64_t rs_exec_init_process(struct pt_regs *regs);
         .thread = &initial_thread, .addr_limit = 0xffffffffffffffff, .pid = 0, .priority = 2,                        \
         .virtual_runtime = 0, .fds = {0}, .next_pcb = &proc, .prev_pcb = &proc, .parent_pcb = &proc, .exit_code = 0, \
         .wait_child_proc_exit = 0, .worker_private = NULL, .policy = SCHED_NORMAL, .sig_blocked = 0,                 \
-        .signal = &INITIAL_SIGNALS, .sighand = &INITIAL_SIGHAND, .address_space = NULL                               \
+        .signal = &INITIAL_SIGNALS, .sighand = &INITIAL_SIGHAND, .address_space = NULL, .cred = NULL, .cwd = NULL    \
     }
 
 struct thread_struct initial_thread = {
@@ -251,6 +253,7 @@ ul process_do_exit(ul code)
 
     // 进程退出时释放资源
     process_exit_files(pcb);
+    process_exit_cwd(pcb);
     process_exit_thread(pcb);
     // todo: 可否在这里释放内存结构体？（在判断共享页引用问题之后）
 
@@ -464,6 +467,8 @@ int process_release_pcb(struct process_control_block *pcb)
     rs_procfs_unregister_pid(pcb->pid);
     // 释放进程的地址空间
     process_exit_mm(pcb);
+    // 释放进程的身份凭证
+    process_exit_cred(pcb);
     // 释放当前pcb
     kfree(pcb);
     return 0;
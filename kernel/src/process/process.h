@@ -175,6 +175,7 @@ extern struct mm_struct initial_mm;
 extern struct thread_struct initial_thread;
 extern union proc_union initial_proc_union;
 extern struct process_control_block *initial_proc[MAX_CPU_NUM];
+extern long process_global_pid; // 下一个要分配的pid
 
 /**
  * @brief 给pcb设置名字
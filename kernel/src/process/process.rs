@@ -1,10 +1,10 @@
 use core::{
-    ffi::c_void,
+    ffi::{c_char, c_void},
     mem::ManuallyDrop,
     ptr::{null_mut, read_volatile, write_volatile},
 };
 
-use alloc::{boxed::Box, sync::Arc};
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
 
 use crate::{
     arch::asm::current::current_pcb,
@@ -24,7 +24,11 @@ use crate::{
     syscall::SystemError,
 };
 
-use super::preempt::{preempt_disable, preempt_enable};
+use super::{
+    cred::{Cred, INIT_CRED},
+    cwd::WorkingDir,
+    preempt::{preempt_disable, preempt_enable},
+};
 
 /// 判断进程是否已经停止
 #[no_mangle]
@@ -118,6 +122,26 @@ pub fn process_is_executing(pcb: *const process_control_block) -> bool {
 }
 
 impl process_control_block {
+    /// @brief 获取进程的名字
+    pub fn name(&self) -> String {
+        let name: Vec<u8> = self
+            .name
+            .iter()
+            .take_while(|c| **c != 0)
+            .map(|c| *c as u8)
+            .collect();
+        return String::from_utf8_lossy(&name).into_owned();
+    }
+
+    /// @brief 设置进程的名字。超出PCB_NAME_LEN - 1的部分会被截断
+    pub fn set_name(&mut self, name: &str) {
+        let len: usize = name.len().min(self.name.len() - 1);
+        for (dst, src) in self.name.iter_mut().zip(name.bytes().take(len)) {
+            *dst = src as c_char;
+        }
+        self.name[len..].fill(0);
+    }
+
     /// @brief 初始化进程PCB的文件描述符数组。
     /// 请注意，如果当前进程已经有文件描述符数组，那么本操作将被禁止
     pub fn init_files(&mut self) -> Result<(), SystemError> {
@@ -346,6 +370,64 @@ impl process_control_block {
         let result = Arc::clone(&arc_wrapper);
         return Some(result);
     }
+
+    /// 获取进程的身份凭证
+    ///
+    /// 如果pcb中没有设置凭证（初始进程和内核线程），那么返回初始进程的凭证
+    pub fn cred(&self) -> Arc<Cred> {
+        let ptr = self.cred as *const Cred;
+        if ptr.is_null() {
+            return INIT_CRED.clone();
+        }
+        // 为了防止pcb中的指针被释放，这里需要将其包装一下，使得Arc的drop不会被调用
+        let arc_wrapper = ManuallyDrop::new(unsafe { Arc::from_raw(ptr) });
+        return Arc::clone(&arc_wrapper);
+    }
+
+    /// 替换进程的身份凭证，原有的凭证会被释放
+    pub fn set_cred(&mut self, cred: Arc<Cred>) {
+        unsafe { self.drop_cred() };
+        self.cred = Arc::into_raw(cred) as *mut c_void;
+    }
+
+    /// 释放pcb中存储的身份凭证的指针
+    pub unsafe fn drop_cred(&mut self) {
+        let p = self.cred as *const Cred;
+        if p.is_null() {
+            return;
+        }
+        drop(Arc::from_raw(p));
+        self.cred = null_mut();
+    }
+
+    /// 获取进程的当前工作目录
+    ///
+    /// 如果pcb中没有设置工作目录（初始进程和内核线程），那么返回根目录
+    pub fn cwd(&self) -> Arc<WorkingDir> {
+        let ptr = self.cwd as *const WorkingDir;
+        if ptr.is_null() {
+            return WorkingDir::root();
+        }
+        // 为了防止pcb中的指针被释放，这里需要将其包装一下，使得Arc的drop不会被调用
+        let arc_wrapper = ManuallyDrop::new(unsafe { Arc::from_raw(ptr) });
+        return Arc::clone(&arc_wrapper);
+    }
+
+    /// 替换进程的当前工作目录，原有的工作目录会被释放
+    pub fn set_cwd(&mut self, cwd: Arc<WorkingDir>) {
+        unsafe { self.drop_cwd() };
+        self.cwd = Arc::into_raw(cwd) as *mut c_void;
+    }
+
+    /// 释放pcb中存储的当前工作目录的指针
+    pub unsafe fn drop_cwd(&mut self) {
+        let p = self.cwd as *const WorkingDir;
+        if p.is_null() {
+            return;
+        }
+        drop(Arc::from_raw(p));
+        self.cwd = null_mut();
+    }
 }
 
 /// @brief 初始化pid=1的进程的stdio
@@ -1,11 +1,18 @@
 use core::ffi::{c_int, c_void};
 
+use alloc::{sync::Arc, vec::Vec};
+
 use crate::{
     arch::asm::current::current_pcb,
     include::bindings::bindings::{pid_t, process_do_exit},
-    syscall::{Syscall, SystemError},
+    syscall::{
+        user_access::{UserBufferReader, UserBufferWriter},
+        Syscall, SystemError,
+    },
 };
 
+use super::cred::NGROUPS_MAX;
+
 extern "C" {
     fn c_sys_wait4(pid: pid_t, wstatus: *mut c_int, options: c_int, rusage: *mut c_void) -> c_int;
 }
@@ -62,4 +69,110 @@ impl Syscall {
     pub fn getpid() -> Result<usize, SystemError> {
         return Ok(current_pcb().pid as usize);
     }
+
+    /// # 获取进程的真实用户ID
+    pub fn getuid() -> Result<usize, SystemError> {
+        return Ok(current_pcb().cred().uid as usize);
+    }
+
+    /// # 获取进程的有效用户ID
+    pub fn geteuid() -> Result<usize, SystemError> {
+        return Ok(current_pcb().cred().euid as usize);
+    }
+
+    /// # 获取进程的真实组ID
+    pub fn getgid() -> Result<usize, SystemError> {
+        return Ok(current_pcb().cred().gid as usize);
+    }
+
+    /// # 获取进程的有效组ID
+    pub fn getegid() -> Result<usize, SystemError> {
+        return Ok(current_pcb().cred().egid as usize);
+    }
+
+    /// # 设置进程的用户ID
+    ///
+    /// ## 参数
+    ///
+    /// - uid: 新的用户ID。超级用户会同时设置真实、有效、保存的用户ID；
+    ///   普通用户只能把有效用户ID设置为自己的真实用户ID或者保存的用户ID
+    pub fn setuid(uid: u32) -> Result<usize, SystemError> {
+        let pcb = current_pcb();
+        let cred = pcb.cred().with_uid(uid)?;
+        pcb.set_cred(cred);
+        return Ok(0);
+    }
+
+    /// # 设置进程的组ID
+    ///
+    /// ## 参数
+    ///
+    /// - gid: 新的组ID。超级用户会同时设置真实、有效、保存的组ID；
+    ///   普通用户只能把有效组ID设置为自己的真实组ID或者保存的组ID
+    pub fn setgid(gid: u32) -> Result<usize, SystemError> {
+        let pcb = current_pcb();
+        let cred = pcb.cred().with_gid(gid)?;
+        pcb.set_cred(cred);
+        return Ok(0);
+    }
+
+    /// # 获取进程的附加组
+    ///
+    /// ## 参数
+    ///
+    /// - size: 用户缓冲区能容纳的组ID的数量。为0时，只返回附加组的数量
+    /// - list: 用户缓冲区
+    ///
+    /// ## 返回值
+    ///
+    /// 成功时返回附加组的数量
+    pub fn getgroups(size: i32, list: *mut u32) -> Result<usize, SystemError> {
+        if size < 0 {
+            return Err(SystemError::EINVAL);
+        }
+        let cred = current_pcb().cred();
+        let count = cred.groups.len();
+        if size == 0 {
+            return Ok(count);
+        }
+        if (size as usize) < count {
+            return Err(SystemError::EINVAL);
+        }
+
+        if count > 0 {
+            let mut writer =
+                UserBufferWriter::new(list, count * core::mem::size_of::<u32>(), true)?;
+            writer.copy_to_user(&cred.groups, 0)?;
+        }
+        return Ok(count);
+    }
+
+    /// # 设置进程的附加组（只有超级用户可以设置）
+    ///
+    /// ## 参数
+    ///
+    /// - size: 组ID的数量
+    /// - list: 存放组ID的用户缓冲区
+    pub fn setgroups(size: usize, list: *const u32) -> Result<usize, SystemError> {
+        if size > NGROUPS_MAX {
+            return Err(SystemError::EINVAL);
+        }
+        let pcb = current_pcb();
+        let cred = pcb.cred();
+        if !cred.is_root() {
+            return Err(SystemError::EPERM);
+        }
+
+        let groups: Vec<u32> = if size == 0 {
+            Vec::new()
+        } else {
+            let reader = UserBufferReader::new(list, size * core::mem::size_of::<u32>(), true)?;
+            reader.read_from_user::<u32>(0)?.to_vec()
+        };
+
+        let mut new_cred = (*cred).clone();
+        new_cred.groups = groups;
+        pcb.set_cred(Arc::new(new_cred));
+        return Ok(0);
+    }
 }
@@ -0,0 +1,79 @@
+//! 系统的平均负载（/proc/loadavg）
+//!
+//! 与Linux相同，每隔LOAD_FREQ对所有cpu的运行队列长度进行一次采样，
+//! 然后按照指数衰减的方式，计算最近1、5、15分钟的平均负载。
+//! 由于内核中不能进行浮点运算，平均负载使用带有FSHIFT位小数的定点数来表示。
+
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::include::bindings::bindings::smp_get_total_cpu;
+
+use super::core::get_cpu_loads;
+
+/// 定点数的小数部分的位数
+pub const FSHIFT: usize = 11;
+/// 定点数表示的1.0
+pub const FIXED_1: usize = 1 << FSHIFT;
+/// 采样的间隔（单位：定时器时间片，即微秒），为5秒
+const LOAD_FREQ: u64 = 5 * 1000000;
+/// 1/exp(5s/1min)、1/exp(5s/5min)、1/exp(5s/15min)的定点数表示
+const EXP: [usize; 3] = [1884, 2014, 2037];
+
+/// 最近1、5、15分钟的平均负载
+static AVENRUN: [AtomicUsize; 3] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+/// 下一次采样的时间
+static NEXT_SAMPLE: AtomicU64 = AtomicU64::new(LOAD_FREQ);
+
+/// @brief 计算新的平均负载：load = load * exp + active * (1 - exp)
+fn calc_load(load: usize, exp: usize, active: usize) -> usize {
+    let mut newload: usize = load * exp + active * (FIXED_1 - exp);
+    // 负载上升时向上取整，使得负载最终能够达到active
+    if active >= load {
+        newload += FIXED_1 - 1;
+    }
+    return newload / FIXED_1;
+}
+
+/// @brief 获取所有cpu的运行队列中的进程数之和
+pub fn nr_running() -> usize {
+    let cpu_num: u32 = unsafe { smp_get_total_cpu() };
+    return (0..cpu_num).map(|cpu| get_cpu_loads(cpu) as usize).sum();
+}
+
+/// @brief 更新平均负载。应当在定时器时间片更新时被调用，每隔LOAD_FREQ才会真正进行一次采样
+///
+/// @param now 当前的定时器时间片
+pub fn calc_global_load(now: u64) {
+    let next: u64 = NEXT_SAMPLE.load(Ordering::Relaxed);
+    if now < next {
+        return;
+    }
+    // 防止多个cpu同时进行采样
+    if NEXT_SAMPLE
+        .compare_exchange(next, next + LOAD_FREQ, Ordering::SeqCst, Ordering::Relaxed)
+        .is_err()
+    {
+        return;
+    }
+
+    let active: usize = nr_running() * FIXED_1;
+    for (avg, exp) in AVENRUN.iter().zip(EXP.iter()) {
+        avg.store(
+            calc_load(avg.load(Ordering::Relaxed), *exp, active),
+            Ordering::Relaxed,
+        );
+    }
+}
+
+/// @brief 获取最近1、5、15分钟的平均负载（FSHIFT位小数的定点数）
+pub fn get_avenrun() -> [usize; 3] {
+    return [
+        AVENRUN[0].load(Ordering::Relaxed),
+        AVENRUN[1].load(Ordering::Relaxed),
+        AVENRUN[2].load(Ordering::Relaxed),
+    ];
+}
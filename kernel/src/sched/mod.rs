@@ -1,4 +1,5 @@
 pub mod cfs;
 pub mod core;
+pub mod loadavg;
 pub mod rt;
 pub mod syscall;
@@ -3,21 +3,30 @@ use core::{
     sync::atomic::{AtomicBool, Ordering},
 };
 
+use alloc::string::String;
 use num_traits::{FromPrimitive, ToPrimitive};
 
 use crate::{
     arch::{cpu::cpu_reset, MMArch},
     driver::base::block::SeekFrom,
-    filesystem::vfs::{
-        fcntl::FcntlCommand,
-        file::FileMode,
-        syscall::{PosixKstat, SEEK_CUR, SEEK_END, SEEK_MAX, SEEK_SET},
-        MAX_PATHLEN,
+    filesystem::{
+        eventpoll::{EPollCtlOption, EPollEvent},
+        vfs::{
+            fcntl::FcntlCommand,
+            file::FileMode,
+            poll::PollFd,
+            syscall::{
+                IoVec, PosixFlock, PosixKstat, PosixStatfs, PosixStatx, PselectSigmask, SEEK_CUR,
+                SEEK_END, SEEK_MAX, SEEK_SET,
+            },
+            MAX_PATHLEN,
+        },
     },
     include::bindings::bindings::{pid_t, PAGE_2M_SIZE, PAGE_4K_SIZE},
+    ipc::signal_types::sigset_t,
     kinfo,
     libs::align::page_align_up,
-    mm::{verify_area, MemoryManagementArch, VirtAddr},
+    mm::{verify_area, verify_area_writable, MemoryManagementArch, VirtAddr},
     net::syscall::SockAddr,
     time::{
         syscall::{PosixTimeZone, PosixTimeval},
@@ -25,7 +34,7 @@ use crate::{
     },
 };
 
-use self::user_access::UserBufferWriter;
+use self::user_access::{check_and_clone_cstr, UserBufferWriter};
 
 pub mod user_access;
 
@@ -377,6 +386,58 @@ pub const SYS_GETPGID: usize = 50;
 
 pub const SYS_FCNTL: usize = 51;
 pub const SYS_FTRUNCATE: usize = 52;
+pub const SYS_SYMLINK: usize = 53;
+pub const SYS_READLINK: usize = 54;
+pub const SYS_LSTAT: usize = 55;
+pub const SYS_OPENAT: usize = 56;
+pub const SYS_MKDIRAT: usize = 57;
+pub const SYS_FSTATAT: usize = 58;
+pub const SYS_RENAMEAT: usize = 59;
+pub const SYS_LINKAT: usize = 60;
+pub const SYS_FACCESSAT: usize = 61;
+pub const SYS_RENAME: usize = 62;
+pub const SYS_RENAMEAT2: usize = 63;
+pub const SYS_MOUNT: usize = 64;
+pub const SYS_UMOUNT2: usize = 65;
+pub const SYS_FSYNC: usize = 66;
+pub const SYS_SYNC: usize = 67;
+pub const SYS_MSYNC: usize = 68;
+pub const SYS_FLOCK: usize = 69;
+pub const SYS_POLL: usize = 70;
+pub const SYS_SELECT: usize = 71;
+pub const SYS_PSELECT6: usize = 72;
+pub const SYS_EPOLL_CREATE: usize = 73;
+pub const SYS_EPOLL_CREATE1: usize = 74;
+pub const SYS_EPOLL_CTL: usize = 75;
+pub const SYS_EPOLL_WAIT: usize = 76;
+pub const SYS_EPOLL_PWAIT: usize = 77;
+pub const SYS_READV: usize = 78;
+pub const SYS_WRITEV: usize = 79;
+pub const SYS_PREAD64: usize = 80;
+pub const SYS_PWRITE64: usize = 81;
+pub const SYS_PREADV: usize = 82;
+pub const SYS_PWRITEV: usize = 83;
+pub const SYS_CHMOD: usize = 84;
+pub const SYS_FCHMOD: usize = 85;
+pub const SYS_CHOWN: usize = 86;
+pub const SYS_FCHOWN: usize = 87;
+pub const SYS_ACCESS: usize = 88;
+pub const SYS_UMASK: usize = 89;
+pub const SYS_SETUID: usize = 90;
+pub const SYS_SETGID: usize = 91;
+pub const SYS_GETUID: usize = 92;
+pub const SYS_GETEUID: usize = 93;
+pub const SYS_GETGID: usize = 94;
+pub const SYS_GETEGID: usize = 95;
+pub const SYS_GETGROUPS: usize = 96;
+pub const SYS_SETGROUPS: usize = 97;
+pub const SYS_STAT: usize = 98;
+pub const SYS_STATX: usize = 99;
+pub const SYS_UTIMENSAT: usize = 100;
+pub const SYS_STATFS: usize = 101;
+pub const SYS_FSTATFS: usize = 102;
+pub const SYS_LINK: usize = 103;
+pub const SYS_UNLINK: usize = 104;
 
 #[derive(Debug)]
 pub struct Syscall;
@@ -421,8 +482,9 @@ impl Syscall {
                     let path: &str = path.unwrap();
                     let flags = args[1];
                     let open_flags: FileMode = FileMode::from_bits_truncate(flags as u32);
+                    let create_mode = args[2] as u32;
 
-                    Self::open(path, open_flags)
+                    Self::open(path, open_flags, create_mode)
                 };
 
                 res
@@ -440,6 +502,9 @@ impl Syscall {
                 let res = if from_user && verify_area(virt_addr, len as usize).is_err() {
                     // 来自用户态，而buffer在内核态，这样的操作不被允许
                     Err(SystemError::EPERM)
+                } else if from_user && verify_area_writable(virt_addr, len as usize).is_err() {
+                    // 缓冲区位于只读的映射中
+                    Err(SystemError::EFAULT)
                 } else {
                     let buf: &mut [u8] = unsafe {
                         core::slice::from_raw_parts_mut::<'static, u8>(buf_vaddr as *mut u8, len)
@@ -821,8 +886,8 @@ impl Syscall {
                 let virt_addr = VirtAddr::new(addr as usize);
                 let security_check = || {
                     // 验证buf的地址是否合法
-                    if verify_area(virt_buf, len as usize).is_err() {
-                        // 地址空间超出了用户空间的范围，不合法
+                    if verify_area_writable(virt_buf, len as usize).is_err() {
+                        // 地址空间超出了用户空间的范围，或者不可写，不合法
                         return Err(SystemError::EFAULT);
                     }
 
@@ -925,7 +990,7 @@ impl Syscall {
                 let vaddr = VirtAddr::new(kstat as usize);
                 // FIXME 由于c中的verify_area与rust中的verify_area重名，所以在引入时加了前缀区分
                 // TODO 应该将用了c版本的verify_area都改为rust的verify_area
-                match verify_area(vaddr, core::mem::size_of::<PosixKstat>()) {
+                match verify_area_writable(vaddr, core::mem::size_of::<PosixKstat>()) {
                     Ok(_) => Self::fstat(fd, kstat),
                     Err(e) => Err(e),
                 }
@@ -936,10 +1001,25 @@ impl Syscall {
                 let cmd: Option<FcntlCommand> =
                     <FcntlCommand as FromPrimitive>::from_u32(args[1] as u32);
                 let arg = args[2] as i32;
-                let res = if let Some(cmd) = cmd {
-                    Self::fcntl(fd, cmd, arg)
-                } else {
-                    Err(SystemError::EINVAL)
+                let res = match cmd {
+                    Some(FcntlCommand::GetLock)
+                    | Some(FcntlCommand::SetLock)
+                    | Some(FcntlCommand::SetLockWait) => {
+                        let flock = args[2] as *mut PosixFlock;
+                        let vaddr = VirtAddr::new(flock as usize);
+                        // F_GETLK会把结果写回用户空间
+                        let check = if cmd == Some(FcntlCommand::GetLock) {
+                            verify_area_writable(vaddr, core::mem::size_of::<PosixFlock>())
+                        } else {
+                            verify_area(vaddr, core::mem::size_of::<PosixFlock>())
+                        };
+                        match check {
+                            Ok(_) => Self::fcntl_lock(fd, cmd.unwrap(), flock),
+                            Err(e) => Err(e),
+                        }
+                    }
+                    Some(cmd) => Self::fcntl(fd, cmd, arg),
+                    None => Err(SystemError::EINVAL),
                 };
 
                 // kdebug!("FCNTL: fd: {}, cmd: {:?}, arg: {}, res: {:?}", fd, cmd, arg, res);
@@ -954,6 +1034,410 @@ impl Syscall {
                 res
             }
 
+            SYS_SYMLINK => {
+                let target = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                let linkpath = check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN));
+                match (target, linkpath) {
+                    (Ok(target), Ok(linkpath)) => Self::symlink(&target, &linkpath),
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                }
+            }
+
+            SYS_READLINK => {
+                let path = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                let buf = args[1] as *mut u8;
+                let len = args[2];
+                match path {
+                    Ok(path) => match UserBufferWriter::new(buf, len, from_user) {
+                        Ok(mut user_buffer_writer) => match user_buffer_writer.buffer::<u8>(0) {
+                            Ok(buf) => Self::readlink(&path, buf),
+                            Err(e) => Err(e),
+                        },
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => Err(e),
+                }
+            }
+
+            SYS_LSTAT => {
+                let path = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                let kstat = args[1] as *mut PosixKstat;
+                let vaddr = VirtAddr::new(kstat as usize);
+                match (
+                    path,
+                    verify_area_writable(vaddr, core::mem::size_of::<PosixKstat>()),
+                ) {
+                    (Ok(path), Ok(_)) => Self::lstat(&path, kstat),
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                }
+            }
+
+            SYS_OPENAT => {
+                let dirfd = args[0] as i32;
+                let path = check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN));
+                let open_flags: FileMode = FileMode::from_bits_truncate(args[2] as u32);
+                let create_mode = args[3] as u32;
+                match path {
+                    Ok(path) => Self::openat(dirfd, &path, open_flags, create_mode),
+                    Err(e) => Err(e),
+                }
+            }
+
+            SYS_MKDIRAT => {
+                let dirfd = args[0] as i32;
+                let path = check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN));
+                let mode = args[2];
+                match path {
+                    Ok(path) => Self::mkdirat(dirfd, &path, mode),
+                    Err(e) => Err(e),
+                }
+            }
+
+            SYS_FSTATAT => {
+                let dirfd = args[0] as i32;
+                let path = check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN));
+                let kstat = args[2] as *mut PosixKstat;
+                let flags = args[3] as u32;
+                let vaddr = VirtAddr::new(kstat as usize);
+                match (
+                    path,
+                    verify_area_writable(vaddr, core::mem::size_of::<PosixKstat>()),
+                ) {
+                    (Ok(path), Ok(_)) => Self::fstatat(dirfd, &path, kstat, flags),
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                }
+            }
+
+            SYS_RENAMEAT => {
+                let olddirfd = args[0] as i32;
+                let oldpath = check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN));
+                let newdirfd = args[2] as i32;
+                let newpath = check_and_clone_cstr(args[3] as *const u8, Some(MAX_PATHLEN));
+                match (oldpath, newpath) {
+                    (Ok(oldpath), Ok(newpath)) => {
+                        Self::renameat(olddirfd, &oldpath, newdirfd, &newpath)
+                    }
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                }
+            }
+
+            SYS_LINKAT => {
+                let olddirfd = args[0] as i32;
+                let oldpath = check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN));
+                let newdirfd = args[2] as i32;
+                let newpath = check_and_clone_cstr(args[3] as *const u8, Some(MAX_PATHLEN));
+                let flags = args[4] as u32;
+                match (oldpath, newpath) {
+                    (Ok(oldpath), Ok(newpath)) => {
+                        Self::linkat(olddirfd, &oldpath, newdirfd, &newpath, flags)
+                    }
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                }
+            }
+
+            SYS_FACCESSAT => {
+                let dirfd = args[0] as i32;
+                let path = check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN));
+                let mode = args[2] as u32;
+                let flags = args[3] as u32;
+                match path {
+                    Ok(path) => Self::faccessat(dirfd, &path, mode, flags),
+                    Err(e) => Err(e),
+                }
+            }
+
+            SYS_RENAME => {
+                let oldpath = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                let newpath = check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN));
+                match (oldpath, newpath) {
+                    (Ok(oldpath), Ok(newpath)) => Self::rename(&oldpath, &newpath),
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                }
+            }
+
+            SYS_RENAMEAT2 => {
+                let olddirfd = args[0] as i32;
+                let oldpath = check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN));
+                let newdirfd = args[2] as i32;
+                let newpath = check_and_clone_cstr(args[3] as *const u8, Some(MAX_PATHLEN));
+                let flags = args[4] as u32;
+                match (oldpath, newpath) {
+                    (Ok(oldpath), Ok(newpath)) => {
+                        Self::renameat2(olddirfd, &oldpath, newdirfd, &newpath, flags)
+                    }
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                }
+            }
+
+            SYS_MOUNT => {
+                let source = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                let target = check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN));
+                let fstype = check_and_clone_cstr(args[2] as *const u8, Some(MAX_PATHLEN));
+                let flags = args[3] as u32;
+                // 挂载参数是可选的
+                let data = if args[4] == 0 {
+                    Ok(String::new())
+                } else {
+                    check_and_clone_cstr(args[4] as *const u8, Some(MAX_PATHLEN))
+                };
+                match (source, target, fstype, data) {
+                    (Ok(source), Ok(target), Ok(fstype), Ok(data)) => {
+                        Self::mount(&source, &target, &fstype, flags, &data)
+                    }
+                    (Err(e), _, _, _)
+                    | (_, Err(e), _, _)
+                    | (_, _, Err(e), _)
+                    | (_, _, _, Err(e)) => Err(e),
+                }
+            }
+
+            SYS_UMOUNT2 => {
+                let target = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                let flags = args[1] as u32;
+                match target {
+                    Ok(target) => Self::umount2(&target, flags),
+                    Err(e) => Err(e),
+                }
+            }
+
+            SYS_FSYNC => Self::fsync(args[0] as i32),
+
+            SYS_SYNC => Self::sync(),
+
+            SYS_MSYNC => {
+                let len = page_align_up(args[1]);
+                Self::msync(VirtAddr::new(args[0]), len, args[2])
+            }
+
+            SYS_FLOCK => Self::flock(args[0] as i32, args[1] as u32),
+
+            SYS_POLL => Self::poll(args[0] as *mut PollFd, args[1] as u32, args[2] as i32),
+
+            SYS_SELECT => Self::select(
+                args[0] as i32,
+                args[1] as *mut u64,
+                args[2] as *mut u64,
+                args[3] as *mut u64,
+                args[4] as *const PosixTimeval,
+            ),
+
+            SYS_PSELECT6 => Self::pselect6(
+                args[0] as i32,
+                args[1] as *mut u64,
+                args[2] as *mut u64,
+                args[3] as *mut u64,
+                args[4] as *const TimeSpec,
+                args[5] as *const PselectSigmask,
+            ),
+
+            SYS_EPOLL_CREATE => Self::epoll_create(args[0] as i32),
+
+            SYS_EPOLL_CREATE1 => Self::epoll_create1(args[0] as u32),
+
+            SYS_EPOLL_CTL => {
+                let op: Option<EPollCtlOption> =
+                    <EPollCtlOption as FromPrimitive>::from_u32(args[1] as u32);
+                if let Some(op) = op {
+                    Self::epoll_ctl(
+                        args[0] as i32,
+                        op,
+                        args[2] as i32,
+                        args[3] as *const EPollEvent,
+                    )
+                } else {
+                    Err(SystemError::EINVAL)
+                }
+            }
+
+            SYS_EPOLL_WAIT => Self::epoll_wait(
+                args[0] as i32,
+                args[1] as *mut EPollEvent,
+                args[2] as i32,
+                args[3] as i32,
+            ),
+
+            SYS_EPOLL_PWAIT => match Self::read_sigmask(args[4] as *const sigset_t, args[5]) {
+                Ok(sigmask) => Self::epoll_pwait(
+                    args[0] as i32,
+                    args[1] as *mut EPollEvent,
+                    args[2] as i32,
+                    args[3] as i32,
+                    sigmask,
+                ),
+                Err(e) => Err(e),
+            },
+
+            SYS_READV => Self::readv(args[0] as i32, args[1] as *const IoVec, args[2]),
+            SYS_WRITEV => Self::writev(args[0] as i32, args[1] as *const IoVec, args[2]),
+
+            SYS_PREAD64 => {
+                let fd = args[0] as i32;
+                let buf_vaddr = args[1];
+                let len = args[2];
+                let offset = args[3] as i64;
+                let virt_addr = VirtAddr::new(buf_vaddr);
+                if from_user && verify_area_writable(virt_addr, len).is_err() {
+                    Err(SystemError::EFAULT)
+                } else {
+                    let buf: &mut [u8] = unsafe {
+                        core::slice::from_raw_parts_mut::<'static, u8>(buf_vaddr as *mut u8, len)
+                    };
+                    Self::pread64(fd, buf, offset)
+                }
+            }
+
+            SYS_PWRITE64 => {
+                let fd = args[0] as i32;
+                let buf_vaddr = args[1];
+                let len = args[2];
+                let offset = args[3] as i64;
+                let virt_addr = VirtAddr::new(buf_vaddr);
+                if from_user && verify_area(virt_addr, len).is_err() {
+                    Err(SystemError::EFAULT)
+                } else {
+                    let buf: &[u8] = unsafe {
+                        core::slice::from_raw_parts::<'static, u8>(buf_vaddr as *const u8, len)
+                    };
+                    Self::pwrite64(fd, buf, offset)
+                }
+            }
+
+            SYS_PREADV => Self::preadv(
+                args[0] as i32,
+                args[1] as *const IoVec,
+                args[2],
+                args[3] as i64,
+            ),
+
+            SYS_PWRITEV => Self::pwritev(
+                args[0] as i32,
+                args[1] as *const IoVec,
+                args[2],
+                args[3] as i64,
+            ),
+
+            SYS_CHMOD => {
+                let path = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                match path {
+                    Ok(path) => Self::chmod(&path, args[1] as u32),
+                    Err(e) => Err(e),
+                }
+            }
+
+            SYS_FCHMOD => Self::fchmod(args[0] as i32, args[1] as u32),
+
+            SYS_CHOWN => {
+                let path = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                match path {
+                    Ok(path) => Self::chown(&path, args[1] as u32, args[2] as u32),
+                    Err(e) => Err(e),
+                }
+            }
+
+            SYS_FCHOWN => Self::fchown(args[0] as i32, args[1] as u32, args[2] as u32),
+
+            SYS_ACCESS => {
+                let path = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                match path {
+                    Ok(path) => Self::access(&path, args[1] as u32),
+                    Err(e) => Err(e),
+                }
+            }
+
+            SYS_UMASK => Self::umask(args[0] as u32),
+            SYS_SETUID => Self::setuid(args[0] as u32),
+            SYS_SETGID => Self::setgid(args[0] as u32),
+            SYS_GETUID => Self::getuid(),
+            SYS_GETEUID => Self::geteuid(),
+            SYS_GETGID => Self::getgid(),
+            SYS_GETEGID => Self::getegid(),
+            SYS_GETGROUPS => Self::getgroups(args[0] as i32, args[1] as *mut u32),
+            SYS_SETGROUPS => Self::setgroups(args[0], args[1] as *const u32),
+
+            SYS_STAT => {
+                let path = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                let kstat = args[1] as *mut PosixKstat;
+                let vaddr = VirtAddr::new(kstat as usize);
+                match (
+                    path,
+                    verify_area_writable(vaddr, core::mem::size_of::<PosixKstat>()),
+                ) {
+                    (Ok(path), Ok(_)) => Self::stat(&path, kstat),
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                }
+            }
+
+            SYS_STATX => {
+                let dirfd = args[0] as i32;
+                let path = check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN));
+                let flags = args[2] as u32;
+                let mask = args[3] as u32;
+                let statx = args[4] as *mut PosixStatx;
+                let vaddr = VirtAddr::new(statx as usize);
+                match (
+                    path,
+                    verify_area_writable(vaddr, core::mem::size_of::<PosixStatx>()),
+                ) {
+                    (Ok(path), Ok(_)) => Self::statx(dirfd, &path, flags, mask, statx),
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                }
+            }
+
+            SYS_UTIMENSAT => {
+                let dirfd = args[0] as i32;
+                let times = args[2] as *const TimeSpec;
+                let flags = args[3] as u32;
+                // 路径为空指针时，修改的是dirfd本身（futimens）
+                if args[1] == 0 {
+                    Self::utimensat(dirfd, None, times, flags)
+                } else {
+                    match check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN)) {
+                        Ok(path) => Self::utimensat(dirfd, Some(&path), times, flags),
+                        Err(e) => Err(e),
+                    }
+                }
+            }
+
+            SYS_STATFS => {
+                let path = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                let statfs = args[1] as *mut PosixStatfs;
+                let vaddr = VirtAddr::new(statfs as usize);
+                match (
+                    path,
+                    verify_area_writable(vaddr, core::mem::size_of::<PosixStatfs>()),
+                ) {
+                    (Ok(path), Ok(_)) => Self::statfs(&path, statfs),
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                }
+            }
+
+            SYS_FSTATFS => {
+                let fd = args[0] as i32;
+                let statfs = args[1] as *mut PosixStatfs;
+                let vaddr = VirtAddr::new(statfs as usize);
+                match verify_area_writable(vaddr, core::mem::size_of::<PosixStatfs>()) {
+                    Ok(_) => Self::fstatfs(fd, statfs),
+                    Err(e) => Err(e),
+                }
+            }
+
+            SYS_LINK => {
+                let oldpath = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                let newpath = check_and_clone_cstr(args[1] as *const u8, Some(MAX_PATHLEN));
+                match (oldpath, newpath) {
+                    (Ok(oldpath), Ok(newpath)) => Self::link(&oldpath, &newpath),
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                }
+            }
+
+            SYS_UNLINK => {
+                let pathname = check_and_clone_cstr(args[0] as *const u8, Some(MAX_PATHLEN));
+                match pathname {
+                    Ok(pathname) => Self::unlink(&pathname),
+                    Err(e) => Err(e),
+                }
+            }
+
             _ => panic!("Unsupported syscall ID: {}", syscall_num),
         };
 
@@ -970,6 +1454,8 @@ impl Syscall {
     }
 
     pub fn reboot() -> Result<usize, SystemError> {
+        // 重启之前把数据写回磁盘，并把文件系统标记为干净的，这样下次启动时不需要检查它们
+        Self::sync()?;
         cpu_reset();
     }
 }
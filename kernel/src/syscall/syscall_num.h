@@ -60,3 +60,61 @@
 
 #define SYS_FSTAT 47        // 根据文件描述符获取文件信息
 
+#define SYS_GETCWD 48       // 获取当前工作目录
+#define SYS_GETPPID 49      // 获取父进程的pid
+#define SYS_GETPGID 50      // 获取进程组id
+#define SYS_FCNTL 51        // 文件控制
+#define SYS_FTRUNCATE 52    // 改变文件大小
+#define SYS_SYMLINK 53      // 创建符号链接
+#define SYS_READLINK 54     // 读取符号链接的内容
+#define SYS_LSTAT 55        // 根据路径获取文件信息（不跟随符号链接）
+#define SYS_OPENAT 56       // 相对于目录文件描述符打开文件
+#define SYS_MKDIRAT 57      // 相对于目录文件描述符创建文件夹
+#define SYS_FSTATAT 58      // 相对于目录文件描述符获取文件信息
+#define SYS_RENAMEAT 59     // 相对于目录文件描述符重命名文件
+#define SYS_LINKAT 60       // 相对于目录文件描述符创建硬链接
+#define SYS_FACCESSAT 61    // 相对于目录文件描述符检查文件的访问权限
+#define SYS_RENAME 62       // 重命名文件
+#define SYS_RENAMEAT2 63    // 重命名文件（支持标志位）
+#define SYS_MOUNT 64        // 挂载文件系统
+#define SYS_UMOUNT2 65      // 卸载文件系统
+#define SYS_FSYNC 66        // 把文件的脏页写回存储设备
+#define SYS_SYNC 67         // 把所有的脏页写回存储设备
+#define SYS_MSYNC 68        // 把文件映射中被修改的内容写回文件
+#define SYS_FLOCK 69        // 对整个文件加锁或解锁
+#define SYS_POLL 70         // 等待一组文件描述符上发生事件
+#define SYS_SELECT 71       // 等待一组文件描述符变为可读、可写或者发生异常
+#define SYS_PSELECT6 72     // select（纳秒精度的超时，可以临时替换信号屏蔽字）
+#define SYS_EPOLL_CREATE 73 // 创建epoll实例
+#define SYS_EPOLL_CREATE1 74 // 创建epoll实例（支持标志位）
+#define SYS_EPOLL_CTL 75    // 在epoll实例中添加、删除或修改被监视的文件描述符
+#define SYS_EPOLL_WAIT 76   // 等待epoll实例中的事件
+#define SYS_EPOLL_PWAIT 77  // 等待epoll实例中的事件（可以临时替换信号屏蔽字）
+#define SYS_READV 78        // 分散读
+#define SYS_WRITEV 79       // 聚集写
+#define SYS_PREAD64 80      // 从指定位置读取文件（不改变文件指针）
+#define SYS_PWRITE64 81     // 向指定位置写入文件（不改变文件指针）
+#define SYS_PREADV 82       // 从指定位置分散读（不改变文件指针）
+#define SYS_PWRITEV 83      // 向指定位置聚集写（不改变文件指针）
+#define SYS_CHMOD 84        // 修改文件的权限位
+#define SYS_FCHMOD 85       // 修改文件描述符所指向的文件的权限位
+#define SYS_CHOWN 86        // 修改文件的所有者和所属的组
+#define SYS_FCHOWN 87       // 修改文件描述符所指向的文件的所有者和所属的组
+#define SYS_ACCESS 88       // 检查进程能否访问文件
+#define SYS_UMASK 89        // 设置文件创建掩码
+#define SYS_SETUID 90       // 设置用户ID
+#define SYS_SETGID 91       // 设置组ID
+#define SYS_GETUID 92       // 获取真实用户ID
+#define SYS_GETEUID 93      // 获取有效用户ID
+#define SYS_GETGID 94       // 获取真实组ID
+#define SYS_GETEGID 95      // 获取有效组ID
+#define SYS_GETGROUPS 96    // 获取附加组
+#define SYS_SETGROUPS 97    // 设置附加组
+#define SYS_STAT 98         // 根据路径获取文件信息
+#define SYS_STATX 99        // 获取文件的扩展信息
+#define SYS_UTIMENSAT 100   // 修改文件的访问时间和修改时间
+#define SYS_STATFS 101      // 获取文件系统的信息
+#define SYS_FSTATFS 102     // 根据文件描述符获取文件系统的信息
+#define SYS_LINK 103        // 创建硬链接
+#define SYS_UNLINK 104      // 删除文件的目录项
+
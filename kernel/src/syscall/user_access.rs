@@ -7,7 +7,7 @@ use core::{
 
 use alloc::{string::String, vec::Vec};
 
-use crate::mm::{verify_area, VirtAddr};
+use crate::mm::{verify_area, verify_area_writable, VirtAddr};
 
 use super::SystemError;
 
@@ -26,7 +26,7 @@ use super::SystemError;
 ///
 /// - `EFAULT`：目标地址不合法
 pub unsafe fn clear_user(dest: VirtAddr, len: usize) -> Result<usize, SystemError> {
-    verify_area(dest, len).map_err(|_| SystemError::EFAULT)?;
+    verify_area_writable(dest, len).map_err(|_| SystemError::EFAULT)?;
 
     let p = dest.data() as *mut u8;
     // 清空用户空间的数据
@@ -35,7 +35,7 @@ pub unsafe fn clear_user(dest: VirtAddr, len: usize) -> Result<usize, SystemErro
 }
 
 pub unsafe fn copy_to_user(dest: VirtAddr, src: &[u8]) -> Result<usize, SystemError> {
-    verify_area(dest, src.len()).map_err(|_| SystemError::EFAULT)?;
+    verify_area_writable(dest, src.len()).map_err(|_| SystemError::EFAULT)?;
 
     let p = dest.data() as *mut u8;
     // 拷贝数据
@@ -258,7 +258,7 @@ impl<'a> UserBufferWriter<'a> {
     /// @return 构造成功返回UserbufferWriter实例，否则返回错误码
     ///
     pub fn new<U>(addr: *mut U, len: usize, from_user: bool) -> Result<Self, SystemError> {
-        if from_user && verify_area(VirtAddr::new(addr as usize), len).is_err() {
+        if from_user && verify_area_writable(VirtAddr::new(addr as usize), len).is_err() {
             return Err(SystemError::EFAULT);
         }
         return Ok(Self {
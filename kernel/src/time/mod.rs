@@ -52,6 +52,11 @@ impl TimeSpec {
             tv_nsec: nsec,
         };
     }
+
+    /// 获取当前的UTC时间（精确到纳秒）
+    pub fn now() -> TimeSpec {
+        return timekeeping::getnstimeofday();
+    }
 }
 
 /// A representation of an absolute time value.
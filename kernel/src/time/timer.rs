@@ -19,6 +19,7 @@ use crate::{
     include::bindings::bindings::{process_control_block, process_wakeup, PROC_RUNNING},
     kdebug, kerror, kinfo,
     libs::spinlock::SpinLock,
+    sched::loadavg::calc_global_load,
     syscall::SystemError,
 };
 
@@ -290,6 +291,7 @@ pub fn update_timer_jiffies(add_jiffies: u64) -> u64 {
     let prev = TIMER_JIFFIES.fetch_add(add_jiffies, Ordering::SeqCst);
     compiler_fence(Ordering::SeqCst);
     update_wall_time();
+    calc_global_load(prev + add_jiffies);
 
     compiler_fence(Ordering::SeqCst);
     return prev + add_jiffies;
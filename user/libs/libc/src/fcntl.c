@@ -1,15 +1,24 @@
 #include <fcntl.h>
 #include <libsystem/syscall.h>
+#include <stdarg.h>
 
 /**
  * @brief 打开文件的接口
  *
  * @param path 文件路径
  * @param options 打开选项
- * @param ...
+ * @param ... 创建文件时，新文件的权限位（仅在指定了O_CREAT时有效）
  * @return int 文件描述符
  */
 int open(const char *path, int options, ...)
 {
-    return syscall_invoke(SYS_OPEN, (uint64_t)path, options, 0, 0, 0, 0, 0, 0);
+    uint64_t mode = 0;
+    if (options & O_CREAT)
+    {
+        va_list args;
+        va_start(args, options);
+        mode = va_arg(args, int);
+        va_end(args);
+    }
+    return syscall_invoke(SYS_OPEN, (uint64_t)path, options, mode, 0, 0, 0, 0, 0);
 }
\ No newline at end of file